@@ -0,0 +1,67 @@
+//! Measures archetype creation and lookup with a large number of archetypes.
+//!
+//! Run with `cargo bench --bench archetypes`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use evenio::component::ComponentIdx;
+use evenio::prelude::*;
+
+const ARCHETYPE_COUNT: u32 = 50_000;
+
+macro_rules! components {
+    ($($c:ident),*) => {
+        $(
+            #[derive(Component)]
+            struct $c;
+        )*
+
+        /// Inserts the components selected by the bits of `mask`.
+        fn insert_by_mask(world: &mut World, entity: EntityId, mask: u32) {
+            let mut _bit = 0;
+            $(
+                if mask & (1 << _bit) != 0 {
+                    world.insert(entity, $c);
+                }
+                _bit += 1;
+            )*
+        }
+    };
+}
+
+components!(C0, C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15);
+
+fn main() {
+    let mut world = World::new();
+
+    let start = Instant::now();
+
+    for mask in 0..ARCHETYPE_COUNT {
+        let e = world.spawn();
+        insert_by_mask(&mut world, e, mask);
+    }
+
+    let created = start.elapsed();
+
+    let sets: Vec<Vec<ComponentIdx>> = world
+        .archetypes()
+        .iter()
+        .map(|arch| arch.component_set().to_vec())
+        .collect();
+
+    let start = Instant::now();
+
+    for set in &sets {
+        black_box(world.archetypes().get_by_components(black_box(set)));
+    }
+
+    let lookups = start.elapsed();
+
+    println!("archetypes: {}", world.archetypes().len());
+    println!("create:     {created:?}");
+    println!(
+        "lookup:     {lookups:?} ({:?} per lookup)",
+        lookups / sets.len() as u32
+    );
+}
@@ -0,0 +1,67 @@
+//! Measures sending events with a 64 KiB payload which are already on the
+//! heap, either by moving them out of their box with `World::send` or by
+//! handing the box to `World::send_boxed`.
+//!
+//! Run with `cargo bench --bench boxed_events`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const LEN: usize = 64 * 1024;
+const SEND_COUNT: u32 = 1_000;
+const RUNS: u32 = 10;
+
+#[derive(Event)]
+#[repr(transparent)]
+struct Payload {
+    bytes: [u8; LEN],
+}
+
+fn new_payloads() -> Vec<Box<Payload>> {
+    (0..SEND_COUNT)
+        .map(|_| {
+            let bytes = vec![1; LEN].into_boxed_slice();
+            // SAFETY: `Payload` is a transparent wrapper around `[u8; LEN]`.
+            unsafe { Box::from_raw(Box::into_raw(bytes).cast::<Payload>()) }
+        })
+        .collect()
+}
+
+/// Returns the average time taken to send `SEND_COUNT` events.
+fn send_all(world: &mut World, mut send: impl FnMut(&mut World, Box<Payload>)) -> Duration {
+    let mut total = Duration::ZERO;
+
+    for _ in 0..RUNS {
+        let payloads = new_payloads();
+
+        let start = Instant::now();
+
+        for payload in payloads {
+            send(world, payload);
+        }
+
+        total += start.elapsed();
+    }
+
+    total / RUNS
+}
+
+fn main() {
+    let mut world = World::new();
+
+    world.add_system(|r: Receiver<Payload>| {
+        black_box(r.event.bytes[LEN - 1]);
+    });
+
+    let by_value = send_all(&mut world, |world, payload| world.send(*payload));
+    let boxed = send_all(&mut world, |world, payload| world.send_boxed(payload));
+
+    println!("events: {SEND_COUNT} of {LEN} bytes");
+    println!(
+        "send(*box): {by_value:?} ({:?} per event)",
+        by_value / SEND_COUNT
+    );
+    println!("send_boxed: {boxed:?} ({:?} per event)", boxed / SEND_COUNT);
+}
@@ -0,0 +1,68 @@
+//! Measures despawning entities whose components don't need to be dropped,
+//! compared to entities with one component that does.
+//!
+//! Run with `cargo bench --bench despawn`.
+
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const ENTITY_COUNT: usize = 1_000_000;
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Pos([f32; 3]);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Vel([f32; 3]);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Name(String);
+
+/// Spawns the entities with `init`, then returns the time taken to despawn
+/// all of them.
+fn despawn_all(init: impl Fn(&mut World, EntityId)) -> Duration {
+    let mut world = World::new();
+
+    let entities: Vec<_> = (0..ENTITY_COUNT)
+        .map(|_| {
+            let e = world.spawn();
+            init(&mut world, e);
+            e
+        })
+        .collect();
+
+    let start = Instant::now();
+
+    // Despawn from the front so every removal has to move the last entity.
+    for &e in &entities {
+        world.despawn(e);
+    }
+
+    start.elapsed()
+}
+
+fn main() {
+    let pod = despawn_all(|world, e| {
+        world.insert(e, Pos([0.0; 3]));
+        world.insert(e, Vel([1.0; 3]));
+    });
+
+    let mixed = despawn_all(|world, e| {
+        world.insert(e, Pos([0.0; 3]));
+        world.insert(e, Vel([1.0; 3]));
+        world.insert(e, Name(String::new()));
+    });
+
+    println!("entities: {ENTITY_COUNT}");
+    println!(
+        "pod:      {pod:?} ({:?} per despawn)",
+        pod / ENTITY_COUNT as u32
+    );
+    println!(
+        "mixed:    {mixed:?} ({:?} per despawn)",
+        mixed / ENTITY_COUNT as u32
+    );
+}
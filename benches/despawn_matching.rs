@@ -0,0 +1,72 @@
+//! Measures despawning every entity with a marker component through
+//! `World::despawn_matching`, compared to collecting the entities and
+//! despawning them one at a time.
+//!
+//! Run with `cargo bench --bench despawn_matching`.
+
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const ENTITY_COUNT: usize = 500_000;
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Pos([f32; 3]);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Name(String);
+
+#[derive(Component)]
+struct Dead;
+
+#[derive(Event)]
+struct Cleanup;
+
+/// Spawns the entities, half of them marked as dead, then returns the time
+/// taken by `cleanup` to remove the dead ones.
+fn cleanup(f: impl FnOnce(&mut World)) -> Duration {
+    let mut world = World::new();
+
+    for i in 0..ENTITY_COUNT {
+        let e = world.spawn();
+        world.insert(e, Pos([0.0; 3]));
+        world.insert(e, Name(String::new()));
+
+        if i % 2 == 0 {
+            world.insert(e, Dead);
+        }
+    }
+
+    let start = Instant::now();
+    f(&mut world);
+    let elapsed = start.elapsed();
+
+    assert_eq!(world.entities().len() as usize, ENTITY_COUNT / 2);
+
+    elapsed
+}
+
+fn main() {
+    let per_entity = cleanup(|world| {
+        world.add_system(
+            |_: Receiver<Cleanup>, f: Fetcher<(EntityId, With<&Dead>)>, mut s: Sender<Despawn>| {
+                for (e, _) in f {
+                    s.despawn(e);
+                }
+            },
+        );
+
+        world.send(Cleanup);
+    });
+
+    let bulk = cleanup(|world| world.despawn_matching::<With<&Dead>>());
+
+    println!(
+        "entities:   {ENTITY_COUNT} ({} despawned)",
+        ENTITY_COUNT / 2
+    );
+    println!("per entity: {per_entity:?}");
+    println!("bulk:       {bulk:?}");
+}
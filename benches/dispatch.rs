@@ -0,0 +1,77 @@
+//! Measures sending a small untargeted event which has a single listener,
+//! which is dominated by the cost of sending and dispatching the event. The
+//! events are sent one at a time, and in batches of `BATCH_SIZE` with
+//! `World::send_all`, which flushes the event queue once per batch.
+//!
+//! Run with `cargo bench --bench dispatch`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const SEND_COUNT: u32 = 1_000_000;
+const RUNS: u32 = 10;
+const BATCH_SIZE: u32 = 50;
+
+#[derive(Event)]
+struct Tick(u32);
+
+#[derive(Event)]
+struct Unused;
+
+/// Returns the average time taken to send `SEND_COUNT` events.
+fn send_all(world: &mut World) -> Duration {
+    // Warm up.
+    world.send(Tick(0));
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        for i in 0..SEND_COUNT {
+            world.send(Tick(i));
+        }
+    }
+
+    start.elapsed() / RUNS
+}
+
+/// Like [`send_all`], but sends the events in batches.
+fn send_batched(world: &mut World) -> Duration {
+    world.send(Tick(0));
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        for i in 0..SEND_COUNT / BATCH_SIZE {
+            world.send_all((i * BATCH_SIZE..(i + 1) * BATCH_SIZE).map(Tick));
+        }
+    }
+
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let mut world = World::new();
+
+    // Other events give `Tick` a larger index.
+    world.add_system(|_: Receiver<Unused>| {});
+
+    world.add_system(|r: Receiver<Tick>| {
+        black_box(r.event.0);
+    });
+
+    println!("events: {SEND_COUNT}");
+
+    let time = send_all(&mut world);
+    println!(
+        "one at a time: {time:?} ({:?} per event)",
+        time / SEND_COUNT
+    );
+
+    let time = send_batched(&mut world);
+    println!(
+        "batches of {BATCH_SIZE}: {time:?} ({:?} per event)",
+        time / SEND_COUNT
+    );
+}
@@ -0,0 +1,81 @@
+//! Measures copying two components of every entity into buffers for another
+//! thread, by iterating a query and by copying whole columns with
+//! `World::extract_column`.
+//!
+//! Run with `cargo bench --bench extract`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const ENTITY_COUNT: usize = 1_000_000;
+const RUNS: u32 = 20;
+
+#[derive(Component, Clone, Copy)]
+struct Transform(#[allow(dead_code)] [f32; 12]);
+
+#[derive(Component, Clone, Copy)]
+struct MeshHandle(#[allow(dead_code)] u32);
+
+#[derive(Component, Clone)]
+struct Visible;
+
+/// Returns the average time taken by `f` over `RUNS` runs.
+fn time(mut f: impl FnMut()) -> Duration {
+    // Warm up.
+    f();
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        f();
+    }
+
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let mut world = World::new();
+
+    for i in 0..ENTITY_COUNT {
+        let e = world.spawn();
+
+        // Split the entities over a few archetypes.
+        if i % 4 == 0 {
+            world.insert(e, Visible);
+        }
+    }
+
+    world.insert_for_all::<(), _>(Transform([1.0; 12]));
+    world.insert_for_all::<(), _>(MeshHandle(7));
+
+    let mut entities = Vec::with_capacity(ENTITY_COUNT);
+    let mut transforms: Vec<Transform> = Vec::with_capacity(ENTITY_COUNT);
+    let mut meshes: Vec<MeshHandle> = Vec::with_capacity(ENTITY_COUNT);
+    let mut pairs = Vec::with_capacity(ENTITY_COUNT);
+
+    let naive = time(|| {
+        pairs.clear();
+
+        for (e, transform, mesh) in &world.query::<(EntityId, &Transform, &MeshHandle)>() {
+            pairs.push((e, *transform, *mesh));
+        }
+
+        black_box(&pairs);
+    });
+
+    let world = &world;
+
+    let extract = time(|| {
+        world.extract_column(&mut transforms, &mut entities);
+        // Both components are in the same archetypes, in the same order.
+        world.extract_column(&mut meshes, &mut entities);
+
+        black_box((&transforms, &meshes, &entities));
+    });
+
+    println!("entities: {ENTITY_COUNT}");
+    println!("query iteration: {naive:?}");
+    println!("extract_column:  {extract:?}");
+}
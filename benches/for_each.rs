@@ -0,0 +1,79 @@
+//! Measures integrating positions over a single large archetype, once with a
+//! `for` loop over `Fetcher::iter_mut` and once with `Fetcher::for_each_mut`,
+//! whose inner loop the compiler can vectorize.
+//!
+//! Run with `cargo bench --bench for_each`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const ENTITY_COUNT: usize = 1_000_000;
+const RUNS: u32 = 20;
+
+#[derive(Component, Clone)]
+struct Pos([f32; 3]);
+
+#[derive(Component, Clone)]
+struct Vel([f32; 3]);
+
+#[derive(Event)]
+struct Step(f32);
+
+static FOR_EACH: AtomicBool = AtomicBool::new(false);
+
+/// Returns the average time taken to step every entity once.
+fn step_all(world: &mut World, for_each: bool) -> Duration {
+    FOR_EACH.store(for_each, Ordering::Relaxed);
+
+    // Warm up.
+    world.send(Step(0.0));
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        world.send(Step(1.0 / 60.0));
+    }
+
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let mut world = World::new();
+
+    for _ in 0..ENTITY_COUNT {
+        world.spawn();
+    }
+
+    world.insert_for_all::<(), _>(Pos([0.0; 3]));
+    world.insert_for_all::<(), _>(Vel([1.0, 2.0, 3.0]));
+
+    world.add_system(|r: Receiver<Step>, mut f: Fetcher<(&mut Pos, &Vel)>| {
+        let dt = r.event.0;
+
+        let step = |(pos, vel): (&mut Pos, &Vel)| {
+            for i in 0..3 {
+                pos.0[i] += vel.0[i] * dt;
+            }
+        };
+
+        if FOR_EACH.load(Ordering::Relaxed) {
+            f.for_each_mut(step);
+        } else {
+            for item in &mut f {
+                step(item);
+            }
+        }
+    });
+
+    println!("entities: {ENTITY_COUNT}");
+
+    for (name, for_each) in [("iter_mut", false), ("for_each_mut", true)] {
+        let time = step_all(&mut world, for_each);
+        println!(
+            "{name:>12}: {time:?} ({:.2}ns per entity)",
+            time.as_secs_f64() * 1e9 / ENTITY_COUNT as f64
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Measures matching queries over an 8-component group against 5,000
+//! archetypes, comparing `GroupMut` with the equivalent tuple query. Half of
+//! the archetypes have every member of the group.
+//!
+//! Run with `cargo bench --bench groups`.
+
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const ARCHETYPE_COUNT: u32 = 5_000;
+const SYSTEM_COUNT: u32 = 100;
+
+macro_rules! components {
+    ($($c:ident),*) => {
+        $(
+            #[derive(Component)]
+            struct $c;
+        )*
+
+        /// Inserts the components selected by the bits of `mask`.
+        fn insert_by_mask(world: &mut World, entity: EntityId, mask: u32) {
+            let mut _bit = 0;
+            $(
+                if mask & (1 << _bit) != 0 {
+                    world.insert(entity, $c);
+                }
+                _bit += 1;
+            )*
+        }
+    };
+}
+
+components!(M0, M1, M2, M3, M4, M5, M6, M7, X0, X1, X2, X3, X4, X5, X6, X7, X8, X9, X10, X11, X12);
+
+struct Wide;
+
+impl ComponentGroup for Wide {
+    type Members = (M0, M1, M2, M3, M4, M5, M6, M7);
+}
+
+type WideTuple = (
+    &'static mut M0,
+    &'static mut M1,
+    &'static mut M2,
+    &'static mut M3,
+    &'static mut M4,
+    &'static mut M5,
+    &'static mut M6,
+    &'static mut M7,
+);
+
+#[derive(Event)]
+struct Tick;
+
+/// Spawns an entity in each archetype. Odd masks are missing `M7`.
+fn spawn_archetypes(world: &mut World) {
+    for i in 0..ARCHETYPE_COUNT {
+        let members = if i % 2 == 0 { 0xff } else { 0x7f };
+
+        let e = world.spawn();
+        insert_by_mask(world, e, members | (i / 2) << 8);
+    }
+}
+
+fn add_systems<Q: Query + 'static>(world: &mut World) {
+    for _ in 0..SYSTEM_COUNT {
+        world.add_system((|_: Receiver<Tick>, _: Fetcher<Q>| {}).no_type_id());
+    }
+}
+
+/// Returns the time taken to register the systems against existing
+/// archetypes, and the time taken to create the archetypes with the systems
+/// already registered.
+fn measure<Q: Query + 'static>() -> (Duration, Duration) {
+    let mut world = World::new();
+    world.add_group::<Wide>();
+    spawn_archetypes(&mut world);
+
+    let start = Instant::now();
+    add_systems::<Q>(&mut world);
+    let register = start.elapsed();
+
+    let mut world = World::new();
+    world.add_group::<Wide>();
+    add_systems::<Q>(&mut world);
+
+    let start = Instant::now();
+    spawn_archetypes(&mut world);
+    let create = start.elapsed();
+
+    (register, create)
+}
+
+fn main() {
+    // Warm up.
+    measure::<WideTuple>();
+
+    for (name, (register, create)) in [
+        ("tuple", measure::<WideTuple>()),
+        ("group", measure::<GroupMut<Wide>>()),
+    ] {
+        println!("{name}: register {register:?}, create archetypes {create:?}");
+    }
+}
@@ -0,0 +1,129 @@
+//! Measures fetching the components of a precomputed list of entities by
+//! calling `Fetcher::get` in a loop and with `Fetcher::iter_many`, and the
+//! same for their mutable counterparts. The entities are spread over a
+//! handful of archetypes.
+//!
+//! Run with `cargo bench --bench iter_many`.
+
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const ENTITY_COUNT: usize = 100_000;
+const RUNS: u32 = 100;
+
+#[derive(Component)]
+struct Pos(f32, f32);
+
+#[derive(Component)]
+struct Tag<const N: usize>;
+
+#[derive(Event)]
+struct Lookup;
+
+#[derive(Component)]
+struct Candidates(Vec<EntityId>);
+
+const GET: u8 = 0;
+const ITER_MANY: u8 = 1;
+const GET_MUT: u8 = 2;
+const ITER_MANY_MUT: u8 = 3;
+
+static MODE: AtomicU8 = AtomicU8::new(GET);
+
+fn run(world: &mut World, mode: u8) -> Duration {
+    MODE.store(mode, Ordering::Relaxed);
+
+    // Warm up.
+    world.send(Lookup);
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        world.send(Lookup);
+    }
+
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let mut world = World::new();
+
+    let mut ids = vec![];
+
+    for i in 0..ENTITY_COUNT {
+        let e = world.spawn();
+        world.insert(e, Pos(i as f32, 0.0));
+
+        // Runs of 16 entities per archetype.
+        match i / 16 % 4 {
+            0 => {}
+            1 => world.insert(e, Tag::<1>),
+            2 => world.insert(e, Tag::<2>),
+            _ => world.insert(e, Tag::<3>),
+        }
+
+        ids.push(e);
+    }
+
+    let holder = world.spawn();
+    world.insert(holder, Candidates(ids));
+
+    world.add_system(
+        |_: Receiver<Lookup>, c: Single<&Candidates>, f: Fetcher<&Pos>| {
+            let mut sum = 0.0;
+
+            match MODE.load(Ordering::Relaxed) {
+                GET => {
+                    for &id in &c.0 .0 {
+                        if let Ok(pos) = f.get(id) {
+                            sum += pos.0 + pos.1;
+                        }
+                    }
+                }
+                ITER_MANY => {
+                    for pos in f.iter_many(c.0 .0.iter().copied()) {
+                        sum += pos.0 + pos.1;
+                    }
+                }
+                _ => {}
+            }
+
+            black_box(sum);
+        },
+    );
+
+    world.add_system(
+        |_: Receiver<Lookup>, c: Single<&Candidates>, mut f: Fetcher<&mut Pos>| match MODE
+            .load(Ordering::Relaxed)
+        {
+            GET_MUT => {
+                for &id in &c.0 .0 {
+                    if let Ok(pos) = f.get_mut(id) {
+                        pos.1 += 1.0;
+                    }
+                }
+            }
+            ITER_MANY_MUT => {
+                for pos in f.iter_many_mut(c.0 .0.iter().copied()) {
+                    pos.1 += 1.0;
+                }
+            }
+            _ => {}
+        },
+    );
+
+    println!("lookups: {ENTITY_COUNT}");
+
+    for (name, mode) in [
+        ("get", GET),
+        ("iter_many", ITER_MANY),
+        ("get_mut", GET_MUT),
+        ("iter_many_mut", ITER_MANY_MUT),
+    ] {
+        let time = run(&mut world, mode);
+        println!("{name:>13}: {time:?}");
+    }
+}
@@ -0,0 +1,56 @@
+//! Measures sending a targeted event to an entity whose archetype has a
+//! single listener for it, which is the common case for archetype listener
+//! lookup.
+//!
+//! Run with `cargo bench --bench listeners`.
+
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const SEND_COUNT: u32 = 1_000_000;
+const RUNS: u32 = 10;
+
+#[derive(Event)]
+struct Hit(#[event(target)] EntityId);
+
+#[derive(Event)]
+struct Unused(#[event(target)] EntityId);
+
+#[derive(Component)]
+struct Health(u32);
+
+/// Returns the average time taken to send `SEND_COUNT` events.
+fn send_all(world: &mut World, target: EntityId) -> Duration {
+    // Warm up.
+    world.send(Hit(target));
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        for _ in 0..SEND_COUNT {
+            world.send(Hit(target));
+        }
+    }
+
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let mut world = World::new();
+
+    // Other targeted events give `Hit` a larger index.
+    world.add_system(|_: Receiver<Unused, &Health>| {});
+
+    world.add_system(|r: Receiver<Hit, &mut Health>| {
+        r.query.0 = r.query.0.wrapping_add(1);
+    });
+
+    let e = world.spawn();
+    world.insert(e, Health(0));
+
+    let time = send_all(&mut world, e);
+
+    println!("events: {SEND_COUNT}");
+    println!("one listener: {time:?} ({:?} per event)", time / SEND_COUNT);
+}
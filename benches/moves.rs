@@ -0,0 +1,109 @@
+//! Measures moving entities with small components between archetypes, with
+//! and without a specialized copy function.
+//!
+//! Run with `cargo bench --bench moves`.
+
+// `criterion_group!` generates an undocumented public function.
+#![allow(missing_docs)]
+
+use std::alloc::Layout;
+use std::any::{self, TypeId};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use evenio::component::ComponentDescriptor;
+use evenio::prelude::*;
+
+const ENTITY_COUNT: usize = 100_000;
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Typed([u32; 3]);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Generic([u32; 3]);
+
+#[derive(Component)]
+struct Tag;
+
+/// Spawns `ENTITY_COUNT` entities with the component returned by `make`.
+fn setup<C: Component>(mut world: World, make: impl Fn(u32) -> C) -> (World, Vec<EntityId>) {
+    let entities = (0..ENTITY_COUNT as u32)
+        .map(|i| {
+            let e = world.spawn();
+            world.insert(e, make(i));
+            e
+        })
+        .collect();
+
+    (world, entities)
+}
+
+/// Inserts and then removes `Tag` on every entity.
+fn move_all((world, entities): &mut (World, Vec<EntityId>)) {
+    for &e in entities.iter() {
+        world.insert(e, Tag);
+    }
+
+    for &e in entities.iter() {
+        world.remove::<Tag>(e);
+    }
+}
+
+/// Returns a world with `Generic` registered without a copy function. There is
+/// no fixed-size copy for 12 bytes, so moves use a copy with a runtime size.
+fn generic_world() -> World {
+    let mut world = World::new();
+
+    unsafe {
+        world.add_component_with_descriptor(ComponentDescriptor {
+            name: any::type_name::<Generic>().into(),
+            type_id: Some(TypeId::of::<Generic>()),
+            key: None,
+            layout: Layout::new::<Generic>(),
+            drop: None,
+            copy_fn: None,
+            clone_fn: None,
+            default_capacity: 0,
+            is_chunked: false,
+            preserve_order: false,
+            is_immutable: false,
+            is_local: false,
+            singleton: None,
+            is_pod: false,
+            required: vec![],
+            on_insert: None,
+            on_remove: None,
+            validate: None,
+        })
+    };
+
+    world
+}
+
+fn moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("moves");
+    group.throughput(Throughput::Elements(ENTITY_COUNT as u64 * 2));
+    group.sample_size(10);
+
+    group.bench_function("typed", |b| {
+        b.iter_batched_ref(
+            || setup(World::new(), |i| Typed([i; 3])),
+            move_all,
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.bench_function("generic", |b| {
+        b.iter_batched_ref(
+            || setup(generic_world(), |i| Generic([i; 3])),
+            move_all,
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, moves);
+criterion_main!(benches);
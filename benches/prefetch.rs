@@ -0,0 +1,77 @@
+//! Measures iterating a three-column query over a single large archetype with
+//! different prefetch distances. The columns have different strides, so each
+//! one streams through memory at its own rate.
+//!
+//! Run with `cargo bench --bench prefetch`.
+
+// `criterion_group!` generates an undocumented public function.
+#![allow(missing_docs)]
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use evenio::fetch::FetcherConfig;
+use evenio::prelude::*;
+
+const ENTITY_COUNT: usize = 4_000_000;
+
+#[derive(Component, Clone)]
+struct Pos([f32; 3]);
+
+#[derive(Component, Clone)]
+struct Vel([f32; 8]);
+
+#[derive(Component, Clone)]
+struct Mass(f32);
+
+#[derive(Event)]
+struct Step;
+
+static DISTANCE: AtomicU32 = AtomicU32::new(0);
+
+fn prefetch(c: &mut Criterion) {
+    let mut world = World::new();
+
+    for _ in 0..ENTITY_COUNT {
+        world.spawn();
+    }
+
+    // Move every entity at once instead of sending millions of `Insert` events.
+    world.insert_for_all::<(), _>(Pos([0.0; 3]));
+    world.insert_for_all::<(), _>(Vel([1.0; 8]));
+    world.insert_for_all::<(), _>(Mass(2.0));
+
+    world.add_system(
+        |_: Receiver<Step>, mut f: Fetcher<(&mut Pos, &Vel, &Mass)>| {
+            f.set_config(FetcherConfig {
+                prefetch_distance: DISTANCE.load(Ordering::Relaxed),
+            });
+
+            for (pos, vel, mass) in f {
+                for i in 0..3 {
+                    pos.0[i] += vel.0[i] * mass.0;
+                }
+            }
+        },
+    );
+
+    let mut group = c.benchmark_group("prefetch");
+    group.throughput(Throughput::Elements(ENTITY_COUNT as u64));
+    group.sample_size(20);
+
+    for distance in [0, 8, 16, 32, 64, 128] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(distance),
+            &distance,
+            |b, &distance| {
+                DISTANCE.store(distance, Ordering::Relaxed);
+                b.iter(|| world.send(Step));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, prefetch);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+//! Measures registering 200 systems with wide queries in a world with 20,000
+//! archetypes.
+//!
+//! Run with `cargo bench --bench register_systems`.
+
+use std::time::Instant;
+
+use evenio::prelude::*;
+
+const ARCHETYPE_COUNT: u32 = 20_000;
+const SYSTEM_COUNT: usize = 200;
+
+macro_rules! components {
+    ($($c:ident),*) => {
+        $(
+            #[derive(Component)]
+            struct $c;
+        )*
+
+        /// Inserts the components selected by the bits of `mask`.
+        fn insert_by_mask(world: &mut World, entity: EntityId, mask: u32) {
+            let mut _bit = 0;
+            $(
+                if mask & (1 << _bit) != 0 {
+                    world.insert(entity, $c);
+                }
+                _bit += 1;
+            )*
+        }
+    };
+}
+
+components!(C0, C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15);
+
+#[derive(Event)]
+struct Tick;
+
+type Wide = (
+    &'static C0,
+    &'static C1,
+    &'static C2,
+    &'static C3,
+    Not<&'static C4>,
+    Not<&'static C5>,
+    Option<&'static C6>,
+    Or<&'static C7, &'static C8>,
+);
+
+fn main() {
+    let mut world = World::new();
+
+    for mask in 0..ARCHETYPE_COUNT {
+        let e = world.spawn();
+        insert_by_mask(&mut world, e, mask);
+    }
+
+    let start = Instant::now();
+
+    for _ in 0..SYSTEM_COUNT {
+        world.add_system((|_: Receiver<Tick>, _: Fetcher<Wide>| {}).no_type_id());
+    }
+
+    let elapsed = start.elapsed();
+
+    println!(
+        "registered {SYSTEM_COUNT} systems against {} archetypes in {elapsed:?} ({:?} per system)",
+        world.archetypes().len(),
+        elapsed / SYSTEM_COUNT as u32
+    );
+}
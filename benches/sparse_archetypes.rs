@@ -0,0 +1,123 @@
+//! Measures iterating a query with negative filters in a world with 10,000
+//! matching archetypes, only 50 of which have entities, against a world with
+//! just the 50 nonempty archetypes. Iteration only visits archetypes with
+//! entities, so both should take about the same time.
+//!
+//! Run with `cargo bench --bench sparse_archetypes`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use evenio::prelude::*;
+
+const ARCHETYPE_COUNT: u32 = 10_000;
+const ACTIVE_COUNT: u32 = 50;
+const ENTITIES_PER_ARCHETYPE: u32 = 100;
+const RUNS: u32 = 10_000;
+
+macro_rules! components {
+    ($($c:ident),*) => {
+        $(
+            #[derive(Component)]
+            struct $c;
+        )*
+
+        /// Inserts the components selected by the bits of `mask`.
+        fn insert_by_mask(world: &mut World, entity: EntityId, mask: u32) {
+            let mut _bit = 0;
+            $(
+                if mask & (1 << _bit) != 0 {
+                    world.insert(entity, $c);
+                }
+                _bit += 1;
+            )*
+        }
+    };
+}
+
+components!(C0, C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13);
+
+#[derive(Component)]
+struct Value(u64);
+
+#[derive(Component)]
+struct Hidden;
+
+#[derive(Component)]
+struct Disabled;
+
+#[derive(Event)]
+struct Sum;
+
+static MATCHED: AtomicUsize = AtomicUsize::new(0);
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+fn make_world(masks: impl Iterator<Item = u32>) -> World {
+    let mut world = World::new();
+
+    world.add_system(
+        |_: Receiver<Sum>, f: Fetcher<(&Value, Not<&Hidden>, Not<&Disabled>)>| {
+            MATCHED.store(f.matched_archetype_count(), Ordering::Relaxed);
+            ACTIVE.store(f.active_archetype_count(), Ordering::Relaxed);
+
+            let mut sum = 0;
+            f.for_each(|(v, _, _)| sum += v.0);
+            std::hint::black_box(sum);
+        },
+    );
+
+    for mask in masks {
+        // Only every `ARCHETYPE_COUNT / ACTIVE_COUNT`th archetype keeps its
+        // entities.
+        let keep = mask % (ARCHETYPE_COUNT / ACTIVE_COUNT) == 0;
+        let count = if keep { ENTITIES_PER_ARCHETYPE } else { 1 };
+
+        for _ in 0..count {
+            let e = world.spawn();
+            world.insert(e, Value(u64::from(mask)));
+            insert_by_mask(&mut world, e, mask);
+
+            if !keep {
+                world.despawn(e);
+            }
+        }
+    }
+
+    world
+}
+
+/// Returns the average time taken to run the query once.
+fn sum_all(world: &mut World) -> Duration {
+    // Warm up.
+    world.send(Sum);
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        world.send(Sum);
+    }
+
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let step = ARCHETYPE_COUNT / ACTIVE_COUNT;
+
+    let worlds = [
+        ("all archetypes", make_world(0..ARCHETYPE_COUNT)),
+        (
+            "active only",
+            make_world((0..ARCHETYPE_COUNT).step_by(step as usize)),
+        ),
+    ];
+
+    for (name, mut world) in worlds {
+        let time = sum_all(&mut world);
+        println!(
+            "{name:>14}: {time:?} per run ({} archetypes, {} matched, {} active)",
+            world.archetypes().len(),
+            MATCHED.load(Ordering::Relaxed),
+            ACTIVE.load(Ordering::Relaxed),
+        );
+    }
+}
@@ -1,8 +1,7 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{parse2, parse_quote, DeriveInput, Result};
-
-use crate::util::parse_attr_immutable;
+use syn::punctuated::Punctuated;
+use syn::{parse2, parse_quote, DeriveInput, LitStr, Path, Result, Token, Type};
 
 pub(crate) fn derive_component(input: TokenStream) -> Result<TokenStream> {
     let mut input = parse2::<DeriveInput>(input)?;
@@ -13,7 +12,151 @@ pub(crate) fn derive_component(input: TokenStream) -> Result<TokenStream> {
         .predicates
         .push(parse_quote!(Self: Send + Sync + 'static));
 
-    let is_immutable = parse_attr_immutable("component", &input.attrs)?;
+    let mut is_immutable = false;
+    let mut is_chunked = false;
+    let mut preserve_order = false;
+    let mut singleton: Option<TokenStream> = None;
+    let mut required: Vec<Type> = vec![];
+    let mut on_insert: Option<Path> = None;
+    let mut on_remove: Option<Path> = None;
+    let mut validate: Option<Path> = None;
+    let mut component_name: Option<LitStr> = None;
+    let mut key: Option<TokenStream> = None;
+
+    for attr in &input.attrs {
+        if attr.path().is_ident("component") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("immutable") {
+                    is_immutable = true;
+                    Ok(())
+                } else if meta.path.is_ident("chunked") {
+                    is_chunked = true;
+                    Ok(())
+                } else if meta.path.is_ident("preserve_order") {
+                    preserve_order = true;
+                    Ok(())
+                } else if meta.path.is_ident("singleton") {
+                    let policy = if meta.input.peek(Token![=]) {
+                        let policy: LitStr = meta.value()?.parse()?;
+
+                        match policy.value().as_str() {
+                            "replace" => quote!(Replace),
+                            "reject" => quote!(Reject),
+                            _ => {
+                                return Err(syn::Error::new(
+                                    policy.span(),
+                                    "expected `\"replace\"` or `\"reject\"`",
+                                ))
+                            }
+                        }
+                    } else {
+                        quote!(Replace)
+                    };
+
+                    singleton = Some(policy);
+                    Ok(())
+                } else if meta.path.is_ident("requires") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+
+                    required.extend(Punctuated::<Type, Token![,]>::parse_terminated(&content)?);
+                    Ok(())
+                } else if meta.path.is_ident("on_insert") {
+                    on_insert = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("on_remove") {
+                    on_remove = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("validate") {
+                    validate = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("name") {
+                    component_name = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("uuid") {
+                    let uuid: LitStr = meta.value()?.parse()?;
+                    key = Some(quote!(#uuid));
+                    Ok(())
+                } else if meta.path.is_ident("key") {
+                    if !input.generics.params.is_empty() {
+                        return Err(meta.error("`key` is not supported on generic components"));
+                    }
+
+                    let ident = &input.ident;
+                    key = Some(quote!(::core::concat!(
+                        ::core::module_path!(),
+                        "::",
+                        ::core::stringify!(#ident)
+                    )));
+                    Ok(())
+                } else {
+                    Err(meta.error("unrecognized argument"))
+                }
+            })?;
+        }
+    }
+
+    let required_fn = if required.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn required(required: &mut ::evenio::component::RequiredComponents) {
+                #(required.add::<#required>();)*
+            }
+        }
+    };
+
+    let key_const = match key {
+        Some(key) => quote! {
+            const KEY: ::core::option::Option<&'static str> = ::core::option::Option::Some(#key);
+        },
+        None => quote!(),
+    };
+
+    let singleton_const = match singleton {
+        Some(policy) => quote! {
+            const SINGLETON: ::core::option::Option<::evenio::component::SingletonPolicy> =
+                ::core::option::Option::Some(::evenio::component::SingletonPolicy::#policy);
+        },
+        None => quote!(),
+    };
+
+    let name_fn = match component_name {
+        Some(name) => quote! {
+            fn name() -> &'static str {
+                #name
+            }
+        },
+        None => quote!(),
+    };
+
+    let hook_fn = |name: TokenStream, hook: Option<Path>| match hook {
+        Some(hook) => quote! {
+            fn #name() -> ::core::option::Option<::evenio::component::ComponentHook> {
+                let hook: ::evenio::component::ComponentHook = |ctx, entity, ptr| {
+                    #hook(ctx, entity, unsafe { ptr.cast::<Self>().as_ref() })
+                };
+
+                ::core::option::Option::Some(hook)
+            }
+        },
+        None => quote!(),
+    };
+
+    let on_insert_fn = hook_fn(quote!(on_insert), on_insert);
+    let on_remove_fn = hook_fn(quote!(on_remove), on_remove);
+
+    let validate_fn = match validate {
+        Some(validate) => quote! {
+            fn validate() -> ::core::option::Option<::evenio::component::ValidateFn> {
+                let validate: ::evenio::component::ValidateFn =
+                    |ptr| #validate(unsafe { ptr.cast::<Self>().as_ref() });
+
+                ::core::option::Option::Some(validate)
+            }
+        },
+        None => quote!(),
+    };
 
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
@@ -22,6 +165,38 @@ pub(crate) fn derive_component(input: TokenStream) -> Result<TokenStream> {
         #[automatically_derived]
         impl #impl_generics ::evenio::component::Component for #name #ty_generics #where_clause {
             const IS_IMMUTABLE: bool = #is_immutable;
+
+            const IS_CHUNKED: bool = #is_chunked;
+
+            const PRESERVE_ORDER: bool = #preserve_order;
+
+            #singleton_const
+
+            #key_const
+
+            #name_fn
+
+            #required_fn
+
+            #on_insert_fn
+
+            #on_remove_fn
+
+            #validate_fn
+
+            fn clone_fn() -> ::core::option::Option<::evenio::component::CloneFn> {
+                #[allow(unused_imports)]
+                use ::evenio::__private::{MaybeClone as _, NotClone as _};
+
+                (&&::evenio::__private::CloneProbe::<Self>(::core::marker::PhantomData)).clone_fn()
+            }
+
+            fn is_pod() -> bool {
+                #[allow(unused_imports)]
+                use ::evenio::__private::{MaybePod as _, NotPod as _};
+
+                (&&::evenio::__private::PodProbe::<Self>(::core::marker::PhantomData)).is_pod()
+            }
         }
     })
 }
@@ -1,8 +1,11 @@
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
-use syn::{parse2, parse_quote, Data, DeriveInput, LitInt, Result};
-
-use crate::util::parse_attr_immutable;
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse2, parse_quote, Data, DeriveInput, Error, Field, Fields, Ident, Index, LitStr, Member,
+    Result, Token, Type,
+};
 
 pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
     let mut input = parse2::<DeriveInput>(input)?;
@@ -14,6 +17,8 @@ pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
         .push(parse_quote!(Self: Send + Sync + 'static));
 
     let mut target_field = None;
+    // The target field of each variant, set with `#[event(target)]` on enums.
+    let mut variant_targets: Vec<(Ident, Member, Field)> = vec![];
 
     match &input.data {
         Data::Struct(struct_) => {
@@ -37,13 +42,27 @@ pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
             }
         }
         Data::Enum(enum_) => {
+            let mut untargeted_variant = None;
+
             for variant in &enum_.variants {
-                for field in &variant.fields {
+                let mut variant_target = None;
+
+                for (idx, field) in variant.fields.iter().enumerate() {
                     for attr in &field.attrs {
                         if attr.path().is_ident("event") {
                             attr.parse_nested_meta(|meta| {
                                 if meta.path.is_ident("target") {
-                                    Err(meta.error("cannot set target on enum"))
+                                    if variant_target.is_some() {
+                                        return Err(meta.error("target already assigned"));
+                                    }
+
+                                    let member = match &field.ident {
+                                        Some(ident) => Member::Named(ident.clone()),
+                                        None => Member::Unnamed(Index::from(idx)),
+                                    };
+
+                                    variant_target = Some((member, field.clone()));
+                                    Ok(())
                                 } else {
                                     Err(meta.error("unrecognized argument"))
                                 }
@@ -51,6 +70,22 @@ pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
                         }
                     }
                 }
+
+                match variant_target {
+                    Some((member, field)) => {
+                        variant_targets.push((variant.ident.clone(), member, field))
+                    }
+                    None => {
+                        untargeted_variant.get_or_insert(variant);
+                    }
+                }
+            }
+
+            if let (Some(variant), false) = (untargeted_variant, variant_targets.is_empty()) {
+                return Err(Error::new(
+                    variant.span(),
+                    "every variant of a targeted enum needs an `#[event(target)]` field",
+                ));
             }
         }
         Data::Union(union_) => {
@@ -70,20 +105,155 @@ pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
         }
     }
 
-    let is_targeted = target_field.is_some();
+    // Path to the target relative to `self`, set with `#[event(target = "...")]`.
+    let mut target_path: Option<Punctuated<Member, Token![.]>> = None;
+    // Error for arguments which need a targeted event, in case no target is set.
+    let mut untargeted_error = None;
+
+    let mut is_immutable = false;
+    let mut is_deferred = false;
+    let mut bubbles = false;
+    let mut required: Vec<Type> = vec![];
+    let mut excluded: Vec<Type> = vec![];
+    let mut key: Option<TokenStream> = None;
+
+    for attr in &input.attrs {
+        if attr.path().is_ident("event") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("immutable") {
+                    is_immutable = true;
+                    Ok(())
+                } else if meta.path.is_ident("deferred") {
+                    is_deferred = true;
+                    Ok(())
+                } else if meta.path.is_ident("bubbles") {
+                    untargeted_error
+                        .get_or_insert(meta.error("bubbling requires a targeted event"));
+                    bubbles = true;
+                    Ok(())
+                } else if meta.path.is_ident("target") {
+                    if !matches!(input.data, Data::Struct(_)) {
+                        return Err(meta.error("target paths are only supported on structs"));
+                    }
+
+                    if target_field.is_some() || target_path.is_some() {
+                        return Err(meta.error("target already assigned"));
+                    }
+
+                    let lit: LitStr = meta.value()?.parse()?;
+                    target_path = Some(lit.parse_with(Punctuated::parse_separated_nonempty)?);
+                    Ok(())
+                } else if meta.path.is_ident("requires") || meta.path.is_ident("excludes") {
+                    untargeted_error
+                        .get_or_insert(meta.error("target filters require a targeted event"));
+
+                    let list = if meta.path.is_ident("requires") {
+                        &mut required
+                    } else {
+                        &mut excluded
+                    };
+
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+
+                    list.extend(Punctuated::<Type, Token![,]>::parse_terminated(&content)?);
+                    Ok(())
+                } else if meta.path.is_ident("uuid") {
+                    let uuid: LitStr = meta.value()?.parse()?;
+                    key = Some(quote!(#uuid));
+                    Ok(())
+                } else if meta.path.is_ident("key") {
+                    if !input.generics.params.is_empty() {
+                        return Err(meta.error("`key` is not supported on generic events"));
+                    }
 
-    let is_immutable = parse_attr_immutable("event", &input.attrs)?;
+                    let ident = &input.ident;
+                    key = Some(quote!(::core::concat!(
+                        ::core::module_path!(),
+                        "::",
+                        ::core::stringify!(#ident)
+                    )));
+                    Ok(())
+                } else {
+                    Err(meta.error("unrecognized argument"))
+                }
+            })?;
+        }
+    }
 
-    let target_fn_body = if let Some((idx, field)) = target_field {
-        let f = match field.ident {
-            Some(ident) => ident.into_token_stream(),
-            None => LitInt::new(&idx.to_string(), Span::call_site()).to_token_stream(),
+    if let Some((idx, field)) = target_field {
+        let member = match field.ident {
+            Some(ident) => Member::Named(ident),
+            None => Member::Unnamed(Index::from(idx)),
         };
 
-        quote!(self.#f)
+        target_path = Some(Punctuated::from_iter([member]));
+    }
+
+    let is_targeted = target_path.is_some() || !variant_targets.is_empty();
+
+    if !is_targeted {
+        if let Some(e) = untargeted_error {
+            return Err(e);
+        }
+    }
+
+    let (target_fn_body, set_target_fn_body) = if let Some(path) = target_path {
+        (quote!(self.#path), quote!(self.#path = target))
+    } else if !variant_targets.is_empty() {
+        let (get_arms, set_arms): (Vec<_>, Vec<_>) = variant_targets
+            .iter()
+            .map(|(variant, member, field)| {
+                // Spanned to the field so that a field of the wrong type is
+                // reported there.
+                let span = field.ty.span();
+                let get = quote_spanned! {span=>
+                    let target: &::evenio::entity::EntityId = field;
+                    *target
+                };
+                let set = quote_spanned! {span=>
+                    let field: &mut ::evenio::entity::EntityId = field;
+                    *field = target;
+                };
+
+                (
+                    quote!(Self::#variant { #member: field, .. } => { #get }),
+                    quote!(Self::#variant { #member: field, .. } => { #set }),
+                )
+            })
+            .unzip();
+
+        (
+            quote!(match self { #(#get_arms)* }),
+            quote!(match self { #(#set_arms)* }),
+        )
     } else {
         let message = format!("`{}` is not a targeted event", &input.ident);
-        quote!(::core::unreachable!(#message))
+        (
+            quote!(::core::unreachable!(#message)),
+            quote! {
+                let _ = target;
+                ::core::unreachable!(#message)
+            },
+        )
+    };
+
+    let target_filter_fn = if required.is_empty() && excluded.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn target_filter(filter: &mut ::evenio::event::TargetFilter) {
+                #(filter.requires::<#required>();)*
+                #(filter.excludes::<#excluded>();)*
+            }
+        }
+    };
+
+    let key_const = match key {
+        Some(key) => quote! {
+            const KEY: ::core::option::Option<&'static str> = ::core::option::Option::Some(#key);
+        },
+        None => quote!(),
     };
 
     let name = &input.ident;
@@ -94,11 +264,131 @@ pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
         impl #impl_generics ::evenio::event::Event for #name #ty_generics #where_clause {
             const IS_TARGETED: bool = #is_targeted;
             const IS_IMMUTABLE: bool = #is_immutable;
+            const IS_DEFERRED: bool = #is_deferred;
+            const BUBBLES: bool = #bubbles;
+
+            #key_const
 
             #[track_caller]
             fn target(&self) -> ::evenio::entity::EntityId {
                 #target_fn_body
             }
+
+            #[track_caller]
+            fn set_target(&mut self, target: ::evenio::entity::EntityId) {
+                #set_target_fn_body
+            }
+
+            #target_filter_fn
+
+            fn clone_fn() -> ::core::option::Option<::evenio::component::CloneFn> {
+                #[allow(unused_imports)]
+                use ::evenio::__private::{MaybeClone as _, NotClone as _};
+
+                (&&::evenio::__private::CloneProbe::<Self>(::core::marker::PhantomData)).clone_fn()
+            }
+
+            fn default_fn() -> ::core::option::Option<::evenio::component::DefaultFn> {
+                #[allow(unused_imports)]
+                use ::evenio::__private::{MaybeDefault as _, NotDefault as _};
+
+                (&&::evenio::__private::DefaultProbe::<Self>(::core::marker::PhantomData)).default_fn()
+            }
+        }
+    })
+}
+
+pub(crate) fn derive_event_enum(input: TokenStream) -> Result<TokenStream> {
+    let input = parse2::<DeriveInput>(input)?;
+    let name = &input.ident;
+
+    let Data::Enum(enum_) = &input.data else {
+        return Err(Error::new(
+            input.span(),
+            "`EventEnum` can only be derived on enums",
+        ));
+    };
+
+    let mut lifetimes = input.generics.lifetimes();
+
+    let (Some(life), None) = (lifetimes.next(), lifetimes.next()) else {
+        return Err(Error::new(
+            input.generics.span(),
+            "`EventEnum` must have exactly one lifetime parameter",
+        ));
+    };
+
+    if input.generics.type_params().next().is_some()
+        || input.generics.const_params().next().is_some()
+    {
+        return Err(Error::new(
+            input.generics.span(),
+            "`EventEnum` can't have type or const parameters",
+        ));
+    }
+
+    if enum_.variants.is_empty() {
+        return Err(Error::new(
+            input.span(),
+            "`EventEnum` must have at least one variant",
+        ));
+    }
+
+    let mut events: Vec<&Type> = vec![];
+
+    for variant in &enum_.variants {
+        let err = || {
+            Error::new(
+                variant.span(),
+                format!(
+                    "variants of `EventEnum` must hold a single `&{} E`, where `E` is an event",
+                    life.lifetime
+                ),
+            )
+        };
+
+        let Fields::Unnamed(fields) = &variant.fields else {
+            return Err(err());
+        };
+
+        let (Some(field), 1) = (fields.unnamed.first(), fields.unnamed.len()) else {
+            return Err(err());
+        };
+
+        match &field.ty {
+            Type::Reference(r)
+                if r.mutability.is_none() && r.lifetime.as_ref() == Some(&life.lifetime) =>
+            {
+                events.push(&r.elem)
+            }
+            _ => return Err(err()),
+        }
+    }
+
+    let variants = enum_.variants.iter().map(|v| &v.ident);
+    let indices = 0..events.len();
+
+    Ok(quote! {
+        #[automatically_derived]
+        unsafe impl<#life> ::evenio::event::EventEnum for #name<#life> {
+            type Item<'__a> = #name<'__a>;
+
+            fn add_events<F: ::core::ops::FnMut(::evenio::event::EventId)>(
+                world: &mut ::evenio::world::World,
+                mut f: F,
+            ) {
+                #(f(world.add_event::<#events>());)*
+            }
+
+            unsafe fn get<'__a>(
+                idx: usize,
+                event: ::evenio::event::EventPtr<'__a>,
+            ) -> Self::Item<'__a> {
+                match idx {
+                    #(#indices => #name::#variants(unsafe { event.as_event::<#events>() }),)*
+                    _ => ::core::unreachable!("invalid event index {idx}"),
+                }
+            }
         }
     })
 }
@@ -50,6 +50,15 @@ pub fn derive_event(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Derive macro for `EventEnum`. See `EventEnum`'s documentation for more
+/// information.
+#[proc_macro_derive(EventEnum)]
+pub fn derive_event_enum(input: TokenStream) -> TokenStream {
+    event::derive_event_enum(input.into())
+        .unwrap_or_else(|e| e.into_compile_error())
+        .into()
+}
+
 /// Derive macro for `Component`. See `Component`'s documentation for more
 /// information.
 #[proc_macro_derive(Component, attributes(component))]
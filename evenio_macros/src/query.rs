@@ -1,8 +1,12 @@
-use proc_macro2::{Span, TokenStream};
-use quote::quote;
-use syn::{parse2, parse_quote, Data, DeriveInput, Error, GenericParam, LitInt, Result, Type};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{parse2, parse_quote, Data, DeriveInput, Error, GenericParam, Result, Type};
 
-use crate::util::{make_tuple, replace_lifetime};
+use crate::util::replace_lifetime;
+
+/// The largest tuple with a `Query` impl.
+const MAX_TUPLE_LEN: usize = 12;
 
 pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
     let mut input = parse2::<DeriveInput>(input)?;
@@ -11,6 +15,7 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
     let lifetimes;
     let tuple_ty;
     let get_body;
+    let mut field_assertions = vec![];
 
     let mut ro_generics = input.generics.clone();
 
@@ -27,19 +32,54 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
                 })
                 .collect::<Vec<_>>();
 
-            tuple_ty = make_tuple(struct_.fields.iter().map(|f| &f.ty));
+            let type_params = input
+                .generics
+                .params
+                .iter()
+                .filter_map(|p| match p {
+                    GenericParam::Type(ty) => Some(ty.ident.clone()),
+                    GenericParam::Const(c) => Some(c.ident.clone()),
+                    GenericParam::Lifetime(_) => None,
+                })
+                .collect::<Vec<_>>();
+
+            let bindings = struct_
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, f)| {
+                    f.ident
+                        .clone()
+                        .unwrap_or_else(|| format_ident!("__field{i}"))
+                })
+                .collect::<Vec<_>>();
+
+            let (ty, pat) = nest_tuples(
+                struct_
+                    .fields
+                    .iter()
+                    .zip(&bindings)
+                    .map(|(f, b)| (f.ty.to_token_stream(), b.to_token_stream()))
+                    .collect(),
+            );
+
+            tuple_ty = ty;
 
             let where_clause = input.generics.make_where_clause();
 
             let ro_where_clause = ro_generics.make_where_clause();
 
+            let static_ident = Ident::new("static", Span::call_site());
+
             for field in &struct_.fields {
                 let ty = &field.ty;
 
                 let mut replaced_ty = ty.clone();
+                let mut static_ty = ty.clone();
 
                 for life in &lifetimes {
                     replace_lifetime(&mut replaced_ty, &life.lifetime.ident, &parse_quote!(__a));
+                    replace_lifetime(&mut static_ty, &life.lifetime.ident, &static_ident);
                 }
 
                 where_clause.predicates.push(
@@ -49,37 +89,30 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
                 ro_where_clause
                     .predicates
                     .push(parse_quote!(#ty: for<'__a> ::evenio::query::ReadOnlyQuery<Item<'__a> = #replaced_ty>));
-            }
 
-            get_body = match &struct_.fields {
-                syn::Fields::Named(fields) => {
-                    let idents: Vec<_> = fields
-                        .named
-                        .iter()
-                        .map(|f| f.ident.clone().unwrap())
-                        .collect();
-
-                    quote! {
-                        let (#(#idents,)*) = <#tuple_ty as ::evenio::query::Query>::get(state, row);
-
-                        #name {
-                            #(#idents),*
-                        }
-                    }
+                // Fields which don't depend on type parameters can be checked
+                // here, so that the error points at the field instead of
+                // wherever the query is used.
+                if !mentions_any(ty.to_token_stream(), &type_params) {
+                    field_assertions.push(quote_spanned! {ty.span()=>
+                        __assert_query::<#static_ty>();
+                    });
                 }
-                syn::Fields::Unnamed(fields) => {
-                    let indices = fields
-                        .unnamed
-                        .iter()
-                        .enumerate()
-                        .map(|(i, _)| LitInt::new(&format!("{i}"), Span::call_site()));
+            }
 
-                    quote! {
-                        let tuple = <#tuple_ty as ::evenio::query::Query>::get(state, row);
+            get_body = match &struct_.fields {
+                syn::Fields::Named(_) => quote! {
+                    let #pat = <#tuple_ty as ::evenio::query::Query>::get(state, row);
 
-                        #name(#(tuple.#indices),*)
+                    #name {
+                        #(#bindings),*
                     }
-                }
+                },
+                syn::Fields::Unnamed(_) => quote! {
+                    let #pat = <#tuple_ty as ::evenio::query::Query>::get(state, row);
+
+                    #name(#(#bindings),*)
+                },
                 syn::Fields::Unit => quote!(#name),
             };
         }
@@ -106,7 +139,21 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
         replace_lifetime(&mut item, &life.lifetime.ident, &parse_quote!(__a));
     }
 
+    let assertions = (!field_assertions.is_empty()).then(|| {
+        quote! {
+            const _: () = {
+                fn __assert_query<Q: ::evenio::query::Query>() {}
+
+                fn __assert_fields() {
+                    #(#field_assertions)*
+                }
+            };
+        }
+    });
+
     Ok(quote! {
+        #assertions
+
         #[automatically_derived]
         unsafe impl #impl_generics ::evenio::query::Query for #name #ty_generics #where_clause {
             type Item<'__a> = #item;
@@ -127,6 +174,13 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
                 <#tuple_ty as ::evenio::query::Query>::new_state(world)
             }
 
+            fn init_existing(
+                world: &::evenio::world::World
+            ) -> ::core::option::Option<(::evenio::access::ComponentAccessExpr, Self::State)>
+            {
+                <#tuple_ty as ::evenio::query::Query>::init_existing(world)
+            }
+
             fn new_arch_state(arch: &::evenio::archetype::Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
                 <#tuple_ty as ::evenio::query::Query>::new_arch_state(arch, state)
             }
@@ -134,9 +188,42 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
             unsafe fn get<'__a>(state: &Self::ArchState, row: ::evenio::archetype::ArchetypeRow) -> Self::Item<'__a> {
                 #get_body
             }
+
+            unsafe fn prefetch(state: &Self::ArchState, row: ::evenio::archetype::ArchetypeRow) {
+                <#tuple_ty as ::evenio::query::Query>::prefetch(state, row)
+            }
         }
 
         #[automatically_derived]
         unsafe impl #ro_impl_generics ::evenio::query::ReadOnlyQuery for #name #ro_ty_generics #ro_where_clause {}
     })
 }
+
+/// Builds a tuple type and a matching pattern from a list of element types and
+/// bindings. Lists longer than [`MAX_TUPLE_LEN`] are split into nested tuples.
+fn nest_tuples(mut elems: Vec<(TokenStream, TokenStream)>) -> (TokenStream, TokenStream) {
+    while elems.len() > MAX_TUPLE_LEN {
+        elems = elems
+            .chunks(MAX_TUPLE_LEN)
+            .map(|chunk| {
+                let tys = chunk.iter().map(|(ty, _)| ty);
+                let pats = chunk.iter().map(|(_, pat)| pat);
+                (quote!((#(#tys,)*)), quote!((#(#pats,)*)))
+            })
+            .collect();
+    }
+
+    let tys = elems.iter().map(|(ty, _)| ty);
+    let pats = elems.iter().map(|(_, pat)| pat);
+
+    (quote!((#(#tys,)*)), quote!((#(#pats,)*)))
+}
+
+/// Does `tokens` contain any of the identifiers in `idents`?
+fn mentions_any(tokens: TokenStream, idents: &[Ident]) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => idents.contains(&ident),
+        TokenTree::Group(group) => mentions_any(group.stream(), idents),
+        TokenTree::Punct(_) | TokenTree::Literal(_) => false,
+    })
+}
@@ -130,6 +130,20 @@ pub(crate) fn derive_system_param(input: TokenStream) -> Result<TokenStream> {
                 #get_body
             }
 
+            unsafe fn should_run(
+                state: &mut Self::State,
+                info: &::evenio::system::SystemInfo,
+                event_ptr: ::evenio::event::EventPtr<'_>,
+                world: ::evenio::world::UnsafeWorldCell<'_>,
+            ) -> bool {
+                <#tuple_ty as ::evenio::system::SystemParam>::should_run(
+                    state,
+                    info,
+                    event_ptr,
+                    world
+                )
+            }
+
             fn refresh_archetype(
                 state: &mut Self::State,
                 arch: &::evenio::archetype::Archetype
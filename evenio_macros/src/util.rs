@@ -1,29 +1,6 @@
 use proc_macro2::Ident;
 use quote::ToTokens;
-use syn::{
-    parse_quote, Attribute, GenericArgument, Path, Result, ReturnType, Type, TypeParamBound,
-    TypeTuple,
-};
-
-/// Parse a `#[foo(immutable)]` attribute where `outer` is `foo`.
-pub(crate) fn parse_attr_immutable(outer: &str, attrs: &[Attribute]) -> Result<bool> {
-    let mut res = false;
-
-    for attr in attrs {
-        if attr.path().is_ident(outer) {
-            attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("immutable") {
-                    res = true;
-                    Ok(())
-                } else {
-                    Err(meta.error("unrecognized argument"))
-                }
-            })?;
-        }
-    }
-
-    Ok(res)
-}
+use syn::{parse_quote, GenericArgument, Path, ReturnType, Type, TypeParamBound, TypeTuple};
 
 /// Make a tuple from a list of the tuple's element types.
 pub(crate) fn make_tuple(types: impl Iterator<Item = impl ToTokens>) -> TypeTuple {
@@ -108,10 +85,8 @@ pub(crate) fn replace_lifetime(ty: &mut Type, old: &Ident, new: &Ident) {
     ) {
         for arg in args {
             match arg {
-                syn::GenericArgument::Lifetime(l) => {
-                    if l.ident == *old {
-                        l.ident = new.clone();
-                    }
+                syn::GenericArgument::Lifetime(l) if l.ident == *old => {
+                    l.ident = new.clone();
                 }
                 syn::GenericArgument::Type(t) => replace_lifetime(t, old, new),
                 syn::GenericArgument::Const(_) => {
@@ -147,10 +122,8 @@ pub(crate) fn replace_lifetime(ty: &mut Type, old: &Ident, new: &Ident) {
         for bound in bounds {
             match bound {
                 syn::TypeParamBound::Trait(t) => handle_path(&mut t.path, old, new),
-                syn::TypeParamBound::Lifetime(l) => {
-                    if l.ident == *old {
-                        l.ident = new.clone();
-                    }
+                syn::TypeParamBound::Lifetime(l) if l.ident == *old => {
+                    l.ident = new.clone();
                 }
                 syn::TypeParamBound::Verbatim(_) => {}
                 _ => {}
@@ -88,6 +88,11 @@ impl<T> AccessMap<T> {
         }
     }
 
+    /// Returns the number of bytes allocated by this map.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.read.heap_size() + self.write.heap_size()
+    }
+
     /// Gets the access for `key`.
     pub fn get(&self, key: T) -> Access
     where
@@ -122,6 +127,23 @@ impl<T> AccessMap<T> {
         }
     }
 
+    /// Returns an iterator over the keys with access other than
+    /// [`Access::None`] and their access, in ascending order of the keys.
+    pub fn iter(&self) -> impl Iterator<Item = (T, Access)> + '_
+    where
+        T: SparseIndex,
+    {
+        self.read.iter().map(|key| {
+            let access = if self.write.contains(key) {
+                Access::ReadWrite
+            } else {
+                Access::Read
+            };
+
+            (key, access)
+        })
+    }
+
     /// Clears the access map. All keys will map to [`Access::None`].
     pub fn clear(&mut self) {
         self.read.clear();
@@ -134,6 +156,12 @@ impl<T> AccessMap<T> {
         self.read.is_disjoint(&other.write) && self.write.is_disjoint(&other.read)
     }
 
+    /// Returns whether every access in `self` is also granted by `other`. A
+    /// read is granted by a read or a write, and a write only by a write.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.read.is_subset(&other.read) && self.write.is_subset(&other.write)
+    }
+
     /// Computes the union between `self` and `other` and assigns the result to
     /// `self`.
     pub fn union_assign(&mut self, other: &Self) {
@@ -1,24 +1,38 @@
 //! [`Archetype`] and related items.
 
+use alloc::borrow::Cow;
 use alloc::boxed::Box;
-use alloc::collections::btree_map::Entry;
 use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::any::TypeId;
 use core::cmp::Ordering;
-use core::ptr;
-use core::ptr::NonNull;
+use core::mem;
+use core::ops::Range;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{self, AtomicU32};
 
 use slab::Slab;
 
-use crate::assert::{assume_debug_checked, GetDebugChecked, UnwrapDebugChecked};
+use crate::assert::{assume_debug_checked, GetDebugChecked, StructureLock, UnwrapDebugChecked};
+use crate::bit_set::BitSet;
 use crate::blob_vec::BlobVec;
-use crate::component::{ComponentIdx, Components};
+use crate::bool_expr::BoolExpr;
+use crate::component::{CloneFn, ComponentId, ComponentIdx, Components};
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::DiagnosticQueue;
+#[cfg(feature = "diff")]
+use crate::diff::{ChangeLog, ColumnChanges};
 use crate::entity::{Entities, EntityId, EntityLocation};
-use crate::event::{EventIdx, EventPtr, TargetedEventIdx};
+use crate::entity_group::EntityGroupId;
+use crate::event::{Event, EventIdx, EventPtr, TargetedEventIdx};
+use crate::group::{GroupIdx, GroupInfo, Groups};
+use crate::memory::ArchetypeMemory;
 use crate::prelude::World;
 use crate::sparse::SparseIndex;
-use crate::sparse_map::SparseMap;
+use crate::sparse_map::SmallSparseMap;
 use crate::system::{
     Config, InitError, SystemInfo, SystemInfoPtr, SystemList, SystemParam, Systems,
 };
@@ -39,16 +53,314 @@ use crate::world::UnsafeWorldCell;
 /// world.add_system(|_: Receiver<E>, archetypes: &Archetypes| {});
 /// ```
 #[derive(Debug)]
+#[allow(clippy::struct_field_names)]
 pub struct Archetypes {
     archetypes: Slab<Archetype>,
-    by_components: BTreeMap<Box<[ComponentIdx]>, ArchetypeIdx>,
+    /// Archetypes ordered by the hash of their component set. The component
+    /// sets themselves are stored once in each [`Archetype`], so lookups
+    /// compare hashes first and only compare full sets on a hash match.
+    by_components: BTreeSet<(u64, ArchetypeIdx)>,
+    settings: ArchetypeSettings,
+    pub(crate) lock: StructureLock,
+    /// The number of archetypes created so far, including removed ones. Used
+    /// to give every archetype a distinct [`ColumnVersion`].
+    created: u64,
+    /// Changed whenever the systems listening for targeted events in an
+    /// existing archetype might have changed, or an archetype was removed.
+    listeners_version: u64,
+    groups: Groups,
+    #[cfg(feature = "diagnostics")]
+    pub(crate) diagnostics: DiagnosticQueue,
+    /// The changes recorded since [`World::start_tracking_changes`].
+    ///
+    /// [`World::start_tracking_changes`]: crate::world::World::start_tracking_changes
+    #[cfg(feature = "diff")]
+    pub(crate) changes: Option<ChangeLog>,
+}
+
+/// Controls how much room [`Archetype`]s allocate for their entities.
+///
+/// Columns normally start out empty and double their capacity whenever they
+/// run out of room. Reserving capacity up front avoids the cost of growing the
+/// columns while entities are added. To reserve room in a single archetype,
+/// use [`World::reserve_archetype`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::archetype::{ArchetypeGrowth, ArchetypeSettings};
+/// use evenio::prelude::*;
+///
+/// let mut world = World::new();
+///
+/// world.set_archetype_settings(ArchetypeSettings {
+///     initial_entity_capacity: 1024,
+///     growth: ArchetypeGrowth::Factor(1.5),
+/// });
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ArchetypeSettings {
+    /// The number of entities that new archetypes have room for. Archetypes
+    /// containing a component with a larger
+    /// [`default_capacity`](crate::component::ComponentDescriptor::default_capacity)
+    /// use that instead.
+    pub initial_entity_capacity: usize,
+    /// How an archetype's capacity grows when it runs out of room.
+    pub growth: ArchetypeGrowth,
+}
+
+impl ArchetypeSettings {
+    /// The default settings. Archetypes start out empty and double their
+    /// capacity when they run out of room.
+    pub const DEFAULT: Self = Self {
+        initial_entity_capacity: 0,
+        growth: ArchetypeGrowth::Doubling,
+    };
+}
+
+/// How an [`Archetype`] grows its capacity when it runs out of room. Every
+/// column of the archetype is grown together. See [`ArchetypeSettings`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ArchetypeGrowth {
+    /// Double the capacity. This is the default.
+    Doubling,
+    /// Grow to exactly the number of entities needed. No memory is wasted, but
+    /// adding entities one at a time reallocates the columns every time.
+    Exact,
+    /// Grow to the next multiple of the given number of entities, which must
+    /// not be zero.
+    Chunked(usize),
+    /// Multiply the capacity by the given factor, which must be at least
+    /// `1.0`.
+    Factor(f32),
+}
+
+impl ArchetypeGrowth {
+    /// Returns the capacity to grow to from `capacity` in order to fit
+    /// `required` entities.
+    fn grow(self, capacity: usize, required: usize) -> usize {
+        let grown = match self {
+            Self::Doubling => capacity.saturating_mul(2),
+            Self::Exact => return required,
+            Self::Chunked(n) => return required.div_ceil(n).saturating_mul(n),
+            Self::Factor(factor) => ceil_to_usize(capacity as f64 * f64::from(factor)),
+        };
+
+        grown.max(required).max(MIN_NON_ZERO_CAPACITY)
+    }
+}
+
+impl Default for ArchetypeSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// An [`Event`] sent when an entity can't be moved to a new archetype because
+/// the world has no room for more archetypes.
+///
+/// The structural change that needed the archetype is dropped: the inserted
+/// component is dropped or the removed component stays on the entity. This
+/// only happens once about `u32::MAX` archetypes exist at the same time.
+///
+/// Like [`Despawned`], this is only sent if the event has been added to the
+/// world, such as by a system receiving it.
+///
+/// [`Despawned`]: crate::event::Despawned
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct ArchetypeLimitReached {
+    /// The number of archetypes in the world.
+    pub count: usize,
+    /// The components of the archetype that couldn't be created, in
+    /// ascending order of their indices.
+    pub components: Box<[ComponentId]>,
+}
+
+/// Identifies the column buffers of an [`Archetype`]. The version changes
+/// whenever the columns are reallocated, and is never shared by two
+/// archetypes, even if one reuses the index of the other after it was
+/// removed.
+///
+/// Archetype states built from an archetype's column pointers stay valid
+/// for as long as its version is unchanged.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct ColumnVersion {
+    /// The position of the archetype in the order archetypes were created.
+    archetype: u64,
+    /// The number of times the columns were reallocated.
+    reallocs: u64,
+}
+
+/// An edge of the archetype graph which inserts or removes several components
+/// at once.
+#[derive(Debug)]
+struct MultiEdge {
+    components: Box<[ComponentIdx]>,
+    dst: ArchetypeIdx,
+}
+
+/// The number of components in each chunk of a [chunked](Column::is_chunked)
+/// column.
+pub const CHUNK_LEN: usize = crate::blob_vec::CHUNK_LEN;
+
+/// The smallest capacity an archetype grows to, so that the first few entities
+/// don't reallocate every time.
+const MIN_NON_ZERO_CAPACITY: usize = 4;
+
+/// Is `with` exactly the union of the sorted sets `set` and `extra`, with no
+/// overlap between the two?
+fn is_set_with(set: &[ComponentIdx], with: &[ComponentIdx], extra: &[ComponentIdx]) -> bool {
+    with.len() == set.len() + extra.len()
+        && set.iter().all(|c| with.binary_search(c).is_ok())
+        && extra.iter().all(|c| with.binary_search(c).is_ok())
+}
+
+/// Rounds `x` up to an integer, saturating at the bounds of `usize`. Unlike
+/// `f64::ceil`, this is available without `std`.
+fn ceil_to_usize(x: f64) -> usize {
+    // Float to integer casts saturate and truncate towards zero.
+    let truncated = x as usize;
+
+    if (truncated as f64) < x {
+        truncated.saturating_add(1)
+    } else {
+        truncated
+    }
+}
+
+/// Returns the number of archetype indices available. The last index is
+/// reserved for [`ArchetypeIdx::NULL`].
+fn max_archetypes() -> usize {
+    #[cfg(test)]
+    if let Some(max) = tests::MAX_ARCHETYPES.get() {
+        return max;
+    }
+
+    u32::MAX as usize
+}
+
+/// Returns the number of rows available in each archetype.
+fn max_rows() -> usize {
+    #[cfg(test)]
+    if let Some(max) = tests::MAX_ROWS.get() {
+        return max;
+    }
+
+    u32::MAX as usize
 }
 
 impl Archetypes {
     pub(crate) fn new() -> Self {
         Self {
             archetypes: Slab::from_iter([(0, Archetype::empty())]),
-            by_components: BTreeMap::from_iter([(vec![].into_boxed_slice(), ArchetypeIdx::EMPTY)]),
+            by_components: BTreeSet::from_iter([(hash_component_set(&[]), ArchetypeIdx::EMPTY)]),
+            settings: ArchetypeSettings::DEFAULT,
+            lock: StructureLock::default(),
+            created: 1,
+            listeners_version: 0,
+            groups: Groups::new(),
+            #[cfg(feature = "diagnostics")]
+            diagnostics: DiagnosticQueue::new(),
+            #[cfg(feature = "diff")]
+            changes: None,
+        }
+    }
+
+    /// Returns the settings used to allocate room for entities.
+    pub fn settings(&self) -> ArchetypeSettings {
+        self.settings
+    }
+
+    /// Gets the [`GroupInfo`] of a [`ComponentGroup`] added with
+    /// [`World::add_group`]. Returns `None` if the index is invalid.
+    ///
+    /// [`ComponentGroup`]: crate::group::ComponentGroup
+    pub fn group(&self, idx: GroupIdx) -> Option<&GroupInfo> {
+        self.groups.get(idx)
+    }
+
+    /// Returns an iterator over the archetypes with every member of the group
+    /// at `idx`.
+    pub fn archetypes_with_group(&self, idx: GroupIdx) -> impl Iterator<Item = &Archetype> {
+        self.archetypes
+            .iter()
+            .map(|(_, arch)| arch)
+            .filter(move |arch| arch.has_group(idx))
+    }
+
+    pub(crate) fn groups(&self) -> &Groups {
+        &self.groups
+    }
+
+    /// Adds a group and records which archetypes have all of its members.
+    pub(crate) fn add_group(
+        &mut self,
+        name: Cow<'static, str>,
+        type_id: TypeId,
+        components: Vec<ComponentIdx>,
+    ) -> GroupIdx {
+        let idx = self.groups.add(name, type_id, components);
+        let info = self.groups.get(idx).unwrap();
+
+        for (_, arch) in &mut self.archetypes {
+            if info.matches(&arch.component_set) {
+                arch.groups.insert(idx);
+            }
+        }
+
+        idx
+    }
+
+    /// Changes the settings used to allocate room for entities. Existing
+    /// archetypes keep their capacity, except for the empty archetype which is
+    /// grown to the new initial capacity.
+    pub(crate) fn set_settings(&mut self, settings: ArchetypeSettings) {
+        match settings.growth {
+            ArchetypeGrowth::Chunked(n) => {
+                assert!(n > 0, "archetype growth chunks must not be empty");
+            }
+            ArchetypeGrowth::Factor(factor) => assert!(
+                factor >= 1.0,
+                "archetype growth factor must be at least 1.0, got {factor}"
+            ),
+            ArchetypeGrowth::Doubling | ArchetypeGrowth::Exact => {}
+        }
+
+        self.settings = settings;
+
+        let len = self.empty().entity_count() as usize;
+        self.reserve_empty(settings.initial_entity_capacity.saturating_sub(len));
+    }
+
+    /// Makes room for at least `additional` more entities in the empty
+    /// archetype.
+    pub(crate) fn reserve_empty(&mut self, additional: usize) {
+        let growth = self.settings.growth;
+        let empty = self.empty_mut();
+
+        if empty.reserve(additional, growth) && empty.entity_count() > 0 {
+            for &ptr in &empty.refresh_listeners {
+                let system = unsafe { &mut (*ptr.as_ptr()).system };
+                system.refresh_archetype(empty);
+            }
+        }
+    }
+
+    /// Grows the archetype at `idx` to exactly `capacity` entities, unless it
+    /// already has room for them.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be a valid archetype index.
+    pub(crate) unsafe fn reserve_exact(&mut self, idx: ArchetypeIdx, capacity: usize) {
+        let arch = unsafe { self.archetypes.get_debug_checked_mut(idx.0 as usize) };
+        let additional = capacity.saturating_sub(arch.entity_ids.len());
+
+        if arch.reserve(additional, ArchetypeGrowth::Exact) && arch.entity_count() > 0 {
+            for &ptr in &arch.refresh_listeners {
+                let system = unsafe { &mut (*ptr.as_ptr()).system };
+                system.refresh_archetype(arch);
+            }
         }
     }
 
@@ -73,24 +385,48 @@ impl Archetypes {
         self.archetypes.get(idx.0 as usize)
     }
 
+    pub(crate) fn pin(&mut self, idx: ArchetypeIdx) -> Option<ArchetypePin> {
+        self.archetypes.get_mut(idx.0 as usize).map(Archetype::pin)
+    }
+
     /// Gets a reference to the archetype with the given set of components.
     ///
     /// Returns `None` if there is no archetype with the given set of
     /// components or the given [`ComponentIdx`] slice is not sorted and
     /// deduplicated.
     pub fn get_by_components(&self, components: &[ComponentIdx]) -> Option<&Archetype> {
-        let idx = *self.by_components.get(components)?;
+        let idx = self.find_by_components(hash_component_set(components), components)?;
         Some(unsafe { self.get(idx).unwrap_debug_checked() })
     }
 
+    fn find_by_components(&self, hash: u64, components: &[ComponentIdx]) -> Option<ArchetypeIdx> {
+        self.by_components
+            .range((hash, ArchetypeIdx(0))..=(hash, ArchetypeIdx(u32::MAX)))
+            .map(|&(_, idx)| idx)
+            .find(|&idx| {
+                unsafe { self.archetypes.get_debug_checked(idx.0 as usize) }.component_set()
+                    == components
+            })
+    }
+
+    /// Gets the [`EntityId`] of the entity at the given row of an archetype.
+    /// Returns `None` if the archetype index is invalid or the row is out of
+    /// bounds.
+    ///
+    /// This is the inverse of [`Entities::get`].
+    pub fn entity_at(&self, idx: ArchetypeIdx, row: ArchetypeRow) -> Option<EntityId> {
+        self.get(idx)?.entity_ids.get(row.0 as usize).copied()
+    }
+
     /// Spawns a new entity into the empty archetype with the given ID and
     /// returns its location.
     pub(crate) fn spawn(&mut self, id: EntityId) -> EntityLocation {
+        let growth = self.settings.growth;
         let empty = self.empty_mut();
 
-        let rellocated = empty.push_would_reallocate();
+        let row = empty.row_at(empty.entity_ids.len());
+        let rellocated = empty.reserve(1, growth);
 
-        let row = ArchetypeRow(empty.entity_count());
         empty.entity_ids.push(id);
 
         if empty.entity_count() == 1 || rellocated {
@@ -100,12 +436,216 @@ impl Archetypes {
             }
         }
 
+        #[cfg(feature = "diff")]
+        if let Some(changes) = &mut self.changes {
+            changes.spawned(id);
+        }
+
         EntityLocation {
             archetype: ArchetypeIdx::EMPTY,
             row,
         }
     }
 
+    /// Adds the entity spawned last with [`spawn`](Self::spawn) to the default
+    /// entity group, once its location is in `entities`.
+    pub(crate) fn attach_spawned(&mut self, entities: &mut Entities) {
+        let empty = self.empty_mut();
+
+        if let Some(&(_, end)) = empty.entity_groups.last() {
+            if (end as usize) < empty.entity_ids.len() {
+                unsafe { empty.attach_row(end as usize, EntityGroupId::DEFAULT, entities) };
+            }
+        }
+    }
+
+    /// Moves an entity to another entity group without changing its
+    /// archetype.
+    ///
+    /// # Safety
+    ///
+    /// `loc` must be the location of an entity in `entities`.
+    pub(crate) unsafe fn set_entity_group(
+        &mut self,
+        loc: EntityLocation,
+        group: EntityGroupId,
+        entities: &mut Entities,
+    ) {
+        self.lock.check();
+
+        let arch = self
+            .archetypes
+            .get_debug_checked_mut(loc.archetype.0 as usize);
+
+        if arch.entity_group_at(loc.row) == group {
+            return;
+        }
+
+        arch.check_unpinned();
+
+        // Make the default group explicit, so that the entity is moved past the other
+        // entities before it joins its new group.
+        if arch.entity_groups.is_empty() {
+            let len = arch.entity_count();
+            arch.entity_groups.push((EntityGroupId::DEFAULT, len));
+        }
+
+        let row = arch.detach_row(loc.row, entities);
+        arch.attach_row(row.0 as usize, group, entities);
+    }
+
+    /// Checks the consistency of the archetype graph and the entity locations
+    /// in `entities`. Panics if an inconsistency is found.
+    #[track_caller]
+    pub(crate) fn check_invariants(&self, entities: &Entities) {
+        assert_eq!(
+            self.by_components.len(),
+            self.archetypes.len(),
+            "archetype lookup table has the wrong number of entries"
+        );
+
+        let mut entity_count = 0_usize;
+
+        for (key, arch) in &self.archetypes {
+            let idx = arch.index;
+
+            assert_eq!(idx.0 as usize, key, "archetype stored at the wrong index");
+
+            assert!(
+                arch.component_set.windows(2).all(|w| w[0] < w[1]),
+                "component set of {idx:?} is not sorted and deduplicated"
+            );
+
+            assert!(
+                self.by_components
+                    .contains(&(hash_component_set(&arch.component_set), idx)),
+                "{idx:?} is missing from the archetype lookup table"
+            );
+
+            assert_eq!(
+                self.find_by_components(
+                    hash_component_set(&arch.component_set),
+                    &arch.component_set
+                ),
+                Some(idx),
+                "component set of {idx:?} doesn't identify it"
+            );
+
+            assert_eq!(
+                arch.columns.len(),
+                arch.component_set.len(),
+                "{idx:?} has the wrong number of columns"
+            );
+
+            for (col, &comp) in arch.columns.iter().zip(arch.component_set.iter()) {
+                assert_eq!(
+                    col.component_idx, comp,
+                    "columns of {idx:?} don't match its component set"
+                );
+                assert_eq!(
+                    col.data.len(),
+                    arch.entity_ids.len(),
+                    "column {comp:?} of {idx:?} has the wrong length"
+                );
+            }
+
+            for (row, &id) in arch.entity_ids.iter().enumerate() {
+                let loc = EntityLocation {
+                    archetype: idx,
+                    row: arch.row_at(row),
+                };
+
+                assert_eq!(
+                    entities.get(id),
+                    Some(loc),
+                    "location of {id:?} doesn't point to its row in {idx:?}"
+                );
+            }
+
+            entity_count += arch.entity_ids.len();
+
+            let mut start = 0;
+
+            for (i, &(group, end)) in arch.entity_groups.iter().enumerate() {
+                assert!(end > start, "{group:?} has no rows in {idx:?}");
+                assert!(
+                    arch.entity_groups[..i].iter().all(|&(g, _)| g != group),
+                    "rows of {group:?} in {idx:?} are not contiguous"
+                );
+
+                start = end;
+            }
+
+            assert!(
+                arch.entity_groups.is_empty() || start as usize == arch.entity_ids.len(),
+                "entity groups of {idx:?} don't cover its rows"
+            );
+            assert!(
+                !matches!(*arch.entity_groups, [(EntityGroupId::DEFAULT, _)]),
+                "{idx:?} lists the default entity group by itself"
+            );
+
+            for (&comp, &dst) in &arch.insert_components {
+                assert!(
+                    arch.component_set.binary_search(&comp).is_err(),
+                    "insert edge for {comp:?} starts at {idx:?} which already has it"
+                );
+
+                let dst_arch = self
+                    .get(dst)
+                    .expect("insert edge leads to a missing archetype");
+
+                assert!(
+                    is_set_with(&arch.component_set, &dst_arch.component_set, &[comp]),
+                    "insert edge for {comp:?} from {idx:?} leads to the wrong archetype"
+                );
+                assert_eq!(
+                    dst_arch.remove_components.get(&comp),
+                    Some(&idx),
+                    "insert edge for {comp:?} from {idx:?} has no matching remove edge"
+                );
+            }
+
+            for (&comp, &dst) in &arch.remove_components {
+                let dst_arch = self
+                    .get(dst)
+                    .expect("remove edge leads to a missing archetype");
+
+                assert!(
+                    is_set_with(&dst_arch.component_set, &arch.component_set, &[comp]),
+                    "remove edge for {comp:?} from {idx:?} leads to the wrong archetype"
+                );
+                assert_eq!(
+                    dst_arch.insert_components.get(&comp),
+                    Some(&idx),
+                    "remove edge for {comp:?} from {idx:?} has no matching insert edge"
+                );
+            }
+
+            for edge in arch.insert_many.values() {
+                let dst_arch = self
+                    .get(edge.dst)
+                    .expect("insert edge leads to a missing archetype");
+
+                assert!(
+                    is_set_with(
+                        &arch.component_set,
+                        &dst_arch.component_set,
+                        &edge.components
+                    ),
+                    "insert edge for {:?} from {idx:?} leads to the wrong archetype",
+                    edge.components
+                );
+            }
+        }
+
+        assert_eq!(
+            entity_count,
+            entities.len() as usize,
+            "number of entities in archetypes doesn't match the number of entities"
+        );
+    }
+
     /// Returns an iterator over all archetypes in an arbitrary order.
     pub fn iter(&self) -> impl Iterator<Item = &Archetype> {
         self.archetypes.iter().map(|(_, v)| v)
@@ -116,7 +656,44 @@ impl Archetypes {
         self.archetypes.len()
     }
 
+    /// Returns the number of entities in all archetypes, which is always
+    /// equal to [`Entities::len`].
+    pub fn entity_count_total(&self) -> u32 {
+        self.iter().map(Archetype::entity_count).sum()
+    }
+
+    /// Returns the number of bytes used by the archetype table and the index
+    /// of component sets, not counting the archetypes themselves.
+    pub(crate) fn table_heap_size(&self) -> usize {
+        self.archetypes.capacity() * mem::size_of::<Archetype>()
+            + self.by_components.len() * mem::size_of::<(u64, ArchetypeIdx)>()
+    }
+
+    /// Returns a number which changes whenever the result of
+    /// [`Archetype::system_list_for`] might change for an existing archetype,
+    /// so that the lists can be cached while an event is broadcast.
+    pub(crate) fn listeners_version(&self) -> u64 {
+        self.listeners_version
+    }
+
+    /// Records the columns borrowed mutably on the current tick, which is
+    /// about to end, if the world is tracking changes.
+    #[cfg(feature = "diff")]
+    pub(crate) fn flush_column_changes(&mut self) {
+        let Some(tick) = self.changes.as_ref().map(|log| log.tick) else {
+            return;
+        };
+
+        for (_, arch) in &mut self.archetypes {
+            for col in &mut arch.columns {
+                col.changes.flush(tick);
+            }
+        }
+    }
+
     pub(crate) fn register_system(&mut self, info: &mut SystemInfo) {
+        self.listeners_version += 1;
+
         // TODO: use a `Component -> Vec<Archetype>` index to make this faster?
         for (_, arch) in &mut self.archetypes {
             arch.register_system(info);
@@ -124,13 +701,17 @@ impl Archetypes {
     }
 
     pub(crate) fn remove_system(&mut self, info: &SystemInfo) {
+        self.listeners_version += 1;
+
         // TODO: use a `Component -> Vec<Archetype>` index to make this faster?
         for (_, arch) in &mut self.archetypes {
             arch.refresh_listeners.remove(&info.ptr());
 
-            if let EventIdx::Targeted(idx) = info.received_event().index() {
-                if let Some(list) = arch.event_listeners.get_mut(idx) {
-                    list.remove(info.ptr());
+            for event in info.received_events() {
+                if let EventIdx::Targeted(idx) = event.index() {
+                    if let Some(list) = arch.event_listeners.get_mut(idx) {
+                        list.remove(info.ptr());
+                    }
                 }
             }
         }
@@ -140,8 +721,19 @@ impl Archetypes {
     where
         F: FnMut(EntityId),
     {
-        self.by_components.retain(|comps, &mut idx| {
-            if comps.binary_search(&component_idx).is_ok() {
+        // The archetypes with the component are removed below, so only the groups
+        // themselves need updating.
+        self.groups.remove_component(component_idx);
+
+        // The indices of the removed archetypes may be reused.
+        self.listeners_version += 1;
+
+        self.by_components.retain(|&(_, idx)| {
+            let arch = unsafe { self.archetypes.get_debug_checked(idx.0 as usize) };
+
+            if arch.component_set.binary_search(&component_idx).is_ok() {
+                arch.check_unpinned();
+
                 let arch = self.archetypes.remove(idx.0 as usize);
 
                 for sys in &arch.refresh_listeners {
@@ -150,6 +742,11 @@ impl Archetypes {
 
                 for &entity_id in arch.entity_ids() {
                     f(entity_id);
+
+                    #[cfg(feature = "diff")]
+                    if let Some(changes) = &mut self.changes {
+                        changes.despawned(entity_id);
+                    }
                 }
 
                 for (comp_idx, arch_idx) in arch.insert_components {
@@ -171,182 +768,389 @@ impl Archetypes {
                 true
             }
         });
+
+        // Archetype indices may be reused, so edges into removed archetypes must not
+        // survive.
+        let live = self
+            .archetypes
+            .iter()
+            .map(|(idx, _)| idx)
+            .collect::<BTreeSet<_>>();
+
+        for (_, arch) in &mut self.archetypes {
+            let is_live = |_: &u64, edge: &mut MultiEdge| live.contains(&(edge.dst.0 as usize));
+
+            arch.insert_many.retain(is_live);
+        }
     }
 
     /// Traverses one edge of the archetype graph in the insertion direction.
-    /// Returns the destination archetype.
+    /// Returns the destination archetype, or an error if it would have to be
+    /// created and there is no room for more archetypes.
     pub(crate) unsafe fn traverse_insert(
         &mut self,
         src_arch_idx: ArchetypeIdx,
         component_idx: ComponentIdx,
         components: &mut Components,
         systems: &mut Systems,
-    ) -> ArchetypeIdx {
-        debug_assert!(components.get_by_index(component_idx).is_some());
+    ) -> Result<ArchetypeIdx, ArchetypeLimitReached> {
+        self.lock.check();
 
-        let next_arch_idx = self.archetypes.vacant_key();
+        debug_assert!(components.get_by_index(component_idx).is_some());
 
         let src_arch = unsafe {
             self.archetypes
                 .get_debug_checked_mut(src_arch_idx.0 as usize)
         };
 
-        match src_arch.insert_components.entry(component_idx) {
-            Entry::Vacant(vacant_insert_components) => {
-                let Err(idx) = src_arch
-                    .columns
-                    .binary_search_by_key(&component_idx, |c| c.component_idx)
-                else {
-                    // Archetype already has this component.
-                    return src_arch_idx;
-                };
-
-                let mut new_components = Vec::with_capacity(src_arch.columns.len() + 1);
-                new_components.extend(src_arch.columns.iter().map(|c| c.component_idx));
-                new_components.insert(idx, component_idx);
-
-                match self.by_components.entry(new_components.into_boxed_slice()) {
-                    Entry::Vacant(vacant_by_components) => {
-                        assert!(next_arch_idx < u32::MAX as usize, "too many archetypes");
-
-                        let arch_id = ArchetypeIdx(next_arch_idx as u32);
-
-                        let mut new_arch = Archetype::new(
-                            arch_id,
-                            vacant_by_components.key().iter().copied(),
-                            components,
-                        );
-
-                        new_arch
-                            .remove_components
-                            .insert(component_idx, src_arch_idx);
+        if let Some(&idx) = src_arch.insert_components.get(&component_idx) {
+            return Ok(idx);
+        }
 
-                        for info in systems.iter_mut() {
-                            new_arch.register_system(info);
-                        }
+        let Err(idx) = src_arch.component_set.binary_search(&component_idx) else {
+            // Archetype already has this component.
+            return Ok(src_arch_idx);
+        };
 
-                        vacant_by_components.insert(arch_id);
+        let mut new_components = Vec::with_capacity(src_arch.component_set.len() + 1);
+        new_components.extend_from_slice(&src_arch.component_set);
+        new_components.insert(idx, component_idx);
 
-                        vacant_insert_components.insert(arch_id);
+        let dst_arch_idx = self.get_or_insert(new_components, components, systems)?;
 
-                        self.archetypes.insert(new_arch);
+        unsafe {
+            self.archetypes
+                .get_debug_checked_mut(src_arch_idx.0 as usize)
+        }
+        .insert_components
+        .insert(component_idx, dst_arch_idx);
 
-                        arch_id
-                    }
-                    Entry::Occupied(o) => *vacant_insert_components.insert(*o.get()),
-                }
-            }
-            Entry::Occupied(o) => *o.get(),
+        unsafe {
+            self.archetypes
+                .get_debug_checked_mut(dst_arch_idx.0 as usize)
         }
+        .remove_components
+        .insert(component_idx, src_arch_idx);
+
+        Ok(dst_arch_idx)
     }
 
     /// Traverses one edge of the archetype graph in the remove direction.
-    /// Returns the destination archetype.
+    /// Returns the destination archetype, or an error like
+    /// [`Self::traverse_insert`].
     pub(crate) unsafe fn traverse_remove(
         &mut self,
         src_arch_idx: ArchetypeIdx,
         component_idx: ComponentIdx,
         components: &mut Components,
         systems: &mut Systems,
-    ) -> ArchetypeIdx {
-        let next_arch_idx = self.archetypes.vacant_key();
+    ) -> Result<ArchetypeIdx, ArchetypeLimitReached> {
+        self.lock.check();
 
         let src_arch = unsafe {
             self.archetypes
                 .get_debug_checked_mut(src_arch_idx.0 as usize)
         };
 
-        match src_arch.remove_components.entry(component_idx) {
-            Entry::Vacant(vacant_remove_components) => {
-                if src_arch
-                    .columns
-                    .binary_search_by_key(&component_idx, |c| c.component_idx)
-                    .is_err()
-                {
-                    // Archetype already doesn't have the component.
-                    return src_arch_idx;
-                }
-
-                let mut new_components = Vec::with_capacity(src_arch.columns.len() - 1);
-                new_components.extend(
-                    src_arch
-                        .columns
-                        .iter()
-                        .map(|c| c.component_idx)
-                        .filter(|&c| c != component_idx),
-                );
+        if let Some(&idx) = src_arch.remove_components.get(&component_idx) {
+            return Ok(idx);
+        }
 
-                match self.by_components.entry(new_components.into_boxed_slice()) {
-                    Entry::Vacant(vacant_by_components) => {
-                        assert!(next_arch_idx < u32::MAX as usize, "too many archetypes");
+        let Ok(idx) = src_arch.component_set.binary_search(&component_idx) else {
+            // Archetype already doesn't have the component.
+            return Ok(src_arch_idx);
+        };
 
-                        let arch_id = ArchetypeIdx(next_arch_idx as u32);
+        let mut new_components = src_arch.component_set.to_vec();
+        new_components.remove(idx);
 
-                        let mut new_arch = Archetype::new(
-                            arch_id,
-                            vacant_by_components.key().iter().copied(),
-                            components,
-                        );
+        let dst_arch_idx = self.get_or_insert(new_components, components, systems)?;
 
-                        new_arch
-                            .insert_components
-                            .insert(component_idx, src_arch_idx);
+        unsafe {
+            self.archetypes
+                .get_debug_checked_mut(src_arch_idx.0 as usize)
+        }
+        .remove_components
+        .insert(component_idx, dst_arch_idx);
 
-                        for info in systems.iter_mut() {
-                            new_arch.register_system(info);
-                        }
+        unsafe {
+            self.archetypes
+                .get_debug_checked_mut(dst_arch_idx.0 as usize)
+        }
+        .insert_components
+        .insert(component_idx, src_arch_idx);
 
-                        vacant_by_components.insert(arch_id);
+        Ok(dst_arch_idx)
+    }
 
-                        vacant_remove_components.insert(arch_id);
+    /// Like [`Self::traverse_insert`], but inserts every component in
+    /// `sorted_components` at once. Intermediate archetypes are not created.
+    pub(crate) unsafe fn traverse_insert_many(
+        &mut self,
+        src_arch_idx: ArchetypeIdx,
+        sorted_components: &[ComponentIdx],
+        components: &mut Components,
+        systems: &mut Systems,
+    ) -> Result<ArchetypeIdx, ArchetypeLimitReached> {
+        self.lock.check();
 
-                        self.archetypes.insert(new_arch);
+        debug_assert!(sorted_components.windows(2).all(|w| w[0] < w[1]));
 
-                        arch_id
-                    }
-                    Entry::Occupied(o) => *vacant_remove_components.insert(*o.get()),
-                }
+        match sorted_components {
+            [] => return Ok(src_arch_idx),
+            &[component_idx] => {
+                return self.traverse_insert(src_arch_idx, component_idx, components, systems)
             }
-            Entry::Occupied(o) => *o.get(),
+            _ => {}
         }
-    }
-
-    /// Move an entity from one archetype to another. Returns the entity's row
-    /// in the new archetype.
-    pub(crate) unsafe fn move_entity(
-        &mut self,
-        src: EntityLocation,
-        dst: ArchetypeIdx,
-        new_components: impl IntoIterator<Item = (ComponentIdx, *const u8)>,
-        entities: &mut Entities,
-    ) -> ArchetypeRow {
-        let mut new_components = new_components.into_iter();
 
-        if src.archetype == dst {
-            let arch = self
-                .archetypes
-                .get_mut(src.archetype.0 as usize)
-                .unwrap_debug_checked();
+        let hash = hash_component_set(sorted_components);
 
-            for (comp_idx, comp_ptr) in new_components {
-                let col = arch.column_of_mut(comp_idx).unwrap_debug_checked();
+        let src_arch = unsafe { self.archetypes.get_debug_checked(src_arch_idx.0 as usize) };
 
-                col.data.assign(src.row.0 as usize, comp_ptr);
+        if let Some(edge) = src_arch.insert_many.get(&hash) {
+            if *edge.components == *sorted_components {
+                return Ok(edge.dst);
             }
-
-            return src.row;
         }
 
-        let (src_arch, dst_arch) = self
-            .archetypes
-            .get2_mut(src.archetype.0 as usize, dst.0 as usize)
-            .unwrap();
+        let src_set = &src_arch.component_set;
 
-        let dst_row = ArchetypeRow(dst_arch.entity_ids.len() as u32);
+        let mut new_components = Vec::with_capacity(src_set.len() + sorted_components.len());
+        new_components.extend_from_slice(src_set);
+        new_components.extend_from_slice(sorted_components);
+        new_components.sort_unstable();
+        new_components.dedup();
 
-        let dst_arch_reallocated = dst_arch.push_would_reallocate();
+        let dst_arch_idx = if new_components.len() == src_set.len() {
+            src_arch_idx
+        } else {
+            self.get_or_insert(new_components, components, systems)?
+        };
 
-        let mut src_it = src_arch.columns.iter_mut().peekable();
+        unsafe {
+            self.archetypes
+                .get_debug_checked_mut(src_arch_idx.0 as usize)
+        }
+        .insert_many
+        .insert(
+            hash,
+            MultiEdge {
+                components: sorted_components.into(),
+                dst: dst_arch_idx,
+            },
+        );
+
+        Ok(dst_arch_idx)
+    }
+
+    /// Returns the archetype with the given sorted set of components, creating
+    /// it if it doesn't exist and there is room for it.
+    unsafe fn get_or_insert(
+        &mut self,
+        component_set: Vec<ComponentIdx>,
+        components: &mut Components,
+        systems: &mut Systems,
+    ) -> Result<ArchetypeIdx, ArchetypeLimitReached> {
+        let hash = hash_component_set(&component_set);
+
+        if let Some(idx) = self.find_by_components(hash, &component_set) {
+            return Ok(idx);
+        }
+
+        let next_arch_idx = self.archetypes.vacant_key();
+
+        let arch_id = match u32::try_from(next_arch_idx) {
+            Ok(idx) if next_arch_idx < max_archetypes() => ArchetypeIdx(idx),
+            _ => {
+                return Err(ArchetypeLimitReached {
+                    count: self.archetypes.len(),
+                    components: component_set
+                        .iter()
+                        .map(|&idx| {
+                            unsafe { components.get_by_index(idx).unwrap_debug_checked() }.id()
+                        })
+                        .collect(),
+                })
+            }
+        };
+
+        let mut new_arch = Archetype::new(
+            arch_id,
+            self.created,
+            component_set.into_boxed_slice(),
+            components,
+            &self.settings,
+        );
+
+        self.created += 1;
+
+        for (group_idx, info) in self.groups.iter() {
+            if info.matches(&new_arch.component_set) {
+                new_arch.groups.insert(group_idx);
+            }
+        }
+
+        for info in systems.iter_mut() {
+            new_arch.register_system(info);
+        }
+
+        self.by_components.insert((hash, arch_id));
+        self.archetypes.insert(new_arch);
+
+        #[cfg(feature = "diagnostics")]
+        self.diagnostics.archetype_created(arch_id);
+
+        Ok(arch_id)
+    }
+
+    /// Spawns a new entity in `dst` with copies of the components of the entity
+    /// at `src`. The components of `dst` must be a subset of the components of
+    /// the source archetype, and `clone_fns` must hold the clone function of
+    /// every column in `dst`, in order.
+    ///
+    /// If a clone function unwinds, the values cloned so far are leaked and no
+    /// entity is spawned.
+    pub(crate) unsafe fn clone_entity(
+        &mut self,
+        src: EntityLocation,
+        dst: ArchetypeIdx,
+        clone_fns: &[CloneFn],
+        entities: &mut Entities,
+    ) -> EntityId {
+        let growth = self.settings.growth;
+
+        // The clone joins the entity group of the original.
+        let group = self
+            .archetypes
+            .get(src.archetype.0 as usize)
+            .unwrap_debug_checked()
+            .entity_group_at(src.row);
+
+        let dst_arch = self
+            .archetypes
+            .get_mut(dst.0 as usize)
+            .unwrap_debug_checked();
+        let row = dst_arch.row_at(dst_arch.entity_ids.len());
+        let reallocated = dst_arch.reserve(1, growth);
+
+        // Reserving may have moved the source columns if `src` and `dst` are the same
+        // archetype, so the source pointers are looked up afterwards.
+        let dst_arch = self.archetypes.get(dst.0 as usize).unwrap_debug_checked();
+        let src_arch = self
+            .archetypes
+            .get(src.archetype.0 as usize)
+            .unwrap_debug_checked();
+
+        debug_assert_eq!(dst_arch.columns.len(), clone_fns.len());
+
+        // Clone into the spare capacity first so the columns are left untouched if a
+        // clone function unwinds.
+        for (dst_col, &clone) in dst_arch.columns.iter().zip(clone_fns) {
+            let src_col = src_arch
+                .column_of(dst_col.component_idx)
+                .unwrap_debug_checked();
+            let src_ptr = src_col.data.slot(src.row.0 as usize);
+            let dst_ptr = dst_col.data.slot(dst_col.data.len());
+
+            clone(src_ptr, dst_ptr);
+        }
+
+        let dst_arch = self
+            .archetypes
+            .get_mut(dst.0 as usize)
+            .unwrap_debug_checked();
+
+        let id = entities.add_with(|_| EntityLocation {
+            archetype: dst,
+            row,
+        });
+
+        for col in &mut dst_arch.columns {
+            // The slot was initialized above.
+            col.data.push();
+        }
+
+        dst_arch.entity_ids.push(id);
+        dst_arch.attach_row(row.0 as usize, group, entities);
+
+        #[cfg(feature = "diff")]
+        if let Some(changes) = &mut self.changes {
+            changes.spawned(id);
+            changes.moved(id, &[], &dst_arch.component_set);
+        }
+
+        if reallocated || dst_arch.entity_count() == 1 {
+            for &ptr in &dst_arch.refresh_listeners {
+                let system = unsafe { &mut (*ptr.as_ptr()).system };
+                system.refresh_archetype(dst_arch);
+            }
+        }
+
+        id
+    }
+
+    /// Move an entity from one archetype to another. Returns the entity's row
+    /// in the new archetype.
+    pub(crate) unsafe fn move_entity(
+        &mut self,
+        src: EntityLocation,
+        dst: ArchetypeIdx,
+        new_components: impl IntoIterator<Item = (ComponentIdx, *const u8)>,
+        entities: &mut Entities,
+    ) -> ArchetypeRow {
+        self.lock.check();
+
+        let mut new_components = new_components.into_iter();
+
+        if src.archetype == dst {
+            let arch = self
+                .archetypes
+                .get_mut(src.archetype.0 as usize)
+                .unwrap_debug_checked();
+
+            for (comp_idx, comp_ptr) in new_components {
+                let col = arch.column_of_mut(comp_idx).unwrap_debug_checked();
+
+                debug_assert_aligned(col.row_ptr(src.row.0 as usize), col.data.elem_layout());
+                col.data.assign(src.row.0 as usize, comp_ptr);
+
+                #[cfg(feature = "diff")]
+                if let Some(changes) = &mut self.changes {
+                    changes.inserted(
+                        *arch.entity_ids.get_debug_checked(src.row.0 as usize),
+                        comp_idx,
+                    );
+                }
+            }
+
+            return src.row;
+        }
+
+        let growth = self.settings.growth;
+
+        let (src_arch, dst_arch) = self
+            .archetypes
+            .get2_mut(src.archetype.0 as usize, dst.0 as usize)
+            .unwrap();
+
+        src_arch.check_unpinned();
+        dst_arch.check_unpinned();
+
+        let dst_row = dst_arch.row_at(dst_arch.entity_ids.len());
+        let preserve_order = src_arch.preserve_order;
+
+        // The entity keeps its entity group. Taking it out of the group first leaves it
+        // in the last row, so that removing it doesn't mix up the other groups.
+        let group = src_arch.entity_group_at(src.row);
+        let src = EntityLocation {
+            row: src_arch.detach_row(src.row, entities),
+            ..src
+        };
+
+        let dst_arch_reallocated = dst_arch.reserve(1, growth);
+
+        let mut src_it = src_arch.columns.iter_mut().peekable();
         let mut dst_it = dst_arch.columns.iter_mut().peekable();
 
         // TODO: does this optimize better with raw pointers?
@@ -359,28 +1163,37 @@ impl Archetypes {
 
                     debug_assert_eq!(component_id, dst_col.component_index());
 
-                    ptr::copy_nonoverlapping(
-                        component_ptr,
-                        dst_col.data.push().as_ptr(),
-                        dst_col.data.elem_layout().size(),
-                    );
+                    let dst_ptr = dst_col.data.push();
+                    debug_assert_aligned(dst_ptr, dst_col.data.elem_layout());
+
+                    dst_col.data.copy_elem(component_ptr, dst_ptr.as_ptr());
 
                     dst_it.next();
                 }
                 (Some(src_col), None) => {
-                    src_col.data.swap_remove(src.row.0 as usize);
+                    src_col.remove_row(src.row, preserve_order);
                     src_it.next();
                 }
                 (Some(src_col), Some(dst_col)) => {
                     match src_col.component_index().cmp(&dst_col.component_index()) {
                         Ordering::Less => {
-                            src_col.data.swap_remove(src.row.0 as usize);
+                            src_col.remove_row(src.row, preserve_order);
                             src_it.next();
                         }
                         Ordering::Equal => {
-                            src_col
-                                .data
-                                .transfer_elem(&mut dst_col.data, src.row.0 as usize);
+                            src_col.data.transfer_elem(
+                                &mut dst_col.data,
+                                src.row.0 as usize,
+                                preserve_order,
+                            );
+
+                            #[cfg(feature = "diff")]
+                            dst_col.changes.merge(&src_col.changes);
+
+                            debug_assert_aligned(
+                                dst_col.row_ptr(dst_col.data.len() - 1),
+                                dst_col.data.elem_layout(),
+                            );
 
                             src_it.next();
                             dst_it.next();
@@ -391,11 +1204,10 @@ impl Archetypes {
 
                             debug_assert_eq!(component_id, dst_col.component_index());
 
-                            ptr::copy_nonoverlapping(
-                                component_ptr,
-                                dst_col.data.push().as_ptr(),
-                                dst_col.data.elem_layout().size(),
-                            );
+                            let dst_ptr = dst_col.data.push();
+                            debug_assert_aligned(dst_ptr, dst_col.data.elem_layout());
+
+                            dst_col.data.copy_elem(component_ptr, dst_ptr.as_ptr());
 
                             dst_it.next();
                         }
@@ -406,7 +1218,7 @@ impl Archetypes {
 
         debug_assert!(new_components.next().is_none());
 
-        let entity_id = src_arch.entity_ids.swap_remove(src.row.0 as usize);
+        let entity_id = src_arch.remove_entity_id(src.row, entities);
         dst_arch.entity_ids.push(entity_id);
 
         *unsafe { entities.get_mut(entity_id).unwrap_debug_checked() } = EntityLocation {
@@ -414,8 +1226,14 @@ impl Archetypes {
             row: dst_row,
         };
 
-        if let Some(&swapped_entity_id) = src_arch.entity_ids.get(src.row.0 as usize) {
-            unsafe { entities.get_mut(swapped_entity_id).unwrap_debug_checked() }.row = src.row;
+        let dst_row = dst_arch.attach_row(dst_row.0 as usize, group, entities);
+
+        #[cfg(feature = "diagnostics")]
+        self.diagnostics.entity_moved(entity_id, src.archetype, dst);
+
+        #[cfg(feature = "diff")]
+        if let Some(changes) = &mut self.changes {
+            changes.moved(entity_id, &src_arch.component_set, &dst_arch.component_set);
         }
 
         if src_arch.entity_ids.is_empty() {
@@ -435,8 +1253,140 @@ impl Archetypes {
         dst_row
     }
 
+    /// Moves every entity in `src` to `dst` in one pass. Columns shared by both
+    /// archetypes are transferred in bulk. Columns in `dst` but not in `src`
+    /// are filled by calling `fill` once per moved entity with the column's
+    /// component index and a pointer to the uninitialized slot. Columns in
+    /// `src` but not in `dst` are dropped.
+    ///
+    /// # Safety
+    /// - `src` and `dst` must be valid and distinct archetypes.
+    /// - `fill` must initialize the slot with a valid component of the given
+    ///   type.
+    pub(crate) unsafe fn move_all_entities(
+        &mut self,
+        src: ArchetypeIdx,
+        dst: ArchetypeIdx,
+        mut fill: impl FnMut(ComponentIdx, NonNull<u8>),
+        entities: &mut Entities,
+    ) {
+        self.lock.check();
+
+        debug_assert_ne!(src, dst);
+
+        let growth = self.settings.growth;
+
+        let (src_arch, dst_arch) = self
+            .archetypes
+            .get2_mut(src.0 as usize, dst.0 as usize)
+            .unwrap();
+
+        let count = src_arch.entity_ids.len();
+
+        if count == 0 {
+            return;
+        }
+
+        src_arch.check_unpinned();
+        dst_arch.check_unpinned();
+
+        // Check that the last entity fits before anything is moved.
+        dst_arch.row_at(dst_arch.entity_ids.len() + count - 1);
+
+        // Construct the new components up front. If `fill` unwinds, the archetypes
+        // are left untouched.
+        let mut filled = vec![];
+
+        for dst_col in &dst_arch.columns {
+            if src_arch.column_of(dst_col.component_idx).is_none() {
+                let mut data = dst_col.data.empty_like();
+                data.extend_with(count, |ptr| fill(dst_col.component_idx, ptr));
+                filled.push(data);
+            }
+        }
+
+        let dst_start = dst_arch.entity_ids.len();
+        let dst_arch_reallocated = dst_arch.reserve(count, growth);
+
+        // The entities keep their entity groups. They're added to the groups one at a
+        // time once everything is moved.
+        let groups = if src_arch.entity_groups.is_empty() && dst_arch.entity_groups.is_empty() {
+            vec![]
+        } else {
+            (0..count)
+                .map(|row| src_arch.entity_group_at(ArchetypeRow(row as u32)))
+                .collect()
+        };
+
+        let mut filled = filled.into_iter();
+
+        for dst_col in &mut dst_arch.columns {
+            match src_arch
+                .columns
+                .binary_search_by_key(&dst_col.component_idx, |c| c.component_idx)
+            {
+                Ok(idx) => {
+                    let src_col = src_arch.columns.get_debug_checked_mut(idx);
+                    src_col.data.transfer_all(&mut dst_col.data);
+
+                    #[cfg(feature = "diff")]
+                    dst_col.changes.merge(&src_col.changes);
+                }
+                Err(_) => filled
+                    .next()
+                    .unwrap_debug_checked()
+                    .transfer_all(&mut dst_col.data),
+            }
+        }
+
+        // Drop the components that aren't in the destination.
+        for src_col in &mut src_arch.columns {
+            src_col.data.clear();
+        }
+
+        for (i, &entity_id) in src_arch.entity_ids.iter().enumerate() {
+            *unsafe { entities.get_mut(entity_id).unwrap_debug_checked() } = EntityLocation {
+                archetype: dst,
+                row: dst_arch.row_at(dst_start + i),
+            };
+        }
+
+        #[cfg(feature = "diagnostics")]
+        if self.diagnostics.is_listening_for_moves() {
+            for &entity_id in &src_arch.entity_ids {
+                self.diagnostics.entity_moved(entity_id, src, dst);
+            }
+        }
+
+        #[cfg(feature = "diff")]
+        if let Some(changes) = &mut self.changes {
+            for &entity_id in &src_arch.entity_ids {
+                changes.moved(entity_id, &src_arch.component_set, &dst_arch.component_set);
+            }
+        }
+
+        dst_arch.entity_ids.append(&mut src_arch.entity_ids);
+        src_arch.entity_groups.clear();
+
+        for (i, group) in groups.into_iter().enumerate() {
+            dst_arch.attach_row(dst_start + i, group, entities);
+        }
+
+        for &ptr in &src_arch.refresh_listeners {
+            let system = unsafe { &mut (*ptr.as_ptr()).system };
+            system.remove_archetype(src_arch);
+        }
+
+        if dst_arch_reallocated || dst_start == 0 {
+            for &ptr in &dst_arch.refresh_listeners {
+                let system = unsafe { &mut (*ptr.as_ptr()).system };
+                system.refresh_archetype(dst_arch);
+            }
+        }
+    }
+
     pub(crate) fn remove_entity(&mut self, entity: EntityId, entities: &mut Entities) {
-        let Some(loc) = entities.remove(entity) else {
+        let Some(mut loc) = entities.get(entity) else {
             return;
         };
 
@@ -445,15 +1395,42 @@ impl Archetypes {
                 .get_debug_checked_mut(loc.archetype.0 as usize)
         };
 
-        for col in arch.columns.iter_mut() {
-            unsafe { col.data.swap_remove(loc.row.0 as usize) };
+        arch.check_unpinned();
+
+        loc.row = unsafe { arch.detach_row(loc.row, entities) };
+        entities.remove(entity);
+
+        let row = loc.row.0 as usize;
+
+        match (arch.needs_drop, arch.preserve_order) {
+            (true, false) => {
+                for col in &mut arch.columns {
+                    unsafe { col.data.swap_remove(row) };
+                }
+            }
+            (false, false) => {
+                for col in &mut arch.columns {
+                    unsafe { col.data.swap_remove_no_drop(row) };
+                }
+            }
+            (true, true) => {
+                for col in &mut arch.columns {
+                    unsafe { col.data.remove(row) };
+                }
+            }
+            (false, true) => {
+                for col in &mut arch.columns {
+                    unsafe { col.data.remove_no_drop(row) };
+                }
+            }
         }
 
-        unsafe {
-            assume_debug_checked((loc.row.0 as usize) < arch.entity_ids.len());
-        };
+        arch.remove_entity_id(loc.row, entities);
 
-        arch.entity_ids.swap_remove(loc.row.0 as usize);
+        #[cfg(feature = "diff")]
+        if let Some(changes) = &mut self.changes {
+            changes.despawned(entity);
+        }
 
         if arch.entity_count() == 0 {
             for &ptr in &arch.refresh_listeners {
@@ -462,6 +1439,39 @@ impl Archetypes {
             }
         }
     }
+
+    /// Removes every entity in the archetype, dropping the components one
+    /// column at a time. The capacity of the archetype is kept.
+    pub(crate) fn remove_all_entities(&mut self, arch_idx: ArchetypeIdx, entities: &mut Entities) {
+        let arch = unsafe { self.archetypes.get_debug_checked_mut(arch_idx.0 as usize) };
+
+        if arch.entity_ids.is_empty() {
+            return;
+        }
+
+        arch.check_unpinned();
+
+        for &entity in &arch.entity_ids {
+            entities.remove(entity);
+
+            #[cfg(feature = "diff")]
+            if let Some(changes) = &mut self.changes {
+                changes.despawned(entity);
+            }
+        }
+
+        for col in &mut arch.columns {
+            col.data.clear();
+        }
+
+        arch.entity_ids.clear();
+        arch.entity_groups.clear();
+
+        for &ptr in &arch.refresh_listeners {
+            let system = unsafe { &mut (*ptr.as_ptr()).system };
+            system.remove_archetype(arch);
+        }
+    }
 }
 
 unsafe impl SystemParam for &'_ Archetypes {
@@ -487,10 +1497,33 @@ unsafe impl SystemParam for &'_ Archetypes {
     fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
 }
 
+/// Asserts that a pointer into a column meets the alignment of the column's
+/// components. Column elements are padded to a multiple of their alignment, so
+/// checking the base pointer covers every row.
+#[inline]
+#[track_caller]
+fn debug_assert_aligned(ptr: NonNull<u8>, layout: Layout) {
+    debug_assert!(
+        (ptr.as_ptr() as usize).is_multiple_of(layout.align()),
+        "component pointer {ptr:?} is not aligned to {}",
+        layout.align()
+    );
+}
+
+/// Hashes a sorted set of components using the same mixing step as rustc's
+/// `FxHasher`, which is fast for small integer keys.
+fn hash_component_set(components: &[ComponentIdx]) -> u64 {
+    const K: u64 = 0x517c_c1b7_2722_0a95;
+
+    components.iter().fold(0, |hash, idx| {
+        (hash.rotate_left(5) ^ u64::from(idx.0)).wrapping_mul(K)
+    })
+}
+
 /// Unique identifier for an archetype.
 ///
 /// Old archetype indices may be reused by new archetypes.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct ArchetypeIdx(pub u32);
 
 impl ArchetypeIdx {
@@ -535,14 +1568,40 @@ pub struct Archetype {
     index: ArchetypeIdx,
     /// Entity IDs of the entities in this archetype.
     entity_ids: Vec<EntityId>,
+    /// The sorted set of components in this archetype. Matches the component
+    /// indices of `columns`.
+    component_set: Box<[ComponentIdx]>,
+    /// The same components as `component_set`, for testing against many
+    /// components at once. Archetypes never change their components and are
+    /// removed along with any of them, so this never goes stale.
+    component_bits: BitSet<ComponentIdx>,
     /// Columns of component data in this archetype. Sorted by component index.
     columns: Box<[Column]>,
     insert_components: BTreeMap<ComponentIdx, ArchetypeIdx>,
     remove_components: BTreeMap<ComponentIdx, ArchetypeIdx>,
+    /// Edges which insert several components at once, keyed by the hash of the
+    /// inserted components.
+    insert_many: BTreeMap<u64, MultiEdge>,
     /// Systems that need to be notified about column changes.
     refresh_listeners: BTreeSet<SystemInfoPtr>,
     /// Targeted event listeners for this archetype.
-    event_listeners: SparseMap<TargetedEventIdx, SystemList>,
+    event_listeners: SmallSparseMap<TargetedEventIdx, SystemList>,
+    /// The component groups whose members are all in this archetype.
+    groups: BitSet<GroupIdx>,
+    /// The [entity groups](crate::entity_group) of the entities as `(group,
+    /// end)` pairs. The rows of each group end at `end` and start where the
+    /// previous group's rows end. Empty if every entity is in the default
+    /// group.
+    entity_groups: Vec<(EntityGroupId, u32)>,
+    /// Whether any of the columns has a [`DropFn`](crate::drop::DropFn).
+    needs_drop: bool,
+    /// Whether any of the components [preserves
+    /// order](crate::component::Component::PRESERVE_ORDER).
+    preserve_order: bool,
+    version: ColumnVersion,
+    /// The number of live [`ArchetypePin`]s, created the first time the
+    /// archetype is pinned.
+    pins: Option<Arc<AtomicU32>>,
 }
 
 impl Archetype {
@@ -550,69 +1609,131 @@ impl Archetype {
         Self {
             index: ArchetypeIdx::EMPTY,
             entity_ids: vec![],
+            component_set: Box::new([]),
+            component_bits: BitSet::new(),
             columns: Box::new([]),
             insert_components: BTreeMap::new(),
+            insert_many: BTreeMap::new(),
             remove_components: BTreeMap::new(),
             refresh_listeners: BTreeSet::new(),
-            event_listeners: SparseMap::new(),
+            event_listeners: SmallSparseMap::new(),
+            groups: BitSet::new(),
+            entity_groups: vec![],
+            needs_drop: false,
+            preserve_order: false,
+            version: ColumnVersion {
+                archetype: 0,
+                reallocs: 0,
+            },
+            pins: None,
         }
     }
 
     /// # Safety
     ///
-    /// Iterator must be sorted in ascending order and all IDs must be valid.
+    /// Component set must be sorted in ascending order and all IDs must be
+    /// valid.
     unsafe fn new(
         index: ArchetypeIdx,
-        ids: impl IntoIterator<Item = ComponentIdx>,
+        serial: u64,
+        component_set: Box<[ComponentIdx]>,
         comps: &Components,
+        settings: &ArchetypeSettings,
     ) -> Self {
-        Self {
-            entity_ids: vec![],
-            columns: ids
-                .into_iter()
-                .map(|idx| {
-                    let comp = unsafe {
-                        comps
-                            .get_by_index(idx)
-                            .expect_debug_checked("invalid component ID")
-                    };
+        let mut capacity = settings.initial_entity_capacity;
+        let mut needs_drop = false;
+        let mut preserve_order = false;
+
+        let columns = component_set
+            .iter()
+            .map(|&idx| {
+                let comp = unsafe {
+                    comps
+                        .get_by_index(idx)
+                        .expect_debug_checked("invalid component ID")
+                };
 
-                    Column {
-                        data: unsafe { BlobVec::new(comp.layout(), comp.drop()) },
-                        component_idx: idx,
-                    }
-                })
-                .collect(),
+                capacity = capacity.max(comp.default_capacity());
+                needs_drop |= comp.drop().is_some();
+                preserve_order |= comp.preserves_order();
+
+                let data = if comp.is_chunked() {
+                    unsafe { BlobVec::new_chunked(comp.layout(), comp.drop(), comp.copy_fn()) }
+                } else {
+                    unsafe { BlobVec::new(comp.layout(), comp.drop(), comp.copy_fn()) }
+                };
+
+                Column {
+                    data,
+                    component_idx: idx,
+                    #[cfg(feature = "diff")]
+                    changes: ColumnChanges::default(),
+                }
+            })
+            .collect::<Box<[_]>>();
+
+        let mut arch = Self {
+            entity_ids: vec![],
+            columns,
+            component_bits: component_set.iter().copied().collect(),
+            component_set,
             insert_components: BTreeMap::new(),
+            insert_many: BTreeMap::new(),
             remove_components: BTreeMap::new(),
             refresh_listeners: BTreeSet::new(),
-            event_listeners: SparseMap::new(),
+            event_listeners: SmallSparseMap::new(),
+            groups: BitSet::new(),
+            entity_groups: vec![],
+            needs_drop,
+            preserve_order,
             index,
-        }
+            version: ColumnVersion {
+                archetype: serial,
+                reallocs: 0,
+            },
+            pins: None,
+        };
+
+        arch.reserve(capacity, settings.growth);
+
+        arch
     }
 
     fn register_system(&mut self, info: &mut SystemInfo) {
-        if info
-            .component_access()
-            .expr
-            .eval(|idx| self.column_of(idx).is_some())
-        {
-            info.system_mut().refresh_archetype(self);
+        if self.matches(&info.component_access().expr) {
+            // Systems are only told about nonempty archetypes. If this archetype is empty,
+            // the system will be refreshed once the first entity is added.
+            if self.entity_count() > 0 {
+                info.system_mut().refresh_archetype(self);
+            }
 
             self.refresh_listeners.insert(info.ptr());
         }
 
-        if let (Some(expr), EventIdx::Targeted(targeted_event_idx)) =
-            (info.targeted_event_expr(), info.received_event().index())
-        {
-            if expr.eval(|idx| self.column_of(idx).is_some()) {
-                if let Some(list) = self.event_listeners.get_mut(targeted_event_idx) {
-                    list.insert(info.ptr(), info.priority());
-                } else {
-                    let mut list = SystemList::new();
-                    list.insert(info.ptr(), info.priority());
+        // Expressions are evaluated against the full set of columns, so negative-only
+        // expressions (e.g. `Not<With<&C>>`) and the "match all" expression of
+        // `Receiver<E, ()>` match the empty archetype too.
+        //
+        // The result never needs to be revisited. Archetypes are immutable sets of
+        // components, and every component named by an expression was registered
+        // before the expression was built, so no archetype created before or after
+        // this call can contain a component the expression doesn't know about.
+        // Negative terms are decided here just like positive ones.
+        if let Some(expr) = info.targeted_event_expr() {
+            if self.matches(expr) {
+                for event in info.received_events() {
+                    let EventIdx::Targeted(targeted_event_idx) = event.index() else {
+                        continue;
+                    };
+
+                    if let Some(list) = self.event_listeners.get_mut(targeted_event_idx) {
+                        list.insert(info.ptr());
+                    } else {
+                        let mut list = SystemList::new();
+                        list.insert(info.ptr());
 
-                    self.event_listeners.insert(targeted_event_idx, list);
+                        self.event_listeners.insert(targeted_event_idx, list);
+                    }
                 }
             }
         }
@@ -622,60 +1743,560 @@ impl Archetype {
         self.event_listeners.get(idx)
     }
 
+    /// Returns the lists of systems listening for each targeted event sent to
+    /// entities in this archetype.
+    pub(crate) fn system_lists(&self) -> impl Iterator<Item = &SystemList> {
+        self.event_listeners.values()
+    }
+
     /// Returns the index of this archetype.
     pub fn index(&self) -> ArchetypeIdx {
         self.index
     }
 
+    /// Returns whether this archetype has every member of the
+    /// [`ComponentGroup`] at `idx`.
+    ///
+    /// [`ComponentGroup`]: crate::group::ComponentGroup
+    pub fn has_group(&self, idx: GroupIdx) -> bool {
+        self.groups.contains(idx)
+    }
+
     /// Returns the total number of entities in this archetype.
     pub fn entity_count(&self) -> u32 {
-        debug_assert!(u32::try_from(self.entity_ids.len()).is_ok());
-        // This doesn't truncate because entity indices are less than u32::MAX.
-        self.entity_ids.len() as u32
+        // This doesn't truncate because the rows are checked with `row_at` as entities
+        // are added.
+        unsafe { u32::try_from(self.entity_ids.len()).unwrap_debug_checked() }
     }
 
-    /// Returns a slice of [`EntityId`]s for all the entities in this archetype.
-    pub fn entity_ids(&self) -> &[EntityId] {
-        &self.entity_ids
+    /// Converts an index into the archetype's rows to an [`ArchetypeRow`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the archetype can't hold an entity at `row`.
+    #[inline]
+    #[track_caller]
+    fn row_at(&self, row: usize) -> ArchetypeRow {
+        match u32::try_from(row) {
+            Ok(row) if (row as usize) < max_rows() => ArchetypeRow(row),
+            _ => self.too_many_rows(),
+        }
     }
 
-    /// Returns a slice of columns sorted by [`ComponentIdx`].
-    pub fn columns(&self) -> &[Column] {
-        &self.columns
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn too_many_rows(&self) -> ! {
+        panic!(
+            "archetype {:?} with components {:?} can't hold more than {} entities",
+            self.index,
+            self.component_set,
+            max_rows()
+        )
     }
 
-    /// Finds the column with the given component. Returns `None` if it doesn't
-    /// exist.
-    pub fn column_of(&self, idx: ComponentIdx) -> Option<&Column> {
-        let idx = self
-            .columns
-            .binary_search_by_key(&idx, |c| c.component_idx)
-            .ok()?;
+    /// Returns whether removing entities from this archetype has to run
+    /// destructors. This is `false` when none of the archetype's components
+    /// have a [`DropFn`](crate::drop::DropFn), so its entities can be removed
+    /// without visiting each component.
+    pub fn needs_drop(&self) -> bool {
+        self.needs_drop
+    }
 
-        // SAFETY: `binary_search_by_key` ensures index is in bounds.
-        Some(unsafe { self.columns.get_debug_checked(idx) })
+    /// Returns whether this archetype keeps its entities in the order they
+    /// were added, which is the case when any of its components [preserves
+    /// order](crate::component::Component::PRESERVE_ORDER).
+    pub fn preserves_order(&self) -> bool {
+        self.preserve_order
     }
 
-    fn column_of_mut(&mut self, idx: ComponentIdx) -> Option<&mut Column> {
-        let idx = self
-            .columns
-            .binary_search_by_key(&idx, |c| c.component_idx)
-            .ok()?;
+    /// Removes the entity at `row` from the list of entity IDs and updates the
+    /// locations of the entities that moved to fill the gap. The columns must
+    /// be updated separately.
+    fn remove_entity_id(&mut self, row: ArchetypeRow, entities: &mut Entities) -> EntityId {
+        let row = row.0 as usize;
+
+        unsafe { assume_debug_checked(row < self.entity_ids.len()) };
+
+        if self.preserve_order {
+            let entity_id = self.entity_ids.remove(row);
+
+            for (i, &shifted) in self.entity_ids.iter().enumerate().skip(row) {
+                unsafe { entities.get_mut(shifted).unwrap_debug_checked() }.row = self.row_at(i);
+            }
+
+            entity_id
+        } else {
+            let entity_id = self.entity_ids.swap_remove(row);
+
+            if let Some(&swapped) = self.entity_ids.get(row) {
+                unsafe { entities.get_mut(swapped).unwrap_debug_checked() }.row = self.row_at(row);
+            }
+
+            entity_id
+        }
+    }
+
+    /// Returns a slice of [`EntityId`]s for all the entities in this archetype.
+    pub fn entity_ids(&self) -> &[EntityId] {
+        &self.entity_ids
+    }
+
+    /// Returns the rows of the entities in the given [entity group]. The range
+    /// is empty if none of the entities are in the group.
+    ///
+    /// [entity group]: crate::entity_group
+    pub fn entity_group_rows(&self, group: EntityGroupId) -> Range<u32> {
+        if self.entity_groups.is_empty() {
+            return if group == EntityGroupId::DEFAULT {
+                0..self.entity_count()
+            } else {
+                0..0
+            };
+        }
+
+        let mut start = 0;
+
+        for &(g, end) in &self.entity_groups {
+            if g == group {
+                return start..end;
+            }
+
+            start = end;
+        }
+
+        0..0
+    }
+
+    /// Returns an iterator over the [entity groups] with entities in this
+    /// archetype, along with the rows of their entities.
+    ///
+    /// [entity groups]: crate::entity_group
+    pub fn entity_groups(&self) -> impl Iterator<Item = (EntityGroupId, Range<u32>)> + '_ {
+        let all_default = (self.entity_groups.is_empty() && !self.entity_ids.is_empty())
+            .then(|| (EntityGroupId::DEFAULT, 0..self.entity_count()));
+
+        let mut start = 0;
+
+        all_default
+            .into_iter()
+            .chain(self.entity_groups.iter().map(move |&(group, end)| {
+                let rows = start..end;
+                start = end;
+                (group, rows)
+            }))
+    }
+
+    /// Returns the entity group of the entity at `row`.
+    pub(crate) fn entity_group_at(&self, row: ArchetypeRow) -> EntityGroupId {
+        let idx = self.entity_groups.partition_point(|&(_, end)| end <= row.0);
+
+        self.entity_groups
+            .get(idx)
+            .map_or(EntityGroupId::DEFAULT, |&(group, _)| group)
+    }
+
+    /// Swaps the entities at rows `a` and `b` and updates their locations.
+    ///
+    /// # Safety
+    ///
+    /// Both rows must be in bounds and the entities must be in `entities`.
+    unsafe fn swap_rows(&mut self, a: usize, b: usize, entities: &mut Entities) {
+        for col in &mut self.columns {
+            col.data.swap(a, b);
+        }
+
+        self.entity_ids.swap(a, b);
+
+        for row in [a, b] {
+            let id = *self.entity_ids.get_debug_checked(row);
+            entities.get_mut(id).unwrap_debug_checked().row = self.row_at(row);
+        }
+    }
+
+    /// Takes the entity at `row` out of its entity group by moving it to the
+    /// last row, after the rows of every group. Returns the entity's new row,
+    /// from which it must be removed.
+    ///
+    /// Entities keep their order if the archetype preserves order.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be in bounds and the entities must be in `entities`.
+    unsafe fn detach_row(&mut self, row: ArchetypeRow, entities: &mut Entities) -> ArchetypeRow {
+        if self.entity_groups.is_empty() {
+            return row;
+        }
+
+        let mut row = row.0 as usize;
+        let first = self
+            .entity_groups
+            .partition_point(|&(_, end)| end as usize <= row);
+
+        if self.preserve_order {
+            for r in row..self.entity_ids.len() - 1 {
+                self.swap_rows(r, r + 1, entities);
+            }
+
+            row = self.entity_ids.len() - 1;
+        } else {
+            // Swap the entity with the last entity of its group, then with the last
+            // entity of each of the following groups.
+            for i in first..self.entity_groups.len() {
+                let last = self.entity_groups.get_debug_checked(i).1 as usize - 1;
+                self.swap_rows(row, last, entities);
+                row = last;
+            }
+        }
+
+        for (_, end) in &mut self.entity_groups[first..] {
+            *end -= 1;
+        }
+
+        let start = first
+            .checked_sub(1)
+            .map_or(0, |prev| self.entity_groups.get_debug_checked(prev).1);
+
+        if self.entity_groups.get_debug_checked(first).1 == start {
+            self.entity_groups.remove(first);
+        }
+
+        if let [(EntityGroupId::DEFAULT, _)] = *self.entity_groups {
+            self.entity_groups.clear();
+        }
+
+        self.row_at(row)
+    }
+
+    /// Adds the entity at `row` to `group`. `row` must be the first row after
+    /// the rows of every group, which is where entities are added. Returns the
+    /// entity's new row.
+    ///
+    /// Entities keep their order if the archetype preserves order.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be in bounds and the entities must be in `entities`.
+    unsafe fn attach_row(
+        &mut self,
+        row: usize,
+        group: EntityGroupId,
+        entities: &mut Entities,
+    ) -> ArchetypeRow {
+        if self.entity_groups.is_empty() {
+            if group == EntityGroupId::DEFAULT {
+                return self.row_at(row);
+            }
+
+            if row > 0 {
+                self.entity_groups
+                    .push((EntityGroupId::DEFAULT, self.row_at(row).0));
+            }
+        }
+
+        debug_assert_eq!(
+            self.entity_groups
+                .last()
+                .map_or(0, |&(_, end)| end as usize),
+            row
+        );
+
+        let end = self.row_at(row).0 + 1;
+
+        let Some(idx) = self.entity_groups.iter().position(|&(g, _)| g == group) else {
+            self.entity_groups.push((group, end));
+            return self.row_at(row);
+        };
+
+        let mut row = row;
+
+        if self.preserve_order {
+            let target = self.entity_groups.get_debug_checked(idx).1 as usize;
+
+            for r in (target..row).rev() {
+                self.swap_rows(r, r + 1, entities);
+            }
+
+            row = target;
+        } else {
+            // Swap the entity with the first entity of each of the following groups,
+            // starting from the last group.
+            for i in (idx + 1..self.entity_groups.len()).rev() {
+                let start = self.entity_groups.get_debug_checked(i - 1).1 as usize;
+                self.swap_rows(start, row, entities);
+                row = start;
+            }
+        }
+
+        for (_, end) in &mut self.entity_groups[idx..] {
+            *end += 1;
+        }
+
+        self.row_at(row)
+    }
+
+    /// Returns the memory used by this archetype's allocations.
+    pub(crate) fn memory_usage(&self) -> ArchetypeMemory {
+        let edge_size = mem::size_of::<(ComponentIdx, ArchetypeIdx)>();
+        let multi_edge_size = mem::size_of::<(u64, MultiEdge)>();
+
+        let edges = (self.insert_components.len() + self.remove_components.len()) * edge_size
+            + self
+                .insert_many
+                .values()
+                .map(|edge| multi_edge_size + mem::size_of_val(&*edge.components))
+                .sum::<usize>();
+
+        let listeners = self.event_listeners.heap_size()
+            + self
+                .event_listeners
+                .values()
+                .map(SystemList::heap_size)
+                .sum::<usize>()
+            + self.refresh_listeners.len() * mem::size_of::<SystemInfoPtr>();
+
+        let entity_groups = self.entity_groups.capacity() * mem::size_of::<(EntityGroupId, u32)>();
+
+        ArchetypeMemory {
+            index: self.index,
+            columns_bytes: self.columns.iter().map(Column::heap_size).sum(),
+            entity_ids_bytes: self.entity_ids.capacity() * mem::size_of::<EntityId>(),
+            metadata_bytes: mem::size_of_val(&*self.component_set)
+                + mem::size_of_val(&*self.columns)
+                + edges
+                + listeners
+                + entity_groups,
+        }
+    }
+
+    /// Returns the sorted set of components in this archetype.
+    pub fn component_set(&self) -> &[ComponentIdx] {
+        &self.component_set
+    }
+
+    /// Returns a slice of columns sorted by [`ComponentIdx`].
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Returns the current version of the column buffers.
+    pub(crate) fn column_version(&self) -> ColumnVersion {
+        self.version
+    }
+
+    /// Returns whether this archetype has the component at `idx`.
+    #[inline]
+    pub fn contains_component(&self, idx: ComponentIdx) -> bool {
+        self.component_bits.contains(idx)
+    }
+
+    /// Returns whether this archetype satisfies `expr`, where a variable is
+    /// `true` if the archetype has that component.
+    #[inline]
+    pub(crate) fn matches(&self, expr: &BoolExpr<ComponentIdx>) -> bool {
+        expr.eval_set(&self.component_bits)
+    }
+
+    /// Finds the column with the given component. Returns `None` if it doesn't
+    /// exist.
+    pub fn column_of(&self, idx: ComponentIdx) -> Option<&Column> {
+        if !self.contains_component(idx) {
+            return None;
+        }
+
+        let idx = self
+            .columns
+            .binary_search_by_key(&idx, |c| c.component_idx)
+            .ok()?;
+
+        // SAFETY: `binary_search_by_key` ensures index is in bounds.
+        Some(unsafe { self.columns.get_debug_checked(idx) })
+    }
+
+    fn column_of_mut(&mut self, idx: ComponentIdx) -> Option<&mut Column> {
+        if !self.contains_component(idx) {
+            return None;
+        }
+
+        let idx = self
+            .columns
+            .binary_search_by_key(&idx, |c| c.component_idx)
+            .ok()?;
 
         // SAFETY: `binary_search_by_key` ensures index is in bounds.
         Some(unsafe { self.columns.get_debug_checked_mut(idx) })
     }
 
-    /// Would the columns of this archetype reallocate if an entity were added
-    /// to it?
-    fn push_would_reallocate(&self) -> bool {
-        // All columns should have the same capacity and length, so we only need to look
-        // at one of them. The `Vec` holding the Entity IDs might have a different
-        // reallocation strategy, so check that too.
+    /// Pins the archetype until the returned guard is dropped.
+    pub(crate) fn pin(&mut self) -> ArchetypePin {
+        let pins = self.pins.get_or_insert_with(Default::default).clone();
+        pins.fetch_add(1, atomic::Ordering::Acquire);
+
+        ArchetypePin {
+            index: self.index,
+            len: self.entity_ids.len(),
+            columns: self
+                .columns
+                .iter()
+                .map(|col| {
+                    let data = (!col.is_chunked()).then(|| col.data.as_ptr());
+                    (col.component_idx, data)
+                })
+                .collect(),
+            pins,
+        }
+    }
+
+    /// Panics if an [`ArchetypePin`] for this archetype exists. Called before
+    /// anything which adds or removes entities or moves the columns.
+    #[inline]
+    #[track_caller]
+    fn check_unpinned(&self) {
+        #[cold]
+        #[track_caller]
+        fn pinned(index: ArchetypeIdx) -> ! {
+            panic!(
+                "attempted to change the structure of archetype {index:?} while it is pinned. \
+                 Entities can't be added to or removed from a pinned archetype until its \
+                 `ArchetypePin` is dropped"
+            )
+        }
+
+        if let Some(pins) = &self.pins {
+            if pins.load(atomic::Ordering::Acquire) > 0 {
+                pinned(self.index);
+            }
+        }
+    }
+
+    /// Returns the number of entities this archetype has room for before its
+    /// columns are reallocated.
+    pub fn capacity(&self) -> usize {
+        // Zero-sized columns never run out of room.
         self.columns
-            .first()
-            .map_or(false, |col| col.data.len() == col.data.capacity())
-            || self.entity_ids.capacity() == self.entity_ids.len()
+            .iter()
+            .map(|col| col.data.capacity())
+            .fold(self.entity_ids.capacity(), usize::min)
+    }
+
+    /// Makes room for at least `additional` more entities, growing the
+    /// capacity according to `growth` if it runs out. Every column is grown
+    /// together so that they share the same capacity. Returns whether the
+    /// columns were reallocated.
+    fn reserve(&mut self, additional: usize, growth: ArchetypeGrowth) -> bool {
+        self.check_unpinned();
+
+        let len = self.entity_ids.len();
+        let capacity = self.capacity();
+
+        if capacity - len >= additional {
+            return false;
+        }
+
+        let required = len.checked_add(additional).expect("capacity overflow");
+        let new_capacity = growth.grow(capacity, required);
+
+        self.entity_ids.reserve_exact(new_capacity - len);
+
+        for col in &mut self.columns {
+            col.data.reserve_exact(new_capacity - col.data.len());
+        }
+
+        self.version.reallocs += 1;
+
+        true
+    }
+}
+
+/// A guard which keeps the entities and columns of an [`Archetype`] in place,
+/// so that pointers to its component data can be held outside of the world.
+/// Returned by [`World::pin_archetype`].
+///
+/// While the guard exists, anything which would add entities to the
+/// archetype, remove entities from it, or reallocate its columns panics.
+/// Component values can still be changed in place, including by replacing a
+/// component the entity already has.
+///
+/// An archetype can be pinned more than once. It stays pinned until every
+/// guard is dropped. The pointers returned by the guard dangle once the
+/// world is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Position([f32; 3]);
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(e, Position([1.0, 2.0, 3.0]));
+///
+/// let arch = world.entities().get(e).unwrap().archetype;
+/// let position = world.add_component::<Position>().index();
+///
+/// let pin = world.pin_archetype(arch).unwrap();
+/// let (ptr, len) = pin.column_ptr(position).unwrap();
+///
+/// // The column stays put until `pin` is dropped, so `ptr` could be handed
+/// // to foreign code here.
+/// let positions = unsafe { std::slice::from_raw_parts(ptr.cast::<Position>().as_ptr(), len) };
+/// assert_eq!(positions[0].0, [1.0, 2.0, 3.0]);
+///
+/// drop(pin);
+/// world.despawn(e);
+/// ```
+///
+/// [`World::pin_archetype`]: crate::world::World::pin_archetype
+#[derive(Debug)]
+pub struct ArchetypePin {
+    index: ArchetypeIdx,
+    len: usize,
+    /// The data of each column, or `None` for chunked columns.
+    columns: Box<[(ComponentIdx, Option<NonNull<u8>>)]>,
+    pins: Arc<AtomicU32>,
+}
+
+impl ArchetypePin {
+    /// Returns the index of the pinned archetype.
+    pub fn index(&self) -> ArchetypeIdx {
+        self.index
+    }
+
+    /// Returns the number of entities in the pinned archetype.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the pinned archetype has no entities.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a pointer to the data of the column for `component` along with
+    /// the number of components in it, or `None` if the archetype doesn't
+    /// have the component or its column is [chunked].
+    ///
+    /// The pointer is valid for reads and writes of that many components for
+    /// as long as the guard exists, provided that nothing else accesses the
+    /// column at the same time.
+    ///
+    /// [chunked]: Column::is_chunked
+    pub fn column_ptr(&self, component: ComponentIdx) -> Option<(NonNull<u8>, usize)> {
+        let idx = self
+            .columns
+            .binary_search_by_key(&component, |&(idx, _)| idx)
+            .ok()?;
+
+        Some((self.columns[idx].1?, self.len))
+    }
+}
+
+impl Drop for ArchetypePin {
+    fn drop(&mut self) {
+        self.pins.fetch_sub(1, atomic::Ordering::Release);
     }
 }
 
@@ -686,12 +2307,106 @@ pub struct Column {
     data: BlobVec,
     /// Type of data in this column.
     component_idx: ComponentIdx,
+    /// When the components in this column were last borrowed mutably.
+    #[cfg(feature = "diff")]
+    changes: ColumnChanges,
 }
 
 impl Column {
     /// Returns a pointer to the beginning of the buffer holding the component
     /// data, or a dangling pointer if the the buffer is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the column is [chunked](Self::is_chunked). Use
+    /// [`chunks`](Self::chunks) instead.
+    #[track_caller]
     pub fn data(&self) -> NonNull<u8> {
+        assert!(
+            !self.data.is_chunked(),
+            "the data of a chunked column is not contiguous"
+        );
+
+        self.data.as_ptr()
+    }
+
+    /// Returns pointers to the buffers holding the component data.
+    ///
+    /// Chunked columns have a buffer for every [`CHUNK_LEN`] components, in
+    /// order. Chunks are allocated as the column grows and are never moved, so
+    /// the last chunk may be partially filled. Other columns have a single
+    /// buffer holding every component.
+    pub fn chunks(&self) -> &[NonNull<u8>] {
+        self.data.chunks()
+    }
+
+    /// Returns `true` if the column is stored in chunks. See
+    /// [`Component::IS_CHUNKED`].
+    ///
+    /// Zero-sized components are never chunked, since they don't need storage.
+    ///
+    /// [`Component::IS_CHUNKED`]: crate::component::Component::IS_CHUNKED
+    pub fn is_chunked(&self) -> bool {
+        self.data.is_chunked()
+    }
+
+    /// Returns a pointer to the component in `row`.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be in bounds.
+    pub(crate) unsafe fn row_ptr(&self, row: usize) -> NonNull<u8> {
+        NonNull::new_unchecked(self.data.slot(row))
+    }
+
+    /// Drops and removes the component in `row`, shifting the following
+    /// components down if `preserve_order` is set and swap removing
+    /// otherwise.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be in bounds.
+    unsafe fn remove_row(&mut self, row: ArchetypeRow, preserve_order: bool) {
+        if preserve_order {
+            self.data.remove(row.0 as usize);
+        } else {
+            self.data.swap_remove(row.0 as usize);
+        }
+    }
+
+    /// Copies the first `len` components of the column to `dst`, with one
+    /// copy per chunk.
+    ///
+    /// # Safety
+    ///
+    /// - `len` must not exceed the length of the column.
+    /// - `dst` must be valid for writes of `len` components.
+    pub(crate) unsafe fn copy_to(&self, len: usize, dst: *mut u8) {
+        let size = self.layout().size();
+
+        if size == 0 {
+            return;
+        }
+
+        let per_chunk = if self.is_chunked() { CHUNK_LEN } else { len };
+
+        let mut copied = 0;
+
+        for &chunk in self.chunks() {
+            if copied == len {
+                break;
+            }
+
+            let n = per_chunk.min(len - copied);
+            ptr::copy_nonoverlapping(chunk.as_ptr(), dst.add(copied * size), n * size);
+            copied += n;
+        }
+    }
+
+    /// Returns the pointer that [`row_ptr`](Self::row_ptr) indexes from. This
+    /// is the start of the data, or the list of chunks if the column is
+    /// chunked. It's invalidated whenever the archetype is refreshed.
+    pub(crate) fn base_ptr(&self) -> NonNull<u8> {
         self.data.as_ptr()
     }
 
@@ -699,25 +2414,52 @@ impl Column {
     pub fn component_index(&self) -> ComponentIdx {
         self.component_idx
     }
+
+    /// Returns the number of bytes allocated for this column's data.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.data.heap_size()
+    }
+
+    /// Returns the layout of a single component in this column.
+    pub fn layout(&self) -> Layout {
+        self.data.elem_layout()
+    }
+
+    /// Returns when the components in this column were last borrowed
+    /// mutably.
+    #[cfg(feature = "diff")]
+    pub(crate) fn changes(&self) -> &ColumnChanges {
+        &self.changes
+    }
 }
 
-// SAFETY: Components are guaranteed `Send` and `Sync`.
+// SAFETY: Components are guaranteed `Send` and `Sync`. Values which are not
+// (see `Unsend`) check the current thread themselves, and worlds containing
+// them refuse to be used from other threads.
 unsafe impl Send for Column {}
 unsafe impl Sync for Column {}
 
 #[cfg(test)]
 mod tests {
+    use alloc::sync::Arc;
+    use core::cell::Cell;
+    use core::ptr;
+    use std::sync::Mutex;
+
+    use super::{ArchetypeLimitReached, CHUNK_LEN};
     use crate::prelude::*;
 
+    std::thread_local! {
+        /// Overrides the archetype limit in tests on this thread.
+        pub(super) static MAX_ARCHETYPES: Cell<Option<usize>> = const { Cell::new(None) };
+        /// Overrides the number of rows in each archetype in tests on this
+        /// thread.
+        pub(super) static MAX_ROWS: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+
     #[derive(Component)]
     struct C(String);
 
-    #[derive(Event)]
-    struct E1;
-
-    #[derive(Event)]
-    struct E2;
-
     #[test]
     fn insert_overwrites() {
         let mut world = World::new();
@@ -732,4 +2474,937 @@ mod tests {
 
         assert_eq!(world.get_component::<C>(e).unwrap().0, "goodbye");
     }
+
+    #[test]
+    fn over_aligned_components_move_between_archetypes() {
+        macro_rules! aligned {
+            ($($name:ident = $align:literal),*) => {
+                $(
+                    #[derive(Component, Clone, Copy, PartialEq, Debug)]
+                    #[repr(align($align))]
+                    struct $name([u8; 3]);
+                )*
+            };
+        }
+
+        aligned!(A16 = 16, A32 = 32, A64 = 64, A128 = 128, A4096 = 4096);
+
+        #[derive(Component)]
+        #[repr(align(64))]
+        struct Zst;
+
+        #[track_caller]
+        fn check<T: Component>(world: &World, e: EntityId) -> &T {
+            let c = world.get_component::<T>(e).unwrap();
+            assert!((c as *const T as usize).is_multiple_of(core::mem::align_of::<T>()));
+            c
+        }
+
+        let mut world = World::new();
+
+        let entities: Vec<_> = (0..20_u8)
+            .map(|i| {
+                let e = world.spawn();
+                // Vary the insertion order so entities take different paths through
+                // the archetype graph.
+                if i.is_multiple_of(2) {
+                    world.insert(e, A16([i; 3]));
+                    world.insert(e, A4096([i; 3]));
+                    world.insert(e, Zst);
+                    world.insert(e, A64([i; 3]));
+                } else {
+                    world.insert(e, A64([i; 3]));
+                    world.insert(e, A32([i; 3]));
+                    world.insert(e, A128([i; 3]));
+                    world.insert(e, A16([i; 3]));
+                }
+                e
+            })
+            .collect();
+
+        for (i, &e) in entities.iter().enumerate() {
+            let i = i as u8;
+
+            if i.is_multiple_of(3) {
+                world.remove::<A16>(e);
+            }
+            world.insert(e, A128([i; 3]));
+            world.insert(e, A32([i; 3]));
+
+            assert_eq!(*check::<A64>(&world, e), A64([i; 3]));
+            assert_eq!(*check::<A128>(&world, e), A128([i; 3]));
+            assert_eq!(*check::<A32>(&world, e), A32([i; 3]));
+
+            if i.is_multiple_of(3) {
+                assert!(world.get_component::<A16>(e).is_none());
+            } else {
+                assert_eq!(*check::<A16>(&world, e), A16([i; 3]));
+            }
+
+            if i.is_multiple_of(2) {
+                assert_eq!(*check::<A4096>(&world, e), A4096([i; 3]));
+                check::<Zst>(&world, e);
+            }
+        }
+
+        // Despawning swaps entities from the end into the removed rows.
+        for &e in entities.iter().step_by(4) {
+            world.despawn(e);
+        }
+
+        for (i, &e) in entities.iter().enumerate() {
+            if !i.is_multiple_of(4) {
+                assert_eq!(*check::<A64>(&world, e), A64([i as u8; 3]));
+            }
+        }
+    }
+
+    #[test]
+    fn register_system_skips_empty_archetypes() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, B);
+        world.insert(e1, A);
+
+        // Leave `{A}` empty.
+        let e2 = world.spawn();
+        world.insert(e2, A);
+        world.remove::<A>(e2);
+
+        let e3 = world.spawn();
+        world.insert(e3, A);
+        world.insert(e3, B);
+
+        world.add_system(|_: Receiver<E>, f: Fetcher<&A>| {
+            assert_eq!(f.iter().len(), 2);
+            assert_eq!(f.iter().count(), 2);
+        });
+
+        world.send(E);
+    }
+
+    /// Adds systems for `Hit` with and without `Not<&Flag>` which log their
+    /// names when they run.
+    fn add_flag_listeners(world: &mut World) -> Arc<Mutex<Vec<&'static str>>> {
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |_: Receiver<Hit, Not<&Flag>>| l.lock().unwrap().push("not"));
+
+        let l = log.clone();
+        world.add_system(move |_: Receiver<Hit, &Flag>| l.lock().unwrap().push("with"));
+
+        let l = log.clone();
+        world.add_system(move |_: Receiver<Hit, (Not<&Flag>, &Other)>| {
+            l.lock().unwrap().push("not_with_other")
+        });
+
+        log
+    }
+
+    #[derive(Component)]
+    struct Flag;
+
+    #[derive(Component)]
+    struct Other;
+
+    #[derive(Event)]
+    struct Hit(#[event(target)] EntityId);
+
+    fn hit(world: &mut World, log: &Mutex<Vec<&'static str>>, e: EntityId) -> Vec<&'static str> {
+        world.send(Hit(e));
+        core::mem::take(&mut *log.lock().unwrap())
+    }
+
+    #[test]
+    fn not_listeners_follow_moves() {
+        let mut world = World::new();
+
+        // The systems exist before any archetype with `Flag`.
+        let log = add_flag_listeners(&mut world);
+
+        let e = world.spawn();
+        assert_eq!(hit(&mut world, &log, e), ["not"]);
+
+        world.insert(e, Flag);
+        assert_eq!(hit(&mut world, &log, e), ["with"]);
+
+        world.remove::<Flag>(e);
+        assert_eq!(hit(&mut world, &log, e), ["not"]);
+
+        world.insert(e, Other);
+        assert_eq!(hit(&mut world, &log, e), ["not", "not_with_other"]);
+
+        world.insert(e, Flag);
+        assert_eq!(hit(&mut world, &log, e), ["with"]);
+
+        world.remove::<Other>(e);
+        assert_eq!(hit(&mut world, &log, e), ["with"]);
+
+        world.remove::<Flag>(e);
+        assert_eq!(hit(&mut world, &log, e), ["not"]);
+    }
+
+    #[test]
+    fn not_listeners_added_after_archetypes() {
+        let mut world = World::new();
+
+        let plain = world.spawn();
+
+        let flagged = world.spawn();
+        world.insert(flagged, Flag);
+
+        let other = world.spawn();
+        world.insert(other, Other);
+
+        let both = world.spawn();
+        world.insert(both, Other);
+        world.insert(both, Flag);
+
+        let log = add_flag_listeners(&mut world);
+
+        assert_eq!(hit(&mut world, &log, plain), ["not"]);
+        assert_eq!(hit(&mut world, &log, flagged), ["with"]);
+        assert_eq!(hit(&mut world, &log, other), ["not", "not_with_other"]);
+        assert_eq!(hit(&mut world, &log, both), ["with"]);
+
+        // Moving between the archetypes which existed before the systems.
+        world.remove::<Flag>(both);
+        assert_eq!(hit(&mut world, &log, both), ["not", "not_with_other"]);
+        world.insert(other, Flag);
+        assert_eq!(hit(&mut world, &log, other), ["with"]);
+    }
+
+    #[test]
+    fn not_listeners_in_reused_archetype_slots() {
+        #[derive(Component)]
+        struct Temp;
+
+        let mut world = World::new();
+
+        let log = add_flag_listeners(&mut world);
+
+        let temp = world.add_component::<Temp>();
+
+        for _ in 0..3 {
+            let e = world.spawn();
+            world.insert(e, Temp);
+            world.insert(e, Flag);
+        }
+
+        // Frees the archetypes containing `Temp` so new archetypes can take their
+        // slots.
+        world.remove_component(temp);
+
+        let e = world.spawn();
+        world.insert(e, Other);
+        assert_eq!(hit(&mut world, &log, e), ["not", "not_with_other"]);
+
+        world.insert(e, Flag);
+        assert_eq!(hit(&mut world, &log, e), ["with"]);
+
+        world.remove::<Flag>(e);
+        assert_eq!(hit(&mut world, &log, e), ["not", "not_with_other"]);
+    }
+
+    #[test]
+    fn refresh_after_column_reallocation() {
+        #[derive(Component)]
+        struct A(u32);
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let seen = Arc::new(Mutex::new(vec![]));
+
+        // Registered before any entities exist, so the fetchers must be refreshed
+        // every time a spawn or insert grows the columns they point into.
+        let seen_clone = seen.clone();
+        world.add_system(
+            move |_: Receiver<E>,
+                  empty: Fetcher<(EntityId, Not<&A>)>,
+                  with_a: Fetcher<(EntityId, &A)>| {
+                let mut seen = seen_clone.lock().unwrap();
+                seen.clear();
+                seen.extend(empty.iter().map(|(id, _)| (id, None)));
+                seen.extend(with_a.iter().map(|(id, a)| (id, Some(a.0))));
+            },
+        );
+
+        let mut expected = vec![];
+
+        for round in 0..8 {
+            for i in 0..(1 << round) {
+                let e = world.spawn();
+
+                if i % 2 == 0 {
+                    world.insert(e, A(i));
+                    expected.push((e, Some(i)));
+                } else {
+                    expected.push((e, None));
+                }
+            }
+
+            world.send(E);
+
+            let mut seen = seen.lock().unwrap();
+            seen.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(*seen, expected);
+        }
+    }
+
+    #[test]
+    fn configured_capacity() {
+        use core::alloc::Layout;
+        use core::any::{self, TypeId};
+
+        use super::{Archetype, ArchetypeGrowth, ArchetypeSettings};
+        use crate::component::{ComponentDescriptor, ComponentIdx};
+
+        #[derive(Component)]
+        struct A(#[allow(dead_code)] u64);
+
+        #[derive(Component)]
+        struct B(#[allow(dead_code)] u32);
+
+        let mut world = World::new();
+
+        world.set_archetype_settings(ArchetypeSettings {
+            initial_entity_capacity: 100,
+            growth: ArchetypeGrowth::Factor(1.5),
+        });
+
+        let a = world.add_component::<A>().index();
+        let b = unsafe {
+            world.add_component_with_descriptor(ComponentDescriptor {
+                name: any::type_name::<B>().into(),
+                type_id: Some(TypeId::of::<B>()),
+                key: None,
+                layout: Layout::new::<B>(),
+                drop: None,
+                copy_fn: None,
+                clone_fn: None,
+                default_capacity: 300,
+                is_chunked: false,
+                preserve_order: false,
+                is_immutable: false,
+                is_local: false,
+                singleton: None,
+                is_pod: false,
+                required: vec![],
+                on_insert: None,
+                on_remove: None,
+                validate: None,
+            })
+        }
+        .index();
+
+        fn arch<'a>(world: &'a World, set: &[ComponentIdx]) -> &'a Archetype {
+            world.archetypes().get_by_components(set).unwrap()
+        }
+
+        fn column_ptrs(arch: &Archetype) -> Vec<*const u8> {
+            let mut ptrs: Vec<_> = arch
+                .columns
+                .iter()
+                .map(|col| col.data.as_ptr().as_ptr().cast_const())
+                .collect();
+            ptrs.push(arch.entity_ids.as_ptr().cast());
+            ptrs
+        }
+
+        let e = world.spawn();
+        world.insert(e, A(0));
+
+        assert_eq!(arch(&world, &[a]).capacity(), 100);
+        let before = column_ptrs(arch(&world, &[a]));
+
+        // Filling the archetype up to the configured capacity never reallocates.
+        for i in 1..100 {
+            let e = world.spawn();
+            world.insert(e, A(i));
+        }
+
+        assert_eq!(column_ptrs(arch(&world, &[a])), before);
+
+        let e = world.spawn();
+        world.insert(e, A(100));
+
+        assert_eq!(arch(&world, &[a]).capacity(), 150);
+
+        world.insert(e, B(0));
+
+        // The larger capacity of `B` applies to the whole archetype.
+        let mut ab = [a, b];
+        ab.sort();
+        assert_eq!(arch(&world, &ab).capacity(), 300);
+    }
+
+    #[test]
+    fn reserve_entities() {
+        let mut world = World::new();
+
+        world.reserve_entities(500);
+
+        let entity_capacity = world.entities().capacity();
+        let empty_capacity = world.archetypes().empty().capacity();
+
+        assert!(entity_capacity >= 500);
+        assert!(empty_capacity >= 500);
+
+        for _ in 0..500 {
+            world.spawn();
+        }
+
+        assert_eq!(world.entities().capacity(), entity_capacity);
+        assert_eq!(world.archetypes().empty().capacity(), empty_capacity);
+    }
+
+    #[test]
+    fn reserve_archetype() {
+        use super::{ArchetypeGrowth, ArchetypeSettings};
+
+        #[derive(Component)]
+        struct A(u32);
+
+        #[derive(Event)]
+        struct Sum;
+
+        let mut world = World::new();
+
+        world.set_archetype_settings(ArchetypeSettings {
+            initial_entity_capacity: 0,
+            growth: ArchetypeGrowth::Chunked(10),
+        });
+
+        let a = world.add_component::<A>();
+
+        let e = world.spawn();
+        world.insert(e, A(1));
+
+        let arch = world.entities().get(e).unwrap().archetype;
+        assert_eq!(world.archetypes().get(arch).unwrap().capacity(), 10);
+
+        let total = Arc::new(Mutex::new(0));
+        let total_clone = total.clone();
+        world.add_system(move |_: Receiver<Sum>, f: Fetcher<&A>| {
+            *total_clone.lock().unwrap() = f.iter().map(|a| a.0).sum::<u32>();
+        });
+
+        // Systems see the moved columns after the reservation reallocates them.
+        assert_eq!(world.reserve_archetype(&[a], 1000), arch);
+        world.send(Sum);
+        assert_eq!(*total.lock().unwrap(), 1);
+
+        let reallocs = world.archetypes().get(arch).unwrap().version.reallocs;
+        assert_eq!(world.archetypes().get(arch).unwrap().capacity(), 1000);
+
+        for i in 1..1000 {
+            let e = world.spawn();
+            world.insert(e, A(i));
+        }
+
+        let arch = world.archetypes().get(arch).unwrap();
+        assert_eq!(arch.version.reallocs, reallocs);
+        assert_eq!(arch.capacity(), 1000);
+    }
+
+    #[test]
+    fn chunked_column_pointer_stability() {
+        #[derive(Component, PartialEq, Debug)]
+        #[component(chunked)]
+        struct Position(u64);
+
+        #[derive(Component)]
+        struct Tag;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let sum = Arc::new(Mutex::new(0));
+
+        let sum_clone = sum.clone();
+        world.add_system(move |_: Receiver<E>, f: Fetcher<&Position>| {
+            *sum_clone.lock().unwrap() = f.iter().map(|p| p.0).sum::<u64>();
+        });
+
+        const COUNT: u64 = 1_000_000;
+
+        let mut sampled = vec![];
+
+        for i in 0..COUNT {
+            let e = world.spawn();
+            world.insert(e, Position(i));
+
+            if i % 100_000 == 0 {
+                let ptr: *const Position = world.get_component::<Position>(e).unwrap();
+                sampled.push((e, ptr));
+            }
+        }
+
+        for &(e, ptr) in &sampled {
+            assert!(ptr::eq(world.get_component::<Position>(e).unwrap(), ptr));
+        }
+
+        let loc = world.entity_location(sampled[0].0).unwrap();
+        let col = &world.archetypes().get(loc.archetype).unwrap().columns()[0];
+
+        assert!(col.is_chunked());
+        assert!(col.chunks().len() >= (COUNT as usize).div_ceil(CHUNK_LEN));
+
+        world.send(E);
+        assert_eq!(*sum.lock().unwrap(), COUNT * (COUNT - 1) / 2);
+
+        // Moving entities in and out of the archetype keeps the chunks consistent.
+        world.insert(sampled[1].0, Tag);
+        world.despawn(sampled[2].0);
+        world.remove::<Tag>(sampled[1].0);
+
+        world.send(E);
+        assert_eq!(*sum.lock().unwrap(), COUNT * (COUNT - 1) / 2 - 200_000);
+        assert_eq!(
+            world.get_component::<Position>(sampled[1].0),
+            Some(&Position(100_000))
+        );
+        assert!(ptr::eq(
+            world.get_component::<Position>(sampled[0].0).unwrap(),
+            sampled[0].1
+        ));
+
+        world.check_invariants();
+    }
+
+    #[test]
+    fn despawn_pod_and_droppy_entities() {
+        #[derive(Component, PartialEq, Debug)]
+        struct Pos(u32);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Vel(u64);
+
+        #[derive(Component)]
+        struct Handle(#[allow(dead_code)] Arc<()>);
+
+        let mut world = World::new();
+
+        let handle = Arc::new(());
+
+        let pod: Vec<_> = (0..3)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, Pos(i));
+                world.insert(e, Vel(u64::from(i)));
+                e
+            })
+            .collect();
+
+        let mixed: Vec<_> = (0..3)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, Pos(i));
+                world.insert(e, Handle(handle.clone()));
+                e
+            })
+            .collect();
+
+        let arch_of = |world: &World, e| {
+            let loc = world.entity_location(e).unwrap();
+            world.archetypes().get(loc.archetype).unwrap().needs_drop()
+        };
+
+        assert!(!world.archetypes().empty().needs_drop());
+        assert!(!arch_of(&world, pod[0]));
+        assert!(arch_of(&world, mixed[0]));
+
+        world.despawn(pod[0]);
+        world.despawn(mixed[0]);
+
+        assert_eq!(Arc::strong_count(&handle), 3);
+        assert_eq!(world.get::<&Pos>(pod[2]), Some(&Pos(2)));
+        assert_eq!(world.get::<&Vel>(pod[2]), Some(&Vel(2)));
+        assert_eq!(world.get::<&Pos>(mixed[2]), Some(&Pos(2)));
+
+        world.check_invariants();
+
+        for &e in pod[1..].iter().chain(&mixed[1..]) {
+            world.despawn(e);
+        }
+
+        assert_eq!(Arc::strong_count(&handle), 1);
+
+        world.check_invariants();
+    }
+
+    #[test]
+    fn preserve_order() {
+        #[derive(Component, PartialEq, Debug)]
+        #[component(preserve_order)]
+        struct Ordered(u32);
+
+        #[derive(Event)]
+        struct Collect;
+
+        let mut world = World::new();
+
+        let pod: Vec<_> = (0..6)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, Ordered(i));
+                e
+            })
+            .collect();
+
+        let droppy: Vec<_> = (0..6)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, C(i.to_string()));
+                world.insert(e, Ordered(i));
+                e
+            })
+            .collect();
+
+        let pod_arch = world.entity_location(pod[0]).unwrap().archetype;
+        let droppy_arch = world.entity_location(droppy[0]).unwrap().archetype;
+
+        assert!(!world.archetypes().empty().preserves_order());
+        assert!(world.archetypes().get(pod_arch).unwrap().preserves_order());
+
+        // Interleave despawns with moves out of and into the archetypes.
+        world.despawn(pod[1]);
+        world.despawn(droppy[0]);
+        world.insert(pod[3], C("moved".into()));
+        world.remove::<C>(droppy[2]);
+        world.despawn(pod[4]);
+        world.despawn(droppy[4]);
+
+        world.check_invariants();
+
+        let ids = |arch| world.archetypes().get(arch).unwrap().entity_ids().to_vec();
+
+        assert_eq!(ids(pod_arch), [pod[0], pod[2], pod[5], droppy[2]]);
+        assert_eq!(ids(droppy_arch), [droppy[1], droppy[3], droppy[5], pod[3]]);
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(
+            move |_: Receiver<Collect>, f: Fetcher<(&Ordered, Option<&C>)>| {
+                for (o, c) in f {
+                    l.lock().unwrap().push((o.0, c.map(|c| c.0.clone())));
+                }
+            },
+        );
+
+        world.send(Collect);
+
+        let mut expected = vec![(0, None), (2, None), (5, None), (2, None)];
+        expected.extend([
+            (1, Some("1".into())),
+            (3, Some("3".into())),
+            (5, Some("5".into())),
+            (3, Some("moved".into())),
+        ]);
+
+        assert_eq!(*log.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn archetype_limit_reached() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        #[allow(dead_code)]
+        struct C(Arc<()>);
+
+        // Room for the empty archetype, `{A}`, and `{A, B}`.
+        MAX_ARCHETYPES.set(Some(3));
+
+        let mut world = World::new();
+
+        let reached = Arc::new(Mutex::new(vec![]));
+
+        let reached_clone = reached.clone();
+        world.add_system(move |r: Receiver<ArchetypeLimitReached>| {
+            reached_clone.lock().unwrap().push(r.event.clone());
+        });
+
+        let a = world.add_component::<A>();
+        let b = world.add_component::<B>();
+        let c = world.add_component::<C>();
+
+        let e = world.spawn();
+        world.insert(e, A);
+        world.insert(e, B);
+        assert_eq!(world.archetypes().len(), 3);
+
+        // `{B}` doesn't fit, so `A` stays on the entity.
+        world.remove::<A>(e);
+        assert!(world.get_component::<A>(e).is_some());
+
+        // `{A, B, C}` doesn't fit, so the component is dropped.
+        let value = Arc::new(());
+        world.insert(e, C(value.clone()));
+        assert!(world.get_component::<C>(e).is_none());
+        assert_eq!(Arc::strong_count(&value), 1);
+
+        // Moving to an existing archetype still works.
+        world.remove::<B>(e);
+        assert!(world.get_component::<B>(e).is_none());
+
+        assert_eq!(
+            *reached.lock().unwrap(),
+            [
+                ArchetypeLimitReached {
+                    count: 3,
+                    components: [b].into(),
+                },
+                ArchetypeLimitReached {
+                    count: 3,
+                    components: [a, b, c].into(),
+                },
+            ]
+        );
+
+        MAX_ARCHETYPES.set(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "with components [] can't hold more than 2 entities")]
+    fn spawn_past_row_limit() {
+        MAX_ROWS.set(Some(2));
+
+        let mut world = World::new();
+
+        world.spawn();
+        world.spawn();
+        world.spawn();
+    }
+
+    #[test]
+    #[should_panic(expected = "with components [ComponentIdx(0)] can't hold more than 2 entities")]
+    fn move_past_row_limit() {
+        #[derive(Component)]
+        struct A;
+
+        MAX_ROWS.set(Some(2));
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        world.insert(e1, A);
+        world.insert(e2, A);
+
+        let e3 = world.spawn();
+        world.insert(e3, A);
+    }
+
+    #[test]
+    fn few_listeners_stay_inline() {
+        use alloc::borrow::Cow;
+        use core::alloc::Layout;
+
+        use crate::bool_expr::BoolExpr;
+        use crate::event::{EventDescriptor, EventIdx, EventKind};
+
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId);
+
+        #[derive(Event)]
+        struct Heal(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct Health;
+
+        let mut world = World::new();
+
+        // Give the listened-to events large indices.
+        for _ in 0..300 {
+            unsafe {
+                world.add_event_with_descriptor(EventDescriptor {
+                    name: Cow::Borrowed("unused"),
+                    type_id: None,
+                    key: None,
+                    is_targeted: true,
+                    kind: EventKind::Normal,
+                    layout: Layout::new::<EntityId>(),
+                    drop: None,
+                    clone_fn: None,
+                    default_fn: None,
+                    is_immutable: false,
+                    is_deferred: false,
+                    bubbles: None,
+                    targets: None,
+                    target_filter: BoolExpr::new(true),
+                })
+            };
+        }
+
+        world.add_system(|_: Receiver<Hit, &Health>| {});
+        world.add_system(|_: Receiver<Heal, &Health>| {});
+
+        let e = world.spawn();
+        world.insert(e, Health);
+
+        let ids = [world.add_event::<Hit>(), world.add_event::<Heal>()];
+
+        let loc = world.entity_location(e).unwrap();
+        let arch = world.archetypes().get(loc.archetype).unwrap();
+
+        for id in ids {
+            let EventIdx::Targeted(idx) = id.index() else {
+                unreachable!()
+            };
+
+            assert_eq!(arch.system_list_for(idx).unwrap().systems().len(), 1);
+        }
+
+        assert_eq!(arch.event_listeners.heap_size(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "while it is pinned")]
+    fn despawn_pinned_entity() {
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, C);
+
+        let loc = world.entity_location(e).unwrap();
+        let _pin = world.pin_archetype(loc.archetype).unwrap();
+
+        world.despawn(e);
+    }
+
+    #[test]
+    fn pin_multiple_archetypes() {
+        #[derive(Component)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct B(u64);
+
+        let mut world = World::new();
+
+        let a = world.add_component::<A>().index();
+        let b = world.add_component::<B>().index();
+
+        let e1 = world.spawn();
+        world.insert(e1, A(1));
+
+        let e2 = world.spawn();
+        world.insert(e2, A(2));
+        world.insert(e2, B(3));
+
+        let arch1 = world.entity_location(e1).unwrap().archetype;
+        let arch2 = world.entity_location(e2).unwrap().archetype;
+
+        let pin1 = world.pin_archetype(arch1).unwrap();
+        let pin2 = world.pin_archetype(arch2).unwrap();
+        let pin3 = world.pin_archetype(arch2).unwrap();
+
+        let (ptr, len) = pin1.column_ptr(a).unwrap();
+        assert_eq!(len, 1);
+        assert_eq!(unsafe { ptr.cast::<A>().as_ref() }.0, 1);
+        assert!(pin1.column_ptr(b).is_none());
+
+        let (ptr, len) = pin2.column_ptr(b).unwrap();
+        assert_eq!(len, 1);
+        assert_eq!(unsafe { ptr.cast::<B>().as_ref() }.0, 3);
+
+        // Replacing a component doesn't change the structure of the archetype.
+        world.insert(e1, A(4));
+        let (ptr, _) = pin1.column_ptr(a).unwrap();
+        assert_eq!(unsafe { ptr.cast::<A>().as_ref() }.0, 4);
+
+        drop(pin1);
+        drop(pin2);
+        world.despawn(e1);
+
+        drop(pin3);
+        world.despawn(e2);
+
+        assert!(world.entities().get(e2).is_none());
+    }
+
+    #[test]
+    fn contains_many_components() {
+        use core::alloc::Layout;
+
+        use crate::bool_expr::BoolExpr;
+        use crate::component::ComponentDescriptor;
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        // Push the indices of `A` and `B` past the first block of bits.
+        for i in 0..100 {
+            unsafe {
+                world.add_component_with_descriptor(ComponentDescriptor {
+                    name: format!("filler {i}").into(),
+                    type_id: None,
+                    key: None,
+                    layout: Layout::new::<()>(),
+                    drop: None,
+                    copy_fn: None,
+                    clone_fn: None,
+                    default_capacity: 0,
+                    is_chunked: false,
+                    preserve_order: false,
+                    is_immutable: false,
+                    is_local: false,
+                    singleton: None,
+                    is_pod: false,
+                    required: vec![],
+                    on_insert: None,
+                    on_remove: None,
+                    validate: None,
+                });
+            }
+        }
+
+        let a = world.add_component::<A>().index();
+        let b = world.add_component::<B>().index();
+
+        let e = world.spawn();
+        world.insert(e, A);
+
+        let arch = world
+            .archetypes()
+            .get(world.entities().get(e).unwrap().archetype)
+            .unwrap();
+
+        assert!(arch.contains_component(a));
+        assert!(!arch.contains_component(b));
+        assert!(arch.column_of(b).is_none());
+        assert!(arch.matches(&BoolExpr::var(a).and(&BoolExpr::not_var(b))));
+        assert!(!arch.matches(&BoolExpr::var(b)));
+    }
 }
@@ -14,6 +14,39 @@ const _: () = assert!(
     "unsupported target"
 );
 
+/// A flag which is set while a system runs, so that structural changes made
+/// from inside a system through a smuggled `&mut World` panic instead of
+/// invalidating the column pointers the system is using.
+///
+/// The flag only exists in debug builds. In release builds, checking it does
+/// nothing.
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct StructureLock {
+    #[cfg(debug_assertions)]
+    locked: bool,
+}
+
+impl StructureLock {
+    /// Sets the flag and returns its previous value.
+    #[cfg(debug_assertions)]
+    #[inline]
+    pub(crate) fn set(&mut self, locked: bool) -> bool {
+        mem::replace(&mut self.locked, locked)
+    }
+
+    /// Panics if the flag is set.
+    #[inline]
+    #[track_caller]
+    pub(crate) fn check(self) {
+        #[cfg(debug_assertions)]
+        assert!(
+            !self.locked,
+            "attempted to change the structure of the world while a system is running. Entities \
+             and components can only be added or removed through events, such as with `Sender`"
+        );
+    }
+}
+
 /// Extension trait for checked array indexing with checks removed in release
 /// mode.
 pub(crate) trait GetDebugChecked {
@@ -202,6 +235,25 @@ impl<E: Event> AssertMutable<E> {
     );
 }
 
+pub(crate) struct AssertImmutable<T>(PhantomData<T>);
+
+impl<C: Component> AssertImmutable<C> {
+    pub(crate) const COMPONENT: () = assert!(
+        C::IS_IMMUTABLE,
+        "component must be immutable (see `Component::IS_IMMUTABLE`)."
+    );
+}
+
+pub(crate) struct AssertNotChunked<C>(PhantomData<C>);
+
+impl<C: Component> AssertNotChunked<C> {
+    pub(crate) const COMPONENT: () = assert!(
+        !C::IS_CHUNKED || mem::size_of::<C>() == 0,
+        "component is stored in chunks and can't be borrowed as a slice (see \
+         `Component::IS_CHUNKED`)."
+    );
+}
+
 pub(crate) struct AssertUntargetedEvent<E>(PhantomData<E>);
 
 impl<E: Event> AssertUntargetedEvent<E> {
@@ -6,7 +6,7 @@ use core::cmp::Ordering;
 use core::iter::FusedIterator;
 use core::marker::PhantomData;
 use core::ops::{BitOr, BitOrAssign};
-use core::{any, fmt};
+use core::{any, fmt, mem};
 
 use crate::assert::GetDebugChecked;
 use crate::sparse::SparseIndex;
@@ -44,6 +44,11 @@ impl<T> BitSet<T> {
         self.blocks.clear();
     }
 
+    /// Returns the number of bytes allocated by this set.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.blocks.capacity() * mem::size_of::<Block>()
+    }
+
     #[inline]
     #[track_caller]
     fn grow_to_block(&mut self, block_idx: usize) -> &mut Block {
@@ -71,6 +76,15 @@ impl<T> BitSet<T> {
             .all(|(a, b)| a & b == 0)
     }
 
+    /// Returns `true` if every element of `self` is also in `other`.
+    #[must_use]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.blocks.iter().enumerate().all(|(i, &a)| {
+            let b = other.blocks.get(i).copied().unwrap_or(0);
+            a & !b == 0
+        })
+    }
+
     /// Returns the number of elements in the set.
     #[must_use]
     pub fn len(&self) -> usize {
@@ -133,11 +147,11 @@ impl<T: SparseIndex> BitSet<T> {
 
         self.blocks
             .get(block)
-            .map_or(false, |&block| (block >> bit) & 1 == 1)
+            .is_some_and(|&block| (block >> bit) & 1 == 1)
     }
 
     /// Returns an iterator over the element in the set in ascending order.
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             bits: self.blocks.first().copied().unwrap_or(0),
             block_idx: 0,
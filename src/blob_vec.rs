@@ -1,13 +1,23 @@
 use alloc::alloc;
 use core::alloc::Layout;
-use core::ptr;
 use core::ptr::NonNull;
+use core::{mem, ptr, slice};
+
+use ::alloc::vec::Vec;
 
 use crate::assert::UnwrapDebugChecked;
+use crate::component::CopyFn;
 use crate::drop::DropFn;
 use crate::layout_util::pad_to_align;
 
+/// The number of elements in each chunk of a chunked [`BlobVec`].
+pub(crate) const CHUNK_LEN: usize = 4096;
+
 /// Like `Vec<T>`, but `T` is erased.
+///
+/// A chunked `BlobVec` stores its elements in separately allocated chunks of
+/// [`CHUNK_LEN`] elements, so elements never move when the vec grows. `data`
+/// then points to the list of chunks instead of the elements.
 #[derive(Debug)]
 pub(crate) struct BlobVec {
     /// Layout of a single element.
@@ -16,65 +26,103 @@ pub(crate) struct BlobVec {
     len: usize,
     /// Capacity of allocated buffer.
     cap: usize,
-    /// Pointer to beginning of allocated buffer.
+    /// Pointer to beginning of allocated buffer, or to the beginning of
+    /// `chunks` if the vec is chunked.
     data: NonNull<u8>,
+    /// The allocated chunks, if the vec is chunked.
+    chunks: Option<Vec<NonNull<u8>>>,
     /// The erased element type's drop function, if any.
     drop: DropFn,
+    /// A copy function specialized for the element type, if any.
+    copy: Option<CopyFn>,
 }
 
 impl BlobVec {
     /// # Safety
     /// - `drop` must be safe to call with elements of this `BlobVec` as
     ///   described by [`DropFn`]'s documentation.
-    pub(crate) unsafe fn new(layout: Layout, drop: DropFn) -> Self {
-        // // SAFETY: `Layout` guarantees alignment is non-zero.
-        // let data = NonNull::new(layout.align() as *mut u8).unwrap_debug_checked();
+    /// - `copy` must copy exactly one element as described by [`CopyFn`]'s
+    ///   documentation.
+    pub(crate) unsafe fn new(layout: Layout, drop: DropFn, copy: Option<CopyFn>) -> Self {
+        // Zero-sized elements never allocate, so the dangling pointer must already
+        // be aligned for the element type.
+        // SAFETY: `Layout` guarantees alignment is non-zero.
+        let data = NonNull::new(ptr::without_provenance_mut(layout.align())).unwrap_debug_checked();
 
         Self {
             elem_layout: pad_to_align(&layout),
             len: 0,
             cap: if layout.size() == 0 { usize::MAX } else { 0 },
-            data: NonNull::dangling(),
+            data,
+            chunks: None,
             drop,
+            copy,
         }
     }
 
+    /// Like [`new`](Self::new), but the elements are stored in chunks which
+    /// are never reallocated. Vecs of zero-sized elements are never chunked,
+    /// since their elements don't need storage.
+    ///
+    /// # Safety
+    /// - See [`new`](Self::new).
+    pub(crate) unsafe fn new_chunked(layout: Layout, drop: DropFn, copy: Option<CopyFn>) -> Self {
+        let mut vec = Self::new(layout, drop, copy);
+
+        if layout.size() != 0 {
+            let chunks = Vec::new();
+            vec.data = NonNull::from(chunks.as_slice()).cast();
+            vec.chunks = Some(chunks);
+        }
+
+        vec
+    }
+
+    /// Is this vec stored in chunks?
+    pub(crate) fn is_chunked(&self) -> bool {
+        self.chunks.is_some()
+    }
+
+    /// Returns a pointer to the slot at `idx`, which may be one past the last
+    /// element if there's spare capacity.
+    ///
+    /// # Safety
+    /// - `idx` must be less than the capacity.
+    #[inline]
+    pub(crate) unsafe fn slot(&self, idx: usize) -> *mut u8 {
+        slot_in(self.data, self.elem_layout.size(), self.is_chunked(), idx)
+    }
+
     pub(crate) unsafe fn push(&mut self) -> NonNull<u8> {
         self.reserve(1);
 
-        let slot = self.data.as_ptr().add(self.elem_layout.size() * self.len);
+        let slot = self.slot(self.len);
 
         self.len += 1;
 
         NonNull::new_unchecked(slot)
     }
 
-    unsafe fn swap_remove_no_drop(&mut self, idx: usize) {
+    /// Like [`swap_remove`](Self::swap_remove), but the removed element is
+    /// forgotten instead of dropped.
+    pub(crate) unsafe fn swap_remove_no_drop(&mut self, idx: usize) {
         debug_assert!(idx < self.len, "index out of bounds");
 
-        let src = self
-            .data
-            .as_ptr()
-            .add(self.elem_layout.size() * (self.len - 1));
-
-        let dst = self.data.as_ptr().add(self.elem_layout.size() * idx);
+        let src = self.slot(self.len - 1);
+        let dst = self.slot(idx);
 
         self.len -= 1;
 
         if src != dst {
-            ptr::copy_nonoverlapping(src, dst, self.elem_layout.size());
+            self.copy_elem(src, dst);
         }
     }
 
     pub(crate) unsafe fn swap_remove(&mut self, idx: usize) {
         debug_assert!(idx < self.len, "index out of bounds");
 
-        let src = self
-            .data
-            .as_ptr()
-            .add(self.elem_layout.size() * (self.len - 1));
-
-        let dst = self.data.as_ptr().add(self.elem_layout.size() * idx);
+        let src = self.slot(self.len - 1);
+        let dst = self.slot(idx);
 
         if let Some(drop) = self.drop {
             drop(NonNull::new_unchecked(dst));
@@ -83,14 +131,62 @@ impl BlobVec {
         self.len -= 1;
 
         if src != dst {
-            ptr::copy_nonoverlapping(src, dst, self.elem_layout.size());
+            self.copy_elem(src, dst);
+        }
+    }
+
+    /// Like [`remove`](Self::remove), but the removed element is forgotten
+    /// instead of dropped.
+    pub(crate) unsafe fn remove_no_drop(&mut self, idx: usize) {
+        debug_assert!(idx < self.len, "index out of bounds");
+
+        let size = self.elem_layout.size();
+
+        if self.is_chunked() {
+            for i in idx + 1..self.len {
+                ptr::copy_nonoverlapping(self.slot(i), self.slot(i - 1), size);
+            }
+        } else {
+            let dst = self.data.as_ptr().add(idx * size);
+
+            ptr::copy(dst.add(size), dst, (self.len - idx - 1) * size);
+        }
+
+        self.len -= 1;
+    }
+
+    /// Removes the element at `idx` and shifts the elements after it down,
+    /// keeping their order.
+    pub(crate) unsafe fn remove(&mut self, idx: usize) {
+        debug_assert!(idx < self.len, "index out of bounds");
+
+        if let Some(drop) = self.drop {
+            drop(NonNull::new_unchecked(self.slot(idx)));
+        }
+
+        self.remove_no_drop(idx);
+    }
+
+    /// Copies one element from `src` to `dst`, using the specialized copy
+    /// function if there is one.
+    ///
+    /// # Safety
+    /// - `src` must point to an element of this vec's type and `dst` to memory
+    ///   with room for one.
+    /// - Both pointers must be aligned for the element type and must not
+    ///   overlap.
+    #[inline]
+    pub(crate) unsafe fn copy_elem(&self, src: *const u8, dst: *mut u8) {
+        match self.copy {
+            Some(copy) => copy(src, dst),
+            None => ptr::copy_nonoverlapping(src, dst, self.elem_layout.size()),
         }
     }
 
     pub(crate) unsafe fn assign(&mut self, idx: usize, elem: *const u8) {
         debug_assert!(idx < self.len, "index out of bounds");
 
-        let ptr = self.data.as_ptr().add(idx * self.elem_layout.size());
+        let ptr = self.slot(idx);
 
         if let Some(drop) = self.drop {
             drop(NonNull::new_unchecked(ptr));
@@ -99,36 +195,118 @@ impl BlobVec {
         ptr::copy_nonoverlapping(elem, ptr, self.elem_layout.size());
     }
 
+    /// Swaps the elements at `a` and `b`.
+    ///
+    /// # Safety
+    /// - `a` and `b` must be in bounds.
+    pub(crate) unsafe fn swap(&mut self, a: usize, b: usize) {
+        debug_assert!(a < self.len && b < self.len, "index out of bounds");
+
+        if a != b {
+            ptr::swap_nonoverlapping(self.slot(a), self.slot(b), self.elem_layout.size());
+        }
+    }
+
     #[cfg(test)]
     fn get_mut(&mut self, idx: usize) -> Option<NonNull<u8>> {
         if idx >= self.len {
             return None;
         }
 
-        Some(unsafe {
-            NonNull::new(self.data.as_ptr().add(idx * self.elem_layout.size()))
-                .unwrap_debug_checked()
-        })
+        Some(unsafe { NonNull::new(self.slot(idx)).unwrap_debug_checked() })
     }
 
     /// Move an element from `self` to `other`. The element at `src_idx` is
-    /// swap removed from `self` and pushed onto the end of `other`.
+    /// removed from `self` and pushed onto the end of `other`. The removal
+    /// keeps the order of the remaining elements if `preserve_order` is set
+    /// and swap removes otherwise.
     ///
     /// # Safety
     /// - `src_idx` must be in bounds within `self`.
     /// - Underlying types of `self` and `other` must be interchangeable.
-    pub(crate) unsafe fn transfer_elem(&mut self, other: &mut Self, src_idx: usize) {
+    pub(crate) unsafe fn transfer_elem(
+        &mut self,
+        other: &mut Self,
+        src_idx: usize,
+        preserve_order: bool,
+    ) {
         debug_assert_eq!(
             self.elem_layout, other.elem_layout,
             "elem layouts must be the same"
         );
         debug_assert!(src_idx < self.len, "index out of bounds");
 
-        let src = self.data.as_ptr().add(src_idx * self.elem_layout.size());
+        let src = self.slot(src_idx);
         let dst = other.push().as_ptr();
 
-        ptr::copy_nonoverlapping(src, dst, self.elem_layout.size());
-        self.swap_remove_no_drop(src_idx);
+        self.copy_elem(src, dst);
+
+        if preserve_order {
+            self.remove_no_drop(src_idx);
+        } else {
+            self.swap_remove_no_drop(src_idx);
+        }
+    }
+
+    /// Move all elements from `self` onto the end of `other`, leaving `self`
+    /// empty. This is a single copy of the underlying buffer unless the vecs
+    /// are chunked.
+    ///
+    /// # Safety
+    /// - Underlying types of `self` and `other` must be interchangeable.
+    pub(crate) unsafe fn transfer_all(&mut self, other: &mut Self) {
+        debug_assert_eq!(
+            self.elem_layout, other.elem_layout,
+            "elem layouts must be the same"
+        );
+
+        other.reserve(self.len);
+
+        let size = self.elem_layout.size();
+
+        if self.is_chunked() || other.is_chunked() {
+            for i in 0..self.len {
+                ptr::copy_nonoverlapping(self.slot(i), other.slot(other.len + i), size);
+            }
+        } else {
+            let dst = other.data.as_ptr().add(other.len * size);
+
+            ptr::copy_nonoverlapping(self.data.as_ptr(), dst, self.len * size);
+        }
+
+        other.len += self.len;
+        self.len = 0;
+    }
+
+    /// Push `count` elements onto the end of `self`, initializing each one by
+    /// passing a pointer to the uninitialized slot to `f`.
+    ///
+    /// If `f` unwinds, the elements initialized so far are kept.
+    ///
+    /// # Safety
+    /// - `f` must initialize the slot with a valid element.
+    pub(crate) unsafe fn extend_with(&mut self, count: usize, mut f: impl FnMut(NonNull<u8>)) {
+        self.reserve(count);
+
+        for _ in 0..count {
+            let slot = self.slot(self.len);
+
+            f(NonNull::new_unchecked(slot));
+
+            self.len += 1;
+        }
+    }
+
+    /// Returns a new empty `BlobVec` with the same element type as `self`.
+    pub(crate) fn empty_like(&self) -> Self {
+        // SAFETY: `drop` and `copy` are already valid for this element type.
+        unsafe {
+            if self.is_chunked() {
+                Self::new_chunked(self.elem_layout, self.drop, self.copy)
+            } else {
+                Self::new(self.elem_layout, self.drop, self.copy)
+            }
+        }
     }
 
     pub(crate) fn reserve(&mut self, additional: usize) {
@@ -140,52 +318,107 @@ impl BlobVec {
                 capacity_overflow()
             };
 
-            debug_assert_ne!(self.elem_layout.size(), 0);
-
             // This doubling cannot overflow because `self.cap <= isize::MAX` and the type
             // of `cap` is `usize`.
-            let new_cap = (self.cap * 2).max(required_cap);
+            self.grow_to((self.cap * 2).max(required_cap));
+        }
+    }
 
-            let Some(new_cap_in_bytes) = new_cap.checked_mul(self.elem_layout.size()) else {
+    /// Like [`reserve`](Self::reserve), but doesn't allocate more than
+    /// `additional` elements of spare capacity.
+    pub(crate) fn reserve_exact(&mut self, additional: usize) {
+        let available = self.cap - self.len;
+
+        if additional > available {
+            let Some(required_cap) = self.len.checked_add(additional) else {
                 capacity_overflow()
             };
 
-            if new_cap_in_bytes > isize::MAX as usize {
-                capacity_overflow()
-            }
+            self.grow_to(required_cap);
+        }
+    }
+
+    /// Reallocates the buffer to hold exactly `new_cap` elements. Chunked vecs
+    /// allocate new chunks instead, rounding the capacity up to a whole number
+    /// of chunks.
+    fn grow_to(&mut self, new_cap: usize) {
+        debug_assert_ne!(self.elem_layout.size(), 0);
+        debug_assert!(new_cap > self.cap);
+
+        if self.is_chunked() {
+            return self.grow_chunks_to(new_cap);
+        }
+
+        let Some(new_cap_in_bytes) = new_cap.checked_mul(self.elem_layout.size()) else {
+            capacity_overflow()
+        };
 
+        if new_cap_in_bytes > isize::MAX as usize {
+            capacity_overflow()
+        }
+
+        // SAFETY:
+        // - `new_cap_in_bytes` is <= `isize::MAX` from above check (size is multiple of
+        //   align).
+        // - Alignment is from layout so must be valid.
+        let new_cap_layout = unsafe {
+            Layout::from_size_align_unchecked(new_cap_in_bytes, self.elem_layout.align())
+        };
+
+        // The current layout of the capacity.
+        let old_cap_layout = self.capacity_layout();
+
+        let ptr = if old_cap_layout.size() == 0 {
+            // SAFETY: `new_cap_layout` is nonzero due to previous ZST check.
+            unsafe { alloc::alloc(new_cap_layout) }
+        } else {
             // SAFETY:
-            // - `new_cap_in_bytes` is <= `isize::MAX` from above check (size is multiple of
-            //   align).
-            // - Alignment is from layout so must be valid.
-            let new_cap_layout = unsafe {
-                Layout::from_size_align_unchecked(new_cap_in_bytes, self.elem_layout.align())
-            };
+            // - `old_cap_layout` size is nonzero, so `data` must be currently allocated via
+            //   the global allocator.
+            // - `old_cap_layout` is the previous layout of the data.
+            // - `new_cap_layout` size does not exceed `isize::MAX` because of `Layout`
+            //   invariant, and is nonzero due to previous ZST check.
+            unsafe { alloc::realloc(self.data.as_ptr(), old_cap_layout, new_cap_layout.size()) }
+        };
+
+        // Check for memory allocation failure before setting new capacity
+        // because `handle_alloc_error` could potentially unwind.
+        match NonNull::new(ptr) {
+            Some(data) => self.data = data,
+            None => alloc::handle_alloc_error(new_cap_layout),
+        }
 
-            // The current layout of the capacity.
-            let old_cap_layout = self.capacity_layout();
+        self.cap = new_cap;
+    }
 
-            let ptr = if old_cap_layout.size() == 0 {
-                // SAFETY: `new_cap_layout` is nonzero due to previous ZST check.
-                unsafe { alloc::alloc(new_cap_layout) }
-            } else {
-                // SAFETY:
-                // - `old_cap_layout` size is nonzero, so `data` must be currently allocated via
-                //   the global allocator.
-                // - `old_cap_layout` is the previous layout of the data.
-                // - `new_cap_layout` size does not exceed `isize::MAX` because of `Layout`
-                //   invariant, and is nonzero due to previous ZST check.
-                unsafe { alloc::realloc(self.data.as_ptr(), old_cap_layout, new_cap_layout.size()) }
-            };
+    fn grow_chunks_to(&mut self, new_cap: usize) {
+        let chunk_layout = self.chunk_layout();
+        let chunks = unsafe { self.chunks.as_mut().unwrap_debug_checked() };
+
+        while self.cap < new_cap {
+            // SAFETY: `chunk_layout` is nonzero because the vec isn't a ZST.
+            let ptr = unsafe { alloc::alloc(chunk_layout) };
 
-            // Check for memory allocation failure before setting new capacity
-            // because `handle_alloc_error` could potentially unwind.
             match NonNull::new(ptr) {
-                Some(data) => self.data = data,
-                None => alloc::handle_alloc_error(new_cap_layout),
+                Some(chunk) => chunks.push(chunk),
+                None => alloc::handle_alloc_error(chunk_layout),
             }
 
-            self.cap = new_cap;
+            self.cap += CHUNK_LEN;
+        }
+
+        self.data = NonNull::from(chunks.as_slice()).cast();
+    }
+
+    /// Returns the layout of a single chunk.
+    fn chunk_layout(&self) -> Layout {
+        let Some(size) = self.elem_layout.size().checked_mul(CHUNK_LEN) else {
+            capacity_overflow()
+        };
+
+        match Layout::from_size_align(size, self.elem_layout.align()) {
+            Ok(layout) => layout,
+            Err(_) => capacity_overflow(),
         }
     }
 
@@ -196,10 +429,8 @@ impl BlobVec {
         self.len = 0;
 
         if let Some(drop) = self.drop {
-            let elem_size = self.elem_layout.size();
-
             for i in 0..len {
-                let elem = unsafe { self.data.as_ptr().add(i * elem_size) };
+                let elem = unsafe { self.slot(i) };
                 // SAFETY:
                 // - `elem` points to a valid element.
                 // - `elem` is nonnull.
@@ -209,7 +440,7 @@ impl BlobVec {
     }
 
     /// Returns the layout of the entire allocated buffer owned by this
-    /// `BlobVec`.
+    /// `BlobVec`, or of all the chunks together if the vec is chunked.
     pub(crate) fn capacity_layout(&self) -> Layout {
         unsafe {
             Layout::from_size_align(self.elem_layout.size() * self.cap, self.elem_layout.align())
@@ -226,10 +457,22 @@ impl BlobVec {
         self.elem_layout
     }
 
+    /// Returns a pointer to the beginning of the buffer, or to the list of
+    /// chunks if the vec is chunked. Pass it to [`slot_in`] to find an
+    /// element.
     pub(crate) fn as_ptr(&self) -> NonNull<u8> {
         self.data
     }
 
+    /// Returns pointers to the beginning of every chunk. Vecs which aren't
+    /// chunked have a single chunk holding every element.
+    pub(crate) fn chunks(&self) -> &[NonNull<u8>] {
+        match &self.chunks {
+            Some(chunks) => chunks,
+            None => slice::from_ref(&self.data),
+        }
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.len
     }
@@ -237,12 +480,35 @@ impl BlobVec {
     pub(crate) fn capacity(&self) -> usize {
         self.cap
     }
+
+    /// Returns the number of bytes allocated by this vec, including the list
+    /// of chunks if the vec is chunked.
+    pub(crate) fn heap_size(&self) -> usize {
+        let chunk_list = self.chunks.as_ref().map_or(0, |chunks| {
+            chunks.capacity() * mem::size_of::<NonNull<u8>>()
+        });
+
+        // Zero-sized elements have a capacity of `usize::MAX`, which doesn't overflow
+        // here.
+        self.elem_layout.size() * self.cap + chunk_list
+    }
 }
 
 impl Drop for BlobVec {
     fn drop(&mut self) {
         self.clear();
 
+        if let Some(chunks) = &self.chunks {
+            let chunk_layout = self.chunk_layout();
+
+            for &chunk in chunks {
+                // SAFETY: Every chunk was allocated with `chunk_layout`.
+                unsafe { alloc::dealloc(chunk.as_ptr(), chunk_layout) };
+            }
+
+            return;
+        }
+
         let cap_layout = self.capacity_layout();
 
         if cap_layout.size() > 0 {
@@ -255,6 +521,24 @@ impl Drop for BlobVec {
     }
 }
 
+/// Returns a pointer to slot `idx` of a vec whose [`as_ptr`] is `base`. The
+/// vec's elements have the given `size`, and `chunked` is whether the vec is
+/// chunked.
+///
+/// # Safety
+/// - `idx` must be less than the capacity of the vec.
+///
+/// [`as_ptr`]: BlobVec::as_ptr
+#[inline]
+pub(crate) unsafe fn slot_in(base: NonNull<u8>, size: usize, chunked: bool, idx: usize) -> *mut u8 {
+    if chunked {
+        let chunk = *base.as_ptr().cast::<NonNull<u8>>().add(idx / CHUNK_LEN);
+        chunk.as_ptr().add(idx % CHUNK_LEN * size)
+    } else {
+        base.as_ptr().add(idx * size)
+    }
+}
+
 #[cold]
 fn capacity_overflow() -> ! {
     panic!("capacity overflow")
@@ -268,7 +552,7 @@ mod tests {
     use crate::drop::drop_fn_of;
 
     fn new_blob_vec<T>() -> BlobVec {
-        unsafe { BlobVec::new(Layout::new::<T>(), drop_fn_of::<T>()) }
+        unsafe { BlobVec::new(Layout::new::<T>(), drop_fn_of::<T>(), None) }
     }
 
     #[test]
@@ -323,14 +607,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn remove_keeps_order() {
+        let contiguous = new_blob_vec::<String>();
+        let chunked =
+            unsafe { BlobVec::new_chunked(Layout::new::<String>(), drop_fn_of::<String>(), None) };
+
+        for mut vec in [contiguous, chunked] {
+            let mut other = vec.empty_like();
+
+            unsafe {
+                for s in ["aaa", "bbb", "ccc", "ddd", "eee"] {
+                    vec.push().as_ptr().cast::<String>().write(s.into());
+                }
+
+                vec.remove(1);
+                vec.transfer_elem(&mut other, 0, true);
+                vec.remove(2);
+
+                let get = |vec: &BlobVec, idx| &*vec.slot(idx).cast::<String>();
+
+                assert_eq!(vec.len(), 2);
+                assert_eq!(get(&vec, 0), "ccc");
+                assert_eq!(get(&vec, 1), "ddd");
+                assert_eq!(get(&other, 0), "aaa");
+            }
+        }
+    }
+
     #[test]
     fn unusual_alignment() {
         unsafe {
-            let mut vec = BlobVec::new(Layout::from_size_align(5, 128).unwrap(), None);
+            let mut vec = BlobVec::new(Layout::from_size_align(5, 128).unwrap(), None, None);
 
             #[track_caller]
             fn check(ptr: NonNull<u8>) {
-                assert!(ptr.as_ptr() as usize % 128 == 0);
+                assert!((ptr.as_ptr() as usize).is_multiple_of(128));
             }
 
             check(vec.push());
@@ -341,4 +653,151 @@ mod tests {
             check(vec.get_mut(1).unwrap());
         }
     }
+
+    #[test]
+    fn over_aligned_from_zero_capacity() {
+        #[track_caller]
+        fn check(ptr: NonNull<u8>, align: usize) {
+            assert!((ptr.as_ptr() as usize).is_multiple_of(align));
+        }
+
+        for align in [16, 32, 64, 128, 256, 1024, 4096] {
+            unsafe {
+                let layout = Layout::from_size_align(align / 2, align).unwrap();
+
+                let mut a = BlobVec::new(layout, None, None);
+                let mut b = BlobVec::new(layout, None, None);
+
+                check(a.as_ptr(), align);
+
+                for _ in 0..10 {
+                    check(a.push(), align);
+                }
+
+                a.transfer_elem(&mut b, 3, false);
+                check(b.as_ptr(), align);
+
+                b.extend_with(5, |ptr| check(ptr, align));
+                a.transfer_all(&mut b);
+
+                for i in 0..b.len() {
+                    check(b.get_mut(i).unwrap(), align);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn over_aligned_zst() {
+        #[repr(align(4096))]
+        struct Z;
+
+        let mut vec = new_blob_vec::<Z>();
+
+        for _ in 0..3 {
+            let ptr = unsafe { vec.push() };
+            assert!((ptr.as_ptr() as usize).is_multiple_of(4096));
+        }
+    }
+
+    #[test]
+    fn transfer_all_and_extend_with() {
+        let mut a = new_blob_vec::<String>();
+        let mut b = new_blob_vec::<String>();
+
+        unsafe {
+            a.extend_with(3, |ptr| ptr.as_ptr().cast::<String>().write("a".into()));
+            b.extend_with(2, |ptr| ptr.as_ptr().cast::<String>().write("b".into()));
+
+            a.transfer_all(&mut b);
+
+            assert_eq!(a.len(), 0);
+            assert_eq!(b.len(), 5);
+
+            let ptr = b.as_ptr().cast::<String>().as_ptr();
+
+            assert_eq!(*ptr.add(1), "b");
+            assert_eq!(*ptr.add(2), "a");
+            assert_eq!(*ptr.add(4), "a");
+        }
+    }
+
+    #[test]
+    fn specialized_copy() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::component::copy_fn_of;
+
+        static COPIES: AtomicUsize = AtomicUsize::new(0);
+
+        unsafe fn counting_copy(src: *const u8, dst: *mut u8) {
+            COPIES.fetch_add(1, Ordering::Relaxed);
+            unsafe { copy_fn_of::<[u32; 3]>()(src, dst) };
+        }
+
+        let layout = Layout::new::<[u32; 3]>();
+
+        unsafe {
+            let mut a = BlobVec::new(layout, None, Some(counting_copy));
+            let mut b = a.empty_like();
+
+            for i in 0..4 {
+                a.push().cast::<[u32; 3]>().as_ptr().write([i; 3]);
+            }
+
+            // Moves the last element into the hole.
+            a.swap_remove(0);
+            // Copies into `b`, then moves the last element of `a` into the hole.
+            a.transfer_elem(&mut b, 1, false);
+
+            assert_eq!(COPIES.load(Ordering::Relaxed), 3);
+
+            let a_ptr = a.as_ptr().cast::<[u32; 3]>().as_ptr();
+            assert_eq!(a.len(), 2);
+            assert_eq!(*a_ptr, [3; 3]);
+            assert_eq!(*a_ptr.add(1), [2; 3]);
+
+            assert_eq!(b.len(), 1);
+            assert_eq!(*b.as_ptr().cast::<[u32; 3]>().as_ptr(), [1; 3]);
+        }
+    }
+
+    #[test]
+    fn chunked() {
+        let mut vec =
+            unsafe { BlobVec::new_chunked(Layout::new::<String>(), drop_fn_of::<String>(), None) };
+        let mut other = vec.empty_like();
+
+        let count = CHUNK_LEN * 2 + 10;
+
+        unsafe {
+            vec.extend_with(count, |ptr| ptr.as_ptr().cast::<String>().write("a".into()));
+        }
+
+        let first = unsafe { vec.slot(0) };
+        let last = unsafe { vec.slot(count - 1) };
+
+        assert_eq!(vec.chunks().len(), 3);
+        assert_eq!(vec.capacity(), CHUNK_LEN * 3);
+
+        unsafe {
+            vec.reserve(CHUNK_LEN * 4);
+
+            assert_eq!(vec.slot(0), first);
+            assert_eq!(vec.slot(count - 1), last);
+
+            vec.push().as_ptr().cast::<String>().write("b".into());
+            vec.swap_remove(0);
+
+            assert_eq!(*vec.slot(0).cast::<String>(), "b");
+
+            vec.transfer_elem(&mut other, CHUNK_LEN, false);
+            vec.transfer_all(&mut other);
+
+            assert_eq!(vec.len(), 0);
+            assert_eq!(other.len(), count);
+            assert_eq!(*other.slot(1).cast::<String>(), "b");
+            assert_eq!(*other.slot(count - 1).cast::<String>(), "a");
+        }
+    }
 }
@@ -38,6 +38,16 @@ impl<T> BoolExpr<T> {
         }
     }
 
+    /// Returns the number of bytes allocated by this expression.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.ands.capacity() * mem::size_of::<Ands<T>>()
+            + self
+                .ands
+                .iter()
+                .map(|ands| ands.vars.heap_size() + ands.negated_vars.heap_size())
+                .sum::<usize>()
+    }
+
     /// Create an expression from a single variable.
     pub fn var(value: T) -> Self
     where
@@ -123,6 +133,39 @@ impl<T> BoolExpr<T> {
         false
     }
 
+    /// Evaluate the boolean expression, where the variables in `set` are
+    /// `true` and all others are `false`.
+    ///
+    /// This gives the same result as `self.eval(|var| set.contains(var))`, but
+    /// checks whole blocks of variables at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evenio::bit_set::BitSet;
+    /// use evenio::bool_expr::BoolExpr;
+    ///
+    /// let expr = BoolExpr::var(0_u32).and(&BoolExpr::not_var(1));
+    ///
+    /// assert!(expr.eval_set(&BitSet::from_iter([0, 2])));
+    /// assert!(!expr.eval_set(&BitSet::from_iter([0, 1])));
+    /// ```
+    pub fn eval_set(&self, set: &BitSet<T>) -> bool {
+        self.ands
+            .iter()
+            .any(|ands| ands.vars.is_subset(set) && ands.negated_vars.is_disjoint(set))
+    }
+
+    /// Does the variable `value` appear anywhere in the expression?
+    pub(crate) fn references(&self, value: T) -> bool
+    where
+        T: SparseIndex,
+    {
+        self.ands
+            .iter()
+            .any(|ands| ands.vars.contains(value) || ands.negated_vars.contains(value))
+    }
+
     /// AND two expressions together.
     #[must_use]
     pub fn and(mut self, other: &Self) -> Self
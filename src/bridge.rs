@@ -0,0 +1,489 @@
+//! Mirroring events out of a [`World`] and into other worlds or external
+//! loops.
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::{vec_deque, BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::{self, Any, TypeId};
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::bool_expr::BoolExpr;
+use crate::entity::EntityId;
+use crate::event::{Event, EventPtr};
+use crate::system::{Config, InitError, Priority, System, SystemId, SystemInfo};
+use crate::world::{UnsafeWorldCell, World};
+
+/// The system added by [`World::add_event_sink`]. Calls a function with every
+/// `E` that reaches the end of its broadcast.
+pub(crate) struct EventSink<E, F> {
+    sink: F,
+    _marker: PhantomData<fn(&E)>,
+}
+
+impl<E, F> EventSink<E, F> {
+    pub(crate) fn new(sink: F) -> Self {
+        Self {
+            sink,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E, F> System for EventSink<E, F>
+where
+    E: Event,
+    F: FnMut(&E) + Send + Sync + 'static,
+{
+    fn type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Owned(alloc::format!("EventSink<{}>", any::type_name::<E>()))
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        init_sink::<E>(world, config);
+
+        Ok(())
+    }
+
+    unsafe fn run(&mut self, _info: &SystemInfo, event_ptr: EventPtr, _world: UnsafeWorldCell) {
+        (self.sink)(event_ptr.as_event::<E>())
+    }
+
+    fn refresh_archetype(&mut self, _arch: &Archetype) {}
+
+    fn remove_archetype(&mut self, _arch: &Archetype) {}
+}
+
+/// Configures a system to receive every `E` once the other listeners are
+/// done with it.
+fn init_sink<E: Event>(world: &mut World, config: &mut Config) {
+    let id = world.add_event::<E>();
+
+    config.received_event = Some(id);
+    config.received_event_access = Access::Read;
+    config.priority = Priority::AfterDefault;
+
+    if E::IS_TARGETED {
+        let info = world.events().get(id).expect("event should exist");
+        config.targeted_event_expr = BoolExpr::new(true).and(info.target_filter());
+    }
+}
+
+/// The system added by [`World::event_channel`]. Copies every `E` that reaches
+/// the end of its broadcast into the world's channel for `E`.
+pub(crate) struct ChannelSink<E>(PhantomData<fn(&E)>);
+
+impl<E> ChannelSink<E> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: Event + Clone> System for ChannelSink<E> {
+    fn type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Owned(alloc::format!("ChannelSink<{}>", any::type_name::<E>()))
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        init_sink::<E>(world, config);
+
+        Ok(())
+    }
+
+    unsafe fn run(&mut self, _info: &SystemInfo, event_ptr: EventPtr, world: UnsafeWorldCell) {
+        // SAFETY: Channels are only touched by their sink while events are being
+        // broadcast.
+        let Some(channel) = (unsafe { world.event_channels() }).get_mut::<E>() else {
+            return;
+        };
+
+        let target = E::IS_TARGETED.then(|| world.current_target());
+
+        channel.push(event_ptr.as_event::<E>(), target);
+    }
+
+    fn refresh_archetype(&mut self, _arch: &Archetype) {}
+
+    fn remove_archetype(&mut self, _arch: &Archetype) {}
+}
+
+/// The channels added to a world with [`World::event_channel`], keyed by the
+/// [`TypeId`] of their events.
+#[derive(Default)]
+pub(crate) struct EventChannels {
+    channels: BTreeMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl EventChannels {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn get<E: Event>(&self) -> Option<&Channel<E>> {
+        self.channels.get(&TypeId::of::<E>())?.downcast_ref()
+    }
+
+    pub(crate) fn get_mut<E: Event>(&mut self) -> Option<&mut Channel<E>> {
+        self.channels.get_mut(&TypeId::of::<E>())?.downcast_mut()
+    }
+
+    pub(crate) fn insert<E: Event>(&mut self, channel: Channel<E>) {
+        self.channels.insert(TypeId::of::<E>(), Box::new(channel));
+    }
+}
+
+impl fmt::Debug for EventChannels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventChannels")
+            .field("len", &self.channels.len())
+            .finish()
+    }
+}
+
+/// A ring buffer of the most recent `E`s sent in a world.
+pub(crate) struct Channel<E> {
+    /// The captured events, oldest first.
+    events: VecDeque<CapturedEvent<E>>,
+    /// The sequence number of the first event in `events`.
+    first: u64,
+    capacity: usize,
+    /// Shared with every [`EventChannelReader`] so the channel can tell if any
+    /// are left.
+    readers: Arc<()>,
+    /// The [`ChannelSink`] filling the channel.
+    pub(crate) sink: SystemId,
+}
+
+impl<E: Clone> Channel<E> {
+    pub(crate) fn new(capacity: usize, sink: SystemId) -> Self {
+        Self {
+            events: VecDeque::new(),
+            first: 0,
+            capacity,
+            readers: Arc::new(()),
+            sink,
+        }
+    }
+
+    /// Raises the capacity of the channel to at least `capacity`.
+    pub(crate) fn reserve(&mut self, capacity: usize) {
+        self.capacity = self.capacity.max(capacity);
+    }
+
+    /// Returns a reader which sees the events captured from now on.
+    pub(crate) fn reader(&self) -> EventChannelReader<E> {
+        EventChannelReader {
+            next: self.first + self.events.len() as u64,
+            readers: self.readers.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn push(&mut self, event: &E, target: Option<EntityId>) {
+        if Arc::strong_count(&self.readers) == 1 {
+            // Nobody is listening, so there's nothing to keep.
+            self.first += self.events.len() as u64;
+            self.events.clear();
+            return;
+        }
+
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+            self.first += 1;
+        }
+
+        self.events.push_back(CapturedEvent {
+            event: event.clone(),
+            target,
+        });
+    }
+}
+
+type Pump = Box<dyn FnMut(&mut World) + Send + Sync>;
+
+/// The receiving ends of the bridges added to a world with
+/// [`World::bridge_events`].
+pub(crate) struct Bridges {
+    pumps: Vec<Pump>,
+}
+
+impl Bridges {
+    pub(crate) fn new() -> Self {
+        Self { pumps: Vec::new() }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn push(&mut self, pump: Pump) {
+        self.pumps.push(pump);
+    }
+
+    /// Moves the pumps out so they can be run with access to the world.
+    pub(crate) fn take(&mut self) -> Vec<Pump> {
+        core::mem::take(&mut self.pumps)
+    }
+
+    /// Puts back the pumps removed with [`take`](Self::take), keeping any
+    /// added in the meantime after them.
+    pub(crate) fn restore(&mut self, mut pumps: Vec<Pump>) {
+        pumps.append(&mut self.pumps);
+        self.pumps = pumps;
+    }
+}
+
+impl fmt::Debug for Bridges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bridges")
+            .field("len", &self.pumps.len())
+            .finish()
+    }
+}
+
+/// An event captured by an [`EventChannelReader`]'s channel.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CapturedEvent<E> {
+    /// A copy of the event, as it was left by the systems listening for it.
+    pub event: E,
+    /// The entity the event was visiting when it was captured, or `None` if
+    /// the event is untargeted.
+    pub target: Option<EntityId>,
+}
+
+/// Reads the events captured by a channel added with
+/// [`World::event_channel`].
+///
+/// Each reader keeps track of the events it has already seen, so every reader
+/// sees every event at most once. Events that were overwritten before the
+/// reader got to them are counted by [`EventChannelDrain::missed`]. Cloning a
+/// reader creates another reader at the same position.
+pub struct EventChannelReader<E> {
+    /// The sequence number of the next event to read.
+    next: u64,
+    readers: Arc<()>,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<E: Event> EventChannelReader<E> {
+    /// Returns the events captured since the last call to `drain`, oldest
+    /// first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `world` isn't the world the reader was created from.
+    #[track_caller]
+    pub fn drain<'a>(&mut self, world: &'a World) -> EventChannelDrain<'a, E> {
+        let channel = world
+            .event_channels()
+            .get::<E>()
+            .filter(|channel| Arc::ptr_eq(&channel.readers, &self.readers))
+            .expect("event channel reader used with a different world");
+
+        let missed = channel.first.saturating_sub(self.next);
+        let start = (self.next + missed - channel.first) as usize;
+
+        self.next = channel.first + channel.events.len() as u64;
+
+        EventChannelDrain {
+            iter: channel.events.range(start..),
+            missed,
+        }
+    }
+}
+
+impl<E> Clone for EventChannelReader<E> {
+    fn clone(&self) -> Self {
+        Self {
+            next: self.next,
+            readers: self.readers.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E> fmt::Debug for EventChannelReader<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventChannelReader")
+            .field("next", &self.next)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator over the events returned by [`EventChannelReader::drain`].
+#[derive(Clone, Debug)]
+pub struct EventChannelDrain<'a, E> {
+    iter: vec_deque::Iter<'a, CapturedEvent<E>>,
+    missed: u64,
+}
+
+impl<E> EventChannelDrain<'_, E> {
+    /// Returns the number of events the reader missed since the last drain
+    /// because the channel was full and overwrote them.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+}
+
+impl<'a, E> Iterator for EventChannelDrain<'a, E> {
+    type Item = &'a CapturedEvent<E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<E> ExactSizeIterator for EventChannelDrain<'_, E> {}
+
+impl<E> FusedIterator for EventChannelDrain<'_, E> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    use super::CapturedEvent;
+    use crate::prelude::*;
+
+    #[derive(Event, Clone)]
+    struct Chat(String);
+
+    #[test]
+    fn event_sink_runs_after_listeners() {
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_clone = log.clone();
+        world.add_event_sink(move |e: &Chat| log_clone.lock().unwrap().push(e.0.clone()));
+
+        world.add_system(|mut r: ReceiverMut<Chat>| r.event.0.push('!'));
+
+        world.send(Chat("hi".into()));
+
+        assert_eq!(*log.lock().unwrap(), ["hi!"]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn bridge_events() {
+        #[derive(Event, Clone)]
+        struct Kick(#[event(target)] EntityId);
+
+        let mut a = World::new();
+        let mut b = World::new();
+
+        let a_player = a.spawn();
+        let a_guest = a.spawn();
+        let b_player = b.spawn();
+
+        a.bridge_events::<Chat, _>(&mut b, |_| None);
+        a.bridge_events::<Kick, _>(&mut b, move |id| (id == a_player).then_some(b_player));
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_clone = log.clone();
+        b.add_system(move |r: Receiver<Chat>| {
+            log_clone.lock().unwrap().push(r.event.0.clone());
+        });
+
+        let kicked = Arc::new(Mutex::new(vec![]));
+
+        let kicked_clone = kicked.clone();
+        b.add_system(move |r: Receiver<Kick, ()>| kicked_clone.lock().unwrap().push(r.event.0));
+
+        a.send(Chat("one".into()));
+        a.send(Kick(a_player));
+        a.send(Kick(a_guest));
+        a.send(Chat("two".into()));
+
+        // Nothing is delivered until the bridges are pumped.
+        assert!(log.lock().unwrap().is_empty());
+
+        b.pump_bridges();
+
+        assert_eq!(*log.lock().unwrap(), ["one", "two"]);
+        assert_eq!(*kicked.lock().unwrap(), [b_player]);
+
+        b.pump_bridges();
+
+        assert_eq!(log.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn event_channel() {
+        #[derive(Event, Clone, PartialEq, Debug)]
+        struct Resized(u32);
+
+        #[derive(Event, Clone, PartialEq, Debug)]
+        struct Hit(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        // Nothing is kept before there are readers.
+        world.send(Resized(0));
+
+        let mut a = world.event_channel::<Resized>(3);
+        let mut hits = world.event_channel::<Hit>(8);
+
+        world.add_system(|mut r: ReceiverMut<Resized>| r.event.0 *= 10);
+
+        world.send(Resized(1));
+        world.send(Resized(2));
+
+        let mut b = a.clone();
+
+        assert_eq!(
+            a.drain(&world).map(|c| c.event.0).collect::<Vec<_>>(),
+            [10, 20]
+        );
+        assert_eq!(a.drain(&world).count(), 0);
+
+        world.send(Resized(3));
+        world.send(Resized(4));
+
+        // `b` fell behind, so the oldest event was overwritten.
+        let drain = b.drain(&world);
+        assert_eq!(drain.missed(), 1);
+        assert_eq!(drain.map(|c| c.event.0).collect::<Vec<_>>(), [20, 30, 40]);
+
+        let drain = a.drain(&world);
+        assert_eq!(drain.missed(), 0);
+        assert_eq!(drain.map(|c| c.event.0).collect::<Vec<_>>(), [30, 40]);
+
+        let e = world.spawn();
+        world.send(Hit(e));
+
+        let captured = hits.drain(&world).collect::<Vec<_>>();
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].target, Some(e));
+
+        assert_eq!(a.drain(&world).next(), None::<&CapturedEvent<Resized>>);
+    }
+
+    #[test]
+    #[should_panic(expected = "different world")]
+    fn event_channel_wrong_world() {
+        let mut a = World::new();
+        let mut b = World::new();
+
+        let mut reader = a.event_channel::<Chat>(1);
+        let _ = b.event_channel::<Chat>(1);
+
+        reader.drain(&b);
+    }
+}
@@ -0,0 +1,361 @@
+//! Deferred structural changes and the [`Commands`] system parameter.
+
+use alloc::format;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::any;
+use core::ptr::NonNull;
+
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::component::Component;
+use crate::drop::{drop_fn_of, DropFn};
+use crate::entity::EntityId;
+use crate::event::EventPtr;
+use crate::system::{Config, InitError, SystemInfo, SystemParam};
+use crate::world::{UnsafeWorldCell, World};
+
+/// A system parameter for queueing structural changes to the world.
+///
+/// Structural changes such as inserting and removing components can't happen
+/// while a system is iterating over a [`Fetcher`]. `Commands` records each
+/// change in a buffer and applies them in order immediately after the system
+/// returns. This happens before lower priority systems receive the current
+/// event.
+///
+/// Applied commands send the usual [`Spawn`], [`Insert`], [`Remove`], and
+/// [`Despawn`] events. Unlike [`Sender`], the component types don't need to be
+/// known ahead of time. Commands targeting an entity that doesn't exist when
+/// the command is applied are skipped, so despawning an entity discards the
+/// commands queued for it afterwards.
+///
+/// `Commands` has exclusive access to the event queue, so a system can't have
+/// both a `Commands` and a [`Sender`] parameter.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::command::Commands;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// #[derive(Component)]
+/// struct Dead;
+///
+/// #[derive(Event)]
+/// struct Tick;
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(0));
+///
+/// world.add_system(
+///     |_: Receiver<Tick>, f: Fetcher<(EntityId, &Health)>, mut cmds: Commands| {
+///         for (id, health) in f {
+///             if health.0 <= 0 {
+///                 cmds.remove::<Health>(id);
+///                 cmds.insert(id, Dead);
+///             }
+///         }
+///     },
+/// );
+///
+/// world.send(Tick);
+///
+/// assert!(world.get_component::<Health>(e).is_none());
+/// assert!(world.get_component::<Dead>(e).is_some());
+/// ```
+///
+/// [`Fetcher`]: crate::fetch::Fetcher
+/// [`Spawn`]: crate::event::Spawn
+/// [`Insert`]: crate::event::Insert
+/// [`Remove`]: crate::event::Remove
+/// [`Despawn`]: crate::event::Despawn
+/// [`Sender`]: crate::event::Sender
+#[derive(Debug)]
+pub struct Commands<'a> {
+    world: UnsafeWorldCell<'a>,
+}
+
+impl Commands<'_> {
+    /// Reserves an [`EntityId`] and queues the entity to be spawned. The
+    /// returned ID is not used by any previous entities in the world.
+    ///
+    /// The entity will not exist in the world until the command is applied.
+    pub fn spawn(&mut self) -> EntityId {
+        let id = unsafe { self.world.reserve_entity() };
+        self.push(move |world| world.spawn_reserved(id));
+        id
+    }
+
+    /// Queues the insertion of `component` on `entity`.
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.push(move |world| {
+            if world.entities().contains(entity) {
+                world.insert(entity, component);
+            }
+        });
+    }
+
+    /// Queues the removal of component `C` from `entity`.
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.push(move |world| {
+            if world.entities().contains(entity) {
+                world.remove::<C>(entity);
+            }
+        });
+    }
+
+    /// Queues `entity` to be despawned.
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.push(move |world| {
+            if world.entities().contains(entity) {
+                world.despawn(entity);
+            }
+        });
+    }
+
+    fn push<F: FnOnce(&mut World) + 'static>(&mut self, f: F) {
+        unsafe fn apply<F: FnOnce(&mut World)>(ptr: NonNull<u8>, world: &mut World) {
+            (ptr.cast::<F>().as_ptr().read())(world)
+        }
+
+        let ptr = unsafe { self.world.alloc_layout(Layout::new::<F>()) };
+        unsafe { ptr.cast::<F>().as_ptr().write(f) };
+
+        unsafe {
+            self.world.commands().push(Command {
+                ptr,
+                apply: apply::<F>,
+                drop: drop_fn_of::<F>(),
+            })
+        };
+    }
+}
+
+unsafe impl SystemParam for Commands<'_> {
+    type State = ();
+
+    type Item<'a> = Commands<'a>;
+
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        if !config
+            .event_queue_access
+            .set_if_compatible(Access::ReadWrite)
+        {
+            return Err(InitError(
+                format!(
+                    "`{}` has conflicting access with a previous system parameter. Only one \
+                     system parameter can send events",
+                    any::type_name::<Self>()
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        _event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        Commands { world }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+/// The buffer of commands queued by [`Commands`]. Command data is allocated in
+/// the event queue's bump allocator.
+#[derive(Debug)]
+pub(crate) struct CommandQueue {
+    items: Vec<Command>,
+}
+
+#[derive(Debug)]
+struct Command {
+    ptr: NonNull<u8>,
+    apply: unsafe fn(NonNull<u8>, &mut World),
+    drop: DropFn,
+}
+
+impl CommandQueue {
+    pub(crate) fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn push(&mut self, command: Command) {
+        self.items.push(command);
+    }
+
+    /// Applies all queued commands in order. Commands queued while applying
+    /// are left in the queue.
+    ///
+    /// # Safety
+    ///
+    /// Must be called during event dispatch, while the event queue's bump
+    /// allocator still holds the command data.
+    pub(crate) unsafe fn apply(world: &mut World) {
+        struct DropRemaining(alloc::vec::IntoIter<Command>);
+
+        impl Drop for DropRemaining {
+            fn drop(&mut self) {
+                for cmd in &mut self.0 {
+                    if let Some(drop) = cmd.drop {
+                        unsafe { drop(cmd.ptr) };
+                    }
+                }
+            }
+        }
+
+        let items = core::mem::take(&mut world.commands_mut().items);
+        let mut remaining = DropRemaining(items.into_iter());
+
+        for cmd in remaining.0.by_ref() {
+            unsafe { (cmd.apply)(cmd.ptr, world) };
+        }
+    }
+
+    /// Drops all queued commands without applying them.
+    pub(crate) unsafe fn drop_all(&mut self) {
+        for cmd in self.items.drain(..) {
+            if let Some(drop) = cmd.drop {
+                unsafe { drop(cmd.ptr) };
+            }
+        }
+    }
+}
+
+// SAFETY: Command data is only accessed behind an exclusive reference to the
+// world.
+unsafe impl Send for CommandQueue {}
+unsafe impl Sync for CommandQueue {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    use crate::command::Commands;
+    use crate::prelude::*;
+
+    #[derive(Component)]
+    struct A(u32);
+
+    #[derive(Component)]
+    struct B(u32);
+
+    #[derive(Event)]
+    struct E;
+
+    #[test]
+    fn commands_apply_after_system() {
+        let mut world = World::new();
+
+        let entities: Vec<_> = (0..5)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, A(i));
+                e
+            })
+            .collect();
+
+        world.add_system(
+            |_: Receiver<E>, f: Fetcher<(EntityId, &A)>, mut cmds: Commands| {
+                for (id, a) in f {
+                    cmds.insert(id, B(a.0 * 10));
+                    cmds.remove::<A>(id);
+                }
+            },
+        );
+
+        world.send(E);
+
+        for (i, &e) in entities.iter().enumerate() {
+            assert!(world.get_component::<A>(e).is_none());
+            assert_eq!(world.get_component::<B>(e).unwrap().0, i as u32 * 10);
+        }
+    }
+
+    #[test]
+    fn commands_preserve_order_and_fire_events() {
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let e = world.spawn();
+
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<Insert<A>, ()>| {
+            log_clone
+                .lock()
+                .unwrap()
+                .push(format!("insert {}", r.event.component.0));
+        });
+
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<Despawn, ()>| {
+            assert_eq!(r.event.0, e);
+            log_clone.lock().unwrap().push("despawn".into());
+        });
+
+        world.add_system(move |_: Receiver<E>, mut cmds: Commands| {
+            cmds.insert(e, A(1));
+            cmds.insert(e, A(2));
+            cmds.despawn(e);
+            // Skipped because the entity was despawned by an earlier command.
+            cmds.insert(e, A(3));
+        });
+
+        let log_clone = log.clone();
+        world.add_system(move |_: Receiver<E>| {
+            log_clone.lock().unwrap().push("after".into());
+        });
+
+        world.send(E);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["insert 1", "insert 2", "despawn", "after"]
+        );
+    }
+
+    #[test]
+    fn commands_spawn() {
+        let mut world = World::new();
+
+        let spawned = Arc::new(Mutex::new(EntityId::NULL));
+
+        let spawned_clone = spawned.clone();
+        world.add_system(move |_: Receiver<E>, mut cmds: Commands| {
+            let e = cmds.spawn();
+            cmds.insert(e, A(123));
+            *spawned_clone.lock().unwrap() = e;
+        });
+
+        world.send(E);
+
+        let e = *spawned.lock().unwrap();
+        assert_eq!(world.get_component::<A>(e).unwrap().0, 123);
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicting access")]
+    fn commands_conflict_with_sender() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<E>, _: Commands, _: Sender<()>| {});
+    }
+}
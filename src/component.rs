@@ -1,23 +1,35 @@
 //! Types for working with [`Component`]s.
 
 use alloc::borrow::Cow;
-use alloc::collections::btree_map::Entry;
+use alloc::boxed::Box;
 use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use core::alloc::Layout;
 use core::any::TypeId;
+#[cfg(feature = "std")]
+use core::mem::ManuallyDrop;
 use core::ops::Index;
+#[cfg(feature = "std")]
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+use core::{any, fmt, mem};
+#[cfg(feature = "std")]
+use std::thread::{self, ThreadId};
 
 pub use evenio_macros::Component;
 
 use crate::archetype::Archetype;
 use crate::assert::UnwrapDebugChecked;
 use crate::drop::DropFn;
-use crate::event::{Event, EventId, EventPtr};
+use crate::entity::EntityId;
+use crate::event::{Despawn, Event, EventId, EventPtr, Insert, Remove};
 use crate::prelude::World;
 use crate::slot_map::{Key, SlotMap};
 use crate::sparse::SparseIndex;
 use crate::system::{Config, InitError, SystemInfo, SystemParam};
-use crate::world::UnsafeWorldCell;
+use crate::world::{UnsafeWorldCell, WorldId};
 
 /// Contains metadata for all the components in a world.
 ///
@@ -36,72 +48,193 @@ use crate::world::UnsafeWorldCell;
 #[derive(Debug)]
 pub struct Components {
     infos: SlotMap<ComponentInfo>,
+    /// Also maps the type IDs of components which were deduplicated by their
+    /// [key](ComponentInfo::key) to the component they were merged with.
     by_type_id: BTreeMap<TypeId, ComponentId>,
+    by_key: BTreeMap<Cow<'static, str>, ComponentId>,
+    by_name: NameIndex<ComponentId>,
+    /// Types whose required components are currently being registered. Used
+    /// to detect cycles.
+    pub(crate) pending: Vec<TypeId>,
+    /// Whether any component has an `on_insert` or `on_remove` hook.
+    has_hooks: bool,
+    world: WorldId,
 }
 
 impl Components {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(world: WorldId) -> Self {
         Self {
             infos: SlotMap::new(),
             by_type_id: BTreeMap::new(),
+            by_key: BTreeMap::new(),
+            by_name: NameIndex::new(),
+            pending: Vec::new(),
+            has_hooks: false,
+            world,
         }
     }
 
-    pub(crate) fn add(&mut self, desc: ComponentDescriptor) -> (ComponentId, bool) {
+    pub(crate) fn add(
+        &mut self,
+        desc: ComponentDescriptor,
+    ) -> Result<(ComponentId, bool), AddComponentError> {
         if let Some(type_id) = desc.type_id {
-            return match self.by_type_id.entry(type_id) {
-                Entry::Vacant(v) => {
-                    let Some(k) = self.infos.insert_with(|k| ComponentInfo {
-                        name: desc.name,
-                        id: ComponentId(k),
-                        type_id: desc.type_id,
+            if let Some(&id) = self.by_type_id.get(&type_id) {
+                return Ok((id, false));
+            }
+        }
+
+        // The same type can have different type IDs when it's compiled into
+        // several libraries, so fall back to the key.
+        if let Some(key) = &desc.key {
+            if let Some(&id) = self.by_key.get(key) {
+                let existing = unsafe { self.get(id).unwrap_debug_checked() };
+
+                if existing.layout != desc.layout {
+                    return Err(AddComponentError::LayoutMismatch {
+                        key: key.clone(),
+                        existing: id,
+                        existing_layout: existing.layout,
                         layout: desc.layout,
-                        drop: desc.drop,
-                        is_immutable: desc.is_immutable,
-                        insert_events: BTreeSet::new(),
-                        remove_events: BTreeSet::new(),
-                    }) else {
-                        panic!("too many components")
-                    };
-
-                    (*v.insert(ComponentId(k)), true)
+                    });
                 }
-                Entry::Occupied(o) => (*o.get(), false),
-            };
+
+                if let Some(type_id) = desc.type_id {
+                    self.by_type_id.insert(type_id, id);
+                }
+
+                return Ok((id, false));
+            }
         }
 
+        let required = self.flatten_required(&desc.required);
+
+        let world = self.world;
+
+        // The holder of singleton components is tracked alongside the hooks.
+        let has_hooks =
+            desc.on_insert.is_some() || desc.on_remove.is_some() || desc.singleton.is_some();
+
+        // Keep the name around in case there's no room for the component.
+        let mut name = Some(desc.name);
+
         let Some(k) = self.infos.insert_with(|k| ComponentInfo {
-            name: desc.name,
-            id: ComponentId(k),
+            name: unsafe { name.take().unwrap_debug_checked() },
+            id: ComponentId(k, world),
             type_id: desc.type_id,
+            key: desc.key.clone(),
             layout: desc.layout,
             drop: desc.drop,
+            copy: if desc.drop.is_some() {
+                None
+            } else {
+                desc.copy_fn.or_else(|| copy_fn_for_layout(desc.layout))
+            },
+            clone: desc.clone_fn,
+            default_capacity: desc.default_capacity,
+            is_chunked: desc.is_chunked,
+            preserve_order: desc.preserve_order,
             is_immutable: desc.is_immutable,
+            is_local: desc.is_local,
+            is_pod: desc.is_pod && desc.drop.is_none(),
+            singleton: desc.singleton,
+            singleton_holder: None,
+            required,
+            on_insert: desc.on_insert,
+            validate: desc.validate,
+            on_remove: desc.on_remove,
             insert_events: BTreeSet::new(),
             remove_events: BTreeSet::new(),
+            removed_event: None,
+            replaced_event: None,
         }) else {
-            panic!("too many components")
+            return Err(AddComponentError::Limit(LimitError {
+                name: unsafe { name.unwrap_debug_checked() },
+                count: self.infos.len() as usize,
+            }));
         };
 
-        (ComponentId(k), true)
+        if let Some(type_id) = desc.type_id {
+            self.by_type_id.insert(type_id, ComponentId(k, world));
+        }
+
+        if let Some(key) = desc.key {
+            self.by_key.insert(key, ComponentId(k, world));
+        }
+
+        self.by_name
+            .insert(&self.infos[k].name, ComponentId(k, world));
+
+        self.has_hooks |= has_hooks;
+
+        Ok((ComponentId(k, world), true))
+    }
+
+    /// Collects the given required components along with everything they
+    /// require in turn.
+    ///
+    /// Required components of existing components are already flattened, so
+    /// only one level needs to be visited.
+    fn flatten_required(
+        &self,
+        required: &[(ComponentId, DefaultFn)],
+    ) -> Box<[(ComponentId, DefaultFn)]> {
+        let mut res: Vec<(ComponentId, DefaultFn)> = Vec::with_capacity(required.len());
+
+        for &(id, default) in required {
+            let Some(info) = self.get(id) else {
+                panic!("required component with ID of {id:?} does not exist")
+            };
+
+            assert!(
+                info.singleton.is_none(),
+                "required component `{}` is a singleton",
+                info.name
+            );
+
+            for &entry in [(id, default)].iter().chain(info.required.iter()) {
+                if !res.iter().any(|&(other, _)| other == entry.0) {
+                    res.push(entry);
+                }
+            }
+        }
+
+        res.into_boxed_slice()
     }
 
     pub(crate) fn remove(&mut self, component_id: ComponentId) -> Option<ComponentInfo> {
+        self.debug_assert_same_world(component_id);
+
         let info = self.infos.remove(component_id.0)?;
 
-        if let Some(type_id) = info.type_id {
+        if let Some(key) = &info.key {
+            self.by_key.remove(key);
+            // Drop the type IDs of other registrations merged by the key.
+            self.by_type_id.retain(|_, &mut id| id != component_id);
+        } else if let Some(type_id) = info.type_id {
             self.by_type_id.remove(&type_id);
         }
 
+        self.by_name.remove(&info.name, component_id);
+
         Some(info)
     }
 
     /// Gets the [`ComponentInfo`] of the given component. Returns `None` if the
     /// ID is invalid.
     pub fn get(&self, id: ComponentId) -> Option<&ComponentInfo> {
+        self.debug_assert_same_world(id);
         self.infos.get(id.0)
     }
 
+    #[track_caller]
+    fn debug_assert_same_world(&self, id: ComponentId) {
+        debug_assert!(
+            id.1.is_compatible(self.world),
+            "component ID {id:?} belongs to a different world"
+        );
+    }
+
     /// Gets the [`ComponentInfo`] for a component using its [`ComponentIdx`].
     /// Returns `None` if the index is invalid.
     pub fn get_by_index(&self, idx: ComponentIdx) -> Option<&ComponentInfo> {
@@ -119,6 +252,55 @@ impl Components {
         Some(unsafe { self.get(id).unwrap_debug_checked() })
     }
 
+    /// Gets the [`ComponentInfo`] of the component with the given
+    /// [key](ComponentInfo::key). Returns `None` if no component has the key.
+    pub fn get_by_key(&self, key: &str) -> Option<&ComponentInfo> {
+        let id = *self.by_key.get(key)?;
+        Some(unsafe { self.get(id).unwrap_debug_checked() })
+    }
+
+    /// Gets the [`ComponentInfo`] of the component with the given
+    /// [name](ComponentInfo::name).
+    ///
+    /// Names aren't required to be unique. If several components have the
+    /// name, [`NameError::Ambiguous`] is returned and the components can be
+    /// told apart with [`iter_by_name`](Self::iter_by_name).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::component::NameError;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// #[component(name = "health")]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_component::<Health>();
+    ///
+    /// let info = world.components().get_by_name("health").unwrap();
+    /// assert_eq!(info.id(), id);
+    ///
+    /// assert_eq!(
+    ///     world.components().get_by_name("mana").unwrap_err(),
+    ///     NameError::NotFound
+    /// );
+    /// ```
+    pub fn get_by_name(&self, name: &str) -> Result<&ComponentInfo, NameError> {
+        let id = self.by_name.get(name)?;
+        Ok(unsafe { self.get(id).unwrap_debug_checked() })
+    }
+
+    /// Returns an iterator over the components with the given
+    /// [name](ComponentInfo::name), in the order they were added.
+    pub fn iter_by_name<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a ComponentInfo> {
+        self.by_name
+            .get_all(name)
+            .iter()
+            .map(|&id| unsafe { self.get(id).unwrap_debug_checked() })
+    }
+
     /// Does the given component exist in the world?
     pub fn contains(&self, id: ComponentId) -> bool {
         self.get(id).is_some()
@@ -128,6 +310,17 @@ impl Components {
     pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
         self.infos.iter().map(|(_, v)| v)
     }
+
+    /// Keeps the indices of the current components from being reused by
+    /// components added later.
+    pub(crate) fn pin_indices(&mut self) {
+        self.infos.pin_existing();
+    }
+
+    /// Returns `true` if any component was added with a [`ComponentHook`].
+    pub(crate) fn has_hooks(&self) -> bool {
+        self.has_hooks
+    }
 }
 
 impl Index<ComponentId> for Components {
@@ -195,18 +388,35 @@ pub struct ComponentInfo {
     name: Cow<'static, str>,
     id: ComponentId,
     type_id: Option<TypeId>,
+    key: Option<Cow<'static, str>>,
     layout: Layout,
     drop: DropFn,
+    copy: Option<CopyFn>,
+    clone: Option<CloneFn>,
+    default_capacity: usize,
+    is_chunked: bool,
+    preserve_order: bool,
     is_immutable: bool,
+    is_local: bool,
+    is_pod: bool,
+    singleton: Option<SingletonPolicy>,
+    /// The entity with the component, if it's a singleton.
+    pub(crate) singleton_holder: Option<EntityId>,
+    required: Box<[(ComponentId, DefaultFn)]>,
+    on_insert: Option<ComponentHook>,
+    on_remove: Option<ComponentHook>,
+    validate: Option<ValidateFn>,
     pub(crate) insert_events: BTreeSet<EventId>,
     pub(crate) remove_events: BTreeSet<EventId>,
+    pub(crate) removed_event: Option<EventId>,
+    pub(crate) replaced_event: Option<EventId>,
 }
 
 impl ComponentInfo {
     /// Gets the name of the component.
     ///
-    /// This name is intended for debugging purposes and should not be relied
-    /// upon for correctness.
+    /// Components can be looked up by name with [`Components::get_by_name`].
+    /// Names aren't required to be unique.
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -222,6 +432,12 @@ impl ComponentInfo {
         self.type_id
     }
 
+    /// Gets the stable key of the component, or `None` if it doesn't have
+    /// one. See [`ComponentDescriptor::key`].
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
     /// Gets the [`Layout`] of the component.
     pub fn layout(&self) -> Layout {
         self.layout
@@ -232,6 +448,37 @@ impl ComponentInfo {
         self.drop
     }
 
+    /// Gets the [`CopyFn`] used to move the component between archetypes.
+    /// This is `None` if the component has a [`DropFn`] or a layout without
+    /// a specialized copy, in which case a copy with a runtime size is used.
+    pub fn copy_fn(&self) -> Option<CopyFn> {
+        self.copy
+    }
+
+    /// Gets the [`CloneFn`] used by [`World::clone_entity`], if the component
+    /// can be cloned.
+    pub fn clone_fn(&self) -> Option<CloneFn> {
+        self.clone
+    }
+
+    /// Gets the number of entities that new archetypes containing this
+    /// component have room for. See [`ComponentDescriptor::default_capacity`].
+    pub fn default_capacity(&self) -> usize {
+        self.default_capacity
+    }
+
+    /// Gets whether the component is stored in chunks. See
+    /// [`Component::IS_CHUNKED`].
+    pub fn is_chunked(&self) -> bool {
+        self.is_chunked
+    }
+
+    /// Gets whether archetypes containing the component keep their entities
+    /// in order. See [`Component::PRESERVE_ORDER`].
+    pub fn preserves_order(&self) -> bool {
+        self.preserve_order
+    }
+
     /// Gets the [immutability] of the component.
     ///
     /// [immutability]: Component::IS_IMMUTABLE
@@ -239,6 +486,60 @@ impl ComponentInfo {
         self.is_immutable
     }
 
+    /// Gets the [thread locality] of the component.
+    ///
+    /// [thread locality]: Component::IS_LOCAL
+    pub fn is_local(&self) -> bool {
+        self.is_local
+    }
+
+    /// Returns `true` if the component can be copied byte for byte, which is
+    /// required by [`World::copy_columns`]. See
+    /// [`ComponentDescriptor::is_pod`].
+    pub fn is_pod(&self) -> bool {
+        self.is_pod
+    }
+
+    /// Gets the [`SingletonPolicy`] of the component, or `None` if it isn't a
+    /// singleton. See [`Component::SINGLETON`].
+    pub fn singleton(&self) -> Option<SingletonPolicy> {
+        self.singleton
+    }
+
+    /// Gets the entity which has the component if it's a singleton, or `None`
+    /// if no entity has it or it isn't a singleton.
+    pub fn singleton_holder(&self) -> Option<EntityId> {
+        self.singleton_holder
+    }
+
+    /// Gets the components required by this component, along with the
+    /// functions used to construct their default values.
+    ///
+    /// This includes the requirements of the required components, so the
+    /// list is closed under the "requires" relation. See
+    /// [`ComponentDescriptor::required`] for more information.
+    pub fn required(&self) -> &[(ComponentId, DefaultFn)] {
+        &self.required
+    }
+
+    /// Gets the hook called after the component is added to an entity. See
+    /// [`ComponentDescriptor::on_insert`].
+    pub fn on_insert(&self) -> Option<ComponentHook> {
+        self.on_insert
+    }
+
+    /// Gets the hook called before the component is removed from an entity.
+    /// See [`ComponentDescriptor::on_remove`].
+    pub fn on_remove(&self) -> Option<ComponentHook> {
+        self.on_remove
+    }
+
+    /// Gets the function which checks the component for corruption. See
+    /// [`ComponentDescriptor::validate`].
+    pub fn validate(&self) -> Option<ValidateFn> {
+        self.validate
+    }
+
     /// Gets the set of [`Insert`] events for this component.
     ///
     /// [`Insert`]: crate::event::Insert
@@ -252,6 +553,22 @@ impl ComponentInfo {
     pub fn remove_events(&self) -> &BTreeSet<EventId> {
         &self.remove_events
     }
+
+    /// Gets the [`Removed`] event for this component, if any. This is the
+    /// event sent after the component is removed from an entity.
+    ///
+    /// [`Removed`]: crate::event::Removed
+    pub fn removed_event(&self) -> Option<EventId> {
+        self.removed_event
+    }
+
+    /// Gets the [`Replaced`] event for this component, if any. This is the
+    /// event which receives the old value when the component is replaced.
+    ///
+    /// [`Replaced`]: crate::event::Replaced
+    pub fn replaced_event(&self) -> Option<EventId> {
+        self.replaced_event
+    }
 }
 
 /// Types which store data on [entities].
@@ -274,6 +591,7 @@ impl ComponentInfo {
 /// Sync + 'static` bound to do so.
 ///
 /// ```
+/// use evenio::component::HookContext;
 /// use evenio::prelude::*;
 ///
 /// // Component with some data.
@@ -304,6 +622,41 @@ impl ComponentInfo {
 /// #[derive(Component)]
 /// #[component(immutable)] // Override the default mutability.
 /// struct FooCounter(i32);
+///
+/// // Components can require other components. Whenever `Transform` is
+/// // inserted on an entity without a `GlobalTransform`, a default
+/// // `GlobalTransform` is inserted along with it.
+/// #[derive(Component)]
+/// #[component(requires(GlobalTransform))]
+/// struct Transform([f32; 3]);
+///
+/// #[derive(Component, Default)]
+/// struct GlobalTransform([f32; 3]);
+///
+/// // Components can have hooks which run when they're added to or removed
+/// // from an entity. See `Component::on_insert`.
+/// #[derive(Component)]
+/// #[component(on_insert = spawned, on_remove = despawned)]
+/// struct Collider(f32);
+///
+/// fn spawned(_: &mut HookContext, entity: EntityId, c: &Collider) {
+///     println!("{entity:?} has a collider of radius {}", c.0);
+/// }
+///
+/// fn despawned(_: &mut HookContext, entity: EntityId, _: &Collider) {
+///     println!("{entity:?} lost its collider");
+/// }
+///
+/// // Singleton components are on at most one entity at a time. Inserting
+/// // `ActiveCamera` removes it from the entity which had it before.
+/// #[derive(Component)]
+/// #[component(singleton)]
+/// struct ActiveCamera;
+///
+/// // Generic components are supported too. Each instantiation is a separate
+/// // component, so `Handle<A>` and `Handle<B>` are stored in different columns.
+/// #[derive(Component)]
+/// struct Handle<T: Send + Sync + 'static>(std::sync::Arc<T>);
 /// ```
 pub trait Component: Send + Sync + 'static {
     /// Whether or not this component is immutable.
@@ -311,139 +664,1704 @@ pub trait Component: Send + Sync + 'static {
     /// Immutable components disallow mutable references, which can be used to
     /// ensure components are used in particular ways.
     const IS_IMMUTABLE: bool = false;
-}
 
-/// Data needed to create a new component.
-#[derive(Clone, Debug)]
-pub struct ComponentDescriptor {
-    /// The name of this component.
+    /// Whether or not this component is stored in chunks.
     ///
-    /// This name is intended for debugging purposes and should not be relied
-    /// upon for correctness.
-    pub name: Cow<'static, str>,
-    /// The [`TypeId`] of this component, if any.
-    pub type_id: Option<TypeId>,
-    /// The [`Layout`] of the component.
-    pub layout: Layout,
-    /// The [`DropFn`] of the component. This is passed a pointer to the
-    /// component in order to drop it.
-    pub drop: DropFn,
-    /// If this component is [immutable](Component::IS_IMMUTABLE).
-    pub is_immutable: bool,
-}
+    /// Columns normally keep their components in a single buffer which is
+    /// reallocated as the column grows, moving every component in it. Chunked
+    /// columns allocate separate chunks of
+    /// [`CHUNK_LEN`](crate::archetype::CHUNK_LEN) components instead, so a
+    /// component only moves when its entity changes archetype or another
+    /// entity in the archetype is removed. This is useful for components
+    /// whose addresses are held outside the world for a long time.
+    ///
+    /// Accessing a chunked component is slightly slower. This can be set with
+    /// `#[component(chunked)]` when deriving.
+    const IS_CHUNKED: bool = false;
 
-/// Lightweight identifier for a component type.
-///
-/// component identifiers are implemented using an [index] and a generation
-/// count. The generation count ensures that IDs from removed components are
-/// not reused by new components.
-///
-/// A component identifier is only meaningful in the [`World`] it was created
-/// from. Attempting to use a component ID in a different world will have
-/// unexpected results.
-///
-/// [index]: ComponentIdx
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
-pub struct ComponentId(Key);
+    /// Whether or not archetypes containing this component keep their
+    /// entities in the order they were added.
+    ///
+    /// Removing an entity from an archetype normally moves the archetype's
+    /// last entity into the gap, which is O(1) but changes the order entities
+    /// are iterated in. If any component of an archetype preserves order,
+    /// the entities after the removed one are shifted down instead, so
+    /// iteration follows the order entities entered the archetype. Removal
+    /// is then O(n) in the number of entities after the removed one, since
+    /// every component of each of those entities is moved.
+    ///
+    /// This can be set with `#[component(preserve_order)]` when deriving.
+    const PRESERVE_ORDER: bool = false;
 
-impl ComponentId {
-    /// The component ID which never identifies a live component. This is the
-    /// default value for `ComponentId`.
-    pub const NULL: Self = Self(Key::NULL);
+    /// Whether or not this component is local to the thread that added it.
+    ///
+    /// Adding a local component to a [`World`] ties the world to the current
+    /// thread. From then on, the world panics when events are sent or
+    /// components are accessed from any other thread. See
+    /// [`World::owner_thread`].
+    ///
+    /// This is set by [`Unsend`], which is how values that aren't `Send` or
+    /// `Sync` are stored in a world.
+    const IS_LOCAL: bool = false;
 
-    /// Creates a new component ID from an index and generation count. Returns
-    /// `None` if a valid ID is not formed.
-    pub const fn new(index: u32, generation: u32) -> Option<Self> {
-        match Key::new(index, generation) {
-            Some(k) => Some(Self(k)),
-            None => None,
-        }
+    /// Whether this component is on at most one entity at a time, and what
+    /// happens when it's inserted on a second entity. See [`SingletonPolicy`].
+    ///
+    /// The entity which has a singleton component can be looked up with
+    /// [`World::singleton`]. This can be set with `#[component(singleton)]`
+    /// when deriving, which is the same as `#[component(singleton =
+    /// "replace")]`, or with `#[component(singleton = "reject")]`.
+    const SINGLETON: Option<SingletonPolicy> = None;
+
+    /// A key identifying this component across compilation units, or `None`
+    /// to only identify it by its [`TypeId`].
+    ///
+    /// Type IDs differ between the copies of a type compiled into separate
+    /// libraries, such as plugins loaded at runtime. Adding a component
+    /// whose type ID is unknown to the world but whose key matches an
+    /// existing component returns the existing component instead of adding
+    /// a duplicate. Adding a component with the key of an existing component
+    /// with a different [`Layout`] fails with
+    /// [`AddComponentError::LayoutMismatch`].
+    ///
+    /// Every type sharing a key is treated as the same component, so the key
+    /// must be unique to the type. It can be set with
+    /// `#[component(uuid = "...")]` when deriving, or to the path of the type
+    /// with `#[component(key)]`, which isn't available for generic types.
+    ///
+    /// Keyed components can be looked up with [`World::component_id_by_key`].
+    const KEY: Option<&'static str> = None;
+
+    /// Returns the name of this component, which is stored in
+    /// [`ComponentInfo::name`] and can be looked up with
+    /// [`Components::get_by_name`].
+    ///
+    /// This is the [type name](any::type_name) of the component by default.
+    /// It can be overridden with `#[component(name = "...")]` when deriving.
+    fn name() -> &'static str {
+        any::type_name::<Self>()
     }
 
-    /// Returns the index of this ID.
-    pub const fn index(self) -> ComponentIdx {
-        ComponentIdx(self.0.index())
+    /// Registers the components required by this component.
+    ///
+    /// This is called once when the component is added to the world. See
+    /// [`ComponentDescriptor::required`] for more information.
+    fn required(required: &mut RequiredComponents) {
+        let _ = required;
     }
 
-    /// Returns the generation count of this ID.
-    pub const fn generation(self) -> u32 {
-        self.0.generation().get()
+    /// Returns the [`CloneFn`] used to duplicate this component in
+    /// [`World::clone_entity`], or `None` if the component can't be cloned.
+    ///
+    /// The derive macro returns [`clone_fn_of`] for components which implement
+    /// [`Clone`]. Generic components are only detected as cloneable if the
+    /// type is `Clone` for every choice of type parameters, since the check
+    /// happens where the component is defined.
+    fn clone_fn() -> Option<CloneFn> {
+        None
+    }
+
+    /// Returns `true` if this component is plain data which can be copied out
+    /// of the world with [`World::copy_columns`]. This must only be `true` for
+    /// types implementing [`Copy`].
+    ///
+    /// The derive macro returns `true` for components which implement
+    /// [`Copy`], with the same caveat about generic components as
+    /// [`clone_fn`](Self::clone_fn).
+    fn is_pod() -> bool {
+        false
+    }
+
+    /// Returns the [`ComponentHook`] called after this component is added to
+    /// an entity. See [`ComponentDescriptor::on_insert`].
+    ///
+    /// The derive macro returns a hook calling the function given with
+    /// `#[component(on_insert = path)]`. The function is passed the
+    /// [`HookContext`], the entity, and a reference to the component.
+    fn on_insert() -> Option<ComponentHook> {
+        None
+    }
+
+    /// Returns the [`ComponentHook`] called before this component is removed
+    /// from an entity. See [`ComponentDescriptor::on_remove`].
+    ///
+    /// This can be set with `#[component(on_remove = path)]` when deriving,
+    /// like [`on_insert`](Component::on_insert).
+    fn on_remove() -> Option<ComponentHook> {
+        None
+    }
+
+    /// Returns the [`ValidateFn`] which checks this component for corruption.
+    /// See [`ComponentDescriptor::validate`].
+    ///
+    /// The derive macro returns a function calling the one given with
+    /// `#[component(validate = path)]`, which is passed a reference to the
+    /// component and returns a `Result<(), String>`.
+    fn validate() -> Option<ValidateFn> {
+        None
     }
 }
 
-/// A [`ComponentId`] with the generation count stripped out.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
-pub struct ComponentIdx(pub u32);
+/// Builder for the list of components required by a [`Component`]. Passed to
+/// [`Component::required`].
+#[derive(Debug)]
+pub struct RequiredComponents<'a> {
+    world: &'a mut World,
+    required: Vec<(ComponentId, DefaultFn)>,
+    /// The first error from adding a required component.
+    error: Option<AddComponentError>,
+}
 
-unsafe impl SparseIndex for ComponentIdx {
-    const MAX: Self = Self(u32::MAX);
+impl<'a> RequiredComponents<'a> {
+    /// Marks the component with the given type ID as pending until the
+    /// builder is dropped, even if [`Component::required`] panics.
+    pub(crate) fn new(world: &'a mut World, type_id: TypeId) -> Self {
+        world.components_mut().pending.push(type_id);
 
-    fn index(self) -> usize {
-        self.0.index()
+        Self {
+            world,
+            required: Vec::new(),
+            error: None,
+        }
     }
 
-    fn from_index(idx: usize) -> Self {
-        Self(u32::from_index(idx))
+    /// Adds `C` as a required component. The component is added to the world
+    /// if it doesn't exist already.
+    ///
+    /// If `C` can't be added, such as when the requirements of `C` form a
+    /// cycle, the error is returned when the component requiring `C` is
+    /// added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `C` is a [singleton](Component::SINGLETON).
+    pub fn add<C: Component + Default>(&mut self) -> &mut Self {
+        if self.error.is_some() {
+            return self;
+        }
+
+        match self.world.try_add_component::<C>() {
+            Ok(id) => {
+                if !self.required.iter().any(|&(other, _)| other == id) {
+                    self.required.push((id, default_fn_of::<C>()));
+                }
+            }
+            Err(e) => self.error = Some(e),
+        }
+
+        self
+    }
+
+    pub(crate) fn into_inner(mut self) -> Result<Vec<(ComponentId, DefaultFn)>, AddComponentError> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(mem::take(&mut self.required)),
+        }
     }
 }
 
-/// An event sent immediately after a new component is added to the world.
-/// Contains the ID of the added component.
-#[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-pub struct AddComponent(pub ComponentId);
+impl Drop for RequiredComponents<'_> {
+    fn drop(&mut self) {
+        self.world.components_mut().pending.pop();
+    }
+}
 
-/// An event sent immediately before a component is removed from the world.
-/// Contains the ID of the component to be removed.
-#[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-pub struct RemoveComponent(pub ComponentId);
+/// A function which writes the default value of a component to the given
+/// pointer.
+///
+/// The pointer is properly aligned for the component and is not expected to
+/// be initialized.
+pub type DefaultFn = unsafe fn(NonNull<u8>);
 
-#[cfg(test)]
-mod tests {
-    use crate::prelude::*;
+/// Returns the [`DefaultFn`] for the type `C`.
+pub fn default_fn_of<C: Default>() -> DefaultFn {
+    |ptr| unsafe { ptr.as_ptr().cast::<C>().write(C::default()) }
+}
 
-    #[derive(Event)]
-    struct E;
+/// A function which copies a component from the first pointer to the second.
+///
+/// Both pointers are properly aligned for the component and don't overlap.
+/// The destination is not expected to be initialized. The copy is bitwise, so
+/// afterwards the source should be considered moved-from.
+pub type CopyFn = unsafe fn(*const u8, *mut u8);
 
-    #[test]
-    fn remove_component() {
-        #[derive(Component)]
-        struct A(String);
+/// Returns the [`CopyFn`] for the type `C`.
+pub const fn copy_fn_of<C>() -> CopyFn {
+    |src, dst| unsafe { ptr::copy_nonoverlapping(src.cast::<C>(), dst.cast::<C>(), 1) }
+}
 
-        #[derive(Component, PartialEq, Debug)]
-        struct B(Vec<String>);
+/// A function which clones a component from the first pointer into the
+/// second.
+///
+/// Both pointers are properly aligned for the component and don't overlap.
+/// The destination is not expected to be initialized, and the source is left
+/// untouched.
+pub type CloneFn = unsafe fn(*const u8, *mut u8);
 
-        let mut world = World::new();
+/// Returns the [`CloneFn`] for the type `C`.
+pub const fn clone_fn_of<C: Clone>() -> CloneFn {
+    |src, dst| unsafe { dst.cast::<C>().write((*src.cast::<C>()).clone()) }
+}
 
-        let c1 = world.add_component::<A>();
-        let e1 = world.spawn();
-        world.insert(e1, A("hello".into()));
-        let s1 = world.add_system(|_: Receiver<E>, Single(A(a)): Single<&mut A>| {
-            a.push_str("hello");
-        });
-        world.send(E);
+/// What happens when a [singleton](Component::SINGLETON) component is
+/// inserted on an entity while another entity has it.
+///
+/// The policy is applied when an [`Insert`] event is broadcast. Singleton
+/// components can't be [required](Component::required) by other components,
+/// and can't be inserted with [`World::insert_for_all`].
+/// [`World::clone_entity`] treats them as if they couldn't be cloned, unless
+/// they're inserted with [`CloneOptions::send_insert_events`].
+///
+/// [`Insert`]: crate::event::Insert
+/// [`CloneOptions::send_insert_events`]: crate::entity::CloneOptions::send_insert_events
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SingletonPolicy {
+    /// The component is removed from the entity which has it before it's
+    /// inserted on the new one. The removal sends the component's [`Remove`]
+    /// event like any other, and if that doesn't remove the component,
+    /// because a listener consumed the event, the insert is dropped.
+    ///
+    /// [`Remove`]: crate::event::Remove
+    Replace,
+    /// The insert is dropped, and the entity which has the component keeps
+    /// it.
+    Reject,
+}
 
-        assert!(world.remove_component(c1).is_some());
-        assert!(!world.systems().contains(s1));
-        assert!(!world.entities().contains(e1));
-        assert_eq!(
-            world.archetypes().len(),
-            1,
-            "only the empty archetype should be present"
-        );
+/// A function called when a component is added to or removed from an entity.
+///
+/// The hook is passed the entity and a pointer to the component, which is
+/// properly aligned and initialized. The component must not be moved out of.
+pub type ComponentHook = unsafe fn(&mut HookContext, EntityId, NonNull<u8>);
 
-        let c2 = world.add_component::<B>();
-        let e2 = world.spawn();
-        assert!(world.entities().contains(e2));
-        world.insert(e2, B(vec![]));
-        let s2 = world.add_system(|_: Receiver<E>, Single(B(b)): Single<&mut B>| {
-            b.push("hello".into());
-        });
-        world.send(E);
-        assert_eq!(world.get_component::<B>(e2), Some(&B(vec!["hello".into()])));
+/// A function which checks a component for corrupted data, returning a
+/// description of the problem if it finds one. See
+/// [`ComponentDescriptor::validate`].
+///
+/// The function is passed a pointer to the component, which is properly
+/// aligned and initialized.
+pub type ValidateFn = unsafe fn(NonNull<u8>) -> Result<(), String>;
 
-        assert!(world.remove_component(c2).is_some());
-        assert!(!world.systems().contains(s2));
+/// A component which failed validation in [`World::validate_all`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ValidationFailure {
+    /// The entity the component belongs to.
+    pub entity: EntityId,
+    /// The component which failed validation.
+    pub component: ComponentId,
+    /// The message returned by the component's [`ValidateFn`].
+    pub message: String,
+}
+
+impl fmt::Display for ValidationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "component {:?} of {:?} is invalid: {}",
+            self.component, self.entity, self.message
+        )
+    }
+}
+
+/// Access to the world from a [`ComponentHook`].
+///
+/// Hooks run in the middle of structural changes, so they can read the world
+/// but can't change it directly. Events sent through the context are queued
+/// and broadcast once the change that ran the hook has finished. This includes
+/// [`Insert`], [`Remove`], and [`Despawn`] events, so changes to the entity
+/// are never applied while it's being moved.
+///
+/// [`Insert`]: crate::event::Insert
+/// [`Remove`]: crate::event::Remove
+/// [`Despawn`]: crate::event::Despawn
+#[derive(Debug)]
+pub struct HookContext<'a> {
+    world: &'a mut World,
+}
+
+impl<'a> HookContext<'a> {
+    pub(crate) fn new(world: &'a mut World) -> Self {
+        Self { world }
+    }
+
+    /// Returns the world the hook is running in.
+    pub fn world(&self) -> &World {
+        self.world
+    }
+
+    /// Returns the world mutably, for bookkeeping which doesn't touch entities,
+    /// components, or the event queue.
+    #[cfg(feature = "name")]
+    pub(crate) fn world_mut(&mut self) -> &mut World {
+        self.world
+    }
+
+    /// Queues an event to be sent after the hook returns.
+    pub fn send<E: Event>(&mut self, event: E) {
+        self.world.queue_event(event);
+    }
+
+    /// Queues an [`Insert`] event.
+    ///
+    /// [`Insert`]: crate::event::Insert
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.send(Insert::new(entity, component))
+    }
+
+    /// Queues a [`Remove`] event.
+    ///
+    /// [`Remove`]: crate::event::Remove
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.send(Remove::<C>::new(entity))
+    }
+
+    /// Queues a [`Despawn`] event.
+    ///
+    /// [`Despawn`]: crate::event::Despawn
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.send(Despawn(entity))
+    }
+}
+
+/// Returns a [`CopyFn`] with a fixed size for common component sizes.
+fn copy_fn_for_layout(layout: Layout) -> Option<CopyFn> {
+    unsafe fn copy_bytes<const N: usize>(src: *const u8, dst: *mut u8) {
+        unsafe { ptr::copy_nonoverlapping(src, dst, N) };
+    }
+
+    match layout.size() {
+        4 => Some(copy_bytes::<4>),
+        8 => Some(copy_bytes::<8>),
+        16 => Some(copy_bytes::<16>),
+        32 => Some(copy_bytes::<32>),
+        _ => None,
+    }
+}
+
+/// A component wrapper for values which are not [`Send`] or [`Sync`], such as
+/// `Rc<RefCell<T>>` handles or raw graphics resources.
+///
+/// `Unsend<T>` remembers the thread it was created on. Accessing or dropping
+/// the inner value from any other thread panics, which makes it safe to send
+/// the wrapper itself between threads. Inserting an `Unsend` component into a
+/// [`World`] also ties the world to that thread (see [`Component::IS_LOCAL`]).
+///
+/// `Unsend<T>` can be accessed through a [`Fetcher`] like any other
+/// component. Because the world can only be used on its owner thread, the
+/// checks in `Deref` never fail for values inside the world.
+///
+/// [`Fetcher`]: crate::fetch::Fetcher
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use evenio::component::Unsend;
+/// use evenio::prelude::*;
+///
+/// # #[derive(Event)] struct E;
+/// let mut world = World::new();
+///
+/// let shared = Rc::new(RefCell::new(0));
+///
+/// let e = world.spawn();
+/// world.insert(e, Unsend::new(shared.clone()));
+///
+/// world.add_system(|_: Receiver<E>, f: Fetcher<&Unsend<Rc<RefCell<i32>>>>| {
+///     for counter in f {
+///         *counter.borrow_mut() += 1;
+///     }
+/// });
+///
+/// world.send(E);
+///
+/// assert_eq!(*shared.borrow(), 1);
+/// ```
+#[cfg(feature = "std")]
+pub struct Unsend<T: 'static> {
+    value: ManuallyDrop<T>,
+    thread: ThreadId,
+}
+
+#[cfg(feature = "std")]
+impl<T: 'static> Unsend<T> {
+    /// Wraps `value`, tying it to the current thread.
+    pub fn new(value: T) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+            thread: thread::current().id(),
+        }
+    }
+
+    /// Returns the ID of the thread this value belongs to.
+    pub fn thread(&self) -> ThreadId {
+        self.thread
+    }
+
+    /// Returns `true` if the current thread is the one this value belongs to.
+    pub fn is_owner_thread(&self) -> bool {
+        thread::current().id() == self.thread
+    }
+
+    /// Returns a reference to the inner value, or `None` if called from a
+    /// thread other than the owner.
+    pub fn try_get(&self) -> Option<&T> {
+        self.is_owner_thread().then(|| &*self.value)
+    }
+
+    /// Returns a mutable reference to the inner value, or `None` if called
+    /// from a thread other than the owner.
+    pub fn try_get_mut(&mut self) -> Option<&mut T> {
+        self.is_owner_thread().then(|| &mut *self.value)
+    }
+
+    /// Unwraps the inner value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a thread other than the owner.
+    #[track_caller]
+    pub fn into_inner(self) -> T {
+        self.assert_owner_thread();
+
+        let mut this = ManuallyDrop::new(self);
+        unsafe { ManuallyDrop::take(&mut this.value) }
+    }
+
+    #[track_caller]
+    fn assert_owner_thread(&self) {
+        assert!(
+            self.is_owner_thread(),
+            "`Unsend<{}>` accessed from a thread other than the one it was created on",
+            core::any::type_name::<T>()
+        );
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: 'static> Deref for Unsend<T> {
+    type Target = T;
+
+    /// Panics if called from a thread other than the owner.
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        self.assert_owner_thread();
+        &self.value
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: 'static> DerefMut for Unsend<T> {
+    /// Panics if called from a thread other than the owner.
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.assert_owner_thread();
+        &mut self.value
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: 'static> Drop for Unsend<T> {
+    fn drop(&mut self) {
+        if self.is_owner_thread() {
+            unsafe { ManuallyDrop::drop(&mut self.value) };
+        } else if !thread::panicking() {
+            panic!(
+                "`Unsend<{}>` dropped on a thread other than the one it was created on",
+                core::any::type_name::<T>()
+            );
+        }
+        // Otherwise the value is leaked to avoid a double panic.
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug + 'static> fmt::Debug for Unsend<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Unsend");
+
+        match self.try_get() {
+            Some(value) => s.field("value", value),
+            None => s.field("value", &format_args!("<other thread>")),
+        };
+
+        s.field("thread", &self.thread).finish()
+    }
+}
+
+// SAFETY: The inner value is only accessed or dropped on the thread it was
+// created on.
+#[cfg(feature = "std")]
+unsafe impl<T: 'static> Send for Unsend<T> {}
+#[cfg(feature = "std")]
+unsafe impl<T: 'static> Sync for Unsend<T> {}
+
+#[cfg(feature = "std")]
+impl<T: 'static> Component for Unsend<T> {
+    const IS_LOCAL: bool = true;
+}
+
+/// Data needed to create a new component.
+#[derive(Clone, Debug)]
+pub struct ComponentDescriptor {
+    /// The name of this component.
+    ///
+    /// Components can be looked up by name with [`Components::get_by_name`].
+    /// Names aren't required to be unique.
+    pub name: Cow<'static, str>,
+    /// The [`TypeId`] of this component, if any.
+    pub type_id: Option<TypeId>,
+    /// A key identifying the component independently of its [`TypeId`]. See
+    /// [`Component::KEY`].
+    ///
+    /// If no component has the type ID, then a component with the same key is
+    /// returned instead, as long as the layouts match. The type ID is then
+    /// mapped to that component too.
+    pub key: Option<Cow<'static, str>>,
+    /// The [`Layout`] of the component.
+    pub layout: Layout,
+    /// The [`DropFn`] of the component. This is passed a pointer to the
+    /// component in order to drop it.
+    pub drop: DropFn,
+    /// A [`CopyFn`] specialized for the component, used to move it between
+    /// archetypes. If this is `None`, a fixed-size copy is chosen for common
+    /// component sizes. Components with a [`DropFn`] always use a copy with a
+    /// runtime size.
+    pub copy_fn: Option<CopyFn>,
+    /// The [`CloneFn`] of the component, used by [`World::clone_entity`].
+    /// Entities with components that don't have one can't be cloned.
+    pub clone_fn: Option<CloneFn>,
+    /// The minimum number of entities that archetypes containing this
+    /// component have room for when they're created. Every column of an
+    /// archetype has the same capacity, so this applies to the other
+    /// components in the archetype too.
+    pub default_capacity: usize,
+    /// If columns of this component are [chunked](Component::IS_CHUNKED).
+    ///
+    /// For components with a [`TypeId`], this must match
+    /// [`Component::IS_CHUNKED`] of the type.
+    pub is_chunked: bool,
+    /// If archetypes containing this component keep their entities in the
+    /// order they were added. See [`Component::PRESERVE_ORDER`].
+    pub preserve_order: bool,
+    /// If this component is [immutable](Component::IS_IMMUTABLE).
+    pub is_immutable: bool,
+    /// If this component is [local](Component::IS_LOCAL) to the thread that
+    /// adds it.
+    pub is_local: bool,
+    /// The [`SingletonPolicy`] of this component, or `None` if it isn't a
+    /// [singleton](Component::SINGLETON).
+    pub singleton: Option<SingletonPolicy>,
+    /// If the component can be copied byte for byte and the copy used in
+    /// place of the original, like a type implementing [`Copy`]. Only these
+    /// components can be copied out with [`World::copy_columns`].
+    ///
+    /// This is ignored for components with a [`DropFn`].
+    pub is_pod: bool,
+    /// Components which are default-constructed and inserted alongside this
+    /// component if they're missing from the entity.
+    ///
+    /// Required components are inserted in the same archetype move as this
+    /// component, and no [`Insert`] events are sent for them. The
+    /// requirements of each required component are included transitively.
+    ///
+    /// Every ID must refer to an existing component which isn't a
+    /// [singleton](Component::SINGLETON). Because the new component doesn't
+    /// have an ID yet, it can't be part of a cycle this way.
+    ///
+    /// [`Insert`]: crate::event::Insert
+    pub required: Vec<(ComponentId, DefaultFn)>,
+    /// The hook called after this component is added to an entity, once the
+    /// entity has moved to its new archetype.
+    ///
+    /// This runs for every way the component can be added, including
+    /// required components, [`World::clone_entity`], and
+    /// [`World::insert_for_all`]. Replacing the component runs the
+    /// [`on_remove`] hook for the old value followed by this hook for the new
+    /// value.
+    ///
+    /// [`on_remove`]: ComponentDescriptor::on_remove
+    pub on_insert: Option<ComponentHook>,
+    /// The hook called before this component is removed from an entity,
+    /// while it's still attached.
+    ///
+    /// Despawning an entity runs this once for each of its components. Hooks
+    /// don't run when the world is dropped, or for entities which still have
+    /// the component when it's removed with [`World::remove_component`].
+    pub on_remove: Option<ComponentHook>,
+    /// A function checking the component's data, which
+    /// [`World::validate_all`] calls for every instance of the component.
+    /// This is intended for catching corruption from unsafe code close to
+    /// where it happens.
+    ///
+    /// With the `validate-components` feature, every component is validated
+    /// after the world finishes broadcasting the events sent from outside of
+    /// any system, and the world panics if any component is invalid.
+    pub validate: Option<ValidateFn>,
+}
+
+/// Lightweight identifier for a component type.
+///
+/// component identifiers are implemented using an [index] and a generation
+/// count. The generation count ensures that IDs from removed components are
+/// not reused by new components.
+///
+/// A component identifier is only meaningful in the [`World`] it was created
+/// from. Attempting to use a component ID in a different world will have
+/// unexpected results.
+///
+/// [index]: ComponentIdx
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct ComponentId(Key, WorldId);
+
+impl ComponentId {
+    /// The component ID which never identifies a live component. This is the
+    /// default value for `ComponentId`.
+    pub const NULL: Self = Self(Key::NULL, WorldId::NONE);
+
+    /// Creates a new component ID from an index and generation count. Returns
+    /// `None` if a valid ID is not formed.
+    ///
+    /// The ID is not associated with any world. See [`WorldId`].
+    pub const fn new(index: u32, generation: u32) -> Option<Self> {
+        match Key::new(index, generation) {
+            Some(k) => Some(Self(k, WorldId::NONE)),
+            None => None,
+        }
+    }
+
+    /// Returns the world this ID was created by, or [`WorldId::NONE`] if it
+    /// was created manually.
+    #[cfg(feature = "validate-ids")]
+    pub const fn world(self) -> WorldId {
+        self.1
+    }
+
+    /// Returns the index of this ID.
+    pub const fn index(self) -> ComponentIdx {
+        ComponentIdx(self.0.index())
+    }
+
+    /// Returns the generation count of this ID.
+    pub const fn generation(self) -> u32 {
+        self.0.generation().get()
+    }
+}
+
+/// A [`ComponentId`] with the generation count stripped out.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct ComponentIdx(pub u32);
+
+unsafe impl SparseIndex for ComponentIdx {
+    const MAX: Self = Self(u32::MAX);
+
+    fn index(self) -> usize {
+        self.0.index()
+    }
+
+    fn from_index(idx: usize) -> Self {
+        Self(u32::from_index(idx))
+    }
+}
+
+/// An error returned when a component can't be added because the world has no
+/// room for more components.
+///
+/// This is part of the [`AddComponentError`] returned by
+/// [`World::try_add_component`] and
+/// [`World::try_add_component_with_descriptor`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LimitError {
+    name: Cow<'static, str>,
+    count: usize,
+}
+
+impl LimitError {
+    /// Returns the name of the component that couldn't be added.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of components in the world when the error occurred.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot add component `{}` because the world already contains the maximum of {} \
+             components",
+            self.name, self.count
+        )
+    }
+}
+
+impl core::error::Error for LimitError {}
+
+/// An error returned when a component couldn't be added to a [`World`].
+///
+/// This is returned by [`World::try_add_component`] and
+/// [`World::try_add_component_with_descriptor`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AddComponentError {
+    /// The world has no room for more components.
+    Limit(LimitError),
+    /// A component with the same [key](ComponentDescriptor::key) exists, but
+    /// has a different layout.
+    LayoutMismatch {
+        /// The shared key.
+        key: Cow<'static, str>,
+        /// The existing component with the key.
+        existing: ComponentId,
+        /// The layout of the existing component.
+        existing_layout: Layout,
+        /// The layout of the component that couldn't be added.
+        layout: Layout,
+    },
+    /// The [required components](Component::required) of a component form a
+    /// cycle.
+    RequirementCycle {
+        /// The name of the component which requires itself.
+        name: &'static str,
+    },
+}
+
+impl From<LimitError> for AddComponentError {
+    fn from(value: LimitError) -> Self {
+        Self::Limit(value)
+    }
+}
+
+impl fmt::Display for AddComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddComponentError::Limit(e) => e.fmt(f),
+            AddComponentError::LayoutMismatch {
+                key,
+                existing_layout,
+                layout,
+                ..
+            } => write!(
+                f,
+                "cannot add component with key `{key}` because its layout (size {}, align {}) \
+                 differs from the existing component with the key (size {}, align {})",
+                layout.size(),
+                layout.align(),
+                existing_layout.size(),
+                existing_layout.align()
+            ),
+            AddComponentError::RequirementCycle { name } => {
+                write!(f, "cycle detected in the required components of `{name}`")
+            }
+        }
+    }
+}
+
+impl core::error::Error for AddComponentError {}
+
+/// An error returned when looking up a component or event by name with
+/// [`Components::get_by_name`] or [`Events::get_by_name`].
+///
+/// [`Events::get_by_name`]: crate::event::Events::get_by_name
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NameError {
+    /// Nothing has the name.
+    NotFound,
+    /// More than one component or event has the name.
+    Ambiguous {
+        /// The number of components or events with the name.
+        count: usize,
+    },
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::NotFound => write!(f, "no component or event has the name"),
+            NameError::Ambiguous { count } => write!(f, "the name is shared by {count} items"),
+        }
+    }
+}
+
+impl core::error::Error for NameError {}
+
+/// Maps names to the IDs of the components or events with that name.
+#[derive(Debug)]
+pub(crate) struct NameIndex<Id> {
+    map: BTreeMap<Box<str>, Vec<Id>>,
+}
+
+impl<Id: Copy + PartialEq> NameIndex<Id> {
+    pub(crate) fn new() -> Self {
+        Self {
+            map: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, name: &str, id: Id) {
+        match self.map.get_mut(name) {
+            Some(ids) => ids.push(id),
+            None => {
+                self.map.insert(name.into(), vec![id]);
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, name: &str, id: Id) {
+        if let Some(ids) = self.map.get_mut(name) {
+            ids.retain(|&other| other != id);
+
+            if ids.is_empty() {
+                self.map.remove(name);
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Result<Id, NameError> {
+        match self.get_all(name) {
+            [] => Err(NameError::NotFound),
+            &[id] => Ok(id),
+            ids => Err(NameError::Ambiguous { count: ids.len() }),
+        }
+    }
+
+    pub(crate) fn get_all(&self, name: &str) -> &[Id] {
+        self.map.get(name).map_or(&[], |ids| ids.as_slice())
+    }
+}
+
+/// An event sent immediately after a new component is added to the world.
+/// Contains the ID of the added component.
+#[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct AddComponent(pub ComponentId);
+
+/// An event sent immediately before a component is removed from the world.
+/// Contains the ID of the component to be removed.
+#[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct RemoveComponent(pub ComponentId);
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use core::any;
+    use std::sync::Mutex;
+
+    use super::{AddComponentError, HookContext, RequiredComponents};
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct E;
+
+    #[test]
+    fn copy_fn_selection() {
+        use alloc::borrow::Cow;
+        use core::alloc::Layout;
+
+        use crate::component::ComponentDescriptor;
+
+        #[derive(Component)]
+        #[allow(dead_code)]
+        struct Small(u64);
+
+        #[derive(Component)]
+        #[allow(dead_code)]
+        struct NeedsDrop(String);
+
+        let mut world = World::new();
+
+        let small = world.add_component::<Small>();
+        let needs_drop = world.add_component::<NeedsDrop>();
+
+        let mut add_dynamic = |size| {
+            let desc = ComponentDescriptor {
+                name: Cow::Borrowed("dynamic"),
+                type_id: None,
+                key: None,
+                layout: Layout::from_size_align(size, 4).unwrap(),
+                drop: None,
+                copy_fn: None,
+                clone_fn: None,
+                default_capacity: 0,
+                is_chunked: false,
+                preserve_order: false,
+                is_immutable: false,
+                is_local: false,
+                singleton: None,
+                is_pod: false,
+                required: vec![],
+                on_insert: None,
+                on_remove: None,
+                validate: None,
+            };
+
+            unsafe { world.add_component_with_descriptor(desc) }
+        };
+
+        let dynamic_16 = add_dynamic(16);
+        let dynamic_12 = add_dynamic(12);
+
+        let copy_fn = |id| world.components().get(id).unwrap().copy_fn();
+
+        assert!(copy_fn(small).is_some());
+        assert!(copy_fn(needs_drop).is_none());
+        assert!(copy_fn(dynamic_16).is_some());
+        assert!(copy_fn(dynamic_12).is_none());
+    }
+
+    #[test]
+    fn generic_components() {
+        use alloc::sync::Arc;
+        use core::fmt::Debug;
+
+        trait Asset: Debug + PartialEq + Send + Sync + 'static {}
+
+        #[derive(Debug, PartialEq)]
+        struct Texture(u32);
+
+        impl Asset for Texture {}
+
+        #[derive(Debug, PartialEq)]
+        struct Mesh(&'static str);
+
+        impl Asset for Mesh {}
+
+        #[derive(Component, Debug)]
+        struct Handle<T: Asset>(Arc<T>)
+        where
+            T: Debug;
+
+        let mut world = World::new();
+
+        let texture = world.add_component::<Handle<Texture>>();
+        let mesh = world.add_component::<Handle<Mesh>>();
+
+        assert_ne!(texture, mesh);
+        assert_eq!(world.add_component::<Handle<Texture>>(), texture);
+
+        let texture_name = world.components().get(texture).unwrap().name();
+        let mesh_name = world.components().get(mesh).unwrap().name();
+
+        assert!(texture_name.contains("Handle<") && texture_name.contains("Texture>"));
+        assert!(mesh_name.contains("Handle<") && mesh_name.contains("Mesh>"));
+
+        let e = world.spawn();
+        world.insert(e, Handle(Arc::new(Texture(5))));
+        world.insert(e, Handle(Arc::new(Mesh("cube"))));
+
+        let arch = world
+            .archetypes()
+            .get(world.entities().get(e).unwrap().archetype)
+            .unwrap();
+
+        assert_eq!(arch.columns().len(), 2);
+
+        world.add_system(
+            move |_: Receiver<E>,
+                  textures: Fetcher<&Handle<Texture>>,
+                  meshes: Fetcher<&Handle<Mesh>>| {
+                assert_eq!(*textures.get(e).unwrap().0, Texture(5));
+                assert_eq!(*meshes.get(e).unwrap().0, Mesh("cube"));
+            },
+        );
+
+        world.send(E);
+    }
+
+    #[test]
+    fn remove_component() {
+        #[derive(Component)]
+        struct A(String);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct B(Vec<String>);
+
+        let mut world = World::new();
+
+        let c1 = world.add_component::<A>();
+        let e1 = world.spawn();
+        world.insert(e1, A("hello".into()));
+        let s1 = world.add_system(|_: Receiver<E>, Single(A(a)): Single<&mut A>| {
+            a.push_str("hello");
+        });
+        world.send(E);
+
+        assert!(world.remove_component(c1).is_some());
+        assert!(!world.systems().contains(s1));
+        assert!(!world.entities().contains(e1));
+        assert_eq!(
+            world.archetypes().len(),
+            1,
+            "only the empty archetype should be present"
+        );
+
+        let c2 = world.add_component::<B>();
+        let e2 = world.spawn();
+        assert!(world.entities().contains(e2));
+        world.insert(e2, B(vec![]));
+        let s2 = world.add_system(|_: Receiver<E>, Single(B(b)): Single<&mut B>| {
+            b.push("hello".into());
+        });
+        world.send(E);
+        assert_eq!(world.get_component::<B>(e2), Some(&B(vec!["hello".into()])));
+
+        assert!(world.remove_component(c2).is_some());
+        assert!(!world.systems().contains(s2));
         assert!(!world.entities().contains(e2));
         assert_eq!(world.archetypes().len(), 1);
     }
+
+    #[test]
+    fn required_components() {
+        #[derive(Component, PartialEq, Debug)]
+        #[component(requires(B, C))]
+        struct A(u32);
+
+        #[derive(Component, Default, PartialEq, Debug)]
+        #[component(requires(D))]
+        struct B(u32);
+
+        #[derive(Component, Default, PartialEq, Debug)]
+        struct C(String);
+
+        #[derive(Component, Default, PartialEq, Debug)]
+        struct D(u64);
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, A(1));
+
+        assert_eq!(world.get_component::<A>(e1), Some(&A(1)));
+        assert_eq!(world.get_component::<B>(e1), Some(&B(0)));
+        assert_eq!(world.get_component::<C>(e1), Some(&C(String::new())));
+        assert_eq!(world.get_component::<D>(e1), Some(&D(0)));
+
+        // Existing components are left alone.
+        let e2 = world.spawn();
+        world.insert(e2, B(2));
+        world.insert(e2, A(2));
+
+        assert_eq!(world.get_component::<B>(e2), Some(&B(2)));
+        assert_eq!(world.get_component::<C>(e2), Some(&C(String::new())));
+
+        // Replacing the component restores missing requirements.
+        world.remove::<C>(e2);
+        world.insert(e2, A(3));
+
+        assert_eq!(world.get_component::<A>(e2), Some(&A(3)));
+        assert_eq!(world.get_component::<C>(e2), Some(&C(String::new())));
+
+        let a = world.add_component::<A>();
+        let required = world.components()[a]
+            .required()
+            .iter()
+            .map(|&(id, _)| id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            required,
+            [
+                world.add_component::<B>(),
+                world.add_component::<D>(),
+                world.add_component::<C>()
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle detected")]
+    fn required_components_cycle() {
+        #[derive(Component, Default)]
+        #[component(requires(B))]
+        struct A;
+
+        #[derive(Component, Default)]
+        #[component(requires(A))]
+        struct B;
+
+        World::new().add_component::<A>();
+    }
+
+    #[test]
+    fn required_components_cycle_error() {
+        #[derive(Component, Default)]
+        #[component(requires(B))]
+        struct A;
+
+        #[derive(Component, Default)]
+        #[component(requires(A))]
+        struct B;
+
+        let mut world = World::new();
+
+        assert_eq!(
+            world.try_add_component::<A>(),
+            Err(AddComponentError::RequirementCycle {
+                name: any::type_name::<A>()
+            })
+        );
+        assert!(world.components().pending.is_empty());
+    }
+
+    #[test]
+    fn required_components_panic_clears_pending() {
+        struct Panics;
+
+        impl Component for Panics {
+            fn required(_: &mut RequiredComponents) {
+                panic!("oops");
+            }
+        }
+
+        let mut world = World::new();
+
+        let res = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            world.add_component::<Panics>();
+        }));
+
+        assert!(res.is_err());
+        assert!(world.components().pending.is_empty());
+    }
+
+    #[derive(Event)]
+    struct Hooked(&'static str, EntityId, u32);
+
+    #[derive(Component, Clone)]
+    #[component(on_insert = hooked_inserted, on_remove = hooked_removed)]
+    struct Collider(u32);
+
+    fn hooked_inserted(ctx: &mut HookContext, entity: EntityId, c: &Collider) {
+        ctx.send(Hooked("insert", entity, c.0));
+    }
+
+    fn hooked_removed(ctx: &mut HookContext, entity: EntityId, c: &Collider) {
+        ctx.send(Hooked("remove", entity, c.0));
+    }
+
+    #[derive(Component)]
+    #[component(on_remove = other_removed)]
+    struct Other;
+
+    fn other_removed(ctx: &mut HookContext, entity: EntityId, _: &Other) {
+        ctx.send(Hooked("remove other", entity, 0));
+    }
+
+    type HookLog = Arc<Mutex<Vec<(&'static str, EntityId, u32)>>>;
+
+    fn hook_log(world: &mut World) -> HookLog {
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<Hooked>| {
+            l.lock().unwrap().push((r.event.0, r.event.1, r.event.2))
+        });
+
+        log
+    }
+
+    #[test]
+    fn component_hooks() {
+        let mut world = World::new();
+        let log = hook_log(&mut world);
+
+        let e = world.spawn();
+        world.insert(e, Collider(1));
+        world.insert(e, Collider(2));
+        world.remove::<Collider>(e);
+        world.remove::<Collider>(e);
+
+        world.insert(e, Collider(3));
+        world.insert(e, Other);
+        world.despawn(e);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                ("insert", e, 1),
+                ("remove", e, 1),
+                ("insert", e, 2),
+                ("remove", e, 2),
+                ("insert", e, 3),
+                ("remove", e, 3),
+                ("remove other", e, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn component_hooks_bulk_and_clone() {
+        #[derive(Component, Clone)]
+        struct Marker;
+
+        let mut world = World::new();
+        let log = hook_log(&mut world);
+
+        let e1 = world.spawn();
+        world.insert(e1, Marker);
+        let e2 = world.spawn();
+        world.insert(e2, Marker);
+        world.insert(e2, Collider(1));
+
+        world.insert_for_all::<With<&Marker>, _>(Collider(2));
+
+        log.lock()
+            .unwrap()
+            .sort_unstable_by_key(|&(name, e, _)| (e, name));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                ("insert", e1, 2),
+                ("insert", e2, 1),
+                ("insert", e2, 2),
+                ("remove", e2, 1),
+            ]
+        );
+
+        log.lock().unwrap().clear();
+
+        let copy = world.clone_entity(e1).unwrap();
+
+        assert_eq!(*log.lock().unwrap(), [("insert", copy, 2)]);
+    }
+
+    #[test]
+    fn component_hooks_are_deferred() {
+        #[derive(Component)]
+        #[component(on_insert = despawn_on_insert)]
+        struct Doomed;
+
+        fn despawn_on_insert(ctx: &mut HookContext, entity: EntityId, _: &Doomed) {
+            // The entity is still moving, so the despawn is queued.
+            assert!(ctx.world().get_component::<Doomed>(entity).is_some());
+            ctx.insert(entity, Collider(1));
+            ctx.despawn(entity);
+        }
+
+        let mut world = World::new();
+        let log = hook_log(&mut world);
+
+        let e = world.spawn();
+        world.insert(e, Doomed);
+
+        assert!(!world.is_alive(e));
+        assert_eq!(*log.lock().unwrap(), [("insert", e, 1), ("remove", e, 1)]);
+
+        world.check_invariants();
+    }
+
+    #[test]
+    fn singleton_replace() {
+        #[derive(Component, Clone, PartialEq, Debug)]
+        #[component(singleton)]
+        struct ActiveCamera(u32);
+
+        let mut world = World::new();
+
+        let removed = Arc::new(Mutex::new(vec![]));
+        let r = removed.clone();
+        world.add_system(move |r2: Receiver<Remove<ActiveCamera>, EntityId>| {
+            r.lock().unwrap().push(r2.query);
+        });
+
+        let a = world.spawn();
+        let b = world.spawn();
+
+        assert_eq!(world.singleton::<ActiveCamera>(), None);
+
+        world.insert(a, ActiveCamera(1));
+        world.insert(a, ActiveCamera(2));
+        assert_eq!(
+            world.singleton::<ActiveCamera>(),
+            Some((a, &ActiveCamera(2)))
+        );
+        assert!(removed.lock().unwrap().is_empty());
+
+        world.insert(b, ActiveCamera(3));
+        assert_eq!(
+            world.singleton::<ActiveCamera>(),
+            Some((b, &ActiveCamera(3)))
+        );
+        assert_eq!(world.get_component::<ActiveCamera>(a), None);
+        assert_eq!(*removed.lock().unwrap(), [a]);
+
+        // Clones only get the component with its `Insert` event.
+        assert!(world.clone_entity(b).is_err());
+
+        let options = crate::entity::CloneOptions {
+            send_insert_events: true,
+            ..Default::default()
+        };
+        let c = world.clone_entity_with(b, options).unwrap();
+        assert_eq!(
+            world.singleton::<ActiveCamera>(),
+            Some((c, &ActiveCamera(3)))
+        );
+
+        world.despawn(c);
+        assert_eq!(world.singleton::<ActiveCamera>(), None);
+
+        world.insert(a, ActiveCamera(4));
+        let id = world.add_component::<ActiveCamera>();
+        assert_eq!(world.singleton_holder(id), Some(a));
+
+        world.check_invariants();
+    }
+
+    #[test]
+    fn singleton_reject() {
+        #[derive(Component, PartialEq, Debug)]
+        #[component(singleton = "reject")]
+        struct LocalPlayer(u32);
+
+        let mut world = World::new();
+
+        let a = world.spawn();
+        let b = world.spawn();
+
+        world.insert(a, LocalPlayer(1));
+        world.insert(b, LocalPlayer(2));
+        assert_eq!(world.singleton::<LocalPlayer>(), Some((a, &LocalPlayer(1))));
+        assert_eq!(world.get_component::<LocalPlayer>(b), None);
+
+        world.remove::<LocalPlayer>(a);
+        world.insert(b, LocalPlayer(3));
+        assert_eq!(world.singleton::<LocalPlayer>(), Some((b, &LocalPlayer(3))));
+    }
+
+    #[test]
+    fn singleton_replace_consumed_remove() {
+        #[derive(Component, PartialEq, Debug)]
+        #[component(singleton)]
+        struct Leader;
+
+        let mut world = World::new();
+
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(a, Leader);
+
+        // Keeping the component on `a` turns the insert on `b` into a no-op.
+        world.add_system(|r: ReceiverMut<Remove<Leader>, ()>| {
+            EventMut::take(r.event);
+        });
+
+        world.insert(b, Leader);
+        assert_eq!(world.singleton::<Leader>(), Some((a, &Leader)));
+        assert_eq!(world.get_component::<Leader>(b), None);
+    }
+
+    #[test]
+    fn console_insert_by_name() {
+        use alloc::collections::BTreeMap;
+
+        use super::NameError;
+
+        #[derive(Component, Default, PartialEq, Debug)]
+        #[component(name = "health")]
+        struct Health(u32);
+
+        #[derive(Component, Default, PartialEq, Debug)]
+        #[component(name = "mana")]
+        struct Mana(u32);
+
+        type InsertDefault = fn(&mut World, EntityId);
+
+        fn insert_default<C: Component + Default>(world: &mut World, entity: EntityId) {
+            world.insert(entity, C::default());
+        }
+
+        let mut world = World::new();
+
+        let mut commands = BTreeMap::<ComponentId, InsertDefault>::new();
+        commands.insert(world.add_component::<Health>(), insert_default::<Health>);
+        commands.insert(world.add_component::<Mana>(), insert_default::<Mana>);
+
+        let run_command = |world: &mut World, line: &str, entity: EntityId| {
+            let name = line.strip_prefix("insert ").unwrap();
+            let id = world.components().get_by_name(name)?.id();
+            commands[&id](world, entity);
+            Ok::<_, NameError>(())
+        };
+
+        let e = world.spawn();
+
+        run_command(&mut world, "insert health", e).unwrap();
+        assert_eq!(world.get_component::<Health>(e), Some(&Health(0)));
+        assert_eq!(world.get_component::<Mana>(e), None);
+
+        assert_eq!(
+            run_command(&mut world, "insert stamina", e),
+            Err(NameError::NotFound)
+        );
+    }
+
+    #[test]
+    fn duplicate_names() {
+        use alloc::borrow::Cow;
+        use core::alloc::Layout;
+
+        use super::{ComponentDescriptor, NameError};
+
+        #[derive(Component)]
+        #[component(name = "position")]
+        struct Position;
+
+        let mut world = World::new();
+        let typed = world.add_component::<Position>();
+
+        let dynamic = unsafe {
+            world.add_component_with_descriptor(ComponentDescriptor {
+                name: Cow::Borrowed("position"),
+                type_id: None,
+                key: None,
+                layout: Layout::new::<()>(),
+                drop: None,
+                copy_fn: None,
+                clone_fn: None,
+                default_capacity: 0,
+                is_chunked: false,
+                preserve_order: false,
+                is_immutable: false,
+                is_local: false,
+                singleton: None,
+                is_pod: false,
+                required: vec![],
+                on_insert: None,
+                on_remove: None,
+                validate: None,
+            })
+        };
+
+        assert_eq!(
+            world.components().get_by_name("position").unwrap_err(),
+            NameError::Ambiguous { count: 2 }
+        );
+
+        let ids: Vec<_> = world
+            .components()
+            .iter_by_name("position")
+            .map(|info| info.id())
+            .collect();
+        assert_eq!(ids, [typed, dynamic]);
+
+        world.remove_component(typed);
+        assert_eq!(
+            world.components().get_by_name("position").unwrap().id(),
+            dynamic
+        );
+
+        world.remove_component(dynamic);
+        assert_eq!(
+            world.components().get_by_name("position").unwrap_err(),
+            NameError::NotFound
+        );
+    }
+
+    #[test]
+    fn event_names() {
+        let mut world = World::new();
+        let id = world.add_event::<E>();
+
+        let name = core::any::type_name::<E>();
+        assert_eq!(world.events().get_by_name(name).unwrap().id(), id);
+
+        world.remove_event(id);
+        assert!(world.events().get_by_name(name).is_err());
+    }
+
+    #[test]
+    fn components_are_deduplicated_by_key() {
+        use alloc::borrow::Cow;
+        use core::alloc::Layout;
+        use core::any::TypeId;
+
+        use super::{AddComponentError, ComponentDescriptor};
+
+        #[derive(Component)]
+        #[component(uuid = "health")]
+        struct Health(#[allow(dead_code)] u32);
+
+        #[derive(Component)]
+        #[component(key)]
+        struct Keyed;
+
+        assert_eq!(Keyed::KEY, Some(concat!(module_path!(), "::Keyed")));
+
+        // Stands in for the copy of `Health` in another library.
+        struct OtherHealth;
+
+        let desc = |layout| ComponentDescriptor {
+            name: Cow::Borrowed("health"),
+            type_id: Some(TypeId::of::<OtherHealth>()),
+            key: Some(Cow::Borrowed("health")),
+            layout,
+            drop: None,
+            copy_fn: None,
+            clone_fn: None,
+            default_capacity: 0,
+            is_chunked: false,
+            preserve_order: false,
+            is_immutable: false,
+            is_local: false,
+            singleton: None,
+            is_pod: false,
+            required: vec![],
+            on_insert: None,
+            on_remove: None,
+            validate: None,
+        };
+
+        let mut world = World::new();
+        let id = world.add_component::<Health>();
+
+        assert_eq!(world.component_id_by_key("health"), Some(id));
+
+        let err = unsafe { world.try_add_component_with_descriptor(desc(Layout::new::<u64>())) };
+        assert!(matches!(
+            err,
+            Err(AddComponentError::LayoutMismatch { existing, .. }) if existing == id
+        ));
+        assert!(world
+            .components()
+            .get_by_type_id(TypeId::of::<OtherHealth>())
+            .is_none());
+
+        let other = unsafe { world.add_component_with_descriptor(desc(Layout::new::<u32>())) };
+        assert_eq!(other, id);
+        assert_eq!(
+            world
+                .components()
+                .get_by_type_id(TypeId::of::<OtherHealth>())
+                .unwrap()
+                .id(),
+            id
+        );
+
+        world.remove_component(id);
+
+        assert_eq!(world.component_id_by_key("health"), None);
+        assert!(world
+            .components()
+            .get_by_type_id(TypeId::of::<OtherHealth>())
+            .is_none());
+    }
+
+    #[derive(Component)]
+    #[component(validate = check_unit)]
+    struct Unit(f32);
+
+    fn check_unit(unit: &Unit) -> Result<(), String> {
+        if (0.0..=1.0).contains(&unit.0) {
+            Ok(())
+        } else {
+            Err(format!("{} is out of range", unit.0))
+        }
+    }
+
+    #[test]
+    fn validate_all() {
+        use super::ValidationFailure;
+
+        #[derive(Component)]
+        #[component(chunked, validate = check_chunked)]
+        struct Chunked(u64);
+
+        fn check_chunked(c: &Chunked) -> Result<(), String> {
+            if c.0 == u64::MAX {
+                Err("poisoned".into())
+            } else {
+                Ok(())
+            }
+        }
+
+        #[derive(Component)]
+        struct Unchecked(#[allow(dead_code)] u8);
+
+        let mut world = World::new();
+
+        let unit = world.add_component::<Unit>();
+        let chunked = world.add_component::<Chunked>();
+
+        let entities: Vec<_> = (0..10)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, Unit(i as f32 / 10.0));
+                world.insert(e, Chunked(i));
+
+                if i % 2 == 0 {
+                    world.insert(e, Unchecked(0));
+                }
+
+                e
+            })
+            .collect();
+
+        assert_eq!(world.validate_all(), []);
+
+        // Corrupt components in both archetypes, as unsafe code might.
+        for &i in &[3, 4] {
+            let e = entities[i];
+            let loc = world.entity_location(e).unwrap();
+            let arch = world.archetypes().get(loc.archetype).unwrap();
+
+            for (col, bytes) in [
+                (unit, 2.0_f32.to_ne_bytes().to_vec()),
+                (chunked, vec![0xff; 8]),
+            ] {
+                let col = arch
+                    .columns()
+                    .iter()
+                    .find(|c| c.component_index() == col.index())
+                    .unwrap();
+
+                unsafe {
+                    let ptr = col.row_ptr(loc.row.0 as usize).as_ptr();
+                    ptr.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+                }
+            }
+        }
+
+        let mut failures = world.validate_all();
+        failures.sort_by_key(|f| (f.entity.index(), f.component.index()));
+
+        let failure = |i: usize, component, message: &str| ValidationFailure {
+            entity: entities[i],
+            component,
+            message: message.into(),
+        };
+
+        assert_eq!(
+            failures,
+            [
+                failure(3, unit, "2 is out of range"),
+                failure(3, chunked, "poisoned"),
+                failure(4, unit, "2 is out of range"),
+                failure(4, chunked, "poisoned"),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "validate-components")]
+    #[should_panic(expected = "1 components failed validation")]
+    fn invalid_component_panics_after_flush() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Unit(0.5));
+
+        world.add_system(|_: Receiver<E>, f: Fetcher<&mut Unit>| {
+            for unit in f {
+                unit.0 = -1.0;
+            }
+        });
+
+        world.send(E);
+    }
 }
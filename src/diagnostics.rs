@@ -0,0 +1,245 @@
+//! Events describing structural changes to a [`World`], for tooling such as
+//! inspectors.
+//!
+//! [`ArchetypeCreated`], [`EntityMoved`], and [`ComponentRegistered`] are
+//! sent by the world itself as structural changes happen. Because those
+//! changes are made in the middle of dispatching other events, the
+//! diagnostic events are [deferred]: they're collected while the world
+//! flushes its event queue and sent once everything else has finished
+//! broadcasting, in the order the changes happened. Changes made by the
+//! listeners of diagnostic events are reported in the same flush, and
+//! changes made outside of any flush are reported with the next one.
+//!
+//! Nothing is collected for an event until a system listens for it, so
+//! worlds without diagnostic listeners only pay for checking a flag.
+//!
+//! This module is only available with the `diagnostics` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::diagnostics::{ArchetypeCreated, EntityMoved};
+//! use evenio::prelude::*;
+//!
+//! #[derive(Component)]
+//! struct Health(u32);
+//!
+//! let mut world = World::new();
+//!
+//! world.add_system(|r: Receiver<ArchetypeCreated>| {
+//!     println!("new archetype {:?}", r.event.idx);
+//! });
+//!
+//! world.add_system(|r: Receiver<EntityMoved>| {
+//!     println!(
+//!         "{:?} moved from {:?} to {:?}",
+//!         r.event.entity, r.event.from, r.event.to
+//!     );
+//! });
+//!
+//! let e = world.spawn();
+//! world.insert(e, Health(10)); // Prints both messages.
+//! ```
+//!
+//! [`World`]: crate::world::World
+//! [deferred]: crate::event::Event::IS_DEFERRED
+
+use alloc::vec::Vec;
+
+use crate::archetype::ArchetypeIdx;
+use crate::component::ComponentId;
+use crate::entity::EntityId;
+use crate::event::Event;
+
+/// Sent after a new archetype is created.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[event(immutable, deferred)]
+pub struct ArchetypeCreated {
+    /// The index of the new archetype.
+    pub idx: ArchetypeIdx,
+}
+
+/// Sent after an entity moves from one archetype to another because of a
+/// component being inserted or removed.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[event(immutable, deferred)]
+pub struct EntityMoved {
+    /// The entity that moved.
+    pub entity: EntityId,
+    /// The archetype the entity was in.
+    pub from: ArchetypeIdx,
+    /// The archetype the entity is in now.
+    pub to: ArchetypeIdx,
+}
+
+/// Sent after a new component is added to the world. Unlike
+/// [`AddComponent`](crate::component::AddComponent), this is deferred like
+/// the other diagnostic events.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[event(immutable, deferred)]
+pub struct ComponentRegistered {
+    /// The ID of the new component.
+    pub id: ComponentId,
+}
+
+/// A structural change waiting to be sent as a diagnostic event.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Diagnostic {
+    ArchetypeCreated(ArchetypeCreated),
+    EntityMoved(EntityMoved),
+    ComponentRegistered(ComponentRegistered),
+}
+
+/// Which diagnostic events have listeners.
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct Listening {
+    pub(crate) archetype_created: bool,
+    pub(crate) entity_moved: bool,
+    pub(crate) component_registered: bool,
+}
+
+/// Collects the diagnostics of the current flush.
+#[derive(Default, Debug)]
+pub(crate) struct DiagnosticQueue {
+    pub(crate) listening: Listening,
+    pending: Vec<Diagnostic>,
+}
+
+impl DiagnosticQueue {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub(crate) fn archetype_created(&mut self, idx: ArchetypeIdx) {
+        if self.listening.archetype_created {
+            self.pending
+                .push(Diagnostic::ArchetypeCreated(ArchetypeCreated { idx }));
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_listening_for_moves(&self) -> bool {
+        self.listening.entity_moved
+    }
+
+    #[inline]
+    pub(crate) fn entity_moved(&mut self, entity: EntityId, from: ArchetypeIdx, to: ArchetypeIdx) {
+        if self.listening.entity_moved {
+            self.pending
+                .push(Diagnostic::EntityMoved(EntityMoved { entity, from, to }));
+        }
+    }
+
+    #[inline]
+    pub(crate) fn component_registered(&mut self, id: ComponentId) {
+        if self.listening.component_registered {
+            self.pending
+                .push(Diagnostic::ComponentRegistered(ComponentRegistered { id }));
+        }
+    }
+
+    /// Takes the collected diagnostics, oldest first.
+    pub(crate) fn take(&mut self) -> Vec<Diagnostic> {
+        core::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component)]
+    struct A;
+
+    #[derive(Component)]
+    struct B;
+
+    fn log_diagnostics(world: &mut World) -> Arc<Mutex<Vec<Diagnostic>>> {
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<ArchetypeCreated>| {
+            l.lock()
+                .unwrap()
+                .push(Diagnostic::ArchetypeCreated(*r.event))
+        });
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<EntityMoved>| {
+            l.lock().unwrap().push(Diagnostic::EntityMoved(*r.event))
+        });
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<ComponentRegistered>| {
+            l.lock()
+                .unwrap()
+                .push(Diagnostic::ComponentRegistered(*r.event))
+        });
+
+        log
+    }
+
+    #[test]
+    fn structural_changes_are_reported() {
+        let mut world = World::new();
+        let log = log_diagnostics(&mut world);
+
+        let a = world.add_component::<A>();
+        let e = world.spawn();
+        world.insert(e, A);
+
+        let arch = world.entities().get(e).unwrap().archetype;
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                Diagnostic::ComponentRegistered(ComponentRegistered { id: a }),
+                Diagnostic::ArchetypeCreated(ArchetypeCreated { idx: arch }),
+                Diagnostic::EntityMoved(EntityMoved {
+                    entity: e,
+                    from: ArchetypeIdx::EMPTY,
+                    to: arch,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_are_deferred() {
+        let mut world = World::new();
+        let log = log_diagnostics(&mut world);
+
+        world.add_component::<B>();
+        let e = world.spawn();
+        log.lock().unwrap().clear();
+
+        // Listeners of the change run before the diagnostics are delivered.
+        let l = log.clone();
+        world.add_system(move |_: Receiver<Insert<B>, ()>| {
+            assert!(l.lock().unwrap().is_empty());
+        });
+
+        world.insert(e, B);
+
+        assert_eq!(log.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn nothing_is_collected_without_listeners() {
+        let mut world = World::new();
+
+        world.add_component::<A>();
+        let e = world.spawn();
+        world.insert(e, A);
+
+        let log = log_diagnostics(&mut world);
+        world.spawn();
+
+        assert!(log.lock().unwrap().is_empty());
+    }
+}
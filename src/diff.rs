@@ -0,0 +1,896 @@
+//! Incremental diffs of a [`World`], for replicating one world into another.
+//!
+//! Once [`World::start_tracking_changes`] is called, the world records the
+//! [tick] on which entities are spawned and despawned and components are
+//! inserted and removed. [`World::diff_since`] uses those records to build a
+//! [`WorldDiff`] with everything that changed on or after a given tick, which
+//! [`WorldDiff::apply`] replays on another world.
+//!
+//! Components are carried by the diff when they were inserted since the tick.
+//! Changes made in place are tracked per archetype column: borrowing a
+//! component with `&mut C` in a query, [`Fetcher::get_mut`] or
+//! [`World::get_component_mut`] marks its column as changed on the current
+//! tick, and every component in a changed column is carried. Moving an entity
+//! to another archetype marks the destination column with the changes of the
+//! source column. [Immutable] components can only change by being inserted
+//! again.
+//!
+//! The diff holds copies of the components made with their [`CloneFn`].
+//! Components without one are left out, and [`EntityId`]s stored inside
+//! components aren't remapped.
+//!
+//! This module is only available with the `diff` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::diff::EntityIdMap;
+//! use evenio::prelude::*;
+//!
+//! #[derive(Component, Clone, PartialEq, Debug)]
+//! #[component(immutable)]
+//! struct Health(u32);
+//!
+//! let mut server = World::new();
+//! server.start_tracking_changes();
+//!
+//! let e = server.spawn();
+//! server.insert(e, Health(100));
+//!
+//! let mut client = World::new();
+//! client.add_event::<Insert<Health>>();
+//!
+//! let mut ids = EntityIdMap::new();
+//!
+//! let diff = server.diff_since(0, |_| true);
+//! assert!(diff.apply(&mut client, &mut ids).is_empty());
+//!
+//! let copy = ids.get(e).unwrap();
+//! assert_eq!(client.get_component::<Health>(copy), Some(&Health(100)));
+//!
+//! server.advance_tick(1);
+//! server.insert(e, Health(90));
+//!
+//! // Only the new health is sent.
+//! let diff = server.diff_since(diff.tick() + 1, |_| true);
+//! assert_eq!(diff.changed::<Health>().count(), 1);
+//!
+//! diff.apply(&mut client, &mut ids);
+//! assert_eq!(client.get_component::<Health>(copy), Some(&Health(90)));
+//! ```
+//!
+//! [tick]: World::tick
+//! [`Fetcher::get_mut`]: crate::fetch::Fetcher::get_mut
+//! [Immutable]: crate::component::Component::IS_IMMUTABLE
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::any::TypeId;
+use core::cell::Cell;
+use core::fmt;
+use core::ptr::NonNull;
+
+use bumpalo::Bump;
+
+use crate::archetype::Archetypes;
+use crate::component::{CloneFn, Component, ComponentId, ComponentIdx, ComponentInfo};
+use crate::drop::DropFn;
+use crate::entity::EntityId;
+use crate::event::{EventIdx, EventKind};
+use crate::world::World;
+
+/// The structural changes recorded while a world is tracking changes.
+#[derive(Debug, Default)]
+pub(crate) struct ChangeLog {
+    /// The tick changes are recorded on.
+    pub(crate) tick: u64,
+    /// The tick each entity was spawned on.
+    spawned: BTreeMap<EntityId, u64>,
+    /// The tick each component of each entity was last inserted on.
+    inserted: BTreeMap<(EntityId, ComponentIdx), u64>,
+    /// The despawned entities, oldest first.
+    despawned: Vec<(u64, EntityId)>,
+    /// The components removed from entities, oldest first.
+    removed: Vec<(u64, EntityId, ComponentIdx)>,
+}
+
+impl ChangeLog {
+    /// Starts a log in which every entity in `archetypes` was spawned on
+    /// `tick` with its current components.
+    pub(crate) fn new(tick: u64, archetypes: &Archetypes) -> Self {
+        let mut log = Self {
+            tick,
+            ..Self::default()
+        };
+
+        for arch in archetypes.iter() {
+            for &entity in arch.entity_ids() {
+                log.spawned(entity);
+                log.moved(entity, &[], arch.component_set());
+            }
+        }
+
+        log
+    }
+
+    pub(crate) fn spawned(&mut self, entity: EntityId) {
+        self.spawned.insert(entity, self.tick);
+    }
+
+    pub(crate) fn inserted(&mut self, entity: EntityId, component: ComponentIdx) {
+        self.inserted.insert((entity, component), self.tick);
+    }
+
+    /// Records an entity moving from the archetype with the sorted set of
+    /// components `src` to the one with `dst`.
+    pub(crate) fn moved(&mut self, entity: EntityId, src: &[ComponentIdx], dst: &[ComponentIdx]) {
+        for &component in dst {
+            if src.binary_search(&component).is_err() {
+                self.inserted(entity, component);
+            }
+        }
+
+        for &component in src {
+            if dst.binary_search(&component).is_err() {
+                self.inserted.remove(&(entity, component));
+                self.removed.push((self.tick, entity, component));
+            }
+        }
+    }
+
+    pub(crate) fn despawned(&mut self, entity: EntityId) {
+        self.spawned.remove(&entity);
+
+        let components = self
+            .inserted
+            .range((entity, ComponentIdx(0))..=(entity, ComponentIdx(u32::MAX)))
+            .map(|(&key, _)| key)
+            .collect::<Vec<_>>();
+
+        for key in components {
+            self.inserted.remove(&key);
+        }
+
+        self.despawned.push((self.tick, entity));
+    }
+
+    /// Discards the records of despawns and removals made before `tick`.
+    pub(crate) fn forget_before(&mut self, tick: u64) {
+        let despawned = self.despawned.partition_point(|&(t, _)| t < tick);
+        self.despawned.drain(..despawned);
+
+        let removed = self.removed.partition_point(|&(t, _, _)| t < tick);
+        self.removed.drain(..removed);
+    }
+}
+
+/// Tracks when the components of a column were last borrowed mutably, since
+/// the world can't see what's written through those borrows. Borrowing only
+/// sets a flag, which [`flush`](Self::flush) turns into a tick once the tick
+/// advances.
+#[derive(Debug, Default)]
+pub(crate) struct ColumnChanges {
+    /// Set when a component in the column is borrowed mutably.
+    dirty: Cell<bool>,
+    /// The last tick before the current one on which the flag was set.
+    tick: u64,
+}
+
+impl ColumnChanges {
+    /// Marks the column as changed on the current tick.
+    pub(crate) fn mark(&self) {
+        self.dirty.set(true);
+    }
+
+    /// Returns a flag for marking the column from a query. It's valid for as
+    /// long as the column's archetype exists.
+    pub(crate) fn flag(&self) -> ChangeFlag {
+        ChangeFlag(NonNull::from(&self.dirty))
+    }
+
+    /// Returns the last tick the column was changed on, given the current
+    /// tick.
+    pub(crate) fn tick(&self, current: u64) -> u64 {
+        if self.dirty.get() {
+            current
+        } else {
+            self.tick
+        }
+    }
+
+    /// Records the changes made on `tick`, which is about to end.
+    pub(crate) fn flush(&mut self, tick: u64) {
+        if self.dirty.replace(false) {
+            self.tick = tick;
+        }
+    }
+
+    /// Carries the changes of a column over to this one, when entities move
+    /// from it. Rows which are already here count as changed as well.
+    pub(crate) fn merge(&mut self, src: &Self) {
+        self.dirty.set(self.dirty.get() || src.dirty.get());
+        self.tick = self.tick.max(src.tick);
+    }
+}
+
+/// A pointer to the flag of a [`ColumnChanges`], stored in the archetype
+/// state of queries which borrow the column mutably.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ChangeFlag(NonNull<Cell<bool>>);
+
+impl ChangeFlag {
+    /// Marks the column as changed on the current tick.
+    ///
+    /// # Safety
+    ///
+    /// The archetype of the column must still exist.
+    #[inline]
+    pub(crate) unsafe fn mark(self) {
+        self.0.as_ref().set(true);
+    }
+}
+
+// SAFETY: The flag is only set through queries with mutable access to the
+// column, which the world never runs on more than one thread at once.
+unsafe impl Send for ChangeFlag {}
+unsafe impl Sync for ChangeFlag {}
+
+/// The changes made to a [`World`] since a given tick. Returned by
+/// [`World::diff_since`].
+///
+/// The diff can be inspected to send it elsewhere, or applied to another
+/// world with [`apply`](WorldDiff::apply).
+#[derive(Debug)]
+pub struct WorldDiff {
+    tick: u64,
+    spawned: Vec<EntityId>,
+    despawned: Vec<EntityId>,
+    components: Vec<DiffComponent>,
+    /// The components removed from living entities, as indices into
+    /// `components`.
+    removed: Vec<(EntityId, usize)>,
+    /// Copies of the changed components, as indices into `components`.
+    changed: Vec<(EntityId, usize, NonNull<u8>)>,
+    /// Storage for the copies of the components.
+    bump: Bump,
+}
+
+/// A component type which appears in a [`WorldDiff`].
+#[derive(Debug)]
+struct DiffComponent {
+    name: Cow<'static, str>,
+    type_id: Option<TypeId>,
+    key: Option<Cow<'static, str>>,
+    layout: Layout,
+    drop: DropFn,
+    clone: Option<CloneFn>,
+}
+
+impl DiffComponent {
+    fn new(info: &ComponentInfo) -> Self {
+        Self {
+            name: String::from(info.name()).into(),
+            type_id: info.type_id(),
+            key: info.key().map(|key| String::from(key).into()),
+            layout: info.layout(),
+            drop: info.drop(),
+            clone: info.clone_fn(),
+        }
+    }
+
+    /// Finds the matching component in `world`.
+    fn resolve<'a>(&self, world: &'a World) -> Option<&'a ComponentInfo> {
+        let components = world.components();
+
+        self.type_id
+            .and_then(|type_id| components.get_by_type_id(type_id))
+            .or_else(|| {
+                self.key
+                    .as_deref()
+                    .and_then(|key| components.get_by_key(key))
+            })
+            .or_else(|| components.get_by_name(&self.name).ok())
+            .filter(|info| info.layout() == self.layout)
+    }
+}
+
+impl WorldDiff {
+    pub(crate) fn new<F>(world: &World, since: u64, mut filter: F) -> Self
+    where
+        F: FnMut(ComponentId) -> bool,
+    {
+        let log = world
+            .archetypes()
+            .changes
+            .as_ref()
+            .expect("the world should be tracking changes");
+
+        let mut diff = Self {
+            tick: log.tick,
+            spawned: vec![],
+            despawned: vec![],
+            components: vec![],
+            removed: vec![],
+            changed: vec![],
+            bump: Bump::new(),
+        };
+
+        // Indices into `components` of the components passing the filter.
+        let mut included = BTreeMap::<ComponentIdx, Option<usize>>::new();
+
+        let mut include = |diff: &mut Self, idx: ComponentIdx| {
+            *included.entry(idx).or_insert_with(|| {
+                let info = world.components().get_by_index(idx)?;
+
+                filter(info.id()).then(|| {
+                    diff.components.push(DiffComponent::new(info));
+                    diff.components.len() - 1
+                })
+            })
+        };
+
+        diff.spawned.extend(
+            log.spawned
+                .iter()
+                .filter(|&(_, &tick)| tick >= since)
+                .map(|(&entity, _)| entity),
+        );
+
+        let despawned = log.despawned.partition_point(|&(tick, _)| tick < since);
+        diff.despawned
+            .extend(log.despawned[despawned..].iter().map(|&(_, entity)| entity));
+
+        let removed = log.removed.partition_point(|&(tick, _, _)| tick < since);
+
+        for &(_, entity, component) in &log.removed[removed..] {
+            let Some(loc) = world.entities().get(entity) else {
+                continue;
+            };
+
+            let arch = world.archetypes().get(loc.archetype).unwrap();
+
+            // Skip components which were inserted again.
+            if arch.column_of(component).is_some() {
+                continue;
+            }
+
+            if let Some(idx) = include(&mut diff, component) {
+                if !diff.removed.contains(&(entity, idx)) {
+                    diff.removed.push((entity, idx));
+                }
+            }
+        }
+
+        for arch in world.archetypes().iter() {
+            for col in arch.columns() {
+                let Some(idx) = include(&mut diff, col.component_index()) else {
+                    continue;
+                };
+
+                let component = &diff.components[idx];
+
+                let Some(clone) = component.clone else {
+                    continue;
+                };
+
+                // Every row counts as changed if the column was borrowed mutably. Otherwise,
+                // only the components inserted since the tick are included.
+                let changed = col.changes().tick(log.tick) >= since;
+
+                for (row, &entity) in arch.entity_ids().iter().enumerate() {
+                    if !changed
+                        && log
+                            .inserted
+                            .get(&(entity, col.component_index()))
+                            .is_none_or(|&tick| tick < since)
+                    {
+                        continue;
+                    }
+
+                    let ptr = diff.bump.alloc_layout(component.layout);
+
+                    unsafe { clone(col.row_ptr(row).as_ptr(), ptr.as_ptr()) };
+
+                    diff.changed.push((entity, idx, ptr));
+                }
+            }
+        }
+
+        diff
+    }
+
+    /// Returns the tick the diff was taken on.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty()
+            && self.despawned.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+
+    /// Returns the entities which were spawned and are still alive.
+    pub fn spawned(&self) -> &[EntityId] {
+        &self.spawned
+    }
+
+    /// Returns the entities which were despawned, oldest first.
+    pub fn despawned(&self) -> &[EntityId] {
+        &self.despawned
+    }
+
+    /// Returns an iterator over the entities which had `C` removed and don't
+    /// have it anymore.
+    pub fn removed<C: Component>(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.removed
+            .iter()
+            .filter(|&&(_, idx)| self.components[idx].type_id == Some(TypeId::of::<C>()))
+            .map(|&(entity, _)| entity)
+    }
+
+    /// Returns an iterator over the copies of `C` in the diff along with the
+    /// entities they belong to.
+    pub fn changed<C: Component>(&self) -> impl Iterator<Item = (EntityId, &C)> + '_ {
+        self.changed
+            .iter()
+            .filter(|&&(_, idx, _)| self.components[idx].type_id == Some(TypeId::of::<C>()))
+            .map(|&(entity, _, ptr)| (entity, unsafe { ptr.cast::<C>().as_ref() }))
+    }
+
+    /// Applies the diff to `world`, which is usually set up the same way as
+    /// the world the diff was taken from.
+    ///
+    /// `id_map` maps the entities of the diffed world to those of `world`,
+    /// and should be kept between diffs. Spawned entities, and changed
+    /// entities which aren't in the map, are spawned in `world` and added to
+    /// it. Despawned entities are despawned and removed from it.
+    ///
+    /// Components are inserted and removed by sending their [`Insert`] and
+    /// [`Remove`] events, so the systems in `world` see the changes as usual.
+    /// Each component is matched with the component in `world` which has the
+    /// same [`TypeId`], key, or name, in that order, and the same layout.
+    /// Changes which can't be applied are skipped and reported in the
+    /// returned list of warnings.
+    ///
+    /// [`Insert`]: crate::event::Insert
+    /// [`Remove`]: crate::event::Remove
+    pub fn apply(&self, world: &mut World, id_map: &mut EntityIdMap) -> Vec<ApplyWarning> {
+        let mut warnings = vec![];
+
+        let mut resolved = self
+            .components
+            .iter()
+            .map(|component| {
+                let target = component.resolve(world).map(|info| ResolvedComponent {
+                    insert: info
+                        .insert_events()
+                        .first()
+                        .and_then(|&id| world.events().get(id))
+                        .and_then(|info| match (info.kind(), info.id().index()) {
+                            (
+                                EventKind::Insert {
+                                    component_offset, ..
+                                },
+                                idx,
+                            ) => Some((idx, component_offset as usize)),
+                            _ => None,
+                        }),
+                    remove: info.remove_events().first().map(|id| id.index()),
+                    warned_insert: false,
+                    warned_remove: false,
+                });
+
+                if target.is_none() {
+                    warnings.push(ApplyWarning::NoSuchComponent {
+                        name: component.name.clone(),
+                    });
+                }
+
+                target
+            })
+            .collect::<Vec<_>>();
+
+        for &entity in &self.spawned {
+            if id_map.get(entity).is_none() {
+                let copy = world.spawn();
+                id_map.insert(entity, copy);
+            }
+        }
+
+        for &(entity, idx) in &self.removed {
+            let (Some(target), Some(resolved)) = (id_map.get(entity), &mut resolved[idx]) else {
+                continue;
+            };
+
+            let Some(event) = resolved.remove else {
+                if !resolved.warned_remove {
+                    resolved.warned_remove = true;
+                    warnings.push(ApplyWarning::NoRemoveEvent {
+                        name: self.components[idx].name.clone(),
+                    });
+                }
+
+                continue;
+            };
+
+            // SAFETY: `Remove` is `repr(transparent)` over the `EntityId`.
+            unsafe { world.send_raw(event, target, |ptr| ptr.cast::<EntityId>().write(target)) };
+        }
+
+        for &(entity, idx, ptr) in &self.changed {
+            let Some(resolved) = &mut resolved[idx] else {
+                continue;
+            };
+
+            let Some((event, offset)) = resolved.insert else {
+                if !resolved.warned_insert {
+                    resolved.warned_insert = true;
+                    warnings.push(ApplyWarning::NoInsertEvent {
+                        name: self.components[idx].name.clone(),
+                    });
+                }
+
+                continue;
+            };
+
+            let target = id_map.get(entity).unwrap_or_else(|| {
+                let copy = world.spawn();
+                id_map.insert(entity, copy);
+                copy
+            });
+
+            // Components are only copied if they have a clone function.
+            let clone = unsafe { self.components[idx].clone.unwrap_unchecked() };
+
+            unsafe {
+                world.send_raw(event, target, |event| {
+                    event.cast::<EntityId>().write(target);
+                    clone(ptr.as_ptr(), event.add(offset));
+                })
+            };
+        }
+
+        for &entity in &self.despawned {
+            if let Some(target) = id_map.remove(entity) {
+                world.despawn(target);
+            }
+        }
+
+        warnings
+    }
+}
+
+impl Drop for WorldDiff {
+    fn drop(&mut self) {
+        for &(_, idx, ptr) in &self.changed {
+            if let Some(drop) = self.components[idx].drop {
+                unsafe { drop(ptr) };
+            }
+        }
+    }
+}
+
+// SAFETY: Components are `Send` and `Sync`, and the diff has sole ownership of
+// its copies.
+unsafe impl Send for WorldDiff {}
+unsafe impl Sync for WorldDiff {}
+
+/// How a component of a [`WorldDiff`] is applied to a world.
+struct ResolvedComponent {
+    /// The `Insert` event of the component and the offset of the component
+    /// within it.
+    insert: Option<(EventIdx, usize)>,
+    /// The `Remove` event of the component.
+    remove: Option<EventIdx>,
+    /// Whether the missing `Insert` event was reported.
+    warned_insert: bool,
+    /// Whether the missing `Remove` event was reported.
+    warned_remove: bool,
+}
+
+/// Maps the entities of one world to the entities of another. Used by
+/// [`WorldDiff::apply`] to keep track of the entities it spawned.
+#[derive(Clone, Default, Debug)]
+pub struct EntityIdMap {
+    map: BTreeMap<EntityId, EntityId>,
+}
+
+impl EntityIdMap {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the entity `entity` is mapped to, if any.
+    pub fn get(&self, entity: EntityId) -> Option<EntityId> {
+        self.map.get(&entity).copied()
+    }
+
+    /// Maps `entity` to `to`, returning the entity it was mapped to before.
+    pub fn insert(&mut self, entity: EntityId, to: EntityId) -> Option<EntityId> {
+        self.map.insert(entity, to)
+    }
+
+    /// Removes the mapping of `entity`, returning the entity it was mapped
+    /// to.
+    pub fn remove(&mut self, entity: EntityId) -> Option<EntityId> {
+        self.map.remove(&entity)
+    }
+
+    /// Returns the number of mapped entities.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if no entities are mapped.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the mapped entities and the entities they're
+    /// mapped to.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, EntityId)> + '_ {
+        self.map.iter().map(|(&from, &to)| (from, to))
+    }
+}
+
+/// A change which [`WorldDiff::apply`] couldn't apply.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ApplyWarning {
+    /// No component in the world matches this component of the diff, so the
+    /// changes to it were skipped.
+    NoSuchComponent {
+        /// The name of the component.
+        name: Cow<'static, str>,
+    },
+    /// The component has no [`Insert`](crate::event::Insert) event in the
+    /// world, so it wasn't inserted.
+    NoInsertEvent {
+        /// The name of the component.
+        name: Cow<'static, str>,
+    },
+    /// The component has no [`Remove`](crate::event::Remove) event in the
+    /// world, so it wasn't removed.
+    NoRemoveEvent {
+        /// The name of the component.
+        name: Cow<'static, str>,
+    },
+}
+
+impl fmt::Display for ApplyWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyWarning::NoSuchComponent { name } => {
+                write!(f, "component `{name}` doesn't exist in the world")
+            }
+            ApplyWarning::NoInsertEvent { name } => {
+                write!(f, "component `{name}` has no `Insert` event in the world")
+            }
+            ApplyWarning::NoRemoveEvent { name } => {
+                write!(f, "component `{name}` has no `Remove` event in the world")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::any;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component, Clone, PartialEq, Debug)]
+    struct Position(i32);
+
+    #[derive(Component, Clone, PartialEq, Debug)]
+    #[component(immutable)]
+    struct Health(u32);
+
+    #[derive(Component, Clone, PartialEq, Debug)]
+    struct Secret(u32);
+
+    fn client() -> World {
+        let mut world = World::new();
+
+        world.add_event::<Insert<Position>>();
+        world.add_event::<Remove<Position>>();
+        world.add_event::<Insert<Health>>();
+        world.add_event::<Remove<Health>>();
+        world.add_event::<Insert<Secret>>();
+
+        world
+    }
+
+    type State = Vec<(EntityId, Option<Position>, Option<Health>)>;
+
+    /// Returns the replicated components of every entity in `world`, with the
+    /// entities mapped by `map`.
+    fn state(world: &World, map: impl Fn(EntityId) -> EntityId) -> State {
+        let mut state = world
+            .entities()
+            .iter()
+            .map(|(id, _)| {
+                (
+                    map(id),
+                    world.get_component::<Position>(id).cloned(),
+                    world.get_component::<Health>(id).cloned(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        state.sort_by_key(|&(id, _, _)| id);
+        state
+    }
+
+    fn round_trip(server: &World, client: &World, ids: &EntityIdMap) {
+        assert!(client
+            .entities()
+            .iter()
+            .all(|(id, _)| client.get_component::<Secret>(id).is_none()));
+
+        assert_eq!(
+            state(server, |id| ids.get(id).unwrap()),
+            state(client, |id| id)
+        );
+    }
+
+    #[test]
+    fn diff_round_trip() {
+        let mut server = World::new();
+
+        let a = server.spawn();
+        server.insert(a, Position(1));
+        server.insert(a, Health(10));
+        server.insert(a, Secret(1));
+
+        server.start_tracking_changes();
+
+        let b = server.spawn();
+        server.insert(b, Position(2));
+
+        let c = server.spawn();
+        server.insert(c, Health(30));
+
+        let secret = server.add_component::<Secret>();
+        let filter = |id| id != secret;
+
+        let mut client = client();
+        let mut ids = EntityIdMap::new();
+
+        let diff = server.diff_since(0, filter);
+        assert_eq!(diff.spawned().len(), 3);
+        assert!(diff.apply(&mut client, &mut ids).is_empty());
+        round_trip(&server, &client, &ids);
+
+        server.advance_tick(1);
+
+        server.get_component_mut::<Position>(a).unwrap().0 += 10;
+        server.insert(b, Health(20));
+        server.remove::<Health>(a);
+        server.insert(c, Health(31));
+        server.despawn(b);
+        let d = server.spawn();
+        server.insert(d, Position(4));
+        server.insert(d, Secret(4));
+
+        let diff = server.diff_since(diff.tick() + 1, filter);
+        assert_eq!(diff.spawned(), [d]);
+        assert_eq!(diff.despawned(), [b]);
+        assert_eq!(diff.removed::<Health>().collect::<Vec<_>>(), [a]);
+        assert_eq!(
+            diff.changed::<Health>().collect::<Vec<_>>(),
+            [(c, &Health(31))]
+        );
+        assert_eq!(diff.changed::<Secret>().count(), 0);
+
+        assert!(diff.apply(&mut client, &mut ids).is_empty());
+        round_trip(&server, &client, &ids);
+        assert_eq!(ids.len(), 3);
+
+        server.advance_tick(1);
+
+        // Nothing changed on this tick.
+        assert!(server.diff_since(diff.tick() + 1, filter).is_empty());
+    }
+
+    #[test]
+    fn mutable_changes() {
+        #[derive(Event)]
+        struct Step;
+
+        let mut world = World::new();
+        world.start_tracking_changes();
+
+        let a = world.spawn();
+        world.insert(a, Position(1));
+        let b = world.spawn();
+        world.insert(b, Position(2));
+        world.insert(b, Health(2));
+
+        world.add_system(
+            |_: Receiver<Step>, f: Fetcher<(&mut Position, With<&Health>)>| {
+                for (pos, _) in f {
+                    pos.0 += 1;
+                }
+            },
+        );
+
+        world.advance_tick(1);
+
+        // Untouched mutable components aren't included.
+        assert!(world.diff_since(1, |_| true).is_empty());
+
+        world.send(Step);
+
+        let diff = world.diff_since(1, |_| true);
+        assert_eq!(
+            diff.changed::<Position>().collect::<Vec<_>>(),
+            [(b, &Position(3))]
+        );
+
+        world.advance_tick(1);
+
+        assert_eq!(
+            world.diff_since(1, |_| true).changed::<Position>().count(),
+            1
+        );
+        assert!(world.diff_since(2, |_| true).is_empty());
+
+        // The change moves along with `b`, and covers `a` in its new archetype.
+        world.remove::<Health>(b);
+
+        let diff = world.diff_since(1, |_| true);
+        assert_eq!(diff.changed::<Position>().count(), 2);
+        assert_eq!(diff.removed::<Health>().collect::<Vec<_>>(), [b]);
+    }
+
+    #[test]
+    fn apply_warnings() {
+        let mut server = World::new();
+        server.start_tracking_changes();
+
+        let e = server.spawn();
+        server.insert(e, Position(1));
+        server.insert(e, Secret(1));
+        server.insert(e, Health(1));
+        server.remove::<Health>(e);
+
+        let mut client = World::new();
+        client.add_component::<Position>();
+        client.add_component::<Health>();
+
+        let mut ids = EntityIdMap::new();
+        let warnings = server.diff_since(0, |_| true).apply(&mut client, &mut ids);
+
+        assert_eq!(
+            warnings,
+            [
+                ApplyWarning::NoSuchComponent {
+                    name: any::type_name::<Secret>().into()
+                },
+                ApplyWarning::NoRemoveEvent {
+                    name: any::type_name::<Health>().into()
+                },
+                ApplyWarning::NoInsertEvent {
+                    name: any::type_name::<Position>().into()
+                },
+            ]
+        );
+
+        // The entity was still spawned.
+        assert!(client.is_alive(ids.get(e).unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "tracking changes")]
+    fn diff_without_tracking() {
+        World::new().diff_since(0, |_| true);
+    }
+}
@@ -1,10 +1,19 @@
 //! Entity related items.
 
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
 use core::ops::Index;
 
-use crate::archetype::{ArchetypeIdx, ArchetypeRow};
-use crate::event::EventPtr;
-use crate::prelude::World;
+use crate::archetype::{ArchetypeIdx, ArchetypeLimitReached, ArchetypeRow};
+use crate::assert::{AssertImmutable, StructureLock};
+use crate::component::{Component, ComponentId};
+use crate::event::{Despawn, Event, EventPtr, Insert, Remove};
+use crate::fetch::Fetcher;
+use crate::global::ResMut;
+use crate::prelude::{Receiver, Sender, World};
 use crate::slot_map::{Key, NextKeyIter, SlotMap};
 use crate::system::{Config, InitError, SystemInfo, SystemParam};
 use crate::world::UnsafeWorldCell;
@@ -26,15 +35,22 @@ use crate::world::UnsafeWorldCell;
 #[derive(Debug)]
 pub struct Entities {
     locs: SlotMap<EntityLocation>,
+    pub(crate) lock: StructureLock,
 }
 
 impl Entities {
     pub(crate) fn new() -> Self {
         Self {
             locs: SlotMap::new(),
+            lock: StructureLock::default(),
         }
     }
 
+    /// Returns the number of bytes allocated for entity locations.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.locs.heap_size()
+    }
+
     /// Gets the [`EntityLocation`] of the given entity. Returns `None` if the
     /// ID is invalid.
     pub fn get(&self, id: EntityId) -> Option<EntityLocation> {
@@ -56,7 +72,20 @@ impl Entities {
         self.get(id).is_some()
     }
 
-    fn add_with(&mut self, f: impl FnOnce(EntityId) -> EntityLocation) -> EntityId {
+    /// Reserves room for at least `additional` more entities.
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.locs.reserve(additional);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn capacity(&self) -> usize {
+        self.locs.capacity()
+    }
+
+    #[track_caller]
+    pub(crate) fn add_with(&mut self, f: impl FnOnce(EntityId) -> EntityLocation) -> EntityId {
+        self.lock.check();
+
         if let Some(k) = self.locs.insert_with(|k| f(EntityId(k))) {
             EntityId(k)
         } else {
@@ -64,7 +93,10 @@ impl Entities {
         }
     }
 
+    #[track_caller]
     pub(crate) fn remove(&mut self, id: EntityId) -> Option<EntityLocation> {
+        self.lock.check();
+
         self.locs.remove(id.0)
     }
 
@@ -73,9 +105,48 @@ impl Entities {
         self.locs.len()
     }
 
-    /// Returns an iterator over all entity locations.
-    pub fn iter(&self) -> impl Iterator<Item = EntityLocation> + '_ {
-        self.locs.iter().map(|(_, v)| *v)
+    /// Returns `true` if there are no entities.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entity indices which have been retired.
+    ///
+    /// Every time an entity is despawned, the generation count of its index is
+    /// incremented so that the old [`EntityId`] no longer refers to anything.
+    /// When the generation count is exhausted, the index is retired instead of
+    /// wrapping around, and no future entity will use it.
+    pub fn retired_slots(&self) -> u32 {
+        self.locs.retired()
+    }
+
+    /// Returns an iterator over the IDs and locations of all entities,
+    /// ordered by [`EntityIdx`].
+    ///
+    /// The iterator borrows the `Entities`, so entities can't be spawned or
+    /// despawned while iterating. Collect the IDs first to despawn entities
+    /// based on the iteration.
+    ///
+    /// ```compile_fail
+    /// # use evenio::prelude::*;
+    /// let mut world = World::new();
+    ///
+    /// for (id, _) in world.entities().iter() {
+    ///     world.despawn(id);
+    /// }
+    /// ```
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # let mut world = World::new();
+    /// let ids: Vec<_> = world.entities().iter().map(|(id, _)| id).collect();
+    ///
+    /// for id in ids {
+    ///     world.despawn(id);
+    /// }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, EntityLocation)> + '_ {
+        self.locs.iter().map(|(k, v)| (EntityId(k), *v))
     }
 }
 
@@ -129,7 +200,7 @@ unsafe impl SystemParam for &'_ Entities {
 }
 
 /// The location of an entity in an archetype.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct EntityLocation {
     /// The archetype where the entity is located.
     pub archetype: ArchetypeIdx,
@@ -141,7 +212,8 @@ pub struct EntityLocation {
 ///
 /// Entity identifiers are implemented using an [index] and a generation count.
 /// The generation count ensures that IDs from despawned entities are not reused
-/// by new entities.
+/// by new entities. Indices whose generation count is exhausted are never
+/// reused (see [`Entities::retired_slots`]).
 ///
 /// An entity identifier is only meaningful in the [`World`] it was created
 /// from. Attempting to use an entity ID in a different world will have
@@ -180,6 +252,354 @@ impl EntityId {
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash, Debug)]
 pub struct EntityIdx(pub u32);
 
+/// An [`EntityId`] which is expected to refer to an entity with component
+/// `C`.
+///
+/// This is meant to be stored in components which refer to other entities,
+/// such as targets or owners. Resolving the reference checks that the entity
+/// is still alive and still has `C`.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::entity::TargetRef;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// #[derive(Component)]
+/// struct Attacker {
+///     target: TargetRef<Health>,
+/// }
+///
+/// let mut world = World::new();
+///
+/// let target = world.spawn();
+/// world.insert(target, Health(10));
+///
+/// let attacker = world.spawn();
+/// world.insert(
+///     attacker,
+///     Attacker {
+///         target: TargetRef::new(target),
+///     },
+/// );
+///
+/// let target_ref = world.get_component::<Attacker>(attacker).unwrap().target;
+/// assert_eq!(target_ref.resolve(&world).unwrap().0, 10);
+///
+/// world.despawn(target);
+/// assert!(target_ref.resolve(&world).is_none());
+/// ```
+#[repr(transparent)]
+pub struct TargetRef<C> {
+    id: EntityId,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C: Component> TargetRef<C> {
+    /// Creates a reference to `id`.
+    pub const fn new(id: EntityId) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the referenced entity.
+    pub const fn id(self) -> EntityId {
+        self.id
+    }
+
+    /// Returns the referenced entity's `C` component, or `None` if the entity
+    /// is despawned or doesn't have it.
+    pub fn resolve(self, world: &World) -> Option<&C> {
+        world.get_component::<C>(self.id)
+    }
+
+    /// Like [`resolve`], but looks the component up through a [`Fetcher`] so
+    /// it can be used inside systems.
+    ///
+    /// [`resolve`]: Self::resolve
+    pub fn resolve_in<'a>(self, fetcher: &'a Fetcher<&C>) -> Option<&'a C> {
+        fetcher.get(self.id).ok()
+    }
+}
+
+impl<C> Clone for TargetRef<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for TargetRef<C> {}
+
+impl<C> PartialEq for TargetRef<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<C> Eq for TargetRef<C> {}
+
+impl<C> Hash for TargetRef<C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<C> fmt::Debug for TargetRef<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TargetRef").field(&self.id).finish()
+    }
+}
+
+/// A component which holds references to other entities and wants to be
+/// notified when they are despawned.
+///
+/// Call [`World::track_targets`] to opt in. From then on, despawning an entity
+/// sends a [`TargetDespawned`] event to every entity whose `Self` component
+/// refers to it.
+///
+/// The targets are read when the component is inserted, so implementors must
+/// be [immutable]. To change the targets, insert a new value.
+///
+/// [immutable]: Component::IS_IMMUTABLE
+pub trait TargetHolder: Component {
+    /// Calls `f` with each entity this component refers to.
+    fn for_each_target(&self, f: impl FnMut(EntityId));
+}
+
+/// A targeted [`Event`] sent to `holder` when an entity referred to by its
+/// [`TargetHolder`] component `H` is about to be despawned.
+///
+/// The event is sent while handling the target's [`Despawn`] event, so the
+/// target still exists when it is received.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::entity::{TargetDespawned, TargetHolder};
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// #[component(immutable)]
+/// struct Owner(EntityId);
+///
+/// impl TargetHolder for Owner {
+///     fn for_each_target(&self, mut f: impl FnMut(EntityId)) {
+///         f(self.0);
+///     }
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.track_targets::<Owner>();
+///
+/// // Despawn items along with their owner.
+/// world.add_system(
+///     |r: Receiver<TargetDespawned<Owner>, ()>, mut s: Sender<Despawn>| {
+///         s.despawn(r.event.holder);
+///     },
+/// );
+///
+/// let owner = world.spawn();
+/// let item = world.spawn();
+/// world.insert(item, Owner(owner));
+///
+/// world.despawn(owner);
+/// assert!(!world.is_alive(item));
+/// ```
+#[derive(Event)]
+pub struct TargetDespawned<H: TargetHolder> {
+    /// The entity with the `H` component.
+    #[event(target)]
+    pub holder: EntityId,
+    /// The entity being despawned.
+    pub despawned: EntityId,
+    _marker: PhantomData<fn() -> H>,
+}
+
+impl<H: TargetHolder> Clone for TargetDespawned<H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H: TargetHolder> Copy for TargetDespawned<H> {}
+
+impl<H: TargetHolder> fmt::Debug for TargetDespawned<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TargetDespawned")
+            .field("holder", &self.holder)
+            .field("despawned", &self.despawned)
+            .finish()
+    }
+}
+
+/// Maps targets to the entities whose `H` component refers to them.
+///
+/// Entries are added when `H` is inserted and removed when it is removed or
+/// its holder is despawned. An entry may outlive the reference if an insert
+/// is dropped, so the holder's component is checked again before it is
+/// notified.
+struct TargetRegistry<H> {
+    holders: BTreeMap<EntityId, BTreeSet<EntityId>>,
+    _marker: PhantomData<fn() -> H>,
+}
+
+impl<H: TargetHolder> TargetRegistry<H> {
+    fn link(&mut self, holder: EntityId, component: &H) {
+        component.for_each_target(|target| {
+            self.holders.entry(target).or_default().insert(holder);
+        });
+    }
+
+    fn unlink(&mut self, holder: EntityId, component: &H) {
+        component.for_each_target(|target| {
+            if let Some(holders) = self.holders.get_mut(&target) {
+                holders.remove(&holder);
+
+                if holders.is_empty() {
+                    self.holders.remove(&target);
+                }
+            }
+        });
+    }
+}
+
+/// Adds the systems that maintain the [`TargetRegistry`] of `H` and send
+/// [`TargetDespawned`].
+pub(crate) fn track_targets<H: TargetHolder>(world: &mut World) {
+    let () = AssertImmutable::<H>::COMPONENT;
+
+    if world.global::<TargetRegistry<H>>().is_some() {
+        return;
+    }
+
+    world.insert_global(TargetRegistry::<H> {
+        holders: BTreeMap::new(),
+        _marker: PhantomData,
+    });
+
+    world.add_system(
+        |r: Receiver<Insert<H>, (EntityId, Option<&H>)>,
+         mut registry: ResMut<TargetRegistry<H>>| {
+            let (holder, old) = r.query;
+
+            if let Some(old) = old {
+                registry.unlink(holder, old);
+            }
+
+            registry.link(holder, &r.event.component);
+        },
+    );
+
+    world.add_system(
+        |r: Receiver<Remove<H>, (EntityId, &H)>, mut registry: ResMut<TargetRegistry<H>>| {
+            let (holder, component) = r.query;
+            registry.unlink(holder, component);
+        },
+    );
+
+    world.add_system(
+        |r: Receiver<Despawn, Option<&H>>,
+         components: Fetcher<&H>,
+         mut registry: ResMut<TargetRegistry<H>>,
+         mut sender: Sender<TargetDespawned<H>>| {
+            let target = r.event.0;
+
+            if let Some(component) = r.query {
+                registry.unlink(target, component);
+            }
+
+            for holder in registry.holders.remove(&target).unwrap_or_default() {
+                let Ok(component) = components.get(holder) else {
+                    continue;
+                };
+
+                let mut refers_to_target = false;
+                component.for_each_target(|id| refers_to_target |= id == target);
+
+                if refers_to_target {
+                    sender.send(TargetDespawned::<H> {
+                        holder,
+                        despawned: target,
+                        _marker: PhantomData,
+                    });
+                }
+            }
+        },
+    );
+}
+
+/// Marks an entity as a child of another entity.
+///
+/// [Bubbling](crate::event::Event::BUBBLES) events move from an entity to
+/// the entity in its `ChildOf` component once the entity's listeners have
+/// run. Entities without a `ChildOf` are the roots of the hierarchy.
+///
+/// Nothing keeps the hierarchy consistent. Despawning a parent leaves its
+/// children pointing at a dead entity, and bubbling stops there.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ChildOf(pub EntityId);
+
+/// Options for [`World::clone_entity_with`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct CloneOptions {
+    /// Leave out components without a [clone function] instead of returning
+    /// an error.
+    ///
+    /// [clone function]: crate::component::ComponentInfo::clone_fn
+    pub skip_uncloneable: bool,
+    /// Add the cloned components with [`Insert`] events, so systems listening
+    /// for them run as if the components were inserted one by one.
+    ///
+    /// Components without a registered `Insert` event are added directly.
+    pub send_insert_events: bool,
+}
+
+/// The error returned by [`World::clone_entity`] and
+/// [`World::clone_entity_with`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CloneError {
+    /// The entity to clone doesn't exist.
+    NoSuchEntity(EntityId),
+    /// The entity has a component without a [clone function], or a
+    /// [singleton] component which wouldn't be inserted with its `Insert`
+    /// event.
+    ///
+    /// [clone function]: crate::component::ComponentInfo::clone_fn
+    /// [singleton]: crate::component::Component::SINGLETON
+    Uncloneable {
+        /// The component that couldn't be cloned.
+        component: ComponentId,
+        /// The name of the component.
+        name: Cow<'static, str>,
+    },
+    /// The archetype for the remaining components couldn't be created.
+    ArchetypeLimitReached(ArchetypeLimitReached),
+}
+
+impl fmt::Display for CloneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloneError::NoSuchEntity(id) => write!(f, "entity {id:?} does not exist"),
+            CloneError::Uncloneable { name, .. } => {
+                write!(f, "component `{name}` cannot be cloned")
+            }
+            CloneError::ArchetypeLimitReached(limit) => write!(
+                f,
+                "the world already contains the maximum of {} archetypes",
+                limit.count
+            ),
+        }
+    }
+}
+
+impl core::error::Error for CloneError {}
+
 /// A queue of entities to be spawned into the world.
 #[derive(Debug)]
 pub(crate) struct ReservedEntities {
@@ -228,7 +648,8 @@ mod tests {
     use alloc::sync::Arc;
     use std::sync::Mutex;
 
-    use crate::entity::Entities;
+    use crate::archetype::{ArchetypeIdx, ArchetypeRow};
+    use crate::entity::{Entities, EntityLocation, TargetDespawned, TargetHolder, TargetRef};
     use crate::prelude::*;
 
     #[test]
@@ -284,11 +705,207 @@ mod tests {
     fn spawn_event_entity_exists() {
         let mut world = World::new();
 
+        world.add_system(|r: Receiver<Spawn, ()>, entities: &Entities| {
+            assert!(entities.contains(r.event.0));
+        });
+    }
+
+    #[test]
+    fn retire_exhausted_index() {
+        let mut entities = Entities::new();
+
+        let loc = EntityLocation {
+            archetype: ArchetypeIdx::EMPTY,
+            row: ArchetypeRow(0),
+        };
+
+        let first = entities.add_with(|_| loc);
+        entities.remove(first);
+
+        // Pretend the index has been reused until its generation is about to run out.
+        entities.locs.set_generation(first.index().0, u32::MAX - 1);
+
+        let last = entities.add_with(|_| loc);
+        assert_eq!(last.index(), first.index());
+        assert_eq!(last.generation(), u32::MAX);
+        assert_eq!(entities.retired_slots(), 0);
+
+        entities.remove(last);
+        assert_eq!(entities.retired_slots(), 1);
+
+        for _ in 0..10 {
+            let e = entities.add_with(|_| loc);
+            assert_ne!(e.index(), first.index());
+        }
+
+        for generation in [1, 3, u32::MAX - 2, u32::MAX] {
+            let stale = EntityId::new(first.index().0, generation).unwrap();
+            assert!(!entities.contains(stale));
+        }
+
+        assert!(entities.get_by_index(first.index()).is_none());
+        assert_eq!(entities.iter().count(), 10);
+    }
+
+    #[test]
+    fn entity_count_and_iter() {
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let check = |world: &World, expected: &[EntityId]| {
+            assert_eq!(world.entity_count(), expected.len() as u32);
+            assert_eq!(
+                world.archetypes().entity_count_total(),
+                world.entity_count()
+            );
+
+            let ids: Vec<_> = world
+                .entities()
+                .iter()
+                .map(|(id, loc)| {
+                    assert_eq!(world.entities().get(id), Some(loc));
+                    id
+                })
+                .collect();
+
+            assert_eq!(ids, expected);
+        };
+
+        check(&world, &[]);
+        assert!(world.entities().is_empty());
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        let e3 = world.spawn();
+        check(&world, &[e1, e2, e3]);
+
+        // Moving between archetypes doesn't change the count.
+        world.insert(e2, C);
+        world.remove::<C>(e2);
+        world.insert(e3, C);
+        check(&world, &[e1, e2, e3]);
+
+        world.despawn(e1);
+        check(&world, &[e2, e3]);
+
+        // The slot of `e1` is reused with a new generation.
+        let e4 = world.spawn();
+        assert_eq!(e4.index(), e1.index());
+        assert_ne!(e4, e1);
+        check(&world, &[e4, e2, e3]);
+
+        let ids: Vec<_> = world.entities().iter().map(|(id, _)| id).collect();
+        for id in ids {
+            world.despawn(id);
+        }
+
+        check(&world, &[]);
+    }
+
+    #[test]
+    fn target_ref() {
+        #[derive(Component)]
+        struct Health(u32);
+
+        #[derive(Component)]
+        struct Target(TargetRef<Health>);
+
         #[derive(Event)]
         struct E;
 
-        world.add_system(|r: Receiver<Spawn, ()>, entities: &Entities| {
-            assert!(entities.contains(r.event.0));
+        let mut world = World::new();
+
+        let target = world.spawn();
+        world.insert(target, Health(5));
+
+        let attacker = world.spawn();
+        world.insert(attacker, Target(TargetRef::new(target)));
+
+        let target_ref = world.get_component::<Target>(attacker).unwrap().0;
+        assert_eq!(target_ref.id(), target);
+        assert_eq!(target_ref.resolve(&world).unwrap().0, 5);
+
+        let seen = Arc::new(Mutex::new(vec![]));
+
+        let seen_clone = seen.clone();
+        world.add_system(
+            move |_: Receiver<E>, attackers: Fetcher<&Target>, health: Fetcher<&Health>| {
+                for t in attackers {
+                    seen_clone
+                        .lock()
+                        .unwrap()
+                        .push(t.0.resolve_in(&health).map(|h| h.0));
+                }
+            },
+        );
+
+        world.send(E);
+        world.remove::<Health>(target);
+        world.send(E);
+        world.insert(target, Health(7));
+        world.despawn(target);
+        world.send(E);
+
+        assert!(target_ref.resolve(&world).is_none());
+        assert_eq!(*seen.lock().unwrap(), [Some(5), None, None]);
+    }
+
+    #[test]
+    fn track_targets() {
+        #[derive(Component)]
+        #[component(immutable)]
+        struct Refs(Vec<EntityId>);
+
+        impl TargetHolder for Refs {
+            fn for_each_target(&self, f: impl FnMut(EntityId)) {
+                self.0.iter().copied().for_each(f);
+            }
+        }
+
+        let mut world = World::new();
+
+        world.track_targets::<Refs>();
+        world.track_targets::<Refs>();
+
+        let notified = Arc::new(Mutex::new(vec![]));
+
+        let notified_clone = notified.clone();
+        world.add_system(move |r: Receiver<TargetDespawned<Refs>, ()>| {
+            notified_clone
+                .lock()
+                .unwrap()
+                .push((r.event.holder, r.event.despawned));
         });
+
+        let t1 = world.spawn();
+        let t2 = world.spawn();
+
+        let a = world.spawn();
+        world.insert(a, Refs(vec![t1, t2]));
+
+        // `b` switches from `t1` to `t2`.
+        let b = world.spawn();
+        world.insert(b, Refs(vec![t1]));
+        world.insert(b, Refs(vec![t2]));
+
+        // `c` stops referring to anything.
+        let c = world.spawn();
+        world.insert(c, Refs(vec![t1]));
+        world.remove::<Refs>(c);
+
+        // `d` is despawned before its target.
+        let d = world.spawn();
+        world.insert(d, Refs(vec![t1, d]));
+        world.despawn(d);
+
+        world.despawn(t1);
+        assert_eq!(*notified.lock().unwrap(), [(a, t1)]);
+
+        notified.lock().unwrap().clear();
+
+        world.despawn(t2);
+        assert_eq!(*notified.lock().unwrap(), [(a, t2), (b, t2)]);
     }
 }
@@ -0,0 +1,339 @@
+//! Entity groups, which split the entities of a world into disjoint sets.
+//!
+//! Every entity belongs to exactly one entity group. Entities are in the
+//! [default group](EntityGroupId::DEFAULT) unless they are spawned with
+//! [`World::spawn_in`] or moved with [`World::set_entity_group`]. Within each
+//! archetype, the entities of a group occupy a contiguous range of rows, so
+//! [`Fetcher::iter_in_group`] visits only the entities of the group instead of
+//! filtering every entity matching the query.
+//!
+//! Keeping the rows contiguous costs a few extra row swaps when entities enter
+//! or leave an archetype which holds several groups. Archetypes with only the
+//! default group are unaffected.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::entity_group::EntityGroupId;
+//! use evenio::prelude::*;
+//!
+//! #[derive(Component)]
+//! struct Health(u32);
+//!
+//! #[derive(Event)]
+//! struct Tick(EntityGroupId);
+//!
+//! let mut world = World::new();
+//!
+//! let arena = world.add_entity_group();
+//!
+//! world.add_system(|r: Receiver<Tick>, mut f: Fetcher<&mut Health>| {
+//!     for health in f.iter_in_group_mut(r.event.0) {
+//!         health.0 -= 1;
+//!     }
+//! });
+//!
+//! let a = world.spawn_in(arena);
+//! world.insert(a, Health(10));
+//!
+//! let b = world.spawn();
+//! world.insert(b, Health(10));
+//!
+//! world.send(Tick(arena));
+//!
+//! assert_eq!(world.get::<&Health>(a).unwrap().0, 9);
+//! assert_eq!(world.get::<&Health>(b).unwrap().0, 10);
+//! ```
+//!
+//! [`World::spawn_in`]: crate::world::World::spawn_in
+//! [`World::set_entity_group`]: crate::world::World::set_entity_group
+//! [`Fetcher::iter_in_group`]: crate::fetch::Fetcher::iter_in_group
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+/// Identifies an entity group in a world. See the [module
+/// documentation](self) for more information.
+///
+/// The IDs of removed groups are never reused.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct EntityGroupId(u32);
+
+impl EntityGroupId {
+    /// The group entities are in unless they are assigned to another one. The
+    /// default group always exists. This is the default value for
+    /// `EntityGroupId`.
+    pub const DEFAULT: Self = Self(0);
+
+    /// Returns the index of this group.
+    pub const fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// The entity groups of a world. Obtained with [`World::entity_groups`].
+///
+/// [`World::entity_groups`]: crate::world::World::entity_groups
+#[derive(Debug)]
+pub struct EntityGroups {
+    /// Whether each group created so far still exists, by index.
+    live: Vec<bool>,
+}
+
+impl EntityGroups {
+    pub(crate) fn new() -> Self {
+        Self { live: vec![true] }
+    }
+
+    /// Returns whether the group exists.
+    pub fn contains(&self, id: EntityGroupId) -> bool {
+        self.live.get(id.0 as usize).copied().unwrap_or(false)
+    }
+
+    /// Returns an iterator over the groups in the world, including the
+    /// default group.
+    pub fn iter(&self) -> impl Iterator<Item = EntityGroupId> + '_ {
+        self.live
+            .iter()
+            .enumerate()
+            .filter(|(_, &live)| live)
+            .map(|(i, _)| EntityGroupId(i as u32))
+    }
+
+    /// Adds a group and returns its ID, or `None` if every ID is used up.
+    pub(crate) fn add(&mut self) -> Option<EntityGroupId> {
+        let id = EntityGroupId(u32::try_from(self.live.len()).ok()?);
+        self.live.push(true);
+        Some(id)
+    }
+
+    /// Removes a group. Returns whether the group existed.
+    pub(crate) fn remove(&mut self, id: EntityGroupId) -> bool {
+        if id == EntityGroupId::DEFAULT {
+            return false;
+        }
+
+        match self.live.get_mut(id.0 as usize) {
+            Some(live) => mem::replace(live, false),
+            None => false,
+        }
+    }
+}
+
+/// Statistics for an entity group, returned by [`World::entity_group_stats`].
+///
+/// [`World::entity_group_stats`]: crate::world::World::entity_group_stats
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct EntityGroupStats {
+    /// The number of entities in the group.
+    pub entity_count: u32,
+    /// The number of archetypes with entities in the group.
+    pub archetype_count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+
+    use crate::entity_group::{EntityGroupId, EntityGroupStats};
+    use crate::prelude::*;
+
+    #[derive(Component, Clone)]
+    struct A(u32);
+
+    #[derive(Component)]
+    struct B(#[allow(dead_code)] String);
+
+    #[derive(Component)]
+    #[component(preserve_order)]
+    struct Ordered;
+
+    fn members(world: &mut World, group: EntityGroupId) -> Vec<EntityId> {
+        let mut members = world
+            .query::<EntityId>()
+            .iter_in_group(group)
+            .collect::<Vec<_>>();
+        members.sort();
+        members
+    }
+
+    #[test]
+    fn groups_stay_contiguous() {
+        let mut world = World::new();
+
+        let groups = [
+            EntityGroupId::DEFAULT,
+            world.add_entity_group(),
+            world.add_entity_group(),
+        ];
+
+        let mut model = BTreeMap::new();
+        let mut rng = 0x2545_f491_u32;
+        let mut next = move |n: u32| {
+            rng ^= rng << 13;
+            rng ^= rng >> 17;
+            rng ^= rng << 5;
+            rng % n
+        };
+
+        for _ in 0..2000 {
+            let ids = model.keys().copied().collect::<Vec<EntityId>>();
+            let pick = (!ids.is_empty()).then(|| ids[next(ids.len() as u32) as usize]);
+
+            match (next(8), pick) {
+                (0 | 1, _) | (_, None) => {
+                    let group = groups[next(3) as usize];
+                    model.insert(world.spawn_in(group), group);
+                }
+                (2, Some(e)) => world.insert(e, A(next(100))),
+                (3, Some(e)) => world.insert(e, B(String::new())),
+                (4, Some(e)) => world.insert(e, Ordered),
+                (5, Some(e)) => match next(3) {
+                    0 => world.remove::<A>(e),
+                    1 => world.remove::<B>(e),
+                    _ => world.remove::<Ordered>(e),
+                },
+                (6, Some(e)) => {
+                    let group = groups[next(3) as usize];
+                    assert_eq!(world.set_entity_group(e, group), model.insert(e, group));
+                }
+                // Moves whole archetypes at once.
+                (7, Some(_)) if next(10) == 0 => world.insert_for_all::<With<&Ordered>, _>(A(0)),
+                (_, Some(e)) => {
+                    world.despawn(e);
+                    model.remove(&e);
+                }
+            }
+
+            world.check_invariants();
+        }
+
+        for group in groups {
+            let expected = model
+                .iter()
+                .filter(|&(_, &g)| g == group)
+                .map(|(&e, _)| e)
+                .collect::<Vec<_>>();
+
+            assert_eq!(members(&mut world, group), expected);
+
+            for &e in &expected {
+                assert_eq!(world.entity_group(e), Some(group));
+            }
+        }
+    }
+
+    #[test]
+    fn ordered_archetypes_keep_order_within_groups() {
+        #[derive(Component)]
+        #[component(preserve_order)]
+        struct Index(u32);
+
+        let mut world = World::new();
+
+        let group = world.add_entity_group();
+
+        let entities = (0..8)
+            .map(|i| {
+                let e = if i % 2 == 0 {
+                    world.spawn_in(group)
+                } else {
+                    world.spawn()
+                };
+                world.insert(e, Index(i));
+                e
+            })
+            .collect::<Vec<_>>();
+
+        world.despawn(entities[2]);
+        world.set_entity_group(entities[3], group);
+        world.check_invariants();
+
+        let indices = |world: &mut World, group| {
+            world
+                .query::<&Index>()
+                .iter_in_group(group)
+                .map(|i| i.0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(indices(&mut world, group), [0, 4, 6, 3]);
+        assert_eq!(indices(&mut world, EntityGroupId::DEFAULT), [1, 5, 7]);
+    }
+
+    #[test]
+    fn iter_in_group_mut() {
+        #[derive(Event)]
+        struct Tick(EntityGroupId);
+
+        let mut world = World::new();
+
+        let group = world.add_entity_group();
+
+        world.add_system(|r: Receiver<Tick>, mut f: Fetcher<&mut A>| {
+            for a in f.iter_in_group_mut(r.event.0) {
+                a.0 += 1;
+            }
+        });
+
+        let a = world.spawn_in(group);
+        world.insert(a, A(0));
+        let b = world.spawn();
+        world.insert(b, A(0));
+        let c = world.clone_entity(a).unwrap();
+
+        world.send(Tick(group));
+
+        assert_eq!(world.entity_group(c), Some(group));
+        assert_eq!(world.get_component::<A>(a).unwrap().0, 1);
+        assert_eq!(world.get_component::<A>(b).unwrap().0, 0);
+        assert_eq!(world.get_component::<A>(c).unwrap().0, 1);
+    }
+
+    #[test]
+    fn remove_entity_group() {
+        let mut world = World::new();
+
+        let group = world.add_entity_group();
+
+        let doomed = world.spawn_in(group);
+        world.insert(doomed, A(1));
+
+        let survivor = world.spawn_in(group);
+        world.insert(survivor, B(String::new()));
+
+        let bystander = world.spawn();
+        world.insert(bystander, A(2));
+
+        world.add_system(|r: ReceiverMut<Despawn, With<&B>>| {
+            EventMut::take(r.event);
+        });
+
+        assert_eq!(
+            world.entity_group_stats(group),
+            Some(EntityGroupStats {
+                entity_count: 2,
+                archetype_count: 2,
+            })
+        );
+
+        assert!(world.remove_entity_group(group));
+        assert!(!world.remove_entity_group(group));
+        assert!(!world.remove_entity_group(EntityGroupId::DEFAULT));
+
+        world.check_invariants();
+
+        assert!(!world.entities().contains(doomed));
+        assert_eq!(world.entity_group(survivor), Some(EntityGroupId::DEFAULT));
+        assert_eq!(world.entity_group(bystander), Some(EntityGroupId::DEFAULT));
+        assert_eq!(world.entity_group_stats(group), None);
+        assert_eq!(
+            world.entity_groups().iter().collect::<Vec<_>>(),
+            [EntityGroupId::DEFAULT]
+        );
+
+        // IDs of removed groups aren't reused.
+        assert_ne!(world.add_entity_group(), group);
+    }
+}
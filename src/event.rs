@@ -1,8 +1,8 @@
 //! Types for sending and receiving [`Event`]s.
 
 use alloc::borrow::Cow;
-use alloc::collections::btree_map::Entry;
-use alloc::collections::BTreeMap;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
 use alloc::vec::Vec;
 use alloc::{format, vec};
 use core::alloc::Layout;
@@ -10,12 +10,12 @@ use core::any::TypeId;
 use core::marker::PhantomData;
 use core::num::NonZeroU32;
 use core::ops::{Deref, DerefMut, Index};
-use core::ptr::NonNull;
-use core::{any, fmt};
+use core::ptr::{self, NonNull};
+use core::{any, fmt, mem};
 
 use bumpalo::Bump;
 use evenio_macros::all_tuples;
-pub use evenio_macros::Event;
+pub use evenio_macros::{Event, EventEnum};
 use memoffset::offset_of;
 
 use crate::access::Access;
@@ -23,16 +23,18 @@ use crate::archetype::Archetype;
 use crate::assert::{
     AssertMutable, AssertTargetedEvent, AssertUntargetedEvent, GetDebugChecked, UnwrapDebugChecked,
 };
-use crate::component::ComponentIdx;
+use crate::bool_expr::BoolExpr;
+use crate::component::{clone_fn_of, CloneFn, ComponentIdx, DefaultFn, NameError, NameIndex};
 use crate::drop::DropFn;
 use crate::entity::EntityId;
 use crate::fetch::FetcherState;
 use crate::prelude::Component;
 use crate::query::Query;
+use crate::schedule::ScheduledEventId;
 use crate::slot_map::{Key, SlotMap};
 use crate::sparse::SparseIndex;
 use crate::system::{Config, InitError, SystemInfo, SystemParam};
-use crate::world::{UnsafeWorldCell, World};
+use crate::world::{UnsafeWorldCell, World, WorldId};
 
 /// Stores metadata for all [`Event`]s in the world.
 ///
@@ -48,42 +50,103 @@ use crate::world::{UnsafeWorldCell, World};
 /// # let mut world = World::new();
 /// world.add_system(|_: Receiver<E>, events: &Events| {});
 #[derive(Debug)]
+#[allow(clippy::struct_field_names)]
 pub struct Events {
     untargeted_events: SlotMap<EventInfo>,
     targeted_events: SlotMap<EventInfo>,
     by_type_id: BTreeMap<TypeId, EventId>,
+    /// Direct-mapped cache in front of `by_type_id` for the event types
+    /// which were sent recently. See [`Events::cached_idx_by_type_id`].
+    type_cache: Box<[Option<(TypeId, u32)>; TYPE_CACHE_LEN]>,
+    by_key: BTreeMap<Cow<'static, str>, EventId>,
+    by_name: NameIndex<EventId>,
+    world: WorldId,
 }
 
+/// The number of entries in [`Events::type_cache`]. Must be a power of two.
+const TYPE_CACHE_LEN: usize = 64;
+
 impl Events {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(world: WorldId) -> Self {
+        // `SpawnQueued` is registered before the world ID is assigned so that its
+        // ID matches `EventId::SPAWN_QUEUED` in every world.
         let mut this = Self {
             untargeted_events: SlotMap::new(),
             targeted_events: SlotMap::new(),
             by_type_id: BTreeMap::new(),
+            type_cache: Box::new([None; TYPE_CACHE_LEN]),
+            by_key: BTreeMap::new(),
+            by_name: NameIndex::new(),
+            world: WorldId::NONE,
         };
 
         this.add(EventDescriptor {
             name: any::type_name::<SpawnQueued>().into(),
             type_id: None,
+            key: None,
             is_targeted: false,
             kind: EventKind::SpawnQueued,
             layout: Layout::new::<SpawnQueued>(),
             drop: None,
+            clone_fn: None,
+            default_fn: None,
             is_immutable: true,
+            is_deferred: false,
+            bubbles: None,
+            targets: None,
+            target_filter: BoolExpr::new(true),
         });
 
+        this.world = world;
+
         this
     }
 
+    #[track_caller]
     pub(crate) fn add(&mut self, desc: EventDescriptor) -> (EventId, bool) {
+        if let Some(type_id) = desc.type_id {
+            if let Some(&id) = self.by_type_id.get(&type_id) {
+                return (id, false);
+            }
+        }
+
+        // Like components, events compiled into several libraries are matched
+        // by their key.
+        if let Some(key) = &desc.key {
+            if let Some(&id) = self.by_key.get(key) {
+                let existing = unsafe { self.get(id).unwrap_debug_checked() };
+
+                assert!(
+                    existing.layout == desc.layout && id.is_targeted() == desc.is_targeted,
+                    "cannot add event with key `{key}` because its layout or targeting differs \
+                     from the existing event with the key"
+                );
+
+                if let Some(type_id) = desc.type_id {
+                    self.by_type_id.insert(type_id, id);
+                }
+
+                return (id, false);
+            }
+        }
+
+        let key = desc.key.clone();
+
         let info = EventInfo {
             id: EventId::NULL,
             name: desc.name,
             kind: desc.kind,
             type_id: desc.type_id,
+            key: desc.key,
             layout: desc.layout,
             drop: desc.drop,
+            clone: desc.clone_fn,
+            default: desc.default_fn,
             is_immutable: desc.is_immutable,
+            is_deferred: desc.is_deferred,
+            bubbles: desc.bubbles,
+            targets: desc.targets,
+            target_filter: desc.target_filter,
         };
 
         let insert = || {
@@ -96,25 +159,33 @@ impl Events {
             let Some(k) = map.insert(info) else {
                 panic!("too many events")
             };
-            let id = EventId::from_key(k, desc.is_targeted);
+            let id = EventId::from_key(k, desc.is_targeted, self.world);
             map[k].id = id;
 
+            self.by_name.insert(&map[k].name, id);
+
             id
         };
 
+        let id = insert();
+
+        if let Some(key) = key {
+            self.by_key.insert(key, id);
+        }
+
         if let Some(type_id) = desc.type_id {
-            match self.by_type_id.entry(type_id) {
-                Entry::Vacant(v) => (*v.insert(insert()), true),
-                Entry::Occupied(o) => (*o.get(), false),
-            }
+            self.by_type_id.insert(type_id, id);
+            (id, true)
         } else {
-            (insert(), false)
+            (id, false)
         }
     }
 
     /// Gets the [`EventInfo`] of the given event. Returns `None` if the ID is
     /// invalid.
     pub fn get(&self, id: EventId) -> Option<&EventInfo> {
+        self.debug_assert_same_world(id);
+
         let k = id.as_key();
         match id.index() {
             EventIdx::Targeted(_) => self.targeted_events.get(k),
@@ -141,13 +212,70 @@ impl Events {
         Some(unsafe { self.get(idx).unwrap_debug_checked() })
     }
 
+    /// Like [`get_by_type_id`](Self::get_by_type_id), but returns the raw
+    /// event index and remembers it, so that sending the same event type again
+    /// doesn't search `by_type_id`.
+    #[inline]
+    pub(crate) fn cached_idx_by_type_id(&mut self, type_id: TypeId) -> Option<u32> {
+        let slot = type_cache_slot(type_id);
+
+        if let Some((cached, idx)) = self.type_cache[slot] {
+            if cached == type_id {
+                return Some(idx);
+            }
+        }
+
+        let idx = self.get_by_type_id(type_id)?.id().index().as_u32();
+        self.type_cache[slot] = Some((type_id, idx));
+
+        Some(idx)
+    }
+
+    /// Gets the [`EventInfo`] of the event with the given
+    /// [key](EventInfo::key). Returns `None` if no event has the key.
+    pub fn get_by_key(&self, key: &str) -> Option<&EventInfo> {
+        let id = *self.by_key.get(key)?;
+        Some(unsafe { self.get(id).unwrap_debug_checked() })
+    }
+
+    /// Gets the [`EventInfo`] of the event with the given
+    /// [name](EventInfo::name).
+    ///
+    /// Like [`Components::get_by_name`], [`NameError::Ambiguous`] is returned
+    /// if several events have the name. Use [`iter_by_name`] to tell them
+    /// apart.
+    ///
+    /// [`Components::get_by_name`]: crate::component::Components::get_by_name
+    /// [`iter_by_name`]: Self::iter_by_name
+    pub fn get_by_name(&self, name: &str) -> Result<&EventInfo, NameError> {
+        let id = self.by_name.get(name)?;
+        Ok(unsafe { self.get(id).unwrap_debug_checked() })
+    }
+
+    /// Returns an iterator over the events with the given
+    /// [name](EventInfo::name), in the order they were added.
+    pub fn iter_by_name<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a EventInfo> {
+        self.by_name
+            .get_all(name)
+            .iter()
+            .map(|&id| unsafe { self.get(id).unwrap_debug_checked() })
+    }
+
     /// Does the given event exist in the world?
     pub fn contains(&self, id: EventId) -> bool {
         self.get(id).is_some()
     }
 
+    /// Keeps the indices of the current events from being reused by events
+    /// added later.
+    pub(crate) fn pin_indices(&mut self) {
+        self.untargeted_events.pin_existing();
+        self.targeted_events.pin_existing();
+    }
+
     pub(crate) fn remove(&mut self, id: EventId) -> Option<EventInfo> {
         debug_assert_ne!(id, EventId::SPAWN_QUEUED);
+        self.debug_assert_same_world(id);
 
         let k = id.as_key();
 
@@ -157,13 +285,34 @@ impl Events {
             self.untargeted_events.remove(k)
         }?;
 
-        if let Some(type_id) = info.type_id {
+        if let Some(key) = &info.key {
+            self.by_key.remove(key);
+            self.by_type_id.retain(|_, &mut other| other != id);
+        } else if let Some(type_id) = info.type_id {
             self.by_type_id.remove(&type_id);
         }
 
+        self.by_name.remove(&info.name, id);
+
+        // The index may be reused by the next event.
+        let idx = id.index().as_u32();
+        for entry in self.type_cache.iter_mut() {
+            if entry.is_some_and(|(_, cached)| cached == idx) {
+                *entry = None;
+            }
+        }
+
         Some(info)
     }
 
+    #[track_caller]
+    fn debug_assert_same_world(&self, id: EventId) {
+        debug_assert!(
+            id.world.is_compatible(self.world),
+            "event ID {id:?} belongs to a different world"
+        );
+    }
+
     /// Returns an iterator over all event infos.
     pub fn iter(&self) -> impl Iterator<Item = &EventInfo> {
         self.targeted_events
@@ -173,6 +322,41 @@ impl Events {
     }
 }
 
+/// Returns the slot of [`Events::type_cache`] for `type_id`. This is usually
+/// constant folded, since the type ID of a sent event is known at compile
+/// time.
+#[inline]
+fn type_cache_slot(type_id: TypeId) -> usize {
+    use core::hash::{Hash, Hasher};
+
+    /// Keeps the bits written by `TypeId`'s `Hash` impl, which are already
+    /// well distributed.
+    struct TypeIdHasher(u64);
+
+    impl Hasher for TypeIdHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = self.0.rotate_left(8) ^ u64::from(b);
+            }
+        }
+
+        fn write_u64(&mut self, n: u64) {
+            self.0 ^= n;
+        }
+    }
+
+    let mut hasher = TypeIdHasher(0);
+    type_id.hash(&mut hasher);
+
+    // Fibonacci hashing, in case the bits aren't as well distributed as hoped.
+    (hasher.finish().wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (64 - TYPE_CACHE_LEN.trailing_zeros()))
+        as usize
+}
+
 impl Index<EventId> for Events {
     type Output = EventInfo;
 
@@ -245,6 +429,11 @@ unsafe impl SystemParam for &'_ Events {
 /// Targeted events allow systems to efficiently filter out events whose target
 /// does not match a particular query.
 ///
+/// The systems receiving a targeted event are chosen when the event begins
+/// broadcasting. If one of them despawns the target, the remaining systems are
+/// skipped. If one of them changes the target's components, each remaining
+/// system is skipped unless the target still matches its query.
+///
 /// # Deriving
 ///
 /// The `Event` trait is automatically implementable by using the associated
@@ -262,8 +451,20 @@ unsafe impl SystemParam for &'_ Events {
 ///     entity: EntityId,
 /// }
 ///
+/// // The target can also be a field nested inside another field.
+/// #[derive(Event)]
+/// #[event(target = "contact.receiver")]
+/// struct Hit {
+///     contact: Contact,
+/// }
+///
+/// struct Contact {
+///     receiver: EntityId,
+///     normal: [f32; 3],
+/// }
+///
 /// // Also works on tuple structs, enums, and unions.
-/// // However, `#[event(target)]` is unavailable for non-struct types.
+/// // However, `#[event(target)]` is unavailable for unions.
 ///
 /// #[derive(Event)]
 /// struct TupleStruct(i32, #[event(target)] EntityId);
@@ -274,6 +475,17 @@ unsafe impl SystemParam for &'_ Events {
 ///     Bar(f32),
 /// }
 ///
+/// // An enum is targeted if each of its variants has a target field.
+/// #[derive(Event)]
+/// enum Input {
+///     Key(#[event(target)] EntityId, char),
+///     Click {
+///         #[event(target)]
+///         target: EntityId,
+///         pos: [f32; 2],
+///     },
+/// }
+///
 /// #[derive(Event)]
 /// union Union {
 ///     foo: i32,
@@ -283,6 +495,27 @@ unsafe impl SystemParam for &'_ Events {
 /// #[derive(Event)]
 /// struct EmptyEvent;
 /// ```
+///
+/// Either all of the variants of an enum have a target or none of them do.
+///
+/// ```compile_fail
+/// # use evenio::prelude::*;
+/// #[derive(Event)]
+/// enum Input {
+///     Key(#[event(target)] EntityId, char),
+///     Resize { width: u32, height: u32 },
+/// }
+/// ```
+///
+/// Targets must be [`EntityId`]s.
+///
+/// ```compile_fail
+/// # use evenio::prelude::*;
+/// #[derive(Event)]
+/// enum Input {
+///     Key(#[event(target)] u64, char),
+/// }
+/// ```
 pub trait Event: Send + Sync + 'static {
     /// If this event is considered "targeted" or "untargeted".
     ///
@@ -299,6 +532,59 @@ pub trait Event: Send + Sync + 'static {
     /// are not altered during their lifespan.
     const IS_IMMUTABLE: bool = false;
 
+    /// Whether or not this event is handled in first-in-first-out order.
+    ///
+    /// By default, events sent from within a system are handled immediately
+    /// after the system returns, before the remaining listeners of the current
+    /// event run (depth-first). Deferred events are instead appended to the
+    /// back of a separate queue and handled once all non-deferred events have
+    /// finished broadcasting (breadth-first). Deferred events are always
+    /// handled in the order they were sent.
+    ///
+    /// Deferred and non-deferred events may be used together in the same
+    /// world.
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// #[event(deferred)]
+    /// struct MyEvent;
+    /// ```
+    const IS_DEFERRED: bool = false;
+
+    /// A key identifying this event across compilation units, or `None` to
+    /// only identify it by its [`TypeId`].
+    ///
+    /// This works like [`Component::KEY`]. Adding an event with the key of an
+    /// existing event with a different [`Layout`] or targeting panics. It can
+    /// be set with `#[event(uuid = "...")]` or `#[event(key)]` when deriving.
+    ///
+    /// [`Component::KEY`]: crate::component::Component::KEY
+    const KEY: Option<&'static str> = None;
+
+    /// Whether or not this targeted event propagates up the entity hierarchy.
+    ///
+    /// Once the listeners for the target have run, a bubbling event which
+    /// hasn't been consumed with [`EventMut::take`] is retargeted to the
+    /// parent in the target's [`ChildOf`] component and broadcast again. This
+    /// repeats until the event is consumed, an entity without a parent is
+    /// reached, or the event has moved [`MAX_BUBBLE_DEPTH`] times.
+    ///
+    /// The entity the event was originally sent to is available from
+    /// [`Receiver::original_target`]. This has no effect on untargeted events.
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// #[event(bubbles)]
+    /// struct Click(#[event(target)] EntityId);
+    /// ```
+    ///
+    /// [`ChildOf`]: crate::entity::ChildOf
+    const BUBBLES: bool = false;
+
     /// Returns the [`EntityId`] target of this event.
     ///
     /// If [`IS_TARGETED`] is `false`, then the result is unspecified. The
@@ -309,6 +595,129 @@ pub trait Event: Send + Sync + 'static {
         unimplemented!()
     }
 
+    /// Returns every [`EntityId`] this event is sent to, in order.
+    ///
+    /// The event is broadcast to each target separately, running the
+    /// listeners of the first target before those of the next. A target which
+    /// appears more than once receives the event once per occurrence, and
+    /// targets that don't exist are skipped. Consuming the event with
+    /// [`EventMut::take`] stops it from reaching the remaining targets.
+    ///
+    /// Receivers fetch their query from the target currently being visited,
+    /// which is available from [`Receiver::original_target`].
+    ///
+    /// If [`IS_TARGETED`] is `false`, then the result is unspecified. The
+    /// default implementation returns [`target`] alone.
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// struct Attack {
+    ///     attacker: EntityId,
+    ///     victim: EntityId,
+    /// }
+    ///
+    /// impl Event for Attack {
+    ///     const IS_TARGETED: bool = true;
+    ///
+    ///     fn target(&self) -> EntityId {
+    ///         self.victim
+    ///     }
+    ///
+    ///     fn targets(&self) -> impl Iterator<Item = EntityId> {
+    ///         [self.attacker, self.victim].into_iter()
+    ///     }
+    ///
+    ///     fn set_target(&mut self, target: EntityId) {
+    ///         self.victim = target;
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`IS_TARGETED`]: Event::IS_TARGETED
+    /// [`target`]: Event::target
+    fn targets(&self) -> impl Iterator<Item = EntityId> {
+        core::iter::once(self.target())
+    }
+
+    /// Changes the [`EntityId`] target of this event.
+    ///
+    /// This is used by [`Sender::forward`] to retarget a copy of a received
+    /// event. If [`IS_TARGETED`] is `false`, then the behavior is unspecified.
+    /// The default implementation panics.
+    ///
+    /// [`IS_TARGETED`]: Event::IS_TARGETED
+    fn set_target(&mut self, target: EntityId) {
+        let _ = target;
+        panic!(
+            "`set_target` must be implemented for targeted events forwarded with \
+             `Sender::forward`, but `{}` doesn't implement it",
+            any::type_name::<Self>()
+        )
+    }
+
+    /// Registers the components the target of this event must have or must not
+    /// have.
+    ///
+    /// This is called once when the event is added to the world. A system only
+    /// receives the event if the target matches both the filter and the
+    /// system's own query. Events whose target doesn't match the filter are
+    /// dropped without running any systems. See
+    /// [`World::set_filtered_event_hook`] for observing these events.
+    ///
+    /// The filter has no effect on untargeted events. The derive macro sets
+    /// the filter from the `requires` and `excludes` arguments.
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(i32);
+    ///
+    /// #[derive(Component)]
+    /// struct Dead;
+    ///
+    /// #[derive(Event)]
+    /// #[event(requires(Health), excludes(Dead))]
+    /// struct Damage {
+    ///     amount: i32,
+    ///     #[event(target)]
+    ///     entity: EntityId,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// // Only receives `Damage` events targeting entities with `Health` and without `Dead`.
+    /// world.add_system(|r: Receiver<Damage, &mut Health>| r.query.0 -= r.event.amount);
+    /// ```
+    fn target_filter(filter: &mut TargetFilter) {
+        let _ = filter;
+    }
+
+    /// Returns the [`CloneFn`] used to copy this event, or `None` if the
+    /// event can't be copied. Events are copied when they're recorded by
+    /// `World::start_recording` so that they can be replayed later.
+    ///
+    /// The derive macro returns [`clone_fn_of`] for events which implement
+    /// [`Clone`], like [`Component::clone_fn`].
+    ///
+    /// [`clone_fn_of`]: crate::component::clone_fn_of
+    fn clone_fn() -> Option<CloneFn> {
+        None
+    }
+
+    /// Returns the [`DefaultFn`] used to create this event, or `None` if the
+    /// event has no default value. This lets the event be sent by ID with
+    /// [`World::send_dynamic_default`].
+    ///
+    /// The derive macro returns [`default_fn_of`] for events which implement
+    /// [`Default`].
+    ///
+    /// [`default_fn_of`]: crate::component::default_fn_of
+    fn default_fn() -> Option<DefaultFn> {
+        None
+    }
+
     /// Gets the [`EventKind`] of this event and performs any necessary
     /// initialization work.
     ///
@@ -350,12 +759,134 @@ pub enum EventKind {
         /// The [`ComponentIdx`] of the component to remove.
         component_idx: ComponentIdx,
     },
+    /// The [`Removed`] event.
+    Removed {
+        /// The [`ComponentIdx`] of the removed component.
+        component_idx: ComponentIdx,
+    },
+    /// The [`Replaced`] event.
+    Replaced {
+        /// The [`ComponentIdx`] of the replaced component.
+        component_idx: ComponentIdx,
+        /// Cached offset from the beginning of the event to the
+        /// [`Replaced::old`] field.
+        component_offset: u32,
+    },
     /// The event which spawns one queued entity. For internal use only.
     SpawnQueued,
     /// The [`Despawn`] event.
     Despawn,
 }
 
+/// Builder for the [target filter] of an [`Event`]. Passed to
+/// [`Event::target_filter`].
+///
+/// [target filter]: Event::target_filter
+#[derive(Debug)]
+pub struct TargetFilter<'a> {
+    world: &'a mut World,
+    expr: BoolExpr<ComponentIdx>,
+}
+
+impl<'a> TargetFilter<'a> {
+    pub(crate) fn new(world: &'a mut World) -> Self {
+        Self {
+            world,
+            expr: BoolExpr::new(true),
+        }
+    }
+
+    /// Requires the target to have component `C`. The component is added to
+    /// the world if it doesn't exist already.
+    pub fn requires<C: Component>(&mut self) -> &mut Self {
+        let idx = self.world.add_component::<C>().index();
+        self.expr = mem::replace(&mut self.expr, BoolExpr::new(true)).and(&BoolExpr::var(idx));
+        self
+    }
+
+    /// Requires the target to not have component `C`. The component is added
+    /// to the world if it doesn't exist already.
+    pub fn excludes<C: Component>(&mut self) -> &mut Self {
+        let idx = self.world.add_component::<C>().index();
+        self.expr = mem::replace(&mut self.expr, BoolExpr::new(true)).and(&BoolExpr::not_var(idx));
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> BoolExpr<ComponentIdx> {
+        self.expr
+    }
+}
+
+/// A function called with a targeted event that was dropped because its target
+/// didn't match the event's [target filter]. See
+/// [`World::set_filtered_event_hook`].
+///
+/// [target filter]: Event::target_filter
+pub type FilteredEventHook = fn(&EventInfo, EntityId);
+
+/// What happens to a targeted event whose target doesn't exist when the event
+/// is broadcast. See [`World::set_dead_target_policy`].
+///
+/// [`Despawn`] is exempt from the policy, since despawning an entity which
+/// doesn't exist does nothing. Every policy drops the event exactly once.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum DeadTargetPolicy {
+    /// Drop the event. This is the default.
+    #[default]
+    Ignore,
+    /// Drop the event and report a [`WarningInfo::DeadTarget`] to the
+    /// [warning handler](World::set_warning_handler).
+    ///
+    /// [`WarningInfo::DeadTarget`]: crate::world::WarningInfo::DeadTarget
+    Warn,
+    /// Panic.
+    Panic,
+    /// Drop the event and send a [`DeadTarget`] as the given event in its
+    /// place, so that systems can observe lost events.
+    ///
+    /// The event is usually [`DeadTarget`] itself, but it may also be an
+    /// untargeted dynamic event with the layout of `DeadTarget` and no drop
+    /// function. [`World::set_dead_target_policy`] panics if the event doesn't
+    /// exist or doesn't qualify. Lost events are dropped without notice if the
+    /// event is removed afterwards.
+    Reroute(EventId),
+}
+
+/// Sent in place of a targeted event whose target doesn't exist, if the
+/// [dead target policy](World::set_dead_target_policy) is
+/// [`DeadTargetPolicy::Reroute`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{DeadTarget, DeadTargetPolicy};
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct Message(#[event(target)] EntityId);
+///
+/// let mut world = World::new();
+///
+/// let dead_target = world.add_event::<DeadTarget>();
+/// world.set_dead_target_policy(DeadTargetPolicy::Reroute(dead_target));
+///
+/// world.add_system(|r: Receiver<DeadTarget>| {
+///     println!("lost an event sent to {:?}", r.event.target);
+/// });
+///
+/// let e = world.spawn();
+/// world.despawn(e);
+/// world.send(Message(e));
+/// ```
+#[derive(Event, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[event(immutable)]
+pub struct DeadTarget {
+    /// The ID of the dropped event.
+    pub event: EventId,
+    /// The entity the event was sent to.
+    pub target: EntityId,
+}
+
 /// Lightweight identifier for an event type.
 ///
 /// Event identifiers are implemented using an [index] and a generation count.
@@ -371,6 +902,7 @@ pub enum EventKind {
 pub struct EventId {
     index: u32,
     generation: u32,
+    world: WorldId,
 }
 
 impl EventId {
@@ -379,24 +911,28 @@ impl EventId {
     pub const NULL: Self = Self {
         index: u32::MAX,
         generation: u32::MAX,
+        world: WorldId::NONE,
     };
 
     // ID of the [`SpawnQueued`] event.
     pub(crate) const SPAWN_QUEUED: EventId = Self {
         index: 0,
         generation: 1,
+        world: WorldId::NONE,
     };
 
     /// Creates a new event ID from an index and generation count. Returns
     /// `None` if the ID is malformed.
+    ///
+    /// The ID is not associated with any world. See [`WorldId`].
     pub const fn new(index: EventIdx, generation: u32) -> Option<Self> {
         match Key::new(index.as_u32(), generation) {
-            Some(k) => Some(Self::from_key(k, index.is_targeted())),
+            Some(k) => Some(Self::from_key(k, index.is_targeted(), WorldId::NONE)),
             None => None,
         }
     }
 
-    const fn from_key(k: Key, is_targeted: bool) -> Self {
+    const fn from_key(k: Key, is_targeted: bool, world: WorldId) -> Self {
         Self {
             index: k.index(),
             generation: if is_targeted {
@@ -404,9 +940,17 @@ impl EventId {
             } else {
                 k.generation().get()
             },
+            world,
         }
     }
 
+    /// Returns the world this ID was created by, or [`WorldId::NONE`] if it
+    /// was created manually.
+    #[cfg(feature = "validate-ids")]
+    pub const fn world(self) -> WorldId {
+        self.world
+    }
+
     fn as_key(self) -> Key {
         Key::new(self.index, self.generation().get()).unwrap()
     }
@@ -532,25 +1076,44 @@ pub struct EventInfo {
     id: EventId,
     kind: EventKind,
     type_id: Option<TypeId>,
+    key: Option<Cow<'static, str>>,
     layout: Layout,
     drop: DropFn,
+    clone: Option<CloneFn>,
+    default: Option<DefaultFn>,
     is_immutable: bool,
+    is_deferred: bool,
+    bubbles: Option<SetTargetFn>,
+    targets: Option<TargetsFn>,
+    target_filter: BoolExpr<ComponentIdx>,
 }
 
 impl EventInfo {
     /// Gets the name of the event.
     ///
-    /// This name is intended for debugging purposes and should not be relied
-    /// upon for correctness.
+    /// Events can be looked up by name with [`Events::get_by_name`].
+    /// Names aren't required to be unique.
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    #[cfg(feature = "replay")]
+    pub(crate) fn name_cow(&self) -> Cow<'static, str> {
+        self.name.clone()
+    }
+
     /// Gets the ID of the event.
     pub fn id(&self) -> EventId {
         self.id
     }
 
+    /// Returns whether the event is [targeted].
+    ///
+    /// [targeted]: Event::IS_TARGETED
+    pub fn is_targeted(&self) -> bool {
+        self.id.is_targeted()
+    }
+
     /// Gets the [`EventKind`] of the event.
     pub fn kind(&self) -> EventKind {
         self.kind
@@ -561,6 +1124,12 @@ impl EventInfo {
         self.type_id
     }
 
+    /// Gets the stable key of the event, or `None` if it doesn't have one.
+    /// See [`EventDescriptor::key`].
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
     /// Gets the [`Layout`] of the event.
     pub fn layout(&self) -> Layout {
         self.layout
@@ -571,12 +1140,55 @@ impl EventInfo {
         self.drop
     }
 
+    /// Gets the [`CloneFn`] of the event, if the event can be copied.
+    pub fn clone_fn(&self) -> Option<CloneFn> {
+        self.clone
+    }
+
+    /// Gets the [`DefaultFn`] of the event, if the event has a default value.
+    pub fn default_fn(&self) -> Option<DefaultFn> {
+        self.default
+    }
+
     /// Gets the [immutability] of the event.
     ///
     /// [immutability]: Event::IS_IMMUTABLE
     pub fn is_immutable(&self) -> bool {
         self.is_immutable
     }
+
+    /// Gets the [deferral] of the event.
+    ///
+    /// [deferral]: Event::IS_DEFERRED
+    pub fn is_deferred(&self) -> bool {
+        self.is_deferred
+    }
+
+    /// Returns whether the event [bubbles].
+    ///
+    /// [bubbles]: Event::BUBBLES
+    pub fn bubbles(&self) -> bool {
+        self.bubbles.is_some()
+    }
+
+    /// Gets the [`SetTargetFn`] used to retarget the event as it bubbles, if
+    /// the event bubbles.
+    pub fn set_target_fn(&self) -> Option<SetTargetFn> {
+        self.bubbles
+    }
+
+    /// Gets the [`TargetsFn`] of the event, if any.
+    pub fn targets_fn(&self) -> Option<TargetsFn> {
+        self.targets
+    }
+
+    /// Gets the [target filter] of the event. This is `true` for events
+    /// without a filter.
+    ///
+    /// [target filter]: Event::target_filter
+    pub fn target_filter(&self) -> &BoolExpr<ComponentIdx> {
+        &self.target_filter
+    }
 }
 
 /// Data needed to create a new event.
@@ -584,11 +1196,17 @@ impl EventInfo {
 pub struct EventDescriptor {
     /// The name of this event.
     ///
-    /// This name is intended for debugging purposes and should not be relied
-    /// upon for correctness.
+    /// Events can be looked up by name with [`Events::get_by_name`].
+    /// Names aren't required to be unique.
     pub name: Cow<'static, str>,
     /// The [`TypeId`] of this event, if any.
     pub type_id: Option<TypeId>,
+    /// A key identifying the event independently of its [`TypeId`]. See
+    /// [`Event::KEY`].
+    ///
+    /// If no event has the type ID, then an event with the same key is
+    /// returned instead. The layouts and targeting of the events must match.
+    pub key: Option<Cow<'static, str>>,
     /// If this event is [targeted](Event::IS_TARGETED).
     pub is_targeted: bool,
     /// The [`EventKind`] of the event.
@@ -598,13 +1216,75 @@ pub struct EventDescriptor {
     /// The [`DropFn`] of the event. This is passed a pointer to the
     /// event in order to drop it.
     pub drop: DropFn,
+    /// The [`CloneFn`] of the event, used to record it. Events without one
+    /// are recorded without their data and can't be replayed.
+    pub clone_fn: Option<CloneFn>,
+    /// The [`DefaultFn`] of the event, used by
+    /// [`World::send_dynamic_default`].
+    pub default_fn: Option<DefaultFn>,
     /// If this event is [immutable](Event::IS_IMMUTABLE).
     pub is_immutable: bool,
+    /// If this event is [deferred](Event::IS_DEFERRED).
+    pub is_deferred: bool,
+    /// If this event [bubbles](Event::BUBBLES), the function used to change
+    /// its target. Must be `None` for untargeted events.
+    pub bubbles: Option<SetTargetFn>,
+    /// The function listing the [targets](Event::targets) of the event. If
+    /// `None`, targeted events are only sent to the target they were queued
+    /// with. Must be `None` for untargeted events.
+    pub targets: Option<TargetsFn>,
+    /// The [target filter](Event::target_filter) of the event. Use
+    /// `BoolExpr::new(true)` for events without a filter.
+    pub target_filter: BoolExpr<ComponentIdx>,
+}
+
+/// Changes the target of a [bubbling](Event::BUBBLES) event through a
+/// pointer to the event.
+///
+/// # Safety
+///
+/// The pointer must point to a valid instance of the event the function was
+/// created for.
+pub type SetTargetFn = unsafe fn(NonNull<u8>, EntityId);
+
+/// Returns the [`SetTargetFn`] for `E` if `E` is a targeted event which
+/// bubbles.
+pub fn set_target_fn_of<E: Event>() -> Option<SetTargetFn> {
+    unsafe fn set_target<E: Event>(ptr: NonNull<u8>, target: EntityId) {
+        (*ptr.cast::<E>().as_ptr()).set_target(target);
+    }
+
+    (E::IS_TARGETED && E::BUBBLES).then_some(set_target::<E>)
+}
+
+/// Passes each of the [targets](Event::targets) of an event to a callback,
+/// through a pointer to the event.
+///
+/// # Safety
+///
+/// The pointer must point to a valid instance of the event the function was
+/// created for.
+pub type TargetsFn = unsafe fn(NonNull<u8>, &mut dyn FnMut(EntityId));
+
+/// Returns the [`TargetsFn`] for `E` if `E` is a targeted event.
+pub fn targets_fn_of<E: Event>() -> Option<TargetsFn> {
+    unsafe fn targets<E: Event>(ptr: NonNull<u8>, f: &mut dyn FnMut(EntityId)) {
+        (*ptr.cast::<E>().as_ptr()).targets().for_each(f);
+    }
+
+    E::IS_TARGETED.then_some(targets::<E>)
 }
 
+/// The maximum number of times a [bubbling](Event::BUBBLES) event moves to
+/// the parent of its target. This stops events from bubbling forever through
+/// a cycle in the hierarchy.
+pub const MAX_BUBBLE_DEPTH: u32 = 64;
+
 #[derive(Debug)]
 pub(crate) struct EventQueue {
     items: Vec<EventQueueItem>,
+    /// [Deferred](Event::IS_DEFERRED) events waiting to be moved to `items`.
+    deferred: VecDeque<EventQueueItem>,
     bump: Bump,
 }
 
@@ -612,6 +1292,7 @@ impl EventQueue {
     pub(crate) fn new() -> Self {
         Self {
             items: vec![],
+            deferred: VecDeque::new(),
             bump: Bump::new(),
         }
     }
@@ -622,45 +1303,126 @@ impl EventQueue {
 
     #[inline]
     pub(crate) unsafe fn push<E: Event>(&mut self, event: E, idx: u32) {
-        let meta = if E::IS_TARGETED {
-            EventMeta::Targeted {
-                idx: TargetedEventIdx(idx),
-                target: event.target(),
-            }
-        } else {
-            EventMeta::Untargeted {
-                idx: UntargetedEventIdx(idx),
-            }
-        };
-
+        let meta = meta_of(&event, idx);
         let event = self.bump.alloc(event) as *mut E as *mut u8;
-        self.items.push(EventQueueItem { meta, event });
-    }
 
-    pub(crate) fn iter(&self) -> impl Iterator<Item = &EventQueueItem> {
-        self.items.iter()
+        self.push_typed::<E>(EventQueueItem { meta, event });
     }
 
-    /// Clears the event queue and resets the internal bump allocator.
-    ///
-    /// Any remaining event pointers are invalidated.
-    pub(crate) fn clear(&mut self) {
-        self.items.clear();
-        self.bump.reset();
-    }
+    /// Like [`push`](Self::push), but the event is moved from the box to the
+    /// queue directly instead of through the stack.
+    #[inline]
+    pub(crate) unsafe fn push_boxed<E: Event>(&mut self, event: Box<E>, idx: u32) {
+        let meta = meta_of(&*event, idx);
 
-    pub(crate) fn len(&self) -> usize {
-        self.items.len()
-    }
+        let src = Box::into_raw(event);
+        let dst = self
+            .bump
+            .alloc_layout(Layout::new::<E>())
+            .cast::<E>()
+            .as_ptr();
 
-    pub(crate) fn is_empty(&self) -> bool {
-        self.len() == 0
-    }
+        ptr::copy_nonoverlapping(src, dst, 1);
 
-    pub(crate) unsafe fn set_len(&mut self, new_len: usize) {
-        self.items.set_len(new_len)
+        // Free the box without dropping the event, which is owned by the queue now.
+        drop(Box::from_raw(src.cast::<mem::ManuallyDrop<E>>()));
+
+        self.push_typed::<E>(EventQueueItem {
+            meta,
+            event: dst.cast(),
+        });
     }
-}
+
+    /// Like [`push`](Self::push), but [deferred](Event::IS_DEFERRED) events
+    /// are not held back.
+    #[inline]
+    pub(crate) unsafe fn push_now<E: Event>(&mut self, event: E, idx: u32) {
+        let meta = meta_of(&event, idx);
+        let event = self.bump.alloc(event) as *mut E as *mut u8;
+
+        self.items.push(EventQueueItem { meta, event });
+    }
+
+    #[inline]
+    fn push_typed<E: Event>(&mut self, item: EventQueueItem) {
+        if E::IS_DEFERRED {
+            self.deferred.push_back(item);
+        } else {
+            self.items.push(item);
+        }
+    }
+
+    /// Moves the oldest deferred event to the end of the queue. Returns `false`
+    /// if there are no deferred events.
+    pub(crate) fn pop_deferred(&mut self) -> bool {
+        if let Some(item) = self.deferred.pop_front() {
+            self.items.push(item);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Pushes an event which was allocated with [`Self::alloc_layout`].
+    pub(crate) unsafe fn push_item(&mut self, item: EventQueueItem) {
+        self.items.push(item);
+    }
+
+    /// Like [`Self::push_item`], but for a [deferred](Event::IS_DEFERRED)
+    /// event.
+    pub(crate) unsafe fn push_deferred_item(&mut self, item: EventQueueItem) {
+        self.deferred.push_back(item);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &EventQueueItem> {
+        self.items.iter().chain(&self.deferred)
+    }
+
+    /// Allocates scratch memory which lives until the queue is cleared.
+    pub(crate) fn alloc_layout(&mut self, layout: Layout) -> NonNull<u8> {
+        self.bump.alloc_layout(layout)
+    }
+
+    /// Clears the event queue and resets the internal bump allocator.
+    ///
+    /// Any remaining event pointers are invalidated.
+    pub(crate) fn clear(&mut self) {
+        self.items.clear();
+        self.deferred.clear();
+        self.bump.reset();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[cfg(feature = "replay")]
+    pub(crate) fn deferred_len(&self) -> usize {
+        self.deferred.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0 && self.deferred.is_empty()
+    }
+
+    pub(crate) unsafe fn set_len(&mut self, new_len: usize) {
+        self.items.set_len(new_len)
+    }
+}
+
+#[inline]
+fn meta_of<E: Event>(event: &E, idx: u32) -> EventMeta {
+    if E::IS_TARGETED {
+        EventMeta::Targeted {
+            idx: TargetedEventIdx(idx),
+            target: event.target(),
+        }
+    } else {
+        EventMeta::Untargeted {
+            idx: UntargetedEventIdx(idx),
+        }
+    }
+}
 
 // SAFETY: The bump allocator is only accessed behind an exclusive reference to
 // the event queue.
@@ -842,6 +1604,28 @@ impl<E: fmt::Debug> fmt::Debug for EventMut<'_, E> {
 ///     println!("got event of type E!");
 /// });
 /// ```
+///
+/// For targeted events, the query `Q` decides which targets the system
+/// listens to. `()` matches every entity regardless of its components, while
+/// queries like `Not<With<&C>>` match every entity _without_ `C`.
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct Damage(#[event(target)] EntityId);
+///
+/// #[derive(Component)]
+/// struct Invulnerable;
+///
+/// let mut world = World::new();
+///
+/// // Receives every `Damage` event.
+/// world.add_system(|r: Receiver<Damage, ()>| {});
+///
+/// // Receives `Damage` events whose target is not `Invulnerable`.
+/// world.add_system(|r: Receiver<Damage, Not<With<&Invulnerable>>>| {});
+/// ```
 #[derive(Clone, Copy)]
 pub struct Receiver<'a, E: Event, Q: ReceiverQuery + 'static = NullReceiverQuery> {
     /// A reference to the received event.
@@ -849,6 +1633,15 @@ pub struct Receiver<'a, E: Event, Q: ReceiverQuery + 'static = NullReceiverQuery
     /// The result of the query. This field is meaningless if `E` is not a
     /// targeted event.
     pub query: Q::Item<'a>,
+    /// The entity the event is being broadcast to, which the query was
+    /// fetched from. This is one of the event's [targets](Event::targets), or
+    /// an ancestor of one if the event [bubbles](Event::BUBBLES). This field
+    /// is meaningless if `E` is not a targeted event.
+    pub target: EntityId,
+    /// The entity the event was sent to. This is the same as `target` unless
+    /// the event has bubbled up from a descendant. This field is meaningless
+    /// if `E` is not a targeted event.
+    pub original_target: EntityId,
 }
 
 unsafe impl<E: Event> SystemParam for Receiver<'_, E> {
@@ -876,6 +1669,8 @@ unsafe impl<E: Event> SystemParam for Receiver<'_, E> {
             // - System was configured to listen for `E`.
             event: event_ptr.as_event::<E>(),
             query: (),
+            target: EntityId::NULL,
+            original_target: EntityId::NULL,
         }
     }
 
@@ -892,13 +1687,15 @@ unsafe impl<E: Event, Q: Query + 'static> SystemParam for Receiver<'_, E, Q> {
     fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
         let () = AssertTargetedEvent::<E>::ASSERTION;
 
-        set_received_event::<E>(world, config, Access::Read)?;
+        let id = set_received_event::<E>(world, config, Access::Read)?;
 
         let (expr, state) = Q::init(world, config)?;
 
         let res = FetcherState::new(state);
 
-        config.targeted_event_expr = expr.expr.clone();
+        let filter = unsafe { world.events().get(id).unwrap_debug_checked() }.target_filter();
+        config.targeted_event_expr = expr.expr.clone().and(filter);
+        config.targeted_event_access = expr.clone();
 
         if let Ok(new_component_access) = expr.or(&config.component_access) {
             config.component_access = new_component_access;
@@ -906,7 +1703,7 @@ unsafe impl<E: Event, Q: Query + 'static> SystemParam for Receiver<'_, E, Q> {
             return Err(InitError(
                 format!(
                     "query `{}` has incompatible component access with previous queries in this \
-                     system",
+                     system (the event target's components could be aliased)",
                     any::type_name::<Q>()
                 )
                 .into(),
@@ -925,14 +1722,21 @@ unsafe impl<E: Event, Q: Query + 'static> SystemParam for Receiver<'_, E, Q> {
         let event = event_ptr.as_event::<E>();
 
         assert!(E::IS_TARGETED);
-        let target = event.target();
+        let target = world.current_target();
 
         // SAFETY: The target entity is guaranteed to match the query.
         let query = state
             .get_mut(world.entities(), target)
             .unwrap_debug_checked();
 
-        Receiver { event, query }
+        let original_target = world.original_target();
+
+        Receiver {
+            event,
+            query,
+            target,
+            original_target,
+        }
     }
 
     fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
@@ -954,12 +1758,21 @@ where
         f.debug_struct("Receiver")
             .field("event", &self.event)
             .field("query", &self.query)
+            .field("target", &self.target)
+            .field("original_target", &self.original_target)
             .finish()
     }
 }
 
 /// Like [`Receiver`], but provides mutable access to the received event.
 ///
+/// Systems receive an event in [priority] order, so changes made through a
+/// `ReceiverMut` are seen by every system that runs afterwards. A system
+/// can't have a `ReceiverMut` alongside any other parameter that accesses
+/// the event.
+///
+/// [priority]: crate::system::Priority
+///
 /// For more information, see the relevant [tutorial
 /// chapter](crate::tutorial::ch02_event_mutation).
 pub struct ReceiverMut<'a, E: Event, Q: ReceiverQuery + 'static = NullReceiverQuery> {
@@ -968,6 +1781,15 @@ pub struct ReceiverMut<'a, E: Event, Q: ReceiverQuery + 'static = NullReceiverQu
     /// The result of the query. This field is meaningless if `E` is not a
     /// targeted event.
     pub query: Q::Item<'a>,
+    /// The entity the event is being broadcast to, which the query was
+    /// fetched from. This is one of the event's [targets](Event::targets), or
+    /// an ancestor of one if the event [bubbles](Event::BUBBLES). This field
+    /// is meaningless if `E` is not a targeted event.
+    pub target: EntityId,
+    /// The entity the event was sent to. This is the same as `target` unless
+    /// the event has bubbled up from a descendant. This field is meaningless
+    /// if `E` is not a targeted event.
+    pub original_target: EntityId,
 }
 
 unsafe impl<E: Event> SystemParam for ReceiverMut<'_, E> {
@@ -993,6 +1815,8 @@ unsafe impl<E: Event> SystemParam for ReceiverMut<'_, E> {
         ReceiverMut {
             event: event_ptr.as_event_mut(),
             query: (),
+            target: EntityId::NULL,
+            original_target: EntityId::NULL,
         }
     }
 
@@ -1010,13 +1834,15 @@ unsafe impl<E: Event, Q: Query + 'static> SystemParam for ReceiverMut<'_, E, Q>
         let () = AssertMutable::<E>::EVENT;
         let () = AssertTargetedEvent::<E>::ASSERTION;
 
-        set_received_event::<E>(world, config, Access::ReadWrite)?;
+        let id = set_received_event::<E>(world, config, Access::ReadWrite)?;
 
         let (expr, state) = Q::init(world, config)?;
 
         let res = FetcherState::new(state);
 
-        config.targeted_event_expr = expr.expr.clone();
+        let filter = unsafe { world.events().get(id).unwrap_debug_checked() }.target_filter();
+        config.targeted_event_expr = expr.expr.clone().and(filter);
+        config.targeted_event_access = expr.clone();
 
         if let Ok(new_component_access) = expr.or(&config.component_access) {
             config.component_access = new_component_access;
@@ -1024,7 +1850,7 @@ unsafe impl<E: Event, Q: Query + 'static> SystemParam for ReceiverMut<'_, E, Q>
             return Err(InitError(
                 format!(
                     "query `{}` has incompatible component access with previous queries in this \
-                     system",
+                     system (the event target's components could be aliased)",
                     any::type_name::<Q>()
                 )
                 .into(),
@@ -1043,14 +1869,21 @@ unsafe impl<E: Event, Q: Query + 'static> SystemParam for ReceiverMut<'_, E, Q>
         let event = event_ptr.as_event_mut::<E>();
 
         assert!(E::IS_TARGETED);
-        let target = event.target();
+        let target = world.current_target();
 
         // SAFETY: The target entity is guaranteed to match the query.
         let query = state
             .get_mut(world.entities(), target)
             .unwrap_debug_checked();
 
-        ReceiverMut { event, query }
+        let original_target = world.original_target();
+
+        ReceiverMut {
+            event,
+            query,
+            target,
+            original_target,
+        }
     }
 
     fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
@@ -1072,10 +1905,346 @@ where
         f.debug_struct("Receiver")
             .field("event", &self.event)
             .field("query", &self.query)
+            .field("target", &self.target)
+            .field("original_target", &self.original_target)
+            .finish()
+    }
+}
+
+/// An enum of references to one of several [`Event`] types, received by a
+/// [`ReceiverAny`].
+///
+/// This trait is usually implemented with `#[derive(EventEnum)]` on an enum
+/// with one lifetime parameter, where every variant holds a `&'a E` for a
+/// different event `E`. The events must be all targeted or all untargeted.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct FireDamage(#[event(target)] EntityId, u32);
+///
+/// #[derive(Event)]
+/// struct IceDamage(#[event(target)] EntityId, u32);
+///
+/// #[derive(EventEnum)]
+/// enum Damage<'a> {
+///     Fire(&'a FireDamage),
+///     Ice(&'a IceDamage),
+/// }
+/// ```
+///
+/// # Safety
+///
+/// [`get`](Self::get) must only reinterpret the event as the type of the
+/// event at the same position in [`add_events`](Self::add_events).
+pub unsafe trait EventEnum {
+    /// The enum with the lifetime `'a`. This is usually the same type as
+    /// `Self`, but with a modified lifetime.
+    type Item<'a>;
+
+    /// Adds the events of the enum to the world, calling `f` with the ID of
+    /// each of them in order.
+    fn add_events<F: FnMut(EventId)>(world: &mut World, f: F);
+
+    /// Creates the variant for the event at `idx` in the order of
+    /// [`add_events`](Self::add_events).
+    ///
+    /// # Safety
+    ///
+    /// `event` must point to a valid instance of the event at `idx`, which
+    /// must be in bounds, and must be safe to access immutably for `'a`.
+    unsafe fn get<'a>(idx: usize, event: EventPtr<'a>) -> Self::Item<'a>;
+}
+
+/// Like [`Receiver`], but listens for every event of the [`EventEnum`] `E` in
+/// a single system.
+///
+/// The system runs with the same [priority] for each of the events, and is
+/// added and removed from all of them at once. For targeted events, the query
+/// `Q` is matched against the target like it is by [`Receiver`], and targets
+/// must satisfy the [requirements] of every event in the enum.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct FireDamage(#[event(target)] EntityId, u32);
+///
+/// #[derive(Event)]
+/// struct IceDamage(#[event(target)] EntityId, u32);
+///
+/// #[derive(EventEnum)]
+/// enum Damage<'a> {
+///     Fire(&'a FireDamage),
+///     Ice(&'a IceDamage),
+/// }
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|r: ReceiverAny<Damage, &mut Health>| {
+///     let amount = match r.event {
+///         Damage::Fire(e) => e.1,
+///         Damage::Ice(e) => e.1 / 2,
+///     };
+///
+///     r.query.0 = r.query.0.saturating_sub(amount);
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100));
+///
+/// world.send(FireDamage(e, 10));
+/// world.send(IceDamage(e, 10));
+///
+/// assert_eq!(world.get_component::<Health>(e).unwrap().0, 85);
+/// ```
+///
+/// [priority]: crate::system::Priority
+/// [requirements]: Event::target_filter
+pub struct ReceiverAny<'a, E: EventEnum, Q: ReceiverQuery + 'static = NullReceiverQuery> {
+    /// The variant of the enum for the received event.
+    pub event: E::Item<'a>,
+    /// The result of the query. This field is meaningless if the events are
+    /// not targeted.
+    pub query: Q::Item<'a>,
+    /// The entity the event is being broadcast to. See [`Receiver::target`].
+    /// This field is meaningless if the events are not targeted.
+    pub target: EntityId,
+    /// The entity the event was sent to. See [`Receiver::original_target`].
+    /// This field is meaningless if the events are not targeted.
+    pub original_target: EntityId,
+}
+
+unsafe impl<E: EventEnum> SystemParam for ReceiverAny<'_, E> {
+    type State = Box<[EventIdx]>;
+
+    type Item<'a> = ReceiverAny<'a, E>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        set_received_events::<E>(world, config, false)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        ReceiverAny {
+            event: get_event_enum::<E>(state, event_ptr, world),
+            query: (),
+            target: EntityId::NULL,
+            original_target: EntityId::NULL,
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+unsafe impl<E: EventEnum, Q: Query + 'static> SystemParam for ReceiverAny<'_, E, Q> {
+    type State = (Box<[EventIdx]>, FetcherState<Q>);
+
+    type Item<'a> = ReceiverAny<'a, E, Q>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        let events = set_received_events::<E>(world, config, true)?;
+
+        let (expr, state) = Q::init(world, config)?;
+
+        let mut targeted_event_expr = expr.expr.clone();
+
+        for &idx in &*events {
+            let info = unsafe { world.events().get_by_index(idx).unwrap_debug_checked() };
+            targeted_event_expr = targeted_event_expr.and(info.target_filter());
+        }
+
+        config.targeted_event_expr = targeted_event_expr;
+        config.targeted_event_access = expr.clone();
+
+        if let Ok(new_component_access) = expr.or(&config.component_access) {
+            config.component_access = new_component_access;
+        } else {
+            return Err(InitError(
+                format!(
+                    "query `{}` has incompatible component access with previous queries in this \
+                     system (the event target's components could be aliased)",
+                    any::type_name::<Q>()
+                )
+                .into(),
+            ));
+        }
+
+        Ok((events, FetcherState::new(state)))
+    }
+
+    unsafe fn get<'a>(
+        (events, state): &'a mut Self::State,
+        _info: &'a SystemInfo,
+        event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        let event = get_event_enum::<E>(events, event_ptr, world);
+
+        let target = world.current_target();
+
+        // SAFETY: The target entity is guaranteed to match the query.
+        let query = state
+            .get_mut(world.entities(), target)
+            .unwrap_debug_checked();
+
+        ReceiverAny {
+            event,
+            query,
+            target,
+            original_target: world.original_target(),
+        }
+    }
+
+    fn refresh_archetype((_, state): &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype((_, state): &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+impl<'a, E, Q> fmt::Debug for ReceiverAny<'a, E, Q>
+where
+    E: EventEnum,
+    E::Item<'a>: fmt::Debug,
+    Q: ReceiverQuery,
+    Q::Item<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReceiverAny")
+            .field("event", &self.event)
+            .field("query", &self.query)
+            .field("target", &self.target)
+            .field("original_target", &self.original_target)
             .finish()
     }
 }
 
+/// Configures the system to receive every event of `E`, returning their
+/// indices in order.
+fn set_received_events<E: EventEnum>(
+    world: &mut World,
+    config: &mut Config,
+    targeted: bool,
+) -> Result<Box<[EventIdx]>, InitError> {
+    let mut ids = vec![];
+    E::add_events(world, |id| ids.push(id));
+
+    let name = |id| {
+        world
+            .events()
+            .get(id)
+            .map_or("<unknown>", |info| info.name())
+    };
+
+    let Some((&first, others)) = ids.split_first() else {
+        return Err(InitError(
+            format!(
+                "event enum `{}` has no events to receive",
+                any::type_name::<E>()
+            )
+            .into(),
+        ));
+    };
+
+    if let Some(received_event) = config.received_event {
+        return Err(InitError(
+            format!(
+                "tried to set the events of `{}` as the received events for this system, but the \
+                 system was already configured to receive `{}`. Systems must have exactly one \
+                 receiver",
+                any::type_name::<E>(),
+                name(received_event)
+            )
+            .into(),
+        ));
+    }
+
+    for (i, &id) in ids.iter().enumerate() {
+        if id.is_targeted() != targeted {
+            return Err(InitError(
+                format!(
+                    "event `{}` of event enum `{}` is {}, but the receiver expects {} events{}",
+                    name(id),
+                    any::type_name::<E>(),
+                    if targeted { "untargeted" } else { "targeted" },
+                    if targeted { "targeted" } else { "untargeted" },
+                    if targeted {
+                        ""
+                    } else {
+                        ". Add a query, such as `()`, to receive targeted events"
+                    }
+                )
+                .into(),
+            ));
+        }
+
+        if ids[..i].contains(&id) {
+            return Err(InitError(
+                format!(
+                    "event `{}` appears more than once in event enum `{}`",
+                    name(id),
+                    any::type_name::<E>()
+                )
+                .into(),
+            ));
+        }
+    }
+
+    config.received_event = Some(first);
+    config.other_received_events = others.to_vec();
+
+    if !config.received_event_access.set_if_compatible(Access::Read) {
+        return Err(InitError(
+            format!(
+                "tried to set `{:?}` as the received event access for this system, but it was \
+                 already set to `{:?}`",
+                Access::Read,
+                config.received_event_access
+            )
+            .into(),
+        ));
+    }
+
+    Ok(ids.iter().map(|id| id.index()).collect())
+}
+
+/// Creates the variant of `E` for the event being broadcast.
+///
+/// # Safety
+///
+/// The system must be receiving the events at `events`, in order.
+unsafe fn get_event_enum<'a, E: EventEnum>(
+    events: &[EventIdx],
+    event_ptr: EventPtr<'a>,
+    world: UnsafeWorldCell<'a>,
+) -> E::Item<'a> {
+    let current = world.current_event();
+
+    let idx = events
+        .iter()
+        .position(|&idx| idx == current)
+        .unwrap_debug_checked();
+
+    E::get(idx, event_ptr)
+}
+
 fn set_received_event<E: Event>(
     world: &mut World,
     config: &mut Config,
@@ -1084,7 +2253,7 @@ fn set_received_event<E: Event>(
     let id = world.add_event::<E>();
 
     if let Some(received_event) = config.received_event {
-        if received_event != id {
+        if received_event != id || !config.other_received_events.is_empty() {
             let other = world
                 .events()
                 .get(received_event)
@@ -1180,6 +2349,85 @@ impl<T: EventSet> Sender<'_, T> {
         unsafe { self.world.send_with_index(event, event_idx) }
     }
 
+    /// Add a boxed event to the queue of events to send. The event is moved
+    /// from the box into the queue without being copied to the stack, which
+    /// avoids large copies for events with big payloads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `E` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn send_boxed<E: Event>(&mut self, event: Box<E>) {
+        let event_idx = T::event_idx_of::<E>(self.state).unwrap_or_else(|| {
+            panic!(
+                "event `{}` is not in the `EventSet` of this `Sender`",
+                any::type_name::<E>()
+            )
+        });
+
+        unsafe { self.world.send_boxed_with_index(event, event_idx) }
+    }
+
+    /// Schedules an event to be sent once [`World::advance_tick`] has moved
+    /// the world forward by `ticks` ticks. See [`World::send_delayed`] for
+    /// details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `E` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn send_delayed<E: Event>(&mut self, event: E, ticks: u32) -> ScheduledEventId {
+        assert!(
+            T::event_idx_of::<E>(self.state).is_some(),
+            "event `{}` is not in the `EventSet` of this `Sender`",
+            any::type_name::<E>()
+        );
+
+        unsafe { self.world.scheduler() }.schedule(event, ticks)
+    }
+
+    /// Clones a targeted event, changes the target of the clone to
+    /// `new_target`, and adds it to the queue of events to send.
+    ///
+    /// The original event is unaffected and continues broadcasting to its
+    /// remaining listeners. Like any other targeted event, the forwarded event
+    /// is silently dropped if `new_target` doesn't exist once it's handled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event, Clone)]
+    /// struct Damage {
+    ///     #[event(target)]
+    ///     entity: EntityId,
+    ///     amount: u32,
+    /// }
+    ///
+    /// /// Redirects damage to another entity.
+    /// #[derive(Component)]
+    /// struct Shield(EntityId);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(|r: Receiver<Damage, &Shield>, mut s: Sender<Damage>| {
+    ///     s.forward(r.event, r.query.0);
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `E` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn forward<E: Event + Clone>(&mut self, event: &E, new_target: EntityId) {
+        let () = AssertTargetedEvent::<E>::ASSERTION;
+
+        let mut event = event.clone();
+        event.set_target(new_target);
+        self.send(event);
+    }
+
     /// Queues an entity to be spawned, returns its [`EntityId`], and queues the
     /// [`Spawn`] event. The returned `EntityId` is not used by any previous
     /// entities in the [`World`].
@@ -1413,11 +2661,62 @@ macro_rules! impl_event_set_tuple {
 all_tuples!(impl_event_set_tuple, 0, 15, E, e);
 
 /// An [`Event`] which adds component `C` on an entity when sent. If the entity
-/// already has the component, then the component is replaced.
+/// already has the component, then the component is replaced and the old
+/// value is sent in a [`Replaced<C>`] event.
 ///
 /// Any system which listens for `Insert<C>` will run before the component is
 /// inserted. `Insert<C>` has no effect if the target entity does not exist or
 /// the event is consumed before it finishes broadcasting.
+///
+/// To only react to first-time insertions, filter out entities which already
+/// have the component:
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct C;
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|r: Receiver<Insert<C>, Not<With<&C>>>| {
+///     println!("{:?} got `C` for the first time", r.event.entity);
+/// });
+/// ```
+///
+/// # Vetoing insertions
+///
+/// Since the component is only inserted once every listener has run, a
+/// listener can veto the insertion by taking the event with
+/// [`EventMut::take`]. The entity is left untouched: it isn't moved to a new
+/// archetype, and neither the component's [insert hook] nor the listeners
+/// that would have run later see the event. The taken component is dropped
+/// along with the event, unless it is kept.
+///
+/// [insert hook]: crate::component::ComponentDescriptor::on_insert
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Wet;
+///
+/// #[derive(Component)]
+/// struct Burning;
+///
+/// let mut world = World::new();
+///
+/// // Wet entities can't catch fire.
+/// world.add_system(|r: ReceiverMut<Insert<Burning>, With<&Wet>>| {
+///     EventMut::take(r.event);
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Wet);
+/// world.insert(e, Burning);
+///
+/// assert!(world.get_component::<Burning>(e).is_none());
+/// ```
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[repr(C)] // Field order is significant!
 pub struct Insert<C> {
@@ -1441,6 +2740,26 @@ impl<C: Component> Event for Insert<C> {
         self.entity
     }
 
+    fn set_target(&mut self, target: EntityId) {
+        self.entity = target;
+    }
+
+    fn clone_fn() -> Option<CloneFn> {
+        unsafe fn clone<C: Component>(src: *const u8, dst: *mut u8) {
+            let src = &*src.cast::<Insert<C>>();
+            let dst = dst.cast::<Insert<C>>();
+            let clone = C::clone_fn().unwrap_debug_checked();
+
+            ptr::addr_of_mut!((*dst).entity).write(src.entity);
+            clone(
+                ptr::addr_of!(src.component).cast(),
+                ptr::addr_of_mut!((*dst).component).cast(),
+            );
+        }
+
+        C::clone_fn().map(|_| clone::<C> as CloneFn)
+    }
+
     unsafe fn init(world: &mut World) -> EventKind {
         EventKind::Insert {
             component_idx: world.add_component::<C>().index(),
@@ -1454,9 +2773,44 @@ impl<C: Component> Event for Insert<C> {
 /// An [`Event`] which removes component `C` from an entity when sent. The
 /// component is dropped and cannot be recovered.
 ///
-/// Any system which listens for `Remove<C>` will run before the component is
-/// removed. `Remove<C>` has no effect if the target entity does not exist or
-/// the event is consumed before it finishes broadcasting.
+/// `Remove<C>` has no effect if the target entity does not exist or the event
+/// is consumed before it finishes broadcasting.
+///
+/// # Ordering
+///
+/// Removal happens in two phases:
+///
+/// 1. `Remove<C>` is broadcast while the entity still has `C`, so listeners can
+///    fetch its final value, including through the receiver's query.
+/// 2. Once every listener has run, `C` is removed from the entity and dropped.
+///    If `C` has a [`Removed<C>`] event, it is then sent to the entity.
+///
+/// Events sent by the listeners of `Remove<C>` are handled before the next
+/// listener runs, and therefore before phase two. Other components inserted or
+/// removed by a listener are already applied when `C` is removed. Removal also
+/// goes ahead if a listener inserts a new `C`, while despawning the entity
+/// during phase one cancels it.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Buffer(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|r: Receiver<Remove<Buffer>, &Buffer>| {
+///     println!("freeing buffer {}", r.query.0);
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Buffer(3));
+/// world.remove::<Buffer>(e); // Prints "freeing buffer 3".
+///
+/// assert!(world.get_component::<Buffer>(e).is_none());
+/// ```
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[repr(transparent)]
 pub struct Remove<C> {
@@ -1482,6 +2836,15 @@ impl<C: Component> Event for Remove<C> {
         self.entity
     }
 
+    fn set_target(&mut self, target: EntityId) {
+        self.entity = target;
+    }
+
+    fn clone_fn() -> Option<CloneFn> {
+        // `Remove` is a transparent wrapper around the entity.
+        Some(clone_fn_of::<EntityId>())
+    }
+
     unsafe fn init(world: &mut World) -> EventKind {
         EventKind::Remove {
             component_idx: world.add_component::<C>().index(),
@@ -1489,6 +2852,142 @@ impl<C: Component> Event for Remove<C> {
     }
 }
 
+/// An [`Event`] sent after a [`Remove<C>`] event removes component `C` from an
+/// entity. Listeners run once the entity no longer has `C`.
+///
+/// `Removed<C>` is not sent when the removal was cancelled, or when the
+/// entity is despawned. Sending it yourself has no effect on the entity.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Buffer(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|r: Receiver<Removed<Buffer>, Has<&Buffer>>| {
+///     assert!(!r.query.get());
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Buffer(3));
+/// world.remove::<Buffer>(e);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(transparent)]
+pub struct Removed<C> {
+    /// The entity the component was removed from.
+    pub entity: EntityId,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C> Removed<C> {
+    /// Create a new instance.
+    pub const fn new(entity: EntityId) -> Self {
+        Self {
+            entity,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C: Component> Event for Removed<C> {
+    const IS_TARGETED: bool = true;
+
+    fn target(&self) -> EntityId {
+        self.entity
+    }
+
+    fn set_target(&mut self, target: EntityId) {
+        self.entity = target;
+    }
+
+    fn clone_fn() -> Option<CloneFn> {
+        // `Removed` is a transparent wrapper around the entity.
+        Some(clone_fn_of::<EntityId>())
+    }
+
+    unsafe fn init(world: &mut World) -> EventKind {
+        EventKind::Removed {
+            component_idx: world.add_component::<C>().index(),
+        }
+    }
+}
+
+/// An [`Event`] sent after an [`Insert<C>`] event replaces an existing
+/// component. Contains the previous value of the component, which listeners
+/// may take ownership of with [`ReceiverMut`].
+///
+/// `Replaced<C>` is not sent when the component is inserted for the first
+/// time.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component, Debug)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|r: Receiver<Replaced<Health>, &Health>| {
+///     println!("health changed from {} to {}", r.event.old.0, r.query.0);
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100)); // Not a replacement.
+/// world.insert(e, Health(50)); // Prints "health changed from 100 to 50".
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(C)] // Field order is significant!
+pub struct Replaced<C> {
+    /// The entity whose component was replaced.
+    pub entity: EntityId,
+    /// The previous value of the component.
+    pub old: C,
+}
+
+impl<C: Component> Event for Replaced<C> {
+    const IS_TARGETED: bool = true;
+
+    fn target(&self) -> EntityId {
+        self.entity
+    }
+
+    fn set_target(&mut self, target: EntityId) {
+        self.entity = target;
+    }
+
+    fn clone_fn() -> Option<CloneFn> {
+        unsafe fn clone<C: Component>(src: *const u8, dst: *mut u8) {
+            let src = &*src.cast::<Replaced<C>>();
+            let dst = dst.cast::<Replaced<C>>();
+            let clone = C::clone_fn().unwrap_debug_checked();
+
+            ptr::addr_of_mut!((*dst).entity).write(src.entity);
+            clone(
+                ptr::addr_of!(src.old).cast(),
+                ptr::addr_of_mut!((*dst).old).cast(),
+            );
+        }
+
+        C::clone_fn().map(|_| clone::<C> as CloneFn)
+    }
+
+    unsafe fn init(world: &mut World) -> EventKind {
+        EventKind::Replaced {
+            component_idx: world.add_component::<C>().index(),
+            component_offset: offset_of!(Self, old)
+                .try_into()
+                .expect("component offset should fit in a `u32`"),
+        }
+    }
+}
+
 /// An [`Event`] which signals the creation of an entity. Contains the
 /// [`EntityId`] of the new entity.
 ///
@@ -1501,8 +3000,10 @@ pub struct Spawn(#[event(target)] pub EntityId);
 /// components of the target entity are dropped.
 ///
 /// Any system which listens for `Despawn` will run before the entity is
-/// removed. `Despawn` has no effect if the target entity does not exist or the
-/// event is consumed before it finishes broadcasting.
+/// removed, so the entity's components can still be accessed. Once the entity
+/// is removed, the [`Despawned`] event is sent. `Despawn` has no effect if the
+/// target entity does not exist or the event is consumed before it finishes
+/// broadcasting.
 ///
 /// # Examples
 ///
@@ -1533,11 +3034,44 @@ impl Event for Despawn {
         self.0
     }
 
+    fn set_target(&mut self, target: EntityId) {
+        self.0 = target;
+    }
+
+    fn clone_fn() -> Option<CloneFn> {
+        Some(clone_fn_of::<Self>())
+    }
+
     unsafe fn init(_world: &mut World) -> EventKind {
         EventKind::Despawn
     }
 }
 
+/// An [`Event`] sent after an entity has been removed from the [`World`] by
+/// [`Despawn`]. Contains the [`EntityId`] of the removed entity.
+///
+/// Listeners for [`Despawn`] run while the entity and its components still
+/// exist. By the time `Despawned` is sent, the entity is gone and its
+/// components have been dropped.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::entity::Entities;
+/// use evenio::prelude::*;
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|r: Receiver<Despawned>, entities: &Entities| {
+///     assert!(!entities.contains(r.event.0));
+/// });
+///
+/// let id = world.spawn();
+/// world.despawn(id);
+/// ```
+#[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Despawned(pub EntityId);
+
 /// An [`Event`] sent immediately after a new event is added to the world.
 ///
 /// Contains the [`EventId`] of the added event.
@@ -1552,8 +3086,64 @@ pub struct RemoveEvent(pub EventId);
 
 #[cfg(test)]
 mod tests {
+    use core::alloc::Layout;
+    use core::any::TypeId;
+
     use crate::prelude::*;
 
+    #[test]
+    fn events_are_deduplicated_by_key() {
+        use alloc::borrow::Cow;
+
+        use crate::bool_expr::BoolExpr;
+        use crate::event::{EventDescriptor, EventKind};
+
+        #[derive(Event)]
+        #[event(uuid = "ping")]
+        struct Ping;
+
+        struct OtherPing;
+
+        let desc = |is_targeted| EventDescriptor {
+            name: Cow::Borrowed("ping"),
+            type_id: Some(TypeId::of::<OtherPing>()),
+            key: Some(Cow::Borrowed("ping")),
+            is_targeted,
+            kind: EventKind::Normal,
+            layout: Layout::new::<()>(),
+            drop: None,
+            clone_fn: None,
+            default_fn: None,
+            is_immutable: false,
+            is_deferred: false,
+            bubbles: None,
+            targets: None,
+            target_filter: BoolExpr::new(true),
+        };
+
+        let mut world = World::new();
+        let id = world.add_event::<Ping>();
+
+        assert_eq!(world.event_id_by_key("ping"), Some(id));
+        assert_eq!(unsafe { world.add_event_with_descriptor(desc(false)) }, id);
+        assert_eq!(
+            world
+                .events()
+                .get_by_type_id(TypeId::of::<OtherPing>())
+                .unwrap()
+                .id(),
+            id
+        );
+
+        let res = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| unsafe {
+            world.add_event_with_descriptor(EventDescriptor {
+                type_id: None,
+                ..desc(true)
+            })
+        }));
+        assert!(res.is_err());
+    }
+
     #[test]
     fn remove_spawn_queued_event() {
         let mut world = World::new();
@@ -1561,4 +3151,1299 @@ mod tests {
         assert!(world.events().contains(EventId::SPAWN_QUEUED));
         assert!(world.remove_event(EventId::SPAWN_QUEUED).is_none());
     }
+
+    #[test]
+    fn event_registry() {
+        #[derive(Event)]
+        struct Global(#[allow(dead_code)] u64);
+
+        #[derive(Event)]
+        struct Targeted(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        let global = world.add_event::<Global>();
+        let targeted = world.add_event::<Targeted>();
+
+        let info = &world.events()[global];
+        assert_eq!(info.id(), global);
+        assert!(!info.is_targeted());
+        assert!(info.name().ends_with("Global"));
+        assert_eq!(info.type_id(), Some(TypeId::of::<Global>()));
+        assert_eq!(info.layout(), Layout::new::<Global>());
+
+        let info = world.events().get_by_index(targeted.index()).unwrap();
+        assert_eq!(info.id(), targeted);
+        assert!(info.is_targeted());
+
+        assert_eq!(world.events()[TypeId::of::<Targeted>()].id(), targeted);
+
+        let ids: Vec<_> = world.event_ids().collect();
+        assert!(ids.contains(&global));
+        assert!(ids.contains(&targeted));
+        assert_eq!(ids.len(), world.events().iter().count());
+
+        world.remove_event(global);
+        assert!(!world.event_ids().any(|id| id == global));
+    }
+
+    #[test]
+    #[should_panic(expected = "`set_target` must be implemented")]
+    fn forward_without_set_target() {
+        #[derive(Clone)]
+        struct Ping(EntityId);
+
+        impl Event for Ping {
+            const IS_TARGETED: bool = true;
+
+            fn target(&self) -> EntityId {
+                self.0
+            }
+        }
+
+        let mut world = World::new();
+
+        world.add_system(|r: Receiver<Ping, ()>, mut s: Sender<Ping>| {
+            s.forward(r.event, r.target);
+        });
+
+        let e = world.spawn();
+        world.send(Ping(e));
+    }
+
+    #[test]
+    fn forward_event() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Event, Clone)]
+        struct Collision {
+            #[event(target)]
+            this: EntityId,
+            other: EntityId,
+            forwarded: bool,
+        }
+
+        let mut world = World::new();
+
+        let a = world.spawn();
+        let b = world.spawn();
+        let dead = world.spawn();
+        world.despawn(dead);
+
+        world.add_system(
+            move |r: Receiver<Collision, ()>, mut s: Sender<Collision>| {
+                if !r.event.forwarded {
+                    let swapped = Collision {
+                        this: r.event.other,
+                        other: r.event.this,
+                        forwarded: true,
+                    };
+                    s.forward(&swapped, r.event.other);
+                    s.forward(&swapped, dead);
+                }
+            },
+        );
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<Collision, ()>| {
+            log_clone
+                .lock()
+                .unwrap()
+                .push((r.event.this, r.event.other, r.event.forwarded))
+        });
+
+        world.send(Collision {
+            this: a,
+            other: b,
+            forwarded: false,
+        });
+
+        assert_eq!(*log.lock().unwrap(), [(b, a, true), (a, b, false)]);
+    }
+
+    #[test]
+    fn targeted_listener_filters() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct E(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        let all = Arc::new(Mutex::new(vec![]));
+        let without_b = Arc::new(Mutex::new(vec![]));
+
+        {
+            let all = all.clone();
+            world.add_system(move |r: Receiver<E, ()>| all.lock().unwrap().push(r.event.0));
+        }
+
+        {
+            let without_b = without_b.clone();
+            world.add_system(move |r: Receiver<E, Not<With<&B>>>| {
+                without_b.lock().unwrap().push(r.event.0)
+            });
+        }
+
+        // Spawn after adding the systems so that the archetypes are new.
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        world.insert(e2, A);
+        let e3 = world.spawn();
+        world.insert(e3, A);
+        world.insert(e3, B);
+        let e4 = world.spawn();
+        world.insert(e4, B);
+        world.remove::<B>(e4);
+
+        for e in [e1, e2, e3, e4] {
+            world.send(E(e));
+        }
+
+        assert_eq!(*all.lock().unwrap(), [e1, e2, e3, e4]);
+        assert_eq!(*without_b.lock().unwrap(), [e1, e2, e4]);
+    }
+
+    #[test]
+    fn deferred_event_order() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        type Log = Arc<Mutex<Vec<String>>>;
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B(u32);
+
+        #[derive(Event)]
+        struct C(u32);
+
+        #[derive(Event)]
+        #[event(deferred)]
+        struct DeferredB(u32);
+
+        #[derive(Event)]
+        #[event(deferred)]
+        struct DeferredC(u32);
+
+        fn immediate(log: &Log) -> World {
+            let mut world = World::new();
+
+            let l = log.clone();
+            world.add_system(move |_: Receiver<A>, mut s: Sender<B>| {
+                l.lock().unwrap().push("A1".into());
+                s.send(B(1));
+                s.send(B(2));
+            });
+            let l = log.clone();
+            world.add_system(move |_: Receiver<A>| l.lock().unwrap().push("A2".into()));
+            let l = log.clone();
+            world.add_system(move |r: Receiver<B>, mut s: Sender<C>| {
+                l.lock().unwrap().push(format!("B{}", r.event.0));
+                s.send(C(r.event.0));
+            });
+            let l = log.clone();
+            world.add_system(move |r: Receiver<C>| {
+                l.lock().unwrap().push(format!("C{}", r.event.0));
+            });
+
+            world
+        }
+
+        fn deferred(log: &Log) -> World {
+            let mut world = World::new();
+
+            let l = log.clone();
+            world.add_system(move |_: Receiver<A>, mut s: Sender<DeferredB>| {
+                l.lock().unwrap().push("A1".into());
+                s.send(DeferredB(1));
+                s.send(DeferredB(2));
+            });
+            let l = log.clone();
+            world.add_system(move |_: Receiver<A>| l.lock().unwrap().push("A2".into()));
+            let l = log.clone();
+            world.add_system(move |r: Receiver<DeferredB>, mut s: Sender<DeferredC>| {
+                l.lock().unwrap().push(format!("B{}", r.event.0));
+                s.send(DeferredC(r.event.0));
+            });
+            let l = log.clone();
+            world.add_system(move |r: Receiver<DeferredC>| {
+                l.lock().unwrap().push(format!("C{}", r.event.0));
+            });
+
+            world
+        }
+
+        let log = Log::default();
+        immediate(&log).send(A);
+        assert_eq!(*log.lock().unwrap(), ["A1", "B1", "C1", "B2", "C2", "A2"]);
+
+        let log = Log::default();
+        deferred(&log).send(A);
+        assert_eq!(*log.lock().unwrap(), ["A1", "A2", "B1", "B2", "C1", "C2"]);
+    }
+
+    #[test]
+    fn cascade_order() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B(u32);
+
+        #[derive(Event)]
+        struct C(u32);
+
+        let log = Arc::new(Mutex::new(Vec::<String>::new()));
+        let mut world = World::new();
+
+        let l = log.clone();
+        world.add_system(move |_: Receiver<A>, mut s: Sender<B>| {
+            l.lock().unwrap().push("A1".into());
+            s.send(B(1));
+            s.send(B(2));
+        });
+        let l = log.clone();
+        world.add_system(move |_: Receiver<A>| l.lock().unwrap().push("A2".into()));
+        let l = log.clone();
+        world.add_system(move |r: Receiver<B>, mut s: Sender<C>| {
+            l.lock().unwrap().push(format!("B{}a", r.event.0));
+            s.send(C(r.event.0));
+        });
+        let l = log.clone();
+        world.add_system(move |r: Receiver<B>| l.lock().unwrap().push(format!("B{}b", r.event.0)));
+        let l = log.clone();
+        world.add_system(move |r: ReceiverMut<C>, mut s: Sender<C>| {
+            l.lock().unwrap().push(format!("C{}a", r.event.0));
+
+            // Take the event once the events sent here are handled.
+            if r.event.0 < 10 {
+                s.send(C(r.event.0 * 10));
+                EventMut::take(r.event);
+            }
+        });
+        let l = log.clone();
+        world.add_system(move |r: Receiver<C>| l.lock().unwrap().push(format!("C{}b", r.event.0)));
+
+        world.send(A);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "A1", "B1a", "C1a", "C10a", "C10b", "B1b", "B2a", "C2a", "C20a", "C20b", "B2b",
+                "A2"
+            ]
+        );
+    }
+
+    #[test]
+    fn deep_event_chain() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        #[derive(Event)]
+        struct Step(u32);
+
+        let count = Arc::new(AtomicU32::new(0));
+        let mut world = World::new();
+
+        let c = count.clone();
+        world.add_system(move |r: Receiver<Step>, mut s: Sender<Step>| {
+            c.fetch_add(1, Ordering::Relaxed);
+
+            if r.event.0 > 0 {
+                s.send(Step(r.event.0 - 1));
+            }
+        });
+
+        world.send(Step(100_000));
+
+        assert_eq!(count.load(Ordering::Relaxed), 100_001);
+    }
+
+    #[test]
+    fn deep_despawn_chain() {
+        let mut world = World::new();
+
+        let ids: Vec<_> = (0..100_001).map(|_| world.spawn()).collect();
+        let first = ids[0];
+        let mut next = 1;
+
+        // Each `Despawned` despawns the next entity in the chain.
+        world.add_system(move |_: Receiver<Despawned>, mut s: Sender<Despawn>| {
+            if let Some(&e) = ids.get(next) {
+                next += 1;
+                s.send(Despawn(e));
+            }
+        });
+
+        world.despawn(first);
+
+        assert_eq!(world.entities().len(), 0);
+    }
+
+    #[test]
+    fn mixed_deferred_and_immediate() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B;
+
+        #[derive(Event)]
+        #[event(deferred)]
+        struct D;
+
+        let mut world = World::new();
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |_: Receiver<A>, mut s: Sender<(B, D)>| {
+            l.lock().unwrap().push("A");
+            s.send(D);
+            s.send(B);
+        });
+        let l = log.clone();
+        world.add_system(move |_: Receiver<B>| l.lock().unwrap().push("B"));
+        let l = log.clone();
+        world.add_system(move |_: Receiver<D>| l.lock().unwrap().push("D"));
+
+        world.send(A);
+        world.send(D);
+
+        assert_eq!(*log.lock().unwrap(), ["A", "B", "D", "D"]);
+    }
+
+    #[test]
+    fn bubbling_through_hierarchy() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        use crate::entity::ChildOf;
+
+        #[derive(Event)]
+        #[event(bubbles)]
+        struct Click(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct Name(&'static str);
+
+        let mut world = World::new();
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<Click, &Name>| {
+            l.lock().unwrap().push((r.query.0, r.original_target));
+        });
+
+        let root = world.spawn();
+        world.insert(root, Name("root"));
+
+        let panel = world.spawn();
+        world.insert(panel, Name("panel"));
+        world.insert(panel, ChildOf(root));
+
+        let button = world.spawn();
+        world.insert(button, Name("button"));
+        world.insert(button, ChildOf(panel));
+
+        world.send(Click(button));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [("button", button), ("panel", button), ("root", button)]
+        );
+    }
+
+    #[test]
+    fn bubbling_stops_when_consumed() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        use crate::entity::ChildOf;
+
+        #[derive(Event)]
+        #[event(bubbles)]
+        struct Click(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct Name(&'static str);
+
+        #[derive(Component)]
+        struct Consumer;
+
+        let mut world = World::new();
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(
+            (move |r: Receiver<Click, &Name>| l.lock().unwrap().push(r.query.0)).before(),
+        );
+        world.add_system(|r: ReceiverMut<Click, With<&Consumer>>| {
+            EventMut::take(r.event);
+        });
+
+        let root = world.spawn();
+        world.insert(root, Name("root"));
+
+        let panel = world.spawn();
+        world.insert(panel, Name("panel"));
+        world.insert(panel, ChildOf(root));
+        world.insert(panel, Consumer);
+
+        let button = world.spawn();
+        world.insert(button, Name("button"));
+        world.insert(button, ChildOf(panel));
+
+        world.send(Click(button));
+
+        assert_eq!(*log.lock().unwrap(), ["button", "panel"]);
+    }
+
+    #[test]
+    fn bubbling_cycle_is_bounded() {
+        use crate::entity::ChildOf;
+        use crate::event::MAX_BUBBLE_DEPTH;
+
+        #[derive(Event)]
+        #[event(bubbles)]
+        struct Click(#[event(target)] EntityId);
+
+        struct Count(u32);
+
+        let mut world = World::new();
+
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(a, ChildOf(b));
+        world.insert(b, ChildOf(a));
+
+        world.insert_global(Count(0));
+        world.add_system(|_: Receiver<Click, ()>, mut c: ResMut<Count>| c.0 += 1);
+
+        world.send(Click(a));
+
+        assert_eq!(world.global::<Count>().unwrap().0, MAX_BUBBLE_DEPTH + 1);
+    }
+
+    #[test]
+    fn nested_target_path() {
+        #[derive(Event)]
+        #[event(target = "contact.receiver")]
+        struct Hit {
+            contact: Contact,
+        }
+
+        struct Contact {
+            receiver: EntityId,
+        }
+
+        #[derive(Component)]
+        struct Hits(u32);
+
+        let mut world = World::new();
+
+        world.add_system(|r: Receiver<Hit, &mut Hits>| r.query.0 += 1);
+
+        let e = world.spawn();
+        world.insert(e, Hits(0));
+
+        world.send(Hit {
+            contact: Contact { receiver: e },
+        });
+
+        assert_eq!(world.get_component::<Hits>(e).unwrap().0, 1);
+    }
+
+    #[test]
+    fn enum_variant_targets() {
+        #[derive(Event)]
+        enum Input {
+            Key(#[event(target)] EntityId),
+            Click {
+                #[event(target)]
+                target: EntityId,
+            },
+        }
+
+        #[derive(Component)]
+        struct Presses(u32);
+
+        let mut world = World::new();
+
+        world.add_system(|r: Receiver<Input, &mut Presses>| r.query.0 += 1);
+
+        let a = world.spawn();
+        world.insert(a, Presses(0));
+        let b = world.spawn();
+        world.insert(b, Presses(0));
+
+        world.send(Input::Key(a));
+        world.send(Input::Click { target: b });
+        world.send(Input::Click { target: b });
+
+        assert_eq!(world.get_component::<Presses>(a).unwrap().0, 1);
+        assert_eq!(world.get_component::<Presses>(b).unwrap().0, 2);
+
+        let mut input = Input::Key(a);
+        input.set_target(b);
+        assert_eq!(input.target(), b);
+    }
+
+    #[test]
+    fn multiple_targets() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        struct Attack {
+            attacker: EntityId,
+            victim: EntityId,
+        }
+
+        impl Event for Attack {
+            const IS_TARGETED: bool = true;
+
+            fn target(&self) -> EntityId {
+                self.victim
+            }
+
+            fn targets(&self) -> impl Iterator<Item = EntityId> {
+                [self.attacker, self.victim].into_iter()
+            }
+
+            fn set_target(&mut self, target: EntityId) {
+                self.victim = target;
+            }
+        }
+
+        #[derive(Component)]
+        struct Name(&'static str);
+
+        let mut world = World::new();
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<Attack, &Name>| {
+            assert_eq!(r.target, r.original_target);
+            l.lock().unwrap().push(r.query.0);
+        });
+
+        let a = world.spawn();
+        world.insert(a, Name("a"));
+
+        let b = world.spawn();
+        world.insert(b, Name("b"));
+
+        world.send(Attack {
+            attacker: a,
+            victim: b,
+        });
+
+        // Duplicate targets receive the event once per occurrence.
+        world.send(Attack {
+            attacker: b,
+            victim: b,
+        });
+
+        // Targets which don't exist are skipped.
+        let dead = world.spawn();
+        world.despawn(dead);
+
+        world.send(Attack {
+            attacker: dead,
+            victim: a,
+        });
+
+        assert_eq!(*log.lock().unwrap(), ["a", "b", "b", "b", "a"]);
+    }
+
+    #[test]
+    fn multiple_targets_stop_when_consumed() {
+        struct Attack([EntityId; 2]);
+
+        impl Event for Attack {
+            const IS_TARGETED: bool = true;
+
+            fn target(&self) -> EntityId {
+                self.0[0]
+            }
+
+            fn targets(&self) -> impl Iterator<Item = EntityId> {
+                self.0.into_iter()
+            }
+        }
+
+        #[derive(Component)]
+        struct Hits(u32);
+
+        let mut world = World::new();
+
+        world.add_system(|r: ReceiverMut<Attack, &mut Hits>| {
+            r.query.0 += 1;
+            EventMut::take(r.event);
+        });
+
+        let a = world.spawn();
+        world.insert(a, Hits(0));
+
+        let b = world.spawn();
+        world.insert(b, Hits(0));
+
+        world.send(Attack([a, b]));
+
+        assert_eq!(world.get_component::<Hits>(a).unwrap().0, 1);
+        assert_eq!(world.get_component::<Hits>(b).unwrap().0, 0);
+    }
+
+    #[test]
+    fn multiple_targets_across_archetypes() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        struct Hit(Vec<EntityId>);
+
+        impl Event for Hit {
+            const IS_TARGETED: bool = true;
+
+            fn target(&self) -> EntityId {
+                self.0[0]
+            }
+
+            fn targets(&self) -> impl Iterator<Item = EntityId> {
+                self.0.clone().into_iter()
+            }
+        }
+
+        #[derive(Component)]
+        struct Name(&'static str);
+
+        #[derive(Component)]
+        struct Armor;
+
+        let mut world = World::new();
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<Hit, (&Name, Has<&Armor>)>| {
+            let (name, armor) = r.query;
+            l.lock().unwrap().push((name.0, armor.get()));
+        });
+
+        let spawn = |world: &mut World, name, armor| {
+            let e = world.spawn();
+            world.insert(e, Name(name));
+            if armor {
+                world.insert(e, Armor);
+            }
+            e
+        };
+
+        let a = spawn(&mut world, "a", false);
+        let b = spawn(&mut world, "b", false);
+        let c = spawn(&mut world, "c", true);
+        let d = spawn(&mut world, "d", false);
+
+        world.send(Hit(vec![a, b, c, d, a]));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                ("a", false),
+                ("b", false),
+                ("c", true),
+                ("d", false),
+                ("a", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn send_after_event_index_reused() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B;
+
+        let mut world = World::new();
+
+        // The second send finds `A` already registered.
+        world.send(A);
+        world.send(A);
+
+        let a = world
+            .events()
+            .get_by_type_id(TypeId::of::<A>())
+            .unwrap()
+            .id();
+        world.remove_event(a);
+
+        let b = world.add_event::<B>();
+        assert_eq!(a.index(), b.index());
+
+        let count = Arc::new(AtomicU32::new(0));
+
+        let c = count.clone();
+        world.add_system(move |_: Receiver<B>| {
+            c.fetch_add(1, Ordering::Relaxed);
+        });
+
+        // `A` must not be sent as `B`, which took its index.
+        world.send(A);
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+
+        world.send(B);
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn event_mutation_pipeline() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct Damage {
+            amount: u32,
+            armor: u32,
+            resist: u32,
+            crit: bool,
+        }
+
+        let mut world = World::new();
+
+        let result = Arc::new(Mutex::new(None));
+        let result_clone = result.clone();
+
+        // Added out of order so that priorities decide who runs first.
+        world.add_system(
+            (move |r: Receiver<Damage>| {
+                *result_clone.lock().unwrap() = Some((r.event.amount, r.event.crit));
+            })
+            .after(),
+        );
+
+        world.add_system(
+            (|mut r: ReceiverMut<Damage>| {
+                r.event.crit = true;
+                r.event.amount *= 2;
+            })
+            .before(),
+        );
+
+        world.add_system(|mut r: ReceiverMut<Damage>| {
+            assert!(r.event.crit);
+            r.event.amount = r.event.amount.saturating_sub(r.event.armor);
+        });
+
+        world.add_system(|mut r: ReceiverMut<Damage>| {
+            r.event.amount -= r.event.amount * r.event.resist / 100;
+        });
+
+        world.send(Damage {
+            amount: 50,
+            armor: 20,
+            resist: 50,
+            crit: false,
+        });
+
+        // (50 * 2 - 20) * 50%
+        assert_eq!(*result.lock().unwrap(), Some((40, true)));
+    }
+
+    #[test]
+    fn send_boxed_large_event() {
+        use alloc::boxed::Box;
+        use alloc::sync::Arc;
+        use core::ptr::addr_of_mut;
+
+        const LEN: usize = 64 * 1024;
+
+        #[derive(Event)]
+        struct Big {
+            #[event(target)]
+            target: EntityId,
+            bytes: [u8; LEN],
+            tag: Arc<()>,
+        }
+
+        /// Builds the event on the heap so it never touches the stack.
+        fn new_big(target: EntityId, tag: &Arc<()>) -> Box<Big> {
+            let mut big = Box::<Big>::new_uninit();
+            let ptr = big.as_mut_ptr();
+
+            unsafe {
+                addr_of_mut!((*ptr).target).write(target);
+                addr_of_mut!((*ptr).bytes).write_bytes(7, 1);
+                addr_of_mut!((*ptr).tag).write(tag.clone());
+
+                big.assume_init()
+            }
+        }
+
+        #[derive(Event)]
+        struct Forward(EntityId);
+
+        let mut world = World::new();
+
+        let tag = Arc::new(());
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+
+        let t = tag.clone();
+        world.add_system(move |r: Receiver<Forward>, mut s: Sender<Big>| {
+            s.send_boxed(new_big(r.event.0, &t));
+        });
+
+        world.add_system(|r: Receiver<Big, EntityId>| {
+            assert_eq!(r.event.target, r.query);
+            assert!(r.event.bytes.iter().all(|&b| b == 7));
+        });
+
+        // Events are dropped by the queue unless a system takes them.
+        world.add_system(move |r: ReceiverMut<Big, ()>| {
+            if r.event.target == e2 {
+                assert_eq!(EventMut::take(r.event).target, e2);
+            }
+        });
+
+        world.send_boxed(new_big(e1, &tag));
+        world.send(Forward(e2));
+
+        assert_eq!(Arc::strong_count(&tag), 2);
+    }
+
+    #[test]
+    fn dead_target_policy() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicU32, Ordering};
+        use std::panic::catch_unwind;
+        use std::sync::Mutex;
+
+        use crate::event::{DeadTarget, DeadTargetPolicy};
+        use crate::world::WarningInfo;
+
+        #[derive(Event)]
+        struct Message(#[event(target)] EntityId, #[allow(dead_code)] Arc<()>);
+
+        static WARNINGS: AtomicU32 = AtomicU32::new(0);
+
+        let mut world = World::new();
+
+        let payload = Arc::new(());
+        let rerouted = Arc::new(Mutex::new(vec![]));
+
+        let r = rerouted.clone();
+        world.add_system(move |r2: Receiver<DeadTarget>| r.lock().unwrap().push(*r2.event));
+        let dead_target = world.add_event::<DeadTarget>();
+
+        world.set_warning_handler(Some(|info| {
+            assert!(matches!(info, WarningInfo::DeadTarget { .. }));
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        let message = world.add_event::<Message>();
+        let alive = world.spawn();
+        let dead = world.spawn();
+        world.despawn(dead);
+
+        let policies = [
+            DeadTargetPolicy::Ignore,
+            DeadTargetPolicy::Warn,
+            DeadTargetPolicy::Reroute(dead_target),
+            DeadTargetPolicy::Panic,
+        ];
+
+        for policy in policies {
+            world.set_dead_target_policy(policy);
+
+            // Live targets and `Despawn` are unaffected.
+            world.send(Message(alive, payload.clone()));
+            world.despawn(dead);
+
+            let p = payload.clone();
+            let res = catch_unwind(core::panic::AssertUnwindSafe(|| {
+                world.send(Message(dead, p));
+            }));
+
+            assert_eq!(res.is_err(), policy == DeadTargetPolicy::Panic);
+            assert_eq!(Arc::strong_count(&payload), 1);
+        }
+
+        assert_eq!(WARNINGS.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            *rerouted.lock().unwrap(),
+            [DeadTarget {
+                event: message,
+                target: dead
+            }]
+        );
+    }
+
+    #[test]
+    fn dead_target_reroute_dynamic() {
+        use alloc::sync::Arc;
+        use core::alloc::Layout;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        use crate::bool_expr::BoolExpr;
+        use crate::event::{DeadTarget, DeadTargetPolicy, EventDescriptor, EventKind};
+
+        #[derive(Event)]
+        struct Message(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        let lost = unsafe {
+            world.add_event_with_descriptor(EventDescriptor {
+                name: "LostMessage".into(),
+                type_id: None,
+                key: None,
+                is_targeted: false,
+                kind: EventKind::Normal,
+                layout: Layout::new::<DeadTarget>(),
+                drop: None,
+                clone_fn: None,
+                default_fn: None,
+                is_immutable: true,
+                is_deferred: false,
+                bubbles: None,
+                targets: None,
+                target_filter: BoolExpr::new(true),
+            })
+        };
+
+        world.set_dead_target_policy(DeadTargetPolicy::Reroute(lost));
+
+        let count = Arc::new(AtomicU32::new(0));
+
+        let c = count.clone();
+        world.add_system(move |_: Receiver<DeadTarget>| {
+            c.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let e = world.spawn();
+        world.despawn(e);
+        world.send(Message(e));
+
+        // Rerouted events are only sent as the chosen event.
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't receive rerouted dead target events")]
+    fn dead_target_reroute_rejects_other_events() {
+        use crate::event::DeadTargetPolicy;
+
+        #[derive(Event)]
+        struct Message(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        let message = world.add_event::<Message>();
+        world.set_dead_target_policy(DeadTargetPolicy::Reroute(message));
+    }
+
+    #[test]
+    #[should_panic(expected = "received event access")]
+    fn receiver_mut_conflicts_with_receiver() {
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        world.add_system(|_: ReceiverMut<E>, _: Receiver<E>| {});
+    }
+
+    #[derive(Event)]
+    struct Targeted(#[event(target)] EntityId);
+
+    #[derive(Component)]
+    struct C;
+
+    #[derive(Component)]
+    struct Marker;
+
+    #[test]
+    #[should_panic(expected = "could alias the components of the received event's target")]
+    fn receiver_and_fetcher_alias_target() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<Targeted, &mut C>, _: Fetcher<&mut C>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "the event target's components could be aliased")]
+    fn fetcher_before_receiver_alias_target() {
+        let mut world = World::new();
+
+        world.add_system(|_: Fetcher<&C>, _: ReceiverMut<Targeted, &mut C>| {});
+    }
+
+    #[test]
+    fn receiver_and_fetcher_disjoint_target() {
+        let mut world = World::new();
+
+        world.add_system(
+            |_: Receiver<Targeted, (&mut C, With<&Marker>)>,
+             _: Fetcher<(&mut C, Without<&Marker>)>| {},
+        );
+        world.add_system(|_: Receiver<Targeted, &C>, _: Fetcher<&C>| {});
+    }
+
+    #[test]
+    fn target_filter() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Mutex;
+
+        #[derive(Component)]
+        struct Health(i32);
+
+        #[derive(Component)]
+        struct Dead;
+
+        #[derive(Component)]
+        struct Armor;
+
+        #[derive(Event)]
+        #[event(requires(Health), excludes(Dead))]
+        struct Damage {
+            amount: i32,
+            #[event(target)]
+            entity: EntityId,
+        }
+
+        static FILTERED: AtomicU32 = AtomicU32::new(0);
+
+        let mut world = World::new();
+
+        world.set_filtered_event_hook(Some(|info, _| {
+            assert!(info.name().ends_with("Damage"));
+            FILTERED.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        let armored = Arc::new(Mutex::new(0));
+
+        world.add_system(|r: Receiver<Damage, &mut Health>| {
+            r.query.0 -= r.event.amount;
+        });
+
+        let armored_clone = armored.clone();
+        world.add_system(move |_: Receiver<Damage, With<&Armor>>| {
+            *armored_clone.lock().unwrap() += 1;
+        });
+
+        let alive = world.spawn();
+        world.insert(alive, Health(100));
+
+        let dead = world.spawn();
+        world.insert(dead, Health(100));
+        world.insert(dead, Dead);
+
+        let no_health = world.spawn();
+        world.insert(no_health, Armor);
+
+        let tank = world.spawn();
+        world.insert(tank, Health(100));
+        world.insert(tank, Armor);
+
+        for entity in [alive, dead, no_health, tank] {
+            world.send(Damage { amount: 10, entity });
+        }
+
+        assert_eq!(world.get_component::<Health>(alive).unwrap().0, 90);
+        assert_eq!(world.get_component::<Health>(dead).unwrap().0, 100);
+        assert_eq!(world.get_component::<Health>(tank).unwrap().0, 90);
+        assert_eq!(*armored.lock().unwrap(), 1);
+        assert_eq!(FILTERED.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn remove_target_filter_component() {
+        #[derive(Component)]
+        struct C;
+
+        #[derive(Event)]
+        #[event(excludes(C))]
+        struct E(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        let system = world.add_system(|_: Receiver<E, ()>| {});
+        let event = world.add_event::<E>();
+        let component = world.add_component::<C>();
+
+        world.remove_component(component);
+
+        assert!(!world.events().contains(event));
+        assert!(!world.systems().contains(system));
+    }
+
+    #[derive(Event)]
+    struct Fire(#[event(target)] EntityId, u32);
+
+    #[derive(Event)]
+    struct Ice(#[event(target)] EntityId, u32);
+
+    #[derive(EventEnum)]
+    enum Damage<'a> {
+        Fire(&'a Fire),
+        Ice(&'a Ice),
+    }
+
+    #[test]
+    fn receiver_any() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct A(u32);
+
+        #[derive(Event)]
+        struct B(&'static str);
+
+        #[derive(EventEnum)]
+        enum AOrB<'a> {
+            A(&'a A),
+            B(&'a B),
+        }
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let mut world = World::new();
+
+        let l = log.clone();
+        world.add_system(move |r: ReceiverAny<AOrB>| {
+            l.lock().unwrap().push(match r.event {
+                AOrB::A(a) => a.0.to_string(),
+                AOrB::B(b) => b.0.to_owned(),
+            });
+        });
+
+        world.send(A(1));
+        world.send(B("b"));
+        world.send(A(2));
+
+        assert_eq!(*log.lock().unwrap(), ["1", "b", "2"]);
+    }
+
+    #[test]
+    fn receiver_any_targeted() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Component)]
+        struct Health(u32);
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let mut world = World::new();
+
+        let l = log.clone();
+        world.add_system(move |_: Receiver<Fire, ()>| l.lock().unwrap().push("fire"));
+
+        let l = log.clone();
+        let any = world.add_system(
+            (move |r: ReceiverAny<Damage, &mut Health>| {
+                r.query.0 -= match r.event {
+                    Damage::Fire(e) => e.1,
+                    Damage::Ice(e) => e.1,
+                };
+                l.lock().unwrap().push("damage");
+            })
+            .before(),
+        );
+
+        let l = log.clone();
+        world.add_system(move |_: Receiver<Ice, ()>| l.lock().unwrap().push("ice"));
+
+        let e = world.spawn();
+        world.insert(e, Health(100));
+        let no_health = world.spawn();
+
+        world.send(Fire(e, 10));
+        world.send(Ice(e, 5));
+        world.send(Fire(no_health, 1));
+
+        assert_eq!(world.get_component::<Health>(e).unwrap().0, 85);
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["damage", "fire", "damage", "ice", "fire"]
+        );
+
+        // Removing the system removes it from the listeners of both events.
+        let fire = world.add_event::<Fire>();
+        let ice = world.add_event::<Ice>();
+        assert_eq!(world.system_order_for_event(fire)[0], any);
+        assert_eq!(world.system_order_for_event(ice)[0], any);
+
+        world.remove_system(any);
+        log.lock().unwrap().clear();
+
+        world.send(Fire(e, 10));
+        world.send(Ice(e, 5));
+
+        assert_eq!(world.get_component::<Health>(e).unwrap().0, 85);
+        assert_eq!(*log.lock().unwrap(), ["fire", "ice"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Add a query, such as `()`, to receive targeted events")]
+    fn receiver_any_untargeted_with_targeted_events() {
+        let mut world = World::new();
+
+        world.add_system(|_: ReceiverAny<Damage>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "Systems must have exactly one receiver")]
+    fn receiver_any_with_receiver() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<Fire, ()>, _: ReceiverAny<Damage, ()>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "already configured to receive")]
+    fn receiver_after_receiver_any() {
+        let mut world = World::new();
+
+        world.add_system(|_: ReceiverAny<Damage, ()>, _: Receiver<Fire, ()>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "appears more than once")]
+    fn receiver_any_duplicate_event() {
+        #[allow(dead_code)]
+        #[derive(EventEnum)]
+        enum Twice<'a> {
+            First(&'a Fire),
+            Second(&'a Fire),
+        }
+
+        let mut world = World::new();
+
+        world.add_system(|_: ReceiverAny<Twice, ()>| {});
+    }
 }
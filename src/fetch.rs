@@ -1,16 +1,27 @@
 //! Accessing components on entities.
 
+use alloc::boxed::Box;
 use alloc::format;
+use alloc::vec::Vec;
 use core::iter::FusedIterator;
 use core::marker::PhantomData;
+use core::ops::Range;
 use core::ptr::NonNull;
-use core::{any, fmt};
+use core::{any, fmt, slice};
 
-use crate::archetype::{Archetype, ArchetypeIdx, ArchetypeRow, Archetypes};
+use crate::access::AccessMap;
+use crate::archetype::{Archetype, ArchetypeIdx, ArchetypeRow, Archetypes, ColumnVersion};
 use crate::assert::{assume_debug_checked, UnwrapDebugChecked};
+use crate::bit_set::BitSet;
+use crate::blob_vec::slot_in;
+use crate::bool_expr::BoolExpr;
+use crate::component::ComponentIdx;
+#[cfg(feature = "diff")]
+use crate::diff::ChangeFlag;
 use crate::entity::{Entities, EntityId};
+use crate::entity_group::EntityGroupId;
 use crate::event::EventPtr;
-use crate::query::{Query, ReadOnlyQuery};
+use crate::query::{ColumnPtr, DynamicQuery, Query, ReadOnlyQuery, SliceQuery, PREFETCH_SUPPORTED};
 use crate::sparse_map::SparseMap;
 use crate::system::{Config, InitError, SystemInfo, SystemParam};
 use crate::world::{UnsafeWorldCell, World};
@@ -18,25 +29,47 @@ use crate::world::{UnsafeWorldCell, World};
 /// Internal state for a [`Fetcher`].
 #[doc(hidden)]
 pub struct FetcherState<Q: Query> {
+    /// The states of matched archetypes that have entities. This is what
+    /// iteration walks, so empty archetypes are skipped without a check.
     map: SparseMap<ArchetypeIdx, Q::ArchState>,
+    /// The states of matched archetypes that became empty, along with the
+    /// column version they were created for.
+    inactive: SparseMap<ArchetypeIdx, (ColumnVersion, Q::ArchState)>,
     state: Q::State,
+    /// The components accessed by the query. Empty unless initialized with
+    /// [`FetcherState::init`].
+    access: AccessMap<ComponentIdx>,
+    config: FetcherConfig,
 }
 
 impl<Q: Query> FetcherState<Q> {
     pub(crate) fn new(state: Q::State) -> Self {
         Self {
             map: SparseMap::new(),
+            inactive: SparseMap::new(),
             state,
+            access: AccessMap::new(),
+            config: FetcherConfig::default(),
         }
     }
 
     pub(crate) fn init(world: &mut World, config: &mut Config) -> Result<Self, InitError> {
         let (expr, state) = Q::init(world, config)?;
 
-        let res = FetcherState::new(state);
+        let mut res = FetcherState::new(state);
+        res.access = expr.access.clone();
 
         match expr.or(&config.component_access) {
             Ok(new_component_access) => config.component_access = new_component_access,
+            Err(expr) if !expr.is_compatible(&config.targeted_event_access) => {
+                return Err(InitError(
+                    format!(
+                        "query `{}` could alias the components of the received event's target",
+                        any::type_name::<Q>()
+                    )
+                    .into(),
+                ))
+            }
             Err(_) => {
                 return Err(InitError(
                     format!(
@@ -52,6 +85,18 @@ impl<Q: Query> FetcherState<Q> {
         Ok(res)
     }
 
+    /// Like [`init`](Self::init), but uses [`Query::init_existing`] and
+    /// doesn't modify the world. Returns `None` if the query has conflicting
+    /// access.
+    pub(crate) fn init_existing(world: &World) -> Option<Self> {
+        let (expr, state) = Q::init_existing(world)?;
+
+        let mut res = FetcherState::new(state);
+        res.access = expr.access;
+
+        Some(res)
+    }
+
     #[inline]
     pub(crate) unsafe fn get(
         &self,
@@ -111,6 +156,44 @@ impl<Q: Query> FetcherState<Q> {
         self.iter_unchecked(archetypes)
     }
 
+    /// Returns an iterator over the entities of `group`. See
+    /// [`Fetcher::iter_in_group`].
+    pub(crate) unsafe fn iter_in_group_unchecked<'a>(
+        &'a self,
+        archetypes: &'a Archetypes,
+        group: EntityGroupId,
+    ) -> EntityGroupIter<'a, Q> {
+        EntityGroupIter {
+            indices: self.map.keys().iter(),
+            states: self.map.values().iter(),
+            state: None,
+            rows: 0..0,
+            group,
+            archetypes,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator looking up each of `ids` in turn. If `UNIQUE` is
+    /// set, the iterator refuses to return the same entity twice.
+    pub(crate) unsafe fn iter_many<'a, I, const UNIQUE: bool>(
+        &'a self,
+        entities: &'a Entities,
+        ids: I,
+    ) -> TryManyIter<'a, Q, I::IntoIter, UNIQUE>
+    where
+        I: IntoIterator<Item = EntityId>,
+    {
+        TryManyIter {
+            ids: ids.into_iter(),
+            map: &self.map,
+            entities,
+            last: (ArchetypeIdx::NULL, None),
+            seen: BitSet::new(),
+            _marker: PhantomData,
+        }
+    }
+
     unsafe fn iter_unchecked<'a>(&'a self, archetypes: &'a Archetypes) -> Iter<'a, Q> {
         let indices = self.map.keys();
         let states = self.map.values();
@@ -121,48 +204,234 @@ impl<Q: Query> FetcherState<Q> {
             unsafe { archetypes.get(idx).unwrap_debug_checked() }.entity_count()
         });
 
-        let state = NonNull::new(states.as_ptr().cast_mut()).unwrap_or(NonNull::dangling());
-        let state_last = if states.is_empty() {
-            NonNull::dangling()
+        // The index vector may still hold an allocation after its archetypes were
+        // removed, so `index` and `index_last` must both be dangling when it's empty.
+        // The end is tracked with the indices rather than the states, because
+        // pointers to zero-sized states never move.
+        let (index, index_last) = if indices.is_empty() {
+            (NonNull::dangling(), NonNull::dangling())
         } else {
-            NonNull::new(state.as_ptr().add(states.len() - 1)).unwrap_debug_checked()
+            let index = NonNull::new(indices.as_ptr().cast_mut()).unwrap_debug_checked();
+            (
+                index,
+                NonNull::new(index.as_ptr().add(indices.len() - 1)).unwrap_debug_checked(),
+            )
         };
 
-        let index = NonNull::new(indices.as_ptr().cast_mut()).unwrap_or(NonNull::dangling());
+        let state = NonNull::new(states.as_ptr().cast_mut()).unwrap_or(NonNull::dangling());
 
         Iter {
             state,
-            state_last,
             index,
+            index_last,
             row: ArchetypeRow(0),
             len: first_arch_len,
+            prefetch_distance: self.config.prefetch_distance,
+            archetypes,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub(crate) unsafe fn for_each<'a, F>(&'a self, archetypes: &'a Archetypes, f: F)
+    where
+        Q: ReadOnlyQuery,
+        F: FnMut(Q::Item<'a>),
+    {
+        self.for_each_unchecked(archetypes, f)
+    }
+
+    #[inline]
+    pub(crate) unsafe fn for_each_mut<'a, F>(&'a mut self, archetypes: &'a Archetypes, f: F)
+    where
+        F: FnMut(Q::Item<'a>),
+    {
+        self.for_each_unchecked(archetypes, f)
+    }
+
+    #[inline]
+    unsafe fn for_each_unchecked<'a, F>(&'a self, archetypes: &'a Archetypes, mut f: F)
+    where
+        F: FnMut(Q::Item<'a>),
+    {
+        for (&idx, state) in self.map.keys().iter().zip(self.map.values()) {
+            let len = unsafe { archetypes.get(idx).unwrap_debug_checked() }.entity_count();
+
+            // Nothing in the inner loop depends on the archetype bookkeeping, so it
+            // can be unrolled and vectorized.
+            for row in 0..len {
+                f(Q::get(state, ArchetypeRow(row)));
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) unsafe fn iter_slices<'a>(&'a self, archetypes: &'a Archetypes) -> SliceIter<'a, Q>
+    where
+        Q: ReadOnlyQuery + SliceQuery,
+    {
+        self.iter_slices_unchecked(archetypes)
+    }
+
+    #[inline]
+    pub(crate) unsafe fn iter_slices_mut<'a>(
+        &'a mut self,
+        archetypes: &'a Archetypes,
+    ) -> SliceIter<'a, Q>
+    where
+        Q: SliceQuery,
+    {
+        self.iter_slices_unchecked(archetypes)
+    }
+
+    unsafe fn iter_slices_unchecked<'a>(&'a self, archetypes: &'a Archetypes) -> SliceIter<'a, Q>
+    where
+        Q: SliceQuery,
+    {
+        SliceIter {
+            indices: self.map.keys().iter(),
+            states: self.map.values().iter(),
             archetypes,
             _marker: PhantomData,
         }
     }
 
+    #[inline]
+    pub(crate) unsafe fn single<'a>(
+        &'a self,
+        archetypes: &'a Archetypes,
+    ) -> Result<Q::Item<'a>, SingleError>
+    where
+        Q: ReadOnlyQuery,
+    {
+        self.single_unchecked(archetypes)
+    }
+
+    #[inline]
+    pub(crate) unsafe fn single_mut<'a>(
+        &'a mut self,
+        archetypes: &'a Archetypes,
+    ) -> Result<Q::Item<'a>, SingleError> {
+        self.single_unchecked(archetypes)
+    }
+
+    unsafe fn single_unchecked<'a>(
+        &'a self,
+        archetypes: &'a Archetypes,
+    ) -> Result<Q::Item<'a>, SingleError> {
+        let mut it = self.iter_unchecked(archetypes);
+
+        let Some(item) = it.next() else {
+            return Err(SingleError::QueryDoesNotMatch);
+        };
+
+        if it.next().is_some() {
+            // Counting the rest only needs the archetype lengths, so we don't have to
+            // visit the remaining entities.
+            return Err(SingleError::MoreThanOneMatch {
+                count: it.len() + 2,
+            });
+        }
+
+        Ok(item)
+    }
+
+    /// Returns the number of matched archetypes this state knows about. See
+    /// [`Fetcher::matched_archetype_count`].
+    pub fn matched_archetype_count(&self) -> usize {
+        self.map.keys().len() + self.inactive.keys().len()
+    }
+
+    /// Returns the number of matched archetypes that have entities. See
+    /// [`Fetcher::active_archetype_count`].
+    pub fn active_archetype_count(&self) -> usize {
+        self.map.keys().len()
+    }
+
     pub(crate) fn refresh_archetype(&mut self, arch: &Archetype) {
+        let idx = arch.index();
+
+        // An archetype that gets entities again keeps its old state unless the
+        // columns were reallocated while it was empty.
+        if let Some((version, state)) = self.inactive.remove(idx) {
+            if version == arch.column_version() {
+                self.map.insert(idx, state);
+                return;
+            }
+        }
+
         if let Some(fetch) = Q::new_arch_state(arch, &mut self.state) {
-            self.map.insert(arch.index(), fetch);
+            self.map.insert(idx, fetch);
         }
     }
 
     pub(crate) fn remove_archetype(&mut self, arch: &Archetype) {
-        self.map.remove(arch.index());
+        let idx = arch.index();
+
+        match self.map.remove(idx) {
+            Some(state) if arch.entity_count() == 0 => {
+                self.inactive.insert(idx, (arch.column_version(), state));
+            }
+            // The archetype is being removed from the world.
+            _ => {
+                self.inactive.remove(idx);
+            }
+        }
     }
 
-    // TODO: get_many_mut
+    /// Creates the state for `Q2` out of the archetypes already matched by
+    /// `Q`.
+    fn transmute<Q2: Query>(&self, world: &World) -> Result<FetcherState<Q2>, TransmuteError> {
+        let Some((expr, state)) = Q2::init_existing(world) else {
+            return Err(TransmuteError::Init);
+        };
+
+        if !expr.access.is_subset(&self.access) {
+            return Err(TransmuteError::Access);
+        }
+
+        let mut res = FetcherState::<Q2>::new(state);
+        res.access = expr.access;
+        res.config = self.config;
+
+        for &idx in self.map.keys() {
+            let arch = unsafe { world.archetypes().get(idx).unwrap_debug_checked() };
+            res.refresh_archetype(arch);
+        }
+
+        Ok(res)
+    }
 }
 
 impl<Q: Query> fmt::Debug for FetcherState<Q> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FetcherState")
             .field("map", &self.map)
+            .field("inactive", &self.inactive)
             .field("state", &self.state)
+            .field("access", &self.access)
+            .field("config", &self.config)
             .finish()
     }
 }
 
+/// Settings for how a [`Fetcher`] iterates over entities.
+///
+/// The settings are kept in the fetcher's state, so changing them with
+/// [`Fetcher::set_config`] affects every later run of the system.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct FetcherConfig {
+    /// How many rows ahead of the current entity the components of the query
+    /// are prefetched, or 0 to disable prefetching. This is 0 by default.
+    ///
+    /// Iterating a large archetype is often limited by memory bandwidth,
+    /// especially when the query reads several columns. Prefetching a few
+    /// dozen rows ahead lets the CPU load the upcoming components while the
+    /// current ones are being processed. Prefetching is skipped on targets
+    /// without a prefetch instruction.
+    pub prefetch_distance: u32,
+}
+
 /// A [`SystemParam`] for accessing data from entities matching a given
 /// [`Query`].
 ///
@@ -195,8 +464,30 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
         unsafe { self.state.get_mut(self.world.entities(), entity) }
     }
 
+    /// Returns the read-only query item for the only entity matching the
+    /// query.
+    ///
+    /// If the query doesn't match exactly one entity, then a [`SingleError`]
+    /// is returned.
+    #[inline]
+    pub fn single(&self) -> Result<Q::Item<'_>, SingleError>
+    where
+        Q: ReadOnlyQuery,
+    {
+        unsafe { self.state.single(self.world.archetypes()) }
+    }
+
+    /// Returns the query item for the only entity matching the query.
+    ///
+    /// If the query doesn't match exactly one entity, then a [`SingleError`]
+    /// is returned.
+    #[inline]
+    pub fn single_mut(&mut self) -> Result<Q::Item<'_>, SingleError> {
+        unsafe { self.state.single_mut(self.world.archetypes()) }
+    }
+
     /// Returns an iterator over all entities matching the read-only query.
-    pub fn iter(&self) -> Iter<Q>
+    pub fn iter(&self) -> Iter<'_, Q>
     where
         Q: ReadOnlyQuery,
     {
@@ -204,9 +495,334 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
     }
 
     /// Returns an iterator over all entities matching the query.
-    pub fn iter_mut(&mut self) -> Iter<Q> {
+    pub fn iter_mut(&mut self) -> Iter<'_, Q> {
         unsafe { self.state.iter_mut(self.world.archetypes()) }
     }
+
+    /// Returns an iterator over the entities of an [entity group] matching
+    /// the read-only query. Only the rows of the group are visited, so this
+    /// doesn't have to look at the entities of other groups.
+    ///
+    /// [entity group]: crate::entity_group
+    pub fn iter_in_group(&self, group: EntityGroupId) -> EntityGroupIter<'_, Q>
+    where
+        Q: ReadOnlyQuery,
+    {
+        unsafe {
+            self.state
+                .iter_in_group_unchecked(self.world.archetypes(), group)
+        }
+    }
+
+    /// Returns an iterator over the entities of an [entity group] matching
+    /// the query. See [`iter_in_group`](Self::iter_in_group).
+    ///
+    /// [entity group]: crate::entity_group
+    pub fn iter_in_group_mut(&mut self, group: EntityGroupId) -> EntityGroupIter<'_, Q> {
+        unsafe {
+            self.state
+                .iter_in_group_unchecked(self.world.archetypes(), group)
+        }
+    }
+
+    /// Returns an iterator over the read-only query items of the given
+    /// entities, in the order the IDs are given. Entities which don't exist or
+    /// don't match the query are skipped.
+    ///
+    /// This is equivalent to calling [`get`](Self::get) for each entity, but
+    /// consecutive entities in the same archetype share a single archetype
+    /// lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct Lookup(Vec<EntityId>);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.spawn();
+    /// world.insert(a, Health(10));
+    /// let b = world.spawn();
+    /// let c = world.spawn();
+    /// world.insert(c, Health(30));
+    ///
+    /// world.add_system(|r: Receiver<Lookup>, f: Fetcher<&Health>| {
+    ///     let total: u32 = f.iter_many(r.event.0.iter().copied()).map(|h| h.0).sum();
+    ///     assert_eq!(total, 40);
+    /// });
+    ///
+    /// world.send(Lookup(vec![c, b, a]));
+    /// ```
+    pub fn iter_many<I>(&self, ids: I) -> ManyIter<'_, Q, I::IntoIter>
+    where
+        Q: ReadOnlyQuery,
+        I: IntoIterator<Item = EntityId>,
+    {
+        ManyIter(self.try_iter_many(ids))
+    }
+
+    /// Returns an iterator over the query items of the given entities, in the
+    /// order the IDs are given. Entities which don't exist or don't match the
+    /// query are skipped.
+    ///
+    /// Because the items are handed out all at once, an entity is only
+    /// returned the first time its ID appears. Later occurrences are skipped
+    /// like missing entities. Use [`try_iter_many_mut`] to find out about
+    /// them. Returned entities are tracked in a bit set indexed by
+    /// [`EntityIdx`], which makes this somewhat slower than
+    /// [`iter_many`](Self::iter_many).
+    ///
+    /// [`try_iter_many_mut`]: Self::try_iter_many_mut
+    /// [`EntityIdx`]: crate::entity::EntityIdx
+    pub fn iter_many_mut<I>(&mut self, ids: I) -> ManyIter<'_, Q, I::IntoIter, true>
+    where
+        I: IntoIterator<Item = EntityId>,
+    {
+        ManyIter(self.try_iter_many_mut(ids))
+    }
+
+    /// Like [`iter_many`](Self::iter_many), but yields a [`GetError`] for
+    /// every entity which was skipped.
+    pub fn try_iter_many<I>(&self, ids: I) -> TryManyIter<'_, Q, I::IntoIter>
+    where
+        Q: ReadOnlyQuery,
+        I: IntoIterator<Item = EntityId>,
+    {
+        unsafe { self.state.iter_many(self.world.entities(), ids) }
+    }
+
+    /// Like [`iter_many_mut`](Self::iter_many_mut), but yields a
+    /// [`GetError`] for every entity which was skipped. Repeated IDs result
+    /// in [`GetError::AliasedMutability`].
+    pub fn try_iter_many_mut<I>(&mut self, ids: I) -> TryManyIter<'_, Q, I::IntoIter, true>
+    where
+        I: IntoIterator<Item = EntityId>,
+    {
+        unsafe { self.state.iter_many(self.world.entities(), ids) }
+    }
+
+    /// Calls `f` with the read-only query item of every entity matching the
+    /// query, in the same order as [`iter`](Self::iter).
+    ///
+    /// Unlike a `for` loop over [`iter`](Self::iter), this visits the
+    /// entities one archetype at a time in a simple inner loop, which the
+    /// compiler can often vectorize. [Prefetching] is not performed.
+    ///
+    /// [Prefetching]: FetcherConfig::prefetch_distance
+    #[inline]
+    pub fn for_each<'b, F>(&'b self, f: F)
+    where
+        Q: ReadOnlyQuery,
+        F: FnMut(Q::Item<'b>),
+    {
+        unsafe { self.state.for_each(self.world.archetypes(), f) }
+    }
+
+    /// Like [`for_each`](Self::for_each), but for every item of the query.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Pos(f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Vel(f32);
+    ///
+    /// #[derive(Event)]
+    /// struct Step(f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(|r: Receiver<Step>, mut f: Fetcher<(&mut Pos, &Vel)>| {
+    ///     let dt = r.event.0;
+    ///     f.for_each_mut(|(pos, vel)| pos.0 += vel.0 * dt);
+    /// });
+    ///
+    /// world.send(Step(1.0 / 60.0));
+    /// ```
+    #[inline]
+    pub fn for_each_mut<'b, F>(&'b mut self, f: F)
+    where
+        F: FnMut(Q::Item<'b>),
+    {
+        unsafe { self.state.for_each_mut(self.world.archetypes(), f) }
+    }
+
+    /// Returns the [`FetcherConfig`] used by this fetcher.
+    pub fn config(&self) -> FetcherConfig {
+        self.state.config
+    }
+
+    /// Sets the [`FetcherConfig`] used by this fetcher. The change persists
+    /// across runs of the system.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(Event)] struct Tick;
+    /// use evenio::fetch::FetcherConfig;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position([f32; 3]);
+    ///
+    /// #[derive(Component)]
+    /// struct Velocity([f32; 3]);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(
+    ///     |_: Receiver<Tick>, mut f: Fetcher<(&mut Position, &Velocity)>| {
+    ///         f.set_config(FetcherConfig {
+    ///             prefetch_distance: 16,
+    ///         });
+    ///
+    ///         for (pos, vel) in f {
+    ///             for i in 0..3 {
+    ///                 pos.0[i] += vel.0[i];
+    ///             }
+    ///         }
+    ///     },
+    /// );
+    ///
+    /// world.send(Tick);
+    /// ```
+    pub fn set_config(&mut self, config: FetcherConfig) {
+        self.state.config = config;
+    }
+
+    /// Returns the number of archetypes matching the query that have had
+    /// entities while the system existed, whether or not they have entities
+    /// now. Archetypes which were always empty aren't counted.
+    pub fn matched_archetype_count(&self) -> usize {
+        self.state.matched_archetype_count()
+    }
+
+    /// Returns the number of archetypes matching the query that have
+    /// entities. This is the number of archetypes visited by iteration, so
+    /// matched archetypes without entities cost nothing to skip.
+    pub fn active_archetype_count(&self) -> usize {
+        self.state.active_archetype_count()
+    }
+
+    /// Returns an iterator over the archetypes matching the read-only query.
+    /// Each item holds the IDs of the entities in the archetype and the
+    /// query's slices of their components, all with a length of
+    /// [`Archetype::entity_count`].
+    ///
+    /// This is useful for handing whole columns to code which expects
+    /// contiguous data. See [`SliceQuery`] for the queries which can be used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(Event)] struct Tick;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Velocity(f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(
+    ///     |_: Receiver<Tick>, mut f: Fetcher<(&mut Position, &Velocity)>| {
+    ///         for (ids, (positions, velocities)) in f.iter_slices_mut() {
+    ///             assert_eq!(ids.len(), positions.len());
+    ///
+    ///             for (pos, vel) in positions.iter_mut().zip(velocities) {
+    ///                 pos.0 += vel.0;
+    ///             }
+    ///         }
+    ///     },
+    /// );
+    ///
+    /// world.send(Tick);
+    /// ```
+    pub fn iter_slices(&self) -> SliceIter<'_, Q>
+    where
+        Q: ReadOnlyQuery + SliceQuery,
+    {
+        unsafe { self.state.iter_slices(self.world.archetypes()) }
+    }
+
+    /// Like [`iter_slices`](Self::iter_slices), but allows mutable access to
+    /// the components.
+    pub fn iter_slices_mut(&mut self) -> SliceIter<'_, Q>
+    where
+        Q: SliceQuery,
+    {
+        unsafe { self.state.iter_slices_mut(self.world.archetypes()) }
+    }
+
+    /// Borrows this fetcher as a fetcher over the query `Q2`, which may access
+    /// a subset of the components accessed by `Q` and may be more selective
+    /// about the entities it matches. For instance, a `Fetcher<(&A, &mut B)>`
+    /// can be narrowed to a `Fetcher<&mut B>` or a `Fetcher<(&A, With<&C>)>`.
+    ///
+    /// The new fetcher only considers the archetypes already matched by `Q`,
+    /// so the world isn't searched again.
+    ///
+    /// Returns an error if `Q2` accesses a component in a way `Q` doesn't, or
+    /// if `Q2` has conflicting access according to [`Query::init_existing`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(Event)] struct Tick;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Velocity(f32);
+    ///
+    /// fn step(positions: &mut Fetcher<&mut Position>) {
+    ///     for pos in positions {
+    ///         pos.0 += 1.0;
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(
+    ///     |_: Receiver<Tick>, mut f: Fetcher<(&mut Position, &Velocity)>| {
+    ///         step(&mut f.transmute::<&mut Position>().unwrap().fetcher());
+    ///
+    ///         // `Velocity` is only read by the original query.
+    ///         assert!(f.transmute::<&mut Velocity>().is_err());
+    ///     },
+    /// );
+    ///
+    /// world.send(Tick);
+    /// ```
+    pub fn transmute<Q2: Query>(&mut self) -> Result<FetcherView<'_, Q2>, TransmuteError> {
+        Ok(FetcherView {
+            state: self.state.transmute(self.world.world())?,
+            world: self.world,
+        })
+    }
+
+    /// Like [`transmute`](Self::transmute), but borrows the fetcher immutably
+    /// and only allows read-only queries.
+    pub fn as_readonly<Q2: ReadOnlyQuery>(&self) -> Result<FetcherView<'_, Q2>, TransmuteError> {
+        Ok(FetcherView {
+            state: self.state.transmute(self.world.world())?,
+            world: self.world,
+        })
+    }
 }
 
 impl<'a, Q: Query> IntoIterator for Fetcher<'a, Q> {
@@ -248,37 +864,212 @@ impl<'a, Q: Query> fmt::Debug for Fetcher<'a, Q> {
     }
 }
 
-/// An error returned when a random-access entity lookup fails.
+/// A narrowed view of a [`Fetcher`], returned by [`Fetcher::transmute`] and
+/// [`Fetcher::as_readonly`].
+pub struct FetcherView<'a, Q: Query> {
+    state: FetcherState<Q>,
+    world: UnsafeWorldCell<'a>,
+}
+
+impl<Q: Query> FetcherView<'_, Q> {
+    /// Returns a [`Fetcher`] over the narrowed query, which can be passed to
+    /// functions expecting one.
+    pub fn fetcher(&mut self) -> Fetcher<'_, Q> {
+        Fetcher {
+            state: &mut self.state,
+            world: self.world,
+        }
+    }
+}
+
+impl<Q: Query> fmt::Debug for FetcherView<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FetcherView")
+            .field("state", &self.state)
+            .field("world", &self.world)
+            .finish()
+    }
+}
+
+/// An error returned by [`Fetcher::transmute`] and [`Fetcher::as_readonly`].
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum GetError {
-    /// Entity does not exist.
-    NoSuchEntity,
-    /// Entity does not match the query.
-    QueryDoesNotMatch,
-    /// Entity was requested mutably more than once.
-    AliasedMutability,
+pub enum TransmuteError {
+    /// The new query has conflicting access.
+    Init,
+    /// The new query accesses components the original query doesn't have
+    /// access to.
+    Access,
 }
 
-impl fmt::Display for GetError {
+impl fmt::Display for TransmuteError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            GetError::NoSuchEntity => write!(f, "entity does not exist"),
-            GetError::QueryDoesNotMatch => write!(f, "entity does not match the query"),
-            GetError::AliasedMutability => write!(f, "entity was requested mutably more than once"),
+            TransmuteError::Init => write!(f, "query could not be initialized from the world"),
+            TransmuteError::Access => write!(
+                f,
+                "query accesses components not accessed by the original query"
+            ),
         }
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for GetError {}
+impl core::error::Error for TransmuteError {}
 
-unsafe impl<Q> SystemParam for Fetcher<'_, Q>
-where
-    Q: Query + 'static,
-{
-    type State = FetcherState<Q>;
+/// Read-only access to the entities matching a [`ReadOnlyQuery`], borrowed
+/// from a [`World`] outside of any system.
+///
+/// This is returned by [`World::query`]. A `WorldFetcher` is `Sync` whenever
+/// the query's state is, so it can be shared between threads to read the
+/// world concurrently. Use [`WorldFetcher::world`] to access the rest of the
+/// world while the fetcher is alive.
+pub struct WorldFetcher<'a, Q: ReadOnlyQuery> {
+    state: FetcherState<Q>,
+    world: &'a World,
+}
 
-    type Item<'a> = Fetcher<'a, Q>;
+impl<'a, Q: ReadOnlyQuery> WorldFetcher<'a, Q> {
+    pub(crate) fn new(state: FetcherState<Q>, world: &'a World) -> Self {
+        Self { state, world }
+    }
+
+    /// Returns the query item for the given entity.
+    ///
+    /// If the entity doesn't exist or doesn't match the query, then a
+    /// [`GetError`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the world contains [local components] and this is called
+    /// from a thread other than the [owner thread].
+    ///
+    /// [local components]: crate::component::Component::IS_LOCAL
+    /// [owner thread]: World::owner_thread
+    #[inline]
+    #[track_caller]
+    pub fn get(&self, entity: EntityId) -> Result<Q::Item<'_>, GetError> {
+        self.world.check_thread();
+
+        // SAFETY: The query is read-only and the world is borrowed immutably.
+        unsafe { self.state.get(self.world.entities(), entity) }
+    }
+
+    /// Returns the query item for the only entity matching the query.
+    ///
+    /// If the query doesn't match exactly one entity, then a [`SingleError`]
+    /// is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`WorldFetcher::get`].
+    #[inline]
+    #[track_caller]
+    pub fn single(&self) -> Result<Q::Item<'_>, SingleError> {
+        self.world.check_thread();
+
+        unsafe { self.state.single(self.world.archetypes()) }
+    }
+
+    /// Returns an iterator over all entities matching the query.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`WorldFetcher::get`].
+    #[track_caller]
+    pub fn iter(&self) -> Iter<'_, Q> {
+        self.world.check_thread();
+
+        unsafe { self.state.iter(self.world.archetypes()) }
+    }
+
+    /// Returns an iterator over the query items of the entities in an [entity
+    /// group]. See [`Fetcher::iter_in_group`].
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`WorldFetcher::get`].
+    ///
+    /// [entity group]: crate::entity_group
+    #[track_caller]
+    pub fn iter_in_group(&self, group: EntityGroupId) -> EntityGroupIter<'_, Q> {
+        self.world.check_thread();
+
+        unsafe {
+            self.state
+                .iter_in_group_unchecked(self.world.archetypes(), group)
+        }
+    }
+
+    /// Returns an iterator over the query items of the given entities. See
+    /// [`Fetcher::iter_many`].
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`WorldFetcher::get`].
+    #[track_caller]
+    pub fn iter_many<I>(&self, ids: I) -> ManyIter<'_, Q, I::IntoIter>
+    where
+        I: IntoIterator<Item = EntityId>,
+    {
+        self.world.check_thread();
+
+        ManyIter(unsafe { self.state.iter_many(self.world.entities(), ids) })
+    }
+
+    /// Returns the world this fetcher borrows from.
+    pub fn world(&self) -> &'a World {
+        self.world
+    }
+}
+
+impl<'a, Q: ReadOnlyQuery> IntoIterator for &'a WorldFetcher<'_, Q> {
+    type Item = Q::Item<'a>;
+
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<Q: ReadOnlyQuery> fmt::Debug for WorldFetcher<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorldFetcher")
+            .field("state", &self.state)
+            .field("world", &self.world)
+            .finish()
+    }
+}
+
+/// An error returned when a random-access entity lookup fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GetError {
+    /// Entity does not exist.
+    NoSuchEntity,
+    /// Entity does not match the query.
+    QueryDoesNotMatch,
+    /// Entity was requested mutably more than once.
+    AliasedMutability,
+}
+
+impl fmt::Display for GetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GetError::NoSuchEntity => write!(f, "entity does not exist"),
+            GetError::QueryDoesNotMatch => write!(f, "entity does not match the query"),
+            GetError::AliasedMutability => write!(f, "entity was requested mutably more than once"),
+        }
+    }
+}
+
+impl core::error::Error for GetError {}
+
+unsafe impl<Q> SystemParam for Fetcher<'_, Q>
+where
+    Q: Query + 'static,
+{
+    type State = FetcherState<Q>;
+
+    type Item<'a> = Fetcher<'a, Q>;
 
     fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
         FetcherState::init(world, config)
@@ -307,6 +1098,8 @@ where
 /// If there isn't exactly one entity that matches the [`Query`], a runtime
 /// panic occurs. This is useful for representing global variables or singleton
 /// entities.
+/// Use [`TrySingle`] to handle the error or [`SingleOrSkip`] to skip the
+/// system instead.
 ///
 /// # Examples
 ///
@@ -372,7 +1165,32 @@ unsafe impl<Q: Query + 'static> SystemParam for Single<'_, Q> {
 /// Like [`Single`], but contains a `Result` instead of panicking on error.
 ///
 /// This is useful if you need to explicitly handle the situation where the
-/// query does not match exactly one entity.
+/// query does not match exactly one entity. For instance, a system can skip
+/// its work by returning early when the result is an error.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(Event)] struct E;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Camera;
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|_: Receiver<E>, TrySingle(camera): TrySingle<&Camera>| {
+///     let Ok(camera) = camera else {
+///         // No camera (or too many cameras), so there's nothing to do.
+///         return;
+///     };
+///
+///     // ...
+///     # let _ = camera;
+/// });
+///
+/// world.send(E);
+/// ```
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct TrySingle<'a, Q: Query>(pub Result<Q::Item<'a>, SingleError>);
 
@@ -391,17 +1209,80 @@ unsafe impl<Q: Query + 'static> SystemParam for TrySingle<'_, Q> {
         _event_ptr: EventPtr<'a>,
         world: UnsafeWorldCell<'a>,
     ) -> Self::Item<'a> {
-        let mut it = state.iter_mut(world.archetypes());
+        TrySingle(state.single_mut(world.archetypes()))
+    }
 
-        let Some(item) = it.next() else {
-            return TrySingle(Err(SingleError::QueryDoesNotMatch));
-        };
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
 
-        if it.next().is_some() {
-            return TrySingle(Err(SingleError::MoreThanOneMatch));
-        }
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+/// Like [`Single`], but the system is skipped instead of panicking when the
+/// query doesn't match exactly one entity.
+///
+/// This is useful for systems which only make sense while a singleton entity
+/// exists, such as a player or camera that may not have been spawned yet.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(Event)] struct E;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Player(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(
+///     |_: Receiver<E>, SingleOrSkip(player): SingleOrSkip<&mut Player>| {
+///         player.0 += 1;
+///     },
+/// );
+///
+/// // No player yet, so the system doesn't run.
+/// world.send(E);
+///
+/// let e = world.spawn();
+/// world.insert(e, Player(0));
+///
+/// world.send(E);
+///
+/// assert_eq!(world.get_component::<Player>(e).unwrap().0, 1);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct SingleOrSkip<'a, Q: Query>(pub Q::Item<'a>);
+
+unsafe impl<Q: Query + 'static> SystemParam for SingleOrSkip<'_, Q> {
+    type State = FetcherState<Q>;
+
+    type Item<'a> = SingleOrSkip<'a, Q>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        FetcherState::init(world, config)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        _event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        // `should_run` has checked that there's exactly one match.
+        SingleOrSkip(state.single_mut(world.archetypes()).unwrap_debug_checked())
+    }
 
-        TrySingle(Ok(item))
+    unsafe fn should_run(
+        state: &mut Self::State,
+        _info: &SystemInfo,
+        _event_ptr: EventPtr,
+        world: UnsafeWorldCell,
+    ) -> bool {
+        state.iter_unchecked(world.archetypes()).len() == 1
     }
 
     fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
@@ -409,8 +1290,125 @@ unsafe impl<Q: Query + 'static> SystemParam for TrySingle<'_, Q> {
     }
 
     fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+/// A [`SystemParam`] which fetches the target of the received event.
+///
+/// The system only runs for targets matching the query, so the item is
+/// available directly without going through a [`Fetcher`]. `Targeted` must
+/// come after the system's [`Receiver`] or [`ReceiverMut`], which must be for a
+/// targeted event. Together with the receiver's own query, `Targeted` decides
+/// which targets the system listens to.
+///
+/// [`Receiver`]: crate::event::Receiver
+/// [`ReceiverMut`]: crate::event::ReceiverMut
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct Damage(#[event(target)] EntityId, u32);
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(
+///     |r: Receiver<Damage, ()>, Targeted(health): Targeted<&mut Health>| {
+///         health.0 = health.0.saturating_sub(r.event.1);
+///     },
+/// );
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(10));
+///
+/// world.send(Damage(e, 3));
+///
+/// assert_eq!(world.get::<&Health>(e).unwrap().0, 7);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct Targeted<'a, Q: Query>(pub Q::Item<'a>);
+
+unsafe impl<Q: Query + 'static> SystemParam for Targeted<'_, Q> {
+    type State = FetcherState<Q>;
+
+    type Item<'a> = Targeted<'a, Q>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        if !config.received_event.is_some_and(|id| id.is_targeted()) {
+            return Err(InitError(
+                format!(
+                    "`Targeted<{}>` must come after a receiver for a targeted event",
+                    any::type_name::<Q>()
+                )
+                .into(),
+            ));
+        }
+
+        let (expr, state) = Q::init(world, config)?;
+
+        let Ok(new_targeted_event_access) = config.targeted_event_access.clone().and(&expr) else {
+            return Err(InitError(
+                format!(
+                    "query `{}` could alias the components of the received event's target",
+                    any::type_name::<Q>()
+                )
+                .into(),
+            ));
+        };
+
+        // Only targets matching the receiver's query are fetched, so previous queries
+        // need only be disjoint from the combined query.
+        let Ok(new_component_access) = new_targeted_event_access
+            .clone()
+            .or(&config.component_access)
+        else {
+            return Err(InitError(
+                format!(
+                    "query `{}` has incompatible component access with previous queries in this \
+                     system (the event target's components could be aliased)",
+                    any::type_name::<Q>()
+                )
+                .into(),
+            ));
+        };
+
+        config.targeted_event_expr = config
+            .targeted_event_expr
+            .clone()
+            .and(&new_targeted_event_access.expr);
+        config.targeted_event_access = new_targeted_event_access;
+        config.component_access = new_component_access;
+
+        Ok(FetcherState::new(state))
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        _event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        // SAFETY: The target entity is guaranteed to match the query.
+        Targeted(
+            state
+                .get_mut(world.entities(), world.current_target())
+                .unwrap_debug_checked(),
+        )
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
         state.refresh_archetype(arch)
     }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
 }
 
 /// Error raised when fetching exactly one entity matching a query fails.
@@ -419,57 +1417,318 @@ pub enum SingleError {
     /// Query does not match any entities
     QueryDoesNotMatch,
     /// More than one entity matched the query.
-    MoreThanOneMatch,
+    MoreThanOneMatch {
+        /// The total number of entities that matched the query.
+        count: usize,
+    },
 }
 
 impl fmt::Display for SingleError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let msg = match self {
-            SingleError::QueryDoesNotMatch => "query does not match any entities",
-            SingleError::MoreThanOneMatch => "more than one entity matched the query",
-        };
-
-        write!(f, "{msg}")
+        match self {
+            SingleError::QueryDoesNotMatch => write!(f, "query does not match any entities"),
+            SingleError::MoreThanOneMatch { count } => {
+                write!(
+                    f,
+                    "more than one entity matched the query (count = {count})"
+                )
+            }
+        }
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for SingleError {}
+impl core::error::Error for SingleError {}
 
-/// Iterator over entities matching the query `Q`.
+/// Internal state for a [`DynamicFetcher`].
 ///
-/// Entities are visited in a deterministic but otherwise unspecified order.
-pub struct Iter<'a, Q: Query> {
-    /// Pointer into the array of archetype states. This pointer moves forward
-    /// until it reaches `state_last`.
-    state: NonNull<Q::ArchState>,
-    /// Pointer to the last arch state, or dangling if there are no arch states.
-    /// This is _not_ a one-past-the-end pointer.
-    state_last: NonNull<Q::ArchState>,
-    /// Pointer into the array of archetype indices. This pointer moves forward
-    /// in lockstep with `state`.
-    index: NonNull<ArchetypeIdx>,
-    /// Current row of the current archetype.
-    row: ArchetypeRow,
-    /// Number of entities in the current archetype.
-    len: u32,
-    archetypes: &'a Archetypes,
-    // Iterator should inherit the variance of the query item.
-    _marker: PhantomData<Q::Item<'a>>,
+/// Unlike [`Fetcher`], this isn't a [`SystemParam`] because the query isn't
+/// known until runtime. Custom [`System`] implementations can hold this state
+/// and forward [`System::refresh_archetype`] and [`System::remove_archetype`]
+/// to it.
+///
+/// [`System`]: crate::system::System
+/// [`System::refresh_archetype`]: crate::system::System::refresh_archetype
+/// [`System::remove_archetype`]: crate::system::System::remove_archetype
+#[derive(Debug)]
+pub struct DynamicFetcherState {
+    fetched: Box<[ComponentIdx]>,
+    /// The fetched components which are accessed mutably.
+    #[cfg(feature = "diff")]
+    written: Box<[ComponentIdx]>,
+    expr: BoolExpr<ComponentIdx>,
+    /// Works like the maps of [`FetcherState`].
+    map: SparseMap<ArchetypeIdx, DynamicArchState>,
+    inactive: SparseMap<ArchetypeIdx, (ColumnVersion, DynamicArchState)>,
 }
 
-impl<'a, Q: Query> Iterator for Iter<'a, Q> {
-    type Item = Q::Item<'a>;
-
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.row.0 == self.len {
-            if self.state == self.state_last {
-                return None;
-            }
-
-            self.state = unsafe { NonNull::new_unchecked(self.state.as_ptr().add(1)) };
-            self.index = unsafe { NonNull::new_unchecked(self.index.as_ptr().add(1)) };
+#[derive(Debug)]
+struct DynamicArchState {
+    entity_ids: ColumnPtr<EntityId>,
+    /// Base pointer, element size, and whether the column is chunked, for
+    /// each fetched column.
+    columns: Box<[(ColumnPtr<u8>, usize, bool)]>,
+    /// The flags marking the mutably fetched columns as changed.
+    #[cfg(feature = "diff")]
+    changed: Box<[ChangeFlag]>,
+}
+
+impl DynamicArchState {
+    /// Marks the mutably fetched columns as changed for the `diff` module.
+    ///
+    /// # Safety
+    ///
+    /// The state must be up to date with the archetype.
+    #[cfg_attr(not(feature = "diff"), allow(clippy::unused_self))]
+    unsafe fn mark_changed(&self) {
+        #[cfg(feature = "diff")]
+        for &flag in &self.changed {
+            flag.mark();
+        }
+    }
+}
+
+impl DynamicFetcherState {
+    /// Initializes the state for the given query. The component access of
+    /// the query is checked against and added to the `config`.
+    pub fn init(
+        query: &DynamicQuery,
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<Self, InitError> {
+        let expr = query.init(world, config)?;
+
+        let res = Self {
+            fetched: query.fetched().collect(),
+            #[cfg(feature = "diff")]
+            written: query.written().collect(),
+            expr: expr.expr.clone(),
+            map: SparseMap::new(),
+            inactive: SparseMap::new(),
+        };
+
+        match expr.or(&config.component_access) {
+            Ok(new_component_access) => config.component_access = new_component_access,
+            Err(expr) if !expr.is_compatible(&config.targeted_event_access) => {
+                return Err(InitError(
+                    "dynamic query could alias the components of the received event's target"
+                        .into(),
+                ))
+            }
+            Err(_) => {
+                return Err(InitError(
+                    "dynamic query has incompatible component access with previous queries in \
+                     this system"
+                        .into(),
+                ))
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Updates the state for a new or changed archetype. See
+    /// [`System::refresh_archetype`].
+    ///
+    /// [`System::refresh_archetype`]: crate::system::System::refresh_archetype
+    pub fn refresh_archetype(&mut self, arch: &Archetype) {
+        if let Some((version, state)) = self.inactive.remove(arch.index()) {
+            if version == arch.column_version() {
+                self.map.insert(arch.index(), state);
+                return;
+            }
+        }
+
+        if !arch.matches(&self.expr) {
+            return;
+        }
+
+        let columns = self
+            .fetched
+            .iter()
+            .map(|&idx| {
+                let col = unsafe { arch.column_of(idx).unwrap_debug_checked() };
+                (
+                    ColumnPtr(col.base_ptr()),
+                    col.layout().size(),
+                    col.is_chunked(),
+                )
+            })
+            .collect();
+
+        let entity_ids = ColumnPtr(unsafe {
+            NonNull::new(arch.entity_ids().as_ptr().cast_mut()).unwrap_debug_checked()
+        });
+
+        #[cfg(feature = "diff")]
+        let changed = self
+            .written
+            .iter()
+            .map(|&idx| {
+                unsafe { arch.column_of(idx).unwrap_debug_checked() }
+                    .changes()
+                    .flag()
+            })
+            .collect();
+
+        self.map.insert(
+            arch.index(),
+            DynamicArchState {
+                entity_ids,
+                columns,
+                #[cfg(feature = "diff")]
+                changed,
+            },
+        );
+    }
+
+    /// Removes an archetype from the state. See
+    /// [`System::remove_archetype`].
+    ///
+    /// [`System::remove_archetype`]: crate::system::System::remove_archetype
+    pub fn remove_archetype(&mut self, arch: &Archetype) {
+        match self.map.remove(arch.index()) {
+            Some(state) if arch.entity_count() == 0 => {
+                self.inactive
+                    .insert(arch.index(), (arch.column_version(), state));
+            }
+            _ => {
+                self.inactive.remove(arch.index());
+            }
+        }
+    }
+
+    /// Returns a [`DynamicFetcher`] for this state.
+    ///
+    /// # Safety
+    ///
+    /// `world` must have permission to access the components described by the
+    /// query this state was initialized with, and the state must be up to date
+    /// with the archetypes in the world.
+    pub unsafe fn fetcher<'a>(&'a mut self, world: UnsafeWorldCell<'a>) -> DynamicFetcher<'a> {
+        DynamicFetcher { state: self, world }
+    }
+}
+
+/// Accesses entities matching a [`DynamicQuery`].
+///
+/// Items are passed to callbacks as an [`EntityId`] and a slice of pointers
+/// to the fetched components, in the order they were added to the query.
+/// Pointers to immutably fetched components must not be written through.
+#[derive(Debug)]
+pub struct DynamicFetcher<'a> {
+    state: &'a mut DynamicFetcherState,
+    world: UnsafeWorldCell<'a>,
+}
+
+impl DynamicFetcher<'_> {
+    /// Passes the components of `entity` to `f` and returns the result.
+    ///
+    /// If the entity doesn't exist or doesn't match the query, then a
+    /// [`GetError`] is returned.
+    pub fn get_mut<F, R>(&mut self, entity: EntityId, f: F) -> Result<R, GetError>
+    where
+        F: FnOnce(&[NonNull<u8>]) -> R,
+    {
+        let Some(loc) = self.world.entities().get(entity) else {
+            return Err(GetError::NoSuchEntity);
+        };
+
+        let Some(state) = self.state.map.get(loc.archetype) else {
+            return Err(GetError::QueryDoesNotMatch);
+        };
+
+        unsafe { state.mark_changed() };
+
+        let ptrs = state
+            .columns
+            .iter()
+            .map(|&(ptr, size, chunked)| unsafe {
+                NonNull::new_unchecked(slot_in(ptr.0, size, chunked, loc.row.0 as usize))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(f(&ptrs))
+    }
+
+    /// Calls `f` for every entity matching the query.
+    pub fn for_each_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(EntityId, &[NonNull<u8>]),
+    {
+        let archetypes = self.world.archetypes();
+        let mut ptrs = Vec::with_capacity(self.state.fetched.len());
+
+        for (&idx, state) in self.state.map.keys().iter().zip(self.state.map.values()) {
+            let len = unsafe { archetypes.get(idx).unwrap_debug_checked() }.entity_count();
+
+            if len > 0 {
+                unsafe { state.mark_changed() };
+            }
+
+            for row in 0..len as usize {
+                ptrs.clear();
+                ptrs.extend(state.columns.iter().map(|&(ptr, size, chunked)| unsafe {
+                    NonNull::new_unchecked(slot_in(ptr.0, size, chunked, row))
+                }));
+
+                let entity = unsafe { *state.entity_ids.0.as_ptr().add(row) };
+
+                f(entity, &ptrs);
+            }
+        }
+    }
+
+    /// Returns the number of entities matching the query.
+    pub fn len(&self) -> usize {
+        let archetypes = self.world.archetypes();
+
+        self.state
+            .map
+            .keys()
+            .iter()
+            .map(|&idx| {
+                unsafe { archetypes.get(idx).unwrap_debug_checked() }.entity_count() as usize
+            })
+            .sum()
+    }
+}
+
+/// Iterator over entities matching the query `Q`.
+///
+/// Entities are visited in a deterministic but otherwise unspecified order.
+pub struct Iter<'a, Q: Query> {
+    /// Pointer into the array of archetype states. This pointer moves forward
+    /// in lockstep with `index`.
+    state: NonNull<Q::ArchState>,
+    /// Pointer into the array of archetype indices. This pointer moves forward
+    /// until it reaches `index_last`.
+    index: NonNull<ArchetypeIdx>,
+    /// Pointer to the last archetype index, or dangling if there are no
+    /// archetypes. This is _not_ a one-past-the-end pointer.
+    index_last: NonNull<ArchetypeIdx>,
+    /// Current row of the current archetype.
+    row: ArchetypeRow,
+    /// Number of entities in the current archetype.
+    len: u32,
+    /// See [`FetcherConfig::prefetch_distance`].
+    prefetch_distance: u32,
+    archetypes: &'a Archetypes,
+    // Iterator should inherit the variance of the query item.
+    _marker: PhantomData<Q::Item<'a>>,
+}
+
+impl<'a, Q: Query> Iterator for Iter<'a, Q> {
+    type Item = Q::Item<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row.0 == self.len {
+            if self.index == self.index_last {
+                return None;
+            }
+
+            self.state = unsafe { NonNull::new_unchecked(self.state.as_ptr().add(1)) };
+            self.index = unsafe { NonNull::new_unchecked(self.index.as_ptr().add(1)) };
 
             let idx = unsafe { *self.index.as_ptr() };
             let arch = unsafe { self.archetypes.get(idx).unwrap_debug_checked() };
@@ -484,6 +1743,14 @@ impl<'a, Q: Query> Iterator for Iter<'a, Q> {
         let state = unsafe { &*self.state.as_ptr().cast_const() };
         let item = unsafe { Q::get(state, self.row) };
 
+        if PREFETCH_SUPPORTED && self.prefetch_distance != 0 {
+            let ahead = self.row.0.saturating_add(self.prefetch_distance);
+
+            if ahead < self.len {
+                unsafe { Q::prefetch(state, ArchetypeRow(ahead)) };
+            }
+        }
+
         self.row.0 += 1;
 
         Some(item)
@@ -500,11 +1767,7 @@ impl<Q: Query> ExactSizeIterator for Iter<'_, Q> {
         let mut remaining = self.len - self.row.0;
 
         let mut index = self.index.as_ptr();
-
-        let index_last = unsafe {
-            // TODO: use `.sub_ptr` when stabilized.
-            index.add(self.state_last.as_ptr().offset_from(self.state.as_ptr()) as usize)
-        };
+        let index_last = self.index_last.as_ptr();
 
         while index != index_last {
             index = unsafe { index.add(1) };
@@ -519,15 +1782,73 @@ impl<Q: Query> ExactSizeIterator for Iter<'_, Q> {
 
 impl<Q: Query> FusedIterator for Iter<'_, Q> {}
 
+impl<'a, Q: Query> Iter<'a, Q> {
+    /// Turns this iterator into one over the [`EntityId`]s of the remaining
+    /// entities, without accessing their components.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Burning;
+    ///
+    /// #[derive(Event)]
+    /// struct Damage(#[event(target)] EntityId);
+    ///
+    /// #[derive(Event)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(
+    ///     |_: Receiver<Tick>, f: Fetcher<With<&Burning>>, mut s: Sender<Damage>| {
+    ///         for e in f.iter().entities() {
+    ///             s.send(Damage(e));
+    ///         }
+    ///     },
+    /// );
+    /// ```
+    pub fn entities(self) -> EntityIter<'a> {
+        let current = if self.row.0 == self.len {
+            [].iter()
+        } else {
+            let idx = unsafe { *self.index.as_ptr() };
+            let arch = unsafe { self.archetypes.get(idx).unwrap_debug_checked() };
+
+            arch.entity_ids()[self.row.0 as usize..self.len as usize].iter()
+        };
+
+        // The archetypes after the current one.
+        let rest = unsafe {
+            let count = self.index_last.as_ptr().offset_from(self.index.as_ptr()) as usize;
+
+            if count == 0 {
+                &[]
+            } else {
+                slice::from_raw_parts(self.index.as_ptr().add(1), count)
+            }
+        };
+
+        EntityIter {
+            current,
+            rest: rest.iter(),
+            archetypes: self.archetypes,
+        }
+    }
+}
+
 // SAFETY: Iter is only cloneable when the query is read-only.
 impl<'a, Q: ReadOnlyQuery> Clone for Iter<'a, Q> {
     fn clone(&self) -> Self {
         Self {
             state: self.state,
-            state_last: self.state_last,
             index: self.index,
+            index_last: self.index_last,
             row: self.row,
             len: self.len,
+            prefetch_distance: self.prefetch_distance,
             archetypes: self.archetypes,
             _marker: self._marker,
         }
@@ -538,10 +1859,322 @@ impl<'a, Q: Query> fmt::Debug for Iter<'a, Q> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Iter")
             .field("state", &self.state)
-            .field("state_last", &self.state_last)
             .field("index", &self.index)
+            .field("index_last", &self.index_last)
             .field("row", &self.row)
             .field("len", &self.len)
+            .field("prefetch_distance", &self.prefetch_distance)
+            .field("archetypes", &self.archetypes)
+            .finish()
+    }
+}
+
+/// Iterator over the entities of an [entity group] matching the query `Q`,
+/// returned by [`Fetcher::iter_in_group`].
+///
+/// Entities are visited in the same order as in [`Iter`].
+///
+/// [entity group]: crate::entity_group
+pub struct EntityGroupIter<'a, Q: Query> {
+    indices: slice::Iter<'a, ArchetypeIdx>,
+    states: slice::Iter<'a, Q::ArchState>,
+    /// The state of the archetype `rows` belongs to.
+    state: Option<&'a Q::ArchState>,
+    /// The remaining rows of the group in the current archetype.
+    rows: Range<u32>,
+    group: EntityGroupId,
+    archetypes: &'a Archetypes,
+    // Iterator should inherit the variance of the query item.
+    _marker: PhantomData<Q::Item<'a>>,
+}
+
+impl<'a, Q: Query> Iterator for EntityGroupIter<'a, Q> {
+    type Item = Q::Item<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                let state = unsafe { self.state.unwrap_debug_checked() };
+                return Some(unsafe { Q::get(state, ArchetypeRow(row)) });
+            }
+
+            let &idx = self.indices.next()?;
+            let arch = unsafe { self.archetypes.get(idx).unwrap_debug_checked() };
+
+            self.state = Some(unsafe { self.states.next().unwrap_debug_checked() });
+            self.rows = arch.entity_group_rows(self.group);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rows.len(), None)
+    }
+}
+
+impl<Q: Query> FusedIterator for EntityGroupIter<'_, Q> {}
+
+// SAFETY: EntityGroupIter is only cloneable when the query is read-only.
+impl<Q: ReadOnlyQuery> Clone for EntityGroupIter<'_, Q> {
+    fn clone(&self) -> Self {
+        Self {
+            indices: self.indices.clone(),
+            states: self.states.clone(),
+            state: self.state,
+            rows: self.rows.clone(),
+            group: self.group,
+            archetypes: self.archetypes,
+            _marker: self._marker,
+        }
+    }
+}
+
+impl<Q: Query> fmt::Debug for EntityGroupIter<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntityGroupIter")
+            .field("rows", &self.rows)
+            .field("group", &self.group)
+            .field("archetypes", &self.archetypes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator over the [`EntityId`]s of the entities matching a query, returned
+/// by [`Iter::entities`].
+///
+/// Entities are visited in the same order as in [`Iter`].
+#[derive(Clone)]
+pub struct EntityIter<'a> {
+    /// The remaining entities of the current archetype.
+    current: slice::Iter<'a, EntityId>,
+    /// The matched archetypes after the current one.
+    rest: slice::Iter<'a, ArchetypeIdx>,
+    archetypes: &'a Archetypes,
+}
+
+impl Iterator for EntityIter<'_> {
+    type Item = EntityId;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&id) = self.current.next() {
+                return Some(id);
+            }
+
+            let &idx = self.rest.next()?;
+            let arch = unsafe { self.archetypes.get(idx).unwrap_debug_checked() };
+
+            self.current = arch.entity_ids().iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for EntityIter<'_> {
+    fn len(&self) -> usize {
+        self.current.len()
+            + self
+                .rest
+                .clone()
+                .map(|&idx| {
+                    unsafe { self.archetypes.get(idx).unwrap_debug_checked() }.entity_count()
+                        as usize
+                })
+                .sum::<usize>()
+    }
+}
+
+impl FusedIterator for EntityIter<'_> {}
+
+impl fmt::Debug for EntityIter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntityIter")
+            .field("current", &self.current)
+            .field("rest", &self.rest)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator over the query items of a list of entities, returned by
+/// [`Fetcher::try_iter_many`] and [`Fetcher::try_iter_many_mut`].
+///
+/// Yields one result per ID, in the order the IDs are given. `UNIQUE` is set
+/// for the mutable iterators, which yield [`GetError::AliasedMutability`]
+/// instead of returning an entity twice.
+pub struct TryManyIter<'a, Q: Query, I, const UNIQUE: bool = false> {
+    ids: I,
+    map: &'a SparseMap<ArchetypeIdx, Q::ArchState>,
+    entities: &'a Entities,
+    /// The archetype of the previous entity and its state, if the query
+    /// matches it. Starts out as the null archetype, which no entity is in.
+    last: (ArchetypeIdx, Option<&'a Q::ArchState>),
+    /// Entities returned so far. Always empty unless `UNIQUE` is set.
+    seen: BitSet<u32>,
+    _marker: PhantomData<Q::Item<'a>>,
+}
+
+impl<'a, Q: Query, I, const UNIQUE: bool> TryManyIter<'a, Q, I, UNIQUE> {
+    #[inline]
+    fn fetch(&mut self, entity: EntityId) -> Result<Q::Item<'a>, GetError> {
+        let Some(loc) = self.entities.get(entity) else {
+            return Err(GetError::NoSuchEntity);
+        };
+
+        // Eliminate a panicking branch.
+        unsafe { assume_debug_checked(loc.archetype != ArchetypeIdx::NULL) };
+
+        if self.last.0 != loc.archetype {
+            self.last = (loc.archetype, self.map.get(loc.archetype));
+        }
+
+        let Some(state) = self.last.1 else {
+            return Err(GetError::QueryDoesNotMatch);
+        };
+
+        if UNIQUE && !self.seen.insert(entity.index().0) {
+            return Err(GetError::AliasedMutability);
+        }
+
+        // SAFETY: The state outlives `'a`, and mutable items are only handed out
+        // once per entity.
+        Ok(unsafe { Q::get(state, loc.row) })
+    }
+}
+
+impl<'a, Q: Query, I: Iterator<Item = EntityId>, const UNIQUE: bool> Iterator
+    for TryManyIter<'a, Q, I, UNIQUE>
+{
+    type Item = Result<Q::Item<'a>, GetError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = self.ids.next()?;
+        Some(self.fetch(entity))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ids.size_hint()
+    }
+}
+
+impl<Q: Query, I: ExactSizeIterator<Item = EntityId>, const UNIQUE: bool> ExactSizeIterator
+    for TryManyIter<'_, Q, I, UNIQUE>
+{
+}
+
+impl<Q: Query, I: FusedIterator<Item = EntityId>, const UNIQUE: bool> FusedIterator
+    for TryManyIter<'_, Q, I, UNIQUE>
+{
+}
+
+impl<Q: Query, I: fmt::Debug, const UNIQUE: bool> fmt::Debug for TryManyIter<'_, Q, I, UNIQUE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryManyIter")
+            .field("ids", &self.ids)
+            .field("seen", &self.seen)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator over the query items of a list of entities, returned by
+/// [`Fetcher::iter_many`] and [`Fetcher::iter_many_mut`].
+///
+/// Entities are visited in the order the IDs are given. Entities which can't
+/// be fetched are skipped.
+pub struct ManyIter<'a, Q: Query, I, const UNIQUE: bool = false>(TryManyIter<'a, Q, I, UNIQUE>);
+
+impl<'a, Q: Query, I: Iterator<Item = EntityId>, const UNIQUE: bool> Iterator
+    for ManyIter<'a, Q, I, UNIQUE>
+{
+    type Item = Q::Item<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entity = self.0.ids.next()?;
+
+            if let Ok(item) = self.0.fetch(entity) {
+                return Some(item);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.0.ids.size_hint().1)
+    }
+}
+
+impl<Q: Query, I: FusedIterator<Item = EntityId>, const UNIQUE: bool> FusedIterator
+    for ManyIter<'_, Q, I, UNIQUE>
+{
+}
+
+impl<Q: Query, I: fmt::Debug, const UNIQUE: bool> fmt::Debug for ManyIter<'_, Q, I, UNIQUE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ManyIter").field(&self.0).finish()
+    }
+}
+
+/// Iterator over the archetypes matching the query `Q`, returned by
+/// [`Fetcher::iter_slices`].
+///
+/// Archetypes are visited in the same order as the entities in [`Iter`].
+pub struct SliceIter<'a, Q: SliceQuery> {
+    indices: slice::Iter<'a, ArchetypeIdx>,
+    states: slice::Iter<'a, Q::ArchState>,
+    archetypes: &'a Archetypes,
+    _marker: PhantomData<Q::Slices<'a>>,
+}
+
+impl<'a, Q: SliceQuery> Iterator for SliceIter<'a, Q> {
+    type Item = (&'a [EntityId], Q::Slices<'a>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.states.next()?;
+        let idx = unsafe { self.indices.next().unwrap_debug_checked() };
+
+        let arch = unsafe { self.archetypes.get(*idx).unwrap_debug_checked() };
+        let ids = arch.entity_ids();
+
+        Some((ids, unsafe { Q::get_slices(state, ids.len()) }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.states.size_hint()
+    }
+}
+
+impl<Q: SliceQuery> ExactSizeIterator for SliceIter<'_, Q> {
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+}
+
+impl<Q: SliceQuery> FusedIterator for SliceIter<'_, Q> {}
+
+// SAFETY: SliceIter is only cloneable when the query is read-only.
+impl<Q: ReadOnlyQuery + SliceQuery> Clone for SliceIter<'_, Q> {
+    fn clone(&self) -> Self {
+        Self {
+            indices: self.indices.clone(),
+            states: self.states.clone(),
+            archetypes: self.archetypes,
+            _marker: self._marker,
+        }
+    }
+}
+
+impl<Q: SliceQuery> fmt::Debug for SliceIter<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SliceIter")
+            .field("indices", &self.indices)
+            .field("states", &self.states)
             .field("archetypes", &self.archetypes)
             .finish()
     }
@@ -553,6 +2186,7 @@ impl<'a, Q: Query> fmt::Debug for Iter<'a, Q> {
 mod tests {
     use alloc::collections::BTreeSet;
 
+    use super::{FetcherConfig, GetError, TransmuteError};
     use crate::prelude::*;
 
     #[derive(Event)]
@@ -602,40 +2236,223 @@ mod tests {
 
         world.send(E2);
 
-        world.add_system(move |_: Receiver<E3>, f: Fetcher<&C2>| {
-            assert_eq!(f.get(e), Err(GetError::QueryDoesNotMatch))
+        world.add_system(move |_: Receiver<E3>, f: Fetcher<&C2>| {
+            assert_eq!(f.get(e), Err(GetError::QueryDoesNotMatch))
+        });
+
+        world.send(E3);
+    }
+
+    #[test]
+    fn iter_cached() {
+        let mut world = World::new();
+
+        let mut set = BTreeSet::new();
+
+        for i in 0..100_u32 {
+            let e = world.spawn();
+            world.insert(e, C1(i.pow(2)));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i.pow(2)));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i.pow(2)));
+            }
+
+            set.insert(i.pow(2));
+        }
+
+        world.add_system(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            for c in f {
+                assert!(set.remove(&c.0));
+            }
+
+            assert!(set.is_empty());
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn iter_entities() {
+        let mut world = World::new();
+
+        let mut with_c2 = vec![];
+
+        for i in 0..30_u32 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+                with_c2.push(e);
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+        }
+
+        world.add_system(move |_: Receiver<E1>, f: Fetcher<(EntityId, &C1)>| {
+            let expected: Vec<_> = f.iter().map(|(e, _)| e).collect();
+
+            let ids = f.iter().entities();
+            assert_eq!(ids.len(), expected.len());
+            assert_eq!(ids.collect::<Vec<_>>(), expected);
+
+            // Starting partway through an archetype.
+            for skip in [0, 1, 7, 15, 29, 30] {
+                let mut it = f.iter();
+                for _ in 0..skip {
+                    it.next();
+                }
+
+                let ids = it.entities();
+                assert_eq!(ids.len(), 30 - skip);
+                assert_eq!(ids.collect::<Vec<_>>(), expected[skip..]);
+            }
+        });
+
+        world.add_system(move |_: Receiver<E1>, f: Fetcher<With<&C2>>| {
+            let mut ids: Vec<_> = f.iter().entities().collect();
+            ids.sort();
+            with_c2.sort();
+            assert_eq!(ids, with_c2);
+        });
+
+        world.add_system(|_: Receiver<E1>, mut f: Fetcher<(&mut C1, &C3)>| {
+            assert_eq!(f.iter_mut().entities().len(), 10);
+        });
+
+        #[derive(Component)]
+        struct Unused;
+
+        world.add_system(|_: Receiver<E1>, f: Fetcher<&Unused>| {
+            assert_eq!(f.iter().entities().next(), None);
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn iter_many() {
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
+        world.insert(e2, C2(2));
+        let e3 = world.spawn();
+        world.insert(e3, C1(3));
+        let no_c1 = world.spawn();
+        world.insert(no_c1, C2(4));
+        let dead = world.spawn();
+        world.despawn(dead);
+
+        let ids = [e3, dead, e1, no_c1, no_c1, e2, e1];
+
+        world.add_system(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            let items: Vec<_> = f.iter_many(ids).map(|c| c.0).collect();
+            assert_eq!(items, [3, 1, 2, 1]);
+
+            let results: Vec<_> = f.try_iter_many(ids).map(|r| r.map(|c| c.0)).collect();
+            assert_eq!(
+                results,
+                [
+                    Ok(3),
+                    Err(GetError::NoSuchEntity),
+                    Ok(1),
+                    Err(GetError::QueryDoesNotMatch),
+                    Err(GetError::QueryDoesNotMatch),
+                    Ok(2),
+                    Ok(1),
+                ]
+            );
+        });
+
+        world.add_system(move |_: Receiver<E2>, mut f: Fetcher<&mut C1>| {
+            let items: Vec<_> = f.iter_many_mut(ids).collect();
+            assert_eq!(items.len(), 3);
+
+            for c in items {
+                c.0 *= 10;
+            }
+
+            let results: Vec<_> = f.try_iter_many_mut([e1, e1, e2]).map(|r| r.err()).collect();
+            assert_eq!(results, [None, Some(GetError::AliasedMutability), None]);
+        });
+
+        world.send(E1);
+        world.send(E2);
+
+        assert_eq!(world.get::<&C1>(e1), Some(&C1(10)));
+        assert_eq!(world.get::<&C1>(e2), Some(&C1(20)));
+        assert_eq!(world.get::<&C1>(e3), Some(&C1(30)));
+    }
+
+    #[test]
+    fn for_each_visits_like_iter() {
+        let mut world = World::new();
+
+        for i in 0..20_u32 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 3 == 0 {
+                world.insert(e, C2(i));
+            }
+        }
+
+        world.add_system(|_: Receiver<E1>, f: Fetcher<(&C1, Has<&C2>)>| {
+            let mut visited = vec![];
+            f.for_each(|(c1, has_c2)| visited.push((c1.0, has_c2.get())));
+            assert_eq!(
+                visited,
+                f.iter()
+                    .map(|(c1, has_c2)| (c1.0, has_c2.get()))
+                    .collect::<Vec<_>>()
+            );
+        });
+
+        world.add_system(|_: Receiver<E1>, mut f: Fetcher<(&mut C1, Has<&C2>)>| {
+            f.for_each_mut(|(c1, has_c2)| {
+                if has_c2.get() {
+                    c1.0 += 100;
+                }
+            });
+        });
+
+        world.send(E1);
+
+        world.add_system(|_: Receiver<E2>, f: Fetcher<&C1>| {
+            let mut sum = 0;
+            f.for_each(|c| sum += c.0);
+            assert_eq!(sum, (0..20).sum::<u32>() + 7 * 100);
         });
 
-        world.send(E3);
+        world.send(E2);
     }
 
     #[test]
-    fn iter_cached() {
+    fn iter_zero_sized_states() {
         let mut world = World::new();
 
-        let mut set = BTreeSet::new();
-
-        for i in 0..100_u32 {
+        for i in 0..10 {
             let e = world.spawn();
-            world.insert(e, C1(i.pow(2)));
+            world.insert(e, C1(i));
 
             if i % 2 == 0 {
-                world.insert(e, C2(i.pow(2)));
-            }
-
-            if i % 3 == 0 {
-                world.insert(e, C3(i.pow(2)));
+                world.insert(e, C2(i));
             }
-
-            set.insert(i.pow(2));
         }
 
-        world.add_system(move |_: Receiver<E1>, f: Fetcher<&C1>| {
-            for c in f {
-                assert!(set.remove(&c.0));
-            }
-
-            assert!(set.is_empty());
+        world.add_system(|_: Receiver<E1>, f: Fetcher<With<&C1>>, g: Fetcher<()>| {
+            assert_eq!(f.iter().len(), 10);
+            assert_eq!(f.iter().count(), 10);
+            assert_eq!(g.iter().count(), 10);
         });
 
         world.send(E1);
@@ -684,6 +2501,116 @@ mod tests {
         world.send(E1);
     }
 
+    #[test]
+    fn iter_slices() {
+        let mut world = World::new();
+
+        let mut expected = BTreeSet::new();
+
+        for i in 0..100_u32 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+            world.insert(e, C2(0));
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+
+            expected.insert((e, i));
+        }
+
+        world.add_system(|_: Receiver<E1>, mut f: Fetcher<(&C1, &mut C2)>| {
+            assert_eq!(f.iter_slices_mut().len(), 2);
+
+            for (ids, (c1, c2)) in f.iter_slices_mut() {
+                assert_eq!(ids.len(), c1.len());
+                assert_eq!(ids.len(), c2.len());
+
+                for (c1, c2) in c1.iter().zip(c2) {
+                    c2.0 = c1.0 * 2;
+                }
+            }
+        });
+
+        world.add_system(move |_: Receiver<E2>, f: Fetcher<(EntityId, &C1, &C2)>| {
+            let mut found = BTreeSet::new();
+
+            for (ids, (c1, c2)) in f
+                .as_readonly::<(&C1, &C2)>()
+                .unwrap()
+                .fetcher()
+                .iter_slices()
+            {
+                for ((&id, c1), c2) in ids.iter().zip(c1).zip(c2) {
+                    assert_eq!(c2.0, c1.0 * 2);
+                    assert_eq!(f.get(id).unwrap().1, c1);
+                    found.insert((id, c1.0));
+                }
+            }
+
+            assert_eq!(found, expected);
+        });
+
+        world.send(E1);
+        world.send(E2);
+    }
+
+    #[test]
+    fn prefetch_visits_every_entity() {
+        #[derive(Component)]
+        struct Zst;
+
+        #[derive(Component)]
+        #[component(chunked)]
+        struct Chunked(u32);
+
+        let mut world = World::new();
+
+        for i in 0..1000_u32 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+            world.insert(e, Zst);
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            if i % 5 == 0 {
+                world.insert(e, Chunked(i));
+            }
+        }
+
+        world.add_system(
+            |_: Receiver<E1>, mut f: Fetcher<(&C1, &mut Zst, Option<&C2>, Option<&Chunked>)>| {
+                assert_eq!(f.config(), FetcherConfig::default());
+
+                for distance in [1, 7, 64, u32::MAX] {
+                    f.set_config(FetcherConfig {
+                        prefetch_distance: distance,
+                    });
+
+                    let mut sum = 0;
+
+                    for (c1, _, c2, chunked) in &mut f {
+                        if let Some(c2) = c2 {
+                            assert_eq!(c1.0, c2.0);
+                        }
+
+                        if let Some(chunked) = chunked {
+                            assert_eq!(c1.0, chunked.0);
+                        }
+
+                        sum += c1.0;
+                    }
+
+                    assert_eq!(sum, (0..1000).sum());
+                }
+            },
+        );
+
+        world.send(E1);
+    }
+
     #[test]
     fn single_param() {
         let mut world = World::new();
@@ -727,6 +2654,32 @@ mod tests {
         world.send(E1);
     }
 
+    #[test]
+    fn single_or_skip_param() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<E1>, SingleOrSkip(c): SingleOrSkip<&mut C1>| {
+            c.0 += 1;
+        });
+
+        // No matches.
+        world.send(E1);
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(0));
+
+        world.send(E1);
+        assert_eq!(world.get_component::<C1>(e1).unwrap().0, 1);
+
+        let e2 = world.spawn();
+        world.insert(e2, C1(0));
+
+        // Too many matches.
+        world.send(E1);
+        assert_eq!(world.get_component::<C1>(e1).unwrap().0, 1);
+        assert_eq!(world.get_component::<C1>(e2).unwrap().0, 0);
+    }
+
     #[test]
     fn try_single_param() {
         let mut world = World::new();
@@ -745,10 +2698,368 @@ mod tests {
             |_: Receiver<E1>, s1: TrySingle<&C1>, s2: TrySingle<&C2>, s3: TrySingle<&C3>| {
                 assert_eq!(s1.0, Err(SingleError::QueryDoesNotMatch));
                 assert_eq!(s2.0, Ok(&C2(123)));
-                assert_eq!(s3.0, Err(SingleError::MoreThanOneMatch));
+                assert_eq!(s3.0, Err(SingleError::MoreThanOneMatch { count: 2 }));
+            },
+        );
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn fetcher_single() {
+        let mut world = World::new();
+
+        {
+            let e = world.spawn();
+            world.insert(e, C1(1));
+            world.insert(e, C2(1));
+
+            for i in 0..5 {
+                let e = world.spawn();
+                world.insert(e, C2(i));
+
+                if i % 2 == 0 {
+                    world.insert(e, C3(i));
+                }
+            }
+        }
+
+        world.add_system(
+            |_: Receiver<E1>, f1: Fetcher<&C1>, mut f2: Fetcher<&mut C2>, f3: Fetcher<&C3>| {
+                assert_eq!(f1.single(), Ok(&C1(1)));
+                assert_eq!(
+                    f2.single_mut(),
+                    Err(SingleError::MoreThanOneMatch { count: 6 })
+                );
+                assert_eq!(f3.single(), Err(SingleError::MoreThanOneMatch { count: 3 }));
+            },
+        );
+
+        world.add_system(|_: Receiver<E2>, f: Fetcher<(&C1, &C3)>| {
+            assert_eq!(f.single(), Err(SingleError::QueryDoesNotMatch));
+        });
+
+        world.send(E1);
+        world.send(E2);
+    }
+
+    #[test]
+    fn dynamic_query() {
+        use core::ptr::NonNull;
+
+        use crate::fetch::{DynamicFetcherState, FetcherState};
+        use crate::query::DynamicQuery;
+        use crate::system::Config;
+
+        let mut world = World::new();
+
+        let c1 = world.add_component::<C1>().index();
+        let c2 = world.add_component::<C2>().index();
+        let c3 = world.add_component::<C3>().index();
+
+        let mut expected = vec![];
+
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i * 10));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(0));
+            } else if i % 2 == 0 {
+                expected.push((e, i));
+            }
+        }
+
+        let query = DynamicQuery::new().read(c1).write(c2).without(c3);
+
+        let mut seen = vec![];
+
+        world
+            .query_dynamic(&query, |e, ptrs: &[NonNull<u8>]| {
+                assert_eq!(ptrs.len(), 2);
+
+                let c1 = unsafe { ptrs[0].cast::<C1>().as_ref() };
+                let c2 = unsafe { ptrs[1].cast::<C2>().as_mut() };
+
+                assert_eq!(c1.0 * 10, c2.0);
+                c2.0 += 1;
+
+                seen.push((e, c1.0));
+            })
+            .unwrap();
+
+        seen.sort();
+        expected.sort();
+        assert_eq!(seen, expected);
+
+        for (e, i) in expected {
+            assert_eq!(world.get_component::<C2>(e), Some(&C2(i * 10 + 1)));
+        }
+
+        // Conflicting access within the query.
+        let bad = DynamicQuery::new().read(c1).write(c1);
+        assert!(world.query_dynamic(&bad, |_, _| {}).is_err());
+
+        // Conflicting access with a static query in the same system.
+        let mut config = Config::default();
+        FetcherState::<&mut C1>::init(&mut world, &mut config).unwrap();
+        assert!(
+            DynamicFetcherState::init(&DynamicQuery::new().write(c1), &mut world, &mut config)
+                .is_err()
+        );
+
+        // Filters don't access the component.
+        let mut config = Config::default();
+        FetcherState::<&mut C1>::init(&mut world, &mut config).unwrap();
+        assert!(DynamicFetcherState::init(
+            &DynamicQuery::new().with(c1).read(c2),
+            &mut world,
+            &mut config
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn emptied_archetypes_are_kept() {
+        use super::FetcherState;
+        use crate::system::Config;
+
+        let mut world = World::new();
+
+        let mut state =
+            FetcherState::<(EntityId, &C1)>::init(&mut world, &mut Config::default()).unwrap();
+
+        let e = world.spawn();
+        world.insert(e, C1(1));
+
+        let idx = world.entities().get(e).unwrap().archetype;
+        let arch = world.archetypes().get(idx).unwrap();
+        state.refresh_archetype(arch);
+        assert_eq!(state.map.keys(), [idx]);
+
+        world.despawn(e);
+
+        state.remove_archetype(world.archetypes().get(idx).unwrap());
+        assert!(state.map.keys().is_empty());
+        assert_eq!(state.inactive.keys(), [idx]);
+
+        // Nothing was reallocated, so the old state is reused.
+        let e = world.spawn();
+        world.insert(e, C1(2));
+
+        state.refresh_archetype(world.archetypes().get(idx).unwrap());
+        assert_eq!(state.map.keys(), [idx]);
+        assert!(state.inactive.keys().is_empty());
+
+        let items: Vec<_> = unsafe { state.iter(world.archetypes()) }
+            .map(|(id, c)| (id, c.0))
+            .collect();
+        assert_eq!(items, [(e, 2)]);
+    }
+
+    #[test]
+    fn emptied_archetype_reallocated() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        let mut world = World::new();
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let s = seen.clone();
+        world.add_system(move |_: Receiver<E1>, f: Fetcher<EntityId>| {
+            s.lock().unwrap().extend(f);
+        });
+
+        let e = world.spawn();
+        world.send(E1);
+        assert_eq!(*seen.lock().unwrap(), [e]);
+
+        world.despawn(e);
+        world.send(E1);
+
+        // Grows the empty archetype while the system isn't told about it.
+        world.reserve_entities(1000);
+
+        let ids: Vec<_> = (0..1000).map(|_| world.spawn()).collect();
+
+        seen.lock().unwrap().clear();
+        world.send(E1);
+        assert_eq!(*seen.lock().unwrap(), ids);
+    }
+
+    #[test]
+    fn active_archetype_count() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        let mut world = World::new();
+
+        let counts = Arc::new(Mutex::new((0, 0)));
+        let c = counts.clone();
+        world.add_system(move |_: Receiver<E1>, f: Fetcher<(&C1, Not<&C3>)>| {
+            *c.lock().unwrap() = (f.matched_archetype_count(), f.active_archetype_count());
+        });
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
+        world.insert(e2, C2(2));
+        let e3 = world.spawn();
+        world.insert(e3, C1(3));
+        world.insert(e3, C3(3));
+
+        world.send(E1);
+        assert_eq!(*counts.lock().unwrap(), (2, 2));
+
+        world.despawn(e1);
+        world.send(E1);
+        assert_eq!(*counts.lock().unwrap(), (2, 1));
+
+        let e4 = world.spawn();
+        world.insert(e4, C1(4));
+        world.send(E1);
+        assert_eq!(*counts.lock().unwrap(), (2, 2));
+    }
+
+    #[test]
+    fn transmute_fetcher() {
+        #[derive(Component)]
+        struct C4;
+
+        #[derive(Query)]
+        struct ReadC1<'a>(&'a C1);
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+        world.insert(e1, C2(10));
+
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
+
+        let e3 = world.spawn();
+        world.insert(e3, C1(3));
+        world.insert(e3, C2(30));
+        world.insert(e3, C3(0));
+
+        fn bump(f: &mut Fetcher<&mut C1>) {
+            for c in f {
+                c.0 += 100;
+            }
+        }
+
+        fn sum(f: &Fetcher<ReadC1>) -> u32 {
+            f.iter().map(|c| c.0 .0).sum()
+        }
+
+        world.add_system(
+            move |_: Receiver<E1>, mut f: Fetcher<(&mut C1, Option<&C2>, Not<&C3>)>| {
+                bump(&mut f.transmute().unwrap().fetcher());
+
+                // Only the archetypes matched by the original query are visible.
+                assert_eq!(sum(&f.as_readonly().unwrap().fetcher()), 203);
+
+                let mut with_c2 = f.as_readonly::<(EntityId, &C2)>().unwrap();
+                let ids: Vec<_> = with_c2.fetcher().iter().map(|(id, _)| id).collect();
+                assert_eq!(ids, [e1]);
+
+                let mut filtered = f.as_readonly::<(&C1, With<&C2>)>().unwrap();
+                assert_eq!(filtered.fetcher().single().unwrap().0, &C1(101));
+
+                assert_eq!(
+                    f.transmute::<&mut C2>().unwrap_err(),
+                    TransmuteError::Access
+                );
+                assert_eq!(f.as_readonly::<&C3>().unwrap_err(), TransmuteError::Access);
+                assert_eq!(
+                    f.transmute::<(&mut C1, &mut C1)>().unwrap_err(),
+                    TransmuteError::Init
+                );
+
+                // Components which haven't been added match nothing.
+                assert_eq!(f.as_readonly::<&C4>().unwrap().fetcher().iter().count(), 0);
             },
         );
 
         world.send(E1);
+
+        assert_eq!(world.get::<&C1>(e3), Some(&C1(3)));
+    }
+
+    #[derive(Event)]
+    struct Hit(#[event(target)] EntityId);
+
+    #[test]
+    fn targeted_param() {
+        let mut world = World::new();
+
+        world.add_system(
+            |r: Receiver<Hit, With<&C2>>,
+             Targeted(c1): Targeted<&mut C1>,
+             mut others: Fetcher<(&mut C1, Not<&C2>)>| {
+                c1.0 += 1;
+
+                // The fetcher can't see the target, which has `C2`.
+                assert!(others.get_mut(r.target).is_err());
+
+                for (c1, _) in &mut others {
+                    c1.0 += 10;
+                }
+            },
+        );
+
+        let target = world.spawn();
+        world.insert(target, C1(0));
+        world.insert(target, C2(0));
+
+        let other = world.spawn();
+        world.insert(other, C1(0));
+
+        // Doesn't match the queries, so the system is skipped.
+        let skipped = world.spawn();
+
+        world.send(Hit(target));
+        world.send(Hit(skipped));
+
+        assert_eq!(world.get::<&C1>(target), Some(&C1(1)));
+        assert_eq!(world.get::<&C1>(other), Some(&C1(10)));
+    }
+
+    #[test]
+    #[should_panic(expected = "could alias the components of the received event's target")]
+    fn targeted_param_aliases_fetcher() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<Hit, ()>, _: Targeted<&mut C1>, _: Fetcher<&C1>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "the event target's components could be aliased")]
+    fn fetcher_aliases_targeted_param() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<Hit, ()>, _: Fetcher<&C1>, _: Targeted<&mut C1>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "could alias the components of the received event's target")]
+    fn targeted_param_aliases_receiver() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<Hit, &mut C1>, _: Targeted<&C1>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "must come after a receiver for a targeted event")]
+    fn targeted_param_before_receiver() {
+        let mut world = World::new();
+
+        world.add_system(|_: Targeted<&C1>, _: Receiver<Hit, ()>| {});
     }
 }
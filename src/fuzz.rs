@@ -0,0 +1,175 @@
+//! Randomized tests of structural world operations.
+//!
+//! Each test runs a deterministic sequence of random spawns, despawns,
+//! insertions, removals, clones, and system changes, checking
+//! [`World::check_invariants`] after every step. Failures print the seed and
+//! step so they can be reproduced.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::entity::CloneOptions;
+use crate::prelude::*;
+
+/// A small xorshift generator, so runs are reproducible without extra
+/// dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[derive(Component, Clone)]
+struct A(#[allow(dead_code)] u8);
+
+#[derive(Component, Clone)]
+struct B(#[allow(dead_code)] u64);
+
+#[derive(Component)]
+struct C;
+
+#[derive(Component, Clone)]
+struct D(#[allow(dead_code)] Arc<()>);
+
+#[derive(Event)]
+struct Tick;
+
+#[derive(Event)]
+struct Poke(#[event(target)] EntityId);
+
+const COMPONENT_COUNT: usize = 4;
+
+fn insert(world: &mut World, e: EntityId, which: usize, payload: &Arc<()>) {
+    match which {
+        0 => world.insert(e, A(1)),
+        1 => world.insert(e, B(2)),
+        2 => world.insert(e, C),
+        _ => world.insert(e, D(payload.clone())),
+    }
+}
+
+fn remove(world: &mut World, e: EntityId, which: usize) {
+    match which {
+        0 => world.remove::<A>(e),
+        1 => world.remove::<B>(e),
+        2 => world.remove::<C>(e),
+        _ => world.remove::<D>(e),
+    }
+}
+
+/// Adds one of a few systems whose fetchers cover different sets of
+/// archetypes. Running them walks every cached column.
+fn add_system(world: &mut World, which: usize) -> SystemId {
+    match which {
+        0 => world.add_system(
+            |_: Receiver<Tick>, f: Fetcher<(EntityId, &A)>| {
+                for _ in f {}
+            },
+        ),
+        1 => world.add_system(
+            |_: Receiver<Tick>, f: Fetcher<(&B, Option<&mut C>)>| {
+                for _ in f {}
+            },
+        ),
+        2 => world.add_system(|_: Receiver<Tick>, f: Fetcher<(&D, Not<&A>)>| for _ in f {}),
+        3 => world.add_system(|_: Receiver<Poke, (&A, &B)>| {}),
+        _ => world.add_system(|_: Receiver<Poke, Or<&C, &D>>| {}),
+    }
+}
+
+fn run(seed: u64, steps: usize) {
+    let mut rng = Rng::new(seed);
+    let mut world = World::new();
+
+    let payload = Arc::new(());
+
+    let mut entities = Vec::new();
+    let mut systems = Vec::new();
+
+    for step in 0..steps {
+        match rng.below(11) {
+            0 | 1 => entities.push(world.spawn()),
+            2 if !entities.is_empty() => {
+                let e = entities.swap_remove(rng.below(entities.len()));
+                world.despawn(e);
+            }
+            3..=5 if !entities.is_empty() => {
+                let e = entities[rng.below(entities.len())];
+                insert(&mut world, e, rng.below(COMPONENT_COUNT), &payload);
+            }
+            6 | 7 if !entities.is_empty() => {
+                let e = entities[rng.below(entities.len())];
+                remove(&mut world, e, rng.below(COMPONENT_COUNT));
+            }
+            8 => {
+                // Adding the same system twice returns the existing ID.
+                let id = add_system(&mut world, rng.below(5));
+                if !systems.contains(&id) {
+                    systems.push(id);
+                }
+            }
+            9 if !systems.is_empty() => {
+                let id = systems.swap_remove(rng.below(systems.len()));
+                assert!(world.remove_system(id).is_some());
+            }
+            10 if !entities.is_empty() => {
+                // `C` isn't `Clone`, so entities with it are copied without it.
+                let e = entities[rng.below(entities.len())];
+                let options = CloneOptions {
+                    skip_uncloneable: true,
+                    send_insert_events: rng.below(2) == 0,
+                };
+                entities.push(world.clone_entity_with(e, options).unwrap());
+            }
+            _ => {}
+        }
+
+        world.send(Tick);
+
+        if let Some(&e) = entities.first() {
+            world.send(Poke(e));
+        }
+
+        if let Err(e) = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            world.check_invariants();
+        })) {
+            std::eprintln!("invariant violated with seed {seed} at step {step}");
+            std::panic::resume_unwind(e);
+        }
+    }
+
+    let live_d = world.query::<&D>().iter().count();
+
+    drop(world);
+
+    assert!(live_d <= entities.len());
+    assert_eq!(Arc::strong_count(&payload), 1, "leaked a component");
+}
+
+#[test]
+fn structural_operations() {
+    for seed in 0..32 {
+        run(seed, 500);
+    }
+}
+
+#[test]
+fn structural_operations_many_entities() {
+    for seed in 100..104 {
+        run(seed, 5000);
+    }
+}
@@ -0,0 +1,654 @@
+//! Global values and the [`Res`] and [`ResMut`] system parameters.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::any::{self, Any, TypeId};
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::event::EventPtr;
+use crate::sparse::SparseIndex;
+use crate::system::{Config, InitError, SystemInfo, SystemParam};
+use crate::world::{UnsafeWorldCell, World};
+
+/// Stores the global values of a world.
+///
+/// Globals are singleton values identified by their type. Unlike components,
+/// they are not attached to any entity and do not participate in archetypes or
+/// queries. Globals are accessed in systems with the [`Res`] and [`ResMut`]
+/// system parameters.
+pub struct Globals {
+    slots: Vec<GlobalSlot>,
+    by_type_id: BTreeMap<TypeId, GlobalIdx>,
+}
+
+struct GlobalSlot {
+    name: &'static str,
+    /// Pointer obtained from `Box::into_raw`. `None` if the global is not
+    /// currently present.
+    value: Option<NonNull<dyn Any + Send + Sync>>,
+}
+
+impl Globals {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: vec![],
+            by_type_id: BTreeMap::new(),
+        }
+    }
+
+    /// Gets the index of global `T`, reserving a new slot for it if it hasn't
+    /// been seen before. The slot starts out empty.
+    pub(crate) fn init<T: Send + Sync + 'static>(&mut self) -> GlobalIdx {
+        if let Some(&idx) = self.by_type_id.get(&TypeId::of::<T>()) {
+            return idx;
+        }
+
+        let Ok(idx) = u32::try_from(self.slots.len()) else {
+            panic!("too many globals")
+        };
+
+        let idx = GlobalIdx(idx);
+
+        self.slots.push(GlobalSlot {
+            name: any::type_name::<T>(),
+            value: None,
+        });
+        self.by_type_id.insert(TypeId::of::<T>(), idx);
+
+        idx
+    }
+
+    pub(crate) fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        let idx = self.init::<T>();
+        let slot = &mut self.slots[idx.0 as usize];
+
+        if let Some(ptr) = slot.value {
+            // SAFETY: The slot for `T` always holds a `T`.
+            let old = unsafe { &mut *ptr.cast::<T>().as_ptr() };
+            return Some(core::mem::replace(old, value));
+        }
+
+        let ptr: NonNull<dyn Any + Send + Sync> =
+            unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(value))) };
+        slot.value = Some(ptr);
+
+        None
+    }
+
+    pub(crate) fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        let idx = *self.by_type_id.get(&TypeId::of::<T>())?;
+        let ptr = self.slots[idx.0 as usize].value.take()?;
+
+        // SAFETY: Pointer came from `Box::into_raw` and holds a `T`.
+        Some(*unsafe { Box::from_raw(ptr.cast::<T>().as_ptr()) })
+    }
+
+    /// Gets the [`GlobalIdx`] of global `T`, if it has been seen by this
+    /// world.
+    pub fn index_of<T: 'static>(&self) -> Option<GlobalIdx> {
+        self.by_type_id.get(&TypeId::of::<T>()).copied()
+    }
+
+    /// Returns whether or not a value for global `T` is currently present.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.index_of::<T>()
+            .is_some_and(|idx| self.slots[idx.0 as usize].value.is_some())
+    }
+
+    /// Gets the type name of the global at `idx`. Returns `None` if the index
+    /// is invalid.
+    pub fn name(&self, idx: GlobalIdx) -> Option<&'static str> {
+        self.slots.get(idx.0 as usize).map(|slot| slot.name)
+    }
+
+    /// Gets an immutable reference to global `T`, or `None` if it is not
+    /// present.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        let idx = self.index_of::<T>()?;
+        self.get_ptr(idx)
+            .map(|ptr| unsafe { &*ptr.cast::<T>().as_ptr() })
+    }
+
+    /// Gets a mutable reference to global `T`, or `None` if it is not
+    /// present.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        let idx = self.index_of::<T>()?;
+        self.get_ptr(idx)
+            .map(|ptr| unsafe { &mut *ptr.cast::<T>().as_ptr() })
+    }
+
+    fn get_ptr(&self, idx: GlobalIdx) -> Option<NonNull<dyn Any + Send + Sync>> {
+        self.slots.get(idx.0 as usize).and_then(|slot| slot.value)
+    }
+
+    /// Returns the number of globals currently present.
+    pub fn len(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.value.is_some())
+            .count()
+    }
+}
+
+impl Drop for Globals {
+    fn drop(&mut self) {
+        for slot in &mut self.slots {
+            if let Some(ptr) = slot.value.take() {
+                drop(unsafe { Box::from_raw(ptr.as_ptr()) });
+            }
+        }
+    }
+}
+
+// SAFETY: All globals are `Send + Sync`.
+unsafe impl Send for Globals {}
+unsafe impl Sync for Globals {}
+
+impl fmt::Debug for Globals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.slots
+                    .iter()
+                    .filter(|slot| slot.value.is_some())
+                    .map(|slot| slot.name),
+            )
+            .finish()
+    }
+}
+
+/// Identifies the slot of a global in the [`Globals`] of a world. Indices are
+/// never reused, even after a global is removed.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct GlobalIdx(pub u32);
+
+unsafe impl SparseIndex for GlobalIdx {
+    const MAX: Self = Self(u32::MAX);
+
+    fn index(self) -> usize {
+        self.0.index()
+    }
+
+    fn from_index(idx: usize) -> Self {
+        Self(u32::from_index(idx))
+    }
+}
+
+/// A [`SystemParam`] providing shared access to global `T`.
+///
+/// The system panics if the global is not present when it runs. Use
+/// `Option<Res<T>>` if the global is optional.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::global::Res;
+/// use evenio::prelude::*;
+///
+/// struct Gravity(f32);
+///
+/// #[derive(Event)]
+/// struct E;
+///
+/// let mut world = World::new();
+///
+/// world.insert_global(Gravity(9.81));
+///
+/// world.add_system(|_: Receiver<E>, gravity: Res<Gravity>| {
+///     println!("gravity is {}", gravity.0);
+/// });
+///
+/// world.send(E);
+/// ```
+pub struct Res<'a, T: ?Sized> {
+    value: &'a T,
+}
+
+impl<'a, T: ?Sized> Res<'a, T> {
+    /// Returns the underlying reference with the full lifetime.
+    pub fn into_inner(self) -> &'a T {
+        self.value
+    }
+
+    /// Makes a `Res` for a part of the global, such as one of its fields.
+    ///
+    /// This is an associated function to avoid conflicts with methods of `T`.
+    /// Use it as `Res::map(res, f)`.
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U>(this: Self, f: F) -> Res<'a, U> {
+        Res {
+            value: f(this.value),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Res<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// A [`SystemParam`] providing exclusive access to global `T`.
+///
+/// The system panics if the global is not present when it runs. Use
+/// `Option<ResMut<T>>` if the global is optional.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::global::ResMut;
+/// use evenio::prelude::*;
+///
+/// struct Tick(u64);
+///
+/// #[derive(Event)]
+/// struct E;
+///
+/// let mut world = World::new();
+///
+/// world.insert_global(Tick(0));
+///
+/// world.add_system(|_: Receiver<E>, mut tick: ResMut<Tick>| {
+///     tick.0 += 1;
+/// });
+///
+/// world.send(E);
+/// world.send(E);
+///
+/// assert_eq!(world.global::<Tick>().unwrap().0, 2);
+/// ```
+pub struct ResMut<'a, T: ?Sized> {
+    value: &'a mut T,
+}
+
+impl<'a, T: ?Sized> ResMut<'a, T> {
+    /// Returns the underlying reference with the full lifetime.
+    pub fn into_inner(self) -> &'a mut T {
+        self.value
+    }
+
+    /// Makes a `ResMut` for a part of the global, such as one of its fields,
+    /// so that helper functions don't need access to the whole global.
+    ///
+    /// This is an associated function to avoid conflicts with methods of `T`.
+    /// Use it as `ResMut::map(res, f)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::global::ResMut;
+    /// use evenio::prelude::*;
+    ///
+    /// struct Camera {
+    ///     position: [f32; 2],
+    ///     zoom: f32,
+    /// }
+    ///
+    /// fn pan(mut position: ResMut<[f32; 2]>) {
+    ///     position[0] += 1.0;
+    /// }
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.insert_global(Camera {
+    ///     position: [0.0; 2],
+    ///     zoom: 1.0,
+    /// });
+    ///
+    /// world.add_system(|_: Receiver<E>, mut camera: ResMut<Camera>| {
+    ///     pan(ResMut::map(ResMut::reborrow(&mut camera), |c| {
+    ///         &mut c.position
+    ///     }));
+    ///     camera.zoom *= 2.0;
+    /// });
+    ///
+    /// world.send(E);
+    ///
+    /// let camera = world.global::<Camera>().unwrap();
+    /// assert_eq!(camera.position, [1.0, 0.0]);
+    /// assert_eq!(camera.zoom, 2.0);
+    /// ```
+    pub fn map<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(this: Self, f: F) -> ResMut<'a, U> {
+        ResMut {
+            value: f(this.value),
+        }
+    }
+
+    /// Makes a `ResMut` with a shorter lifetime which borrows from `this`, so
+    /// that `this` can be used again once the new `ResMut` is dropped.
+    ///
+    /// This is an associated function to avoid conflicts with methods of `T`.
+    /// Use it as `ResMut::reborrow(&mut res)`.
+    pub fn reborrow<'b>(this: &'b mut Self) -> ResMut<'b, T> {
+        ResMut { value: this.value }
+    }
+}
+
+impl<T: ?Sized> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T: ?Sized> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for ResMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+fn init_global_access<T: Send + Sync + 'static>(
+    world: &mut World,
+    config: &mut Config,
+    access: Access,
+) -> Result<GlobalIdx, InitError> {
+    let idx = world.globals_mut().init::<T>();
+
+    let mut current = config.global_access.get(idx);
+
+    if !current.set_if_compatible(access) {
+        return Err(InitError(
+            format!(
+                "`{access:?}` access to global `{}` conflicts with previous access in this system",
+                any::type_name::<T>()
+            )
+            .into(),
+        ));
+    }
+
+    config.global_access.set(idx, current);
+
+    Ok(idx)
+}
+
+/// Gets a pointer to the global at `idx`, or `None` if it is absent.
+unsafe fn global_ptr<T: 'static>(world: UnsafeWorldCell, idx: GlobalIdx) -> Option<NonNull<T>> {
+    world.globals().get_ptr(idx).map(NonNull::cast)
+}
+
+#[cold]
+#[track_caller]
+fn missing_global<T>() -> ! {
+    panic!(
+        "global `{}` does not exist (hint: use `Option<Res<T>>` or `Option<ResMut<T>>` for \
+         optional globals)",
+        any::type_name::<T>()
+    )
+}
+
+unsafe impl<T: Send + Sync + 'static> SystemParam for Res<'_, T> {
+    type State = GlobalIdx;
+
+    type Item<'a> = Res<'a, T>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        init_global_access::<T>(world, config, Access::Read)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        _event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        match global_ptr::<T>(world, *state) {
+            Some(ptr) => Res {
+                value: &*ptr.as_ptr(),
+            },
+            None => missing_global::<T>(),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+unsafe impl<T: Send + Sync + 'static> SystemParam for ResMut<'_, T> {
+    type State = GlobalIdx;
+
+    type Item<'a> = ResMut<'a, T>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        init_global_access::<T>(world, config, Access::ReadWrite)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        _event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        match global_ptr::<T>(world, *state) {
+            Some(ptr) => ResMut {
+                value: &mut *ptr.as_ptr(),
+            },
+            None => missing_global::<T>(),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+unsafe impl<T: Send + Sync + 'static> SystemParam for Option<Res<'_, T>> {
+    type State = GlobalIdx;
+
+    type Item<'a> = Option<Res<'a, T>>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        init_global_access::<T>(world, config, Access::Read)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        _event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        global_ptr::<T>(world, *state).map(|ptr| Res {
+            value: &*ptr.as_ptr(),
+        })
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+unsafe impl<T: Send + Sync + 'static> SystemParam for Option<ResMut<'_, T>> {
+    type State = GlobalIdx;
+
+    type Item<'a> = Option<ResMut<'a, T>>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        init_global_access::<T>(world, config, Access::ReadWrite)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a SystemInfo,
+        _event_ptr: EventPtr<'a>,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        global_ptr::<T>(world, *state).map(|ptr| ResMut {
+            value: &mut *ptr.as_ptr(),
+        })
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct E;
+
+    #[derive(PartialEq, Debug)]
+    struct G1(u32);
+
+    #[derive(PartialEq, Debug)]
+    struct G2(u32);
+
+    #[test]
+    fn insert_remove_global() {
+        let mut world = World::new();
+
+        assert_eq!(world.global::<G1>(), None);
+        assert_eq!(world.insert_global(G1(1)), None);
+        assert_eq!(world.insert_global(G1(2)), Some(G1(1)));
+        assert_eq!(world.global::<G1>(), Some(&G1(2)));
+
+        world.global_mut::<G1>().unwrap().0 += 1;
+        assert_eq!(world.global::<G1>(), Some(&G1(3)));
+
+        assert_eq!(world.remove_global::<G1>(), Some(G1(3)));
+        assert_eq!(world.remove_global::<G1>(), None);
+        assert_eq!(world.global::<G1>(), None);
+        assert_eq!(world.globals().len(), 0);
+    }
+
+    #[test]
+    fn res_and_res_mut() {
+        let mut world = World::new();
+
+        world.insert_global(G1(10));
+        world.insert_global(G2(0));
+
+        world.add_system(|_: Receiver<E>, g1: Res<G1>, mut g2: ResMut<G2>| {
+            g2.0 += g1.0;
+        });
+
+        world.send(E);
+        world.send(E);
+
+        assert_eq!(world.global::<G2>(), Some(&G2(20)));
+    }
+
+    #[test]
+    fn map_res_mut() {
+        struct Pair(G1, G2);
+
+        fn add(mut g: ResMut<u32>, n: u32) {
+            *g += n;
+        }
+
+        let mut world = World::new();
+
+        world.insert_global(Pair(G1(0), G2(0)));
+
+        world.add_system(|_: Receiver<E>, mut pair: ResMut<Pair>| {
+            add(ResMut::map(ResMut::reborrow(&mut pair), |p| &mut p.0 .0), 1);
+            add(ResMut::map(pair, |p| &mut p.1 .0), 2);
+        });
+
+        world.add_system(|_: Receiver<E>, pair: Res<Pair>| {
+            assert_eq!(*Res::map(pair, |p| &p.0), G1(1));
+        });
+
+        world.send(E);
+
+        let pair = world.global::<Pair>().unwrap();
+        assert_eq!((&pair.0, &pair.1), (&G1(1), &G2(2)));
+    }
+
+    #[test]
+    fn optional_global() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<E>, g1: Option<ResMut<G1>>| {
+            if let Some(mut g1) = g1 {
+                g1.0 += 1;
+            }
+        });
+
+        world.send(E);
+        assert_eq!(world.global::<G1>(), None);
+
+        world.insert_global(G1(0));
+        world.send(E);
+        assert_eq!(world.global::<G1>(), Some(&G1(1)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_global_panics() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<E>, _: Res<G1>| {});
+
+        world.send(E);
+    }
+
+    #[test]
+    fn conflicting_global_access() {
+        let mut world = World::new();
+
+        fn try_add<S: IntoSystem<M>, M>(world: &mut World, s: S) -> bool {
+            std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+                world.add_system(s);
+            }))
+            .is_ok()
+        }
+
+        assert!(try_add(
+            &mut world,
+            |_: Receiver<E>, _: Res<G1>, _: Res<G1>| {}
+        ));
+        assert!(!try_add(
+            &mut world,
+            |_: Receiver<E>, _: Res<G1>, _: ResMut<G1>| {}
+        ));
+        assert!(!try_add(
+            &mut world,
+            |_: Receiver<E>, _: ResMut<G1>, _: ResMut<G1>| {}
+        ));
+        assert!(try_add(
+            &mut world,
+            |_: Receiver<E>, _: ResMut<G1>, _: ResMut<G2>| {}
+        ));
+    }
+
+    #[test]
+    fn system_global_access() {
+        let mut world = World::new();
+
+        let id = world.add_system(|_: Receiver<E>, _: Res<G1>, _: ResMut<G2>| {});
+
+        let info = world.systems().get(id).unwrap();
+        let g1 = world.globals().index_of::<G1>().unwrap();
+        let g2 = world.globals().index_of::<G2>().unwrap();
+
+        assert_eq!(info.global_access().get(g1), Access::Read);
+        assert_eq!(info.global_access().get(g2), Access::ReadWrite);
+    }
+}
@@ -0,0 +1,430 @@
+//! Groups of components which are fetched together.
+//!
+//! Some components are always queried as a set, like the parts of a
+//! transform. A [`ComponentGroup`] names such a set, and the [`Group`] and
+//! [`GroupMut`] queries fetch all of its members at once. The world keeps
+//! track of which archetypes have every member of each group, which can be
+//! listed with [`Archetypes::archetypes_with_group`].
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::prelude::*;
+//!
+//! #[derive(Component)]
+//! struct Translation([f32; 3]);
+//!
+//! #[derive(Component)]
+//! struct Rotation(f32);
+//!
+//! #[derive(Component)]
+//! struct Scale(f32);
+//!
+//! struct Transform;
+//!
+//! impl ComponentGroup for Transform {
+//!     type Members = (Translation, Rotation, Scale);
+//! }
+//!
+//! #[derive(Event)]
+//! struct Tick;
+//!
+//! let mut world = World::new();
+//!
+//! world.add_system(|_: Receiver<Tick>, f: Fetcher<GroupMut<Transform>>| {
+//!     for (translation, rotation, scale) in f {
+//!         translation.0[0] += 1.0;
+//!         rotation.0 += 0.5;
+//!         scale.0 *= 2.0;
+//!     }
+//! });
+//!
+//! let e = world.spawn();
+//! world.insert(e, Translation([0.0; 3]));
+//! world.insert(e, Rotation(0.0));
+//! world.insert(e, Scale(1.0));
+//!
+//! world.send(Tick);
+//!
+//! assert_eq!(world.get::<&Scale>(e).unwrap().0, 2.0);
+//! ```
+//!
+//! [`Archetypes::archetypes_with_group`]: crate::archetype::Archetypes::archetypes_with_group
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::marker::PhantomData;
+use core::{any, fmt};
+
+use evenio_macros::all_tuples;
+
+use crate::access::ComponentAccessExpr;
+use crate::archetype::{Archetype, ArchetypeRow};
+use crate::bool_expr::BoolExpr;
+use crate::component::{Component, ComponentIdx};
+use crate::query::{Query, ReadOnlyQuery};
+use crate::sparse::SparseIndex;
+use crate::system::{Config, InitError};
+use crate::world::World;
+
+/// A named set of components which are fetched together with [`Group`] or
+/// [`GroupMut`].
+///
+/// Groups are added to the world the first time they are used in a query, or
+/// explicitly with [`World::add_group`].
+pub trait ComponentGroup: 'static {
+    /// The components in the group, as a tuple like `(A, B, C)`.
+    type Members: GroupMembers;
+}
+
+/// Tuples of [`Component`] types which can be the members of a
+/// [`ComponentGroup`]. This is implemented for tuples of up to 12 components.
+pub trait GroupMembers: 'static {
+    /// The query reading every member, e.g. `(&A, &B)`.
+    type Read: ReadOnlyQuery + 'static;
+    /// The query writing every member, e.g. `(&mut A, &mut B)`.
+    type Write: Query + 'static;
+
+    /// Adds every member to the world and returns their indices.
+    fn add_components(world: &mut World) -> Vec<ComponentIdx>;
+}
+
+macro_rules! impl_group_members {
+    ($(($C:ident, $c:ident)),*) => {
+        impl<$($C: Component),*> GroupMembers for ($($C,)*) {
+            type Read = ($(&'static $C,)*);
+            type Write = ($(&'static mut $C,)*);
+
+            fn add_components(world: &mut World) -> Vec<ComponentIdx> {
+                #![allow(unused_variables)]
+
+                vec![$(world.add_component::<$C>().index()),*]
+            }
+        }
+    }
+}
+
+all_tuples!(impl_group_members, 0, 12, C, c);
+
+/// Index of a [`ComponentGroup`] in the world.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct GroupIdx(pub u32);
+
+unsafe impl SparseIndex for GroupIdx {
+    const MAX: Self = Self(u32::MAX);
+
+    fn index(self) -> usize {
+        self.0.index()
+    }
+
+    fn from_index(idx: usize) -> Self {
+        Self(u32::from_index(idx))
+    }
+}
+
+/// Metadata for a [`ComponentGroup`] added to the world.
+#[derive(Debug)]
+pub struct GroupInfo {
+    name: Cow<'static, str>,
+    type_id: TypeId,
+    /// The sorted and deduplicated members.
+    components: Box<[ComponentIdx]>,
+    /// Whether one of the members was removed from the world. Removed groups
+    /// don't match any archetype.
+    removed: bool,
+}
+
+impl GroupInfo {
+    /// Gets the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the [`TypeId`] of the group.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Gets the members of the group, in ascending order of their indices.
+    pub fn components(&self) -> &[ComponentIdx] {
+        &self.components
+    }
+
+    /// Returns whether an archetype with the sorted `component_set` has every
+    /// member of the group.
+    pub(crate) fn matches(&self, component_set: &[ComponentIdx]) -> bool {
+        !self.removed
+            && self
+                .components
+                .iter()
+                .all(|c| component_set.binary_search(c).is_ok())
+    }
+}
+
+/// The [`ComponentGroup`]s added to the world.
+#[derive(Debug, Default)]
+pub(crate) struct Groups {
+    infos: Vec<GroupInfo>,
+    by_type_id: Vec<(TypeId, GroupIdx)>,
+}
+
+impl Groups {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn get(&self, idx: GroupIdx) -> Option<&GroupInfo> {
+        self.infos.get(idx.0 as usize)
+    }
+
+    pub(crate) fn get_by_type_id(&self, type_id: TypeId) -> Option<GroupIdx> {
+        self.by_type_id
+            .iter()
+            .find(|&&(id, _)| id == type_id)
+            .map(|&(_, idx)| idx)
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        name: Cow<'static, str>,
+        type_id: TypeId,
+        mut components: Vec<ComponentIdx>,
+    ) -> GroupIdx {
+        let idx = GroupIdx::from_index(self.infos.len());
+
+        components.sort_unstable();
+        components.dedup();
+
+        self.infos.push(GroupInfo {
+            name,
+            type_id,
+            components: components.into_boxed_slice(),
+            removed: false,
+        });
+        self.by_type_id.push((type_id, idx));
+
+        idx
+    }
+
+    /// Marks the groups with `component` as removed.
+    pub(crate) fn remove_component(&mut self, component: ComponentIdx) {
+        for info in &mut self.infos {
+            if !info.removed && info.components.binary_search(&component).is_ok() {
+                info.removed = true;
+            }
+        }
+
+        let infos = &self.infos;
+        self.by_type_id
+            .retain(|&(_, idx)| !infos[idx.0 as usize].removed);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (GroupIdx, &GroupInfo)> {
+        self.infos
+            .iter()
+            .enumerate()
+            .map(|(i, info)| (GroupIdx::from_index(i), info))
+    }
+}
+
+/// A [`Query`] which reads every member of the [`ComponentGroup`] `G`.
+///
+/// The item is a tuple of references to the members, in the order they are
+/// listed in [`ComponentGroup::Members`].
+pub struct Group<G>(PhantomData<fn() -> G>);
+
+/// A [`Query`] which writes every member of the [`ComponentGroup`] `G`.
+///
+/// The item is a tuple of mutable references to the members, in the order
+/// they are listed in [`ComponentGroup::Members`].
+pub struct GroupMut<G>(PhantomData<fn() -> G>);
+
+impl<G> fmt::Debug for Group<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Group<{}>", any::type_name::<G>())
+    }
+}
+
+impl<G> fmt::Debug for GroupMut<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GroupMut<{}>", any::type_name::<G>())
+    }
+}
+
+macro_rules! impl_group_query {
+    ($ty:ident, $Q:ident) => {
+        unsafe impl<G: ComponentGroup> Query for $ty<G> {
+            type Item<'a> = <<G::Members as GroupMembers>::$Q as Query>::Item<'a>;
+
+            type ArchState = <<G::Members as GroupMembers>::$Q as Query>::ArchState;
+
+            type State = (GroupIdx, <<G::Members as GroupMembers>::$Q as Query>::State);
+
+            fn init(
+                world: &mut World,
+                config: &mut Config,
+            ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+                let group = world.add_group::<G>();
+                let (expr, state) = <G::Members as GroupMembers>::$Q::init(world, config)?;
+
+                Ok((expr, (group, state)))
+            }
+
+            fn new_state(world: &mut World) -> Self::State {
+                (
+                    world.add_group::<G>(),
+                    <G::Members as GroupMembers>::$Q::new_state(world),
+                )
+            }
+
+            fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+                let (mut expr, state) = <G::Members as GroupMembers>::$Q::init_existing(world)?;
+
+                // No archetype has a group which hasn't been added.
+                let group = match world
+                    .archetypes()
+                    .groups()
+                    .get_by_type_id(TypeId::of::<G>())
+                {
+                    Some(group) => group,
+                    None => {
+                        expr.expr = BoolExpr::new(false);
+                        GroupIdx::MAX
+                    }
+                };
+
+                Some((expr, (group, state)))
+            }
+
+            fn new_arch_state(
+                arch: &Archetype,
+                (group, state): &mut Self::State,
+            ) -> Option<Self::ArchState> {
+                if !arch.has_group(*group) {
+                    return None;
+                }
+
+                <G::Members as GroupMembers>::$Q::new_arch_state(arch, state)
+            }
+
+            unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+                <G::Members as GroupMembers>::$Q::get(state, row)
+            }
+
+            #[inline]
+            unsafe fn prefetch(state: &Self::ArchState, row: ArchetypeRow) {
+                <G::Members as GroupMembers>::$Q::prefetch(state, row)
+            }
+        }
+    };
+}
+
+impl_group_query!(Group, Read);
+impl_group_query!(GroupMut, Write);
+
+unsafe impl<G: ComponentGroup> ReadOnlyQuery for Group<G> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component, PartialEq, Debug)]
+    struct A(u32);
+
+    #[derive(Component, PartialEq, Debug)]
+    struct B(u32);
+
+    #[derive(Component)]
+    struct C;
+
+    struct AB;
+
+    impl ComponentGroup for AB {
+        type Members = (A, B);
+    }
+
+    #[derive(Event)]
+    struct E;
+
+    #[test]
+    fn group_matches_archetypes_with_every_member() {
+        let mut world = World::new();
+
+        let ab = world.spawn();
+        world.insert(ab, A(1));
+        world.insert(ab, B(2));
+
+        let abc = world.spawn();
+        world.insert(abc, A(3));
+        world.insert(abc, B(4));
+        world.insert(abc, C);
+
+        let a = world.spawn();
+        world.insert(a, A(5));
+
+        world.add_system(|_: Receiver<E>, f: Fetcher<GroupMut<AB>>| {
+            for (a, b) in f {
+                a.0 += 10;
+                b.0 += 10;
+            }
+        });
+
+        let group = world.add_group::<AB>();
+        assert_eq!(world.add_group::<AB>(), group);
+
+        let archetypes = world.archetypes();
+        let with_group = archetypes
+            .archetypes_with_group(group)
+            .map(|arch| arch.index())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            with_group,
+            [ab, abc].map(|e| world.entities().get(e).unwrap().archetype)
+        );
+
+        world.send(E);
+
+        assert_eq!(world.get::<&A>(ab), Some(&A(11)));
+        assert_eq!(world.get::<&B>(abc), Some(&B(14)));
+        assert_eq!(world.get::<&A>(a), Some(&A(5)));
+
+        // Archetypes created after the group is added are tracked too.
+        world.insert(a, B(6));
+        world.send(E);
+
+        assert_eq!(world.get::<(&A, &B)>(a), Some((&A(15), &B(16))));
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicting access")]
+    fn group_conflicts_with_member() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<E>, _: Fetcher<(GroupMut<AB>, &A)>| {});
+    }
+
+    #[test]
+    fn removed_member_removes_group() {
+        let mut world = World::new();
+
+        let group = world.add_group::<AB>();
+
+        let e = world.spawn();
+        world.insert(e, A(1));
+        world.insert(e, B(2));
+
+        let b = world.add_component::<B>();
+        world.remove_component(b);
+
+        assert_eq!(world.archetypes().archetypes_with_group(group).count(), 0);
+        assert_eq!(
+            world.archetypes().group(group).unwrap().components().len(),
+            2
+        );
+        assert_ne!(world.add_group::<AB>(), group);
+    }
+}
@@ -14,14 +14,31 @@ mod assert;
 pub mod bit_set;
 mod blob_vec;
 pub mod bool_expr;
+pub mod bridge;
+pub mod command;
 pub mod component;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+#[cfg(feature = "diff")]
+pub mod diff;
 pub mod drop;
 pub mod entity;
+pub mod entity_group;
 pub mod event;
 pub mod exclusive;
 pub mod fetch;
+#[cfg(test)]
+mod fuzz;
+pub mod global;
+pub mod group;
 mod layout_util;
+pub mod memory;
+#[cfg(feature = "name")]
+pub mod name;
 pub mod query;
+#[cfg(feature = "replay")]
+pub mod replay;
+pub mod schedule;
 mod slot_map;
 pub mod sparse;
 mod sparse_map;
@@ -33,7 +50,87 @@ pub mod world;
 /// For macros only.
 #[doc(hidden)]
 pub mod __private {
+    use core::marker::PhantomData;
+
     pub use memoffset::offset_of;
+
+    use crate::component::{clone_fn_of, default_fn_of, CloneFn, DefaultFn};
+
+    /// Picks the [`CloneFn`] of `T` if it implements `Clone`, using autoref
+    /// specialization: `(&&CloneProbe::<T>(PhantomData)).clone_fn()` resolves
+    /// to [`MaybeClone`] when `T: Clone` and falls back to [`NotClone`]
+    /// otherwise.
+    #[derive(Debug)]
+    pub struct CloneProbe<T>(pub PhantomData<fn() -> T>);
+
+    pub trait MaybeClone {
+        fn clone_fn(&self) -> Option<CloneFn>;
+    }
+
+    impl<T: Clone> MaybeClone for &CloneProbe<T> {
+        fn clone_fn(&self) -> Option<CloneFn> {
+            Some(clone_fn_of::<T>())
+        }
+    }
+
+    pub trait NotClone {
+        fn clone_fn(&self) -> Option<CloneFn>;
+    }
+
+    impl<T> NotClone for CloneProbe<T> {
+        fn clone_fn(&self) -> Option<CloneFn> {
+            None
+        }
+    }
+
+    /// Like [`CloneProbe`], but picks the [`DefaultFn`] of `T` if it
+    /// implements `Default`.
+    #[derive(Debug)]
+    pub struct DefaultProbe<T>(pub PhantomData<fn() -> T>);
+
+    pub trait MaybeDefault {
+        fn default_fn(&self) -> Option<DefaultFn>;
+    }
+
+    impl<T: Default> MaybeDefault for &DefaultProbe<T> {
+        fn default_fn(&self) -> Option<DefaultFn> {
+            Some(default_fn_of::<T>())
+        }
+    }
+
+    pub trait NotDefault {
+        fn default_fn(&self) -> Option<DefaultFn>;
+    }
+
+    impl<T> NotDefault for DefaultProbe<T> {
+        fn default_fn(&self) -> Option<DefaultFn> {
+            None
+        }
+    }
+
+    /// Like [`CloneProbe`], but checks whether `T` implements `Copy`.
+    #[derive(Debug)]
+    pub struct PodProbe<T>(pub PhantomData<fn() -> T>);
+
+    pub trait MaybePod {
+        fn is_pod(&self) -> bool;
+    }
+
+    impl<T: Copy> MaybePod for &PodProbe<T> {
+        fn is_pod(&self) -> bool {
+            true
+        }
+    }
+
+    pub trait NotPod {
+        fn is_pod(&self) -> bool;
+    }
+
+    impl<T> NotPod for PodProbe<T> {
+        fn is_pod(&self) -> bool {
+            false
+        }
+    }
 }
 
 /// Re-exports of the most commonly used items in the library.
@@ -44,13 +141,19 @@ pub mod __private {
 /// use evenio::prelude::*;
 /// ```
 pub mod prelude {
+    pub use crate::command::Commands;
     pub use crate::component::{Component, ComponentId};
     pub use crate::entity::EntityId;
     pub use crate::event::{
-        Despawn, Event, EventId, EventMut, Insert, Receiver, ReceiverMut, Remove, Sender, Spawn,
+        Despawn, Despawned, Event, EventEnum, EventId, EventMut, Insert, Receiver, ReceiverAny,
+        ReceiverMut, Remove, Removed, Replaced, Sender, Spawn,
+    };
+    pub use crate::fetch::{
+        Fetcher, GetError, Single, SingleError, SingleOrSkip, Targeted, TrySingle,
     };
-    pub use crate::fetch::{Fetcher, GetError, Single, SingleError, TrySingle};
-    pub use crate::query::{Has, Not, Or, Query, ReadOnlyQuery, With, Xor};
+    pub use crate::global::{Res, ResMut};
+    pub use crate::group::{ComponentGroup, Group, GroupMut};
+    pub use crate::query::{Has, Not, Or, Query, ReadOnlyQuery, With, Without, Xor};
     pub use crate::system::{IntoSystem, SystemId, SystemParam};
     pub use crate::world::World;
 }
@@ -0,0 +1,120 @@
+//! Reporting the memory used by a [`World`].
+//!
+//! [`World`]: crate::world::World
+
+use alloc::vec::Vec;
+
+use crate::archetype::ArchetypeIdx;
+use crate::component::ComponentId;
+
+/// An estimate of the heap memory used by a [`World`], returned by
+/// [`World::memory_usage`].
+///
+/// Sizes are measured from the capacity of each allocation, so memory which
+/// is reserved but unused is included. The size of tree maps and sets is
+/// estimated from their length. Memory owned by component values, such as the
+/// buffer of a `Vec` inside a component, is not included.
+///
+/// [`World`]: crate::world::World
+/// [`World::memory_usage`]: crate::world::World::memory_usage
+#[derive(Clone, Default, Debug)]
+pub struct MemoryReport {
+    /// The sum of every other size in the report.
+    pub total_bytes: usize,
+    /// Memory used by the locations of entities in [`Entities`].
+    ///
+    /// [`Entities`]: crate::entity::Entities
+    pub entities_bytes: usize,
+    /// Memory used by each archetype, in the order of [`Archetypes::iter`].
+    ///
+    /// [`Archetypes::iter`]: crate::archetype::Archetypes::iter
+    pub archetypes: Vec<ArchetypeMemory>,
+    /// Memory used by the table of archetypes and the index used to look them
+    /// up by their components.
+    pub archetype_table_bytes: usize,
+    /// Memory used by component columns, summed over all archetypes. Every
+    /// component with at least one column appears once, sorted by component
+    /// index.
+    pub bytes_by_component: Vec<(ComponentId, usize)>,
+    /// Memory used by systems and the tables of untargeted event listeners.
+    /// This includes the state of each system, but not any allocations owned
+    /// by that state.
+    pub systems_bytes: usize,
+}
+
+/// The memory used by a single [`Archetype`], as part of a
+/// [`MemoryReport`].
+///
+/// [`Archetype`]: crate::archetype::Archetype
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ArchetypeMemory {
+    /// The index of the archetype.
+    pub index: ArchetypeIdx,
+    /// Memory used by the component columns.
+    pub columns_bytes: usize,
+    /// Memory used by the IDs of the entities in the archetype.
+    pub entity_ids_bytes: usize,
+    /// Memory used by the archetype's targeted event listeners, the systems
+    /// notified of column changes, and the edges to other archetypes.
+    pub metadata_bytes: usize,
+}
+
+impl ArchetypeMemory {
+    /// Returns the sum of the sizes in this report.
+    pub fn total_bytes(&self) -> usize {
+        self.columns_bytes + self.entity_ids_bytes + self.metadata_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem;
+
+    use crate::entity::EntityLocation;
+    use crate::prelude::*;
+
+    #[test]
+    fn report_grows_with_entities() {
+        #[derive(Component)]
+        struct Big(#[allow(dead_code)] [u64; 4]);
+
+        let mut world = World::new();
+        let id = world.add_component::<Big>();
+
+        let before = world.memory_usage();
+        assert!(before.bytes_by_component.is_empty());
+
+        let count = 1000;
+
+        for _ in 0..count {
+            let e = world.spawn();
+            world.insert(e, Big([0; 4]));
+        }
+
+        let after = world.memory_usage();
+
+        let &[(c, bytes)] = after.bytes_by_component.as_slice() else {
+            panic!("expected one component, got {:?}", after.bytes_by_component)
+        };
+
+        assert_eq!(c, id);
+        assert!(bytes >= count * mem::size_of::<Big>());
+        // Columns at most double their capacity when they grow.
+        assert!(bytes <= 2 * count * mem::size_of::<Big>());
+
+        let arch = after
+            .archetypes
+            .iter()
+            .find(|arch| arch.columns_bytes > 0)
+            .unwrap();
+
+        assert_eq!(arch.columns_bytes, bytes);
+        assert!(arch.entity_ids_bytes >= count * mem::size_of::<EntityId>());
+
+        assert!(
+            after.entities_bytes - before.entities_bytes
+                >= count * mem::size_of::<EntityLocation>()
+        );
+        assert!(after.total_bytes - before.total_bytes >= bytes + arch.entity_ids_bytes);
+    }
+}
@@ -0,0 +1,159 @@
+//! The built-in [`Name`] component for labeling entities.
+//!
+//! Names are meant for debugging and tooling. The world keeps an index of
+//! them, so [`World::find_by_name`] doesn't have to visit every entity. The
+//! panic raised by the `validate-components` feature mentions the names of
+//! the entities with invalid components.
+//!
+//! This module is only available with the `name` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::prelude::*;
+//!
+//! let mut world = World::new();
+//!
+//! let player = world.spawn();
+//! world.set_name(player, "player");
+//!
+//! assert_eq!(world.name(player), Some("player"));
+//! assert_eq!(world.find_by_name("player").collect::<Vec<_>>(), [player]);
+//! ```
+//!
+//! [`World::find_by_name`]: crate::world::World::find_by_name
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
+use crate::component::{Component, HookContext};
+use crate::entity::EntityId;
+
+/// A human-readable name for an entity. Names don't have to be unique.
+///
+/// The component is immutable so that the world's index of names stays up to
+/// date. Rename an entity by inserting a new `Name`, for instance with
+/// [`World::set_name`].
+///
+/// [`World::set_name`]: crate::world::World::set_name
+#[derive(Component, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[component(immutable, on_insert = on_insert, on_remove = on_remove)]
+pub struct Name(Cow<'static, str>);
+
+impl Name {
+    /// Creates a new name.
+    pub fn new<N: Into<Cow<'static, str>>>(name: N) -> Self {
+        Self(name.into())
+    }
+
+    /// Returns the name as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn on_insert(ctx: &mut HookContext, entity: EntityId, name: &Name) {
+    ctx.world_mut().names_mut().insert(name.0.clone(), entity);
+}
+
+fn on_remove(ctx: &mut HookContext, entity: EntityId, name: &Name) {
+    ctx.world_mut().names_mut().remove(&name.0, entity);
+}
+
+/// Maps names to the entities which have them.
+#[derive(Default, Debug)]
+pub(crate) struct NameIndex {
+    /// The entities with each name, in the order they were named.
+    map: BTreeMap<Cow<'static, str>, Vec<EntityId>>,
+}
+
+impl NameIndex {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, name: Cow<'static, str>, entity: EntityId) {
+        self.map.entry(name).or_default().push(entity);
+    }
+
+    fn remove(&mut self, name: &str, entity: EntityId) {
+        if let Some(entities) = self.map.get_mut(name) {
+            entities.retain(|&e| e != entity);
+
+            if entities.is_empty() {
+                self.map.remove(name);
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> &[EntityId] {
+        self.map.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn index_follows_renames_and_despawns() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        let b = world.spawn();
+        let c = world.spawn();
+
+        world.set_name(a, "crate");
+        world.set_name(b, String::from("crate"));
+        world.set_name(c, "barrel");
+
+        assert_eq!(world.find_by_name("crate").collect::<Vec<_>>(), [a, b]);
+        assert_eq!(world.name(c), Some("barrel"));
+
+        world.set_name(a, "barrel");
+        assert_eq!(world.find_by_name("crate").collect::<Vec<_>>(), [b]);
+        assert_eq!(world.find_by_name("barrel").collect::<Vec<_>>(), [c, a]);
+
+        world.despawn(c);
+        assert_eq!(world.find_by_name("barrel").collect::<Vec<_>>(), [a]);
+
+        world.remove::<Name>(b);
+        assert_eq!(world.name(b), None);
+        assert_eq!(world.find_by_name("crate").count(), 0);
+
+        let d = world.clone_entity(a).unwrap();
+        assert_eq!(world.find_by_name("barrel").collect::<Vec<_>>(), [a, d]);
+
+        let name = world.add_component::<Name>();
+        world.remove_component(name);
+        assert_eq!(world.find_by_name("barrel").count(), 0);
+    }
+}
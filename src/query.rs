@@ -1,18 +1,24 @@
 //! Type-level DSL for retrieving data from entities.
 
 use alloc::format;
+use alloc::vec::Vec;
+use core::any::TypeId;
 use core::marker::PhantomData;
 use core::ptr::NonNull;
-use core::{any, fmt};
+use core::{any, fmt, mem, slice};
 
 use evenio_macros::all_tuples;
 pub use evenio_macros::Query;
 
 use crate::access::{Access, ComponentAccessExpr};
 use crate::archetype::{Archetype, ArchetypeRow};
-use crate::assert::{AssertMutable, UnwrapDebugChecked};
+use crate::assert::{AssertMutable, AssertNotChunked, UnwrapDebugChecked};
+use crate::blob_vec::slot_in;
 use crate::component::{Component, ComponentIdx};
+#[cfg(feature = "diff")]
+use crate::diff::ChangeFlag;
 use crate::entity::EntityId;
+use crate::sparse::SparseIndex;
 use crate::system::{Config, InitError};
 use crate::world::World;
 
@@ -24,7 +30,9 @@ use crate::world::World;
 /// # Deriving
 ///
 /// This trait can be safely implemented using the `Query` derive macro. For a
-/// struct to derive `Query`, all fields must also implement `Query`.
+/// struct to derive `Query`, all fields must also implement `Query`. Derived
+/// queries can be nested in other derived queries, and the struct is also a
+/// [`ReadOnlyQuery`] when all of its fields are.
 ///
 /// ```
 /// # #[derive(Event)]
@@ -55,10 +63,113 @@ use crate::world::World;
 /// world.send(MyEvent);
 /// ```
 ///
+/// # Borrowing parts of components
+///
+/// Mutable queries like `&mut C` yield plain mutable references, so a
+/// component can be split into its fields and passed to helpers which only
+/// need one part of it.
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Transform {
+///     translation: [f32; 3],
+///     rotation: f32,
+/// }
+///
+/// fn translate(translation: &mut [f32; 3], by: [f32; 3]) {
+///     for (t, b) in translation.iter_mut().zip(by) {
+///         *t += b;
+///     }
+/// }
+///
+/// fn rotate(rotation: &mut f32, by: f32) {
+///     *rotation += by;
+/// }
+///
+/// #[derive(Event)]
+/// struct Tick;
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|_: Receiver<Tick>, f: Fetcher<&mut Transform>| {
+///     for transform in f {
+///         let Transform {
+///             translation,
+///             rotation,
+///         } = transform;
+///
+///         translate(translation, [1.0, 0.0, 0.0]);
+///         rotate(rotation, 0.5);
+///     }
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(
+///     e,
+///     Transform {
+///         translation: [0.0; 3],
+///         rotation: 0.0,
+///     },
+/// );
+///
+/// world.send(Tick);
+///
+/// let transform = world.get::<&Transform>(e).unwrap();
+/// assert_eq!(transform.translation, [1.0, 0.0, 0.0]);
+/// assert_eq!(transform.rotation, 0.5);
+/// ```
+///
+/// The [`ResMut`](crate::global::ResMut) wrapper for globals can be narrowed
+/// the same way with [`ResMut::map`](crate::global::ResMut::map).
+///
+/// # Access conflicts
+///
+/// A query must not be able to produce a mutable reference to a component
+/// along with any other reference to the same component of the same entity.
+/// This is checked when the query is initialized, so adding a system with a
+/// conflicting query panics.
+///
+/// Access which only happens for some entities, like the access of
+/// `Option<&mut A>` or of one branch of an [`Or`], counts as full access for
+/// this check. Both of these queries are rejected:
+///
+/// ```should_panic
+/// # use evenio::prelude::*;
+/// # #[derive(Event)]
+/// # struct MyEvent;
+/// # #[derive(Component)]
+/// # struct A;
+/// # let mut world = World::new();
+/// // Entities with `A` would get a `&mut A` and a `&A`.
+/// world.add_system(|_: Receiver<MyEvent>, _: Fetcher<(Option<&mut A>, &A)>| {});
+/// ```
+///
+/// ```should_panic
+/// # use evenio::prelude::*;
+/// # #[derive(Event)]
+/// # struct MyEvent;
+/// # #[derive(Component)]
+/// # struct A;
+/// # #[derive(Component)]
+/// # struct B;
+/// # let mut world = World::new();
+/// // Entities with `A` and `B` would match both branches.
+/// world.add_system(|_: Receiver<MyEvent>, _: Fetcher<Or<(&mut A, &B), &A>>| {});
+/// ```
+///
+/// Accesses that can never overlap are allowed, such as `(&mut A, Not<&A>)`
+/// or the two branches of an [`Xor`].
+///
 /// # Safety
 ///
 /// Implementors must ensure that [`Query::init`] correctly registers the data
 /// accessed in [`Query::get`].
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a query",
+    note = "if `{Self}` is a component, query it by reference with `&{Self}` or `&mut {Self}`"
+)]
 pub unsafe trait Query {
     /// The item returned by this query. This is usually the same type as
     /// `Self`, but with a modified lifetime.
@@ -78,6 +189,19 @@ pub unsafe trait Query {
     /// Returns a new [`Self::State`] instance.
     fn new_state(world: &mut World) -> Self::State;
 
+    /// Like [`init`](Self::init), but only uses components which are already
+    /// in the world and doesn't modify it. Used by [`World::get`] and
+    /// [`Fetcher::transmute`] to initialize queries without mutable access to
+    /// the world.
+    ///
+    /// Components which haven't been added to the world are treated as absent
+    /// from every archetype, so `&C` matches nothing while `Option<&C>` and
+    /// `Not<&C>` match everything. Returns `None` if the query has conflicting
+    /// access.
+    ///
+    /// [`Fetcher::transmute`]: crate::fetch::Fetcher::transmute
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)>;
+
     /// Returns a new [`Self::ArchState`] instance.
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState>;
 
@@ -92,6 +216,20 @@ pub unsafe trait Query {
     ///
     /// [`init`]: Self::init
     unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a>;
+
+    /// Hints that the query item at the given row will be accessed soon.
+    /// [`Iter`] calls this for rows ahead of the current one when
+    /// [prefetching] is enabled. The default implementation does nothing.
+    ///
+    /// # Safety
+    /// - `row` must be in bounds.
+    ///
+    /// [`Iter`]: crate::fetch::Iter
+    /// [prefetching]: crate::fetch::FetcherConfig::prefetch_distance
+    #[inline]
+    unsafe fn prefetch(state: &Self::ArchState, row: ArchetypeRow) {
+        let _ = (state, row);
+    }
 }
 
 /// Marker trait for queries which dot not access data mutably.
@@ -106,6 +244,55 @@ pub unsafe trait Query {
 /// [`Fetcher::get`]: crate::fetch::Fetcher::get
 pub unsafe trait ReadOnlyQuery: Query {}
 
+/// Queries whose items can be borrowed as whole columns of an archetype. Used
+/// by [`Fetcher::iter_slices`].
+///
+/// This is implemented for `&C`, `&mut C`, and tuples of sliceable queries.
+/// Queries which may be missing some of their data, like `Option<&C>` or
+/// `Not<&C>`, are not sliceable. Slicing a [chunked] component fails to
+/// compile, since chunked columns aren't contiguous.
+///
+/// ```compile_fail
+/// # #[derive(Event)] struct Tick;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(|_: Receiver<Tick>, f: Fetcher<Option<&Health>>| {
+///     // `Option<&Health>` can't be fetched as slices.
+///     for _ in f.iter_slices() {}
+/// });
+/// ```
+///
+/// # Safety
+///
+/// [`SliceQuery::get_slices`] must only access the data described by the
+/// [`ComponentAccessExpr`] returned by [`Query::init`].
+///
+/// [`Fetcher::iter_slices`]: crate::fetch::Fetcher::iter_slices
+/// [chunked]: Component::IS_CHUNKED
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be fetched as slices",
+    note = "only `&C`, `&mut C`, and tuples of them can be fetched as slices"
+)]
+pub unsafe trait SliceQuery: Query {
+    /// The slices of an archetype returned by this query.
+    type Slices<'a>;
+
+    /// Gets the slices covering the first `len` rows of the archetype.
+    ///
+    /// # Safety
+    /// - `len` must not exceed the number of entities in the archetype.
+    /// - Must have the appropriate component access permissions described by
+    ///   the [`ComponentAccessExpr`] returned by [`Query::init`].
+    /// - The lifetime of the slices is chosen by the caller. The slices must
+    ///   not outlive the data they reference.
+    unsafe fn get_slices<'a>(state: &Self::ArchState, len: usize) -> Self::Slices<'a>;
+}
+
 unsafe impl<C: Component> Query for &'_ C {
     type Item<'a> = &'a C;
 
@@ -128,21 +315,43 @@ unsafe impl<C: Component> Query for &'_ C {
         world.add_component::<C>().index()
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        Some(existing_component::<C>(world, Access::Read))
+    }
+
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
-        arch.column_of(*state).map(|c| ColumnPtr(c.data().cast()))
+        arch.column_of(*state).map(|c| {
+            debug_assert_eq!(c.is_chunked(), ColumnPtr::<C>::IS_CHUNKED);
+            ColumnPtr(c.base_ptr().cast())
+        })
     }
 
     unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
-        &*state.0.as_ptr().cast_const().add(row.0 as usize)
+        &*state.row(row)
+    }
+
+    #[inline]
+    unsafe fn prefetch(state: &Self::ArchState, row: ArchetypeRow) {
+        state.prefetch(row)
     }
 }
 
 unsafe impl<C: Component> ReadOnlyQuery for &'_ C {}
 
+unsafe impl<C: Component> SliceQuery for &'_ C {
+    type Slices<'a> = &'a [C];
+
+    unsafe fn get_slices<'a>(state: &Self::ArchState, len: usize) -> Self::Slices<'a> {
+        let () = AssertNotChunked::<C>::COMPONENT;
+
+        slice::from_raw_parts(state.0.as_ptr(), len)
+    }
+}
+
 unsafe impl<C: Component> Query for &'_ mut C {
     type Item<'a> = &'a mut C;
 
-    type ArchState = ColumnPtr<C>;
+    type ArchState = ColumnPtrMut<C>;
 
     type State = ComponentIdx;
 
@@ -163,12 +372,58 @@ unsafe impl<C: Component> Query for &'_ mut C {
         world.add_component::<C>().index()
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let () = AssertMutable::<C>::COMPONENT;
+
+        Some(existing_component::<C>(world, Access::ReadWrite))
+    }
+
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
-        <&C>::new_arch_state(arch, state)
+        arch.column_of(*state).map(|c| {
+            debug_assert_eq!(c.is_chunked(), ColumnPtr::<C>::IS_CHUNKED);
+            ColumnPtrMut {
+                ptr: ColumnPtr(c.base_ptr().cast()),
+                #[cfg(feature = "diff")]
+                changed: c.changes().flag(),
+            }
+        })
     }
 
     unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
-        &mut *state.0.as_ptr().add(row.0 as usize)
+        state.mark_changed();
+        &mut *state.ptr.row(row)
+    }
+
+    #[inline]
+    unsafe fn prefetch(state: &Self::ArchState, row: ArchetypeRow) {
+        state.ptr.prefetch(row)
+    }
+}
+
+/// Looks up `C` for [`Query::init_existing`]. If `C` hasn't been added to the
+/// world, the returned expression is `false` and the index is
+/// [`ComponentIdx::MAX`], which no archetype contains.
+fn existing_component<C: Component>(
+    world: &World,
+    access: Access,
+) -> (ComponentAccessExpr, ComponentIdx) {
+    match world.components().get_by_type_id(TypeId::of::<C>()) {
+        Some(info) => {
+            let idx = info.id().index();
+            (ComponentAccessExpr::with(idx, access), idx)
+        }
+        None => (ComponentAccessExpr::new(false), ComponentIdx::MAX),
+    }
+}
+
+unsafe impl<C: Component> SliceQuery for &'_ mut C {
+    type Slices<'a> = &'a mut [C];
+
+    unsafe fn get_slices<'a>(state: &Self::ArchState, len: usize) -> Self::Slices<'a> {
+        let () = AssertNotChunked::<C>::COMPONENT;
+
+        state.mark_changed();
+        slice::from_raw_parts_mut(state.ptr.0.as_ptr(), len)
     }
 }
 
@@ -216,6 +471,18 @@ macro_rules! impl_query_tuple {
                 )
             }
 
+            fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+                #[allow(unused_mut)]
+                let mut res = ComponentAccessExpr::new(true);
+
+                $(
+                    let (expr, $q) = $Q::init_existing(world)?;
+                    res = res.and(&expr).ok()?;
+                )*
+
+                Some((res, ($($q,)*)))
+            }
+
             fn new_arch_state(arch: &Archetype, ($($q,)*): &mut Self::State) -> Option<Self::ArchState> {
                 Some((
                     $(
@@ -231,6 +498,13 @@ macro_rules! impl_query_tuple {
                     )*
                 )
             }
+
+            #[inline]
+            unsafe fn prefetch(($($q,)*): &Self::ArchState, row: ArchetypeRow) {
+                $(
+                    $Q::prefetch($q, row);
+                )*
+            }
         }
 
         unsafe impl<$($Q: ReadOnlyQuery),*> ReadOnlyQuery for ($($Q,)*) {}
@@ -240,6 +514,25 @@ macro_rules! impl_query_tuple {
 // Currently, debug impls for tuples only go up to arity 12.
 all_tuples!(impl_query_tuple, 0, 12, Q, q);
 
+macro_rules! impl_slice_query_tuple {
+    ($(($Q:ident, $q:ident)),*) => {
+        #[allow(unused_variables, clippy::unused_unit)]
+        unsafe impl<$($Q: SliceQuery),*> SliceQuery for ($($Q,)*) {
+            type Slices<'a> = ($($Q::Slices<'a>,)*);
+
+            unsafe fn get_slices<'a>(($($q,)*): &Self::ArchState, len: usize) -> Self::Slices<'a> {
+                (
+                    $(
+                        $Q::get_slices($q, len),
+                    )*
+                )
+            }
+        }
+    }
+}
+
+all_tuples!(impl_slice_query_tuple, 0, 12, Q, q);
+
 /// Returns the result of `Q` as `Some`, or `None` if `Q` does not match.
 unsafe impl<Q: Query> Query for Option<Q> {
     type Item<'a> = Option<Q::Item<'a>>;
@@ -263,6 +556,12 @@ unsafe impl<Q: Query> Query for Option<Q> {
         Q::new_state(world)
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let (expr, state) = Q::init_existing(world)?;
+
+        Some((expr.or(&ComponentAccessExpr::new(true)).unwrap(), state))
+    }
+
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
         Some(Q::new_arch_state(arch, state))
     }
@@ -270,6 +569,13 @@ unsafe impl<Q: Query> Query for Option<Q> {
     unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
         state.as_ref().map(|f| Q::get(f, row))
     }
+
+    #[inline]
+    unsafe fn prefetch(state: &Self::ArchState, row: ArchetypeRow) {
+        if let Some(state) = state {
+            Q::prefetch(state, row);
+        }
+    }
 }
 
 unsafe impl<Q: ReadOnlyQuery> ReadOnlyQuery for Option<Q> {}
@@ -354,6 +660,13 @@ where
         (L::new_state(world), R::new_state(world))
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let (left_expr, left_state) = L::init_existing(world)?;
+        let (right_expr, right_state) = R::init_existing(world)?;
+
+        Some((left_expr.or(&right_expr).ok()?, (left_state, right_state)))
+    }
+
     fn new_arch_state(
         arch: &Archetype,
         (left_state, right_state): &mut Self::State,
@@ -445,6 +758,13 @@ where
         (L::new_state(world), R::new_state(world))
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let (left_expr, left_state) = L::init_existing(world)?;
+        let (right_expr, right_state) = R::init_existing(world)?;
+
+        Some((left_expr.xor(&right_expr), (left_state, right_state)))
+    }
+
     fn new_arch_state(
         arch: &Archetype,
         (left_state, right_state): &mut Self::State,
@@ -522,6 +842,12 @@ unsafe impl<Q: Query> Query for Not<Q> {
         Q::new_state(world)
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let (expr, state) = Q::init_existing(world)?;
+
+        Some((expr.not(), state))
+    }
+
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
         match Q::new_arch_state(arch, state) {
             Some(_) => None,
@@ -594,6 +920,14 @@ unsafe impl<Q: Query> Query for With<Q> {
         Q::new_state(world)
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let (mut expr, state) = Q::init_existing(world)?;
+
+        expr.access.clear();
+
+        Some((expr, state))
+    }
+
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
         Q::new_arch_state(arch, state).map(|_| ())
     }
@@ -605,6 +939,82 @@ unsafe impl<Q: Query> Query for With<Q> {
 
 unsafe impl<Q: Query> ReadOnlyQuery for With<Q> {}
 
+/// A [`Query`] which matches if query `Q` doesn't match.
+///
+/// This is the opposite of [`With`], and like `With`, it does not require
+/// access to the data returned by `Q`. `Without<Q>` matches the same entities
+/// as [`Not<Q>`].
+///
+/// Example: `Without<&C>` matches all entities which don't have component `C`.
+pub struct Without<Q>(PhantomData<fn() -> Q>);
+
+impl<Q> Without<Q> {
+    /// Create a new instance.
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<Q> Clone for Without<Q> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Q> Copy for Without<Q> {}
+
+impl<Q> Default for Without<Q> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<Q> fmt::Debug for Without<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Without").finish()
+    }
+}
+
+unsafe impl<Q: Query> Query for Without<Q> {
+    type Item<'a> = Self;
+
+    type ArchState = ();
+
+    type State = Q::State;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        let (expr, state) = Q::init(world, config)?;
+
+        Ok((expr.not(), state))
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        Q::new_state(world)
+    }
+
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let (expr, state) = Q::init_existing(world)?;
+
+        Some((expr.not(), state))
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        match Q::new_arch_state(arch, state) {
+            Some(_) => None,
+            None => Some(()),
+        }
+    }
+
+    unsafe fn get<'a>(_state: &Self::ArchState, _row: ArchetypeRow) -> Self::Item<'a> {
+        Without::new()
+    }
+}
+
+unsafe impl<Q: Query> ReadOnlyQuery for Without<Q> {}
+
 /// A [`Query`] which returns a boolean indicating whether the query `Q`
 /// matches.
 ///
@@ -672,6 +1082,12 @@ unsafe impl<Q: Query> Query for Has<Q> {
         Q::new_state(world)
     }
 
+    fn init_existing(world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        let (_, state) = Q::init_existing(world)?;
+
+        Some((ComponentAccessExpr::new(true), state))
+    }
+
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
         Some(Q::new_arch_state(arch, state).is_some())
     }
@@ -700,6 +1116,10 @@ unsafe impl Query for EntityId {
 
     fn new_state(_world: &mut World) -> Self::State {}
 
+    fn init_existing(_world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        Some((ComponentAccessExpr::new(true), ()))
+    }
+
     fn new_arch_state(arch: &Archetype, (): &mut Self::State) -> Option<Self::ArchState> {
         Some(ColumnPtr(unsafe {
             NonNull::new(arch.entity_ids().as_ptr().cast_mut()).unwrap_debug_checked()
@@ -730,6 +1150,10 @@ unsafe impl<T: ?Sized> Query for PhantomData<T> {
 
     fn new_state(_world: &mut World) -> Self::State {}
 
+    fn init_existing(_world: &World) -> Option<(ComponentAccessExpr, Self::State)> {
+        Some((ComponentAccessExpr::new(true), ()))
+    }
+
     fn new_arch_state(_arch: &Archetype, _state: &mut Self::State) -> Option<Self::ArchState> {
         Some(())
     }
@@ -741,11 +1165,169 @@ unsafe impl<T: ?Sized> Query for PhantomData<T> {
 
 unsafe impl<T: ?Sized> ReadOnlyQuery for PhantomData<T> {}
 
+/// A query built at runtime from a list of [`ComponentIdx`]s.
+///
+/// This is useful when component types aren't known at compile time, such
+/// as in scripting bindings. Components are fetched as type-erased pointers
+/// in the order they were added with [`read`] and [`write`]. See
+/// [`World::query_dynamic`] and [`DynamicFetcherState`] for ways to run the
+/// query.
+///
+/// Access is checked the same way as static queries, so writing to a
+/// component will conflict with a `&mut C` in the same system.
+///
+/// [`read`]: Self::read
+/// [`write`]: Self::write
+/// [`DynamicFetcherState`]: crate::fetch::DynamicFetcherState
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::query::DynamicQuery;
+///
+/// #[derive(Component)]
+/// struct Health(f32);
+///
+/// #[derive(Component)]
+/// struct Dead;
+///
+/// let mut world = World::new();
+///
+/// let health = world.add_component::<Health>().index();
+/// let dead = world.add_component::<Dead>().index();
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(10.0));
+///
+/// let query = DynamicQuery::new().write(health).without(dead);
+///
+/// world
+///     .query_dynamic(&query, |entity, ptrs| {
+///         assert_eq!(entity, e);
+///         unsafe { ptrs[0].cast::<Health>().as_mut().0 -= 1.0 };
+///     })
+///     .unwrap();
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct DynamicQuery {
+    fetched: Vec<(ComponentIdx, Access)>,
+    filters: Vec<(ComponentIdx, bool)>,
+}
+
+impl DynamicQuery {
+    /// Creates a new dynamic query which matches every entity and fetches
+    /// nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetches the given component immutably.
+    #[must_use]
+    pub fn read(mut self, component: ComponentIdx) -> Self {
+        self.fetched.push((component, Access::Read));
+        self
+    }
+
+    /// Fetches the given component mutably.
+    #[must_use]
+    pub fn write(mut self, component: ComponentIdx) -> Self {
+        self.fetched.push((component, Access::ReadWrite));
+        self
+    }
+
+    /// Only matches entities with the given component, without accessing it.
+    #[must_use]
+    pub fn with(mut self, component: ComponentIdx) -> Self {
+        self.filters.push((component, true));
+        self
+    }
+
+    /// Only matches entities without the given component.
+    #[must_use]
+    pub fn without(mut self, component: ComponentIdx) -> Self {
+        self.filters.push((component, false));
+        self
+    }
+
+    /// Returns the fetched components in the order they were added.
+    pub fn fetched(&self) -> impl Iterator<Item = ComponentIdx> + '_ {
+        self.fetched.iter().map(|&(idx, _)| idx)
+    }
+
+    /// Returns the components fetched mutably.
+    #[cfg(feature = "diff")]
+    pub(crate) fn written(&self) -> impl Iterator<Item = ComponentIdx> + '_ {
+        self.fetched
+            .iter()
+            .filter(|&&(_, access)| access == Access::ReadWrite)
+            .map(|&(idx, _)| idx)
+    }
+
+    /// Returns an expression describing the components accessed by this
+    /// query. Like [`Query::init`], the referenced components are added to the
+    /// `config`.
+    pub fn init(
+        &self,
+        world: &World,
+        config: &mut Config,
+    ) -> Result<ComponentAccessExpr, InitError> {
+        let mut res = ComponentAccessExpr::new(true);
+
+        let filters = self
+            .filters
+            .iter()
+            .map(|&(idx, with)| (idx, with, Access::None));
+        let fetched = self
+            .fetched
+            .iter()
+            .map(|&(idx, access)| (idx, true, access));
+
+        for (idx, with, access) in fetched.chain(filters) {
+            let Some(info) = world.components().get_by_index(idx) else {
+                return Err(InitError(
+                    format!("dynamic query references nonexistent component {idx:?}").into(),
+                ));
+            };
+
+            if access == Access::ReadWrite && info.is_immutable() {
+                return Err(InitError(
+                    format!(
+                        "dynamic query cannot access immutable component `{}` mutably",
+                        info.name()
+                    )
+                    .into(),
+                ));
+            }
+
+            config.referenced_components.insert(idx);
+
+            let expr = if with {
+                ComponentAccessExpr::with(idx, access)
+            } else {
+                ComponentAccessExpr::without(idx)
+            };
+
+            res = res.and(&expr).map_err(|_| {
+                InitError(
+                    format!(
+                        "dynamic query has conflicting access to component `{}`",
+                        info.name()
+                    )
+                    .into(),
+                )
+            })?;
+        }
+
+        Ok(res)
+    }
+}
+
 /// Wrapper for `NonNull` which implements [`Send`] and [`Sync`]
 /// unconditionally.
 #[doc(hidden)]
 #[repr(transparent)]
-pub struct ColumnPtr<T>(NonNull<T>);
+pub struct ColumnPtr<T>(pub(crate) NonNull<T>);
 
 impl<T> Clone for ColumnPtr<T> {
     fn clone(&self) -> Self {
@@ -755,6 +1337,119 @@ impl<T> Clone for ColumnPtr<T> {
 
 impl<T> Copy for ColumnPtr<T> {}
 
+impl<C: Component> ColumnPtr<C> {
+    /// Whether columns of `C` are chunked. Zero-sized components never are.
+    const IS_CHUNKED: bool = C::IS_CHUNKED && mem::size_of::<C>() != 0;
+
+    /// Returns a pointer to the component in `row` of the column this points
+    /// into.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be in bounds.
+    #[inline]
+    unsafe fn row(self, row: ArchetypeRow) -> *mut C {
+        slot_in(
+            self.0.cast(),
+            mem::size_of::<C>(),
+            Self::IS_CHUNKED,
+            row.0 as usize,
+        )
+        .cast()
+    }
+
+    /// Prefetches the component in `row` of the column this points into.
+    /// Zero-sized components aren't stored anywhere, so they're skipped.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be in bounds.
+    #[inline]
+    unsafe fn prefetch(self, row: ArchetypeRow) {
+        if mem::size_of::<C>() != 0 {
+            prefetch_read(self.row(row));
+        }
+    }
+}
+
+/// Whether [`prefetch_read`] does anything on the target. Prefetching is
+/// skipped entirely on other targets.
+pub(crate) const PREFETCH_SUPPORTED: bool = cfg!(any(
+    target_arch = "x86_64",
+    all(target_arch = "x86", target_feature = "sse"),
+    target_arch = "aarch64"
+));
+
+/// Hints to the CPU that the cache line holding `ptr` will be read soon. This
+/// never faults, even if `ptr` is dangling.
+#[inline(always)]
+fn prefetch_read<T>(ptr: *const T) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr.cast());
+    }
+
+    #[cfg(all(target_arch = "x86", target_feature = "sse"))]
+    unsafe {
+        use core::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr.cast());
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        core::arch::asm!(
+            "prfm pldl1keep, [{ptr}]",
+            ptr = in(reg) ptr,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "x86", target_feature = "sse"),
+        target_arch = "aarch64"
+    )))]
+    let _ = ptr;
+}
+
+/// The archetype state of `&mut C`. With the `diff` feature, fetching a
+/// component also marks the column as changed.
+#[doc(hidden)]
+pub struct ColumnPtrMut<C> {
+    ptr: ColumnPtr<C>,
+    #[cfg(feature = "diff")]
+    changed: ChangeFlag,
+}
+
+impl<C> Clone for ColumnPtrMut<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for ColumnPtrMut<C> {}
+
+impl<C> ColumnPtrMut<C> {
+    /// Marks the column as changed for the `diff` module.
+    ///
+    /// # Safety
+    ///
+    /// The state must be up to date with the archetype.
+    #[inline]
+    #[cfg_attr(not(feature = "diff"), allow(clippy::unused_self))]
+    unsafe fn mark_changed(self) {
+        #[cfg(feature = "diff")]
+        self.changed.mark();
+    }
+}
+
+impl<C> fmt::Debug for ColumnPtrMut<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ColumnPtrMut").field(&self.ptr).finish()
+    }
+}
+
 impl<T> fmt::Debug for ColumnPtr<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("ColumnPtr").field(&self.0).finish()
@@ -808,6 +1503,13 @@ mod tests {
     #[derive(Component)]
     struct C;
 
+    #[derive(Query)]
+    #[allow(dead_code)]
+    struct OptionalAlias<'a> {
+        a: Option<&'a mut A>,
+        b: &'a A,
+    }
+
     t!(t00, true, &mut A);
     t!(t01, true, (&mut A, &mut B));
     t!(t02, false, (&mut A, &mut A));
@@ -825,6 +1527,69 @@ mod tests {
     t!(t14, true, (Option<&A>, &A, &A));
     t!(t15, false, (Xor<(&A, &B), (&B, &C)>, &mut B));
     t!(t16, true, (Xor<(&A, &B), (&B, &C)>, &B));
+    t!(t17, true, (&mut A, With<&A>));
+    t!(t18, true, (&mut A, With<&mut A>, Without<&mut A>));
+    t!(t19, true, (Or<With<&A>, Without<&B>>, &mut A, &mut B));
+    t!(t20, false, (Or<With<&A>, &mut B>, &B));
+    t!(t21, false, (Option<&mut A>, &A));
+    t!(t22, false, (&A, Option<&mut A>));
+    t!(t23, false, (Option<&mut A>, Option<&A>));
+    t!(t24, false, (Option<(&mut A, &B)>, (&A, &C)));
+    t!(t25, false, (Or<&mut A, &B>, &A));
+    t!(t26, false, (Option<Or<&mut A, &B>>, &A));
+    t!(t27, false, Or<(&mut A, &B), &A>);
+    t!(t28, true, (Option<&mut A>, Has<&A>));
+    t!(t29, false, OptionalAlias);
+
+    #[test]
+    fn with_and_without_do_not_conflict() {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<E>, _: Fetcher<&mut A>, _: Fetcher<(With<&A>, &B)>| {});
+        world.add_system(|_: Receiver<E>, _: Fetcher<&mut B>, _: Fetcher<(Without<&B>, &A)>| {});
+
+        world.send(E);
+    }
+
+    #[test]
+    fn with_and_without_filter_archetypes() {
+        type WithBOrWithoutA = Or<With<&'static B>, Without<&'static A>>;
+
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.insert(a, A);
+
+        let ab = world.spawn();
+        world.insert(ab, A);
+        world.insert(ab, B);
+
+        let b = world.spawn();
+        world.insert(b, B);
+
+        world.add_system(
+            move |_: Receiver<E>,
+                  with: Fetcher<(EntityId, With<&A>)>,
+                  without: Fetcher<(EntityId, Without<&A>)>,
+                  either: Fetcher<(EntityId, WithBOrWithoutA)>| {
+                let mut with: Vec<_> = with.iter().map(|(e, _)| e).collect();
+                with.sort();
+                let mut expected = vec![a, ab];
+                expected.sort();
+                assert_eq!(with, expected);
+
+                assert_eq!(without.iter().map(|(e, _)| e).collect::<Vec<_>>(), [b]);
+
+                let mut either: Vec<_> = either.iter().map(|(e, _)| e).collect();
+                either.sort();
+                let mut expected = vec![ab, b];
+                expected.sort();
+                assert_eq!(either, expected);
+            },
+        );
+
+        world.send(E);
+    }
 
     #[test]
     fn derived_query() {
@@ -853,11 +1618,174 @@ mod tests {
         #[derive(Query)]
         struct TupleStructQuery<'a>(&'a A, &'a mut B);
 
+        #[derive(Query)]
+        struct FilterQuery<'a> {
+            foo: &'a mut A,
+            with_foo: With<&'a A>,
+            without_bar: Without<&'a B>,
+        }
+
         assert_read_only_query::<UnitQuery>();
         assert_read_only_query::<QueryWithLifetime>();
         assert_read_only_query::<QueryWithTwoLifetimes>();
         assert_read_only_query::<QueryWithTypeParam<()>>();
+        assert_read_only_query::<(With<&mut A>, Without<&mut B>)>();
 
         fn assert_read_only_query<Q: ReadOnlyQuery>() {}
     }
+
+    #[test]
+    fn nested_derived_query() {
+        #[derive(Component)]
+        struct Pos(u32);
+
+        #[derive(Component)]
+        struct Vel(u32);
+
+        #[derive(Query)]
+        struct Motion<'a> {
+            pos: &'a mut Pos,
+            vel: &'a Vel,
+        }
+
+        #[derive(Query)]
+        struct Body<'a> {
+            id: EntityId,
+            motion: Motion<'a>,
+            tag: Option<&'a A>,
+        }
+
+        #[derive(Query)]
+        struct ReadOnlyBody<'a>(&'a Pos, Has<&'static A>);
+
+        #[derive(Event)]
+        struct Step;
+
+        let mut world = World::new();
+
+        for i in 0..3 {
+            let e = world.spawn();
+            world.insert(e, Pos(i));
+            world.insert(e, Vel(10));
+            if i == 0 {
+                world.insert(e, A);
+            }
+        }
+
+        world.add_system(|_: Receiver<Step>, f: Fetcher<Body>| {
+            for body in f {
+                assert_ne!(body.id, EntityId::NULL);
+                body.motion.pos.0 += body.motion.vel.0;
+                if body.tag.is_some() {
+                    body.motion.pos.0 += 100;
+                }
+            }
+        });
+
+        world.add_system(|_: Receiver<Step>, f: Fetcher<ReadOnlyBody>| {
+            let mut sum = 0;
+            for ReadOnlyBody(pos, has_a) in &f {
+                sum += pos.0;
+                assert_eq!(has_a.get(), pos.0 >= 100);
+            }
+            assert_eq!(sum, 110 + 11 + 12);
+        });
+
+        world.send(Step);
+
+        assert!(check_query::<Body>());
+        assert!(!check_query::<(Body, &mut Vel)>());
+        assert!(!check_query::<(Motion, Option<&mut Pos>)>());
+    }
+
+    #[test]
+    fn derived_query_with_many_fields() {
+        #[derive(Component)]
+        struct C0(u32);
+        #[derive(Component)]
+        struct C1(u32);
+        #[derive(Component)]
+        struct C2(u32);
+        #[derive(Component)]
+        struct C3(u32);
+        #[derive(Component)]
+        struct C4(u32);
+        #[derive(Component)]
+        struct C5(u32);
+        #[derive(Component)]
+        struct C6(u32);
+        #[derive(Component)]
+        struct C7(u32);
+        #[derive(Component)]
+        struct C8(u32);
+        #[derive(Component)]
+        struct C9(u32);
+        #[derive(Component)]
+        struct C10(u32);
+        #[derive(Component)]
+        struct C11(u32);
+        #[derive(Component)]
+        struct C12(u32);
+        #[derive(Component)]
+        struct C13(u32);
+
+        #[derive(Query)]
+        struct Wide<'a> {
+            c0: &'a C0,
+            c1: &'a C1,
+            c2: &'a C2,
+            c3: &'a C3,
+            c4: &'a C4,
+            c5: &'a C5,
+            c6: &'a C6,
+            c7: &'a C7,
+            c8: &'a C8,
+            c9: &'a C9,
+            c10: &'a C10,
+            c11: &'a C11,
+            c12: &'a C12,
+            c13: &'a mut C13,
+        }
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, C0(0));
+        world.insert(e, C1(1));
+        world.insert(e, C2(2));
+        world.insert(e, C3(3));
+        world.insert(e, C4(4));
+        world.insert(e, C5(5));
+        world.insert(e, C6(6));
+        world.insert(e, C7(7));
+        world.insert(e, C8(8));
+        world.insert(e, C9(9));
+        world.insert(e, C10(10));
+        world.insert(e, C11(11));
+        world.insert(e, C12(12));
+        world.insert(e, C13(0));
+
+        world.add_system(|_: Receiver<E>, f: Fetcher<Wide>| {
+            for w in f {
+                w.c13.0 = w.c0.0
+                    + w.c1.0
+                    + w.c2.0
+                    + w.c3.0
+                    + w.c4.0
+                    + w.c5.0
+                    + w.c6.0
+                    + w.c7.0
+                    + w.c8.0
+                    + w.c9.0
+                    + w.c10.0
+                    + w.c11.0
+                    + w.c12.0;
+            }
+        });
+
+        world.send(E);
+
+        assert_eq!(world.get_component::<C13>(e).unwrap().0, 78);
+        assert!(!check_query::<(Wide, &C13)>());
+    }
 }
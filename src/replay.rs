@@ -0,0 +1,469 @@
+//! Recording and replaying the events sent to a [`World`].
+//!
+//! [`World::start_recording`] captures every event in the order it's
+//! dispatched, including the events sent by systems. The resulting
+//! [`EventLog`] can be [replayed](EventLog::replay) on a world set up the
+//! same way to reproduce a session. Only the root events, which were sent
+//! from outside of any system, are sent again. The rest are expected to be
+//! sent by the systems in the same order as before, which can be checked by
+//! recording the replay and comparing the logs.
+//!
+//! This module is only available with the `replay` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::prelude::*;
+//!
+//! #[derive(Event, Clone)]
+//! struct Ping(u32);
+//!
+//! #[derive(Event, Clone)]
+//! struct Pong(u32);
+//!
+//! fn setup() -> World {
+//!     let mut world = World::new();
+//!
+//!     world.add_system(|r: Receiver<Ping>, mut s: Sender<Pong>| s.send(Pong(r.event.0 + 1)));
+//!
+//!     world
+//! }
+//!
+//! let mut world = setup();
+//!
+//! world.start_recording();
+//! world.send(Ping(1));
+//! world.send(Ping(2));
+//! let log = world.stop_recording();
+//!
+//! // Each `Ping` is followed by the `Pong` it caused.
+//! assert_eq!(log.len(), 4);
+//! assert_eq!(log.iter().filter(|r| r.is_root()).count(), 2);
+//!
+//! let mut copy = setup();
+//!
+//! copy.start_recording();
+//! assert!(log.replay(&mut copy).is_empty());
+//!
+//! assert_eq!(copy.stop_recording(), log);
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::any::TypeId;
+use core::fmt;
+use core::ptr::NonNull;
+
+use bumpalo::Bump;
+
+use crate::component::CloneFn;
+use crate::drop::DropFn;
+use crate::entity::EntityId;
+use crate::event::{EventInfo, EventKind, EventMeta, Spawn};
+use crate::world::World;
+
+/// The events recorded by [`World::start_recording`], in the order they
+/// were dispatched.
+///
+/// Two logs are equal if they have the same events in the same order. See
+/// [`RecordedEvent`] for how events are compared.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    records: Vec<RecordedEvent>,
+    /// Storage for the copies of the events.
+    bump: Bump,
+}
+
+impl EventLog {
+    /// Returns the number of recorded events.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns `true` if no events were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns an iterator over the recorded events in dispatch order.
+    pub fn iter(&self) -> impl Iterator<Item = &RecordedEvent> {
+        self.records.iter()
+    }
+
+    /// Sends the root events of the log to `world` in the order they were
+    /// recorded.
+    ///
+    /// Each event is matched with the event in `world` which has the same
+    /// [`TypeId`], or the same name and layout for events without one. Root
+    /// events which can't be sent are skipped and reported in the returned
+    /// list of warnings.
+    pub fn replay(&self, world: &mut World) -> Vec<ReplayWarning> {
+        let mut warnings = Vec::new();
+
+        for (index, record) in self.records.iter().enumerate() {
+            if !record.is_root {
+                continue;
+            }
+
+            let (ptr, clone) = match record.data {
+                RecordedData::Copied { ptr, clone, .. } => (ptr, clone),
+                RecordedData::Spawn => {
+                    world.spawn();
+                    continue;
+                }
+                RecordedData::Opaque => {
+                    warnings.push(ReplayWarning::Opaque { index });
+                    continue;
+                }
+            };
+
+            let info = match record.type_id {
+                Some(type_id) => world.events().get_by_type_id(type_id),
+                None => world.events().get_by_name(&record.name).ok(),
+            };
+
+            let Some(idx) = info
+                .filter(|info| {
+                    info.layout() == record.layout
+                        && info.id().is_targeted() == record.target.is_some()
+                })
+                .map(|info| info.id().index())
+            else {
+                warnings.push(ReplayWarning::NoSuchEvent { index });
+                continue;
+            };
+
+            unsafe {
+                world.send_cloned(
+                    idx,
+                    record.target.unwrap_or(EntityId::NULL),
+                    ptr.as_ptr(),
+                    clone,
+                )
+            };
+        }
+
+        warnings
+    }
+}
+
+impl PartialEq for EventLog {
+    fn eq(&self, other: &Self) -> bool {
+        self.records == other.records
+    }
+}
+
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        for record in &self.records {
+            if let RecordedData::Copied {
+                ptr,
+                drop: Some(drop),
+                ..
+            } = record.data
+            {
+                unsafe { drop(ptr) };
+            }
+        }
+    }
+}
+
+// SAFETY: Events are `Send` and `Sync`, and the log has sole ownership of its
+// copies.
+unsafe impl Send for EventLog {}
+unsafe impl Sync for EventLog {}
+
+/// An event in an [`EventLog`].
+///
+/// Recorded events are compared by their name, [`TypeId`], target, and
+/// whether they're a root and could be copied. Their data and [`EventId`]
+/// are ignored so that logs recorded in different worlds can be compared.
+///
+/// [`EventId`]: crate::event::EventId
+#[derive(Debug)]
+pub struct RecordedEvent {
+    name: Cow<'static, str>,
+    type_id: Option<TypeId>,
+    layout: Layout,
+    target: Option<EntityId>,
+    is_root: bool,
+    data: RecordedData,
+}
+
+impl RecordedEvent {
+    /// Returns the name of the event.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the [`TypeId`] of the event, if it has one.
+    pub fn type_id(&self) -> Option<TypeId> {
+        self.type_id
+    }
+
+    /// Returns the target the event was sent to, or `None` if the event is
+    /// untargeted.
+    pub fn target(&self) -> Option<EntityId> {
+        self.target
+    }
+
+    /// Returns `true` if the event was sent from outside of any system and
+    /// will be sent again by [`EventLog::replay`].
+    pub fn is_root(&self) -> bool {
+        self.is_root
+    }
+
+    /// Returns `true` if the event was recorded without its data because it
+    /// has no [`CloneFn`]. Opaque root events can't be replayed.
+    pub fn is_opaque(&self) -> bool {
+        matches!(self.data, RecordedData::Opaque)
+    }
+}
+
+impl PartialEq for RecordedEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.type_id == other.type_id
+            && self.target == other.target
+            && self.is_root == other.is_root
+            && self.is_opaque() == other.is_opaque()
+    }
+}
+
+#[derive(Debug)]
+enum RecordedData {
+    /// A copy of the event, owned by the log.
+    Copied {
+        ptr: NonNull<u8>,
+        drop: DropFn,
+        clone: CloneFn,
+    },
+    /// The internal event which spawns an entity reserved with
+    /// [`World::spawn`]. It's replayed by spawning another entity.
+    Spawn,
+    /// An event without a [`CloneFn`].
+    Opaque,
+}
+
+/// A problem encountered by [`EventLog::replay`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReplayWarning {
+    /// The root event at this index of the log was recorded without its
+    /// data, so it wasn't sent.
+    Opaque {
+        /// The index of the event in the log.
+        index: usize,
+    },
+    /// No event in the world matches the root event at this index of the log,
+    /// so it wasn't sent.
+    NoSuchEvent {
+        /// The index of the event in the log.
+        index: usize,
+    },
+}
+
+impl fmt::Display for ReplayWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayWarning::Opaque { index } => {
+                write!(
+                    f,
+                    "event {index} of the log can't be copied and was skipped"
+                )
+            }
+            ReplayWarning::NoSuchEvent { index } => {
+                write!(f, "event {index} of the log doesn't exist in the world")
+            }
+        }
+    }
+}
+
+/// Records events as they're dispatched by a [`World`].
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    log: EventLog,
+    /// The number of nested calls handling events.
+    depth: u32,
+    /// Whether the events handled by the outermost call are roots. This is
+    /// `false` for deferred events sent by systems.
+    top_level_is_root: bool,
+    /// The number of deferred root events which haven't been handled yet.
+    deferred_roots: usize,
+    /// Whether the last event recorded was the internal spawn event.
+    after_spawn: bool,
+}
+
+impl Recorder {
+    /// Called when the world starts handling the events sent from outside of
+    /// any system, of which `deferred` are deferred.
+    pub(crate) fn begin_flush(&mut self, deferred: usize) {
+        self.depth = 0;
+        self.top_level_is_root = true;
+        self.deferred_roots = deferred;
+    }
+
+    /// Called before the world handles the next deferred event.
+    pub(crate) fn next_deferred(&mut self) {
+        self.top_level_is_root = self.deferred_roots > 0;
+        self.deferred_roots = self.deferred_roots.saturating_sub(1);
+    }
+
+    pub(crate) fn enter(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn exit(&mut self) {
+        // Recording may have started partway through handling an event.
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Records the event at `event`, which is about to be dispatched.
+    ///
+    /// # Safety
+    ///
+    /// `event` must point to a valid instance of the event described by
+    /// `info`.
+    pub(crate) unsafe fn record(&mut self, info: &EventInfo, meta: EventMeta, event: *const u8) {
+        let data = if matches!(info.kind(), EventKind::SpawnQueued) {
+            RecordedData::Spawn
+        } else if let Some(clone) = info.clone_fn() {
+            let ptr = self.log.bump.alloc_layout(info.layout());
+            clone(event, ptr.as_ptr());
+
+            RecordedData::Copied {
+                ptr,
+                drop: info.drop(),
+                clone,
+            }
+        } else {
+            RecordedData::Opaque
+        };
+
+        let target = match meta {
+            EventMeta::Untargeted { .. } => None,
+            EventMeta::Targeted { target, .. } => Some(target),
+        };
+
+        // `World::spawn` sends `Spawn` right after the internal spawn event, and
+        // does so again when the spawn is replayed.
+        let is_spawn_notice = self.after_spawn && info.type_id() == Some(TypeId::of::<Spawn>());
+        self.after_spawn = matches!(data, RecordedData::Spawn);
+
+        self.log.records.push(RecordedEvent {
+            name: info.name_cow(),
+            type_id: info.type_id(),
+            layout: info.layout(),
+            target,
+            is_root: self.depth == 1 && self.top_level_is_root && !is_spawn_notice,
+            data,
+        });
+    }
+
+    pub(crate) fn finish(self) -> EventLog {
+        self.log
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::ReplayWarning;
+    use crate::prelude::*;
+
+    #[derive(Event, Clone)]
+    struct Tick;
+
+    #[derive(Event, Clone)]
+    #[event(deferred)]
+    struct Later(#[allow(dead_code)] u32);
+
+    #[derive(Event, Clone)]
+    struct Hit(#[event(target)] EntityId);
+
+    #[derive(Event)]
+    struct Opaque;
+
+    #[derive(Component, Clone)]
+    struct Health(u32);
+
+    fn setup() -> World {
+        let mut world = World::new();
+
+        world.add_system(|_: Receiver<Tick>, mut s: Sender<Later>| s.send(Later(1)));
+
+        world.add_system(|r: Receiver<Hit, &Health>, mut s: Sender<Insert<Health>>| {
+            let (target, health) = (r.event.0, r.query.0);
+            s.insert(target, Health(health - 1));
+        });
+
+        world.add_system(|_: Receiver<Opaque>| {});
+
+        world
+    }
+
+    fn session(world: &mut World) -> EntityId {
+        let e = world.spawn();
+        world.insert(e, Health(10));
+        world.send(Later(0));
+        world.send(Tick);
+        world.send(Hit(e));
+        e
+    }
+
+    #[test]
+    fn replay_matches_recording() {
+        let mut world = setup();
+
+        world.start_recording();
+        let e = session(&mut world);
+        let log = world.stop_recording();
+
+        let roots = log.iter().filter(|r| r.is_root()).count();
+        // The spawn, `Insert`, `Later(0)`, `Tick`, and `Hit`. `Spawn` is sent
+        // by the spawn.
+        assert_eq!(roots, 5);
+
+        // The nested deferred `Later(1)` isn't a root.
+        let later = log
+            .iter()
+            .filter(|r| r.name().ends_with("Later"))
+            .map(|r| r.is_root())
+            .collect::<Vec<_>>();
+        assert_eq!(later, [true, false]);
+
+        let mut copy = setup();
+
+        copy.start_recording();
+        assert_eq!(log.replay(&mut copy), vec![]);
+        assert_eq!(copy.stop_recording(), log);
+
+        // Entities are spawned in the same order, so they have the same IDs.
+        assert_eq!(copy.get_component::<Health>(e).unwrap().0, 9);
+    }
+
+    #[test]
+    fn opaque_events_warn() {
+        let mut world = setup();
+
+        world.start_recording();
+        world.send(Opaque);
+        world.send(Tick);
+        let log = world.stop_recording();
+
+        assert!(log.iter().next().unwrap().is_opaque());
+
+        let mut copy = setup();
+        assert_eq!(log.replay(&mut copy), [ReplayWarning::Opaque { index: 0 }]);
+    }
+
+    #[test]
+    fn not_recording() {
+        let mut world = setup();
+        world.send(Tick);
+
+        assert!(!world.is_recording());
+        assert!(world.stop_recording().is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! Events scheduled to be sent on a later tick.
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BinaryHeap};
+use core::cmp::Reverse;
+use core::fmt;
+
+use crate::event::Event;
+use crate::world::World;
+
+/// Identifies an event scheduled with [`World::send_delayed`] or
+/// [`Sender::send_delayed`]. Pass it to [`World::cancel_scheduled`] to cancel
+/// the event before it is sent.
+///
+/// IDs are never reused within a world.
+///
+/// [`Sender::send_delayed`]: crate::event::Sender::send_delayed
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ScheduledEventId(u64);
+
+type SendFn = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+/// Pending events ordered by the tick they're due on, then by the order they
+/// were scheduled in.
+pub(crate) struct Scheduler {
+    tick: u64,
+    next_seq: u64,
+    /// `(due tick, sequence number)` of every pending event. Cancelled events
+    /// stay in the heap until they're popped.
+    queue: BinaryHeap<Reverse<(u64, u64)>>,
+    /// The events still pending, keyed by sequence number.
+    pending: BTreeMap<u64, SendFn>,
+}
+
+impl Scheduler {
+    pub(crate) fn new() -> Self {
+        Self {
+            tick: 0,
+            next_seq: 0,
+            queue: BinaryHeap::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub(crate) fn schedule<E: Event>(&mut self, event: E, ticks: u32) -> ScheduledEventId {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        let due = self.tick + u64::from(ticks);
+
+        self.queue.push(Reverse((due, seq)));
+        self.pending.insert(
+            seq,
+            Box::new(move |world: &mut World| {
+                // Dropping the event runs its destructor.
+                if !E::IS_TARGETED || world.entities().contains(event.target()) {
+                    world.send(event);
+                }
+            }),
+        );
+
+        ScheduledEventId(seq)
+    }
+
+    pub(crate) fn cancel(&mut self, id: ScheduledEventId) -> bool {
+        self.pending.remove(&id.0).is_some()
+    }
+
+    pub(crate) fn increment_tick(&mut self) {
+        self.tick += 1;
+    }
+
+    /// Removes the next pending event which is due on or before the current
+    /// tick.
+    pub(crate) fn pop_due(&mut self) -> Option<SendFn> {
+        while let Some(&Reverse((due, seq))) = self.queue.peek() {
+            if due > self.tick {
+                break;
+            }
+
+            self.queue.pop();
+
+            if let Some(f) = self.pending.remove(&seq) {
+                return Some(f);
+            }
+        }
+
+        None
+    }
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("tick", &self.tick)
+            .field("pending", &self.pending.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct E(u32);
+
+    #[test]
+    fn delayed_event_order() {
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<E>| {
+            log_clone.lock().unwrap().push(r.event.0);
+        });
+
+        world.send_delayed(E(1), 2);
+        world.send_delayed(E(2), 1);
+        world.send_delayed(E(3), 2);
+        let cancelled = world.send_delayed(E(4), 1);
+
+        assert!(world.cancel_scheduled(cancelled));
+        assert!(!world.cancel_scheduled(cancelled));
+
+        world.advance_tick(1);
+        assert_eq!(*log.lock().unwrap(), [2]);
+
+        world.advance_tick(1);
+        assert_eq!(*log.lock().unwrap(), [2, 1, 3]);
+
+        assert_eq!(world.tick(), 2);
+    }
+
+    #[test]
+    fn send_delayed_from_system() {
+        let mut world = World::new();
+
+        world.add_system(|r: Receiver<E>, mut s: Sender<E>| {
+            if r.event.0 > 0 {
+                s.send_delayed(E(r.event.0 - 1), 10);
+            }
+        });
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<E>| {
+            log_clone.lock().unwrap().push(r.event.0);
+        });
+
+        world.send(E(3));
+        world.advance_tick(9);
+        assert_eq!(*log.lock().unwrap(), [3]);
+        world.advance_tick(21);
+        assert_eq!(*log.lock().unwrap(), [3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn delayed_event_to_dead_target() {
+        #[derive(Event)]
+        struct Targeted {
+            #[event(target)]
+            target: EntityId,
+            _payload: Arc<()>,
+        }
+
+        let mut world = World::new();
+
+        let received = Arc::new(Mutex::new(0));
+
+        let received_clone = received.clone();
+        world.add_system(move |_: Receiver<Targeted, ()>| {
+            *received_clone.lock().unwrap() += 1;
+        });
+
+        let payload = Arc::new(());
+
+        let alive = world.spawn();
+        let dead = world.spawn();
+
+        world.send_delayed(
+            Targeted {
+                target: alive,
+                _payload: payload.clone(),
+            },
+            5,
+        );
+        world.send_delayed(
+            Targeted {
+                target: dead,
+                _payload: payload.clone(),
+            },
+            5,
+        );
+        let cancelled = world.send_delayed(
+            Targeted {
+                target: alive,
+                _payload: payload.clone(),
+            },
+            5,
+        );
+
+        world.despawn(dead);
+        world.cancel_scheduled(cancelled);
+
+        assert_eq!(Arc::strong_count(&payload), 3);
+
+        world.advance_tick(5);
+
+        assert_eq!(*received.lock().unwrap(), 1);
+        assert_eq!(Arc::strong_count(&payload), 1);
+    }
+}
@@ -11,6 +11,11 @@ pub(crate) struct SlotMap<T> {
     slots: Vec<Slot<T>>,
     next_free: u32,
     len: u32,
+    /// Number of slots whose generation count was exhausted or which were
+    /// vacated below `pinned`.
+    retired: u32,
+    /// Slots below this index are never reused once vacated.
+    pinned: u32,
 }
 
 impl<T> SlotMap<T> {
@@ -19,9 +24,35 @@ impl<T> SlotMap<T> {
             slots: vec![],
             next_free: u32::MAX,
             len: 0,
+            retired: 0,
+            pinned: 0,
         }
     }
 
+    /// Prevents the slots which exist right now from ever being reused.
+    /// Removing a value from one of them retires the slot, so its index is
+    /// never handed out again.
+    pub(crate) fn pin_existing(&mut self) {
+        self.pinned = self.slots.len() as u32;
+    }
+
+    /// Reserves room for at least `additional` more values to be inserted
+    /// without reallocating.
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        let vacant = self.slots.len() - self.len as usize - self.retired as usize;
+        self.slots.reserve(additional.saturating_sub(vacant));
+    }
+
+    /// Returns the number of bytes allocated for the slots.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.slots.capacity() * mem::size_of::<Slot<T>>()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
     pub(crate) fn insert(&mut self, value: T) -> Option<Key> {
         self.insert_with(|_| value)
     }
@@ -88,8 +119,12 @@ impl<T> SlotMap<T> {
         let res = unsafe { ManuallyDrop::take(&mut slot.union.value) };
 
         // If the generation overflowed then we consider the slot retired and won't try
-        // to use it again.
-        if slot.generation != 0 {
+        // to use it again. Keys never have a generation of zero, so no key can refer
+        // to the slot anymore. Pinned slots are retired with any generation.
+        if slot.generation == 0 || key.index < self.pinned {
+            slot.union.next_free = u32::MAX;
+            self.retired += 1;
+        } else {
             slot.union.next_free = self.next_free;
             self.next_free = key.index;
         }
@@ -163,6 +198,20 @@ impl<T> SlotMap<T> {
         self.len
     }
 
+    /// Returns the number of slots which have been retired.
+    pub(crate) const fn retired(&self) -> u32 {
+        self.retired
+    }
+
+    /// Overwrites the generation count of a slot without changing whether it
+    /// is vacant.
+    #[cfg(test)]
+    pub(crate) fn set_generation(&mut self, index: u32, generation: u32) {
+        let slot = &mut self.slots[index as usize];
+        assert_eq!(slot.is_vacant(), generation.is_multiple_of(2));
+        slot.generation = generation;
+    }
+
     pub(crate) fn iter(&self) -> impl Iterator<Item = (Key, &T)> {
         self.slots
             .iter()
@@ -221,7 +270,7 @@ struct Slot<T> {
 
 impl<T> Slot<T> {
     const fn is_vacant(&self) -> bool {
-        self.generation % 2 == 0
+        self.generation.is_multiple_of(2)
     }
 }
 
@@ -287,7 +336,7 @@ impl Key {
 
     pub(crate) const fn new(index: u32, generation: u32) -> Option<Self> {
         // LSB of generation must be 1 for safety.
-        if generation % 2 == 0 {
+        if generation.is_multiple_of(2) {
             None
         } else {
             let generation = unsafe { NonZeroU32::new_unchecked(generation) };
@@ -435,9 +484,37 @@ mod tests {
         };
 
         assert_eq!(sm.remove(k), Some(123));
+        assert_eq!(sm.retired(), 1);
+        assert_eq!(sm.len(), 0);
         let k2 = sm.insert(456).unwrap();
 
         assert_ne!(k2.index(), 0);
+        assert_eq!(sm.get(k), None);
+        assert_eq!(sm.get_by_index(0), None);
+        assert_eq!(sm.iter().count(), 1);
+
+        // Cloning reads the free list link of the retired slot.
+        let sm2 = sm.clone();
+        assert_eq!(sm2.get(k2), Some(&456));
+    }
+
+    #[test]
+    fn pinned_slots_are_not_reused() {
+        let mut sm = SlotMap::new();
+
+        let k0 = sm.insert(0).unwrap();
+        let k1 = sm.insert(1).unwrap();
+        sm.pin_existing();
+        let k2 = sm.insert(2).unwrap();
+
+        assert_eq!(sm.remove(k0), Some(0));
+        assert_eq!(sm.remove(k2), Some(2));
+        assert_eq!(sm.retired(), 1);
+
+        // Only the unpinned slot is reused.
+        assert_eq!(sm.insert(3).unwrap().index(), 2);
+        assert_eq!(sm.insert(4).unwrap().index(), 3);
+        assert_eq!(sm.get(k1), Some(&1));
     }
 
     #[test]
@@ -111,6 +111,13 @@ impl<K: SparseIndex, V> SparseMap<K, V> {
         }
     }
 
+    /// Returns the number of bytes allocated by this map, not counting memory
+    /// owned by the values.
+    pub(crate) fn heap_size(&self) -> usize {
+        (self.sparse.capacity() + self.indices.capacity()) * mem::size_of::<K>()
+            + self.dense.capacity() * mem::size_of::<V>()
+    }
+
     pub(crate) fn keys(&self) -> &[K] {
         &self.indices
     }
@@ -139,6 +146,142 @@ impl<K: SparseIndex, V> SparseMap<K, V> {
     }
 }
 
+/// The number of entries a [`SmallSparseMap`] stores without allocating.
+pub(crate) const INLINE_LEN: usize = 2;
+
+/// A map with the same interface as [`SparseMap`] which stores up to
+/// [`INLINE_LEN`] entries inline and finds them with a linear scan. It only
+/// switches to a [`SparseMap`] once it has more entries than that, so maps
+/// with few entries don't pay for a sparse array as long as the largest key.
+#[derive(Clone, Debug)]
+pub(crate) enum SmallSparseMap<K, V> {
+    /// The entries, packed at the start of the array.
+    Inline([Option<(K, V)>; INLINE_LEN]),
+    Sparse(SparseMap<K, V>),
+}
+
+#[allow(dead_code)]
+impl<K: SparseIndex, V> SmallSparseMap<K, V> {
+    pub(crate) const fn new() -> Self {
+        Self::Inline([None, None])
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, key: K) -> Option<&V> {
+        match self {
+            Self::Inline(entries) => entries
+                .iter()
+                .map_while(Option::as_ref)
+                .find(|(k, _)| k.index() == key.index())
+                .map(|(_, v)| v),
+            Self::Sparse(map) => map.get(key),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn contains_key(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        match self {
+            Self::Inline(entries) => entries
+                .iter_mut()
+                .map_while(Option::as_mut)
+                .find(|(k, _)| k.index() == key.index())
+                .map(|(_, v)| v),
+            Self::Sparse(map) => map.get_mut(key),
+        }
+    }
+
+    #[track_caller]
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let entries = match self {
+            Self::Inline(entries) => entries,
+            Self::Sparse(map) => return map.insert(key, value),
+        };
+
+        assert_ne!(
+            key.index(),
+            K::MAX.index(),
+            "cannot insert in sparse map with `K::MAX` as key"
+        );
+
+        for entry in entries.iter_mut() {
+            match entry {
+                Some((k, v)) if k.index() == key.index() => return Some(mem::replace(v, value)),
+                Some(_) => {}
+                None => {
+                    *entry = Some((key, value));
+                    return None;
+                }
+            }
+        }
+
+        // The inline entries are full.
+        let mut map = SparseMap::new();
+
+        for (k, v) in mem::take(entries).into_iter().flatten() {
+            map.insert(k, v);
+        }
+
+        map.insert(key, value);
+        *self = Self::Sparse(map);
+
+        None
+    }
+
+    pub(crate) fn remove(&mut self, key: K) -> Option<V> {
+        let entries = match self {
+            Self::Inline(entries) => entries,
+            Self::Sparse(map) => return map.remove(key),
+        };
+
+        let len = entries.iter().take_while(|e| e.is_some()).count();
+        let idx = entries[..len]
+            .iter()
+            .position(|e| e.as_ref().is_some_and(|(k, _)| k.index() == key.index()))?;
+
+        // Keep the entries packed by moving the last one into the hole.
+        entries.swap(idx, len - 1);
+        entries[len - 1].take().map(|(_, v)| v)
+    }
+
+    /// Returns the number of bytes allocated by this map, not counting memory
+    /// owned by the values. This is zero while the entries are inline.
+    pub(crate) fn heap_size(&self) -> usize {
+        match self {
+            Self::Inline(_) => 0,
+            Self::Sparse(map) => map.heap_size(),
+        }
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &V> {
+        let (inline, sparse) = match self {
+            Self::Inline(entries) => (&entries[..], &[][..]),
+            Self::Sparse(map) => (&[][..], map.values()),
+        };
+
+        inline
+            .iter()
+            .map_while(|e| e.as_ref().map(|(_, v)| v))
+            .chain(sparse)
+    }
+
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        let (inline, sparse) = match self {
+            Self::Inline(entries) => (&mut entries[..], &mut [][..]),
+            Self::Sparse(map) => (&mut [][..], map.values_mut()),
+        };
+
+        inline
+            .iter_mut()
+            .map_while(|e| e.as_mut().map(|(_, v)| v))
+            .chain(sparse)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::collections::BTreeSet;
@@ -176,6 +319,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn small_sparse_map() {
+        let mut map = SmallSparseMap::<u32, char>::new();
+
+        // Large keys don't allocate while the entries are inline.
+        assert_eq!(map.insert(1000, 'a'), None);
+        assert_eq!(map.insert(5, 'b'), None);
+        assert_eq!(map.insert(1000, 'c'), Some('a'));
+        assert_eq!(map.heap_size(), 0);
+
+        assert_eq!(map.get(1000), Some(&'c'));
+        assert_eq!(map.get(5), Some(&'b'));
+        assert_eq!(map.get(6), None);
+
+        assert_eq!(map.remove(1000), Some('c'));
+        assert_eq!(map.remove(1000), None);
+        assert_eq!(map.get(5), Some(&'b'));
+
+        assert_eq!(map.insert(7, 'd'), None);
+        assert_eq!(map.insert(8, 'e'), None);
+        assert!(matches!(map, SmallSparseMap::Sparse(_)));
+        assert_ne!(map.heap_size(), 0);
+
+        assert_eq!(map.get_mut(7), Some(&mut 'd'));
+        assert_eq!(
+            map.values().copied().collect::<BTreeSet<_>>(),
+            BTreeSet::from_iter(['b', 'd', 'e'])
+        );
+    }
+
     #[test]
     #[should_panic]
     fn sparse_map_insert_max() {
@@ -2,29 +2,31 @@
 
 use alloc::borrow::Cow;
 use alloc::boxed::Box;
-use alloc::collections::BTreeMap;
-use alloc::vec;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::vec::Vec;
+use alloc::{format, vec};
 use core::any::TypeId;
+use core::error::Error;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut, Index};
 use core::ptr::NonNull;
-use core::{any, fmt};
+use core::{any, fmt, iter, mem, ptr};
 
 use evenio_macros::all_tuples;
 pub use evenio_macros::SystemParam;
 
-use crate::access::{Access, ComponentAccessExpr};
+use crate::access::{Access, AccessMap, ComponentAccessExpr};
 use crate::archetype::Archetype;
 use crate::assert::UnwrapDebugChecked;
 use crate::bit_set::BitSet;
 use crate::bool_expr::BoolExpr;
-use crate::component::ComponentIdx;
+use crate::component::{Component, ComponentId, ComponentIdx};
 use crate::event::{Event, EventId, EventIdx, EventPtr, TargetedEventIdx, UntargetedEventIdx};
 use crate::exclusive::Exclusive;
+use crate::global::GlobalIdx;
 use crate::slot_map::{Key, SlotMap};
 use crate::sparse::SparseIndex;
-use crate::world::{UnsafeWorldCell, World};
+use crate::world::{UnsafeWorldCell, World, WorldId};
 
 /// Contains metadata for all the systems in a world.
 ///
@@ -47,14 +49,23 @@ pub struct Systems {
     /// the event.
     by_untargeted_event: Vec<SystemList>,
     by_type_id: BTreeMap<TypeId, SystemInfoPtr>,
+    /// Pairs of systems left out of [`World::detect_order_ambiguities`], with
+    /// the lower ID first.
+    allowed_ambiguities: BTreeSet<(SystemId, SystemId)>,
+    /// The registration order assigned to the next added system.
+    next_order: u64,
+    world: WorldId,
 }
 
 impl Systems {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(world: WorldId) -> Self {
         Self {
             infos: SlotMap::new(),
             by_untargeted_event: vec![],
             by_type_id: BTreeMap::new(),
+            allowed_ambiguities: BTreeSet::new(),
+            next_order: 0,
+            world,
         }
     }
 
@@ -65,20 +76,27 @@ impl Systems {
             assert!(self.by_type_id.insert(type_id, ptr).is_none());
         }
 
+        let world = self.world;
+
+        unsafe { (*ptr.as_ptr()).order = self.next_order };
+        self.next_order += 1;
+
         let Some(k) = self.infos.insert_with(|k| {
-            let id = SystemId(k);
+            let id = SystemId(k, world);
 
             unsafe { (*ptr.as_ptr()).id = id };
 
-            if let EventIdx::Untargeted(idx) = info.received_event().index() {
-                let idx = idx.0 as usize;
+            for event in info.received_events() {
+                if let EventIdx::Untargeted(idx) = event.index() {
+                    let idx = idx.0 as usize;
 
-                if idx >= self.by_untargeted_event.len() {
-                    self.by_untargeted_event
-                        .resize_with(idx + 1, SystemList::default);
-                }
+                    if idx >= self.by_untargeted_event.len() {
+                        self.by_untargeted_event
+                            .resize_with(idx + 1, SystemList::default);
+                    }
 
-                self.by_untargeted_event[idx].insert(ptr, info.priority())
+                    self.by_untargeted_event[idx].insert(ptr)
+                }
             }
 
             info
@@ -86,23 +104,28 @@ impl Systems {
             panic!("too many systems")
         };
 
-        SystemId(k)
+        SystemId(k, world)
     }
 
     pub(crate) fn remove(&mut self, id: SystemId) -> Option<SystemInfo> {
-        let info = self.infos.remove(id.0)?;
+        self.debug_assert_same_world(id);
 
-        let received_event = info.received_event();
+        let info = self.infos.remove(id.0)?;
 
-        if received_event.is_untargeted() {
-            let list = &mut self.by_untargeted_event[received_event.index().as_u32() as usize];
-            list.remove(info.ptr());
+        for event in info.received_events() {
+            if event.is_untargeted() {
+                let list = &mut self.by_untargeted_event[event.index().as_u32() as usize];
+                list.remove(info.ptr());
+            }
         }
 
         if let Some(type_id) = info.type_id() {
             self.by_type_id.remove(&type_id);
         }
 
+        self.allowed_ambiguities
+            .retain(|&(a, b)| a != id && b != id);
+
         Some(info)
     }
 
@@ -119,16 +142,40 @@ impl Systems {
         self.by_untargeted_event.get(idx.0 as usize)
     }
 
+    pub(crate) fn untargeted_lists(&self) -> impl Iterator<Item = &SystemList> {
+        self.by_untargeted_event.iter()
+    }
+
+    pub(crate) fn allow_ambiguity(&mut self, a: SystemId, b: SystemId) {
+        self.allowed_ambiguities.insert((a.min(b), a.max(b)));
+    }
+
+    /// Returns `true` if the pair of systems was passed to
+    /// [`World::allow_order_ambiguity`].
+    pub fn is_ambiguity_allowed(&self, a: SystemId, b: SystemId) -> bool {
+        self.allowed_ambiguities.contains(&(a.min(b), a.max(b)))
+    }
+
     /// Gets the [`SystemInfo`] of the given system. Returns `None` if the ID is
     /// invalid.
     pub fn get(&self, id: SystemId) -> Option<&SystemInfo> {
+        self.debug_assert_same_world(id);
         self.infos.get(id.0)
     }
 
     pub(crate) fn get_mut(&mut self, id: SystemId) -> Option<&mut SystemInfo> {
+        self.debug_assert_same_world(id);
         self.infos.get_mut(id.0)
     }
 
+    #[track_caller]
+    fn debug_assert_same_world(&self, id: SystemId) {
+        debug_assert!(
+            id.1.is_compatible(self.world),
+            "system ID {id:?} belongs to a different world"
+        );
+    }
+
     /// Gets the [`SystemInfo`] for a system using its [`SystemIdx`].
     /// Returns `None` if the index is invalid.
     pub fn get_by_index(&self, idx: SystemIdx) -> Option<&SystemInfo> {
@@ -153,6 +200,20 @@ impl Systems {
         self.infos.iter().map(|(_, v)| v)
     }
 
+    /// Returns the number of bytes used by the systems and the tables of
+    /// untargeted event listeners.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.infos.heap_size()
+            + self.iter().map(SystemInfo::heap_size).sum::<usize>()
+            + self.by_untargeted_event.capacity() * mem::size_of::<SystemList>()
+            + self
+                .by_untargeted_event
+                .iter()
+                .map(SystemList::heap_size)
+                .sum::<usize>()
+            + self.by_type_id.len() * mem::size_of::<(TypeId, SystemInfoPtr)>()
+    }
+
     pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut SystemInfo> {
         self.infos.iter_mut().map(|(_, v)| v)
     }
@@ -241,6 +302,7 @@ pub(crate) struct SystemInfoInner<S: ?Sized = dyn System> {
     pub(crate) name: Cow<'static, str>,
     pub(crate) id: SystemId,
     pub(crate) received_event: EventId,
+    pub(crate) other_received_events: Box<[EventId]>,
     pub(crate) received_event_access: Access,
     pub(crate) targeted_event_expr: BoolExpr<ComponentIdx>,
     pub(crate) sent_untargeted_events: BitSet<UntargetedEventIdx>,
@@ -248,8 +310,15 @@ pub(crate) struct SystemInfoInner<S: ?Sized = dyn System> {
     pub(crate) event_queue_access: Access,
     pub(crate) component_access: ComponentAccessExpr,
     pub(crate) referenced_components: BitSet<ComponentIdx>,
+    pub(crate) global_access: AccessMap<GlobalIdx>,
     pub(crate) priority: Priority,
+    pub(crate) sub_priority: i32,
+    /// Increases with every system added to the world. Breaks ties between
+    /// systems with the same priority.
+    pub(crate) order: u64,
     pub(crate) type_id: Option<TypeId>,
+    #[cfg(feature = "stats")]
+    pub(crate) stats: SystemStats,
     // SAFETY: There is intentionally no public accessor for this field as it would lead to mutable
     // aliasing.
     pub(crate) system: S,
@@ -271,16 +340,53 @@ impl SystemInfo {
         unsafe { &(*self.inner.as_ptr()).name }
     }
 
+    /// Returns the number of bytes used by the boxed system and the metadata
+    /// it owns. Allocations made by the system itself are not counted.
+    pub(crate) fn heap_size(&self) -> usize {
+        let inner = unsafe { &*self.inner.as_ptr() };
+
+        let name = match &inner.name {
+            Cow::Borrowed(_) => 0,
+            Cow::Owned(name) => name.capacity(),
+        };
+
+        mem::size_of_val(inner)
+            + name
+            + mem::size_of_val(&*inner.other_received_events)
+            + inner.targeted_event_expr.heap_size()
+            + inner.sent_untargeted_events.heap_size()
+            + inner.sent_targeted_events.heap_size()
+            + inner.component_access.expr.heap_size()
+            + inner.component_access.access.heap_size()
+            + inner.referenced_components.heap_size()
+            + inner.global_access.heap_size()
+    }
+
     /// Gets the ID of this system.
     pub fn id(&self) -> SystemId {
         unsafe { (*self.inner.as_ptr()).id }
     }
 
-    /// Gets the [`EventId`] of the event is system listens for.
+    /// Gets the [`EventId`] of the event is system listens for. For systems
+    /// with a [`ReceiverAny`], this is the first event of the [`EventEnum`].
+    ///
+    /// [`ReceiverAny`]: crate::event::ReceiverAny
+    /// [`EventEnum`]: crate::event::EventEnum
     pub fn received_event(&self) -> EventId {
         unsafe { (*self.inner.as_ptr()).received_event }
     }
 
+    /// Returns an iterator over every event this system listens for, starting
+    /// with [`received_event`](Self::received_event). Systems only listen for
+    /// more than one event if they have a [`ReceiverAny`].
+    ///
+    /// [`ReceiverAny`]: crate::event::ReceiverAny
+    pub fn received_events(&self) -> impl Iterator<Item = EventId> + '_ {
+        let inner = unsafe { &*self.inner.as_ptr() };
+
+        iter::once(inner.received_event).chain(inner.other_received_events.iter().copied())
+    }
+
     /// Gets the system's [`Access`] to the event it listens for.
     pub fn received_event_access(&self) -> Access {
         unsafe { (*self.inner.as_ptr()).received_event_access }
@@ -304,6 +410,21 @@ impl SystemInfo {
         unsafe { &(*self.inner.as_ptr()).sent_targeted_events }
     }
 
+    /// Returns an iterator over every event this system may send, untargeted
+    /// events first.
+    ///
+    /// This is the union of [`sent_untargeted_events`] and
+    /// [`sent_targeted_events`].
+    ///
+    /// [`sent_untargeted_events`]: Self::sent_untargeted_events
+    /// [`sent_targeted_events`]: Self::sent_targeted_events
+    pub fn sent_events(&self) -> impl Iterator<Item = EventIdx> + '_ {
+        self.sent_untargeted_events()
+            .iter()
+            .map(EventIdx::Untargeted)
+            .chain(self.sent_targeted_events().iter().map(EventIdx::Targeted))
+    }
+
     /// Gets this system's [`Access`] to the event queue.
     pub fn event_queue_access(&self) -> Access {
         unsafe { (*self.inner.as_ptr()).event_queue_access }
@@ -314,6 +435,17 @@ impl SystemInfo {
         unsafe { &(*self.inner.as_ptr()).component_access }
     }
 
+    /// Returns an iterator over the components this system reads or writes
+    /// and the [`Access`] it needs to each of them.
+    ///
+    /// This is the combined access of all the system's parameters, so it's
+    /// enough to decide whether two systems can run at the same time. Use
+    /// [`component_access`](Self::component_access) for the archetypes the
+    /// system actually accesses.
+    pub fn component_accesses(&self) -> impl Iterator<Item = (ComponentIdx, Access)> + '_ {
+        self.component_access().access.iter()
+    }
+
     /// Gets the set of components referenced by this system.
     ///
     /// Referenced components are components used by the system in any way. Used
@@ -322,16 +454,43 @@ impl SystemInfo {
         unsafe { &(*self.inner.as_ptr()).referenced_components }
     }
 
+    /// Gets this system's access to the world's globals.
+    pub fn global_access(&self) -> &AccessMap<GlobalIdx> {
+        unsafe { &(*self.inner.as_ptr()).global_access }
+    }
+
     /// Gets the [`Priority`] of this system.
     pub fn priority(&self) -> Priority {
         unsafe { (*self.inner.as_ptr()).priority }
     }
 
+    /// Gets the [sub-priority](Config::sub_priority) of this system.
+    pub fn sub_priority(&self) -> i32 {
+        unsafe { (*self.inner.as_ptr()).sub_priority }
+    }
+
+    /// Returns the key systems handling the same event are sorted by.
+    pub(crate) fn order_key(&self) -> (Priority, i32, u64) {
+        let inner = unsafe { &*self.inner.as_ptr() };
+        (inner.priority, inner.sub_priority, inner.order)
+    }
+
     /// Gets the [`TypeId`] of this system, if any.
     pub fn type_id(&self) -> Option<TypeId> {
         unsafe { (*self.inner.as_ptr()).type_id }
     }
 
+    /// Gets the execution statistics of this system.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> &SystemStats {
+        unsafe { &(*self.inner.as_ptr()).stats }
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn stats_mut(&mut self) -> &mut SystemStats {
+        unsafe { &mut (*self.inner.as_ptr()).stats }
+    }
+
     pub(crate) fn ptr(&self) -> SystemInfoPtr {
         self.inner
     }
@@ -357,12 +516,14 @@ impl fmt::Debug for SystemInfo {
         f.debug_struct("SystemInfo")
             .field("name", &self.name())
             .field("received_event", &self.received_event())
+            .field("other_received_events", &self.received_events().skip(1).collect::<Vec<_>>())
             .field("received_event_access", &self.received_event_access())
             .field("targeted_event_expr", &self.targeted_event_expr())
             .field("sent_untargeted_events", &self.sent_untargeted_events())
             .field("sent_targeted_events", &self.sent_targeted_events())
             .field("event_queue_access", &self.event_queue_access())
             .field("priority", &self.priority())
+            .field("sub_priority", &self.sub_priority())
             .field("id", &self.id())
             .field("type_id", &self.type_id())
             // Don't access the `system` field.
@@ -378,10 +539,54 @@ impl Drop for SystemInfo {
     }
 }
 
+/// Execution statistics for a system, accumulated since the system was added
+/// or since the last call to [`World::reset_stats`].
+///
+/// Only the time spent running the system itself is measured. Events sent by
+/// the system are handled after the measurement ends.
+///
+/// Available with the `stats` feature.
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct SystemStats {
+    calls: u64,
+    targets: u64,
+    total_time: core::time::Duration,
+    max_time: core::time::Duration,
+}
+
+#[cfg(feature = "stats")]
+impl SystemStats {
+    /// Returns the number of times the system was run.
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// Returns the number of targeted events the system handled.
+    pub fn targets(&self) -> u64 {
+        self.targets
+    }
+
+    /// Returns the total time spent running the system.
+    pub fn total_time(&self) -> core::time::Duration {
+        self.total_time
+    }
+
+    /// Returns the longest time spent in a single run of the system.
+    pub fn max_time(&self) -> core::time::Duration {
+        self.max_time
+    }
+
+    pub(crate) fn record(&mut self, elapsed: core::time::Duration, targeted: bool) {
+        self.calls += 1;
+        self.targets += u64::from(targeted);
+        self.total_time += elapsed;
+        self.max_time = self.max_time.max(elapsed);
+    }
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct SystemList {
-    before: u32,
-    after: u32,
     entries: Vec<SystemInfoPtr>,
 }
 
@@ -389,46 +594,36 @@ unsafe impl Sync for SystemList {}
 
 impl SystemList {
     pub(crate) const fn new() -> SystemList {
-        Self {
-            before: 0,
-            after: 0,
-            entries: vec![],
-        }
+        Self { entries: vec![] }
     }
 
-    pub(crate) fn insert(&mut self, ptr: SystemInfoPtr, priority: Priority) {
+    /// Inserts a system after the systems with a higher priority tier, then
+    /// after those in the same tier with a lower sub-priority.
+    ///
+    /// Systems with the same priority and sub-priority are kept in the order
+    /// they were added to the world, regardless of the order they're inserted
+    /// into this list. This tiebreak is part of the guarantee documented on
+    /// [`Priority`].
+    pub(crate) fn insert(&mut self, ptr: SystemInfoPtr) {
         assert!(self.entries.len() < u32::MAX as usize);
 
-        match priority {
-            Priority::Before => {
-                self.entries.insert(self.before as usize, ptr);
-                self.before += 1;
-                self.after += 1;
-            }
-            Priority::Normal => {
-                self.entries.insert(self.after as usize, ptr);
-                self.after += 1;
-            }
-            Priority::After => {
-                self.entries.push(ptr);
-            }
-        }
+        // SAFETY: Systems in the list are alive.
+        let key = |p: &SystemInfoPtr| unsafe { SystemInfo::ref_from_ptr(p) }.order_key();
+        let new_key = key(&ptr);
+
+        let idx = self.entries.partition_point(|p| key(p) < new_key);
+
+        self.entries.insert(idx, ptr);
     }
 
     pub(crate) fn remove(&mut self, ptr: SystemInfoPtr) -> bool {
-        if let Some(idx) = self.entries.iter().position(|&p| p == ptr) {
+        if let Some(idx) = self
+            .entries
+            .iter()
+            .position(|&p| ptr::addr_eq(p.as_ptr(), ptr.as_ptr()))
+        {
             self.entries.remove(idx);
 
-            let idx = idx as u32;
-
-            if idx < self.after {
-                self.after -= 1;
-
-                if idx < self.before {
-                    self.before -= 1;
-                }
-            }
-
             true
         } else {
             false
@@ -438,6 +633,19 @@ impl SystemList {
     pub(crate) fn systems(&self) -> &[SystemInfoPtr] {
         &self.entries
     }
+
+    /// Returns the number of bytes allocated for the entries.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.entries.capacity() * mem::size_of::<SystemInfoPtr>()
+    }
+
+    /// Returns the systems in the list in the order they run.
+    pub(crate) fn infos(&self) -> impl Iterator<Item = &SystemInfo> {
+        // SAFETY: Systems in the list are alive.
+        self.entries
+            .iter()
+            .map(|ptr| unsafe { SystemInfo::ref_from_ptr(ptr) })
+    }
 }
 
 /// Lightweight identifier for a system.
@@ -452,12 +660,18 @@ impl SystemList {
 ///
 /// [index]: SystemIdx
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
-pub struct SystemId(Key);
+pub struct SystemId(Key, WorldId);
 
 impl SystemId {
     /// The system ID which never identifies a live system. This is the default
     /// value for `SystemId`.
-    pub const NULL: Self = Self(Key::NULL);
+    pub const NULL: Self = Self(Key::NULL, WorldId::NONE);
+
+    /// Returns the world this ID was created by.
+    #[cfg(feature = "validate-ids")]
+    pub const fn world(self) -> WorldId {
+        self.1
+    }
 
     /// Returns the index of this ID.
     pub const fn index(self) -> SystemIdx {
@@ -524,16 +738,128 @@ pub trait IntoSystem<Marker>: Sized {
     }
 
     /// Returns a wrapper which sets the priority of this system to
-    /// [`Priority::Before`].
+    /// [`Priority::BeforeDefault`].
     fn before(self) -> Before<Self::System> {
         Before(self.into_system())
     }
 
     /// Returns a wrapper which sets the priority of this system to
-    /// [`Priority::After`].
+    /// [`Priority::AfterDefault`].
     fn after(self) -> After<Self::System> {
         After(self.into_system())
     }
+
+    /// Returns a wrapper which sets the priority of this system to
+    /// `priority`, with a [sub-priority](Config::sub_priority) of `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::system::Priority;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.add_system((|_: Receiver<E>| {}).priority(Priority::Last));
+    /// let b = world.add_system(
+    ///     (|_: Receiver<E>| {})
+    ///         .priority(Priority::First)
+    ///         .sub_priority(1),
+    /// );
+    /// let c = world.add_system((|_: Receiver<E>| {}).priority(Priority::First));
+    ///
+    /// let event = world.add_event::<E>();
+    /// assert_eq!(world.system_order_for_event(event), [c, b, a]);
+    /// ```
+    fn priority(self, priority: Priority) -> WithPriority<Self::System> {
+        WithPriority {
+            system: self.into_system(),
+            priority,
+            sub_priority: 0,
+        }
+    }
+
+    /// Returns a wrapper which only runs this system for event targets whose
+    /// component `C` satisfies `predicate`.
+    ///
+    /// The system must listen for a targeted event. Targets without a `C`
+    /// component are skipped as well, like targets which don't match the
+    /// system's [`Receiver`] query. The predicate is checked for each target
+    /// right before the system would run.
+    ///
+    /// Unlike other systems, the returned system has no [`TypeId`], so the
+    /// same function can be added several times with different filters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Region(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct Explode(#[event(target)] EntityId);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(
+    ///     (|r: Receiver<Explode, ()>| println!("boom at {:?}", r.target))
+    ///         .filter_on::<Region>(|r| r.0 == 7),
+    /// );
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Region(3));
+    /// world.send(Explode(e)); // Prints nothing.
+    /// ```
+    ///
+    /// [`Receiver`]: crate::event::Receiver
+    fn filter_on<C: Component>(
+        self,
+        predicate: fn(&C) -> bool,
+    ) -> FilterOn<Self::System, C, fn(&C) -> bool> {
+        self.filter_on_with(predicate, |c, predicate| predicate(c))
+    }
+
+    /// Like [`filter_on`](Self::filter_on), but passes `data` to the
+    /// predicate along with the component. This allows one function to serve
+    /// several filters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Region(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct Explode(#[event(target)] EntityId);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// for region in 0..4 {
+    ///     world.add_system(
+    ///         (move |r: Receiver<Explode, ()>| println!("boom at {:?} in {region}", r.target))
+    ///             .filter_on_with::<Region, u32>(region, |r, &region| r.0 == region),
+    ///     );
+    /// }
+    /// ```
+    fn filter_on_with<C: Component, D: Send + Sync + 'static>(
+        self,
+        data: D,
+        predicate: fn(&C, &D) -> bool,
+    ) -> FilterOn<Self::System, C, D> {
+        FilterOn {
+            system: self.into_system(),
+            predicate,
+            data,
+            component: ComponentIdx(u32::MAX),
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -605,7 +931,7 @@ impl<S: System> System for Before<S> {
 
     fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
         let res = self.0.init(world, config);
-        config.priority = Priority::Before;
+        config.priority = Priority::BeforeDefault;
         res
     }
 
@@ -637,7 +963,7 @@ impl<S: System> System for After<S> {
 
     fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
         let res = self.0.init(world, config);
-        config.priority = Priority::After;
+        config.priority = Priority::AfterDefault;
         res
     }
 
@@ -654,6 +980,136 @@ impl<S: System> System for After<S> {
     }
 }
 
+/// The wrapper system returned by [`IntoSystem::priority`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct WithPriority<S> {
+    /// The wrapped system.
+    pub system: S,
+    /// The priority tier of the system.
+    pub priority: Priority,
+    /// The [sub-priority](Config::sub_priority) of the system.
+    pub sub_priority: i32,
+}
+
+impl<S> WithPriority<S> {
+    /// Sets the [sub-priority](Config::sub_priority) of the system.
+    pub fn sub_priority(mut self, sub_priority: i32) -> Self {
+        self.sub_priority = sub_priority;
+        self
+    }
+}
+
+impl<S: System> System for WithPriority<S> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.system.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.system.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        let res = self.system.init(world, config);
+        config.priority = self.priority;
+        config.sub_priority = self.sub_priority;
+        res
+    }
+
+    unsafe fn run(&mut self, info: &SystemInfo, event_ptr: EventPtr, world: UnsafeWorldCell) {
+        self.system.run(info, event_ptr, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.system.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.system.remove_archetype(arch)
+    }
+}
+
+/// The wrapper system returned by [`IntoSystem::filter_on`] and
+/// [`IntoSystem::filter_on_with`].
+pub struct FilterOn<S, C, D> {
+    system: S,
+    predicate: fn(&C, &D) -> bool,
+    data: D,
+    /// The index of `C`, assigned in `init`.
+    component: ComponentIdx,
+}
+
+impl<S: System, C: Component, D: Send + Sync + 'static> System for FilterOn<S, C, D> {
+    fn type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.system.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        self.system.init(world, config)?;
+
+        if !config.received_event.is_some_and(|id| id.is_targeted()) {
+            return Err(InitError(
+                format!(
+                    "system `{}` filters on the value of `{}` but doesn't receive a targeted event",
+                    self.name(),
+                    any::type_name::<C>()
+                )
+                .into(),
+            ));
+        }
+
+        let idx = world.add_component::<C>().index();
+
+        // Only targets with the component are considered.
+        config.targeted_event_expr =
+            mem::replace(&mut config.targeted_event_expr, BoolExpr::new(false))
+                .and(&BoolExpr::var(idx));
+        config.referenced_components.insert(idx);
+
+        self.component = idx;
+
+        Ok(())
+    }
+
+    unsafe fn run(&mut self, info: &SystemInfo, event_ptr: EventPtr, world: UnsafeWorldCell) {
+        let target = world.current_target();
+
+        // SAFETY: The system only runs for targets with the component, which it
+        // doesn't access mutably before the predicate is checked.
+        let value = unsafe {
+            let loc = world.entities().get(target).unwrap_debug_checked();
+            let arch = world.archetypes().get(loc.archetype).unwrap_debug_checked();
+            let col = arch.column_of(self.component).unwrap_debug_checked();
+            &*col.row_ptr(loc.row.0 as usize).as_ptr().cast::<C>()
+        };
+
+        if (self.predicate)(value, &self.data) {
+            self.system.run(info, event_ptr, world)
+        }
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.system.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.system.remove_archetype(arch)
+    }
+}
+
+impl<S: fmt::Debug, C, D: fmt::Debug> fmt::Debug for FilterOn<S, C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterOn")
+            .field("system", &self.system)
+            .field("component", &any::type_name::<C>())
+            .field("data", &self.data)
+            .finish_non_exhaustive()
+    }
+}
+
 /// An [`Event`] handler function that can be added to a [`World`].
 ///
 /// Systems are added to a world using the [`World::add_system`] method.
@@ -720,23 +1176,90 @@ impl fmt::Display for InitError {
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for InitError {}
+impl core::error::Error for InitError {}
 
-/// The priority of a system relative to other systems that handle the same
-/// event.
+/// The priority tier of a system relative to other systems that handle the
+/// same event. Tiers run in the order they're declared.
 ///
-/// If multiple systems have the same priority, then the order they were added
-/// to the [`World`] is used as a fallback.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+/// Within a tier, systems are ordered by their [sub-priority], lowest first,
+/// and then by the order they were added to the [`World`]. The registration
+/// order is guaranteed, so systems with equal priorities always run in the
+/// order of their [`World::add_system`] calls. A system that is removed and
+/// added again counts as added last.
+///
+/// For targeted events, this holds for every target: systems whose queries
+/// only match some archetypes are interleaved with the systems matching all
+/// of them in the same order. [`World::system_order_for_event`] returns the
+/// order of every system handling an event.
+///
+/// [sub-priority]: Config::sub_priority
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
 pub enum Priority {
-    /// The system runs before other systems.
-    Before,
+    /// The system runs before systems in every other tier.
+    First,
+    /// The system runs before systems with the default priority. This is set
+    /// by [`IntoSystem::before`].
+    BeforeDefault,
     /// The default system priority.
     #[default]
-    Normal,
-    /// The system runs after other systems.
-    After,
+    Default,
+    /// The system runs after systems with the default priority. This is set
+    /// by [`IntoSystem::after`].
+    AfterDefault,
+    /// The system runs after systems in every other tier.
+    Last,
+}
+
+#[allow(non_upper_case_globals)]
+impl Priority {
+    /// The old name of [`Priority::BeforeDefault`].
+    #[deprecated = "use `Priority::BeforeDefault`"]
+    pub const Before: Self = Self::BeforeDefault;
+
+    /// The old name of [`Priority::Default`].
+    #[deprecated = "use `Priority::Default`"]
+    pub const Normal: Self = Self::Default;
+
+    /// The old name of [`Priority::AfterDefault`].
+    #[deprecated = "use `Priority::AfterDefault`"]
+    pub const After: Self = Self::AfterDefault;
+}
+
+/// A pair of systems handling the same event with the same [`Priority`] and
+/// [sub-priority] whose component accesses conflict. Returned by
+/// [`World::detect_order_ambiguities`].
+///
+/// Such systems run in the order they were added to the world. That order is
+/// guaranteed, but nothing in either system says it matters, so it's easy to
+/// change by accident.
+///
+/// [sub-priority]: Config::sub_priority
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Ambiguity {
+    /// The event both systems handle.
+    pub event: EventId,
+    /// The system which runs first.
+    pub first: SystemId,
+    /// The name of the system which runs first.
+    pub first_name: Cow<'static, str>,
+    /// The system which runs second.
+    pub second: SystemId,
+    /// The name of the system which runs second.
+    pub second_name: Cow<'static, str>,
+    /// The components one of the systems writes while the other reads or
+    /// writes them, in ascending order of index.
+    pub components: Vec<ComponentId>,
+}
+
+impl fmt::Display for Ambiguity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "systems `{}` and `{}` handle event {:?} with the same priority and conflicting \
+             access to components {:?}",
+            self.first_name, self.second_name, self.event, self.components
+        )
+    }
 }
 
 /// The Configuration of a system. Accessible during system initialization.
@@ -745,16 +1268,32 @@ pub enum Priority {
 pub struct Config {
     /// The priority of this system.
     pub priority: Priority,
+    /// The order of this system within its [`Priority`] tier. Systems with a
+    /// lower sub-priority run first. Defaults to `0`.
+    pub sub_priority: i32,
     /// The event type to be received by the system.
     ///
     /// Defaults to `None`, but must be assigned to `Some` before configuration
     /// is finished.
     pub received_event: Option<EventId>,
+    /// The events received by the system besides [`Self::received_event`],
+    /// which are all targeted or all untargeted like it. Set by
+    /// [`ReceiverAny`].
+    ///
+    /// [`ReceiverAny`]: crate::event::ReceiverAny
+    pub other_received_events: Vec<EventId>,
     /// Access to the received event value.
     pub received_event_access: Access,
     /// The targeted event filter. This should be a subset of
     /// [`Self::component_access`].
     pub targeted_event_expr: BoolExpr<ComponentIdx>,
+    /// Expression describing the components of the event target accessed by
+    /// the system's [`Receiver`] or [`ReceiverMut`]. This is a subset of
+    /// [`Self::component_access`].
+    ///
+    /// [`Receiver`]: crate::event::Receiver
+    /// [`ReceiverMut`]: crate::event::ReceiverMut
+    pub targeted_event_access: ComponentAccessExpr,
     /// The set of untargeted events sent by the system.
     pub sent_untargeted_events: BitSet<UntargetedEventIdx>,
     /// The set of targeted events sent by the system.
@@ -771,6 +1310,8 @@ pub struct Config {
     /// of `C`'s component index, so the whole system must be removed when
     /// component `C` is removed.
     pub referenced_components: BitSet<ComponentIdx>,
+    /// Access to the globals of the world.
+    pub global_access: AccessMap<GlobalIdx>,
 }
 
 impl Config {
@@ -778,14 +1319,18 @@ impl Config {
     pub fn new() -> Self {
         Self {
             priority: Default::default(),
+            sub_priority: 0,
             received_event: Default::default(),
+            other_received_events: vec![],
             received_event_access: Default::default(),
             targeted_event_expr: BoolExpr::new(false),
+            targeted_event_access: ComponentAccessExpr::new(false),
             sent_untargeted_events: Default::default(),
             sent_targeted_events: Default::default(),
             event_queue_access: Default::default(),
             component_access: ComponentAccessExpr::new(false),
             referenced_components: Default::default(),
+            global_access: Default::default(),
         }
     }
 }
@@ -862,6 +1407,27 @@ pub unsafe trait SystemParam {
         world: UnsafeWorldCell<'a>,
     ) -> Self::Item<'a>;
 
+    /// Returns whether the system should run. Called before [`get`] whenever
+    /// the system is about to be called. If this returns `false`, the system
+    /// is skipped and [`get`] is not called.
+    ///
+    /// The default implementation always returns `true`.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`get`].
+    ///
+    /// [`get`]: Self::get
+    #[inline]
+    unsafe fn should_run(
+        _state: &mut Self::State,
+        _info: &SystemInfo,
+        _event_ptr: EventPtr,
+        _world: UnsafeWorldCell,
+    ) -> bool {
+        true
+    }
+
     /// Refresh an archetype for this system param. Called whenever
     /// [`System::refresh_archetype`] is called.
     fn refresh_archetype(state: &mut Self::State, arch: &Archetype);
@@ -925,6 +1491,16 @@ macro_rules! impl_system_param_tuple {
                 )
             }
 
+            #[inline]
+            unsafe fn should_run(
+                ($($s,)*): &mut Self::State,
+                _info: &SystemInfo,
+                _event_ptr: EventPtr,
+                _world: UnsafeWorldCell,
+            ) -> bool {
+                true $(&& $P::should_run($s, _info, _event_ptr, _world))*
+            }
+
             fn refresh_archetype(
                 ($($s,)*): &mut Self::State,
                 _arch: &Archetype
@@ -957,6 +1533,8 @@ all_tuples!(impl_system_param_tuple, 0, 15, P, s);
 pub struct FunctionSystem<Marker, F: SystemParamFunction<Marker>> {
     func: F,
     state: Option<<F::Param as SystemParam>::State>,
+    /// Index of the [`SystemError`] event, if the function is fallible.
+    error_idx: Option<u32>,
 }
 
 impl<Marker, F> FunctionSystem<Marker, F>
@@ -965,7 +1543,11 @@ where
 {
     /// Create a new uninitialized function system.
     pub fn new(func: F) -> Self {
-        Self { func, state: None }
+        Self {
+            func,
+            state: None,
+            error_idx: None,
+        }
     }
 }
 
@@ -978,6 +1560,7 @@ where
         f.debug_struct("FunctionSystem")
             .field("func", &self.func)
             .field("state", &self.state)
+            .field("error_idx", &self.error_idx)
             .finish()
     }
 }
@@ -997,6 +1580,17 @@ where
 
     fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
         self.state = Some(<F::Param as SystemParam>::init(world, config)?);
+
+        if <F::Out as SystemOutput>::IS_FALLIBLE {
+            let idx = world.add_event::<SystemError>().index();
+
+            if let EventIdx::Untargeted(i) = idx {
+                config.sent_untargeted_events.insert(i);
+            }
+
+            self.error_idx = Some(idx.as_u32());
+        }
+
         Ok(())
     }
 
@@ -1012,8 +1606,27 @@ where
                 .expect_debug_checked("system must be initialized")
         };
 
+        if !<F::Param as SystemParam>::should_run(state, system_info, event_ptr, world) {
+            return;
+        }
+
         let param = <F::Param as SystemParam>::get(state, system_info, event_ptr, world);
-        self.func.run(param);
+        let out = self.func.run(param);
+
+        // Infallible systems return `()`, so this compiles away for them.
+        if let Err(error) = out.into_result() {
+            let idx = unsafe {
+                self.error_idx
+                    .expect_debug_checked("fallible system must have an error event")
+            };
+
+            let event = SystemError {
+                system: system_info.id(),
+                error,
+            };
+
+            unsafe { world.send_with_index(event, idx) };
+        }
     }
 
     fn refresh_archetype(&mut self, arch: &Archetype) {
@@ -1042,8 +1655,11 @@ pub trait SystemParamFunction<Marker>: Send + Sync + 'static {
     /// The system params used by this function, combined into a single type.
     type Param: SystemParam;
 
+    /// The return type of the function.
+    type Out: SystemOutput;
+
     /// Call the function.
-    fn run(&mut self, param: <Self::Param as SystemParam>::Item<'_>);
+    fn run(&mut self, param: <Self::Param as SystemParam>::Item<'_>) -> Self::Out;
 }
 
 macro_rules! impl_system_param_function {
@@ -1054,6 +1670,8 @@ macro_rules! impl_system_param_function {
         {
             type Param = ($($P,)*);
 
+            type Out = ();
+
             fn run(
                 &mut self,
                 ($($p,)*): <Self::Param as SystemParam>::Item<'_>
@@ -1061,11 +1679,120 @@ macro_rules! impl_system_param_function {
                 (self)($($p),*)
             }
         }
+
+        impl<F, E, $($P: SystemParam),*> SystemParamFunction<(FallibleMarker, fn($($P),*) -> E)>
+            for Fallible<F>
+        where
+            F: FnMut($($P),*) -> Result<(), E>
+                + FnMut($($P::Item<'_>),*) -> Result<(), E>
+                + Send
+                + Sync
+                + 'static,
+            Result<(), E>: SystemOutput,
+        {
+            type Param = ($($P,)*);
+
+            type Out = Result<(), E>;
+
+            fn run(
+                &mut self,
+                ($($p,)*): <Self::Param as SystemParam>::Item<'_>
+            ) -> Self::Out {
+                (self.0)($($p),*)
+            }
+        }
     }
 }
 
 all_tuples!(impl_system_param_function, 0, 15, P, p);
 
+/// Wrapper for functions which return `Result<(), E>` to be used as systems.
+///
+/// The error type `E` can be anything convertible to
+/// `Box<dyn Error + Send + Sync>`. Errors returned by a system are sent in a
+/// [`SystemError`] event and don't stop other systems from receiving the
+/// event.
+///
+/// Ordinary systems return `()`, so that closures whose body only panics can
+/// still infer their return type.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::system::{Fallible, SystemError};
+///
+/// #[derive(Event)]
+/// struct Parse(&'static str);
+///
+/// let mut world = World::new();
+///
+/// world.add_system(Fallible(
+///     |r: Receiver<Parse>| -> Result<(), core::num::ParseIntError> {
+///         let n: u32 = r.event.0.parse()?;
+///         println!("parsed {n}");
+///         Ok(())
+///     },
+/// ));
+///
+/// world.add_system(|r: Receiver<SystemError>| {
+///     println!("system {:?} failed: {}", r.event.system, r.event.error);
+/// });
+///
+/// world.send(Parse("not a number"));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Fallible<F>(pub F);
+
+/// Marker for the [`SystemParamFunction`] impls of [`Fallible`].
+#[allow(missing_debug_implementations)]
+pub struct FallibleMarker;
+
+/// The return type of a [`SystemParamFunction`].
+///
+/// This is `()` for ordinary functions and `Result<(), E>` for functions
+/// wrapped in [`Fallible`].
+pub trait SystemOutput: 'static {
+    /// Whether this output can contain an error. Fallible systems register
+    /// the [`SystemError`] event when they are initialized.
+    const IS_FALLIBLE: bool;
+
+    /// Converts this output into a `Result`.
+    fn into_result(self) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+impl SystemOutput for () {
+    const IS_FALLIBLE: bool = false;
+
+    #[inline(always)]
+    fn into_result(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+impl<E> SystemOutput for Result<(), E>
+where
+    E: Into<Box<dyn Error + Send + Sync>> + 'static,
+{
+    const IS_FALLIBLE: bool = true;
+
+    #[inline]
+    fn into_result(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.map_err(Into::into)
+    }
+}
+
+/// An [`Event`] sent when a system returns an error.
+///
+/// See [`Fallible`] for more information.
+#[derive(Event, Debug)]
+pub struct SystemError {
+    /// The system that returned the error.
+    pub system: SystemId,
+    /// The error returned by the system.
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
 /// A [`SystemParam`] for storing system-local state.
 ///
 /// Any type that implements [`Default`] can be wrapped in a `Local`.
@@ -1202,6 +1929,15 @@ unsafe impl<P: SystemParam> SystemParam for std::sync::Mutex<P> {
         std::sync::Mutex::new(P::get(state, info, event_ptr, world))
     }
 
+    unsafe fn should_run(
+        state: &mut Self::State,
+        info: &SystemInfo,
+        event_ptr: EventPtr,
+        world: UnsafeWorldCell,
+    ) -> bool {
+        P::should_run(state, info, event_ptr, world)
+    }
+
     fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
         P::refresh_archetype(state, arch)
     }
@@ -1230,6 +1966,15 @@ unsafe impl<P: SystemParam> SystemParam for std::sync::RwLock<P> {
         std::sync::RwLock::new(P::get(state, info, event_ptr, world))
     }
 
+    unsafe fn should_run(
+        state: &mut Self::State,
+        info: &SystemInfo,
+        event_ptr: EventPtr,
+        world: UnsafeWorldCell,
+    ) -> bool {
+        P::should_run(state, info, event_ptr, world)
+    }
+
     fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
         P::refresh_archetype(state, arch)
     }
@@ -1288,4 +2033,430 @@ mod tests {
 
         fn assert_system_param<P: SystemParam>() {}
     }
+
+    #[test]
+    fn targeted_listener_order() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        use crate::prelude::*;
+
+        #[derive(Event)]
+        struct E(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        let temp = world.add_system(move |_: Receiver<E, ()>| l.lock().unwrap().push("temp"));
+        let l = log.clone();
+        world.add_system(move |_: Receiver<E, &A>| l.lock().unwrap().push("a"));
+        let l = log.clone();
+        world.add_system((move |_: Receiver<E, ()>| l.lock().unwrap().push("all after")).after());
+        let l = log.clone();
+        world.add_system((move |_: Receiver<E, &B>| l.lock().unwrap().push("b before")).before());
+
+        world.remove_system(temp);
+
+        // Reuses the slot of the removed system, but was added last.
+        let l = log.clone();
+        world.add_system(move |_: Receiver<E, ()>| l.lock().unwrap().push("all"));
+        let l = log.clone();
+        world.add_system((move |_: Receiver<E, ()>| l.lock().unwrap().push("all before")).before());
+        let l = log.clone();
+        world.add_system((move |_: Receiver<E, &A>| l.lock().unwrap().push("a after")).after());
+
+        // The archetypes are created after the systems are added.
+        let e1 = world.spawn();
+        world.insert(e1, A);
+        world.insert(e1, B);
+
+        let e2 = world.spawn();
+        world.insert(e2, A);
+
+        let e3 = world.spawn();
+
+        world.send(E(e1));
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["b before", "all before", "a", "all", "all after", "a after"]
+        );
+        log.lock().unwrap().clear();
+
+        world.send(E(e2));
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["all before", "a", "all", "all after", "a after"]
+        );
+        log.lock().unwrap().clear();
+
+        world.send(E(e3));
+        assert_eq!(*log.lock().unwrap(), ["all before", "all", "all after"]);
+    }
+
+    #[test]
+    fn system_info_accessors() {
+        use crate::prelude::*;
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Event)]
+        struct Sent;
+
+        let mut world = World::new();
+
+        let id = world.add_system(
+            (|_: Receiver<E>, _: Fetcher<(&A, &mut B)>, _: Sender<(Sent, Despawn)>| {})
+                .priority(Priority::Last),
+        );
+
+        // The components were added while initializing the system.
+        let a = world
+            .components()
+            .get_by_type_id(TypeId::of::<A>())
+            .unwrap()
+            .id()
+            .index();
+        let b = world
+            .components()
+            .get_by_type_id(TypeId::of::<B>())
+            .unwrap()
+            .id()
+            .index();
+
+        let e = world.add_event::<E>();
+        let info = world.system_info(id).unwrap();
+
+        assert_eq!(info.received_event(), e);
+        assert_eq!(info.priority(), Priority::Last);
+        assert_eq!(
+            info.component_accesses().collect::<Vec<_>>(),
+            [(a, Access::Read), (b, Access::ReadWrite)]
+        );
+
+        let sent = info.sent_events().collect::<Vec<_>>();
+        let sent_idx = world
+            .events()
+            .get_by_type_id(TypeId::of::<Sent>())
+            .unwrap()
+            .id()
+            .index();
+        let despawn_idx = world
+            .events()
+            .get_by_type_id(TypeId::of::<Despawn>())
+            .unwrap()
+            .id()
+            .index();
+
+        assert!(sent.contains(&sent_idx));
+        assert!(sent.contains(&despawn_idx));
+    }
+
+    #[test]
+    fn priority_tiers() {
+        use crate::prelude::*;
+
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Event)]
+        struct T(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        let last = world.add_system((|_: Receiver<E>| {}).priority(Priority::Last));
+        let default = world.add_system(|_: Receiver<E>| {});
+        let first_1 = world.add_system(
+            (|_: Receiver<E>| {})
+                .priority(Priority::First)
+                .sub_priority(1),
+        );
+        let first_0 = world.add_system((|_: Receiver<E>| {}).priority(Priority::First));
+        let first_neg = world.add_system(
+            (|_: Receiver<E>| {})
+                .priority(Priority::First)
+                .sub_priority(-5),
+        );
+        let after = world.add_system((|_: Receiver<E>| {}).after());
+        let before = world.add_system((|_: Receiver<E>| {}).before());
+
+        let event = world.add_event::<E>();
+
+        let expected = [first_neg, first_0, first_1, before, default, after, last];
+        assert_eq!(world.system_order_for_event(event), expected);
+
+        let handlers: Vec<_> = world
+            .handlers_for_event(event)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(handlers, expected);
+
+        // A re-added system counts as added last within its tier.
+        world.remove_system(first_0);
+        let first_0 = world.add_system((|_: Receiver<E>| {}).priority(Priority::First));
+        let default_2 = world.add_system((|_: Receiver<E>| {}).priority(Priority::Default));
+
+        let expected = [
+            first_neg, first_0, first_1, before, default, default_2, after, last,
+        ];
+        assert_eq!(world.system_order_for_event(event), expected);
+
+        let handlers: Vec<_> = world
+            .handlers_for_event(event)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(handlers, expected);
+
+        world.remove_system(default);
+        let default = world.add_system(|_: Receiver<E>| {});
+
+        let expected = [
+            first_neg, first_0, first_1, before, default_2, default, after, last,
+        ];
+        assert_eq!(world.system_order_for_event(event), expected);
+
+        // Targeted events follow the same order.
+        let t_last = world.add_system((|_: Receiver<T, ()>| {}).priority(Priority::Last));
+        let t_default = world.add_system(|_: Receiver<T, ()>| {});
+        let t_first = world.add_system((|_: Receiver<T, ()>| {}).priority(Priority::First));
+
+        let t = world.add_event::<T>();
+        let e = world.spawn();
+
+        let expected = [t_first, t_default, t_last];
+        assert_eq!(world.system_order_for_event(t), expected);
+
+        let handlers: Vec<_> = world
+            .handlers_for_targeted_event(t, e)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(handlers, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn system_stats() {
+        use crate::prelude::*;
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        let a = world.add_system(|_: Receiver<A>| {});
+        let b = world.add_system(|_: Receiver<B, ()>| {});
+
+        let e = world.spawn();
+
+        for _ in 0..3 {
+            world.send(A);
+        }
+        world.send(B(e));
+
+        let a_stats = *world.systems().get(a).unwrap().stats();
+        let b_stats = *world.systems().get(b).unwrap().stats();
+
+        assert_eq!(a_stats.calls(), 3);
+        assert_eq!(a_stats.targets(), 0);
+        assert!(a_stats.max_time() <= a_stats.total_time());
+        assert_eq!(b_stats.calls(), 1);
+        assert_eq!(b_stats.targets(), 1);
+
+        world.reset_stats();
+
+        assert_eq!(
+            *world.systems().get(a).unwrap().stats(),
+            SystemStats::default()
+        );
+    }
+
+    #[test]
+    fn failing_system_sends_error() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        use crate::prelude::*;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        let failing = world.add_system(Fallible(
+            move |_: Receiver<E>| -> Result<(), &'static str> {
+                l.lock().unwrap().push("failing");
+                Err("oops")
+            },
+        ));
+        let l = log.clone();
+        world.add_system((move |_: Receiver<E>| l.lock().unwrap().push("after")).after());
+
+        let errors = Arc::new(Mutex::new(vec![]));
+
+        let e = errors.clone();
+        world.add_system(move |r: Receiver<SystemError>| {
+            e.lock()
+                .unwrap()
+                .push((r.event.system, r.event.error.to_string()))
+        });
+
+        world.send(E);
+
+        assert_eq!(*log.lock().unwrap(), ["failing", "after"]);
+        assert_eq!(*errors.lock().unwrap(), [(failing, "oops".into())]);
+    }
+
+    #[test]
+    fn succeeding_system_sends_no_error() {
+        use crate::prelude::*;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        world.add_system(Fallible(|_: Receiver<E>| -> Result<(), std::io::Error> {
+            Ok(())
+        }));
+        world.add_system(|_: Receiver<SystemError>| panic!("no system failed"));
+
+        world.send(E);
+    }
+
+    #[test]
+    fn filter_on_component_value() {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
+
+        use crate::prelude::*;
+
+        #[derive(Component)]
+        struct Region(u32);
+
+        #[derive(Component)]
+        struct Hp(u32);
+
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId, u32);
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let handler = |region| {
+            let log = log.clone();
+            move |r: Receiver<Hit, &mut Hp>| {
+                r.query.0 -= 1;
+                log.lock().unwrap().push((region, r.event.1));
+            }
+        };
+
+        world.add_system(handler(1).filter_on_with::<Region, u32>(1, |r, &id| r.0 == id));
+        world.add_system(handler(2).filter_on::<Region>(|r| r.0 == 2));
+
+        let ents: Vec<_> = [1, 2, 1, 3]
+            .into_iter()
+            .map(|region| {
+                let e = world.spawn();
+                world.insert(e, Region(region));
+                world.insert(e, Hp(10));
+                e
+            })
+            .collect();
+
+        // No region at all.
+        let outside = world.spawn();
+        world.insert(outside, Hp(10));
+
+        for (i, &e) in ents.iter().chain([&outside]).enumerate() {
+            world.send(Hit(e, i as u32));
+        }
+
+        assert_eq!(*log.lock().unwrap(), [(1, 0), (2, 1), (1, 2)]);
+        assert_eq!(world.get::<&Hp>(ents[3]).unwrap().0, 10);
+        assert_eq!(world.get::<&Hp>(outside).unwrap().0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't receive a targeted event")]
+    fn filter_on_untargeted_event() {
+        use crate::prelude::*;
+
+        #[derive(Component)]
+        struct Region(u32);
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        world.add_system((|_: Receiver<E>| {}).filter_on::<Region>(|r| r.0 == 0));
+    }
+
+    #[test]
+    fn order_ambiguities() {
+        use crate::prelude::*;
+
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct Health(u32);
+
+        #[derive(Component)]
+        struct Armor;
+
+        let mut world = World::new();
+
+        let damage = world.add_system(|r: Receiver<Hit, &mut Health>| r.query.0 -= 1);
+        // Only meets `damage` on entities with both components.
+        let armor = world.add_system(|_: Receiver<Hit, (&Health, &Armor)>| {});
+        // Reads something else.
+        world.add_system(|_: Receiver<Hit, &Armor>| {});
+        // Ordered explicitly.
+        world.add_system((|_: Receiver<Hit, &Health>| {}).after());
+
+        assert!(world.detect_order_ambiguities().is_empty());
+
+        let e = world.spawn();
+        world.insert(e, Health(10));
+        world.insert(e, Armor);
+
+        let health = world.add_component::<Health>();
+        let hit = world.add_event::<Hit>();
+
+        let found = world.detect_order_ambiguities();
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            (found[0].event, found[0].first, found[0].second),
+            (hit, damage, armor)
+        );
+        assert_eq!(found[0].components, [health]);
+
+        world.allow_order_ambiguity(armor, damage);
+        assert!(world.detect_order_ambiguities().is_empty());
+    }
 }
@@ -1,43 +1,117 @@
 //! Defines the [`World`] and related APIs.
 
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::alloc::Layout;
 use core::any::{self, TypeId};
 use core::cell::UnsafeCell;
 use core::marker::PhantomData;
-use core::mem;
+use core::mem::MaybeUninit;
+use core::ops::Range;
 use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr::{self, NonNull};
+use core::{fmt, mem};
 
-use crate::archetype::Archetypes;
-use crate::assert::{AssertMutable, UnwrapDebugChecked};
+use crate::access::ComponentAccessExpr;
+use crate::archetype::{
+    Archetype, ArchetypeIdx, ArchetypeLimitReached, ArchetypePin, ArchetypeSettings, Archetypes,
+};
+use crate::assert::{AssertMutable, GetDebugChecked, UnwrapDebugChecked};
+use crate::bridge::{Bridges, Channel, ChannelSink, EventChannelReader, EventChannels, EventSink};
+use crate::command::CommandQueue;
+#[cfg(feature = "replay")]
+use crate::component::CloneFn;
 use crate::component::{
-    AddComponent, Component, ComponentDescriptor, ComponentId, ComponentInfo, Components,
-    RemoveComponent,
+    copy_fn_of, AddComponent, AddComponentError, Component, ComponentDescriptor, ComponentId,
+    ComponentIdx, ComponentInfo, Components, HookContext, RemoveComponent, RequiredComponents,
+    SingletonPolicy, ValidationFailure,
 };
+#[cfg(feature = "diff")]
+use crate::diff::{ChangeLog, WorldDiff};
 use crate::drop::{drop_fn_of, DropFn};
-use crate::entity::{Entities, EntityId, ReservedEntities};
+use crate::entity::{
+    self, ChildOf, CloneError, CloneOptions, Entities, EntityId, EntityLocation, ReservedEntities,
+    TargetHolder,
+};
+use crate::entity_group::{EntityGroupId, EntityGroupStats, EntityGroups};
 use crate::event::{
-    AddEvent, Despawn, Event, EventDescriptor, EventId, EventIdx, EventInfo, EventKind, EventMeta,
-    EventPtr, EventQueue, Events, Insert, Remove, RemoveEvent, Spawn, SpawnQueued,
+    set_target_fn_of, targets_fn_of, AddEvent, DeadTarget, DeadTargetPolicy, Despawn, Despawned,
+    Event, EventDescriptor, EventId, EventIdx, EventInfo, EventKind, EventMeta, EventPtr,
+    EventQueue, EventQueueItem, Events, FilteredEventHook, Insert, Remove, RemoveEvent,
+    SetTargetFn, Spawn, SpawnQueued, TargetFilter, MAX_BUBBLE_DEPTH,
 };
+use crate::fetch::{DynamicFetcherState, FetcherState, WorldFetcher};
+use crate::global::Globals;
+use crate::group::{ComponentGroup, GroupIdx, GroupMembers};
+use crate::memory::{ArchetypeMemory, MemoryReport};
+#[cfg(feature = "name")]
+use crate::name::{Name, NameIndex};
+use crate::query::{DynamicQuery, Query, ReadOnlyQuery};
+#[cfg(feature = "replay")]
+use crate::replay::{EventLog, Recorder};
+use crate::schedule::{ScheduledEventId, Scheduler};
 use crate::system::{
-    AddSystem, Config, IntoSystem, RemoveSystem, System, SystemId, SystemInfo, SystemInfoInner,
-    SystemList, Systems,
+    AddSystem, Ambiguity, Config, InitError, IntoSystem, Priority, RemoveSystem, System, SystemId,
+    SystemInfo, SystemInfoInner, SystemInfoPtr, SystemList, Systems,
 };
 
 /// A container for all data in the ECS. This includes entities, components,
 /// systems, and events.
+///
+/// `World` is `Send` and `Sync`. Methods taking `&self`, such as
+/// [`get_component`] and the [`WorldFetcher`] returned by [`query`], have no
+/// hidden mutation and can be called from several threads at once. Worlds
+/// containing [local components] panic when accessed from other threads
+/// instead.
+///
+/// [`get_component`]: World::get_component
+/// [`query`]: World::query
+/// [local components]: Component::IS_LOCAL
 #[derive(Debug)]
 pub struct World {
+    #[cfg(feature = "validate-ids")]
+    id: WorldId,
     entities: Entities,
     reserved_entities: ReservedEntities,
+    entity_groups: EntityGroups,
     components: Components,
     systems: Systems,
     archetypes: Archetypes,
     events: Events,
     event_queue: EventQueue,
+    commands: CommandQueue,
+    scheduler: Scheduler,
+    bridges: Bridges,
+    event_channels: EventChannels,
+    globals: Globals,
+    filtered_event_hook: Option<FilteredEventHook>,
+    dead_target_policy: DeadTargetPolicy,
+    warning_handler: Option<WarningHandler>,
+    #[cfg(feature = "name")]
+    names: NameIndex,
+    /// The entity the targeted event being broadcast is currently visiting,
+    /// and the entity it was sent to.
+    current_target: EntityId,
+    original_target: EntityId,
+    /// The event being broadcast to the running system.
+    current_event: EventIdx,
+    /// Whether a [`ComponentHook`](crate::component::ComponentHook) is running.
+    /// Events sent about new components and events are queued instead of
+    /// broadcast while this is set.
+    running_hook: bool,
+    /// Where the event sent by [`World::send_and_return`] is moved to once it
+    /// finishes broadcasting.
+    return_slot: Option<ReturnSlot>,
+    /// The recorder started by [`World::start_recording`], if any.
+    #[cfg(feature = "replay")]
+    recorder: Option<Recorder>,
+    /// The thread this world is tied to, if it contains local components.
+    #[cfg(feature = "std")]
+    owner_thread: Option<std::thread::ThreadId>,
 }
 
 impl World {
@@ -51,17 +125,64 @@ impl World {
     /// let mut world = World::new();
     /// ```
     pub fn new() -> Self {
+        let id = WorldId::next();
+
         Self {
+            #[cfg(feature = "validate-ids")]
+            id,
             entities: Entities::new(),
             reserved_entities: ReservedEntities::new(),
-            components: Components::new(),
-            systems: Systems::new(),
+            entity_groups: EntityGroups::new(),
+            components: Components::new(id),
+            systems: Systems::new(id),
             archetypes: Archetypes::new(),
-            events: Events::new(),
+            events: Events::new(id),
             event_queue: EventQueue::new(),
+            commands: CommandQueue::new(),
+            scheduler: Scheduler::new(),
+            bridges: Bridges::new(),
+            event_channels: EventChannels::new(),
+            globals: Globals::new(),
+            filtered_event_hook: None,
+            dead_target_policy: DeadTargetPolicy::Ignore,
+            warning_handler: None,
+            #[cfg(feature = "name")]
+            names: NameIndex::new(),
+            current_target: EntityId::NULL,
+            original_target: EntityId::NULL,
+            current_event: EventId::NULL.index(),
+            running_hook: false,
+            return_slot: None,
+            #[cfg(feature = "replay")]
+            recorder: None,
+            #[cfg(feature = "std")]
+            owner_thread: None,
         }
     }
 
+    /// Returns the [`WorldId`] of this world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C;
+    ///
+    /// let mut a = World::new();
+    /// let mut b = World::new();
+    ///
+    /// assert_ne!(a.id(), b.id());
+    ///
+    /// let id = a.add_component::<C>();
+    /// assert_eq!(id.world(), a.id());
+    /// ```
+    #[cfg(feature = "validate-ids")]
+    pub fn id(&self) -> WorldId {
+        self.id
+    }
+
     /// Broadcast an event to all systems in this world.
     ///
     /// Any events sent by systems will also broadcast. This process continues
@@ -94,1140 +215,7604 @@ impl World {
         self.send_many(|mut s| s.send(event))
     }
 
-    /// Enqueue an arbitrary number of events and send them all at once.
-    ///
-    /// The closure `f` is passed a [`Sender`] used to add events to a queue.
-    /// Once the closure returns, all enqueued events are broadcasted as
-    /// described by [`send`].
-    ///
-    /// [`send`]: World::send
+    /// Like [`send`](Self::send), but takes the event in a [`Box`]. The event
+    /// is moved from the box into the event queue without being copied to the
+    /// stack, which avoids large copies for events with big payloads.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # #[derive(Event)]
-    /// # struct A;
-    /// #
-    /// # #[derive(Event)]
-    /// # struct B;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// world.send_many(|mut sender| {
-    ///     sender.send(A);
-    ///     sender.send(B);
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Mesh {
+    ///     vertices: [[f32; 3]; 4096],
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_system(|r: Receiver<Mesh>| {
+    ///     println!("got a mesh with {} vertices", r.event.vertices.len());
     /// });
+    ///
+    /// let mesh = vec![[0.0; 3]; 4096].try_into().unwrap();
+    /// world.send_boxed(Box::new(Mesh { vertices: mesh }));
     /// ```
-    pub fn send_many<F, R>(&mut self, f: F) -> R
-    where
-        F: FnOnce(Sender) -> R,
-    {
-        let res = f(Sender { world: self });
-
-        self.flush_event_queue();
-
-        res
+    pub fn send_boxed<E: Event>(&mut self, event: Box<E>) {
+        self.send_many(|mut s| s.send_boxed(event))
     }
 
-    /// Creates a new entity, returns its [`EntityId`], and sends the [`Spawn`]
-    /// event to signal its creation.
+    /// Like [`send`](Self::send), but gives the event back once it has
+    /// finished broadcasting, along with any changes listeners made to it
+    /// through [`EventMut`]. This is useful for events which ask a question,
+    /// where the listeners fill in the answer.
     ///
-    /// The new entity is spawned without any components attached. The returned
-    /// `EntityId` is not used by any previous entities in this world.
+    /// Returns `None` if a listener [took] the event, or if the event was
+    /// consumed by the world, like [`Insert`] and [`Remove`] are. [Deferred]
+    /// events are broadcast right away instead of being held back.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
+    /// #[derive(Component)]
+    /// struct Name(String);
+    ///
+    /// #[derive(Event)]
+    /// struct GetName {
+    ///     #[event(target)]
+    ///     entity: EntityId,
+    ///     name: Option<String>,
+    /// }
+    ///
     /// let mut world = World::new();
-    /// let id = world.spawn();
     ///
-    /// assert!(world.entities().contains(id));
-    /// ```
-    pub fn spawn(&mut self) -> EntityId {
-        self.send_many(|mut s| s.spawn())
-    }
-
-    /// Sends the [`Insert`] event.
+    /// world.add_system(|mut r: ReceiverMut<GetName, &Name>| {
+    ///     r.event.name = Some(r.query.0.clone());
+    /// });
     ///
-    /// This is equivalent to:
+    /// let e = world.spawn();
+    /// world.insert(e, Name("Alice".into()));
     ///
+    /// let event = world.send_and_return(GetName {
+    ///     entity: e,
+    ///     name: None,
+    /// });
+    /// assert_eq!(event.unwrap().name.as_deref(), Some("Alice"));
     /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// # #[derive(Component)]
-    /// # struct C;
-    /// #
-    /// # let component = C;
-    /// #
-    /// world.send(Insert::new(entity, component));
-    /// ```
-    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
-        self.send(Insert::new(entity, component))
+    ///
+    /// [`EventMut`]: crate::event::EventMut
+    /// [took]: crate::event::EventMut::take
+    /// [`Insert`]: crate::event::Insert
+    /// [`Remove`]: crate::event::Remove
+    /// [Deferred]: crate::event::Event::IS_DEFERRED
+    pub fn send_and_return<E: Event>(&mut self, event: E) -> Option<E> {
+        self.check_thread();
+
+        let mut dst = MaybeUninit::<E>::uninit();
+
+        let start = self.event_queue.len();
+        let idx = self.queued_event_idx::<E>();
+        unsafe { self.event_queue.push_now(event, idx) };
+
+        let slot = ReturnSlot {
+            queue_idx: Some(start),
+            dst: dst.as_mut_ptr().cast(),
+            size: mem::size_of::<E>(),
+            returned: false,
+        };
+
+        let guard = ReturnSlotGuard::new(self, slot);
+        guard.world.flush_event_queue(start);
+
+        // SAFETY: `returned` is only set once the event was moved to `dst`.
+        guard.finish().then(|| unsafe { dst.assume_init() })
     }
 
-    /// Sends the [`Remove`] event.
+    /// Adds a system which calls `sink` with every `E` that finishes
+    /// broadcasting in this world.
     ///
-    /// This is equivalent to:
+    /// The sink runs after all other systems listening for `E`, so it sees the
+    /// event as they left it. Events which are consumed before reaching the
+    /// sink are not passed to it. Returns the [`SystemId`] of the added system,
+    /// which can be given to [`remove_system`] to remove the sink.
+    ///
+    /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// # #[derive(Component)]
-    /// # struct C;
-    /// #
-    /// world.send(Remove::<C>::new(entity));
-    /// ```
-    pub fn remove<C: Component>(&mut self, entity: EntityId) {
-        self.send(Remove::<C>::new(entity))
-    }
-
-    /// Sends the [`Despawn`] event.
+    /// use evenio::prelude::*;
     ///
-    /// This is equivalent to:
+    /// #[derive(Event)]
+    /// struct Chat(String);
     ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_event_sink(|e: &Chat| println!("chat: {}", e.0));
+    ///
+    /// world.send(Chat("hello".into())); // Prints "chat: hello".
     /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// world.send(Despawn(entity));
-    /// ```
-    pub fn despawn(&mut self, entity: EntityId) {
-        self.send(Despawn(entity))
+    ///
+    /// [`remove_system`]: World::remove_system
+    pub fn add_event_sink<E, F>(&mut self, sink: F) -> SystemId
+    where
+        E: Event,
+        F: FnMut(&E) + Send + Sync + 'static,
+    {
+        self.add_system(EventSink::<E, F>::new(sink))
     }
 
-    /// Gets an immutable reference to component `C` on `entity`. Returns `None`
-    /// if `entity` doesn't exist or doesn't have the requested component.
+    /// Mirrors events of type `E` sent in this world into `other`.
+    ///
+    /// A copy of every `E` which finishes broadcasting in this world (see
+    /// [`add_event_sink`]) is queued for `other`. Queued events aren't sent
+    /// until [`pump_bridges`] is called on `other`, so neither world ever
+    /// dispatches events while the other is in the middle of broadcasting.
+    ///
+    /// Entity IDs aren't shared between worlds, so the targets of targeted
+    /// events are passed through `remap_target`. The event is dropped if it
+    /// returns `None`. `remap_target` is never called for untargeted events.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
-    /// #[derive(Component, PartialEq, Debug)]
-    /// struct MyComponent(i32);
+    /// #[derive(Event, Clone)]
+    /// struct Chat(String);
     ///
-    /// let mut world = World::new();
+    /// let mut shard_a = World::new();
+    /// let mut shard_b = World::new();
     ///
-    /// let e = world.spawn();
-    /// world.insert(e, MyComponent(123));
+    /// shard_a.bridge_events::<Chat, _>(&mut shard_b, |_| None);
     ///
-    /// assert_eq!(
-    ///     world.get_component::<MyComponent>(e),
-    ///     Some(&MyComponent(123))
-    /// );
+    /// shard_b.add_system(|r: Receiver<Chat>| println!("mirrored: {}", r.event.0));
+    ///
+    /// shard_a.send(Chat("hello".into()));
+    ///
+    /// shard_b.pump_bridges(); // Prints "mirrored: hello".
     /// ```
-    pub fn get_component<C: Component>(&self, entity: EntityId) -> Option<&C> {
-        let loc = self.entities.get(entity)?;
+    ///
+    /// [`add_event_sink`]: World::add_event_sink
+    /// [`pump_bridges`]: World::pump_bridges
+    #[cfg(feature = "std")]
+    pub fn bridge_events<E, F>(&mut self, other: &mut World, mut remap_target: F) -> SystemId
+    where
+        E: Event + Clone,
+        F: FnMut(EntityId) -> Option<EntityId> + Send + Sync + 'static,
+    {
+        use alloc::sync::Arc;
+        use std::sync::Mutex;
 
-        let component_idx = self
-            .components()
-            .get_by_type_id(TypeId::of::<C>())?
-            .id()
-            .index();
+        let queue = Arc::new(Mutex::new(Vec::<E>::new()));
 
-        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_debug_checked() };
+        let queue_clone = queue.clone();
+        other.bridges.push(Box::new(move |world| {
+            let events = mem::take(&mut *queue_clone.lock().unwrap());
 
-        let col = arch.column_of(component_idx)?;
+            for mut event in events {
+                if E::IS_TARGETED {
+                    let Some(target) = remap_target(event.target()) else {
+                        continue;
+                    };
 
-        Some(unsafe {
-            &*col
-                .data()
-                .as_ptr()
-                .cast_const()
-                .cast::<C>()
-                .add(loc.row.0 as usize)
-        })
+                    event.set_target(target);
+                }
+
+                world.send(event);
+            }
+        }));
+
+        self.add_event_sink(move |event: &E| queue.lock().unwrap().push(event.clone()))
     }
 
-    /// Gets a mutable reference to component `C` on `entity`. Returns `None` if
-    /// `entity` doesn't exist or doesn't have the requested component.
+    /// Sends the events queued for this world by [`bridge_events`].
+    ///
+    /// Bridges are pumped in the order they were added. The events from each
+    /// bridge are sent in the order they were mirrored.
+    ///
+    /// [`bridge_events`]: World::bridge_events
+    pub fn pump_bridges(&mut self) {
+        let mut pumps = self.bridges.take();
+
+        for pump in &mut pumps {
+            pump(self);
+        }
+
+        self.bridges.restore(pumps);
+    }
+
+    /// Starts keeping the most recent `E`s sent in this world, and returns a
+    /// reader for them.
+    ///
+    /// This is meant for code outside of the world, like a render loop, which
+    /// wants to see the events produced during the last dispatch. Every `E`
+    /// which finishes broadcasting (see [`add_event_sink`]) is copied into a
+    /// ring buffer holding up to `capacity` events, but only while at least
+    /// one reader exists. Targeted events are captured once for each target
+    /// they visit, along with the target.
+    ///
+    /// All the readers of `E` share one buffer. A reader only sees the events
+    /// captured after it was created. When the buffer is full, the oldest
+    /// event is overwritten, and readers which haven't seen it yet count it
+    /// as [missed]. Creating another reader with a larger `capacity` makes the
+    /// buffer larger.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
-    /// #[derive(Component, PartialEq, Debug)]
-    /// struct MyComponent(i32);
+    /// #[derive(Event, Clone)]
+    /// struct WindowResized {
+    ///     width: u32,
+    ///     height: u32,
+    /// }
     ///
     /// let mut world = World::new();
     ///
-    /// let e = world.spawn();
-    /// world.insert(e, MyComponent(123));
+    /// let mut reader = world.event_channel::<WindowResized>(16);
     ///
-    /// assert_eq!(
-    ///     world.get_component_mut::<MyComponent>(e),
-    ///     Some(&mut MyComponent(123))
-    /// );
+    /// world.send(WindowResized {
+    ///     width: 800,
+    ///     height: 600,
+    /// });
+    ///
+    /// for resized in reader.drain(&world) {
+    ///     println!(
+    ///         "resized to {}x{}",
+    ///         resized.event.width, resized.event.height
+    ///     );
+    /// }
     /// ```
-    pub fn get_component_mut<C: Component>(&mut self, entity: EntityId) -> Option<&mut C> {
-        let () = AssertMutable::<C>::COMPONENT;
-
-        let loc = self.entities.get(entity)?;
+    ///
+    /// [`add_event_sink`]: World::add_event_sink
+    /// [missed]: crate::bridge::EventChannelDrain::missed
+    #[track_caller]
+    pub fn event_channel<E: Event + Clone>(&mut self, capacity: usize) -> EventChannelReader<E> {
+        assert!(capacity > 0, "event channels must have room for an event");
 
-        let component_idx = self
-            .components()
-            .get_by_type_id(TypeId::of::<C>())?
-            .id()
-            .index();
+        if let Some(channel) = self.event_channels.get_mut::<E>() {
+            channel.reserve(capacity);
 
-        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_debug_checked() };
+            // The sink is removed along with the event.
+            let sink = channel.sink;
+            if !self.systems.contains(sink) {
+                let sink = self.add_system(ChannelSink::<E>::new());
+                self.event_channels.get_mut::<E>().unwrap().sink = sink;
+            }
+        } else {
+            let sink = self.add_system(ChannelSink::<E>::new());
+            self.event_channels
+                .insert(Channel::<E>::new(capacity, sink));
+        }
 
-        let col = arch.column_of(component_idx)?;
+        self.event_channels.get::<E>().unwrap().reader()
+    }
 
-        Some(unsafe { &mut *col.data().as_ptr().cast::<C>().add(loc.row.0 as usize) })
+    pub(crate) fn event_channels(&self) -> &EventChannels {
+        &self.event_channels
     }
 
-    /// Adds a new system to the world, returns its [`SystemId`], and sends the
-    /// [`AddSystem`] event to signal its creation.
+    /// Schedules an event to be sent once [`advance_tick`] has moved the
+    /// world forward by `ticks` ticks. Events due on the same tick are sent
+    /// in the order they were scheduled.
     ///
-    /// If the system already exists (as determined by [`System::type_id`]) then
-    /// the `SystemId` of the existing system is returned and no event is sent.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the configuration of the system is invalid. This can occur
-    /// when, for instance, the system does not specify an event to receive.
-    ///
-    /// ```should_panic
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// world.add_system(|| {}); // Panics
-    /// ```
+    /// Targeted events are dropped without being sent if the target no
+    /// longer exists when the event is due. The returned
+    /// [`ScheduledEventId`] can be passed to [`cancel_scheduled`].
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
-    /// # #[derive(Event)]
-    /// # struct MyEvent;
     ///
-    /// fn my_system(_: Receiver<MyEvent>) {};
+    /// #[derive(Event)]
+    /// struct RespawnPlayer;
     ///
     /// let mut world = World::new();
-    /// let id = world.add_system(my_system);
     ///
-    /// assert!(world.systems().contains(id));
+    /// world.add_system(|_: Receiver<RespawnPlayer>| println!("respawning"));
+    ///
+    /// world.send_delayed(RespawnPlayer, 200);
+    ///
+    /// world.advance_tick(199); // Nothing happens.
+    /// world.advance_tick(1); // Prints "respawning".
     /// ```
     ///
-    /// [`System::type_id`]: crate::system::System::type_id
-    #[track_caller]
-    pub fn add_system<S: IntoSystem<M>, M>(&mut self, system: S) -> SystemId {
-        let mut system = system.into_system();
-        let mut config = Config::default();
+    /// [`advance_tick`]: World::advance_tick
+    /// [`cancel_scheduled`]: World::cancel_scheduled
+    pub fn send_delayed<E: Event>(&mut self, event: E, ticks: u32) -> ScheduledEventId {
+        self.scheduler.schedule(event, ticks)
+    }
 
-        let type_id = system.type_id();
+    /// Cancels an event scheduled with [`send_delayed`] and drops it. Returns
+    /// `false` if the event was already sent or cancelled.
+    ///
+    /// [`send_delayed`]: World::send_delayed
+    pub fn cancel_scheduled(&mut self, id: ScheduledEventId) -> bool {
+        self.scheduler.cancel(id)
+    }
 
-        if let Some(type_id) = type_id {
-            if let Some(info) = self.systems.get_by_type_id(type_id) {
-                return info.id();
+    /// Returns the world's current tick. The tick starts at zero and is only
+    /// changed by [`advance_tick`].
+    ///
+    /// [`advance_tick`]: World::advance_tick
+    pub fn tick(&self) -> u64 {
+        self.scheduler.tick()
+    }
+
+    /// Moves the world forward by `n` ticks, sending the scheduled events
+    /// which become due along the way.
+    ///
+    /// The tick is incremented one step at a time. After each step, every
+    /// event due on or before the new tick is broadcast, ordered by the tick
+    /// it was due on and then by the order it was scheduled in. Events
+    /// scheduled while this is running are sent on a later step, or on the
+    /// current step if they have no delay.
+    pub fn advance_tick(&mut self, n: u32) {
+        for _ in 0..n {
+            #[cfg(feature = "diff")]
+            self.archetypes.flush_column_changes();
+
+            self.scheduler.increment_tick();
+
+            #[cfg(feature = "diff")]
+            if let Some(changes) = &mut self.archetypes.changes {
+                changes.tick = self.scheduler.tick();
             }
-        }
 
-        if let Err(e) = system.init(self, &mut config) {
-            panic!("{e}");
+            while let Some(send) = self.scheduler.pop_due() {
+                send(self);
+            }
         }
+    }
 
-        let Some(received_event) = config.received_event else {
-            panic!(
-                "system `{}` did not specify an event to receive. All systems must listen for \
-                 exactly one event type (see `Receiver`)",
-                any::type_name::<S>()
-            )
-        };
-
-        let info = SystemInfo::new(SystemInfoInner {
-            name: system.name(),
-            id: SystemId::NULL, // Filled in later.
-            received_event,
-            received_event_access: config.received_event_access,
-            targeted_event_expr: config.targeted_event_expr,
-            sent_untargeted_events: config.sent_untargeted_events,
-            sent_targeted_events: config.sent_targeted_events,
-            event_queue_access: config.event_queue_access,
-            component_access: config.component_access,
-            referenced_components: config.referenced_components,
-            priority: config.priority,
-            type_id,
-            system,
-        });
+    /// Enqueue an arbitrary number of events and send them all at once.
+    ///
+    /// The closure `f` is passed a [`Sender`] used to add events to a queue.
+    /// Once the closure returns, all enqueued events are broadcasted as
+    /// described by [`send`], in a single flush of the event queue.
+    ///
+    /// Nothing is left for later when this returns. Commands, the spawning of
+    /// reserved entities, and [deferred](crate::event::Event::IS_DEFERRED)
+    /// events all complete before the flush ends.
+    ///
+    /// [`send`]: World::send
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # #[derive(Event)]
+    /// # struct A;
+    /// #
+    /// # #[derive(Event)]
+    /// # struct B;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// world.send_many(|mut sender| {
+    ///     sender.send(A);
+    ///     sender.send(B);
+    /// });
+    /// ```
+    pub fn send_many<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(Sender) -> R,
+    {
+        self.check_thread();
 
-        let id = self.systems.add(info);
-        let info = self.systems.get_mut(id).unwrap();
+        let start = self.event_queue.len();
 
-        self.archetypes.register_system(info);
+        let res = f(Sender { world: self });
 
-        self.send(AddSystem(id));
+        self.flush_event_queue(start);
 
-        id
+        res
     }
 
-    /// Removes a system from the world, returns its [`SystemInfo`], and sends
-    /// the [`RemoveSystem`] event. If the `system` ID is invalid, then `None`
-    /// is returned and no event is sent.
+    /// Sends every event of `events` in order, like calling [`send`] for each
+    /// of them, but with one flush of the event queue for the whole batch.
     ///
-    /// # Example
+    /// The events are queued before any of them is broadcast. Each event still
+    /// finishes broadcasting, including the events sent by its listeners,
+    /// before the next one starts. [Deferred] events are held back until the
+    /// whole batch has been broadcast.
+    ///
+    /// Use [`send_many`] to send events of different types in one batch.
+    ///
+    /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
-    /// let mut world = World::new();
+    /// #[derive(Event)]
+    /// struct Tick(u32);
     ///
-    /// # #[derive(Event)]
-    /// # struct MyEvent;
-    /// let system_id = world.add_system(|_: Receiver<MyEvent>| {});
+    /// let mut world = World::new();
     ///
-    /// let info = world.remove_system(system_id).unwrap();
+    /// world.add_system(|r: Receiver<Tick>| println!("tick {}", r.event.0));
     ///
-    /// assert_eq!(info.id(), system_id);
-    /// assert!(!world.systems().contains(system_id));
+    /// world.send_all((0..3).map(Tick)); // Prints ticks 0, 1, and 2.
     /// ```
-    pub fn remove_system(&mut self, system: SystemId) -> Option<SystemInfo> {
-        if !self.systems.contains(system) {
-            return None;
+    ///
+    /// [`send`]: World::send
+    /// [Deferred]: crate::event::Event::IS_DEFERRED
+    /// [`send_many`]: World::send_many
+    pub fn send_all<I>(&mut self, events: I)
+    where
+        I: IntoIterator,
+        I::Item: Event,
+    {
+        self.send_many(|mut s| {
+            for event in events {
+                s.send(event);
+            }
+        })
+    }
+
+    /// Starts recording the events dispatched by this world, discarding any
+    /// recording in progress. See the [`replay`](crate::replay) module.
+    #[cfg(feature = "replay")]
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::default());
+    }
+
+    /// Stops recording and returns the events recorded since
+    /// [`start_recording`]. The log is empty if the world wasn't recording.
+    ///
+    /// [`start_recording`]: World::start_recording
+    #[cfg(feature = "replay")]
+    pub fn stop_recording(&mut self) -> EventLog {
+        self.recorder
+            .take()
+            .map(Recorder::finish)
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if the world is recording events.
+    #[cfg(feature = "replay")]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Starts recording the changes made to this world, so that
+    /// [`diff_since`] can be used. The entities and components which already
+    /// exist count as spawned and inserted on the current tick. Does nothing
+    /// if the world is already tracking changes. See the [`diff`] module.
+    ///
+    /// [`diff_since`]: World::diff_since
+    /// [`diff`]: crate::diff
+    #[cfg(feature = "diff")]
+    pub fn start_tracking_changes(&mut self) {
+        if self.archetypes.changes.is_none() {
+            self.archetypes.changes = Some(ChangeLog::new(self.tick(), &self.archetypes));
         }
+    }
 
-        self.send(RemoveSystem(system));
+    /// Stops recording changes and discards the recorded changes.
+    #[cfg(feature = "diff")]
+    pub fn stop_tracking_changes(&mut self) {
+        self.archetypes.changes = None;
+    }
 
-        let info = self.systems.remove(system).unwrap();
+    /// Returns `true` if the world is tracking changes.
+    #[cfg(feature = "diff")]
+    pub fn is_tracking_changes(&self) -> bool {
+        self.archetypes.changes.is_some()
+    }
 
-        self.archetypes.remove_system(&info);
+    /// Returns the changes made on or after `tick` to the components for
+    /// which `filter` returns `true`, along with the entities which were
+    /// spawned and despawned. Components excluded by the filter are never
+    /// included. See the [`diff`] module.
+    ///
+    /// Changes made on the current tick after this is called are included by
+    /// the next diff only if it's taken since the current tick. To send each
+    /// change once, take diffs just before calling [`advance_tick`] and pass
+    /// [`WorldDiff::tick`] plus one to the next call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the world isn't [tracking changes].
+    ///
+    /// [`diff`]: crate::diff
+    /// [`advance_tick`]: World::advance_tick
+    /// [tracking changes]: World::start_tracking_changes
+    #[cfg(feature = "diff")]
+    #[track_caller]
+    pub fn diff_since<F>(&self, tick: u64, filter: F) -> WorldDiff
+    where
+        F: FnMut(ComponentId) -> bool,
+    {
+        self.check_thread();
+        WorldDiff::new(self, tick, filter)
+    }
 
-        Some(info)
+    /// Discards the records of the entities despawned and the components
+    /// removed before `tick`, which are otherwise kept for as long as the
+    /// world tracks changes. Diffs taken since an earlier tick no longer
+    /// include them.
+    #[cfg(feature = "diff")]
+    pub fn forget_changes_before(&mut self, tick: u64) {
+        if let Some(changes) = &mut self.archetypes.changes {
+            changes.forget_before(tick);
+        }
     }
 
-    /// Adds the component `C` to the world, returns its [`ComponentId`], and
-    /// sends the [`AddComponent`] event to signal its creation.
+    /// Sends a copy of the event at `src` made with `clone`.
     ///
-    /// If the component already exists, then the [`ComponentId`] of the
-    /// existing component is returned and no event is sent.
+    /// # Safety
+    ///
+    /// `src` must point to a valid instance of the event at `idx`, and
+    /// `clone` must be able to clone it. Targeted events are sent to
+    /// `target`.
+    #[cfg(feature = "replay")]
+    pub(crate) unsafe fn send_cloned(
+        &mut self,
+        idx: EventIdx,
+        target: EntityId,
+        src: *const u8,
+        clone: CloneFn,
+    ) {
+        unsafe { self.send_raw(idx, target, |event| clone(src, event)) }
+    }
+
+    /// Sends the event with the given [`EventId`], moving its value out of
+    /// `payload`. This works like [`send`], for events whose types are only
+    /// known at runtime.
+    ///
+    /// Targeted events are sent to `target`, which must be `Some`.
+    /// Untargeted events ignore `target`. The event is dispatched the same
+    /// way as if it were sent with [`send`], so targeted events run the
+    /// systems listening in the archetype of `target`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `event` doesn't exist in this world, or if it's targeted and
+    /// `target` is `None`.
+    ///
+    /// # Safety
+    ///
+    /// - `payload` must point to an initialized value of the event, with the
+    ///   [`Layout`] it was added with.
+    /// - Ownership of the value is transferred to the world, so the caller must
+    ///   not use or drop it afterwards. `payload` is not deallocated.
+    /// - The target of a targeted event must be the same as what the event
+    ///   reports, such as through its [`Event::target`] method.
     ///
     /// # Examples
     ///
     /// ```
+    /// use core::mem::ManuallyDrop;
+    ///
     /// use evenio::prelude::*;
     ///
-    /// #[derive(Component)]
-    /// struct MyComponent;
+    /// #[derive(Event)]
+    /// struct Message(String);
     ///
     /// let mut world = World::new();
-    /// let id = world.add_component::<MyComponent>();
     ///
-    /// assert_eq!(id, world.add_component::<MyComponent>());
-    /// ```
-    pub fn add_component<C: Component>(&mut self) -> ComponentId {
-        let desc = ComponentDescriptor {
-            name: any::type_name::<C>().into(),
-            type_id: Some(TypeId::of::<C>()),
-            layout: Layout::new::<C>(),
-            drop: drop_fn_of::<C>(),
-            is_immutable: C::IS_IMMUTABLE,
-        };
-
-        unsafe { self.add_component_with_descriptor(desc) }
-    }
-
-    /// Adds a component described by a given [`ComponentDescriptor`].
+    /// world.add_system(|r: Receiver<Message>| println!("{}", r.event.0));
     ///
-    /// Like [`add_component`], an [`AddComponent`] event is sent if the
-    /// component is newly added. If the [`TypeId`] of the component matches an
-    /// existing component, then the existing component's [`ComponentId`] is
-    /// returned and no event is sent.
+    /// let id = world.add_event::<Message>();
     ///
-    /// # Safety
+    /// let mut message = ManuallyDrop::new(Message("hello".into()));
+    /// let payload = (&mut *message as *mut Message).cast::<u8>();
     ///
-    /// - If the component is given a [`TypeId`], then the `layout` and `drop`
-    ///   function must be compatible with the Rust type identified by the type
-    ///   ID.
-    /// - Drop function must be safe to call with a pointer to the component as
-    ///   described by [`DropFn`]'s documentation.
+    /// unsafe { world.send_dynamic(id, None, payload) }; // Prints "hello".
+    /// ```
     ///
-    /// [`add_component`]: World::add_component
-    pub unsafe fn add_component_with_descriptor(
+    /// [`send`]: World::send
+    #[track_caller]
+    pub unsafe fn send_dynamic(
         &mut self,
-        desc: ComponentDescriptor,
-    ) -> ComponentId {
-        let (id, is_new) = self.components.add(desc);
+        event: EventId,
+        target: Option<EntityId>,
+        payload: *mut u8,
+    ) {
+        let Some(info) = self.events.get(event) else {
+            panic!("event {event:?} doesn't exist")
+        };
 
-        if is_new {
-            self.send(AddComponent(id));
-        }
+        let size = info.layout().size();
 
-        id
+        let target = match (event.index(), target) {
+            (EventIdx::Targeted(_), None) => {
+                panic!("targeted event `{}` was sent without a target", info.name())
+            }
+            (_, target) => target.unwrap_or(EntityId::NULL),
+        };
+
+        unsafe {
+            self.send_raw(event.index(), target, |dst| {
+                ptr::copy_nonoverlapping(payload, dst, size)
+            })
+        }
     }
 
-    /// Removes a component from the world and returns its [`ComponentInfo`]. If
-    /// the `component` ID is invalid, then `None` is returned and the function
-    /// has no effect.
+    /// Sends the default value of the untargeted event with the given
+    /// [`EventId`], created with its [`DefaultFn`]. Events deriving [`Event`]
+    /// have a `DefaultFn` if they implement [`Default`].
     ///
-    /// Removing a component has the following effects in the order listed:
-    /// 1. The [`RemoveComponent`] event is sent.
-    /// 2. All entities with the component are despawned.
-    /// 3. All systems that reference the component are removed.
-    /// 4. The corresponding [`Insert`] events for the component are removed.
-    /// 5. The corresponding [`Remove`] events for the component are removed.
+    /// # Panics
+    ///
+    /// Panics if `event` doesn't exist in this world, is targeted, or has no
+    /// `DefaultFn`.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// # let mut world = World::new();
-    /// # #[derive(Component)] struct C;
-    /// # #[derive(Event)] struct E;
-    /// #
-    /// let component = world.add_component::<C>();
-    /// let system = world.add_system(|_: Receiver<E>, _: Fetcher<&C>| {});
+    /// use evenio::prelude::*;
     ///
-    /// assert!(world.components().contains(component));
-    /// assert!(world.systems().contains(system));
+    /// #[derive(Event, Default)]
+    /// struct Tick;
     ///
-    /// world.remove_component(component);
+    /// let mut world = World::new();
     ///
-    /// assert!(!world.components().contains(component));
-    /// // System was also removed because it references `C` in its `Fetcher`.
-    /// assert!(!world.systems().contains(system));
+    /// world.add_system(|_: Receiver<Tick>| println!("tick"));
+    ///
+    /// let id = world.add_event::<Tick>();
+    /// world.send_dynamic_default(id); // Prints "tick".
     /// ```
-    pub fn remove_component(&mut self, component: ComponentId) -> Option<ComponentInfo> {
-        if !self.components.contains(component) {
-            return None;
-        }
+    ///
+    /// [`DefaultFn`]: crate::component::DefaultFn
+    #[track_caller]
+    pub fn send_dynamic_default(&mut self, event: EventId) {
+        let Some(info) = self.events.get(event) else {
+            panic!("event {event:?} doesn't exist")
+        };
 
-        // Send event first.
-        self.send(RemoveComponent(component));
+        assert!(
+            event.is_untargeted(),
+            "targeted event `{}` can't be sent with its default value",
+            info.name()
+        );
 
-        let despawn_idx = self.add_event::<Despawn>().index().as_u32();
+        let Some(default) = info.default_fn() else {
+            panic!("event `{}` has no default value", info.name())
+        };
 
-        // Attempt to despawn all entities that still have this component.
-        for arch in self.archetypes.iter() {
-            if arch.column_of(component.index()).is_some() {
-                for &entity_id in arch.entity_ids() {
-                    unsafe { self.event_queue.push(Despawn(entity_id), despawn_idx) };
-                }
-            }
+        unsafe {
+            self.send_raw(event.index(), EntityId::NULL, |dst| {
+                default(NonNull::new_unchecked(dst))
+            })
         }
+    }
 
-        self.flush_event_queue();
+    /// Sends the event at `idx` after `init` writes its value, and flushes
+    /// the queue.
+    ///
+    /// # Safety
+    ///
+    /// `init` must initialize the event. Targeted events are sent to
+    /// `target`.
+    pub(crate) unsafe fn send_raw(
+        &mut self,
+        idx: EventIdx,
+        target: EntityId,
+        init: impl FnOnce(*mut u8),
+    ) {
+        self.check_thread();
 
-        // Remove all systems that reference this component.
-        let mut systems_to_remove = vec![];
+        let info = unsafe { self.events.get_by_index(idx).unwrap_debug_checked() };
+        let is_deferred = info.is_deferred();
 
-        for sys in self.systems.iter() {
-            if sys.referenced_components().contains(component.index()) {
-                systems_to_remove.push(sys.id());
-            }
-        }
+        let start = self.event_queue.len();
+        let event = self.event_queue.alloc_layout(info.layout()).as_ptr();
 
-        for sys_id in systems_to_remove {
-            self.remove_system(sys_id);
-        }
+        init(event);
 
-        let info = &self.components[component];
+        let meta = match idx {
+            EventIdx::Targeted(idx) => EventMeta::Targeted { idx, target },
+            EventIdx::Untargeted(idx) => EventMeta::Untargeted { idx },
+        };
 
-        // Remove all the `Insert` and `Remove` events for this component.
-        let events_to_remove = info
-            .insert_events()
-            .iter()
-            .copied()
-            .chain(info.remove_events().iter().copied())
-            .collect::<Vec<_>>();
+        let item = EventQueueItem { meta, event };
 
-        for event in events_to_remove {
-            self.remove_event(event);
+        unsafe {
+            if is_deferred {
+                self.event_queue.push_deferred_item(item);
+            } else {
+                self.event_queue.push_item(item);
+            }
         }
 
-        // Remove all archetypes with this component. If there are still entities with
-        // the component by this point, then they will be silently removed.
-        self.archetypes
-            .remove_component(component.index(), |entity_id| {
-                self.entities.remove(entity_id);
-            });
-
-        self.components.remove(component)
+        self.flush_event_queue(start);
     }
 
-    /// Adds the event `E` to the world, returns its [`EventId`], and sends the
-    /// [`AddEvent`] event to signal its creation.
+    /// Creates a new entity, returns its [`EntityId`], and sends the [`Spawn`]
+    /// event to signal its creation.
     ///
-    /// If the event already exists, then the [`EventId`] of the existing event
-    /// is returned and no event is sent.
+    /// The new entity is spawned without any components attached. The returned
+    /// `EntityId` is not used by any previous entities in this world.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
-    /// #[derive(Event)]
-    /// struct MyEvent;
-    ///
     /// let mut world = World::new();
-    /// let id = world.add_event::<MyEvent>();
+    /// let id = world.spawn();
     ///
-    /// assert_eq!(id, world.add_event::<MyEvent>());
+    /// assert!(world.entities().contains(id));
     /// ```
-    pub fn add_event<E: Event>(&mut self) -> EventId {
-        let desc = EventDescriptor {
-            name: any::type_name::<E>().into(),
-            type_id: Some(TypeId::of::<E>()),
-            is_targeted: E::IS_TARGETED,
-            kind: unsafe { E::init(self) },
-            layout: Layout::new::<E>(),
-            drop: drop_fn_of::<E>(),
-            is_immutable: E::IS_IMMUTABLE,
+    pub fn spawn(&mut self) -> EntityId {
+        self.send_many(|mut s| s.spawn())
+    }
+
+    /// Spawns a new entity in the given [entity group] and returns its
+    /// [`EntityId`].
+    ///
+    /// The entity joins the group after the [`Spawn`] event is broadcast, so
+    /// listeners of `Spawn` see it in the default group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the group doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let group = world.add_entity_group();
+    /// let id = world.spawn_in(group);
+    ///
+    /// assert_eq!(world.entity_group(id), Some(group));
+    /// ```
+    ///
+    /// [entity group]: crate::entity_group
+    #[track_caller]
+    pub fn spawn_in(&mut self, group: EntityGroupId) -> EntityId {
+        assert!(
+            self.entity_groups.contains(group),
+            "entity group {group:?} doesn't exist"
+        );
+
+        let id = self.spawn();
+
+        // Listeners of `Spawn` may have despawned the entity already.
+        self.set_entity_group(id, group);
+
+        id
+    }
+
+    /// Returns the [entity groups] of the world.
+    ///
+    /// [entity groups]: crate::entity_group
+    pub fn entity_groups(&self) -> &EntityGroups {
+        &self.entity_groups
+    }
+
+    /// Adds a new, empty [entity group] and returns its ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maximum number of entity groups was reached.
+    ///
+    /// [entity group]: crate::entity_group
+    #[track_caller]
+    pub fn add_entity_group(&mut self) -> EntityGroupId {
+        self.entity_groups
+            .add()
+            .expect("too many entity groups were added")
+    }
+
+    /// Removes an [entity group] and despawns its entities by sending a
+    /// [`Despawn`] event to each of them. Entities which survive, because a
+    /// listener consumed their `Despawn` event, are moved to the default
+    /// group.
+    ///
+    /// Returns `false` if the group doesn't exist or is the default group,
+    /// which can't be removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let group = world.add_entity_group();
+    /// let e = world.spawn_in(group);
+    ///
+    /// assert!(world.remove_entity_group(group));
+    /// assert!(!world.entities().contains(e));
+    /// assert!(!world.entity_groups().contains(group));
+    /// ```
+    ///
+    /// [entity group]: crate::entity_group
+    pub fn remove_entity_group(&mut self, group: EntityGroupId) -> bool {
+        if !self.entity_groups.remove(group) {
+            return false;
+        }
+
+        let despawn_idx = self.add_event::<Despawn>().index().as_u32();
+
+        for arch in self.archetypes.iter() {
+            let rows = arch.entity_group_rows(group);
+
+            for &entity_id in &arch.entity_ids()[rows.start as usize..rows.end as usize] {
+                unsafe { self.event_queue.push(Despawn(entity_id), despawn_idx) };
+            }
+        }
+
+        self.flush_event_queue(0);
+
+        let survivors = self
+            .archetypes
+            .iter()
+            .flat_map(|arch| {
+                let rows = arch.entity_group_rows(group);
+                &arch.entity_ids()[rows.start as usize..rows.end as usize]
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        for entity_id in survivors {
+            let loc = unsafe { self.entities.get(entity_id).unwrap_debug_checked() };
+
+            unsafe {
+                self.archetypes
+                    .set_entity_group(loc, EntityGroupId::DEFAULT, &mut self.entities)
+            };
+        }
+
+        true
+    }
+
+    /// Returns the [entity group] of an entity, or `None` if the entity
+    /// doesn't exist.
+    ///
+    /// [entity group]: crate::entity_group
+    pub fn entity_group(&self, entity: EntityId) -> Option<EntityGroupId> {
+        let loc = self.entities.get(entity)?;
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+        Some(arch.entity_group_at(loc.row))
+    }
+
+    /// Moves an entity to another [entity group] and returns the group it was
+    /// in before, or `None` if the entity doesn't exist.
+    ///
+    /// The entity keeps its components and no events are sent. The rows of
+    /// other entities in the entity's archetype may change.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the group doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::entity_group::EntityGroupId;
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let group = world.add_entity_group();
+    /// let e = world.spawn();
+    ///
+    /// assert_eq!(
+    ///     world.set_entity_group(e, group),
+    ///     Some(EntityGroupId::DEFAULT)
+    /// );
+    /// assert_eq!(world.entity_group(e), Some(group));
+    /// ```
+    ///
+    /// [entity group]: crate::entity_group
+    #[track_caller]
+    pub fn set_entity_group(
+        &mut self,
+        entity: EntityId,
+        group: EntityGroupId,
+    ) -> Option<EntityGroupId> {
+        assert!(
+            self.entity_groups.contains(group),
+            "entity group {group:?} doesn't exist"
+        );
+
+        let previous = self.entity_group(entity)?;
+        let loc = unsafe { self.entities.get(entity).unwrap_debug_checked() };
+
+        unsafe {
+            self.archetypes
+                .set_entity_group(loc, group, &mut self.entities)
+        };
+
+        Some(previous)
+    }
+
+    /// Returns the number of entities in an [entity group] and the number of
+    /// archetypes they're spread over, or `None` if the group doesn't exist.
+    ///
+    /// [entity group]: crate::entity_group
+    pub fn entity_group_stats(&self, group: EntityGroupId) -> Option<EntityGroupStats> {
+        if !self.entity_groups.contains(group) {
+            return None;
+        }
+
+        let mut stats = EntityGroupStats::default();
+
+        for arch in self.archetypes.iter() {
+            let rows = arch.entity_group_rows(group);
+
+            if !rows.is_empty() {
+                stats.entity_count += rows.end - rows.start;
+                stats.archetype_count += 1;
+            }
+        }
+
+        Some(stats)
+    }
+
+    /// Sends the [`Insert`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// # #[derive(Component)]
+    /// # struct C;
+    /// #
+    /// # let component = C;
+    /// #
+    /// world.send(Insert::new(entity, component));
+    /// ```
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.send(Insert::new(entity, component))
+    }
+
+    /// Sends the [`Remove`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// # #[derive(Component)]
+    /// # struct C;
+    /// #
+    /// world.send(Remove::<C>::new(entity));
+    /// ```
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.send(Remove::<C>::new(entity))
+    }
+
+    /// Sends the [`Despawn`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// world.send(Despawn(entity));
+    /// ```
+    ///
+    /// Despawning an entity which doesn't exist, such as one which was
+    /// already despawned, does nothing. Use [`try_despawn`] to find out
+    /// whether the entity was despawned.
+    ///
+    /// [`try_despawn`]: Self::try_despawn
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.send(Despawn(entity))
+    }
+
+    /// Like [`despawn`], but returns whether `entity` was despawned.
+    ///
+    /// `false` is returned if the entity doesn't exist, in which case no
+    /// [`Despawn`] event is sent, or if a listener consumed the `Despawn`
+    /// event before the entity was removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let entity = world.spawn();
+    ///
+    /// assert!(world.try_despawn(entity));
+    /// assert!(!world.try_despawn(entity));
+    /// ```
+    ///
+    /// [`despawn`]: Self::despawn
+    pub fn try_despawn(&mut self, entity: EntityId) -> bool {
+        if !self.entities.contains(entity) {
+            return false;
+        }
+
+        self.despawn(entity);
+
+        !self.entities.contains(entity)
+    }
+
+    /// Spawns a copy of `entity` with clones of all its components and returns
+    /// the [`EntityId`] of the copy. This is equivalent to
+    /// [`clone_entity_with`] with the default [`CloneOptions`].
+    ///
+    /// Components are cloned with their [clone function], which the
+    /// [`Component`] derive provides for types implementing [`Clone`]. An
+    /// error is returned if any component can't be cloned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, Clone, PartialEq, Debug)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let prefab = world.spawn();
+    /// world.insert(prefab, Health(100));
+    ///
+    /// let copy = world.clone_entity(prefab).unwrap();
+    ///
+    /// assert_ne!(prefab, copy);
+    /// assert_eq!(world.get_component::<Health>(copy), Some(&Health(100)));
+    /// ```
+    ///
+    /// [`clone_entity_with`]: World::clone_entity_with
+    /// [clone function]: ComponentInfo::clone_fn
+    pub fn clone_entity(&mut self, entity: EntityId) -> Result<EntityId, CloneError> {
+        self.clone_entity_with(entity, CloneOptions::default())
+    }
+
+    /// Spawns a copy of `entity` like [`clone_entity`], configured by
+    /// `options`.
+    ///
+    /// The copy is placed directly in the archetype of `entity` and the
+    /// [`Spawn`] event is sent for it. If [`CloneOptions::send_insert_events`]
+    /// is set, components with a registered [`Insert`] event are added by
+    /// sending the event after `Spawn` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::entity::CloneOptions;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, Clone)]
+    /// struct Name(String);
+    ///
+    /// #[derive(Component)]
+    /// struct Connection;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let player = world.spawn();
+    /// world.insert(player, Name("player".into()));
+    /// world.insert(player, Connection);
+    ///
+    /// assert!(world.clone_entity(player).is_err());
+    ///
+    /// let options = CloneOptions {
+    ///     skip_uncloneable: true,
+    ///     ..Default::default()
+    /// };
+    /// let copy = world.clone_entity_with(player, options).unwrap();
+    ///
+    /// assert!(world.get_component::<Name>(copy).is_some());
+    /// assert!(world.get_component::<Connection>(copy).is_none());
+    /// ```
+    ///
+    /// [`clone_entity`]: World::clone_entity
+    pub fn clone_entity_with(
+        &mut self,
+        entity: EntityId,
+        options: CloneOptions,
+    ) -> Result<EntityId, CloneError> {
+        self.check_thread();
+
+        let Some(loc) = self.entities.get(entity) else {
+            return Err(CloneError::NoSuchEntity(entity));
+        };
+
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+        // Components added directly, and components added with `Insert` events.
+        let mut direct = vec![];
+        let mut inserted = vec![];
+        let mut skipped = false;
+
+        for col in arch.columns() {
+            let info = unsafe {
+                self.components
+                    .get_by_index(col.component_index())
+                    .unwrap_debug_checked()
+            };
+
+            let insert_event = info
+                .insert_events()
+                .first()
+                .filter(|_| options.send_insert_events);
+
+            // A direct copy of a singleton would be a second holder, so singletons are
+            // only cloned through their `Insert` event, which applies their policy.
+            let clone = info
+                .clone_fn()
+                .filter(|_| info.singleton().is_none() || insert_event.is_some());
+
+            let Some(clone) = clone else {
+                if options.skip_uncloneable {
+                    skipped = true;
+                    continue;
+                }
+
+                return Err(CloneError::Uncloneable {
+                    component: info.id(),
+                    name: String::from(info.name()).into(),
+                });
+            };
+
+            match insert_event {
+                Some(&event) => inserted.push((col.component_index(), clone, event)),
+                None => direct.push((col.component_index(), clone)),
+            }
+        }
+
+        let dst = if skipped || !inserted.is_empty() {
+            let idxs = direct.iter().map(|&(idx, _)| idx).collect::<Vec<_>>();
+
+            unsafe {
+                self.archetypes.traverse_insert_many(
+                    ArchetypeIdx::EMPTY,
+                    &idxs,
+                    &mut self.components,
+                    &mut self.systems,
+                )
+            }
+            .map_err(CloneError::ArchetypeLimitReached)?
+        } else {
+            loc.archetype
+        };
+
+        let clone_fns = direct.iter().map(|&(_, clone)| clone).collect::<Vec<_>>();
+
+        let id = unsafe {
+            self.archetypes
+                .clone_entity(loc, dst, &clone_fns, &mut self.entities)
         };
 
-        unsafe { self.add_event_with_descriptor(desc) }
+        self.reserved_entities.refresh(&self.entities);
+
+        let start = self.event_queue.len();
+
+        let spawn_idx = self.add_event::<Spawn>().index().as_u32();
+        unsafe { self.event_queue.push(Spawn(id), spawn_idx) };
+
+        self.run_insert_hooks(id, ArchetypeIdx::EMPTY, None);
+
+        // The source entity hasn't moved, since no events have run yet.
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+        for (component_idx, clone, event_id) in inserted {
+            let info = unsafe {
+                self.events
+                    .get_by_index(event_id.index())
+                    .unwrap_debug_checked()
+            };
+
+            let (
+                EventKind::Insert {
+                    component_offset, ..
+                },
+                EventIdx::Targeted(idx),
+            ) = (info.kind(), event_id.index())
+            else {
+                unreachable!("insert event should be a targeted `Insert` event")
+            };
+
+            let col = unsafe { arch.column_of(component_idx).unwrap_debug_checked() };
+            let src = unsafe { col.row_ptr(loc.row.0 as usize) }.as_ptr();
+
+            let event = self.event_queue.alloc_layout(info.layout()).as_ptr();
+
+            unsafe {
+                event.cast::<EntityId>().write(id);
+                clone(src, event.add(component_offset as usize));
+
+                self.event_queue.push_item(EventQueueItem {
+                    meta: EventMeta::Targeted { idx, target: id },
+                    event,
+                });
+            }
+        }
+
+        self.flush_event_queue(start);
+
+        Ok(id)
+    }
+
+    /// Returns `true` if `entity` exists in the world. This is a shorthand for
+    /// `world.entities().contains(entity)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// assert!(world.is_alive(e));
+    ///
+    /// world.despawn(e);
+    /// assert!(!world.is_alive(e));
+    /// ```
+    pub fn is_alive(&self, entity: EntityId) -> bool {
+        self.entities.contains(entity)
+    }
+
+    /// Starts sending [`TargetDespawned`] events to entities whose `H`
+    /// component refers to a despawned entity. Calling this more than once for
+    /// the same `H` has no effect.
+    ///
+    /// This adds a few systems and a global which map targets to the entities
+    /// referring to them, so despawning an entity only visits its own holders.
+    /// See [`TargetHolder`] for an example.
+    ///
+    /// [`TargetDespawned`]: crate::entity::TargetDespawned
+    /// [`TargetHolder`]: crate::entity::TargetHolder
+    pub fn track_targets<H: TargetHolder>(&mut self) {
+        entity::track_targets::<H>(self);
+    }
+
+    /// Gives `entity` a [`Name`], replacing its current name. Does nothing if
+    /// the entity doesn't exist.
+    ///
+    /// This is a shorthand for inserting `Name::new(name)`.
+    #[cfg(feature = "name")]
+    pub fn set_name<N: Into<Cow<'static, str>>>(&mut self, entity: EntityId, name: N) {
+        self.insert(entity, Name::new(name));
+    }
+
+    /// Returns the [`Name`] of `entity`, or `None` if it doesn't exist or has
+    /// no name.
+    #[cfg(feature = "name")]
+    pub fn name(&self, entity: EntityId) -> Option<&str> {
+        self.get_component::<Name>(entity).map(Name::as_str)
+    }
+
+    /// Returns an iterator over the entities [named](Name) `name`, in the
+    /// order they were given the name.
+    ///
+    /// This uses an index of the world's names, so it doesn't need to visit
+    /// every entity.
+    #[cfg(feature = "name")]
+    pub fn find_by_name(&self, name: &str) -> impl Iterator<Item = EntityId> + '_ {
+        self.names.get(name).iter().copied()
+    }
+
+    #[cfg(feature = "name")]
+    pub(crate) fn names_mut(&mut self) -> &mut NameIndex {
+        &mut self.names
+    }
+
+    /// Returns the entity which has the [singleton] component `component`, or
+    /// `None` if no entity has it, the component doesn't exist, or it isn't a
+    /// singleton.
+    ///
+    /// [singleton]: Component::SINGLETON
+    pub fn singleton_holder(&self, component: ComponentId) -> Option<EntityId> {
+        self.components.get(component)?.singleton_holder()
+    }
+
+    /// Returns the entity which has the [singleton] component `C` along with
+    /// the component, or `None` if no entity has it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// #[component(singleton)]
+    /// struct LocalPlayer;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.spawn();
+    /// let b = world.spawn();
+    ///
+    /// world.insert(a, LocalPlayer);
+    /// world.insert(b, LocalPlayer);
+    ///
+    /// assert_eq!(world.singleton::<LocalPlayer>().map(|(e, _)| e), Some(b));
+    /// assert!(world.get_component::<LocalPlayer>(a).is_none());
+    /// ```
+    ///
+    /// [singleton]: Component::SINGLETON
+    pub fn singleton<C: Component>(&self) -> Option<(EntityId, &C)> {
+        let id = self.components.get_by_type_id(TypeId::of::<C>())?.id();
+        let entity = self.singleton_holder(id)?;
+
+        Some((entity, self.get_component::<C>(entity)?))
+    }
+
+    /// Gets an immutable reference to component `C` on `entity`. Returns `None`
+    /// if `entity` doesn't exist or doesn't have the requested component.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct MyComponent(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, MyComponent(123));
+    ///
+    /// assert_eq!(
+    ///     world.get_component::<MyComponent>(e),
+    ///     Some(&MyComponent(123))
+    /// );
+    /// ```
+    pub fn get_component<C: Component>(&self, entity: EntityId) -> Option<&C> {
+        self.check_thread();
+
+        let loc = self.entities.get(entity)?;
+
+        let component_idx = self
+            .components()
+            .get_by_type_id(TypeId::of::<C>())?
+            .id()
+            .index();
+
+        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_debug_checked() };
+
+        let col = arch.column_of(component_idx)?;
+
+        Some(unsafe { col.row_ptr(loc.row.0 as usize).cast::<C>().as_ref() })
+    }
+
+    /// Gets a mutable reference to component `C` on `entity`. Returns `None` if
+    /// `entity` doesn't exist or doesn't have the requested component.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct MyComponent(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, MyComponent(123));
+    ///
+    /// assert_eq!(
+    ///     world.get_component_mut::<MyComponent>(e),
+    ///     Some(&mut MyComponent(123))
+    /// );
+    /// ```
+    pub fn get_component_mut<C: Component>(&mut self, entity: EntityId) -> Option<&mut C> {
+        let () = AssertMutable::<C>::COMPONENT;
+
+        self.check_thread();
+
+        let loc = self.entities.get(entity)?;
+
+        let component_idx = self
+            .components()
+            .get_by_type_id(TypeId::of::<C>())?
+            .id()
+            .index();
+
+        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_debug_checked() };
+
+        let col = arch.column_of(component_idx)?;
+
+        #[cfg(feature = "diff")]
+        col.changes().mark();
+
+        Some(unsafe { col.row_ptr(loc.row.0 as usize).cast::<C>().as_mut() })
+    }
+
+    /// Gets the read-only [`Query`] item for `entity`. Returns `None` if
+    /// `entity` doesn't exist or doesn't match the query.
+    ///
+    /// Unlike [`get_mut`](Self::get_mut), this doesn't add the components the
+    /// query refers to. Components which haven't been added are absent from
+    /// every entity, so `Option<&C>` gives `None` and `Not<&C>` matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct Pos(f32, f32);
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct Vel(f32, f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Pos(1.0, 2.0));
+    ///
+    /// assert_eq!(
+    ///     world.get::<(&Pos, Option<&Vel>)>(e),
+    ///     Some((&Pos(1.0, 2.0), None))
+    /// );
+    /// assert_eq!(world.get::<&Vel>(e), None);
+    /// ```
+    #[track_caller]
+    pub fn get<Q: ReadOnlyQuery>(&self, entity: EntityId) -> Option<Q::Item<'_>> {
+        self.check_thread();
+
+        let (expr, mut state) = Q::init_existing(self)?;
+
+        // SAFETY: The query is read-only.
+        unsafe { self.get_by_query::<Q>(&expr, &mut state, entity) }
+    }
+
+    /// Gets the [`Query`] item for `entity`, possibly with mutable access to
+    /// its components. Returns `None` if `entity` doesn't exist or doesn't
+    /// match the query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query fails to initialize, such as when it accesses the
+    /// same component mutably twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct Pos(f32, f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Pos(1.0, 2.0));
+    ///
+    /// world.get_mut::<&mut Pos>(e).unwrap().0 = 5.0;
+    ///
+    /// assert_eq!(world.get::<&Pos>(e), Some(&Pos(5.0, 2.0)));
+    /// ```
+    #[track_caller]
+    pub fn get_mut<Q: Query>(&mut self, entity: EntityId) -> Option<Q::Item<'_>> {
+        self.check_thread();
+
+        let (expr, mut state) = match Q::init(self, &mut Config::default()) {
+            Ok(res) => res,
+            Err(e) => panic!("{e}"),
+        };
+
+        // SAFETY: We have exclusive access to the world, and the query's access
+        // was validated by `init`.
+        unsafe { self.get_by_query::<Q>(&expr, &mut state, entity) }
+    }
+
+    /// Returns a [`WorldFetcher`] over all entities matching the read-only
+    /// query `Q`.
+    ///
+    /// Like [`get`](Self::get), this takes `&self` and doesn't add the
+    /// components the query refers to. [`WorldFetcher::world`] gives access to
+    /// the rest of the world while the fetcher is alive. Since `World` is
+    /// `Sync`, threads holding `&World` can build and read fetchers at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query has conflicting access.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Pos(f32, f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// for i in 0..10 {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Pos(i as f32, 0.0));
+    /// }
+    ///
+    /// let world = &world;
+    ///
+    /// let sum = std::thread::scope(|s| {
+    ///     let handle = s.spawn(|| world.query::<&Pos>().iter().map(|pos| pos.0).sum::<f32>());
+    ///     handle.join().unwrap()
+    /// });
+    ///
+    /// assert_eq!(sum, 45.0);
+    /// ```
+    ///
+    /// [`WorldFetcher`]: crate::fetch::WorldFetcher
+    /// [`WorldFetcher::world`]: crate::fetch::WorldFetcher::world
+    #[track_caller]
+    pub fn query<Q: ReadOnlyQuery>(&self) -> WorldFetcher<'_, Q> {
+        self.check_thread();
+
+        let Some(mut state) = FetcherState::<Q>::init_existing(self) else {
+            panic!("query `{}` has conflicting access", any::type_name::<Q>());
+        };
+
+        for arch in self.archetypes.iter() {
+            if arch.entity_count() > 0 {
+                state.refresh_archetype(arch);
+            }
+        }
+
+        WorldFetcher::new(state, self)
+    }
+
+    /// # Safety
+    ///
+    /// The caller must have the access described by `expr` to the components
+    /// of the entity for the lifetime of the item.
+    unsafe fn get_by_query<Q: Query>(
+        &self,
+        expr: &ComponentAccessExpr,
+        state: &mut Q::State,
+        entity: EntityId,
+    ) -> Option<Q::Item<'_>> {
+        let loc = self.entities.get(entity)?;
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+        if !arch.matches(&expr.expr) {
+            return None;
+        }
+
+        let arch_state = Q::new_arch_state(arch, state)?;
+
+        // SAFETY: The caller has the required access and `row` is in bounds.
+        Some(unsafe { Q::get(&arch_state, loc.row) })
+    }
+
+    /// Inserts global `T` into the world, returning the previous value if one
+    /// was present.
+    ///
+    /// Globals are accessed in systems with [`Res`] and [`ResMut`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(PartialEq, Debug)]
+    /// struct Score(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// assert_eq!(world.insert_global(Score(1)), None);
+    /// assert_eq!(world.insert_global(Score(2)), Some(Score(1)));
+    /// ```
+    ///
+    /// [`Res`]: crate::global::Res
+    /// [`ResMut`]: crate::global::ResMut
+    pub fn insert_global<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.globals.insert(value)
+    }
+
+    /// Removes global `T` from the world and returns it. Returns `None` if the
+    /// global was not present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(PartialEq, Debug)]
+    /// struct Score(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.insert_global(Score(1));
+    ///
+    /// assert_eq!(world.remove_global::<Score>(), Some(Score(1)));
+    /// assert_eq!(world.global::<Score>(), None);
+    /// ```
+    pub fn remove_global<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.globals.remove()
+    }
+
+    /// Gets an immutable reference to global `T`. Returns `None` if the global
+    /// is not present.
+    pub fn global<T: 'static>(&self) -> Option<&T> {
+        self.globals.get()
+    }
+
+    /// Gets a mutable reference to global `T`. Returns `None` if the global is
+    /// not present.
+    pub fn global_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.globals.get_mut()
+    }
+
+    /// Returns the [`EntityLocation`] of `entity`, or `None` if the entity
+    /// doesn't exist.
+    ///
+    /// The location is only valid until the next structural change to the
+    /// world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// let loc = world.entity_location(e).unwrap();
+    ///
+    /// assert_eq!(
+    ///     world.archetypes().entity_at(loc.archetype, loc.row),
+    ///     Some(e)
+    /// );
+    /// ```
+    pub fn entity_location(&self, entity: EntityId) -> Option<EntityLocation> {
+        self.entities.get(entity)
+    }
+
+    /// Returns an iterator over the entities in an archetype along with
+    /// pointers to each of their components, or `None` if the archetype index
+    /// is invalid.
+    ///
+    /// Each entity is yielded with an iterator over `(component, pointer,
+    /// layout)` triples in ascending order of [`ComponentIdx`]. This allows
+    /// walking component data without knowing the component types at compile
+    /// time. The pointers are valid for reads until the world is next
+    /// modified. Pointers to zero-sized components are dangling but aligned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C(123));
+    ///
+    /// let loc = world.entity_location(e).unwrap();
+    ///
+    /// for (entity, components) in world.iter_archetype(loc.archetype).unwrap() {
+    ///     assert_eq!(entity, e);
+    ///
+    ///     for (_idx, ptr, layout) in components {
+    ///         assert_eq!(layout.size(), 4);
+    ///         assert_eq!(unsafe { *ptr.cast::<u32>().as_ptr() }, 123);
+    ///     }
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn iter_archetype(
+        &self,
+        idx: ArchetypeIdx,
+    ) -> Option<
+        impl Iterator<
+                Item = (
+                    EntityId,
+                    impl Iterator<Item = (ComponentIdx, NonNull<u8>, Layout)> + '_,
+                ),
+            > + '_,
+    > {
+        self.check_thread();
+
+        let arch = self.archetypes.get(idx)?;
+
+        Some(arch.entity_ids().iter().enumerate().map(move |(row, &id)| {
+            let components = arch.columns().iter().map(move |col| {
+                // SAFETY: `row` is in bounds of the column. Zero-sized layouts
+                // yield the column's dangling base pointer.
+                let ptr = unsafe { col.row_ptr(row) };
+
+                (col.component_index(), ptr, col.layout())
+            });
+
+            (id, components)
+        }))
+    }
+
+    /// Copies the data of a [plain data] component out of every archetype
+    /// containing it into the contiguous buffer `out`, along with the IDs of
+    /// the entities into `entities`. Both buffers are cleared first, so they
+    /// can be reused between calls.
+    ///
+    /// Each column is copied at once instead of visiting every entity. The
+    /// return value holds the range of `entities` belonging to each
+    /// archetype. The component of the entity at `entities[i]` is stored at
+    /// byte offset `i * size` of `out`, where `size` is the size of the
+    /// component's layout.
+    ///
+    /// The buffer holds [`MaybeUninit`] bytes because the component may
+    /// contain padding, and it's only aligned to one byte, so components
+    /// should be read out of it with [`ptr::read_unaligned`]. Use
+    /// [`extract_column`] to copy a typed component instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component doesn't exist or isn't [plain data].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::mem::MaybeUninit;
+    ///
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, Clone, Copy)]
+    /// struct Transform([f32; 2]);
+    ///
+    /// let mut world = World::new();
+    /// let idx = world.add_component::<Transform>().index();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Transform([1.0, 2.0]));
+    ///
+    /// let mut out: Vec<MaybeUninit<u8>> = vec![];
+    /// let mut entities = vec![];
+    /// let ranges = world.copy_columns(idx, &mut out, &mut entities);
+    ///
+    /// assert_eq!(ranges.len(), 1);
+    /// assert_eq!(entities, [e]);
+    ///
+    /// let transform = unsafe { out.as_ptr().cast::<Transform>().read_unaligned() };
+    /// assert_eq!(transform.0, [1.0, 2.0]);
+    /// ```
+    ///
+    /// [plain data]: ComponentInfo::is_pod
+    /// [`extract_column`]: World::extract_column
+    #[track_caller]
+    pub fn copy_columns(
+        &self,
+        component: ComponentIdx,
+        out: &mut Vec<MaybeUninit<u8>>,
+        entities: &mut Vec<EntityId>,
+    ) -> Vec<(ArchetypeIdx, Range<usize>)> {
+        self.check_thread();
+
+        let Some(info) = self.components.get_by_index(component) else {
+            panic!("no such component with index of {component:?} exists")
+        };
+
+        assert!(
+            info.is_pod(),
+            "component `{}` is not plain data",
+            info.name()
+        );
+
+        let size = info.layout().size();
+
+        out.clear();
+        let ranges = self.collect_columns(component, entities);
+        out.reserve(entities.len() * size);
+
+        for (arch, range) in &ranges {
+            let arch = unsafe { self.archetypes.get(*arch).unwrap_debug_checked() };
+            let col = unsafe { arch.column_of(component).unwrap_debug_checked() };
+
+            // SAFETY: `out` has room for every entity in `entities`.
+            unsafe {
+                col.copy_to(range.len(), out.as_mut_ptr().add(range.start * size).cast());
+            }
+        }
+
+        unsafe { out.set_len(entities.len() * size) };
+
+        ranges
+    }
+
+    /// Copies a component out of every archetype containing it into `out`,
+    /// like [`copy_columns`] but typed.
+    ///
+    /// The component at `out[i]` belongs to the entity at `entities[i]`.
+    /// Both buffers are left empty if the component hasn't been added to the
+    /// world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, Clone, Copy, PartialEq, Debug)]
+    /// struct MeshHandle(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e1 = world.spawn();
+    /// world.insert(e1, MeshHandle(1));
+    /// let e2 = world.spawn();
+    /// world.insert(e2, MeshHandle(2));
+    ///
+    /// let mut meshes: Vec<MeshHandle> = vec![];
+    /// let mut entities = vec![];
+    /// world.extract_column(&mut meshes, &mut entities);
+    ///
+    /// assert_eq!(entities, [e1, e2]);
+    /// assert_eq!(meshes, [MeshHandle(1), MeshHandle(2)]);
+    /// ```
+    ///
+    /// [`copy_columns`]: World::copy_columns
+    pub fn extract_column<C: Component + Copy>(
+        &self,
+        out: &mut Vec<C>,
+        entities: &mut Vec<EntityId>,
+    ) -> Vec<(ArchetypeIdx, Range<usize>)> {
+        self.check_thread();
+
+        out.clear();
+
+        let Some(info) = self.components.get_by_type_id(TypeId::of::<C>()) else {
+            entities.clear();
+            return vec![];
+        };
+
+        let component = info.id().index();
+
+        let ranges = self.collect_columns(component, entities);
+        out.reserve(entities.len());
+
+        for (arch, range) in &ranges {
+            let arch = unsafe { self.archetypes.get(*arch).unwrap_debug_checked() };
+            let col = unsafe { arch.column_of(component).unwrap_debug_checked() };
+
+            // SAFETY: `out` has room for every entity in `entities`.
+            unsafe { col.copy_to(range.len(), out.as_mut_ptr().add(range.start).cast()) };
+        }
+
+        unsafe { out.set_len(entities.len()) };
+
+        ranges
+    }
+
+    /// Clears `entities` and fills it with the entities of every archetype
+    /// containing `component`, returning the range of each archetype.
+    fn collect_columns(
+        &self,
+        component: ComponentIdx,
+        entities: &mut Vec<EntityId>,
+    ) -> Vec<(ArchetypeIdx, Range<usize>)> {
+        entities.clear();
+
+        let mut ranges = vec![];
+
+        for arch in self.archetypes.iter() {
+            if arch.entity_count() == 0 || arch.column_of(component).is_none() {
+                continue;
+            }
+
+            let start = entities.len();
+            entities.extend_from_slice(arch.entity_ids());
+            ranges.push((arch.index(), start..entities.len()));
+        }
+
+        ranges
+    }
+
+    /// Inserts a clone of `component` on every entity matching the query
+    /// filter `F`. If an entity already has the component, then it is
+    /// replaced.
+    ///
+    /// This is a bulk operation: each matching archetype is moved to its
+    /// destination archetype in a single pass rather than one entity at a
+    /// time. Required components missing from an entity are inserted with
+    /// their default values.
+    ///
+    /// Unlike [`insert`], no [`Insert`] or [`Replaced`] events are sent for
+    /// the affected entities, although [component hooks] still run. Entities
+    /// which would need a new archetype when the world has no room for one
+    /// are left unchanged, and an [`ArchetypeLimitReached`] event is sent
+    /// for each of their archetypes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `F` fails to initialize, or if `C` is a
+    /// [singleton](Component::SINGLETON).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Selected;
+    ///
+    /// #[derive(Component, Clone)]
+    /// struct Outline;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e1 = world.spawn();
+    /// world.insert(e1, Selected);
+    /// let e2 = world.spawn();
+    ///
+    /// world.insert_for_all::<With<&Selected>, _>(Outline);
+    ///
+    /// assert!(world.get_component::<Outline>(e1).is_some());
+    /// assert!(world.get_component::<Outline>(e2).is_none());
+    /// ```
+    ///
+    /// [`insert`]: World::insert
+    /// [`Replaced`]: crate::event::Replaced
+    /// [component hooks]: ComponentDescriptor::on_insert
+    #[track_caller]
+    pub fn insert_for_all<F: Query, C: Component + Clone>(&mut self, component: C) {
+        self.check_thread();
+
+        assert!(
+            C::SINGLETON.is_none(),
+            "singleton component `{}` can't be inserted on every matching entity",
+            C::name()
+        );
+
+        let (expr, _) = match F::init(self, &mut Config::default()) {
+            Ok(res) => res,
+            Err(e) => panic!("{e}"),
+        };
+
+        let component_idx = self.add_component::<C>().index();
+
+        let matching = self
+            .archetypes
+            .iter()
+            .filter(|arch| arch.entity_count() > 0 && arch.matches(&expr.expr))
+            .map(|arch| arch.index())
+            .collect::<Vec<_>>();
+
+        let hooks_start = self.event_queue.len();
+
+        // Replace existing values first so entities moved into these archetypes below
+        // aren't visited twice.
+        for &arch_idx in &matching {
+            let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+
+            if arch.column_of(component_idx).is_none() {
+                continue;
+            }
+
+            for row in 0..arch.entity_count() as usize {
+                let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+                let entity = unsafe { *arch.entity_ids().get_debug_checked(row) };
+
+                self.run_remove_hooks(entity, arch_idx, Some(component_idx));
+
+                let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+                let col = unsafe { arch.column_of(component_idx).unwrap_debug_checked() };
+                unsafe { *col.row_ptr(row).cast::<C>().as_ptr() = component.clone() };
+
+                self.run_insert_hooks(entity, arch_idx, Some(component_idx));
+            }
+        }
+
+        let required = unsafe {
+            self.components
+                .get_by_index(component_idx)
+                .unwrap_debug_checked()
+        }
+        .required()
+        .to_vec();
+
+        // The required components might have been removed from the world.
+        let mut inserted = required
+            .iter()
+            .filter(|(id, _)| self.components.get(*id).is_some())
+            .map(|(id, _)| id.index())
+            .chain([component_idx])
+            .collect::<Vec<_>>();
+
+        inserted.sort_unstable();
+        inserted.dedup();
+
+        let mut limits_reached = vec![];
+
+        for src in matching {
+            if unsafe { self.archetypes.get(src).unwrap_debug_checked() }.entity_count() == 0 {
+                continue;
+            }
+
+            let dst = match unsafe {
+                self.archetypes.traverse_insert_many(
+                    src,
+                    &inserted,
+                    &mut self.components,
+                    &mut self.systems,
+                )
+            } {
+                Ok(dst) => dst,
+                Err(limit) => {
+                    limits_reached.push(limit);
+                    continue;
+                }
+            };
+
+            if dst == src {
+                continue;
+            }
+
+            let moved = if self.components.has_hooks() {
+                unsafe { self.archetypes.get(src).unwrap_debug_checked() }
+                    .entity_ids()
+                    .to_vec()
+            } else {
+                vec![]
+            };
+
+            unsafe {
+                self.archetypes.move_all_entities(
+                    src,
+                    dst,
+                    |idx, ptr| {
+                        if idx == component_idx {
+                            ptr.as_ptr().cast::<C>().write(component.clone());
+                        } else if let Some(&(_, default)) =
+                            required.iter().find(|(id, _)| id.index() == idx)
+                        {
+                            default(ptr);
+                        }
+                    },
+                    &mut self.entities,
+                )
+            };
+
+            for entity in moved {
+                self.run_insert_hooks(entity, src, None);
+            }
+        }
+
+        self.flush_event_queue(hooks_start);
+
+        if self
+            .events
+            .get_by_type_id(TypeId::of::<ArchetypeLimitReached>())
+            .is_some()
+        {
+            for limit in limits_reached {
+                self.send(limit);
+            }
+        }
+    }
+
+    /// Despawns every entity matching the query filter `F`.
+    ///
+    /// This is a bulk operation: the entities of each matching archetype are
+    /// removed together, dropping their components one column at a time,
+    /// instead of sending a [`Despawn`] event per entity. Component hooks
+    /// still run for every entity, and a [`Despawned`] event is sent for each
+    /// of them afterwards.
+    ///
+    /// Entities in archetypes with [`Despawn`] listeners are despawned one at
+    /// a time with a `Despawn` event so that the listeners run before the
+    /// entity is removed. This happens after the other entities are removed.
+    /// Use [`despawn_matching_without_events`] to remove
+    /// them in bulk without sending any events.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `F` fails to initialize.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Dead;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e1 = world.spawn();
+    /// world.insert(e1, Dead);
+    /// let e2 = world.spawn();
+    ///
+    /// world.despawn_matching::<With<&Dead>>();
+    ///
+    /// assert!(!world.entities().contains(e1));
+    /// assert!(world.entities().contains(e2));
+    /// ```
+    ///
+    /// [`despawn_matching_without_events`]: World::despawn_matching_without_events
+    #[track_caller]
+    pub fn despawn_matching<F: Query>(&mut self) {
+        self.despawn_matching_impl::<F>(true);
+    }
+
+    /// Like [`despawn_matching`], but doesn't send [`Despawn`] or
+    /// [`Despawned`] events, so every matching archetype is removed in bulk.
+    /// Component hooks still run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `F` fails to initialize.
+    ///
+    /// [`despawn_matching`]: World::despawn_matching
+    #[track_caller]
+    pub fn despawn_matching_without_events<F: Query>(&mut self) {
+        self.despawn_matching_impl::<F>(false);
+    }
+
+    #[track_caller]
+    fn despawn_matching_impl<F: Query>(&mut self, send_events: bool) {
+        self.check_thread();
+
+        let (expr, _) = match F::init(self, &mut Config::default()) {
+            Ok(res) => res,
+            Err(e) => panic!("{e}"),
+        };
+
+        let despawn_idx = match self.events.get_by_type_id(TypeId::of::<Despawn>()) {
+            Some(info) if send_events => match info.id().index() {
+                EventIdx::Targeted(idx) => Some(idx),
+                EventIdx::Untargeted(_) => None,
+            },
+            _ => None,
+        };
+
+        let mut bulk = vec![];
+        let mut one_by_one = vec![];
+
+        for arch in self.archetypes.iter() {
+            if arch.entity_count() == 0 || !arch.matches(&expr.expr) {
+                continue;
+            }
+
+            let has_listeners = despawn_idx
+                .and_then(|idx| arch.system_list_for(idx))
+                .is_some_and(|list| !list.systems().is_empty());
+
+            if has_listeners {
+                one_by_one.extend_from_slice(arch.entity_ids());
+            } else {
+                bulk.push(arch.index());
+            }
+        }
+
+        let despawned_idx = match self.events.get_by_type_id(TypeId::of::<Despawned>()) {
+            Some(info) if send_events => Some(info.id().index().as_u32()),
+            _ => None,
+        };
+
+        let hooks_start = self.event_queue.len();
+
+        for arch_idx in bulk {
+            let count = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() }
+                .entity_count() as usize;
+
+            for row in 0..count {
+                let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+                let entity = unsafe { *arch.entity_ids().get_debug_checked(row) };
+
+                self.run_remove_hooks(entity, ArchetypeIdx::EMPTY, None);
+            }
+
+            if let Some(idx) = despawned_idx {
+                let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+
+                for &entity in arch.entity_ids() {
+                    unsafe { self.event_queue.push(Despawned(entity), idx) };
+                }
+            }
+
+            self.archetypes
+                .remove_all_entities(arch_idx, &mut self.entities);
+        }
+
+        self.reserved_entities.refresh(&self.entities);
+
+        // Broadcast the events sent by hooks, then `Despawned`.
+        self.flush_event_queue(hooks_start);
+
+        for entity in one_by_one {
+            self.despawn(entity);
+        }
+    }
+
+    /// Runs a [`DynamicQuery`] over all matching entities, passing each
+    /// entity and its fetched component pointers to `f`.
+    ///
+    /// Returns an [`InitError`] if the query is invalid, such as when it
+    /// accesses a component mutably and immutably at the same time.
+    pub fn query_dynamic<F>(&mut self, query: &DynamicQuery, f: F) -> Result<(), InitError>
+    where
+        F: FnMut(EntityId, &[NonNull<u8>]),
+    {
+        self.check_thread();
+
+        let mut state = DynamicFetcherState::init(query, self, &mut Config::default())?;
+
+        for arch in self.archetypes.iter() {
+            if arch.entity_count() > 0 {
+                state.refresh_archetype(arch);
+            }
+        }
+
+        // SAFETY: We have exclusive access to the world.
+        unsafe { state.fetcher(self.unsafe_cell_mut()) }.for_each_mut(f);
+
+        Ok(())
+    }
+
+    /// Adds a new system to the world, returns its [`SystemId`], and sends the
+    /// [`AddSystem`] event to signal its creation.
+    ///
+    /// If the system already exists (as determined by [`System::type_id`]) then
+    /// the `SystemId` of the existing system is returned and no event is sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration of the system is invalid. This can occur
+    /// when, for instance, the system does not specify an event to receive.
+    ///
+    /// ```should_panic
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// world.add_system(|| {}); // Panics
+    /// ```
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// # #[derive(Event)]
+    /// # struct MyEvent;
+    ///
+    /// fn my_system(_: Receiver<MyEvent>) {};
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_system(my_system);
+    ///
+    /// assert!(world.systems().contains(id));
+    /// ```
+    ///
+    /// [`System::type_id`]: crate::system::System::type_id
+    #[track_caller]
+    pub fn add_system<S: IntoSystem<M>, M>(&mut self, system: S) -> SystemId {
+        let mut system = system.into_system();
+        let mut config = Config::default();
+
+        let type_id = system.type_id();
+
+        if let Some(type_id) = type_id {
+            if let Some(info) = self.systems.get_by_type_id(type_id) {
+                return info.id();
+            }
+        }
+
+        if let Err(e) = system.init(self, &mut config) {
+            panic!("{e}");
+        }
+
+        let Some(received_event) = config.received_event else {
+            panic!(
+                "system `{}` did not specify an event to receive. All systems must listen for \
+                 exactly one event type (see `Receiver`)",
+                any::type_name::<S>()
+            )
+        };
+
+        let info = SystemInfo::new(SystemInfoInner {
+            name: system.name(),
+            id: SystemId::NULL, // Filled in later.
+            received_event,
+            other_received_events: config.other_received_events.into_boxed_slice(),
+            received_event_access: config.received_event_access,
+            targeted_event_expr: config.targeted_event_expr,
+            sent_untargeted_events: config.sent_untargeted_events,
+            sent_targeted_events: config.sent_targeted_events,
+            event_queue_access: config.event_queue_access,
+            component_access: config.component_access,
+            referenced_components: config.referenced_components,
+            global_access: config.global_access,
+            priority: config.priority,
+            sub_priority: config.sub_priority,
+            order: 0, // Filled in later.
+            type_id,
+            #[cfg(feature = "stats")]
+            stats: Default::default(),
+            system,
+        });
+
+        let id = self.systems.add(info);
+        let info = self.systems.get_mut(id).unwrap();
+
+        self.archetypes.register_system(info);
+
+        #[cfg(feature = "diagnostics")]
+        self.refresh_diagnostic_listeners();
+
+        self.send(AddSystem(id));
+
+        id
+    }
+
+    /// Removes a system from the world, returns its [`SystemInfo`], and sends
+    /// the [`RemoveSystem`] event. If the `system` ID is invalid, then `None`
+    /// is returned and no event is sent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// # #[derive(Event)]
+    /// # struct MyEvent;
+    /// let system_id = world.add_system(|_: Receiver<MyEvent>| {});
+    ///
+    /// let info = world.remove_system(system_id).unwrap();
+    ///
+    /// assert_eq!(info.id(), system_id);
+    /// assert!(!world.systems().contains(system_id));
+    /// ```
+    pub fn remove_system(&mut self, system: SystemId) -> Option<SystemInfo> {
+        if !self.systems.contains(system) {
+            return None;
+        }
+
+        self.send(RemoveSystem(system));
+
+        let info = self.systems.remove(system).unwrap();
+
+        self.archetypes.remove_system(&info);
+
+        #[cfg(feature = "diagnostics")]
+        self.refresh_diagnostic_listeners();
+
+        Some(info)
+    }
+
+    /// Adds the component `C` to the world, returns its [`ComponentId`], and
+    /// sends the [`AddComponent`] event to signal its creation.
+    ///
+    /// If the component already exists, then the [`ComponentId`] of the
+    /// existing component is returned and no event is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct MyComponent;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_component::<MyComponent>();
+    ///
+    /// assert_eq!(id, world.add_component::<MyComponent>());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [required components] of `C` form a cycle, or if the
+    /// world has no room for more components. See [`try_add_component`] for
+    /// a version which returns an error instead.
+    ///
+    /// [required components]: Component::required
+    /// [`try_add_component`]: World::try_add_component
+    #[track_caller]
+    pub fn add_component<C: Component>(&mut self) -> ComponentId {
+        match self.try_add_component::<C>() {
+            Ok(id) => id,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Like [`add_component`], but returns an [`AddComponentError`] instead
+    /// of panicking if the world has no room for more components, the
+    /// component's [key](Component::KEY) is taken by a component with a
+    /// different layout, or the [required components] of `C` form a cycle.
+    ///
+    /// The errors of required components are returned as well. Required
+    /// components added before the error remain in the world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct MyComponent;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// match world.try_add_component::<MyComponent>() {
+    ///     Ok(id) => assert!(world.components().contains(id)),
+    ///     Err(e) => eprintln!("{e}"),
+    /// }
+    /// ```
+    ///
+    /// [`add_component`]: World::add_component
+    /// [required components]: Component::required
+    pub fn try_add_component<C: Component>(&mut self) -> Result<ComponentId, AddComponentError> {
+        let type_id = TypeId::of::<C>();
+
+        if let Some(info) = self.components.get_by_type_id(type_id) {
+            return Ok(info.id());
+        }
+
+        if self.components.pending.contains(&type_id) {
+            return Err(AddComponentError::RequirementCycle {
+                name: any::type_name::<C>(),
+            });
+        }
+
+        let mut required = RequiredComponents::new(self, type_id);
+        C::required(&mut required);
+        let required = required.into_inner()?;
+
+        let desc = ComponentDescriptor {
+            name: C::name().into(),
+            type_id: Some(type_id),
+            key: C::KEY.map(Cow::Borrowed),
+            layout: Layout::new::<C>(),
+            drop: drop_fn_of::<C>(),
+            copy_fn: Some(copy_fn_of::<C>()),
+            clone_fn: C::clone_fn(),
+            default_capacity: 0,
+            is_chunked: C::IS_CHUNKED,
+            preserve_order: C::PRESERVE_ORDER,
+            is_immutable: C::IS_IMMUTABLE,
+            is_local: C::IS_LOCAL,
+            singleton: C::SINGLETON,
+            is_pod: C::is_pod(),
+            required,
+            on_insert: C::on_insert(),
+            on_remove: C::on_remove(),
+            validate: C::validate(),
+        };
+
+        unsafe { self.try_add_component_with_descriptor(desc) }
+    }
+
+    /// Adds a component described by a given [`ComponentDescriptor`].
+    ///
+    /// Like [`add_component`], an [`AddComponent`] event is sent if the
+    /// component is newly added. If the [`TypeId`] or
+    /// [key](ComponentDescriptor::key) of the component matches an existing
+    /// component, then the existing component's [`ComponentId`] is returned
+    /// and no event is sent.
+    ///
+    /// # Safety
+    ///
+    /// - If the component is given a [`TypeId`], then the `layout` and `drop`
+    ///   function must be compatible with the Rust type identified by the type
+    ///   ID, and `is_chunked` must match [`Component::IS_CHUNKED`] of the type.
+    /// - If the component is given a key, then it must describe the same type
+    ///   as any other component with the key.
+    /// - Drop function must be safe to call with a pointer to the component as
+    ///   described by [`DropFn`]'s documentation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the world has no room for more components, or if the key is
+    /// taken by a component with a different layout.
+    ///
+    /// [`add_component`]: World::add_component
+    #[track_caller]
+    pub unsafe fn add_component_with_descriptor(
+        &mut self,
+        desc: ComponentDescriptor,
+    ) -> ComponentId {
+        match self.try_add_component_with_descriptor(desc) {
+            Ok(id) => id,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Like [`add_component_with_descriptor`], but returns an
+    /// [`AddComponentError`] instead of panicking if the world has no room for
+    /// more components or the [key](ComponentDescriptor::key) is taken by a
+    /// component with a different layout.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`add_component_with_descriptor`].
+    ///
+    /// [`add_component_with_descriptor`]: World::add_component_with_descriptor
+    pub unsafe fn try_add_component_with_descriptor(
+        &mut self,
+        desc: ComponentDescriptor,
+    ) -> Result<ComponentId, AddComponentError> {
+        #[cfg(feature = "std")]
+        let is_local = desc.is_local;
+
+        let (id, is_new) = self.components.add(desc)?;
+
+        #[cfg(feature = "std")]
+        if is_new && is_local && self.owner_thread.is_none() {
+            self.owner_thread = Some(std::thread::current().id());
+        }
+
+        if is_new {
+            #[cfg(feature = "diagnostics")]
+            self.archetypes.diagnostics.component_registered(id);
+
+            self.send_notice(AddComponent(id));
+        }
+
+        Ok(id)
+    }
+
+    /// Returns the ID of the component with the given
+    /// [key](Component::KEY), or `None` if no component has the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// #[component(uuid = "6f3b2a9e-4c1d-4e8a-9b7f-2d5c8e1a0b3c")]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_component::<Health>();
+    ///
+    /// assert_eq!(
+    ///     world.component_id_by_key("6f3b2a9e-4c1d-4e8a-9b7f-2d5c8e1a0b3c"),
+    ///     Some(id)
+    /// );
+    /// ```
+    pub fn component_id_by_key(&self, key: &str) -> Option<ComponentId> {
+        self.components.get_by_key(key).map(|info| info.id())
+    }
+
+    /// Removes a component from the world and returns its [`ComponentInfo`]. If
+    /// the `component` ID is invalid, then `None` is returned and the function
+    /// has no effect.
+    ///
+    /// Removing a component has the following effects in the order listed:
+    /// 1. The [`RemoveComponent`] event is sent.
+    /// 2. All entities with the component are despawned.
+    /// 3. All systems that reference the component are removed.
+    /// 4. The corresponding [`Insert`] events for the component are removed.
+    /// 5. The corresponding [`Remove`] events for the component are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # let mut world = World::new();
+    /// # #[derive(Component)] struct C;
+    /// # #[derive(Event)] struct E;
+    /// #
+    /// let component = world.add_component::<C>();
+    /// let system = world.add_system(|_: Receiver<E>, _: Fetcher<&C>| {});
+    ///
+    /// assert!(world.components().contains(component));
+    /// assert!(world.systems().contains(system));
+    ///
+    /// world.remove_component(component);
+    ///
+    /// assert!(!world.components().contains(component));
+    /// // System was also removed because it references `C` in its `Fetcher`.
+    /// assert!(!world.systems().contains(system));
+    /// ```
+    pub fn remove_component(&mut self, component: ComponentId) -> Option<ComponentInfo> {
+        if !self.components.contains(component) {
+            return None;
+        }
+
+        // Send event first.
+        self.send(RemoveComponent(component));
+
+        let despawn_idx = self.add_event::<Despawn>().index().as_u32();
+
+        // Attempt to despawn all entities that still have this component.
+        for arch in self.archetypes.iter() {
+            if arch.column_of(component.index()).is_some() {
+                for &entity_id in arch.entity_ids() {
+                    unsafe { self.event_queue.push(Despawn(entity_id), despawn_idx) };
+                }
+            }
+        }
+
+        self.flush_event_queue(0);
+
+        // Remove all systems that reference this component.
+        let mut systems_to_remove = vec![];
+
+        for sys in self.systems.iter() {
+            if sys.referenced_components().contains(component.index()) {
+                systems_to_remove.push(sys.id());
+            }
+        }
+
+        for sys_id in systems_to_remove {
+            self.remove_system(sys_id);
+        }
+
+        let info = &self.components[component];
+
+        // Remove all the `Insert`, `Remove`, `Removed`, and `Replaced` events for this
+        // component, along with the events whose target filter refers to it.
+        let events_to_remove = info
+            .insert_events()
+            .iter()
+            .copied()
+            .chain(info.remove_events().iter().copied())
+            .chain(info.removed_event())
+            .chain(info.replaced_event())
+            .chain(
+                self.events
+                    .iter()
+                    .filter(|e| e.target_filter().references(component.index()))
+                    .map(|e| e.id()),
+            )
+            .collect::<Vec<_>>();
+
+        for event in events_to_remove {
+            self.remove_event(event);
+        }
+
+        // Remove all archetypes with this component. If there are still entities with
+        // the component by this point, then they will be silently removed.
+        self.archetypes
+            .remove_component(component.index(), |entity_id| {
+                self.entities.remove(entity_id);
+            });
+
+        // The hooks maintaining the index of names may not have run for every
+        // removed entity.
+        #[cfg(feature = "name")]
+        if self.components[component].type_id() == Some(TypeId::of::<Name>()) {
+            self.names.clear();
+        }
+
+        self.components.remove(component)
+    }
+
+    /// Adds the [`ComponentGroup`] `G` to the world along with its members, and
+    /// returns the index of the group.
+    ///
+    /// If the group already exists, then the index of the existing group is
+    /// returned. Groups are also added by the [`Group`] and [`GroupMut`]
+    /// queries, so this is only needed to look up a group's archetypes before
+    /// it is queried.
+    ///
+    /// [`Group`]: crate::group::Group
+    /// [`GroupMut`]: crate::group::GroupMut
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct A;
+    ///
+    /// #[derive(Component)]
+    /// struct B;
+    ///
+    /// struct AB;
+    ///
+    /// impl ComponentGroup for AB {
+    ///     type Members = (A, B);
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// let group = world.add_group::<AB>();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, A);
+    /// world.insert(e, B);
+    ///
+    /// assert_eq!(world.archetypes().archetypes_with_group(group).count(), 1);
+    /// ```
+    pub fn add_group<G: ComponentGroup>(&mut self) -> GroupIdx {
+        if let Some(idx) = self.archetypes.groups().get_by_type_id(TypeId::of::<G>()) {
+            return idx;
+        }
+
+        let components = G::Members::add_components(self);
+
+        self.archetypes
+            .add_group(any::type_name::<G>().into(), TypeId::of::<G>(), components)
+    }
+
+    /// Adds the event `E` to the world, returns its [`EventId`], and sends the
+    /// [`AddEvent`] event to signal its creation.
+    ///
+    /// If the event already exists, then the [`EventId`] of the existing event
+    /// is returned and no event is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_event::<MyEvent>();
+    ///
+    /// assert_eq!(id, world.add_event::<MyEvent>());
+    /// ```
+    pub fn add_event<E: Event>(&mut self) -> EventId {
+        let target_filter = {
+            let mut filter = TargetFilter::new(self);
+            E::target_filter(&mut filter);
+            filter.into_inner()
+        };
+
+        let desc = EventDescriptor {
+            name: any::type_name::<E>().into(),
+            type_id: Some(TypeId::of::<E>()),
+            key: E::KEY.map(Cow::Borrowed),
+            is_targeted: E::IS_TARGETED,
+            kind: unsafe { E::init(self) },
+            layout: Layout::new::<E>(),
+            drop: drop_fn_of::<E>(),
+            clone_fn: E::clone_fn(),
+            default_fn: E::default_fn(),
+            is_immutable: E::IS_IMMUTABLE,
+            is_deferred: E::IS_DEFERRED,
+            bubbles: set_target_fn_of::<E>(),
+            targets: targets_fn_of::<E>(),
+            target_filter,
+        };
+
+        unsafe { self.add_event_with_descriptor(desc) }
+    }
+
+    /// Adds an event described by a given [`EventDescriptor`].
+    ///
+    /// Like [`add_event`], an [`AddEvent`] event is sent if the
+    /// event is newly added. If the [`TypeId`] or
+    /// [key](EventDescriptor::key) of the event matches an existing event,
+    /// then the existing event's [`EventId`] is returned and no event is sent.
+    ///
+    /// # Safety
+    ///
+    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
+    ///   function must be compatible with the Rust type identified by the type
+    ///   ID.
+    /// - If the event is given a key, then it must describe the same type as
+    ///   any other event with the key.
+    /// - Drop function must be safe to call with a pointer to the event as
+    ///   described by [`DropFn`]'s documentation.
+    /// - The event's kind must be correct for the descriptor. See
+    ///   [`EventKind`]'s documentation for more information.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is taken by an event with a different layout or
+    /// targeting.
+    ///
+    /// [`add_event`]: World::add_event
+    #[track_caller]
+    pub unsafe fn add_event_with_descriptor(&mut self, desc: EventDescriptor) -> EventId {
+        let kind = desc.kind;
+
+        let (id, is_new) = self.events.add(desc);
+
+        if is_new {
+            self.systems.register_event(id.index());
+
+            match kind {
+                EventKind::Normal => {}
+                EventKind::Insert { component_idx, .. } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.insert_events.insert(id);
+                    }
+                }
+                EventKind::Remove { component_idx } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.remove_events.insert(id);
+                    }
+                }
+                EventKind::Removed { component_idx } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.removed_event = Some(id);
+                    }
+                }
+                EventKind::Replaced { component_idx, .. } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.replaced_event = Some(id);
+                    }
+                }
+                EventKind::SpawnQueued => {}
+                EventKind::Despawn => {}
+            }
+
+            self.send_notice(AddEvent(id));
+        }
+
+        id
+    }
+
+    /// Returns the ID of the event with the given [key](Event::KEY), or `None`
+    /// if no event has the key.
+    pub fn event_id_by_key(&self, key: &str) -> Option<EventId> {
+        self.events.get_by_key(key).map(|info| info.id())
+    }
+
+    /// Removes an event from the world and returns its [`EventInfo`]. If
+    /// the `event` ID is invalid, then `None` is returned and the function
+    /// has no effect.
+    ///
+    /// Removing an event has the following effects in the order listed:
+    /// 1. The [`RemoveEvent`] event is sent.
+    /// 2. All systems that send or receive the event are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_event::<MyEvent>();
+    /// world.remove_event(id);
+    ///
+    /// assert!(!world.events().contains(id));
+    /// ```
+    pub fn remove_event(&mut self, event: EventId) -> Option<EventInfo> {
+        assert!(self.event_queue.is_empty());
+
+        if !self.events.contains(event) || event == EventId::SPAWN_QUEUED {
+            return None;
+        }
+
+        // Send event before removing anything.
+        self.send(RemoveEvent(event));
+
+        // Remove all systems that send or receive this event.
+        let mut to_remove = vec![];
+
+        for sys in self.systems.iter() {
+            if sys.received_events().any(|e| e == event)
+                || match event.index() {
+                    EventIdx::Targeted(idx) => sys.sent_targeted_events().contains(idx),
+                    EventIdx::Untargeted(idx) => sys.sent_untargeted_events().contains(idx),
+                }
+            {
+                to_remove.push(sys.id());
+            }
+        }
+
+        for id in to_remove {
+            self.remove_system(id);
+        }
+
+        let info = self.events.remove(event).unwrap();
+
+        match info.kind() {
+            EventKind::Normal => {}
+            EventKind::Insert { component_idx, .. } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    info.insert_events.remove(&event);
+                }
+            }
+            EventKind::Remove { component_idx } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    info.remove_events.remove(&event);
+                }
+            }
+            EventKind::Removed { component_idx } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    if info.removed_event == Some(event) {
+                        info.removed_event = None;
+                    }
+                }
+            }
+            EventKind::Replaced { component_idx, .. } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    if info.replaced_event == Some(event) {
+                        info.replaced_event = None;
+                    }
+                }
+            }
+            EventKind::SpawnQueued => {}
+            EventKind::Despawn => {}
+        }
+
+        Some(info)
+    }
+
+    /// Sets the function to call when a targeted event is dropped because its
+    /// target doesn't match the event's [target filter]. This is intended for
+    /// debugging. Pass `None` to remove the hook.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::event::EventInfo;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(i32);
+    ///
+    /// #[derive(Event)]
+    /// #[event(requires(Health))]
+    /// struct Damage(#[event(target)] EntityId);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_filtered_event_hook(Some(|info: &EventInfo, target| {
+    ///     eprintln!("dropped `{}` targeting {target:?}", info.name());
+    /// }));
+    ///
+    /// let e = world.spawn();
+    /// world.send(Damage(e));
+    /// ```
+    ///
+    /// [target filter]: Event::target_filter
+    pub fn set_filtered_event_hook(&mut self, hook: Option<FilteredEventHook>) {
+        self.filtered_event_hook = hook;
+    }
+
+    /// Sets what happens to targeted events whose target doesn't exist when
+    /// they're broadcast, such as events sent to an entity which was
+    /// despawned. They're ignored by default.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use evenio::event::DeadTargetPolicy;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Message(#[event(target)] EntityId);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_dead_target_policy(DeadTargetPolicy::Panic);
+    ///
+    /// let e = world.spawn();
+    /// world.despawn(e);
+    /// world.send(Message(e)); // Panics.
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is [`DeadTargetPolicy::Reroute`] and its event
+    /// doesn't exist or can't hold a [`DeadTarget`].
+    #[track_caller]
+    pub fn set_dead_target_policy(&mut self, policy: DeadTargetPolicy) {
+        if let DeadTargetPolicy::Reroute(id) = policy {
+            let Some(info) = self.events.get(id) else {
+                panic!("event {id:?} doesn't exist")
+            };
+
+            let holds_dead_target = !info.is_targeted()
+                && info.kind() == EventKind::Normal
+                && info.layout() == Layout::new::<DeadTarget>()
+                && match info.type_id() {
+                    Some(type_id) => type_id == TypeId::of::<DeadTarget>(),
+                    None => info.drop().is_none(),
+                };
+
+            assert!(
+                holds_dead_target,
+                "event `{}` can't receive rerouted dead target events",
+                info.name()
+            );
+        }
+
+        self.dead_target_policy = policy;
+    }
+
+    /// Returns the policy set with
+    /// [`set_dead_target_policy`](Self::set_dead_target_policy).
+    pub fn dead_target_policy(&self) -> DeadTargetPolicy {
+        self.dead_target_policy
+    }
+
+    /// Sets the function to call with warnings about the world, such as
+    /// events dropped because of [`DeadTargetPolicy::Warn`]. Warnings are
+    /// ignored if there is no handler. Pass `None` to remove the handler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::event::DeadTargetPolicy;
+    /// use evenio::prelude::*;
+    /// use evenio::world::WarningInfo;
+    ///
+    /// #[derive(Event)]
+    /// struct Message(#[event(target)] EntityId);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_dead_target_policy(DeadTargetPolicy::Warn);
+    /// world.set_warning_handler(Some(|info: &WarningInfo| eprintln!("warning: {info}")));
+    ///
+    /// let e = world.spawn();
+    /// world.despawn(e);
+    /// world.send(Message(e)); // Prints a warning.
+    /// ```
+    pub fn set_warning_handler(&mut self, handler: Option<WarningHandler>) {
+        self.warning_handler = handler;
+    }
+
+    /// Applies the [`DeadTargetPolicy`] to the event at `idx`, which was sent
+    /// to `target` after it stopped existing.
+    fn handle_dead_target(&mut self, idx: EventIdx, target: EntityId) {
+        match self.dead_target_policy {
+            DeadTargetPolicy::Ignore => {}
+            DeadTargetPolicy::Warn => {
+                if let Some(handler) = self.warning_handler {
+                    let event = unsafe { self.events.get_by_index(idx).unwrap_debug_checked() };
+
+                    handler(&WarningInfo::DeadTarget { event, target });
+                }
+            }
+            DeadTargetPolicy::Panic => {
+                let event = unsafe { self.events.get_by_index(idx).unwrap_debug_checked() };
+
+                panic!("{}", WarningInfo::DeadTarget { event, target });
+            }
+            DeadTargetPolicy::Reroute(reroute) => {
+                let event = unsafe { self.events.get_by_index(idx).unwrap_debug_checked() }.id();
+
+                // The event was checked by `set_dead_target_policy`, but may have been
+                // removed since.
+                if self.events.get(reroute).is_some() {
+                    let idx = reroute.index().as_u32();
+
+                    unsafe { self.event_queue.push(DeadTarget { event, target }, idx) };
+                }
+            }
+        }
+    }
+
+    /// Resets the [`SystemStats`] of every system in this world.
+    ///
+    /// [`SystemStats`]: crate::system::SystemStats
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        for info in self.systems.iter_mut() {
+            *info.stats_mut() = Default::default();
+        }
+    }
+
+    /// Returns the [`Entities`] for this world.
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    /// Returns the number of entities in this world. This is the same as
+    /// [`Entities::len`].
+    ///
+    /// Entities [spawned](Sender::spawn) from systems are counted once their
+    /// [`Spawn`] event is handled.
+    pub fn entity_count(&self) -> u32 {
+        self.entities.len()
+    }
+
+    /// Checks every component with a [validation
+    /// function](ComponentDescriptor::validate) and returns the components
+    /// which failed. Components without one are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// #[component(validate = check_health)]
+    /// struct Health(f32);
+    ///
+    /// fn check_health(health: &Health) -> Result<(), String> {
+    ///     if health.0.is_finite() {
+    ///         Ok(())
+    ///     } else {
+    ///         Err(format!("health is {}", health.0))
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Health(100.0));
+    ///
+    /// world.get_component_mut::<Health>(e).unwrap().0 = f32::NAN;
+    ///
+    /// let failures = world.validate_all();
+    ///
+    /// assert_eq!(failures.len(), 1);
+    /// assert_eq!(failures[0].entity, e);
+    /// assert_eq!(failures[0].message, "health is NaN");
+    /// ```
+    pub fn validate_all(&self) -> Vec<ValidationFailure> {
+        let mut failures = vec![];
+
+        for arch in self.archetypes.iter() {
+            for col in arch.columns() {
+                let info = unsafe {
+                    self.components
+                        .get_by_index(col.component_index())
+                        .unwrap_debug_checked()
+                };
+
+                let Some(validate) = info.validate() else {
+                    continue;
+                };
+
+                for (row, &entity) in arch.entity_ids().iter().enumerate() {
+                    if let Err(message) = unsafe { validate(col.row_ptr(row)) } {
+                        failures.push(ValidationFailure {
+                            entity,
+                            component: info.id(),
+                            message,
+                        });
+                    }
+                }
+            }
+        }
+
+        failures
+    }
+
+    /// Panics if any component fails validation.
+    #[cfg(feature = "validate-components")]
+    #[track_caller]
+    fn assert_components_valid(&self) {
+        #[cfg(feature = "name")]
+        use core::fmt::Write;
+
+        let failures = self.validate_all();
+
+        if !failures.is_empty() {
+            let mut msg = format!("{} components failed validation:", failures.len());
+
+            for failure in &failures {
+                msg += "\n  ";
+                msg += &failure.to_string();
+
+                #[cfg(feature = "name")]
+                if let Some(name) = self.name(failure.entity) {
+                    let _ = write!(msg, " (entity `{name}`)");
+                }
+            }
+
+            panic!("{msg}");
+        }
+    }
+
+    /// Returns the [`Components`] for this world.  
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// Returns the [`Systems`] for this world.
+    pub fn systems(&self) -> &Systems {
+        &self.systems
+    }
+
+    /// Returns the [`SystemInfo`] of the given system, or `None` if the ID is
+    /// invalid. This is a shorthand for `world.systems().get(system)`.
+    pub fn system_info(&self, system: SystemId) -> Option<&SystemInfo> {
+        self.systems.get(system)
+    }
+
+    /// Returns the [`Archetypes`] for this world.
+    pub fn archetypes(&self) -> &Archetypes {
+        &self.archetypes
+    }
+
+    /// Pins the archetype at `idx` so that pointers to its columns can be
+    /// held outside of systems, such as by foreign code. Returns `None` if
+    /// the index is invalid.
+    ///
+    /// Until the returned [`ArchetypePin`] is dropped, spawning entities
+    /// into the archetype, moving entities into or out of it, despawning its
+    /// entities, or removing one of its components panics. See
+    /// [`ArchetypePin`] for an example.
+    pub fn pin_archetype(&mut self, idx: ArchetypeIdx) -> Option<ArchetypePin> {
+        self.check_thread();
+        self.archetypes.pin(idx)
+    }
+
+    /// Returns the [`Events`] for this world.
+    pub fn events(&self) -> &Events {
+        &self.events
+    }
+
+    /// Returns an iterator over the IDs of all events in this world, in an
+    /// arbitrary order. Use [`World::events`] to look up their [`EventInfo`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_event::<E>();
+    ///
+    /// assert!(world.event_ids().any(|e| e == id));
+    ///
+    /// for id in world.event_ids() {
+    ///     println!("{}", world.events()[id].name());
+    /// }
+    /// ```
+    pub fn event_ids(&self) -> impl Iterator<Item = EventId> + '_ {
+        self.events.iter().map(EventInfo::id)
+    }
+
+    /// Returns the systems that would handle the untargeted `event` if it were
+    /// sent now, in the order they would run, along with their priorities.
+    ///
+    /// The systems are looked up the same way as when the event is
+    /// broadcast. The list is empty if the event doesn't exist or is targeted.
+    /// Use [`handlers_for_targeted_event`] for targeted events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::system::Priority;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.add_system(|_: Receiver<E>| {});
+    /// let b = world.add_system((|_: Receiver<E>| {}).before());
+    ///
+    /// let event = world.add_event::<E>();
+    ///
+    /// assert_eq!(
+    ///     world.handlers_for_event(event),
+    ///     [(b, Priority::BeforeDefault), (a, Priority::Default)]
+    /// );
+    /// ```
+    ///
+    /// [`handlers_for_targeted_event`]: World::handlers_for_targeted_event
+    pub fn handlers_for_event(&self, event: EventId) -> Vec<(SystemId, Priority)> {
+        let Some(EventIdx::Untargeted(idx)) = self.events.get(event).map(|e| e.id().index()) else {
+            return vec![];
+        };
+
+        self.systems
+            .get_untargeted_list(idx)
+            .map_or_else(Vec::new, |list| {
+                list.infos().map(|s| (s.id(), s.priority())).collect()
+            })
+    }
+
+    /// Returns the systems that would handle the targeted `event` if it were
+    /// sent to `target` now, in the order they would run, along with their
+    /// priorities.
+    ///
+    /// The systems are looked up in the archetype of `target` the same way as
+    /// when the event is broadcast, so only systems whose queries match the
+    /// target are included. A system may still be skipped if an earlier
+    /// system moves the target to an archetype it doesn't match. The list is
+    /// empty if the event doesn't exist or isn't targeted, or if `target`
+    /// doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::system::Priority;
+    ///
+    /// #[derive(Event)]
+    /// struct Hit(#[event(target)] EntityId);
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let any = world.add_system(|_: Receiver<Hit, ()>| {});
+    /// let with_health = world.add_system(|_: Receiver<Hit, &Health>| {});
+    ///
+    /// let event = world.add_event::<Hit>();
+    ///
+    /// let e = world.spawn();
+    /// assert_eq!(
+    ///     world.handlers_for_targeted_event(event, e),
+    ///     [(any, Priority::Default)]
+    /// );
+    ///
+    /// world.insert(e, Health(10));
+    /// assert_eq!(
+    ///     world.handlers_for_targeted_event(event, e),
+    ///     [(any, Priority::Default), (with_health, Priority::Default)]
+    /// );
+    /// ```
+    pub fn handlers_for_targeted_event(
+        &self,
+        event: EventId,
+        target: EntityId,
+    ) -> Vec<(SystemId, Priority)> {
+        let Some(EventIdx::Targeted(idx)) = self.events.get(event).map(|e| e.id().index()) else {
+            return vec![];
+        };
+
+        let Some(loc) = self.entities.get(target) else {
+            return vec![];
+        };
+
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+        arch.system_list_for(idx).map_or_else(Vec::new, |list| {
+            list.infos().map(|s| (s.id(), s.priority())).collect()
+        })
+    }
+
+    /// Returns every system that receives `event`, in the order they run
+    /// when it's sent.
+    ///
+    /// Systems are sorted by their [`Priority`], then by their
+    /// [sub-priority](crate::system::Config::sub_priority), and then by the
+    /// order they were added to the world. For targeted events, the systems
+    /// that run for a particular target are a subset of this list in
+    /// the same order. The list is empty if the event doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.add_system((|_: Receiver<E>| {}).after());
+    /// let b = world.add_system(|_: Receiver<E>| {});
+    /// let c = world.add_system(|_: Receiver<E>| {});
+    ///
+    /// let event = world.add_event::<E>();
+    /// assert_eq!(world.system_order_for_event(event), [b, c, a]);
+    /// ```
+    pub fn system_order_for_event(&self, event: EventId) -> Vec<SystemId> {
+        let mut infos: Vec<_> = self
+            .systems
+            .iter()
+            .filter(|s| s.received_events().any(|e| e == event))
+            .collect();
+
+        infos.sort_by_key(|s| s.order_key());
+
+        infos.into_iter().map(|s| s.id()).collect()
+    }
+
+    /// Finds pairs of systems whose relative order may matter but is only
+    /// decided by the order they were added to the world.
+    ///
+    /// A pair is reported if both systems handle the same event with the same
+    /// [`Priority`] and [sub-priority], and one of them writes a component
+    /// the other reads or writes. For targeted events, only systems which
+    /// both run for the entities of some archetype are compared. Pairs passed
+    /// to [`allow_order_ambiguity`] are skipped.
+    ///
+    /// This only inspects the world and is meant for tests and debugging
+    /// tools. Ambiguities are returned sorted by event, then by system.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Tick;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let regen = world.add_system(|_: Receiver<Tick>, f: Fetcher<&mut Health>| {
+    ///     for h in f {
+    ///         h.0 += 1;
+    ///     }
+    /// });
+    /// let report = world.add_system(|_: Receiver<Tick>, f: Fetcher<&Health>| {
+    ///     for h in f {
+    ///         println!("{}", h.0);
+    ///     }
+    /// });
+    ///
+    /// let found = world.detect_order_ambiguities();
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!((found[0].first, found[0].second), (regen, report));
+    ///
+    /// // The order doesn't matter here.
+    /// world.allow_order_ambiguity(regen, report);
+    /// assert!(world.detect_order_ambiguities().is_empty());
+    /// ```
+    ///
+    /// [sub-priority]: crate::system::Config::sub_priority
+    /// [`allow_order_ambiguity`]: World::allow_order_ambiguity
+    pub fn detect_order_ambiguities(&self) -> Vec<Ambiguity> {
+        let lists = self
+            .systems
+            .untargeted_lists()
+            .chain(self.archetypes.iter().flat_map(|arch| arch.system_lists()));
+
+        let mut seen = BTreeSet::new();
+        let mut res = vec![];
+
+        for list in lists {
+            let infos = list.infos().collect::<Vec<_>>();
+
+            // Lists are sorted by priority and sub-priority, so systems which only
+            // differ by registration order are next to each other.
+            for (i, first) in infos.iter().enumerate() {
+                for second in &infos[i + 1..] {
+                    if (second.priority(), second.sub_priority())
+                        != (first.priority(), first.sub_priority())
+                    {
+                        break;
+                    }
+
+                    if !seen.insert((first.id(), second.id()))
+                        || self.systems.is_ambiguity_allowed(first.id(), second.id())
+                    {
+                        continue;
+                    }
+
+                    let a = &first.component_access().access;
+                    let b = &second.component_access().access;
+
+                    if a.is_compatible(b) {
+                        continue;
+                    }
+
+                    let components = a
+                        .iter()
+                        .filter(|&(idx, access)| !access.is_compatible(b.get(idx)))
+                        .map(|(idx, _)| {
+                            unsafe { self.components.get_by_index(idx).unwrap_debug_checked() }.id()
+                        })
+                        .collect();
+
+                    // Systems with a `ReceiverAny` may share the list for any of their events.
+                    let event = first
+                        .received_events()
+                        .find(|&e| second.received_events().any(|f| f == e))
+                        .unwrap_or_else(|| first.received_event());
+
+                    res.push(Ambiguity {
+                        event,
+                        first: first.id(),
+                        first_name: String::from(first.name()).into(),
+                        second: second.id(),
+                        second_name: String::from(second.name()).into(),
+                        components,
+                    });
+                }
+            }
+        }
+
+        res.sort_by_key(|a| (a.event, a.first, a.second));
+
+        res
+    }
+
+    /// Leaves the pair of systems `a` and `b` out of the results of
+    /// [`detect_order_ambiguities`], for systems whose order is known not to
+    /// matter. The order of the arguments doesn't matter either.
+    ///
+    /// [`detect_order_ambiguities`]: World::detect_order_ambiguities
+    pub fn allow_order_ambiguity(&mut self, a: SystemId, b: SystemId) {
+        self.systems.allow_ambiguity(a, b);
+    }
+
+    /// Changes how much room new archetypes allocate for their entities. See
+    /// [`ArchetypeSettings`] for details.
+    ///
+    /// Archetypes which already exist keep their current capacity and use the
+    /// new growth policy the next time they run out of room. The empty
+    /// archetype, which holds newly spawned entities, is grown to the new
+    /// initial capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the growth factor is less than `1.0` or the growth chunk size
+    /// is zero.
+    pub fn set_archetype_settings(&mut self, settings: ArchetypeSettings) {
+        self.archetypes.set_settings(settings);
+    }
+
+    /// Reserves room for at least `additional` more entities to be spawned
+    /// without reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.reserve_entities(1000);
+    ///
+    /// for _ in 0..1000 {
+    ///     world.spawn();
+    /// }
+    /// ```
+    pub fn reserve_entities(&mut self, additional: usize) {
+        self.entities.reserve(additional);
+        self.archetypes.reserve_empty(additional);
+    }
+
+    /// Gets the archetype with exactly the given components, creating it if
+    /// it doesn't exist, and makes room in it for `capacity` entities in
+    /// total.
+    ///
+    /// The archetype's columns are grown to exactly `capacity` instead of by
+    /// its [growth policy], so archetypes whose size is known ahead of time
+    /// don't allocate more than they need. Nothing happens if the archetype
+    /// already has room for `capacity` entities.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the components don't exist, or if the archetype would
+    /// have to be created and the world has no room for more archetypes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Tile(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let tile = world.add_component::<Tile>();
+    /// let arch = world.reserve_archetype(&[tile], 4096);
+    ///
+    /// assert_eq!(world.archetypes().get(arch).unwrap().capacity(), 4096);
+    /// ```
+    ///
+    /// [growth policy]: crate::archetype::ArchetypeSettings::growth
+    #[track_caller]
+    pub fn reserve_archetype(
+        &mut self,
+        components: &[ComponentId],
+        capacity: usize,
+    ) -> ArchetypeIdx {
+        self.check_thread();
+
+        let mut idxs = components
+            .iter()
+            .map(|&id| match self.components.get(id) {
+                Some(info) => info.id().index(),
+                None => panic!("component {id:?} doesn't exist"),
+            })
+            .collect::<Vec<_>>();
+
+        idxs.sort_unstable();
+        idxs.dedup();
+
+        let idx = match unsafe {
+            self.archetypes.traverse_insert_many(
+                ArchetypeIdx::EMPTY,
+                &idxs,
+                &mut self.components,
+                &mut self.systems,
+            )
+        } {
+            Ok(idx) => idx,
+            Err(limit) => panic!(
+                "can't create an archetype with components {:?}: the world already has {} \
+                 archetypes",
+                limit.components, limit.count
+            ),
+        };
+
+        unsafe { self.archetypes.reserve_exact(idx, capacity) };
+
+        idx
+    }
+
+    /// Checks that the world's internal bookkeeping is consistent, panicking
+    /// with a description of the first problem found. This is meant to be
+    /// called from tests.
+    ///
+    /// The following are checked:
+    /// - Every entity's [`EntityLocation`] points to the row holding its ID.
+    /// - Every column of an archetype has one component per entity.
+    /// - Archetypes can be found by their component sets.
+    /// - Edges between archetypes lead to archetypes with the right components,
+    ///   and single-component edges have a matching edge going back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C);
+    ///
+    /// world.check_invariants();
+    /// ```
+    #[track_caller]
+    pub fn check_invariants(&self) {
+        self.archetypes.check_invariants(&self.entities);
+    }
+
+    /// Returns an estimate of the heap memory used by this world, broken down
+    /// by archetype and by component. See [`MemoryReport`] for what is
+    /// counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position([f32; 3]);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// for _ in 0..100 {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Position([0.0; 3]));
+    /// }
+    ///
+    /// let report = world.memory_usage();
+    /// let id = world.add_component::<Position>();
+    ///
+    /// let (_, bytes) = report
+    ///     .bytes_by_component
+    ///     .iter()
+    ///     .find(|&&(c, _)| c == id)
+    ///     .unwrap();
+    ///
+    /// assert!(*bytes >= 100 * 12);
+    /// ```
+    pub fn memory_usage(&self) -> MemoryReport {
+        let archetypes = self
+            .archetypes
+            .iter()
+            .map(Archetype::memory_usage)
+            .collect::<Vec<_>>();
+
+        let mut by_component = self
+            .archetypes
+            .iter()
+            .flat_map(|arch| arch.columns())
+            .map(|col| (col.component_index(), col.heap_size()))
+            .collect::<Vec<_>>();
+
+        by_component.sort_unstable_by_key(|&(idx, _)| idx);
+
+        // Merge the columns of each component in place.
+        by_component.dedup_by(|(idx, bytes), (prev_idx, prev_bytes)| {
+            let same = idx == prev_idx;
+            if same {
+                *prev_bytes += *bytes;
+            }
+            same
+        });
+
+        let bytes_by_component = by_component
+            .into_iter()
+            .map(|(idx, bytes)| {
+                let info = unsafe { self.components.get_by_index(idx).unwrap_debug_checked() };
+                (info.id(), bytes)
+            })
+            .collect();
+
+        let mut report = MemoryReport {
+            total_bytes: 0,
+            entities_bytes: self.entities.heap_size(),
+            archetypes,
+            archetype_table_bytes: self.archetypes.table_heap_size(),
+            bytes_by_component,
+            systems_bytes: self.systems.heap_size(),
+        };
+
+        report.total_bytes = report.entities_bytes
+            + report.archetype_table_bytes
+            + report.systems_bytes
+            + report
+                .archetypes
+                .iter()
+                .map(ArchetypeMemory::total_bytes)
+                .sum::<usize>();
+
+        report
+    }
+
+    /// Returns the [`Globals`] for this world.
+    pub fn globals(&self) -> &Globals {
+        &self.globals
+    }
+
+    /// Pushes an event to the event queue without broadcasting it.
+    pub(crate) fn queue_event<E: Event>(&mut self, event: E) {
+        let idx = self.queued_event_idx::<E>();
+        unsafe { self.event_queue.push(event, idx) };
+    }
+
+    /// Like [`queue_event`](Self::queue_event), but for a boxed event.
+    pub(crate) fn queue_boxed_event<E: Event>(&mut self, event: Box<E>) {
+        let idx = self.queued_event_idx::<E>();
+        unsafe { self.event_queue.push_boxed(event, idx) };
+    }
+
+    #[inline]
+    fn queued_event_idx<E: Event>(&mut self) -> u32 {
+        // Only register the event the first time it's sent, since
+        // `add_event` does much more work than the lookup.
+        match self.events.cached_idx_by_type_id(TypeId::of::<E>()) {
+            Some(idx) => idx,
+            None => self.add_event::<E>().index().as_u32(),
+        }
+    }
+
+    /// Updates which diagnostic events the archetypes collect.
+    #[cfg(feature = "diagnostics")]
+    fn refresh_diagnostic_listeners(&mut self) {
+        use crate::diagnostics::{ArchetypeCreated, ComponentRegistered, EntityMoved, Listening};
+
+        fn has_listeners<E: Event>(world: &World) -> bool {
+            let Some(info) = world.events.get_by_type_id(TypeId::of::<E>()) else {
+                return false;
+            };
+
+            match info.id().index() {
+                EventIdx::Untargeted(idx) => world
+                    .systems
+                    .get_untargeted_list(idx)
+                    .is_some_and(|list| !list.systems().is_empty()),
+                EventIdx::Targeted(_) => false,
+            }
+        }
+
+        self.archetypes.diagnostics.listening = Listening {
+            archetype_created: has_listeners::<ArchetypeCreated>(self),
+            entity_moved: has_listeners::<EntityMoved>(self),
+            component_registered: has_listeners::<ComponentRegistered>(self),
+        };
+    }
+
+    /// Moves the collected diagnostics to the deferred event queue.
+    #[cfg(feature = "diagnostics")]
+    fn queue_diagnostics(&mut self) {
+        use crate::diagnostics::Diagnostic;
+
+        // The event may have been removed since the diagnostic was collected.
+        fn queue<E: Event>(world: &mut World, event: E) {
+            if let Some(info) = world.events.get_by_type_id(TypeId::of::<E>()) {
+                let idx = info.id().index().as_u32();
+                unsafe { world.event_queue.push(event, idx) };
+            }
+        }
+
+        for diagnostic in self.archetypes.diagnostics.take() {
+            match diagnostic {
+                Diagnostic::ArchetypeCreated(e) => queue(self, e),
+                Diagnostic::EntityMoved(e) => queue(self, e),
+                Diagnostic::ComponentRegistered(e) => queue(self, e),
+            }
+        }
+    }
+
+    /// Sends an event announcing a new component or event. While a hook is
+    /// running, the event is queued with the hook's other events instead.
+    fn send_notice<E: Event>(&mut self, event: E) {
+        if self.running_hook {
+            self.queue_event(event);
+        } else {
+            self.send(event);
+        }
+    }
+
+    /// Runs the `on_insert` hooks of the components `entity` has that weren't
+    /// in the archetype `src`, along with the hook of `replaced` if it's
+    /// given.
+    ///
+    /// Must be called after the entity moved to its new archetype.
+    fn run_insert_hooks(
+        &mut self,
+        entity: EntityId,
+        src: ArchetypeIdx,
+        replaced: Option<ComponentIdx>,
+    ) {
+        if self.components.has_hooks() {
+            self.run_hooks(entity, src, replaced, true);
+        }
+    }
+
+    /// Runs the `on_remove` hooks of the components `entity` has that aren't
+    /// in the archetype `dst`, along with the hook of `replaced` if it's
+    /// given.
+    ///
+    /// Must be called before the entity moves to its new archetype.
+    fn run_remove_hooks(
+        &mut self,
+        entity: EntityId,
+        dst: ArchetypeIdx,
+        replaced: Option<ComponentIdx>,
+    ) {
+        if self.components.has_hooks() {
+            self.run_hooks(entity, dst, replaced, false);
+        }
+    }
+
+    #[cold]
+    fn run_hooks(
+        &mut self,
+        entity: EntityId,
+        other: ArchetypeIdx,
+        replaced: Option<ComponentIdx>,
+        insert: bool,
+    ) {
+        let Some(loc) = self.entities.get(entity) else {
+            return;
+        };
+
+        let column_count = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() }
+            .columns()
+            .len();
+
+        // Hooks can't make structural changes, so the entity stays where it is.
+        for i in 0..column_count {
+            let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+            let col = unsafe { arch.columns().get_debug_checked(i) };
+            let idx = col.component_index();
+
+            let other = unsafe { self.archetypes.get(other).unwrap_debug_checked() };
+
+            if Some(idx) != replaced && other.column_of(idx).is_some() {
+                continue;
+            }
+
+            let info = unsafe { self.components.get_by_index_mut(idx).unwrap_debug_checked() };
+
+            if info.singleton().is_some() {
+                if insert {
+                    info.singleton_holder = Some(entity);
+                } else if info.singleton_holder == Some(entity) {
+                    info.singleton_holder = None;
+                }
+            }
+
+            let hook = if insert {
+                info.on_insert()
+            } else {
+                info.on_remove()
+            };
+
+            let Some(hook) = hook else {
+                continue;
+            };
+
+            let ptr = unsafe { col.row_ptr(loc.row.0 as usize) };
+
+            let running_hook = mem::replace(&mut self.running_hook, true);
+            unsafe { hook(&mut HookContext::new(self), entity, ptr) };
+            self.running_hook = running_hook;
+        }
+    }
+
+    pub(crate) fn commands_mut(&mut self) -> &mut CommandQueue {
+        &mut self.commands
+    }
+
+    /// Spawns an entity previously reserved with
+    /// [`UnsafeWorldCell::reserve_entity`] and sends the [`Spawn`] event.
+    pub(crate) fn spawn_reserved(&mut self, id: EntityId) {
+        self.send_many(|mut s| {
+            unsafe {
+                s.world
+                    .event_queue
+                    .push(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32())
+            }
+            s.send(Spawn(id));
+        })
+    }
+
+    pub(crate) fn globals_mut(&mut self) -> &mut Globals {
+        &mut self.globals
+    }
+
+    pub(crate) fn components_mut(&mut self) -> &mut Components {
+        &mut self.components
+    }
+
+    /// Returns the thread this world is tied to, or `None` if the world may be
+    /// used from any thread.
+    ///
+    /// A world becomes tied to the current thread once a [local component] is
+    /// added to it. After that, sending events or accessing components from
+    /// any other thread panics, even though `World` itself is `Send`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// use evenio::component::Unsend;
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// assert_eq!(world.owner_thread(), None);
+    ///
+    /// world.add_component::<Unsend<Rc<i32>>>();
+    /// assert_eq!(world.owner_thread(), Some(std::thread::current().id()));
+    /// ```
+    ///
+    /// [local component]: Component::IS_LOCAL
+    #[cfg(feature = "std")]
+    pub fn owner_thread(&self) -> Option<std::thread::ThreadId> {
+        self.owner_thread
+    }
+
+    /// Panics if this world is tied to a thread other than the current one.
+    #[track_caller]
+    #[cfg_attr(not(feature = "std"), allow(clippy::unused_self))]
+    pub(crate) fn check_thread(&self) {
+        #[cfg(feature = "std")]
+        if let Some(owner) = self.owner_thread {
+            assert!(
+                owner == std::thread::current().id(),
+                "world contains local components and cannot be used from a thread other than \
+                 {owner:?}"
+            );
+        }
+    }
+
+    /// Send queued events to systems, starting with the event at index
+    /// `start`. The event queue will have a length of `start` after this call.
+    ///
+    /// `start` is only nonzero when [`Commands`] are applied in the middle of
+    /// broadcasting. Deferred events and the reset of the event queue are left
+    /// to the outermost call.
+    ///
+    /// [`Commands`]: crate::command::Commands
+    fn flush_event_queue(&mut self, start: usize) {
+        // The queue is only empty when the events were sent from outside of any
+        // system.
+        #[cfg(feature = "replay")]
+        if let (0, Some(recorder)) = (start, &mut self.recorder) {
+            recorder.begin_flush(self.event_queue.deferred_len());
+        }
+
+        handle_events(start, self);
+
+        if start != 0 {
+            return;
+        }
+
+        // Deferred events are handled one at a time in the order they were sent,
+        // after everything else has finished broadcasting.
+        loop {
+            // Diagnostics are queued behind the deferred events sent so far,
+            // so they're delivered after everything that caused them.
+            #[cfg(feature = "diagnostics")]
+            self.queue_diagnostics();
+
+            if !self.event_queue.pop_deferred() {
+                break;
+            }
+
+            #[cfg(feature = "replay")]
+            if let Some(recorder) = &mut self.recorder {
+                recorder.next_deferred();
+            }
+
+            handle_events(0, self);
+        }
+
+        debug_assert_eq!(self.event_queue.len(), 0);
+        debug_assert!(self.commands.is_empty());
+        self.event_queue.clear();
+
+        #[cfg(feature = "validate-components")]
+        self.assert_components_valid();
+
+        fn handle_events(queue_start_idx: usize, world: &mut World) {
+            // The events sent by a system are handled before the next system runs, and the
+            // events caused by an event's structural change before the next event. Instead
+            // of recursing, the batch being handled is suspended here, so that
+            // long chains of events don't overflow the stack.
+            let mut suspended: Vec<Batch> = vec![];
+            let mut batch = Batch::new(queue_start_idx, world);
+
+            loop {
+                if let Some(events_before) = handle_batch(&mut batch, world) {
+                    let nested = Batch::new(events_before, world);
+                    suspended.push(mem::replace(&mut batch, nested));
+                    continue;
+                }
+
+                unsafe { world.event_queue.set_len(batch.start) };
+
+                #[cfg(feature = "replay")]
+                if let Some(recorder) = &mut world.recorder {
+                    recorder.exit();
+                }
+
+                match suspended.pop() {
+                    Some(outer) => batch = outer,
+                    None => return,
+                }
+            }
+        }
+
+        /// A range of the event queue being handled by [`handle_events`].
+        struct Batch {
+            /// The index of the first event. The queue is truncated to it once
+            /// the batch is handled.
+            start: usize,
+            /// The index of the next event to handle.
+            next: usize,
+            /// The length of the queue when the batch was created.
+            end: usize,
+            /// The event which was being broadcast when the batch was
+            /// suspended.
+            current: Option<Broadcast>,
+        }
+
+        impl Batch {
+            fn new(start: usize, world: &mut World) -> Self {
+                #[cfg(feature = "replay")]
+                if let Some(recorder) = &mut world.recorder {
+                    recorder.enter();
+                }
+
+                Self {
+                    start,
+                    next: start,
+                    end: world.event_queue.len(),
+                    current: None,
+                }
+            }
+        }
+
+        /// Handles the events of `batch` until a system or a structural change
+        /// queues more events. Returns the index of the first queued event, or
+        /// `None` once every event of the batch is handled.
+        fn handle_batch(batch: &mut Batch, world: &mut World) -> Option<usize> {
+            'next_event: loop {
+                let mut broadcast = match batch.current.take() {
+                    Some(broadcast) => broadcast,
+                    None if batch.next < batch.end => {
+                        let queue_idx = batch.next;
+                        batch.next += 1;
+
+                        let item = unsafe { world.event_queue.get_debug_checked_mut(queue_idx) };
+                        let event_meta = item.meta;
+                        let event_info = unsafe {
+                            world
+                                .events
+                                .get_by_index(event_meta.event_idx())
+                                .unwrap_debug_checked()
+                        };
+                        let event_kind = event_info.kind();
+
+                        // The event sent by `send_and_return` is the first one handled at its
+                        // index.
+                        let returned = match &mut world.return_slot {
+                            Some(slot) if slot.queue_idx == Some(queue_idx) => {
+                                slot.queue_idx = None;
+                                true
+                            }
+                            _ => false,
+                        };
+
+                        // Take the event pointer out of the queue because pointers into the event
+                        // queue would be invalidated by pushes.
+                        let event = EventDropper {
+                            // Set pointer to null so the `World`'s destructor will know we've taken
+                            // ownership of the event.
+                            event: mem::replace(&mut item.event, ptr::null_mut()),
+                            drop: event_info.drop(),
+                        };
+
+                        #[cfg(feature = "replay")]
+                        if let Some(recorder) = &mut world.recorder {
+                            unsafe { recorder.record(event_info, event_meta, event.event) };
+                        }
+
+                        // Targeted events visit each of their targets in order. Events without a
+                        // `TargetsFn` only visit the target they were queued with, and untargeted
+                        // events are broadcast once with a null target.
+                        let mut first_target = None;
+                        let mut other_targets = Vec::new();
+
+                        match event_meta {
+                            EventMeta::Untargeted { .. } => first_target = Some(EntityId::NULL),
+                            EventMeta::Targeted { target, .. } => match event_info.targets_fn() {
+                                Some(targets) if matches!(event_kind, EventKind::Normal) => unsafe {
+                                    targets(NonNull::new_unchecked(event.event), &mut |target| {
+                                        if first_target.is_none() {
+                                            first_target = Some(target);
+                                        } else {
+                                            other_targets.push(target);
+                                        }
+                                    })
+                                },
+                                _ => first_target = Some(target),
+                            },
+                        }
+
+                        Broadcast {
+                            event,
+                            meta: event_meta,
+                            kind: event_kind,
+                            set_target: event_info.set_target_fn(),
+                            first_target,
+                            other_targets: other_targets.into_iter(),
+                            original_target: EntityId::NULL,
+                            current_target: EntityId::NULL,
+                            depth: 0,
+                            stage: Stage::NextTarget,
+                            returned,
+                            listeners: None,
+                        }
+                    }
+                    None => return None,
+                };
+
+                match broadcast_event(&mut broadcast, world) {
+                    Outcome::Suspended(events_before) => {
+                        batch.current = Some(broadcast);
+                        return Some(events_before);
+                    }
+                    Outcome::Taken => {
+                        // Event is null; destructor wouldn't do anything.
+                        broadcast.event.unpack();
+                        continue 'next_event;
+                    }
+                    Outcome::Finished => {}
+                }
+
+                // Inserting a singleton component removes it from its previous holder
+                // first, which has to be handled before the insert continues.
+                if let EventKind::Insert { component_idx, .. } = broadcast.kind {
+                    if world.components.has_hooks() && !matches!(broadcast.stage, Stage::Evicted) {
+                        let entity_id = unsafe { *broadcast.event.event.cast::<EntityId>() };
+                        let evicted_start = world.event_queue.len();
+
+                        evict_singleton(world, entity_id, component_idx);
+
+                        if evicted_start < world.event_queue.len() {
+                            broadcast.stage = Stage::Evicted;
+                            batch.current = Some(broadcast);
+                            return Some(evicted_start);
+                        }
+                    }
+                }
+
+                let follow_up_start = world.event_queue.len();
+
+                finish_event(broadcast, world);
+
+                if follow_up_start < world.event_queue.len() {
+                    // Broadcast the events caused by the event, such as `Replaced`, `Removed`
+                    // and `Despawned`, before the next one.
+                    return Some(follow_up_start);
+                }
+            }
+        }
+
+        /// Applies the structural change of an event which finished
+        /// broadcasting, then drops it. Any events this causes are left in the
+        /// queue.
+        fn finish_event(broadcast: Broadcast, world: &mut World) {
+            let Broadcast {
+                event,
+                kind: event_kind,
+                returned,
+                ..
+            } = broadcast;
+
+            match event_kind {
+                EventKind::Normal if returned => {
+                    // Move the event to `send_and_return` instead of dropping it.
+                    let (event, _) = event.unpack();
+                    let slot = unsafe { world.return_slot.as_mut().unwrap_debug_checked() };
+                    unsafe { ptr::copy_nonoverlapping(event, slot.dst, slot.size) };
+                    slot.returned = true;
+                }
+                EventKind::Normal => {
+                    // Ordinary event. Run event dropper destructor.
+                }
+                EventKind::Insert {
+                    component_idx,
+                    component_offset,
+                } => {
+                    let entity_id = unsafe { *event.event.cast::<EntityId>() };
+
+                    if world.components.has_hooks()
+                        && !admit_singleton(world, entity_id, component_idx)
+                    {
+                        // Drop the event along with the component.
+                        return;
+                    }
+
+                    if let Some(loc) = world.entities.get(entity_id) {
+                        let dst = match unsafe {
+                            world.archetypes.traverse_insert(
+                                loc.archetype,
+                                component_idx,
+                                &mut world.components,
+                                &mut world.systems,
+                            )
+                        } {
+                            Ok(dst) => dst,
+                            Err(limit) => {
+                                // Drop the event along with the component.
+                                drop(event);
+                                send_archetype_limit_reached(world, limit);
+                                return;
+                            }
+                        };
+
+                        // `traverse_insert` doesn't move if the component is already present.
+                        let replaced = (dst == loc.archetype).then_some(component_idx);
+
+                        let component_ptr =
+                            unsafe { event.event.add(component_offset as usize) }.cast_const();
+
+                        let has_required = !unsafe {
+                            world
+                                .components
+                                .get_by_index(component_idx)
+                                .unwrap_debug_checked()
+                        }
+                        .required()
+                        .is_empty();
+
+                        if has_required {
+                            if let Err(limit) = unsafe {
+                                insert_with_required(world, loc, dst, component_idx, component_ptr)
+                            } {
+                                drop(event);
+                                send_archetype_limit_reached(world, limit);
+                                return;
+                            }
+                        } else if dst == loc.archetype {
+                            unsafe { replace_component(world, loc, component_idx, component_ptr) };
+                        } else {
+                            unsafe {
+                                world.archetypes.move_entity(
+                                    loc,
+                                    dst,
+                                    [(component_idx, component_ptr)],
+                                    &mut world.entities,
+                                )
+                            };
+                        }
+
+                        // Inserted component is owned by the archetype now. We wait to unpack
+                        // in case one of the above functions panics.
+                        event.unpack();
+
+                        world.run_insert_hooks(entity_id, loc.archetype, replaced);
+                    }
+                }
+                EventKind::Removed { .. } | EventKind::Replaced { .. } => {
+                    // Ordinary event. Run event dropper destructor.
+                }
+                EventKind::Remove { component_idx } => {
+                    // `Remove` doesn't need drop.
+                    let (event, _) = event.unpack();
+
+                    // SAFETY: `Remove` is `repr(transparent)` with the first field being the
+                    // `EntityId`, so we can safely reinterpret this pointer.
+                    let entity_id = unsafe { *event.cast::<EntityId>() };
+
+                    // Every listener has seen the component by now. The entity is looked up
+                    // again since the listeners may have changed its archetype or despawned it.
+                    if let Some(loc) = world.entities.get(entity_id) {
+                        unsafe { remove_from_entity(world, entity_id, loc, component_idx) };
+                    }
+                }
+                EventKind::SpawnQueued => {
+                    // `SpawnQueued` doesn't need drop.
+                    let _ = event.unpack();
+
+                    // Spawn one entity from the reserved entity queue.
+                    world
+                        .reserved_entities
+                        .spawn_one(&mut world.entities, |id| world.archetypes.spawn(id));
+
+                    world.archetypes.attach_spawned(&mut world.entities);
+                }
+                EventKind::Despawn => {
+                    // `Despawn` doesn't need drop.
+                    let (event, _) = event.unpack();
+
+                    let entity_id = unsafe { *event.cast::<Despawn>() }.0;
+
+                    if world.entities.contains(entity_id) {
+                        world.run_remove_hooks(entity_id, ArchetypeIdx::EMPTY, None);
+
+                        world
+                            .archetypes
+                            .remove_entity(entity_id, &mut world.entities);
+
+                        // Reset next key iter.
+                        world.reserved_entities.refresh(&world.entities);
+
+                        if let Some(info) = world.events.get_by_type_id(TypeId::of::<Despawned>()) {
+                            let idx = info.id().index().as_u32();
+
+                            unsafe { world.event_queue.push(Despawned(entity_id), idx) };
+                        }
+                    }
+                }
+            }
+        }
+
+        /// An event which is visiting its targets and running their systems.
+        struct Broadcast {
+            event: EventDropper,
+            meta: EventMeta,
+            kind: EventKind,
+            set_target: Option<SetTargetFn>,
+            first_target: Option<EntityId>,
+            other_targets: vec::IntoIter<EntityId>,
+            /// The target the event is being broadcast for.
+            original_target: EntityId,
+            /// The entity the event is being broadcast to. This only differs
+            /// from `original_target` once the event has bubbled.
+            current_target: EntityId,
+            /// The number of times the event has bubbled.
+            depth: u32,
+            stage: Stage,
+            /// Whether the event is moved to the world's `return_slot` once it
+            /// finishes.
+            returned: bool,
+            /// The systems listening in the archetype of the last target.
+            listeners: Option<CachedListeners>,
+        }
+
+        /// The systems listening for a targeted event in an archetype, valid
+        /// while [`Archetypes::listeners_version`] is unchanged.
+        #[derive(Clone, Copy)]
+        struct CachedListeners {
+            archetype: ArchetypeIdx,
+            version: u64,
+            systems: *const [SystemInfoPtr],
+        }
+
+        enum Stage {
+            /// Start broadcasting to the next of the targets.
+            NextTarget,
+            /// Look up the systems listening at `current_target`.
+            Target,
+            /// Run the systems of the list from `next` onwards.
+            Systems {
+                systems: *const [SystemInfoPtr],
+                next: usize,
+                /// The target and the archetype it was in when the list was
+                /// looked up, for targeted events.
+                target: Option<(EntityId, ArchetypeIdx)>,
+            },
+            /// The event finished broadcasting, and the singleton component it
+            /// inserts was removed from its previous holder.
+            Evicted,
+        }
+
+        enum Outcome {
+            /// A system sent events starting at this index of the queue, which
+            /// have to be handled before the broadcast continues.
+            Suspended(usize),
+            /// A system took ownership of the event.
+            Taken,
+            /// The event reached all of its targets.
+            Finished,
+        }
+
+        /// Continues broadcasting the event until it finishes or a system sends
+        /// more events.
+        fn broadcast_event(b: &mut Broadcast, world: &mut World) -> Outcome {
+            loop {
+                match b.stage {
+                    Stage::Evicted => return Outcome::Finished,
+                    Stage::NextTarget => {
+                        let Some(target) = b.first_target.take().or_else(|| b.other_targets.next())
+                        else {
+                            return Outcome::Finished;
+                        };
+
+                        b.original_target = target;
+                        b.current_target = target;
+                        b.depth = 0;
+                        b.stage = Stage::Target;
+                    }
+                    Stage::Target => {
+                        let (system_list, target) = match b.meta {
+                            EventMeta::Untargeted { idx } => unsafe {
+                                let list = world
+                                    .systems
+                                    .get_untargeted_list(idx)
+                                    .unwrap_debug_checked();
+
+                                (list.systems() as *const _, None)
+                            },
+                            EventMeta::Targeted { idx, .. } => {
+                                let Some(location) = world.entities.get(b.current_target) else {
+                                    b.stage = Stage::NextTarget;
+
+                                    if b.depth == 0 && !matches!(b.kind, EventKind::Despawn) {
+                                        let rerouted_start = world.event_queue.len();
+
+                                        world.handle_dead_target(
+                                            b.meta.event_idx(),
+                                            b.current_target,
+                                        );
+
+                                        if rerouted_start < world.event_queue.len() {
+                                            return Outcome::Suspended(rerouted_start);
+                                        }
+                                    }
+
+                                    continue;
+                                };
+
+                                if let Some(hook) = world.filtered_event_hook {
+                                    let arch = unsafe {
+                                        world
+                                            .archetypes
+                                            .get(location.archetype)
+                                            .unwrap_debug_checked()
+                                    };
+
+                                    let event_info = unsafe {
+                                        world
+                                            .events
+                                            .get_by_index(b.meta.event_idx())
+                                            .unwrap_debug_checked()
+                                    };
+
+                                    if !arch.matches(event_info.target_filter()) {
+                                        hook(event_info, b.current_target);
+                                    }
+                                }
+
+                                let version = world.archetypes.listeners_version();
+
+                                // Events with many targets usually visit several entities of the
+                                // same archetype in a row, which share the list.
+                                let systems = match b.listeners {
+                                    Some(cached)
+                                        if cached.archetype == location.archetype
+                                            && cached.version == version =>
+                                    {
+                                        cached.systems
+                                    }
+                                    _ => {
+                                        let arch = unsafe {
+                                            world
+                                                .archetypes
+                                                .get(location.archetype)
+                                                .unwrap_debug_checked()
+                                        };
+
+                                        static EMPTY: SystemList = SystemList::new();
+
+                                        // Use an empty system list instead of continuing in case
+                                        // this event is special.
+                                        let systems =
+                                            arch.system_list_for(idx).unwrap_or(&EMPTY).systems()
+                                                as *const _;
+
+                                        b.listeners = Some(CachedListeners {
+                                            archetype: location.archetype,
+                                            version,
+                                            systems,
+                                        });
+
+                                        systems
+                                    }
+                                };
+
+                                (systems, Some((b.current_target, location.archetype)))
+                            }
+                        };
+
+                        b.stage = Stage::Systems {
+                            systems: system_list,
+                            next: 0,
+                            target,
+                        };
+                    }
+                    Stage::Systems {
+                        systems,
+                        next,
+                        target,
+                    } => {
+                        // Did the last system take ownership of the event?
+                        if b.event.event.is_null() {
+                            return Outcome::Taken;
+                        }
+
+                        for (i, info_ptr) in unsafe { &*systems }.iter().enumerate().skip(next) {
+                            let info = unsafe { SystemInfo::ref_from_ptr(info_ptr) };
+
+                            // A previous system may have despawned the target or moved it to
+                            // another archetype. The remaining systems
+                            // are skipped if the target is gone, and
+                            // each is skipped if the target no longer matches its filter.
+                            if let Some((target, arch_idx)) = target {
+                                let Some(location) = world.entities.get(target) else {
+                                    break;
+                                };
+
+                                if location.archetype != arch_idx {
+                                    let arch = unsafe {
+                                        world
+                                            .archetypes
+                                            .get(location.archetype)
+                                            .unwrap_debug_checked()
+                                    };
+
+                                    let matches = info
+                                        .targeted_event_expr()
+                                        .is_some_and(|expr| arch.matches(expr));
+
+                                    if !matches {
+                                        continue;
+                                    }
+                                }
+                            }
+
+                            let events_before = world.event_queue.len();
+
+                            world.current_target = b.current_target;
+                            world.original_target = b.original_target;
+                            world.current_event = b.meta.event_idx();
+
+                            let system = unsafe { &mut (*info_ptr.as_ptr()).system };
+
+                            let event_ptr = EventPtr::new(NonNull::from(&mut b.event.event));
+                            let world_cell = world.unsafe_cell_mut();
+
+                            let lock = unsafe { StructureGuard::new(world_cell) };
+
+                            #[cfg(feature = "stats")]
+                            let start = std::time::Instant::now();
+
+                            unsafe { system.run(info, event_ptr, world_cell) };
+
+                            drop(lock);
+
+                            if !world.commands.is_empty() {
+                                unsafe { CommandQueue::apply(world) };
+                            }
+
+                            #[cfg(feature = "stats")]
+                            unsafe { &mut (*info_ptr.as_ptr()).stats }.record(
+                                start.elapsed(),
+                                matches!(b.meta, EventMeta::Targeted { .. }),
+                            );
+
+                            if events_before < world.event_queue.len() {
+                                // Eagerly handle any events produced by the system.
+                                b.stage = Stage::Systems {
+                                    systems,
+                                    next: i + 1,
+                                    target,
+                                };
+
+                                return Outcome::Suspended(events_before);
+                            }
+
+                            if b.event.event.is_null() {
+                                return Outcome::Taken;
+                            }
+                        }
+
+                        b.stage = Stage::NextTarget;
+
+                        let Some(set_target) = b.set_target else {
+                            continue;
+                        };
+
+                        if b.depth == MAX_BUBBLE_DEPTH {
+                            continue;
+                        }
+
+                        let Some(parent) = parent_of(world, b.current_target) else {
+                            continue;
+                        };
+
+                        // The event is still ours since no system took it.
+                        unsafe { set_target(NonNull::new_unchecked(b.event.event), parent) };
+
+                        b.current_target = parent;
+                        b.depth += 1;
+                        b.stage = Stage::Target;
+                    }
+                }
+            }
+        }
+
+        struct EventDropper {
+            event: *mut u8,
+            drop: DropFn,
+        }
+
+        impl EventDropper {
+            /// Extracts the event pointer and drop fn without running
+            /// the destructor.
+            #[inline]
+            fn unpack(self) -> (*mut u8, DropFn) {
+                let event = self.event;
+                let drop = self.drop;
+                mem::forget(self);
+
+                (event, drop)
+            }
+        }
+
+        // In case `System::run` or `handle_events` unwinds, we need to drop the event
+        // we're holding on the stack. The other events in the event queue will be
+        // handled by `World`'s destructor.
+        impl Drop for EventDropper {
+            #[inline]
+            fn drop(&mut self) {
+                if let (Some(event), Some(drop)) = (NonNull::new(self.event), self.drop) {
+                    unsafe { drop(event) };
+                }
+            }
+        }
+
+        /// Removes a component from an entity, then pushes the component's
+        /// [`Removed`] event to the event queue if it has one and the entity
+        /// had the component.
+        ///
+        /// [`Removed`]: crate::event::Removed
+        unsafe fn remove_from_entity(
+            world: &mut World,
+            entity_id: EntityId,
+            loc: EntityLocation,
+            component_idx: ComponentIdx,
+        ) {
+            let dst = match unsafe {
+                world.archetypes.traverse_remove(
+                    loc.archetype,
+                    component_idx,
+                    &mut world.components,
+                    &mut world.systems,
+                )
+            } {
+                Ok(dst) => dst,
+                Err(limit) => {
+                    send_archetype_limit_reached(world, limit);
+                    return;
+                }
+            };
+
+            world.run_remove_hooks(entity_id, dst, None);
+
+            unsafe {
+                world
+                    .archetypes
+                    .move_entity(loc, dst, [], &mut world.entities)
+            };
+
+            // `traverse_remove` doesn't move if the component is already absent.
+            if dst == loc.archetype {
+                return;
+            }
+
+            let removed_event = unsafe {
+                world
+                    .components
+                    .get_by_index(component_idx)
+                    .unwrap_debug_checked()
+            }
+            .removed_event()
+            .and_then(|id| world.events.get(id));
+
+            let Some(event_info) = removed_event else {
+                return;
+            };
+
+            let EventIdx::Targeted(idx) = event_info.id().index() else {
+                unreachable!("removed event should be a targeted `Removed` event")
+            };
+
+            let event = world.event_queue.alloc_layout(event_info.layout()).as_ptr();
+
+            // SAFETY: `Removed` is `repr(transparent)` over the target's `EntityId`.
+            unsafe {
+                event.cast::<EntityId>().write(entity_id);
+
+                world.event_queue.push_item(EventQueueItem {
+                    meta: EventMeta::Targeted {
+                        idx,
+                        target: entity_id,
+                    },
+                    event,
+                });
+            }
+        }
+
+        /// Replaces a component the entity already has. If the component has a
+        /// [`Replaced`] event, then the old value is moved into the event and
+        /// the event is pushed to the event queue. Otherwise, the old value is
+        /// dropped. The `on_remove` hook of the old value runs first.
+        ///
+        /// [`Replaced`]: crate::event::Replaced
+        unsafe fn replace_component(
+            world: &mut World,
+            loc: EntityLocation,
+            component_idx: ComponentIdx,
+            component_ptr: *const u8,
+        ) {
+            let arch = unsafe { world.archetypes.get(loc.archetype).unwrap_debug_checked() };
+            let entity_id = unsafe { *arch.entity_ids().get_debug_checked(loc.row.0 as usize) };
+
+            world.run_remove_hooks(entity_id, loc.archetype, Some(component_idx));
+
+            let replaced_event = unsafe {
+                world
+                    .components
+                    .get_by_index(component_idx)
+                    .unwrap_debug_checked()
+            }
+            .replaced_event()
+            .and_then(|id| world.events.get(id));
+
+            let Some(event_info) = replaced_event else {
+                unsafe {
+                    world.archetypes.move_entity(
+                        loc,
+                        loc.archetype,
+                        [(component_idx, component_ptr)],
+                        &mut world.entities,
+                    )
+                };
+                return;
+            };
+
+            let (
+                EventKind::Replaced {
+                    component_offset, ..
+                },
+                EventIdx::Targeted(idx),
+            ) = (event_info.kind(), event_info.id().index())
+            else {
+                unreachable!("replaced event should be a targeted `Replaced` event")
+            };
+
+            let layout = event_info.layout();
+
+            let arch = unsafe { world.archetypes.get(loc.archetype).unwrap_debug_checked() };
+            let col = unsafe { arch.column_of(component_idx).unwrap_debug_checked() };
+            let size = col.layout().size();
+            let slot = unsafe { col.row_ptr(loc.row.0 as usize) }.as_ptr();
+
+            let event = world.event_queue.alloc_layout(layout).as_ptr();
+
+            // Move the old value into the event and the new value into the column without
+            // running any destructors.
+            unsafe {
+                event.cast::<EntityId>().write(entity_id);
+                ptr::copy_nonoverlapping(slot, event.add(component_offset as usize), size);
+                ptr::copy_nonoverlapping(component_ptr, slot, size);
+
+                world.event_queue.push_item(EventQueueItem {
+                    meta: EventMeta::Targeted {
+                        idx,
+                        target: entity_id,
+                    },
+                    event,
+                });
+            }
+        }
+
+        /// Applies the [`SingletonPolicy`] of a component about to be inserted
+        /// on `entity` by removing the component from its current holder, if
+        /// the policy allows it. The `Remove` event, or the events caused by
+        /// removing the component directly, are left in the queue.
+        #[cold]
+        fn evict_singleton(world: &mut World, entity: EntityId, component_idx: ComponentIdx) {
+            let info = unsafe {
+                world
+                    .components
+                    .get_by_index(component_idx)
+                    .unwrap_debug_checked()
+            };
+
+            let (Some(policy), Some(holder)) = (info.singleton(), info.singleton_holder()) else {
+                return;
+            };
+
+            if holder == entity || policy == SingletonPolicy::Reject {
+                return;
+            }
+
+            if let Some(&event_id) = info.remove_events().first() {
+                let info = unsafe {
+                    world
+                        .events
+                        .get_by_index(event_id.index())
+                        .unwrap_debug_checked()
+                };
+
+                let EventIdx::Targeted(idx) = event_id.index() else {
+                    unreachable!("remove event should be a targeted `Remove` event")
+                };
+
+                let event = world.event_queue.alloc_layout(info.layout()).as_ptr();
+
+                // SAFETY: `Remove` is `repr(transparent)` over the target's `EntityId`.
+                unsafe {
+                    event.cast::<EntityId>().write(holder);
+
+                    world.event_queue.push_item(EventQueueItem {
+                        meta: EventMeta::Targeted {
+                            idx,
+                            target: holder,
+                        },
+                        event,
+                    });
+                }
+            } else {
+                // Nothing can listen for a `Remove` event which doesn't exist, so the
+                // component is removed directly.
+                let loc = unsafe { world.entities.get(holder).unwrap_debug_checked() };
+
+                unsafe { remove_from_entity(world, holder, loc, component_idx) };
+            }
+        }
+
+        /// Returns whether a component may be inserted on `entity` once
+        /// [`evict_singleton`] has run. Singleton components stay where they
+        /// are if the policy rejects the insert or a listener consumed the
+        /// `Remove` event.
+        fn admit_singleton(world: &World, entity: EntityId, component_idx: ComponentIdx) -> bool {
+            let info = unsafe {
+                world
+                    .components
+                    .get_by_index(component_idx)
+                    .unwrap_debug_checked()
+            };
+
+            info.singleton().is_none()
+                || info
+                    .singleton_holder()
+                    .is_none_or(|holder| holder == entity)
+        }
+
+        /// Inserts a component on an entity along with any of the component's
+        /// required components the entity is missing.
+        ///
+        /// `dst` is the archetype after inserting only the component itself.
+        #[cold]
+        unsafe fn insert_with_required(
+            world: &mut World,
+            loc: EntityLocation,
+            mut dst: ArchetypeIdx,
+            component_idx: ComponentIdx,
+            component_ptr: *const u8,
+        ) -> Result<(), ArchetypeLimitReached> {
+            // `traverse_insert` doesn't move if the component is already present.
+            let src_has_component = dst == loc.archetype;
+
+            let mut missing = vec![];
+
+            let required = unsafe {
+                world
+                    .components
+                    .get_by_index(component_idx)
+                    .unwrap_debug_checked()
+            }
+            .required()
+            .to_vec();
+
+            for (id, default) in required {
+                // The required component might have been removed from the world.
+                let Some(info) = world.components.get(id) else {
+                    continue;
+                };
+
+                let dst_arch = unsafe { world.archetypes.get(dst).unwrap_debug_checked() };
+
+                if dst_arch.column_of(id.index()).is_some() {
+                    continue;
+                }
+
+                missing.push((id.index(), default, info.layout()));
+            }
+
+            // `traverse_insert_many` and `move_entity` expect components in column order.
+            missing.sort_unstable_by_key(|&(idx, _, _)| idx);
+
+            let inserted = missing.iter().map(|&(idx, _, _)| idx).collect::<Vec<_>>();
+
+            // Find the destination before creating any default values, so that nothing
+            // needs to be cleaned up if there is no room for it.
+            dst = unsafe {
+                world.archetypes.traverse_insert_many(
+                    dst,
+                    &inserted,
+                    &mut world.components,
+                    &mut world.systems,
+                )
+            }?;
+
+            let mut new_components = missing
+                .into_iter()
+                .map(|(idx, default, layout)| {
+                    let ptr = world.event_queue.alloc_layout(layout);
+                    unsafe { default(ptr) };
+
+                    (idx, ptr.as_ptr().cast_const())
+                })
+                .collect::<Vec<_>>();
+
+            if src_has_component {
+                // Replace the existing value in place first.
+                unsafe { replace_component(world, loc, component_idx, component_ptr) };
+
+                if new_components.is_empty() {
+                    return Ok(());
+                }
+            } else {
+                new_components.push((component_idx, component_ptr));
+            }
+
+            // `move_entity` expects new components in column order.
+            new_components.sort_unstable_by_key(|&(idx, _)| idx);
+
+            unsafe {
+                world
+                    .archetypes
+                    .move_entity(loc, dst, new_components, &mut world.entities)
+            };
+
+            Ok(())
+        }
+
+        /// Queues [`ArchetypeLimitReached`] for a structural change which was
+        /// dropped, if anything listens for it.
+        #[cold]
+        fn send_archetype_limit_reached(world: &mut World, limit: ArchetypeLimitReached) {
+            if let Some(info) = world
+                .events
+                .get_by_type_id(TypeId::of::<ArchetypeLimitReached>())
+            {
+                let idx = info.id().index().as_u32();
+
+                unsafe { world.event_queue.push(limit, idx) };
+            }
+        }
+
+        /// Returns the parent in the [`ChildOf`] component of `entity`, if any.
+        fn parent_of(world: &World, entity: EntityId) -> Option<EntityId> {
+            let loc = world.entities.get(entity)?;
+
+            let idx = world
+                .components
+                .get_by_type_id(TypeId::of::<ChildOf>())?
+                .id()
+                .index();
+
+            let arch = unsafe { world.archetypes.get(loc.archetype).unwrap_debug_checked() };
+            let col = arch.column_of(idx)?;
+
+            Some(unsafe { col.row_ptr(loc.row.0 as usize).cast::<ChildOf>().as_ref() }.0)
+        }
+    }
+
+    /// Returns a new [`UnsafeWorldCell`] with permission to _read_ all data in
+    /// this world.
+    pub fn unsafe_cell(&self) -> UnsafeWorldCell<'_> {
+        UnsafeWorldCell {
+            world: NonNull::from(self),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a new [`UnsafeWorldCell`] with permission to _read and write_
+    /// all data in this world.
+    pub fn unsafe_cell_mut(&mut self) -> UnsafeWorldCell<'_> {
+        UnsafeWorldCell {
+            world: NonNull::from(self),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds a [`World`] whose components and events have fixed indices.
+///
+/// Indices such as [`ComponentIdx`] and [`EventIdx`] are assigned in the order
+/// things are added to a world. Registering everything up front in the same
+/// order gives worlds in different builds, like the client and server of a
+/// networked game, the same indices. [`manifest`] lists the assignment so the
+/// two sides can compare them.
+///
+/// Components and events can still be added to the built world as usual.
+/// They're given indices after the pre-registered ones, and the indices of
+/// pre-registered components and events are never reused, even if they're
+/// removed.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::world::WorldBuilder;
+///
+/// #[derive(Component)]
+/// struct Pos(f32, f32);
+///
+/// #[derive(Event)]
+/// struct Damage(#[event(target)] EntityId, u32);
+///
+/// fn build() -> WorldBuilder {
+///     let mut builder = WorldBuilder::new();
+///     builder.register_component::<Pos>();
+///     builder.register_event::<Damage>();
+///     builder
+/// }
+///
+/// let server = build();
+/// let client = build();
+/// assert_eq!(server.manifest(), client.manifest());
+///
+/// let world = server.build();
+/// ```
+///
+/// [`ComponentIdx`]: crate::component::ComponentIdx
+/// [`EventIdx`]: crate::event::EventIdx
+/// [`manifest`]: WorldBuilder::manifest
+#[derive(Debug)]
+pub struct WorldBuilder {
+    world: World,
+}
+
+impl WorldBuilder {
+    /// Creates a new builder for an empty world.
+    pub fn new() -> Self {
+        Self {
+            world: World::new(),
+        }
+    }
+
+    /// Registers the component `C` along with its [required components] and
+    /// returns its ID. See [`World::add_component`].
+    ///
+    /// [required components]: Component::required
+    #[track_caller]
+    pub fn register_component<C: Component>(&mut self) -> ComponentId {
+        self.world.add_component::<C>()
+    }
+
+    /// Registers the event `E` and returns its ID. See [`World::add_event`].
+    pub fn register_event<E: Event>(&mut self) -> EventId {
+        self.world.add_event::<E>()
+    }
+
+    /// Adds a system to the world. See [`World::add_system`].
+    ///
+    /// The components and events the system accesses are registered as well,
+    /// in the order the system's parameters mention them.
+    #[track_caller]
+    pub fn add_system<S: IntoSystem<M>, M>(&mut self, system: S) -> SystemId {
+        self.world.add_system(system)
+    }
+
+    /// Returns the name and index of every registered component, followed by
+    /// those of every event. Components are listed in order of their
+    /// [`ComponentIdx`], and events in order of their [`EventIdx`], with
+    /// untargeted events first.
+    ///
+    /// This includes the events the world registers for itself.
+    ///
+    /// [`ComponentIdx`]: crate::component::ComponentIdx
+    /// [`EventIdx`]: crate::event::EventIdx
+    pub fn manifest(&self) -> Vec<(String, u32)> {
+        let components = self
+            .world
+            .components()
+            .iter()
+            .map(|info| (info.name().into(), info.id().index().0));
+
+        let mut events: Vec<_> = self.world.events().iter().collect();
+        events.sort_by_key(|info| {
+            let idx = info.id().index();
+            (idx.is_targeted(), idx.as_u32())
+        });
+
+        components
+            .chain(
+                events
+                    .into_iter()
+                    .map(|info| (info.name().into(), info.id().index().as_u32())),
+            )
+            .collect()
+    }
+
+    /// Finishes registration and returns the world.
+    pub fn build(mut self) -> World {
+        self.world.components.pin_indices();
+        self.world.events.pin_indices();
+        self.world
+    }
+}
+
+impl Default for WorldBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The destination of an event sent by [`World::send_and_return`].
+#[derive(Debug)]
+struct ReturnSlot {
+    /// The index of the event in the event queue, until it starts
+    /// broadcasting.
+    queue_idx: Option<usize>,
+    dst: *mut u8,
+    size: usize,
+    /// Whether the event was moved to `dst`.
+    returned: bool,
+}
+
+/// Installs a [`ReturnSlot`] for the duration of a flush. The previous slot is
+/// restored when the guard is dropped, including when a system unwinds, so a
+/// stale `dst` is never written to.
+struct ReturnSlotGuard<'a> {
+    world: &'a mut World,
+    prev: Option<ReturnSlot>,
+}
+
+impl<'a> ReturnSlotGuard<'a> {
+    fn new(world: &'a mut World, slot: ReturnSlot) -> Self {
+        let prev = world.return_slot.replace(slot);
+        Self { world, prev }
+    }
+
+    /// Removes the slot and returns whether the event was moved to it.
+    fn finish(self) -> bool {
+        self.world
+            .return_slot
+            .as_ref()
+            .is_some_and(|slot| slot.returned)
+    }
+}
+
+impl Drop for ReturnSlotGuard<'_> {
+    fn drop(&mut self) {
+        self.world.return_slot = self.prev.take();
+    }
+}
+
+/// Locks structural changes to a world while a system runs. The previous lock
+/// state is restored when the guard is dropped, including when the system
+/// unwinds. Does nothing in release builds.
+struct StructureGuard<'a> {
+    #[cfg(debug_assertions)]
+    world: UnsafeWorldCell<'a>,
+    #[cfg(debug_assertions)]
+    was_locked: bool,
+    #[cfg(not(debug_assertions))]
+    _marker: PhantomData<UnsafeWorldCell<'a>>,
+}
+
+impl<'a> StructureGuard<'a> {
+    /// # Safety
+    ///
+    /// Nothing may access the world until the system starts running, and
+    /// the guard must be dropped before anything else accesses the world
+    /// after the system returns.
+    #[inline]
+    unsafe fn new(world: UnsafeWorldCell<'a>) -> Self {
+        #[cfg(debug_assertions)]
+        {
+            let w = unsafe { &mut *world.world.as_ptr() };
+            let was_locked = w.archetypes.lock.set(true);
+            w.entities.lock.set(true);
+
+            Self { world, was_locked }
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            let _ = world;
+            Self {
+                _marker: PhantomData,
+            }
+        }
+    }
+}
+
+impl Drop for StructureGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            // SAFETY: The system has returned or unwound, so nothing else is using
+            // the world.
+            let w = unsafe { &mut *self.world.world.as_ptr() };
+            w.archetypes.lock.set(self.was_locked);
+            w.entities.lock.set(self.was_locked);
+        }
+    }
+}
+
+impl Drop for World {
+    fn drop(&mut self) {
+        // Drop commands left over from a system which panicked. Their data lives
+        // in the event queue's allocator, so this must happen first.
+        unsafe { self.commands.drop_all() };
+
+        // Drop in-flight events still in the event queue. This can happen if a panic
+        // occurs.
+        for item in self.event_queue.iter() {
+            if let Some(event) = NonNull::new(item.event) {
+                let info = unsafe {
+                    self.events
+                        .get_by_index(item.meta.event_idx())
+                        .unwrap_debug_checked()
+                };
+
+                if let Some(drop) = info.drop() {
+                    unsafe { drop(event) };
+                }
+            }
+        }
+    }
+}
+
+unsafe impl Send for World {}
+unsafe impl Sync for World {}
+
+impl UnwindSafe for World {}
+impl RefUnwindSafe for World {}
+
+/// Identifies a [`World`].
+///
+/// World IDs are only tracked when the `validate-ids` feature is enabled.
+/// [`ComponentId`]s, [`SystemId`]s, and [`EventId`]s then record the world
+/// that created them, so IDs from different worlds never compare equal, and
+/// looking up an ID in the wrong world fails a debug assertion.
+///
+/// When the feature is disabled, `WorldId` is zero-sized and all world IDs are
+/// equal, so the representation of the other IDs is unaffected.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct WorldId {
+    #[cfg(feature = "validate-ids")]
+    id: u32,
+}
+
+impl WorldId {
+    /// The world ID which doesn't belong to any world. IDs created manually,
+    /// such as with [`ComponentId::new`], use this and are accepted by every
+    /// world.
+    pub const NONE: Self = Self {
+        #[cfg(feature = "validate-ids")]
+        id: 0,
+    };
+
+    fn next() -> Self {
+        #[cfg(feature = "validate-ids")]
+        {
+            use core::sync::atomic::{AtomicU32, Ordering};
+
+            static NEXT: AtomicU32 = AtomicU32::new(1);
+
+            let id = NEXT.fetch_add(1, Ordering::Relaxed);
+            assert_ne!(id, 0, "too many worlds");
+
+            Self { id }
+        }
+
+        #[cfg(not(feature = "validate-ids"))]
+        Self::NONE
+    }
+
+    /// Can an ID tagged with `self` be used in the world identified by
+    /// `world`?
+    #[cfg_attr(
+        not(feature = "validate-ids"),
+        allow(clippy::unused_self, unused_variables)
+    )]
+    pub(crate) fn is_compatible(self, world: WorldId) -> bool {
+        #[cfg(feature = "validate-ids")]
+        return self.id == 0 || self.id == world.id;
+
+        #[cfg(not(feature = "validate-ids"))]
+        true
+    }
+}
+
+impl fmt::Debug for WorldId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "validate-ids")]
+        return f.debug_tuple("WorldId").field(&self.id).finish();
+
+        #[cfg(not(feature = "validate-ids"))]
+        f.write_str("WorldId")
+    }
+}
+
+/// A warning passed to the [warning handler](World::set_warning_handler).
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum WarningInfo<'a> {
+    /// A targeted event was dropped because its target doesn't exist. Only
+    /// reported with [`DeadTargetPolicy::Warn`].
+    DeadTarget {
+        /// The dropped event.
+        event: &'a EventInfo,
+        /// The entity the event was sent to.
+        target: EntityId,
+    },
+}
+
+impl fmt::Display for WarningInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningInfo::DeadTarget { event, target } => write!(
+                f,
+                "event `{}` was sent to {target:?}, which doesn't exist",
+                event.name()
+            ),
+        }
+    }
+}
+
+/// A function called with warnings about a world. See
+/// [`World::set_warning_handler`].
+pub type WarningHandler = fn(&WarningInfo<'_>);
+
+/// Used for queueing events. Passed to the closure given in [`send_many`].
+///
+/// [`send_many`]: World::send_many
+#[derive(Debug)]
+pub struct Sender<'a> {
+    world: &'a mut World,
+}
+
+impl Sender<'_> {
+    /// Enqueue an event.
+    pub fn send<E: Event>(&mut self, event: E) {
+        self.world.queue_event(event);
+    }
+
+    /// Enqueue a boxed event. See [`World::send_boxed`].
+    pub fn send_boxed<E: Event>(&mut self, event: Box<E>) {
+        self.world.queue_boxed_event(event);
+    }
+
+    /// Enqueue the spawning of an entity and [`Spawn`] event. Returns the
+    /// [`EntityId`] of the entity that will be spawned.
+    pub fn spawn(&mut self) -> EntityId {
+        let id = self.world.reserved_entities.reserve(&self.world.entities);
+        unsafe {
+            self.world
+                .event_queue
+                .push(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32())
+        }
+        self.send(Spawn(id));
+        id
+    }
+
+    /// Enqueue an [`Insert`] event.
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.send(Insert::new(entity, component))
+    }
+
+    /// Enqueue a [`Remove`] event.
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.send(Remove::<C>::new(entity))
+    }
+
+    /// Enqueue a [`Despawn`] event.
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.send(Despawn(entity))
+    }
+}
+
+/// Reference to a [`World`] where all methods take `&self` and aliasing rules
+/// are not checked. It is the caller's responsibility to ensure that
+/// Rust's aliasing rules are not violated.
+///
+/// This is what systems use internally to access the world, and it can be used
+/// by integration layers to implement their own fetching logic. The metadata
+/// returned by [`entities`], [`archetypes`], and [`components`] may always be
+/// read. Component data obtained through [`Column::data`] or
+/// [`get_component_ptr`] may only be read while nothing else is writing it, and
+/// may only be written if the cell was created with [`World::unsafe_cell_mut`]
+/// and nothing else is accessing it.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100));
+///
+/// let idx = world.add_component::<Health>().index();
+/// let cell = world.unsafe_cell_mut();
+///
+/// let ptr = cell.get_component_ptr(e, idx).unwrap();
+///
+/// // SAFETY: The cell has write access to the whole world, the pointer refers
+/// // to a `Health`, and there are no other references to it.
+/// unsafe { ptr.cast::<Health>().as_mut().0 -= 10 };
+///
+/// assert_eq!(world.get_component::<Health>(e).unwrap().0, 90);
+/// ```
+///
+/// [`entities`]: Self::entities
+/// [`archetypes`]: Self::archetypes
+/// [`components`]: Self::components
+/// [`Column::data`]: crate::archetype::Column::data
+/// [`get_component_ptr`]: Self::get_component_ptr
+#[derive(Clone, Copy, Debug)]
+pub struct UnsafeWorldCell<'a> {
+    world: NonNull<World>,
+    _marker: PhantomData<(&'a World, &'a UnsafeCell<World>)>,
+}
+
+impl<'a> UnsafeWorldCell<'a> {
+    /// # Safety
+    ///
+    /// - Must be called from within a system.
+    /// - Must have permission to access the event queue mutably.
+    /// - Event index must be correct for the given event.
+    pub unsafe fn send_with_index<E: Event>(self, event: E, idx: u32) {
+        unsafe { (*self.world.as_ptr()).event_queue.push(event, idx) }
+    }
+
+    /// Like [`send_with_index`](Self::send_with_index), but for a boxed
+    /// event.
+    ///
+    /// # Safety
+    ///
+    /// See [`send_with_index`](Self::send_with_index).
+    pub unsafe fn send_boxed_with_index<E: Event>(self, event: Box<E>, idx: u32) {
+        unsafe { (*self.world.as_ptr()).event_queue.push_boxed(event, idx) }
+    }
+
+    /// # Safety
+    ///
+    /// - Must be called from within a system.
+    /// - Must have permission to access the event queue mutably.
+    pub unsafe fn queue_spawn(self) -> EntityId {
+        let entity_id = (*self.world.as_ptr())
+            .reserved_entities
+            .reserve(self.entities());
+        self.send_with_index(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32());
+        entity_id
+    }
+
+    /// # Safety
+    ///
+    /// - Must be called from within a system.
+    /// - Must have permission to access the event queue mutably.
+    pub(crate) unsafe fn reserve_entity(self) -> EntityId {
+        (*self.world.as_ptr())
+            .reserved_entities
+            .reserve(self.entities())
+    }
+
+    /// Allocates memory which lives until the event queue is cleared.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a system.
+    /// - Must have permission to access the event queue mutably.
+    pub(crate) unsafe fn alloc_layout(self, layout: Layout) -> NonNull<u8> {
+        (*self.world.as_ptr()).event_queue.alloc_layout(layout)
+    }
+
+    /// # Safety
+    ///
+    /// - Must be called from within a system.
+    /// - Must have permission to access the event queue mutably.
+    pub(crate) unsafe fn commands(self) -> &'a mut CommandQueue {
+        &mut (*self.world.as_ptr()).commands
+    }
+
+    /// # Safety
+    ///
+    /// - Must be called from within a system.
+    /// - Must have permission to access the event queue mutably.
+    pub(crate) unsafe fn scheduler(self) -> &'a mut Scheduler {
+        &mut (*self.world.as_ptr()).scheduler
+    }
+
+    /// # Safety
+    ///
+    /// - Must be called from within a system.
+    /// - No other references to the channels may exist.
+    pub(crate) unsafe fn event_channels(self) -> &'a mut EventChannels {
+        &mut (*self.world.as_ptr()).event_channels
+    }
+
+    /// Returns the entity the targeted event being broadcast is currently
+    /// visiting.
+    pub(crate) fn current_target(self) -> EntityId {
+        unsafe { (*self.world.as_ptr()).current_target }
+    }
+
+    /// Returns the entity the targeted event being broadcast was sent to,
+    /// before any [bubbling](Event::BUBBLES).
+    pub(crate) fn original_target(self) -> EntityId {
+        unsafe { (*self.world.as_ptr()).original_target }
+    }
+
+    /// Returns the index of the event being broadcast to the running system.
+    pub(crate) fn current_event(self) -> EventIdx {
+        unsafe { (*self.world.as_ptr()).current_event }
+    }
+
+    /// Returns the [`Entities`] for this world.
+    pub fn entities(self) -> &'a Entities {
+        unsafe { &(*self.world.as_ptr()).entities }
+    }
+
+    /// Returns the [`Components`] for this world.
+    pub fn components(self) -> &'a Components {
+        unsafe { &(*self.world.as_ptr()).components }
+    }
+
+    /// Returns the [`Systems`] for this world.
+    pub fn systems(self) -> &'a Systems {
+        unsafe { &(*self.world.as_ptr()).systems }
+    }
+
+    /// Returns the [`Archetypes`] for this world.
+    pub fn archetypes(self) -> &'a Archetypes {
+        unsafe { &(*self.world.as_ptr()).archetypes }
+    }
+
+    /// Returns the [`Events`] for this world.
+    pub fn events(self) -> &'a Events {
+        unsafe { &(*self.world.as_ptr()).events }
+    }
+
+    /// Returns the [`Globals`] for this world.
+    pub fn globals(self) -> &'a Globals {
+        unsafe { &(*self.world.as_ptr()).globals }
+    }
+
+    /// Returns a pointer to the component with index `component` on `entity`,
+    /// or `None` if the entity doesn't exist or doesn't have the component.
+    ///
+    /// The pointer is valid until the entity is moved or despawned. See the
+    /// [type-level documentation](Self) for the rules on accessing the data
+    /// behind it.
+    pub fn get_component_ptr(
+        self,
+        entity: EntityId,
+        component: ComponentIdx,
+    ) -> Option<NonNull<u8>> {
+        self.world().check_thread();
+
+        let loc = self.entities().get(entity)?;
+
+        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_debug_checked() };
+
+        let col = arch.column_of(component)?;
+
+        Some(unsafe { col.row_ptr(loc.row.0 as usize) })
+    }
+
+    /// Returns an immutable reference to the underlying world.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission to access the entire world immutably.
+    pub fn world(self) -> &'a World {
+        unsafe { &*self.world.as_ptr() }
+    }
+
+    /// Returns a mutable reference to the underlying world.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission the access the entire world immutably.
+    pub unsafe fn world_mut(self) -> &'a mut World {
+        &mut *self.world.as_ptr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use core::alloc::Layout;
+    use core::any::TypeId;
+    use std::panic;
+    use std::sync::Mutex;
+
+    use crate::archetype::{ArchetypeIdx, ArchetypeRow};
+    use crate::component::HookContext;
+    use crate::entity::{CloneError, CloneOptions};
+    use crate::event::{EventIdx, UntargetedEventIdx};
+    use crate::prelude::*;
+    use crate::system::Priority;
+    use crate::world::{WorldBuilder, WorldId};
+
+    #[test]
+    fn world_drops_events() {
+        #[derive(Event)]
+        struct A(Arc<()>);
+
+        impl Drop for A {
+            fn drop(&mut self) {
+                eprintln!("calling A destructor");
+            }
+        }
+
+        #[derive(Event)]
+        struct B(Arc<()>);
+
+        impl Drop for B {
+            fn drop(&mut self) {
+                eprintln!("calling B destructor");
+            }
+        }
+
+        #[derive(Event)]
+        struct C(Arc<()>);
+
+        impl Drop for C {
+            fn drop(&mut self) {
+                eprintln!("calling C destructor");
+            }
+        }
+
+        let mut world = World::new();
+
+        world.add_system(|r: Receiver<A>, mut s: Sender<B>| {
+            s.send(B(r.event.0.clone()));
+            s.send(B(r.event.0.clone()));
+        });
+
+        world.add_system(|r: Receiver<B>, mut s: Sender<C>| {
+            s.send(C(r.event.0.clone()));
+            s.send(C(r.event.0.clone()));
+        });
+
+        world.add_system(|r: Receiver<C>| println!("got C {:?}", Arc::as_ptr(&r.event.0)));
+
+        let arc = Arc::new(());
+
+        world.send(A(arc.clone()));
+
+        drop(world);
+
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    fn world_drops_events_on_panic() {
+        #[derive(Event)]
+        struct A(Arc<()>);
+
+        impl Drop for A {
+            fn drop(&mut self) {
+                eprintln!("calling A destructor");
+            }
+        }
+
+        #[derive(Event)]
+        struct B(Arc<()>);
+
+        impl Drop for B {
+            fn drop(&mut self) {
+                eprintln!("calling B destructor");
+            }
+        }
+
+        #[allow(dead_code)]
+        #[derive(Event)]
+        struct C(Arc<()>);
+
+        impl Drop for C {
+            fn drop(&mut self) {
+                eprintln!("calling C destructor");
+            }
+        }
+
+        let mut world = World::new();
+
+        world.add_system(|r: Receiver<A>, mut s: Sender<B>| {
+            s.send(B(r.event.0.clone()));
+            s.send(B(r.event.0.clone()));
+        });
+
+        world.add_system(|r: Receiver<B>, mut sender: Sender<C>| {
+            sender.send(C(r.event.0.clone()));
+            sender.send(C(r.event.0.clone()));
+        });
+
+        world.add_system(|_: Receiver<C>| panic!("oops!"));
+
+        let arc = Arc::new(());
+        let arc_cloned = arc.clone();
+
+        let res = panic::catch_unwind(move || world.send(A(arc_cloned)));
+
+        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "oops!");
+
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    fn entity_location_after_swap_remove() {
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let entities = [(); 3].map(|()| {
+            let e = world.spawn();
+            world.insert(e, C);
+            e
+        });
+
+        let arch = world.entity_location(entities[0]).unwrap().archetype;
+
+        for (i, &e) in entities.iter().enumerate() {
+            let loc = world.entity_location(e).unwrap();
+            assert_eq!(loc.archetype, arch);
+            assert_eq!(loc.row.0, i as u32);
+            assert_eq!(
+                world.archetypes().entity_at(loc.archetype, loc.row),
+                Some(e)
+            );
+        }
+
+        // Despawning the middle entity swaps the last entity into its row.
+        let middle = world.entity_location(entities[1]).unwrap();
+        world.despawn(entities[1]);
+
+        assert_eq!(world.entity_location(entities[1]), None);
+        assert_eq!(world.entity_location(entities[2]), Some(middle));
+        assert_eq!(
+            world.archetypes().entity_at(arch, middle.row),
+            Some(entities[2])
+        );
+        assert_eq!(world.archetypes().entity_at(arch, ArchetypeRow(2)), None);
+
+        // Moving an entity to another archetype also swaps.
+        world.remove::<C>(entities[0]);
+
+        let empty = world.entity_location(entities[0]).unwrap();
+        assert_eq!(empty.archetype, ArchetypeIdx::EMPTY);
+        assert_eq!(
+            world.archetypes().entity_at(empty.archetype, empty.row),
+            Some(entities[0])
+        );
+
+        let loc = world.entity_location(entities[2]).unwrap();
+        assert_eq!(loc.archetype, arch);
+        assert_eq!(loc.row, ArchetypeRow(0));
+        assert_eq!(
+            world.archetypes().entity_at(arch, ArchetypeRow(0)),
+            Some(entities[2])
+        );
+        assert_eq!(
+            world
+                .archetypes()
+                .entity_at(ArchetypeIdx::NULL, ArchetypeRow(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn insert_replacement_drops_old_value_once() {
+        #[allow(dead_code)]
+        #[derive(Component)]
+        struct C(Arc<()>);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+
+        let first = Arc::new(());
+        let second = Arc::new(());
+
+        world.insert(e, C(first.clone()));
+        world.insert(e, C(second.clone()));
+
+        assert_eq!(Arc::strong_count(&first), 1);
+        assert_eq!(Arc::strong_count(&second), 2);
+
+        drop(world);
+
+        assert_eq!(Arc::strong_count(&second), 1);
+    }
+
+    #[test]
+    fn send_and_return() {
+        #[derive(Component)]
+        struct Name(&'static str);
+
+        #[allow(dead_code)]
+        #[derive(Event)]
+        struct GetName {
+            #[event(target)]
+            entity: EntityId,
+            name: Option<&'static str>,
+            token: Arc<()>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Event)]
+        struct Discard(Arc<()>);
+
+        let mut world = World::new();
+
+        world.add_system(|mut r: ReceiverMut<GetName, &Name>| {
+            r.event.name = Some(r.query.0);
+        });
+        world.add_system(|r: ReceiverMut<Discard>| {
+            EventMut::take(r.event);
+        });
+
+        let named = world.spawn();
+        world.insert(named, Name("alice"));
+        let unnamed = world.spawn();
+
+        let token = Arc::new(());
+
+        let event = world
+            .send_and_return(GetName {
+                entity: named,
+                name: None,
+                token: token.clone(),
+            })
+            .unwrap();
+        assert_eq!(event.name, Some("alice"));
+        assert_eq!(Arc::strong_count(&token), 2);
+        drop(event);
+
+        let event = world
+            .send_and_return(GetName {
+                entity: unnamed,
+                name: Some("unchanged"),
+                token: token.clone(),
+            })
+            .unwrap();
+        assert_eq!(event.name, Some("unchanged"));
+        drop(event);
+
+        assert!(world.send_and_return(Discard(token.clone())).is_none());
+        assert_eq!(Arc::strong_count(&token), 1);
+    }
+
+    #[test]
+    fn vetoed_insert() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static HOOK_RUNS: AtomicU32 = AtomicU32::new(0);
+
+        #[derive(Component)]
+        struct Wet;
+
+        #[allow(dead_code)]
+        #[derive(Component)]
+        #[component(on_insert = count_inserts)]
+        struct Burning(Arc<()>);
+
+        fn count_inserts(_: &mut HookContext, _: EntityId, _: &Burning) {
+            HOOK_RUNS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut world = World::new();
+
+        world.add_system(
+            |r: ReceiverMut<Insert<Burning>, ()>, wet: Fetcher<Has<&Wet>>| {
+                if wet.get(r.target).is_ok_and(|has| has.get()) {
+                    EventMut::take(r.event);
+                }
+            },
+        );
+
+        let seen = Arc::new(AtomicU32::new(0));
+        let seen_clone = seen.clone();
+        world.add_system(
+            (move |_: Receiver<Insert<Burning>, ()>| {
+                seen_clone.fetch_add(1, Ordering::Relaxed);
+            })
+            .after(),
+        );
+
+        let wet = world.add_component::<Wet>().index();
+        let burning = world.add_component::<Burning>().index();
+
+        let e = world.spawn();
+        world.insert(e, Wet);
+
+        let archetype_count = world.archetypes().len();
+        let value = Arc::new(());
+
+        world.insert(e, Burning(value.clone()));
+
+        assert!(world.get_component::<Burning>(e).is_none());
+        assert_eq!(Arc::strong_count(&value), 1);
+        assert_eq!(world.archetypes().len(), archetype_count);
+        assert!(world
+            .archetypes()
+            .get_by_components(&[wet, burning])
+            .is_none());
+        assert_eq!(HOOK_RUNS.load(Ordering::Relaxed), 0);
+        assert_eq!(seen.load(Ordering::Relaxed), 0);
+
+        let dry = world.spawn();
+        world.insert(dry, Burning(value.clone()));
+
+        assert!(world.get_component::<Burning>(dry).is_some());
+        assert_eq!(HOOK_RUNS.load(Ordering::Relaxed), 1);
+        assert_eq!(seen.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn replaced_event() {
+        #[allow(dead_code)]
+        #[derive(Component, Debug)]
+        struct C(u32, Arc<()>);
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+        let log_cloned = log.clone();
+
+        world.add_system(move |r: Receiver<Replaced<C>, &C>| {
+            log_cloned.lock().unwrap().push((r.event.old.0, r.query.0));
+        });
+
+        let taken = Arc::new(Mutex::new(None));
+        let taken_cloned = taken.clone();
+
+        world.add_system(move |r: ReceiverMut<Replaced<C>, ()>| {
+            *taken_cloned.lock().unwrap() = Some(EventMut::take(r.event).old);
+        });
+
+        let arc = Arc::new(());
+
+        let e = world.spawn();
+        world.insert(e, C(1, arc.clone()));
+        assert!(log.lock().unwrap().is_empty());
+
+        world.insert(e, C(2, arc.clone()));
+        assert_eq!(*log.lock().unwrap(), [(1, 2)]);
+
+        // The old value was moved out by the second listener.
+        assert_eq!(taken.lock().unwrap().as_ref().unwrap().0, 1);
+        assert_eq!(Arc::strong_count(&arc), 3);
+
+        *taken.lock().unwrap() = None;
+        assert_eq!(Arc::strong_count(&arc), 2);
+    }
+
+    #[test]
+    fn replaced_event_with_required_components() {
+        #[derive(Component, Default)]
+        struct A;
+
+        #[allow(dead_code)]
+        #[derive(Component)]
+        #[component(requires(A))]
+        struct B(Arc<()>);
+
+        let mut world = World::new();
+
+        let count = Arc::new(Mutex::new(0));
+        let count_cloned = count.clone();
+
+        world.add_system(move |_: Receiver<Replaced<B>, (&A, &B)>| {
+            *count_cloned.lock().unwrap() += 1;
+        });
+
+        let arc = Arc::new(());
+
+        let e = world.spawn();
+        world.insert(e, B(arc.clone()));
+        world.remove::<A>(e);
+        world.insert(e, B(arc.clone()));
+
+        assert_eq!(*count.lock().unwrap(), 1);
+        assert!(world.get_component::<A>(e).is_some());
+        assert_eq!(Arc::strong_count(&arc), 2);
+    }
+
+    #[test]
+    fn despawn_listeners_see_components() {
+        #[derive(Component, Clone, Copy, PartialEq, Debug)]
+        struct Position(i32, i32);
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Despawn, ()>, f: Fetcher<&Position>| {
+            log_cloned
+                .lock()
+                .unwrap()
+                .push(("before", f.get(r.event.0).ok().copied()));
+        });
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Despawned>, f: Fetcher<&Position>| {
+            log_cloned
+                .lock()
+                .unwrap()
+                .push(("after", f.get(r.event.0).ok().copied()));
+        });
+
+        let e = world.spawn();
+        world.insert(e, Position(1, 2));
+        world.despawn(e);
+
+        // Despawning a missing entity sends neither event.
+        world.despawn(e);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [("before", Some(Position(1, 2))), ("after", None)]
+        );
+    }
+
+    #[test]
+    fn remove_listeners_see_component() {
+        #[derive(Component)]
+        struct Buffer(u32, Arc<()>);
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Remove<Buffer>, &Buffer>| {
+            log_cloned.lock().unwrap().push((
+                "remove",
+                Some(r.query.0),
+                Arc::strong_count(&r.query.1),
+            ));
+        });
+
+        let arc = Arc::new(());
+
+        let log_cloned = log.clone();
+        let arc_cloned = arc.clone();
+        world.add_system(
+            move |r: Receiver<Removed<Buffer>, ()>, f: Fetcher<&Buffer>| {
+                let buffer = f.get(r.event.entity).ok().map(|b| b.0);
+                log_cloned.lock().unwrap().push((
+                    "removed",
+                    buffer,
+                    Arc::strong_count(&arc_cloned),
+                ));
+            },
+        );
+
+        let e = world.spawn();
+        world.insert(e, Buffer(7, arc.clone()));
+        world.remove::<Buffer>(e);
+
+        // Removing a missing component doesn't send `Removed`.
+        world.remove::<Buffer>(e);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [("remove", Some(7), 3), ("removed", None, 2)]
+        );
+    }
+
+    #[test]
+    fn remove_listeners_change_other_components() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        world.add_system(
+            |r: Receiver<Remove<A>, ()>, mut s: Sender<(Remove<B>, Insert<C>)>| {
+                s.remove::<B>(r.event.entity);
+                s.insert(r.event.entity, C);
+            },
+        );
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Remove<B>, Has<&A>>| {
+            assert!(r.query.get());
+            log_cloned.lock().unwrap().push("remove B");
+        });
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Insert<C>, Has<&A>>| {
+            assert!(r.query.get());
+            log_cloned.lock().unwrap().push("insert C");
+        });
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Removed<A>, (Has<&B>, Has<&C>)>| {
+            assert_eq!((r.query.0.get(), r.query.1.get()), (false, true));
+            log_cloned.lock().unwrap().push("removed A");
+        });
+
+        let e = world.spawn();
+        world.insert(e, A);
+        world.insert(e, B);
+        world.remove::<A>(e);
+
+        // The listener's changes are applied before `A` is removed.
+        assert_eq!(*log.lock().unwrap(), ["remove B", "insert C", "removed A"]);
+        assert!(world.get_component::<A>(e).is_none());
+        assert!(world.get_component::<B>(e).is_none());
+        assert!(world.get_component::<C>(e).is_some());
+    }
+
+    #[test]
+    fn remove_listeners_with_commands() {
+        #[derive(Component)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct Kill;
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        world.add_system(
+            |r: Receiver<Remove<A>, (&A, Has<&Kill>)>, mut cmds: Commands| {
+                let (a, kill) = r.query;
+
+                if kill.get() {
+                    cmds.despawn(r.event.entity);
+                } else {
+                    cmds.insert(r.event.entity, A(a.0 + 1));
+                    cmds.insert(r.event.entity, B);
+                }
+            },
+        );
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Replaced<A>, &A>| {
+            log_cloned
+                .lock()
+                .unwrap()
+                .push((r.event.old.0, Some(r.query.0)));
+        });
+
+        let log_cloned = log.clone();
+        world.add_system(move |r: Receiver<Removed<A>, Has<&B>>| {
+            assert!(r.query.get());
+            log_cloned.lock().unwrap().push((0, None));
+        });
+
+        let e1 = world.spawn();
+        world.insert(e1, A(1));
+        world.remove::<A>(e1);
+
+        // Inserting a new `A` from a listener doesn't cancel the removal.
+        assert_eq!(*log.lock().unwrap(), [(1, Some(2)), (0, None)]);
+        assert!(world.get_component::<A>(e1).is_none());
+        assert!(world.get_component::<B>(e1).is_some());
+
+        log.lock().unwrap().clear();
+
+        // Despawning the entity does.
+        let e2 = world.spawn();
+        world.insert(e2, A(1));
+        world.insert(e2, Kill);
+        world.remove::<A>(e2);
+
+        assert!(log.lock().unwrap().is_empty());
+        assert!(!world.entities().contains(e2));
+    }
+
+    #[test]
+    fn consumed_remove_does_not_send_removed() {
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        world.add_system(|r: ReceiverMut<Remove<C>, ()>| {
+            EventMut::take(r.event);
+        });
+
+        let removed = Arc::new(Mutex::new(false));
+        let removed_cloned = removed.clone();
+
+        world.add_system(move |_: Receiver<Removed<C>, ()>| {
+            *removed_cloned.lock().unwrap() = true;
+        });
+
+        let e = world.spawn();
+        world.insert(e, C);
+        world.remove::<C>(e);
+
+        assert!(world.get_component::<C>(e).is_some());
+        assert!(!*removed.lock().unwrap());
+    }
+
+    #[test]
+    fn insert_for_all() {
+        #[derive(Component)]
+        struct Selected;
+
+        #[derive(Component)]
+        struct Tag;
+
+        #[derive(Component, Default)]
+        struct Required;
+
+        #[allow(dead_code)]
+        #[derive(Component, Clone)]
+        #[component(requires(Required))]
+        struct Outline(u32, Arc<()>);
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let arc = Arc::new(());
+
+        let selected = [(); 3].map(|()| {
+            let e = world.spawn();
+            world.insert(e, Selected);
+            e
+        });
+        let selected_tagged = [(); 2].map(|()| {
+            let e = world.spawn();
+            world.insert(e, Selected);
+            world.insert(e, Tag);
+            e
+        });
+        let already_outlined = world.spawn();
+        world.insert(already_outlined, Selected);
+        world.insert(already_outlined, Outline(0, arc.clone()));
+        let unselected = world.spawn();
+        world.insert(unselected, Tag);
+
+        let count = Arc::new(Mutex::new(0));
+        let count_cloned = count.clone();
+
+        world.add_system(move |_: Receiver<E>, f: Fetcher<(&Outline, &Required)>| {
+            *count_cloned.lock().unwrap() = f.iter().count();
+        });
+
+        world.insert_for_all::<With<&Selected>, _>(Outline(1, arc.clone()));
+
+        for e in selected
+            .into_iter()
+            .chain(selected_tagged)
+            .chain([already_outlined])
+        {
+            assert_eq!(world.get_component::<Outline>(e).unwrap().0, 1);
+            assert!(world.get_component::<Required>(e).is_some());
+            assert!(world.get_component::<Selected>(e).is_some());
+        }
+
+        for e in selected_tagged {
+            assert!(world.get_component::<Tag>(e).is_some());
+        }
+
+        assert!(world.get_component::<Outline>(unselected).is_none());
+
+        // One clone per entity. The old value was dropped.
+        assert_eq!(Arc::strong_count(&arc), 7);
+
+        world.send(E);
+        assert_eq!(*count.lock().unwrap(), 6);
+
+        for e in selected {
+            let loc = world.entity_location(e).unwrap();
+            assert_eq!(
+                world.archetypes().entity_at(loc.archetype, loc.row),
+                Some(e)
+            );
+        }
+
+        drop(world);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    fn despawn_matching() {
+        #[derive(Component)]
+        struct Dead(#[allow(dead_code)] Arc<()>);
+
+        #[derive(Component)]
+        struct Tag;
+
+        #[derive(Component)]
+        struct Watched;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let arc = Arc::new(());
+
+        let spawn = |world: &mut World, tagged: bool| {
+            let e = world.spawn();
+            world.insert(e, Dead(arc.clone()));
+            if tagged {
+                world.insert(e, Tag);
+            }
+            e
+        };
+
+        let dead = [spawn(&mut world, false), spawn(&mut world, true)];
+        let watched = spawn(&mut world, false);
+        world.insert(watched, Watched);
+        let alive = world.spawn();
+        world.insert(alive, Tag);
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<Despawn, &Watched>| {
+            l.lock().unwrap().push(("despawn", r.event.0));
+        });
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<Despawned>| {
+            l.lock().unwrap().push(("despawned", r.event.0));
+        });
+
+        let count = Arc::new(Mutex::new(0));
+        let c = count.clone();
+        world.add_system(move |_: Receiver<E>, f: Fetcher<&Dead>| {
+            *c.lock().unwrap() = f.iter().count();
+        });
+
+        world.despawn_matching::<With<&Dead>>();
+
+        assert_eq!(Arc::strong_count(&arc), 1);
+
+        for e in dead.into_iter().chain([watched]) {
+            assert!(!world.entities().contains(e));
+        }
+
+        let loc = world.entity_location(alive).unwrap();
+        assert_eq!(
+            world.archetypes().entity_at(loc.archetype, loc.row),
+            Some(alive)
+        );
+
+        let mut log = core::mem::take(&mut *log.lock().unwrap());
+        // Entities with `Despawn` listeners are despawned after the others.
+        assert_eq!(log[2..], [("despawn", watched), ("despawned", watched)]);
+        log[..2].sort();
+        let mut expected = [("despawned", dead[0]), ("despawned", dead[1])];
+        expected.sort();
+        assert_eq!(log[..2], expected);
+
+        world.send(E);
+        assert_eq!(*count.lock().unwrap(), 0);
+
+        // Emptied archetypes can be refilled.
+        let e = spawn(&mut world, true);
+        world.send(E);
+        assert_eq!(*count.lock().unwrap(), 1);
+
+        world.despawn_matching_without_events::<With<&Dead>>();
+        assert!(!world.entities().contains(e));
+        assert!(world.entities().contains(alive));
+    }
+
+    #[test]
+    fn get_by_query() {
+        #[derive(Component, PartialEq, Debug)]
+        struct Pos(i32);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Vel(i32);
+
+        #[derive(Component)]
+        struct Frozen;
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, Pos(1));
+        world.insert(e1, Vel(2));
+
+        let e2 = world.spawn();
+        world.insert(e2, Pos(3));
+        world.insert(e2, Frozen);
+
+        assert_eq!(
+            world.get::<(&Pos, Option<&Vel>)>(e1),
+            Some((&Pos(1), Some(&Vel(2))))
+        );
+        assert_eq!(world.get::<(&Pos, Option<&Vel>)>(e2), Some((&Pos(3), None)));
+        assert_eq!(world.get::<&Vel>(e2), None);
+        assert!(world.get::<(&Pos, Not<&Frozen>)>(e2).is_none());
+        assert_eq!(
+            world.get::<(EntityId, With<&Frozen>)>(e2).map(|(e, _)| e),
+            Some(e2)
+        );
+
+        if let Some((pos, vel)) = world.get_mut::<(&mut Pos, &Vel)>(e1) {
+            pos.0 += vel.0;
+        }
+        assert_eq!(world.get::<&Pos>(e1), Some(&Pos(3)));
+
+        // Looking up unknown components doesn't add them.
+        #[derive(Component, PartialEq, Debug)]
+        struct Unknown;
+
+        let shared = &world;
+        assert_eq!(
+            shared.get::<(&Pos, Option<&Unknown>)>(e1),
+            Some((&Pos(3), None))
+        );
+        assert!(shared.get::<(&Pos, Not<&Unknown>)>(e1).is_some());
+        assert!(shared.get::<(&Pos, Without<&Unknown>)>(e1).is_some());
+        assert!(shared.get::<&Unknown>(e1).is_none());
+        assert!(world
+            .components()
+            .get_by_type_id(TypeId::of::<Unknown>())
+            .is_none());
+
+        world.despawn(e1);
+        assert_eq!(world.get::<&Pos>(e1), None);
+        assert!(world.get_mut::<&mut Pos>(e1).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn local_component_ties_world_to_thread() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+        use std::thread;
+
+        use crate::component::Unsend;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+        assert_eq!(world.owner_thread(), None);
+
+        let counter = Rc::new(Cell::new(0));
+
+        let e = world.spawn();
+        world.insert(e, Unsend::new(counter.clone()));
+        assert_eq!(world.owner_thread(), Some(thread::current().id()));
+
+        world.add_system(|_: Receiver<E>, f: Fetcher<&Unsend<Rc<Cell<i32>>>>| {
+            for c in f {
+                c.set(c.get() + 1);
+            }
+        });
+
+        world.send(E);
+        assert_eq!(counter.get(), 1);
+        assert_eq!(
+            world
+                .get_component::<Unsend<Rc<Cell<i32>>>>(e)
+                .unwrap()
+                .get(),
+            1
+        );
+
+        // Using the world from another thread panics instead of touching the
+        // local components.
+        let res = thread::spawn(move || world.send(E)).join();
+        assert!(res.is_err());
+        assert_eq!(counter.get(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_only_query_from_threads() {
+        use std::thread;
+
+        fn assert_sync<T: Sync>(_: &T) {}
+
+        #[derive(Component)]
+        struct Pos(u32);
+
+        #[derive(Component)]
+        struct Vel(u32);
+
+        let mut world = World::new();
+
+        let mut expected = 0;
+        for i in 0..100 {
+            let e = world.spawn();
+            world.insert(e, Pos(i));
+            if i % 3 == 0 {
+                world.insert(e, Vel(i));
+            }
+            expected += i;
+        }
+
+        let fetcher = world.query::<(EntityId, &Pos, Option<&Vel>)>();
+        assert_sync(&fetcher);
+
+        let world = fetcher.world();
+
+        thread::scope(|s| {
+            let handles = (0..4)
+                .map(|i| {
+                    let fetcher = &fetcher;
+                    s.spawn(move || {
+                        // Half of the threads build their own fetcher from `&World`.
+                        let own;
+                        let fetcher = if i % 2 == 0 {
+                            own = world.query::<(EntityId, &Pos, Option<&Vel>)>();
+                            &own
+                        } else {
+                            fetcher
+                        };
+
+                        let mut sum = 0;
+                        for (id, pos, vel) in fetcher {
+                            assert_eq!(world.get_component::<Pos>(id).unwrap().0, pos.0);
+                            assert_eq!(vel.map(|v| v.0), (pos.0 % 3 == 0).then_some(pos.0));
+                            sum += pos.0;
+                        }
+                        sum
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_only_query_checks_thread() {
+        use alloc::rc::Rc;
+        use std::thread;
+
+        use crate::component::Unsend;
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Unsend::new(Rc::new(123)));
+
+        let fetcher = world.query::<&Unsend<Rc<i32>>>();
+        assert_eq!(fetcher.iter().count(), 1);
+
+        thread::scope(|s| {
+            assert!(s.spawn(|| fetcher.iter().count()).join().is_err());
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn unsend_checks_thread() {
+        use alloc::rc::Rc;
+        use std::thread;
+
+        use crate::component::Unsend;
+
+        let value = Unsend::new(Rc::new(123));
+        assert!(value.is_owner_thread());
+        assert_eq!(**value, 123);
+
+        let res = thread::spawn(move || {
+            assert!(!value.is_owner_thread());
+            assert!(value.try_get().is_none());
+            let _ = &**value;
+        })
+        .join();
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn iter_archetype() {
+        #[derive(Component)]
+        struct A(u64);
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct C(u8);
+
+        let mut world = World::new();
+
+        let entities: Vec<_> = (0..10)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, A(i));
+                world.insert(e, B);
+                world.insert(e, C(i as u8 * 2));
+                e
+            })
+            .collect();
+
+        let a = world.add_component::<A>().index();
+        let b = world.add_component::<B>().index();
+        let c = world.add_component::<C>().index();
+
+        let arch = world.entity_location(entities[0]).unwrap().archetype;
+
+        let mut count = 0;
+
+        for (row, (entity, components)) in world.iter_archetype(arch).unwrap().enumerate() {
+            assert_eq!(entity, entities[row]);
+
+            let components: Vec<_> = components.collect();
+            assert_eq!(components.len(), 3);
+
+            for (idx, ptr, layout) in components {
+                if idx == a {
+                    assert_eq!(layout, core::alloc::Layout::new::<A>());
+                    assert_eq!(unsafe { ptr.cast::<A>().as_ref() }.0, row as u64);
+                } else if idx == b {
+                    assert_eq!(layout.size(), 0);
+                } else if idx == c {
+                    assert_eq!(unsafe { ptr.cast::<C>().as_ref() }.0, row as u8 * 2);
+                } else {
+                    panic!("unexpected component");
+                }
+            }
+
+            count += 1;
+        }
+
+        assert_eq!(count, entities.len());
+        assert!(world.iter_archetype(ArchetypeIdx(u32::MAX - 1)).is_none());
+    }
+
+    #[test]
+    #[cfg(not(feature = "validate-ids"))]
+    fn world_id_is_zero_sized() {
+        assert_eq!(core::mem::size_of::<WorldId>(), 0);
+        assert_eq!(core::mem::size_of::<ComponentId>(), 8);
+        assert_eq!(core::mem::size_of::<SystemId>(), 8);
+        assert_eq!(core::mem::size_of::<EventId>(), 8);
+    }
+
+    #[test]
+    #[cfg(feature = "validate-ids")]
+    fn ids_from_different_worlds() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut w1 = World::new();
+        let mut w2 = World::new();
+
+        assert_ne!(w1.id(), w2.id());
+
+        let a = w1.add_component::<A>();
+        let b = w2.add_component::<B>();
+
+        // Same index and generation, but different worlds.
+        assert_eq!(a.index(), b.index());
+        assert_eq!(a.generation(), b.generation());
+        assert_ne!(a, b);
+
+        assert_eq!(a.world(), w1.id());
+        assert_eq!(b.world(), w2.id());
+
+        // Manually constructed IDs are accepted by every world.
+        let manual = ComponentId::new(a.index().0, a.generation()).unwrap();
+        assert_eq!(manual.world(), WorldId::NONE);
+        assert!(w2.components().get(manual).is_some());
+
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| w2.components().get(a)));
+
+        assert_eq!(res.is_err(), cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn send_all_nests_each_root() {
+        #[derive(Event)]
+        struct A(u32);
+
+        #[derive(Event)]
+        struct B(u32);
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<A>, mut s: Sender<B>| {
+            l.lock().unwrap().push(format!("a{}", r.event.0));
+            s.send(B(r.event.0));
+        });
+
+        let l = log.clone();
+        world.add_system(move |r: Receiver<B>| l.lock().unwrap().push(format!("b{}", r.event.0)));
+
+        world.send_all((0..3).map(A));
+
+        assert_eq!(*log.lock().unwrap(), ["a0", "b0", "a1", "b1", "a2", "b2"]);
+    }
+
+    #[test]
+    fn world_builder_indices() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct Lazy;
+
+        #[derive(Event)]
+        struct E(#[event(target)] EntityId);
+
+        #[derive(Event)]
+        struct LazyEvent;
+
+        let build = |reversed: bool| {
+            let mut builder = WorldBuilder::new();
+            if reversed {
+                builder.register_component::<B>();
+                builder.register_component::<A>();
+            } else {
+                builder.register_component::<A>();
+                builder.register_component::<B>();
+            }
+            builder.register_event::<E>();
+            builder
+        };
+
+        assert_eq!(build(false).manifest(), build(false).manifest());
+        assert_ne!(build(false).manifest(), build(true).manifest());
+
+        let manifest = build(false).manifest();
+        let idx_of = |name: &str| manifest.iter().find(|(n, _)| n.ends_with(name)).unwrap().1;
+        assert_eq!(idx_of("::A"), 0);
+        assert_eq!(idx_of("::B"), 1);
+        assert_eq!(idx_of("::E"), 0);
+
+        let mut world = build(false).build();
+
+        // Removed components don't give up their index.
+        let a = world
+            .components()
+            .get_by_type_id(TypeId::of::<A>())
+            .unwrap()
+            .id();
+        world.remove_component(a);
+        assert_eq!(world.add_component::<Lazy>().index().0, 2);
+        assert_eq!(world.add_component::<A>().index().0, 3);
+
+        let untargeted = world
+            .events()
+            .iter()
+            .filter(|e| !e.id().is_targeted())
+            .count();
+        let lazy_event = world.add_event::<LazyEvent>().index();
+        assert_eq!(
+            lazy_event,
+            EventIdx::Untargeted(UntargetedEventIdx(untargeted as u32))
+        );
+    }
+
+    #[test]
+    fn despawn_twice() {
+        let mut world = World::new();
+
+        let despawns = Arc::new(Mutex::new(vec![]));
+        let despawned = Arc::new(Mutex::new(vec![]));
+
+        let d = despawns.clone();
+        world.add_system(move |r: Receiver<Despawn, ()>| d.lock().unwrap().push(r.event.0));
+
+        let d = despawned.clone();
+        world.add_system(move |r: Receiver<Despawned>| d.lock().unwrap().push(r.event.0));
+
+        // Across flushes.
+        let e1 = world.spawn();
+        assert!(world.try_despawn(e1));
+        assert!(!world.try_despawn(e1));
+        world.despawn(e1);
+
+        // Stale generation.
+        let e2 = world.spawn();
+        assert_eq!(e2.index(), e1.index());
+        assert!(!world.try_despawn(e1));
+        assert!(world.entities().contains(e2));
+
+        #[derive(Event)]
+        struct DespawnTwice(EntityId);
+
+        // In the same flush. The second `Despawn` is queued before the first is
+        // handled.
+        world.add_system(|r: Receiver<DespawnTwice>, mut s: Sender<Despawn>| {
+            s.despawn(r.event.0);
+            s.despawn(r.event.0);
+        });
+
+        world.send(DespawnTwice(e2));
+
+        assert!(!world.entities().contains(e2));
+        assert_eq!(*despawns.lock().unwrap(), [e1, e2]);
+        assert_eq!(*despawned.lock().unwrap(), [e1, e2]);
+    }
+
+    #[test]
+    fn despawn_target_mid_dispatch() {
+        #[derive(Event)]
+        struct E(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct C(u32);
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let log_clone = log.clone();
+        world.add_system(
+            (move |r: Receiver<E, &C>, mut s: Sender<Despawn>| {
+                log_clone.lock().unwrap().push(r.query.0);
+                s.despawn(r.event.0);
+            })
+            .before(),
+        );
+
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<E, &C>| {
+            log_clone.lock().unwrap().push(r.query.0 + 100);
+        });
+
+        let log_clone = log.clone();
+        world.add_system(
+            (move |r: Receiver<E, &C>| {
+                log_clone.lock().unwrap().push(r.query.0 + 200);
+            })
+            .after(),
+        );
+
+        let e1 = world.spawn();
+        world.insert(e1, C(1));
+
+        // Reuses the index of `e1` while the first system is still dispatching.
+        world.add_system(
+            |r: Receiver<Despawned>, mut s: Sender<(Spawn, Insert<C>)>| {
+                let e = s.spawn();
+                assert_eq!(e.index(), r.event.0.index());
+                s.insert(e, C(2));
+            },
+        );
+
+        world.send(E(e1));
+
+        assert!(!world.entities().contains(e1));
+        assert_eq!(*log.lock().unwrap(), [1]);
     }
 
-    /// Adds an event described by a given [`EventDescriptor`].
-    ///
-    /// Like [`add_event`], an [`AddEvent`] event is sent if the
-    /// event is newly added. If the [`TypeId`] of the event matches an
-    /// existing event, then the existing event's [`EventId`] is
-    /// returned and no event is sent.
-    ///
-    /// # Safety
-    ///
-    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
-    ///   function must be compatible with the Rust type identified by the type
-    ///   ID.
-    /// - Drop function must be safe to call with a pointer to the event as
-    ///   described by [`DropFn`]'s documentation.
-    /// - The event's kind must be correct for the descriptor. See
-    ///   [`EventKind`]'s documentation for more information.
-    ///
-    /// [`add_event`]: World::add_event
-    pub unsafe fn add_event_with_descriptor(&mut self, desc: EventDescriptor) -> EventId {
-        let kind = desc.kind;
+    #[test]
+    fn move_target_mid_dispatch() {
+        #[derive(Event)]
+        struct E(#[event(target)] EntityId);
 
-        let (id, is_new) = self.events.add(desc);
+        #[derive(Component)]
+        struct A;
 
-        if is_new {
-            self.systems.register_event(id.index());
+        #[derive(Component)]
+        struct B;
 
-            match kind {
-                EventKind::Normal => {}
-                EventKind::Insert { component_idx, .. } => {
-                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                        info.insert_events.insert(id);
-                    }
-                }
-                EventKind::Remove { component_idx } => {
-                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                        info.remove_events.insert(id);
-                    }
-                }
-                EventKind::SpawnQueued => {}
-                EventKind::Despawn => {}
-            }
+        let mut world = World::new();
 
-            self.send(AddEvent(id));
-        }
+        let log = Arc::new(Mutex::new(vec![]));
 
-        id
+        world.add_system(
+            (|r: Receiver<E, ()>, mut s: Sender<(Remove<A>, Insert<B>)>| {
+                s.remove::<A>(r.event.0);
+                s.insert(r.event.0, B);
+            })
+            .before(),
+        );
+
+        let log_clone = log.clone();
+        world.add_system(move |_: Receiver<E, &A>| {
+            log_clone.lock().unwrap().push("a");
+        });
+
+        let log_clone = log.clone();
+        world.add_system(move |_: Receiver<E, ()>| {
+            log_clone.lock().unwrap().push("any");
+        });
+
+        // Not in the list of systems when dispatch started.
+        let log_clone = log.clone();
+        world.add_system(move |_: Receiver<E, &B>| {
+            log_clone.lock().unwrap().push("b");
+        });
+
+        let e = world.spawn();
+        world.insert(e, A);
+
+        world.send(E(e));
+
+        assert_eq!(*log.lock().unwrap(), ["any"]);
     }
 
-    /// Removes an event from the world and returns its [`EventInfo`]. If
-    /// the `event` ID is invalid, then `None` is returned and the function
-    /// has no effect.
-    ///
-    /// Removing an event has the following effects in the order listed:
-    /// 1. The [`RemoveEvent`] event is sent.
-    /// 2. All systems that send or receive the event are removed.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use evenio::prelude::*;
-    ///
-    /// #[derive(Event)]
-    /// struct MyEvent;
-    ///
-    /// let mut world = World::new();
-    ///
-    /// let id = world.add_event::<MyEvent>();
-    /// world.remove_event(id);
-    ///
-    /// assert!(!world.events().contains(id));
-    /// ```
-    pub fn remove_event(&mut self, event: EventId) -> Option<EventInfo> {
-        assert!(self.event_queue.is_empty());
+    #[test]
+    fn get_component_ptr() {
+        #[derive(Component, PartialEq, Debug)]
+        struct A(u64);
 
-        if !self.events.contains(event) || event == EventId::SPAWN_QUEUED {
-            return None;
-        }
+        #[derive(Component)]
+        struct B;
 
-        // Send event before removing anything.
-        self.send(RemoveEvent(event));
+        let mut world = World::new();
 
-        // Remove all systems that send or receive this event.
-        let mut to_remove = vec![];
+        let a_idx = world.add_component::<A>().index();
+        let b_idx = world.add_component::<B>().index();
 
-        for sys in self.systems.iter() {
-            if sys.received_event() == event
-                || match event.index() {
-                    EventIdx::Targeted(idx) => sys.sent_targeted_events().contains(idx),
-                    EventIdx::Untargeted(idx) => sys.sent_untargeted_events().contains(idx),
-                }
-            {
-                to_remove.push(sys.id());
-            }
-        }
+        let entities: Vec<_> = (0..3)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, A(i));
+                e
+            })
+            .collect();
 
-        for id in to_remove {
-            self.remove_system(id);
-        }
+        world.insert(entities[1], B);
 
-        let info = self.events.remove(event).unwrap();
+        let cell = world.unsafe_cell_mut();
 
-        match info.kind() {
-            EventKind::Normal => {}
-            EventKind::Insert { component_idx, .. } => {
-                if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                    info.insert_events.remove(&event);
-                }
-            }
-            EventKind::Remove { component_idx } => {
-                if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                    info.remove_events.remove(&event);
-                }
-            }
-            EventKind::SpawnQueued => {}
-            EventKind::Despawn => {}
+        for &e in &entities {
+            let info = cell.components().get_by_index(a_idx).unwrap();
+            assert_eq!(info.layout(), Layout::new::<A>());
+
+            let ptr = cell.get_component_ptr(e, a_idx).unwrap();
+            unsafe { ptr.cast::<A>().as_mut().0 += 10 };
         }
 
-        Some(info)
-    }
+        assert!(cell.get_component_ptr(entities[0], b_idx).is_none());
+        assert!(cell.get_component_ptr(entities[1], b_idx).is_some());
 
-    /// Returns the [`Entities`] for this world.
-    pub fn entities(&self) -> &Entities {
-        &self.entities
-    }
+        world.despawn(entities[2]);
 
-    /// Returns the [`Components`] for this world.  
-    pub fn components(&self) -> &Components {
-        &self.components
+        let cell = world.unsafe_cell();
+        assert!(cell.get_component_ptr(entities[2], a_idx).is_none());
+
+        assert_eq!(world.get_component::<A>(entities[0]), Some(&A(10)));
+        assert_eq!(world.get_component::<A>(entities[1]), Some(&A(11)));
     }
 
-    /// Returns the [`Systems`] for this world.
-    pub fn systems(&self) -> &Systems {
-        &self.systems
+    #[test]
+    fn required_components_skip_intermediate_archetypes() {
+        #[derive(Component, Default)]
+        struct B;
+
+        #[derive(Component, Default)]
+        struct C;
+
+        #[derive(Component, Default)]
+        struct D;
+
+        #[derive(Component)]
+        #[component(requires(B, C, D))]
+        struct A;
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, A);
+
+        // The empty archetype, `{A}`, and `{A, B, C, D}`.
+        assert_eq!(world.archetypes().len(), 3);
+
+        let e2 = world.spawn();
+        world.insert(e2, A);
+
+        assert_eq!(world.archetypes().len(), 3);
+        assert_eq!(
+            world.entities().get(e1).unwrap().archetype,
+            world.entities().get(e2).unwrap().archetype
+        );
+        assert!(world.get_component::<D>(e2).is_some());
     }
 
-    /// Returns the [`Archetypes`] for this world.
-    pub fn archetypes(&self) -> &Archetypes {
-        &self.archetypes
+    #[test]
+    fn traverse_many() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let a = world.add_component::<A>().index();
+        let b = world.add_component::<B>().index();
+        let c_id = world.add_component::<C>();
+        let c = c_id.index();
+
+        let w = &mut world;
+
+        let abc = unsafe {
+            w.archetypes
+                .traverse_insert_many(
+                    ArchetypeIdx::EMPTY,
+                    &[a, b, c],
+                    &mut w.components,
+                    &mut w.systems,
+                )
+                .unwrap()
+        };
+
+        assert_eq!(w.archetypes.len(), 2);
+        assert_eq!(w.archetypes.get(abc).unwrap().component_set(), [a, b, c]);
+
+        let cached = unsafe {
+            w.archetypes
+                .traverse_insert_many(
+                    ArchetypeIdx::EMPTY,
+                    &[a, b, c],
+                    &mut w.components,
+                    &mut w.systems,
+                )
+                .unwrap()
+        };
+
+        assert_eq!(cached, abc);
+
+        let same = unsafe {
+            w.archetypes
+                .traverse_insert_many(abc, &[a, b], &mut w.components, &mut w.systems)
+                .unwrap()
+        };
+
+        assert_eq!(same, abc);
+
+        world.remove_component(c_id);
+
+        let w = &mut world;
+
+        let ab = unsafe {
+            w.archetypes
+                .traverse_insert_many(
+                    ArchetypeIdx::EMPTY,
+                    &[a, b],
+                    &mut w.components,
+                    &mut w.systems,
+                )
+                .unwrap()
+        };
+
+        assert_eq!(w.archetypes.get(ab).unwrap().component_set(), [a, b]);
     }
 
-    /// Returns the [`Events`] for this world.
-    pub fn events(&self) -> &Events {
-        &self.events
+    #[test]
+    fn clone_entity() {
+        #[derive(Component, Clone, PartialEq, Debug)]
+        struct A(String);
+
+        #[derive(Component, Clone, Copy, PartialEq, Debug)]
+        struct B(u32);
+
+        #[derive(Component)]
+        struct C;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let seen = Arc::new(Mutex::new(vec![]));
+
+        let seen_clone = seen.clone();
+        world.add_system(move |_: Receiver<E>, f: Fetcher<(EntityId, &A)>| {
+            let mut seen = seen_clone.lock().unwrap();
+            seen.clear();
+            seen.extend(f.iter().map(|(id, a)| (id, a.0.clone())));
+        });
+
+        let e = world.spawn();
+        world.insert(e, A("a".into()));
+        world.insert(e, B(1));
+
+        // Enough clones to reallocate the columns a few times.
+        let clones = (0..100)
+            .map(|_| world.clone_entity(e).unwrap())
+            .collect::<Vec<_>>();
+
+        for &c in &clones {
+            assert_eq!(world.get_component::<A>(c), Some(&A("a".into())));
+            assert_eq!(world.get_component::<B>(c), Some(&B(1)));
+        }
+
+        world.send(E);
+        assert_eq!(seen.lock().unwrap().len(), 101);
+
+        world.insert(e, C);
+
+        let err = world.clone_entity(e).unwrap_err();
+        let CloneError::Uncloneable { name, .. } = err else {
+            panic!("expected an uncloneable component, got {err:?}")
+        };
+        assert!(name.ends_with("::C"));
+
+        let options = CloneOptions {
+            skip_uncloneable: true,
+            ..Default::default()
+        };
+        let skipped = world.clone_entity_with(e, options).unwrap();
+
+        assert!(world.get_component::<A>(skipped).is_some());
+        assert!(world.get_component::<C>(skipped).is_none());
+
+        world.despawn(e);
+
+        assert_eq!(world.clone_entity(e), Err(CloneError::NoSuchEntity(e)));
+
+        world.check_invariants();
     }
 
-    /// Send all queued events to systems. The event queue will be empty after
-    /// this call.
-    fn flush_event_queue(&mut self) {
-        handle_events(0, self);
-        debug_assert_eq!(self.event_queue.len(), 0);
-        self.event_queue.clear();
+    #[test]
+    fn clone_entity_sends_insert_events() {
+        #[derive(Component, Clone)]
+        struct A(#[allow(dead_code)] Arc<()>);
 
-        fn handle_events(queue_start_idx: usize, world: &mut World) {
-            'next_event: for queue_idx in queue_start_idx..world.event_queue.len() {
-                let item = unsafe { world.event_queue.get_debug_checked_mut(queue_idx) };
-                let event_meta = item.meta;
-                let event_info = unsafe {
-                    world
-                        .events
-                        .get_by_index(event_meta.event_idx())
-                        .unwrap_debug_checked()
-                };
-                let event_kind = event_info.kind();
-
-                // Put the event pointer on the stack because pointers into the event queue
-                // would be invalidated by pushes.
-                let mut event = EventDropper {
-                    // Set pointer to null so the `World`'s destructor will know we've taken
-                    // ownership of the event.
-                    event: mem::replace(&mut item.event, ptr::null_mut()),
-                    drop: event_info.drop(),
-                };
+        #[derive(Component, Clone)]
+        struct B;
 
-                struct EventDropper {
-                    event: *mut u8,
-                    drop: DropFn,
-                }
+        let mut world = World::new();
 
-                impl EventDropper {
-                    /// Extracts the event pointer and drop fn without running
-                    /// the destructor.
-                    #[inline]
-                    fn unpack(self) -> (*mut u8, DropFn) {
-                        let event = self.event;
-                        let drop = self.drop;
-                        mem::forget(self);
+        let log = Arc::new(Mutex::new(vec![]));
 
-                        (event, drop)
-                    }
-                }
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<Spawn, ()>| {
+            log_clone.lock().unwrap().push(("spawn", r.event.0))
+        });
 
-                // In case `System::run` or `handle_events` unwinds, we need to drop the event
-                // we're holding on the stack. The other events in the event queue will be
-                // handled by `World`'s destructor.
-                impl Drop for EventDropper {
-                    #[inline]
-                    fn drop(&mut self) {
-                        if let (Some(event), Some(drop)) = (NonNull::new(self.event), self.drop) {
-                            unsafe { drop(event) };
-                        }
-                    }
-                }
+        let log_clone = log.clone();
+        world.add_system(move |r: Receiver<Insert<A>, ()>| {
+            log_clone.lock().unwrap().push(("insert", r.event.entity))
+        });
 
-                let system_list = match event_meta {
-                    EventMeta::Untargeted { idx } => unsafe {
-                        world
-                            .systems
-                            .get_untargeted_list(idx)
-                            .unwrap_debug_checked()
-                    },
-                    EventMeta::Targeted { idx, target } => {
-                        let Some(location) = world.entities.get(target) else {
-                            continue;
-                        };
+        let payload = Arc::new(());
 
-                        let arch = unsafe {
-                            world
-                                .archetypes
-                                .get(location.archetype)
-                                .unwrap_debug_checked()
-                        };
+        let e = world.spawn();
+        world.insert(e, A(payload.clone()));
+        world.insert(e, B);
 
-                        static EMPTY: SystemList = SystemList::new();
+        log.lock().unwrap().clear();
 
-                        // Return an empty system list instead of continuing in case this event is
-                        // special.
-                        arch.system_list_for(idx).unwrap_or(&EMPTY)
-                    }
-                };
+        let options = CloneOptions {
+            send_insert_events: true,
+            ..Default::default()
+        };
+        let c = world.clone_entity_with(e, options).unwrap();
 
-                let systems: *const [_] = system_list.systems();
+        assert_eq!(*log.lock().unwrap(), [("spawn", c), ("insert", c)]);
+        assert!(world.get_component::<A>(c).is_some());
+        assert!(world.get_component::<B>(c).is_some());
+        assert_eq!(Arc::strong_count(&payload), 3);
 
-                for info_ptr in unsafe { &*systems } {
-                    let events_before = world.event_queue.len();
+        log.lock().unwrap().clear();
 
-                    let system = unsafe { &mut (*info_ptr.as_ptr()).system };
+        let c = world.clone_entity(e).unwrap();
 
-                    let info = unsafe { SystemInfo::ref_from_ptr(info_ptr) };
+        assert_eq!(*log.lock().unwrap(), [("spawn", c)]);
 
-                    let event_ptr = EventPtr::new(NonNull::from(&mut event.event));
-                    let world_cell = world.unsafe_cell_mut();
+        world.check_invariants();
+
+        drop(world);
+        assert_eq!(Arc::strong_count(&payload), 1);
+    }
 
-                    unsafe { system.run(info, event_ptr, world_cell) };
+    #[test]
+    fn handlers_match_dispatch_order() {
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId);
 
-                    let events_after = world.event_queue.len();
+        #[derive(Component)]
+        struct A;
 
-                    if events_before < events_after {
-                        // Eagerly handle any events produced by the system.
-                        handle_events(events_before, world);
-                    }
+        let mut world = World::new();
 
-                    debug_assert_eq!(world.event_queue.len(), events_before);
+        let log = Arc::new(Mutex::new(vec![]));
 
-                    // Did the system take ownership of the event?
-                    if event.event.is_null() {
-                        // Event is null; destructor wouldn't do anything.
-                        event.unpack();
+        let mut ids = vec![];
 
-                        continue 'next_event;
-                    }
-                }
+        for i in 0..3 {
+            let log_clone = log.clone();
+            ids.push(world.add_system(
+                (move |_: Receiver<Hit, &A>| log_clone.lock().unwrap().push(i)).after(),
+            ));
 
-                match event_kind {
-                    EventKind::Normal => {
-                        // Ordinary event. Run event dropper destructor.
-                    }
-                    EventKind::Insert {
-                        component_idx,
-                        component_offset,
-                    } => {
-                        let entity_id = unsafe { *event.event.cast::<EntityId>() };
-
-                        if let Some(loc) = world.entities.get(entity_id) {
-                            let dst = unsafe {
-                                world.archetypes.traverse_insert(
-                                    loc.archetype,
-                                    component_idx,
-                                    &mut world.components,
-                                    &mut world.systems,
-                                )
-                            };
+            let log_clone = log.clone();
+            ids.push(
+                world
+                    .add_system(move |_: Receiver<Hit, ()>| log_clone.lock().unwrap().push(i + 10)),
+            );
 
-                            let component_ptr =
-                                unsafe { event.event.add(component_offset as usize) }.cast_const();
+            let log_clone = log.clone();
+            ids.push(world.add_system(
+                (move |_: Receiver<Hit, Not<&A>>| log_clone.lock().unwrap().push(i + 20)).before(),
+            ));
+        }
 
-                            unsafe {
-                                world.archetypes.move_entity(
-                                    loc,
-                                    dst,
-                                    [(component_idx, component_ptr)],
-                                    &mut world.entities,
-                                )
-                            };
+        world.remove_system(ids[4]);
 
-                            // Inserted component is owned by the archetype now. We wait to unpack
-                            // in case one of the above functions panics.
-                            event.unpack();
-                        }
-                    }
-                    EventKind::Remove { component_idx } => {
-                        // `Remove` doesn't need drop.
-                        let (event, _) = event.unpack();
-
-                        // SAFETY: `Remove` is `repr(transparent)` with the first field being the
-                        // `EntityId`, so we can safely reinterpret this pointer.
-                        let entity_id = unsafe { *event.cast::<EntityId>() };
-
-                        if let Some(loc) = world.entities.get(entity_id) {
-                            let dst = unsafe {
-                                world.archetypes.traverse_remove(
-                                    loc.archetype,
-                                    component_idx,
-                                    &mut world.components,
-                                    &mut world.systems,
-                                )
-                            };
+        let event = world.add_event::<Hit>();
 
-                            unsafe {
-                                world
-                                    .archetypes
-                                    .move_entity(loc, dst, [], &mut world.entities)
-                            };
-                        }
-                    }
-                    EventKind::SpawnQueued => {
-                        // `SpawnQueued` doesn't need drop.
-                        let _ = event.unpack();
+        for insert_a in [false, true] {
+            let e = world.spawn();
 
-                        // Spawn one entity from the reserved entity queue.
-                        world
-                            .reserved_entities
-                            .spawn_one(&mut world.entities, |id| world.archetypes.spawn(id));
-                    }
-                    EventKind::Despawn => {
-                        // `Despawn` doesn't need drop.
-                        let (event, _) = event.unpack();
+            if insert_a {
+                world.insert(e, A);
+            }
+
+            log.lock().unwrap().clear();
+            world.send(Hit(e));
+
+            let ran = log.lock().unwrap().clone();
+            let handlers = world.handlers_for_targeted_event(event, e);
 
-                        let entity_id = unsafe { *event.cast::<Despawn>() }.0;
+            assert_eq!(handlers.len(), ran.len());
 
-                        world
-                            .archetypes
-                            .remove_entity(entity_id, &mut world.entities);
+            for ((id, priority), i) in handlers.into_iter().zip(ran) {
+                let expected = match i {
+                    0..=9 => (ids[i * 3], Priority::AfterDefault),
+                    10..=19 => (ids[(i - 10) * 3 + 1], Priority::Default),
+                    _ => (ids[(i - 20) * 3 + 2], Priority::BeforeDefault),
+                };
 
-                        // Reset next key iter.
-                        world.reserved_entities.refresh(&world.entities);
-                    }
-                }
+                assert_eq!((id, priority), expected);
             }
-
-            unsafe { world.event_queue.set_len(queue_start_idx) };
         }
-    }
 
-    /// Returns a new [`UnsafeWorldCell`] with permission to _read_ all data in
-    /// this world.
-    pub fn unsafe_cell(&self) -> UnsafeWorldCell {
-        UnsafeWorldCell {
-            world: NonNull::from(self),
-            _marker: PhantomData,
-        }
+        assert!(world.handlers_for_event(event).is_empty());
+        assert!(world
+            .handlers_for_targeted_event(event, EntityId::NULL)
+            .is_empty());
     }
 
-    /// Returns a new [`UnsafeWorldCell`] with permission to _read and write_
-    /// all data in this world.
-    pub fn unsafe_cell_mut(&mut self) -> UnsafeWorldCell {
-        UnsafeWorldCell {
-            world: NonNull::from(self),
-            _marker: PhantomData,
-        }
-    }
-}
+    #[test]
+    fn copy_columns_across_archetypes() {
+        use crate::archetype::CHUNK_LEN;
 
-impl Default for World {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+        #[derive(Component, Clone, Copy, PartialEq, Debug)]
+        #[component(chunked)]
+        struct Pos(u16, u64);
 
-impl Drop for World {
-    fn drop(&mut self) {
-        // Drop in-flight events still in the event queue. This can happen if a panic
-        // occurs.
-        for item in self.event_queue.iter() {
-            if let Some(event) = NonNull::new(item.event) {
-                let info = unsafe {
-                    self.events
-                        .get_by_index(item.meta.event_idx())
-                        .unwrap_debug_checked()
-                };
+        #[derive(Component)]
+        struct Marker;
 
-                if let Some(drop) = info.drop() {
-                    unsafe { drop(event) };
-                }
+        let mut world = World::new();
+
+        let mut expected = vec![];
+
+        // Spread the entities over two archetypes with several chunks each.
+        for i in 0..CHUNK_LEN * 2 + 3 {
+            let e = world.spawn();
+            world.insert(e, Pos(i as u16, i as u64));
+
+            if i % 3 == 0 {
+                world.insert(e, Marker);
             }
+
+            expected.push((e, Pos(i as u16, i as u64)));
         }
-    }
-}
 
-unsafe impl Send for World {}
-unsafe impl Sync for World {}
+        let idx = world.add_component::<Pos>().index();
 
-impl UnwindSafe for World {}
-impl RefUnwindSafe for World {}
+        let mut out = vec![];
+        let mut entities = vec![];
+        let ranges = world.copy_columns(idx, &mut out, &mut entities);
 
-/// Used for queueing events. Passed to the closure given in [`send_many`].
-///
-/// [`send_many`]: World::send_many
-#[derive(Debug)]
-pub struct Sender<'a> {
-    world: &'a mut World,
-}
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(out.len(), entities.len() * core::mem::size_of::<Pos>());
 
-impl Sender<'_> {
-    /// Enqueue an event.
-    pub fn send<E: Event>(&mut self, event: E) {
-        let idx = self.world.add_event::<E>().index().as_u32();
-        unsafe { self.world.event_queue.push(event, idx) };
-    }
+        let mut copied = entities
+            .iter()
+            .enumerate()
+            .map(|(i, &e)| {
+                let ptr = unsafe { out.as_ptr().cast::<Pos>().add(i) };
+                (e, unsafe { ptr.read_unaligned() })
+            })
+            .collect::<Vec<_>>();
 
-    /// Enqueue the spawning of an entity and [`Spawn`] event. Returns the
-    /// [`EntityId`] of the entity that will be spawned.
-    pub fn spawn(&mut self) -> EntityId {
-        let id = self.world.reserved_entities.reserve(&self.world.entities);
-        unsafe {
-            self.world
-                .event_queue
-                .push(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32())
+        for (arch, range) in ranges {
+            assert_eq!(
+                world.archetypes().get(arch).unwrap().entity_ids(),
+                &entities[range]
+            );
         }
-        self.send(Spawn(id));
-        id
-    }
 
-    /// Enqueue an [`Insert`] event.
-    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
-        self.send(Insert::new(entity, component))
-    }
+        let mut typed = vec![];
+        world.extract_column::<Pos>(&mut typed, &mut entities);
+        assert_eq!(
+            entities.iter().copied().zip(typed).collect::<Vec<_>>(),
+            copied
+        );
 
-    /// Enqueue a [`Remove`] event.
-    pub fn remove<C: Component>(&mut self, entity: EntityId) {
-        self.send(Remove::<C>::new(entity))
+        copied.sort_by_key(|(_, pos)| pos.0);
+        assert_eq!(copied, expected);
     }
 
-    /// Enqueue a [`Despawn`] event.
-    pub fn despawn(&mut self, entity: EntityId) {
-        self.send(Despawn(entity))
-    }
-}
+    #[test]
+    #[should_panic = "is not plain data"]
+    fn copy_columns_requires_pod() {
+        #[derive(Component, Clone)]
+        struct Name(#[allow(dead_code)] String);
 
-/// Reference to a [`World`] where all methods take `&self` and aliasing rules
-/// are not checked. It is the caller's responsibility to ensure that
-/// Rust's aliasing rules are not violated.
-#[derive(Clone, Copy, Debug)]
-pub struct UnsafeWorldCell<'a> {
-    world: NonNull<World>,
-    _marker: PhantomData<(&'a World, &'a UnsafeCell<World>)>,
-}
+        let mut world = World::new();
+        let idx = world.add_component::<Name>().index();
 
-impl<'a> UnsafeWorldCell<'a> {
-    /// # Safety
-    ///
-    /// - Must be called from within a system.
-    /// - Must have permission to access the event queue mutably.
-    /// - Event index must be correct for the given event.
-    pub unsafe fn send_with_index<E: Event>(self, event: E, idx: u32) {
-        unsafe { (*self.world.as_ptr()).event_queue.push(event, idx) }
+        world.copy_columns(idx, &mut vec![], &mut vec![]);
     }
 
-    /// # Safety
-    ///
-    /// - Must be called from within a system.
-    /// - Must have permission to access the event queue mutably.
-    pub unsafe fn queue_spawn(self) -> EntityId {
-        let entity_id = (*self.world.as_ptr())
-            .reserved_entities
-            .reserve(self.entities());
-        self.send_with_index(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32());
-        entity_id
-    }
+    #[test]
+    fn send_dynamic() {
+        use core::mem::ManuallyDrop;
 
-    /// Returns the [`Entities`] for this world.
-    pub fn entities(self) -> &'a Entities {
-        unsafe { &(*self.world.as_ptr()).entities }
-    }
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId, #[allow(dead_code)] Arc<()>);
 
-    /// Returns the [`Components`] for this world.
-    pub fn components(self) -> &'a Components {
-        unsafe { &(*self.world.as_ptr()).components }
-    }
+        #[derive(Component)]
+        struct Health(u32);
 
-    /// Returns the [`Systems`] for this world.
-    pub fn systems(self) -> &'a Systems {
-        unsafe { &(*self.world.as_ptr()).systems }
-    }
+        let mut world = World::new();
 
-    /// Returns the [`Archetypes`] for this world.
-    pub fn archetypes(self) -> &'a Archetypes {
-        unsafe { &(*self.world.as_ptr()).archetypes }
-    }
+        let log = Arc::new(Mutex::new(vec![]));
 
-    /// Returns the [`Events`] for this world.
-    pub fn events(self) -> &'a Events {
-        unsafe { &(*self.world.as_ptr()).events }
-    }
+        let l = log.clone();
+        world.add_system(move |r: Receiver<Hit, &mut Health>| {
+            r.query.0 -= 1;
+            l.lock().unwrap().push(r.event.0);
+        });
 
-    /// Returns an immutable reference to the underlying world.
-    ///
-    /// # Safety
-    ///
-    /// Must have permission to access the entire world immutably.
-    pub fn world(self) -> &'a World {
-        unsafe { &*self.world.as_ptr() }
-    }
+        let hit = world.add_event::<Hit>();
 
-    /// Returns a mutable reference to the underlying world.
-    ///
-    /// # Safety
-    ///
-    /// Must have permission the access the entire world immutably.
-    pub unsafe fn world_mut(self) -> &'a mut World {
-        &mut *self.world.as_ptr()
-    }
-}
+        let e1 = world.spawn();
+        world.insert(e1, Health(10));
+        let e2 = world.spawn();
 
-#[cfg(test)]
-mod tests {
-    use alloc::sync::Arc;
-    use std::panic;
+        let arc = Arc::new(());
 
-    use crate::prelude::*;
+        for target in [e1, e2] {
+            let mut event = ManuallyDrop::new(Hit(target, arc.clone()));
+            let payload = (&mut *event as *mut Hit).cast::<u8>();
+
+            unsafe { world.send_dynamic(hit, Some(target), payload) };
+        }
+
+        // Only the target with `Health` matches the listener.
+        assert_eq!(*log.lock().unwrap(), [e1]);
+        assert_eq!(world.get_component::<Health>(e1).unwrap().0, 9);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
 
     #[test]
-    fn world_drops_events() {
-        #[derive(Event)]
-        struct A(Arc<()>);
+    fn send_dynamic_runtime_event() {
+        use alloc::borrow::Cow;
+        use core::any::TypeId;
+
+        use crate::access::Access;
+        use crate::archetype::Archetype;
+        use crate::bool_expr::BoolExpr;
+        use crate::event::{EventDescriptor, EventKind, EventPtr};
+        use crate::system::{Config, InitError, System, SystemInfo};
+        use crate::world::UnsafeWorldCell;
+
+        /// Listens for an event which has no Rust type, made of one `u64`.
+        struct Listener(EventId, Arc<Mutex<Vec<u64>>>);
+
+        impl System for Listener {
+            fn type_id(&self) -> Option<TypeId> {
+                None
+            }
 
-        impl Drop for A {
-            fn drop(&mut self) {
-                eprintln!("calling A destructor");
+            fn name(&self) -> Cow<'static, str> {
+                "Listener".into()
             }
-        }
 
-        #[derive(Event)]
-        struct B(Arc<()>);
+            fn init(&mut self, _world: &mut World, config: &mut Config) -> Result<(), InitError> {
+                config.received_event = Some(self.0);
+                config.received_event_access = Access::Read;
+                Ok(())
+            }
 
-        impl Drop for B {
-            fn drop(&mut self) {
-                eprintln!("calling B destructor");
+            unsafe fn run(&mut self, _info: &SystemInfo, event_ptr: EventPtr, _: UnsafeWorldCell) {
+                let value = unsafe { *event_ptr.as_ptr().cast::<u64>() };
+                self.1.lock().unwrap().push(value);
             }
-        }
 
-        #[derive(Event)]
-        struct C(Arc<()>);
+            fn refresh_archetype(&mut self, _arch: &Archetype) {}
 
-        impl Drop for C {
-            fn drop(&mut self) {
-                eprintln!("calling C destructor");
-            }
+            fn remove_archetype(&mut self, _arch: &Archetype) {}
         }
 
         let mut world = World::new();
 
-        world.add_system(|r: Receiver<A>, mut s: Sender<B>| {
-            s.send(B(r.event.0.clone()));
-            s.send(B(r.event.0.clone()));
-        });
+        let counter = unsafe {
+            world.add_event_with_descriptor(EventDescriptor {
+                name: "counter".into(),
+                type_id: None,
+                key: None,
+                is_targeted: false,
+                kind: EventKind::Normal,
+                layout: Layout::new::<u64>(),
+                drop: None,
+                clone_fn: None,
+                default_fn: Some(|ptr| ptr.cast::<u64>().write(7)),
+                is_immutable: true,
+                is_deferred: false,
+                bubbles: None,
+                targets: None,
+                target_filter: BoolExpr::new(true),
+            })
+        };
 
-        world.add_system(|r: Receiver<B>, mut s: Sender<C>| {
-            s.send(C(r.event.0.clone()));
-            s.send(C(r.event.0.clone()));
-        });
+        let seen = Arc::new(Mutex::new(vec![]));
+        world.add_system(Listener(counter, seen.clone()));
 
-        world.add_system(|r: Receiver<C>| println!("got C {:?}", Arc::as_ptr(&r.event.0)));
+        let mut value = 42_u64;
+        unsafe { world.send_dynamic(counter, None, (&mut value as *mut u64).cast()) };
 
-        let arc = Arc::new(());
+        world.send_dynamic_default(counter);
 
-        world.send(A(arc.clone()));
+        assert_eq!(*seen.lock().unwrap(), [42, 7]);
+    }
 
-        drop(world);
+    #[test]
+    #[should_panic = "without a target"]
+    fn send_dynamic_requires_target() {
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId);
 
-        assert_eq!(Arc::strong_count(&arc), 1);
+        let mut world = World::new();
+        let hit = world.add_event::<Hit>();
+
+        let mut event = Hit(EntityId::NULL);
+        unsafe { world.send_dynamic(hit, None, (&mut event as *mut Hit).cast()) };
     }
 
     #[test]
-    fn world_drops_events_on_panic() {
-        #[derive(Event)]
-        struct A(Arc<()>);
+    #[cfg(debug_assertions)]
+    fn structural_change_in_system_panics() {
+        use alloc::borrow::Cow;
+        use core::any::TypeId;
 
-        impl Drop for A {
-            fn drop(&mut self) {
-                eprintln!("calling A destructor");
-            }
-        }
+        use crate::archetype::Archetype;
+        use crate::event::EventPtr;
+        use crate::system::{Config, InitError, System, SystemInfo};
+        use crate::world::UnsafeWorldCell;
 
         #[derive(Event)]
-        struct B(Arc<()>);
+        struct E;
 
-        impl Drop for B {
-            fn drop(&mut self) {
-                eprintln!("calling B destructor");
+        #[derive(Component)]
+        struct C;
+
+        /// Inserts a component through the world behind the cell, which no
+        /// system is allowed to do.
+        struct Smuggler(EntityId);
+
+        impl System for Smuggler {
+            fn type_id(&self) -> Option<TypeId> {
+                None
             }
-        }
 
-        #[allow(dead_code)]
-        #[derive(Event)]
-        struct C(Arc<()>);
+            fn name(&self) -> Cow<'static, str> {
+                "Smuggler".into()
+            }
 
-        impl Drop for C {
-            fn drop(&mut self) {
-                eprintln!("calling C destructor");
+            fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+                config.received_event = Some(world.add_event::<E>());
+                Ok(())
+            }
+
+            unsafe fn run(&mut self, _: &SystemInfo, _: EventPtr, world: UnsafeWorldCell) {
+                unsafe { world.world_mut() }.insert(self.0, C);
             }
+
+            fn refresh_archetype(&mut self, _arch: &Archetype) {}
+
+            fn remove_archetype(&mut self, _arch: &Archetype) {}
         }
 
         let mut world = World::new();
 
-        world.add_system(|r: Receiver<A>, mut s: Sender<B>| {
-            s.send(B(r.event.0.clone()));
-            s.send(B(r.event.0.clone()));
-        });
-
-        world.add_system(|r: Receiver<B>, mut sender: Sender<C>| {
-            sender.send(C(r.event.0.clone()));
-            sender.send(C(r.event.0.clone()));
-        });
+        let e = world.spawn();
+        world.add_system(Smuggler(e));
 
-        world.add_system(|_: Receiver<C>| panic!("oops!"));
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| world.send(E)));
 
-        let arc = Arc::new(());
-        let arc_cloned = arc.clone();
+        let msg = res.unwrap_err();
+        let msg = msg.downcast_ref::<&str>().unwrap();
+        assert!(msg.contains("while a system is running"), "{msg}");
 
-        let res = panic::catch_unwind(move || world.send(A(arc_cloned)));
+        drop(world);
 
-        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "oops!");
+        fn boom(_: Receiver<E>) {
+            panic!("oops");
+        }
 
-        assert_eq!(Arc::strong_count(&arc), 1);
+        // The lock is released when the system unwinds.
+        let mut world = World::new();
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            world.add_system(boom);
+            world.send(E);
+        }));
+        assert!(res.is_err());
+
+        let e = world.spawn();
+        world.insert(e, C);
+        assert!(world.get_component::<C>(e).is_some());
     }
 }